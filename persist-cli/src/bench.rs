@@ -0,0 +1,361 @@
+//! `persist bench`: measure save and load throughput of the configured storage
+
+use crate::{format_size, OutputFormat};
+use chrono::{DateTime, Utc};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, observability::serve_metrics, retention,
+    GarbageItem, PersistError, SnapshotEngineInterface, SnapshotMetadata, SnapshotQuery,
+};
+use serde_json::json;
+use std::net::SocketAddr;
+use tabled::{Table, Tabled};
+use tracing::{error, info};
+
+/// Key prefix of the snapshots written by `persist bench`
+const BENCH_PREFIX: &str = "persist-bench/";
+
+/// Tag marking snapshots written by `persist bench`; its value is the run id
+const BENCH_TAG: &str = "persist-bench";
+
+/// Snapshots of earlier `persist bench` runs last written more than `older_than` ago
+///
+/// Unless `dry_run` is set, they are deleted.
+pub fn bench_leftovers(
+    storage_config: &StorageConfig,
+    older_than: std::time::Duration,
+    dry_run: bool,
+) -> Result<Vec<GarbageItem>, anyhow::Error> {
+    let cutoff = chrono::Duration::from_std(older_than)
+        .ok()
+        .and_then(|age| Utc::now().checked_sub_signed(age))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let mut leftovers = Vec::new();
+    for summary in engine.query(BENCH_PREFIX, &SnapshotQuery::new())? {
+        let metadata = &summary.metadata;
+        if !metadata.tags.contains_key(BENCH_TAG) || metadata.timestamp >= cutoff {
+            continue;
+        }
+        if !dry_run {
+            engine.delete_snapshot(&summary.path)?;
+        }
+        leftovers.push(GarbageItem {
+            size: Some(retention::stored_size(metadata)),
+            modified: metadata.timestamp,
+            location: summary.path,
+        });
+    }
+    Ok(leftovers)
+}
+
+/// Latencies of one operation measured by `persist bench`
+#[derive(Debug, Clone)]
+struct BenchPhase {
+    /// Latency of every operation, fastest first
+    latencies: Vec<std::time::Duration>,
+    /// Wall-clock time of the whole phase
+    elapsed: std::time::Duration,
+    /// Agent state bytes saved or loaded
+    bytes: u64,
+}
+
+impl BenchPhase {
+    /// Latency below which `percent` of the operations completed (nearest rank)
+    fn percentile(&self, percent: f64) -> std::time::Duration {
+        let rank = (percent / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        self.latencies
+            .get(rank.clamp(1, self.latencies.len().max(1)) - 1)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Agent state throughput in MB (10^6 bytes) per second
+    fn throughput(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.bytes as f64 / 1e6 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let ms = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+        json!({
+            "operations": self.latencies.len(),
+            "bytes": self.bytes,
+            "elapsed_ms": ms(self.elapsed),
+            "p50_ms": ms(self.percentile(50.0)),
+            "p95_ms": ms(self.percentile(95.0)),
+            "p99_ms": ms(self.percentile(99.0)),
+            "mb_per_s": self.throughput(),
+            "latencies_ms": self.latencies.iter().copied().map(ms).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Results of a `persist bench` run
+#[derive(Debug, Clone)]
+struct BenchReport {
+    run_id: String,
+    size: usize,
+    count: usize,
+    parallel: usize,
+    save: BenchPhase,
+    load: BenchPhase,
+    kept: bool,
+}
+
+impl BenchReport {
+    /// Key prefix the run's snapshots were written under
+    fn prefix(&self) -> String {
+        format!("{BENCH_PREFIX}{}/", self.run_id)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "run_id": self.run_id,
+            "prefix": self.prefix(),
+            "size": self.size,
+            "count": self.count,
+            "parallel": self.parallel,
+            "kept": self.kept,
+            "save": self.save.to_json(),
+            "load": self.load.to_json(),
+        })
+    }
+}
+
+#[derive(Tabled)]
+struct BenchRow {
+    #[tabled(rename = "Operation")]
+    operation: &'static str,
+    #[tabled(rename = "p50")]
+    p50: String,
+    #[tabled(rename = "p95")]
+    p95: String,
+    #[tabled(rename = "p99")]
+    p99: String,
+    #[tabled(rename = "Throughput")]
+    throughput: String,
+}
+
+impl BenchRow {
+    fn new(operation: &'static str, phase: &BenchPhase) -> Self {
+        let ms =
+            |duration: std::time::Duration| format!("{:.1} ms", duration.as_secs_f64() * 1000.0);
+        Self {
+            operation,
+            p50: ms(phase.percentile(50.0)),
+            p95: ms(phase.percentile(95.0)),
+            p99: ms(phase.percentile(99.0)),
+            throughput: format!("{:.1} MB/s", phase.throughput()),
+        }
+    }
+}
+
+/// Run `operation` on indices `0..count` with `parallel` workers, each with its own
+/// engine, timing every call
+///
+/// The first failure stops all workers and is returned.
+fn bench_phase(
+    storage_config: &StorageConfig,
+    count: usize,
+    parallel: usize,
+    bytes_per_operation: usize,
+    operation: impl Fn(&dyn SnapshotEngineInterface, usize) -> Result<(), PersistError> + Sync,
+) -> Result<BenchPhase, anyhow::Error> {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let worker = || -> Result<Vec<std::time::Duration>, anyhow::Error> {
+        let engine = create_engine_from_config(storage_config.clone())?;
+        let mut latencies = Vec::new();
+        while !failed.load(Ordering::SeqCst) {
+            let i = next.fetch_add(1, Ordering::SeqCst);
+            if i >= count {
+                break;
+            }
+            let started = std::time::Instant::now();
+            if let Err(e) = operation(engine.as_ref(), i) {
+                failed.store(true, Ordering::SeqCst);
+                return Err(e.into());
+            }
+            latencies.push(started.elapsed());
+        }
+        Ok(latencies)
+    };
+
+    let started = std::time::Instant::now();
+    let mut latencies = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..parallel.max(1)).map(|_| scope.spawn(worker)).collect();
+        workers
+            .into_iter()
+            .map(|handle| handle.join().expect("bench worker panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    let elapsed = started.elapsed();
+    latencies.sort();
+    Ok(BenchPhase {
+        bytes: (latencies.len() * bytes_per_operation) as u64,
+        latencies,
+        elapsed,
+    })
+}
+
+/// Save and then load `count` synthetic snapshots of `size` bytes
+///
+/// The snapshots are deleted afterwards, also when the run fails, unless `keep`.
+fn run_bench(
+    storage_config: &StorageConfig,
+    size: usize,
+    count: usize,
+    parallel: usize,
+    keep: bool,
+) -> Result<BenchReport, anyhow::Error> {
+    let run_id = format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        std::process::id()
+    );
+    let keys: Vec<String> = (0..count)
+        .map(|i| format!("{BENCH_PREFIX}{run_id}/{i:05}.json.gz"))
+        .collect();
+    let state = persist_core::testdata::synthetic_agent_state(size);
+
+    let phases = bench_phase(storage_config, count, parallel, state.len(), |engine, i| {
+        let metadata = SnapshotMetadata::builder(BENCH_TAG, &run_id, i as u64)
+            .tag(BENCH_TAG, &run_id)
+            .description("persist bench")
+            .build();
+        engine
+            .save_snapshot(&state, &metadata, &keys[i])
+            .map(|_| ())
+    })
+    .and_then(|save| {
+        let load = bench_phase(storage_config, count, parallel, state.len(), |engine, i| {
+            engine.load_snapshot(&keys[i]).map(|_| ())
+        })?;
+        Ok((save, load))
+    });
+
+    if !keep {
+        let engine = create_engine_from_config(storage_config.clone())?;
+        for key in keys.iter().filter(|key| engine.snapshot_exists(key)) {
+            if let Err(e) = engine.delete_snapshot(key) {
+                error!("Failed to remove benchmark snapshot {}: {}", key, e);
+            }
+        }
+    }
+
+    let (save, load) = phases?;
+    Ok(BenchReport {
+        run_id,
+        size: state.len(),
+        count,
+        parallel,
+        save,
+        load,
+        kept: keep,
+    })
+}
+
+pub async fn bench(
+    storage_config: &StorageConfig,
+    size: usize,
+    count: usize,
+    parallel: usize,
+    keep: bool,
+    metrics_port: Option<u16>,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!(
+        "Benchmarking {} snapshots of {} bytes with {} workers",
+        count, size, parallel
+    );
+
+    // Stopped when dropped, once the report is printed
+    let _metrics_server = metrics_port
+        .map(|port| serve_metrics(SocketAddr::from(([127, 0, 0, 1], port))))
+        .transpose()?;
+    let report = run_bench(storage_config, size, count, parallel, keep)?;
+
+    if format == OutputFormat::Json {
+        println!("{}", report.to_json());
+        return Ok(());
+    }
+    println!(
+        "Saved and loaded {count} snapshots of {} with {parallel} workers",
+        format_size(report.size as u64)
+    );
+    let rows = vec![
+        BenchRow::new("save", &report.save),
+        BenchRow::new("load", &report.load),
+    ];
+    println!("{}", Table::new(rows));
+    if keep {
+        println!("Kept the benchmark snapshots under {}", report.prefix());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{local_store_with_snapshot, remaining_snapshots};
+
+    #[test]
+    fn test_bench_report_and_cleanup() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+
+        let report = run_bench(&config, 4096, 5, 2, false).unwrap();
+        assert_eq!(report.size, 4096);
+        for phase in [&report.save, &report.load] {
+            assert_eq!(phase.latencies.len(), 5);
+            assert_eq!(phase.bytes, 5 * 4096);
+            assert!(phase.percentile(50.0) <= phase.percentile(99.0));
+        }
+        let json = report.to_json();
+        for field in ["run_id", "prefix", "size", "count", "parallel", "kept"] {
+            assert!(json.get(field).is_some(), "missing {field} in {json}");
+        }
+        for field in ["operations", "p50_ms", "p95_ms", "p99_ms", "mb_per_s"] {
+            assert!(json["save"][field].is_number(), "missing save.{field}");
+            assert!(json["load"][field].is_number(), "missing load.{field}");
+        }
+        assert_eq!(json["load"]["latencies_ms"].as_array().unwrap().len(), 5);
+        assert_eq!(remaining_snapshots(&config), vec!["snap.json.gz"]);
+    }
+
+    #[test]
+    fn test_bench_keep_leaves_tagged_snapshots_for_gc() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+
+        let report = run_bench(&config, 1024, 3, 1, true).unwrap();
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        let kept = engine
+            .query(&report.prefix(), &SnapshotQuery::new())
+            .unwrap();
+        assert_eq!(kept.len(), 3);
+        assert!(kept
+            .iter()
+            .all(|summary| summary.metadata.tags.get(BENCH_TAG) == Some(&report.run_id)));
+
+        // Too recent for the default threshold, but found without one
+        let day = std::time::Duration::from_secs(24 * 3600);
+        assert!(bench_leftovers(&config, day, false).unwrap().is_empty());
+        let found = bench_leftovers(&config, std::time::Duration::ZERO, true).unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(remaining_snapshots(&config).len(), 4);
+
+        bench_leftovers(&config, std::time::Duration::ZERO, false).unwrap();
+        assert_eq!(remaining_snapshots(&config), vec!["snap.json.gz"]);
+    }
+}
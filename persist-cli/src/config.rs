@@ -0,0 +1,57 @@
+//! `persist config show`: the effective settings and where each came from
+
+use crate::settings::Settings;
+use crate::OutputFormat;
+use serde_json::json;
+use tabled::{Table, Tabled};
+
+#[derive(Tabled)]
+struct ConfigRow {
+    #[tabled(rename = "Setting")]
+    name: &'static str,
+    #[tabled(rename = "Value")]
+    value: String,
+    #[tabled(rename = "Source")]
+    source: String,
+}
+
+/// Print the effective settings and where each came from
+pub fn show_config(settings: &Settings, format: OutputFormat) -> Result<(), anyhow::Error> {
+    let rows = settings.rows();
+    if format == OutputFormat::Json {
+        let values: serde_json::Map<String, serde_json::Value> = rows
+            .into_iter()
+            .map(|(name, value, source)| {
+                (
+                    name.to_string(),
+                    json!({ "value": value, "source": source }),
+                )
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "config_file": settings.config_file,
+                "profile": settings.profile,
+                "settings": values,
+            })
+        );
+        return Ok(());
+    }
+
+    match &settings.config_file {
+        Some(path) => println!("Config file: {}", path.display()),
+        None => println!("Config file: none"),
+    }
+    println!("Profile: {}", settings.profile.as_deref().unwrap_or("none"));
+    let rows: Vec<ConfigRow> = rows
+        .into_iter()
+        .map(|(name, value, source)| ConfigRow {
+            name,
+            value,
+            source,
+        })
+        .collect();
+    println!("{}", Table::new(rows));
+    Ok(())
+}
@@ -0,0 +1,236 @@
+//! `persist cp`: copy snapshots within or across storage backends
+
+use crate::progress::Progress;
+use crate::{OutputFormat, UsageError};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, create_storage_from_config, PersistError,
+    SnapshotEngineInterface, StorageAdapter,
+};
+use serde_json::json;
+use tracing::info;
+
+/// Source and destination keys of every snapshot `persist cp` copies
+fn copy_plan(
+    source: &dyn StorageAdapter,
+    src_key: &str,
+    dst_key: &str,
+    recursive: bool,
+) -> Result<Vec<(String, String)>, anyhow::Error> {
+    if recursive {
+        return Ok(source
+            .list(src_key)?
+            .into_iter()
+            .map(|path| {
+                let dst = format!("{dst_key}{}", &path[src_key.len()..]);
+                (path, dst)
+            })
+            .collect());
+    }
+
+    if src_key.is_empty() || src_key.ends_with('/') {
+        return Err(UsageError(format!(
+            "Source '{src_key}' is not a snapshot; use --recursive to copy a prefix"
+        ))
+        .into());
+    }
+    let dst = if dst_key.is_empty() || dst_key.ends_with('/') {
+        let file_name = src_key.rsplit('/').next().unwrap_or(src_key);
+        format!("{dst_key}{file_name}")
+    } else {
+        dst_key.to_string()
+    };
+    Ok(vec![(src_key.to_string(), dst)])
+}
+
+/// Copy the raw container bytes of one snapshot, optionally verifying the copy
+fn copy_one(
+    source: &dyn StorageAdapter,
+    target: &dyn StorageAdapter,
+    verifier: Option<&(dyn SnapshotEngineInterface + Send + Sync)>,
+    src_key: &str,
+    dst_key: &str,
+    progress: &Progress,
+) -> Result<(), PersistError> {
+    let data = source.load_with_options(src_key, &progress.call_options())?;
+    target.save_with_options(&data, dst_key, &progress.call_options())?;
+
+    if let Some(verifier) = verifier {
+        if target.load(dst_key)? != data {
+            return Err(PersistError::storage(format!(
+                "Copy at '{dst_key}' differs from its source"
+            )));
+        }
+        verifier.verify_snapshot(dst_key)?;
+    }
+    Ok(())
+}
+
+pub async fn copy_snapshots(
+    src: &str,
+    dst: &str,
+    verify: bool,
+    recursive: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Copying snapshots: {} -> {}", src, dst);
+
+    let (src_config, src_key) = StorageConfig::from_uri(src)?;
+    let (dst_config, dst_key) = StorageConfig::from_uri(dst)?;
+    let source = create_storage_from_config(src_config)?;
+    let target = create_storage_from_config(dst_config.clone())?;
+    let verifier = if verify {
+        Some(create_engine_from_config(dst_config)?)
+    } else {
+        None
+    };
+
+    let plan = copy_plan(source.as_ref(), &src_key, &dst_key, recursive)?;
+    let mut copied = Vec::new();
+    let mut failures = Vec::new();
+    let progress = Progress::new(plan.len(), format);
+    for (from, to) in &plan {
+        progress.start(from);
+        match copy_one(
+            source.as_ref(),
+            target.as_ref(),
+            verifier.as_deref(),
+            from,
+            to,
+            &progress,
+        ) {
+            Ok(()) => {
+                if format == OutputFormat::Table {
+                    progress.println(format_args!("✓ {from} -> {to}"));
+                }
+                copied.push(json!({ "from": from, "to": to }));
+            }
+            Err(e) => {
+                if format == OutputFormat::Table {
+                    progress.println(format_args!("✗ {from} -> {to}: {e}"));
+                }
+                failures.push(json!({ "from": from, "to": to, "error": e.to_string() }));
+            }
+        }
+        progress.finish_item();
+    }
+    drop(progress);
+    let failed = failures.len();
+
+    if format == OutputFormat::Json {
+        println!("{}", json!({ "copied": copied, "failed": failures }));
+    } else if recursive {
+        println!(
+            "Copied {} of {} snapshots ({} failed)",
+            plan.len() - failed,
+            plan.len(),
+            failed
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} snapshot copies failed", plan.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{local_store_with_snapshot, tamper_snapshot};
+
+    #[test]
+    fn test_copy_plan_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = persist_core::LocalFileStorage::with_base_dir(dir.path());
+        for path in [
+            "agents/a/1.json.gz",
+            "agents/a/2.json.gz",
+            "agents/b/1.json.gz",
+        ] {
+            storage.save(b"x", path).unwrap();
+        }
+
+        let plan = |src, dst, recursive| copy_plan(&storage, src, dst, recursive);
+        let pair = |src: &str, dst: &str| (src.to_string(), dst.to_string());
+        assert_eq!(
+            plan("agents/a/1.json.gz", "backup/", false).unwrap(),
+            vec![pair("agents/a/1.json.gz", "backup/1.json.gz")]
+        );
+        assert_eq!(
+            plan("agents/a/1.json.gz", "", false).unwrap(),
+            vec![pair("agents/a/1.json.gz", "1.json.gz")]
+        );
+        assert_eq!(
+            plan("agents/a/1.json.gz", "renamed.json.gz", false).unwrap(),
+            vec![pair("agents/a/1.json.gz", "renamed.json.gz")]
+        );
+        assert!(plan("agents/a/", "backup/", false).is_err());
+        assert!(plan("", "backup/", false).is_err());
+        assert_eq!(
+            plan("agents/a/", "backup/a/", true).unwrap(),
+            vec![
+                pair("agents/a/1.json.gz", "backup/a/1.json.gz"),
+                pair("agents/a/2.json.gz", "backup/a/2.json.gz"),
+            ]
+        );
+        assert!(plan("agents/c/", "backup/", true).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_copy_across_directories() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        local_store_with_snapshot(src.path());
+        let src_uri = format!("file://{}/", src.path().display());
+        let dst_uri = format!("file://{}/copies/", dst.path().display());
+
+        copy_snapshots(
+            &format!("{src_uri}snap.json.gz"),
+            &dst_uri,
+            true,
+            false,
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap();
+        copy_snapshots(
+            &src_uri,
+            &format!("{dst_uri}all/"),
+            true,
+            true,
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap();
+
+        let mut config = StorageConfig::default_local();
+        config.local_base_path = Some(dst.path().to_path_buf());
+        let engine = create_engine_from_config(config).unwrap();
+        for copy in ["copies/snap.json.gz", "copies/all/snap.json.gz"] {
+            let (metadata, agent_json) = engine.load_snapshot(copy).unwrap();
+            assert_eq!(metadata.agent_id, "agent-1");
+            assert_eq!(agent_json, r#"{"memory":["hello"]}"#);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_verification_failure() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        local_store_with_snapshot(src.path());
+        tamper_snapshot(src.path(), "snap.json.gz");
+        let src_uri = format!("file://{}/snap.json.gz", src.path().display());
+        let dst_uri = format!("file://{}/", dst.path().display());
+
+        let err = copy_snapshots(&src_uri, &dst_uri, true, false, OutputFormat::Table)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("1 of 1 snapshot copies failed"));
+
+        // Without --verify the bytes are copied as they are
+        copy_snapshots(&src_uri, &dst_uri, false, false, OutputFormat::Table)
+            .await
+            .unwrap();
+        assert!(dst.path().join("snap.json.gz").exists());
+    }
+}
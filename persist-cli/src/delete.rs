@@ -0,0 +1,337 @@
+//! `persist delete`: delete a snapshot, or every snapshot under a prefix or matching a glob
+
+use crate::{confirm, format_size, OutputFormat, UsageError};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, create_storage_from_config, retention,
+    SnapshotEngineInterface, StorageAdapter,
+};
+use serde_json::json;
+use tabled::{Table, Tabled};
+
+/// Snapshots chosen by `persist delete`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    Path(String),
+    Prefix(String),
+    Glob(String),
+}
+
+/// A snapshot selected for deletion, with its stored size if its metadata is readable
+#[derive(Debug, Clone)]
+struct DeleteCandidate {
+    path: String,
+    size: Option<u64>,
+}
+
+#[derive(Tabled)]
+struct DeleteRow {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Size")]
+    size: String,
+}
+
+impl From<&DeleteCandidate> for DeleteRow {
+    fn from(candidate: &DeleteCandidate) -> Self {
+        Self {
+            path: candidate.path.clone(),
+            size: candidate.size.map_or_else(|| "?".to_string(), format_size),
+        }
+    }
+}
+
+impl Selection {
+    /// Storage keys selected, sorted
+    ///
+    /// Keys come from listing the storage, so a glob can only match snapshots the
+    /// backend lists (for local storage, regular files under the base directory).
+    fn resolve(&self, storage: &dyn StorageAdapter) -> Result<Vec<String>, anyhow::Error> {
+        let mut paths = match self {
+            Selection::Path(path) => vec![path.clone()],
+            Selection::Prefix(prefix) => storage.list(prefix)?,
+            Selection::Glob(pattern) => {
+                if pattern.starts_with('/') || pattern.split('/').any(|segment| segment == "..") {
+                    return Err(UsageError(format!(
+                        "glob '{pattern}' must be relative to the storage root and must not contain '..'"
+                    ))
+                    .into());
+                }
+                let literal = &pattern[..pattern.find(['*', '?']).unwrap_or(pattern.len())];
+                let mut paths = storage.list(literal)?;
+                paths.retain(|path| glob_match(pattern, path));
+                paths
+            }
+        };
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+/// Whether `key` matches `pattern`: `*` matches any run of characters and `?` any one
+/// character within a path segment, `**` matches across segments
+fn glob_match(pattern: &str, key: &str) -> bool {
+    fn matches(pattern: &[char], key: &[char]) -> bool {
+        match pattern {
+            [] => key.is_empty(),
+            ['*', '*', rest @ ..] => {
+                // `a/**/b` also matches `a/b`
+                (rest.first() == Some(&'/') && matches(&rest[1..], key))
+                    || (0..=key.len()).any(|i| matches(rest, &key[i..]))
+            }
+            ['*', rest @ ..] => (0..=key.len())
+                .take_while(|&i| i == 0 || key[i - 1] != '/')
+                .any(|i| matches(rest, &key[i..])),
+            ['?', rest @ ..] => matches!(key, [c, ..] if *c != '/') && matches(rest, &key[1..]),
+            [c, rest @ ..] => key.first() == Some(c) && matches(rest, &key[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+    matches(&pattern, &key)
+}
+
+/// Delete each candidate, returning the failures with their errors
+fn delete_candidates<'a>(
+    engine: &dyn SnapshotEngineInterface,
+    candidates: &'a [DeleteCandidate],
+    format: OutputFormat,
+) -> Vec<(&'a str, String)> {
+    let mut failures = Vec::new();
+    for candidate in candidates {
+        match engine.delete_snapshot(&candidate.path) {
+            Ok(()) if format == OutputFormat::Table => println!("✓ {}", candidate.path),
+            Ok(()) => {}
+            Err(e) => {
+                if format == OutputFormat::Table {
+                    println!("✗ {}: {e}", candidate.path);
+                }
+                failures.push((candidate.path.as_str(), e.to_string()));
+            }
+        }
+    }
+    failures
+}
+
+pub async fn delete_snapshots(
+    storage_config: &StorageConfig,
+    selection: &Selection,
+    dry_run: bool,
+    force: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    let engine = create_engine_from_config(storage_config.clone())?;
+
+    // A single snapshot keeps the plain prompt and output
+    if let Selection::Path(snapshot_id) = selection {
+        if dry_run {
+            let exists = engine.snapshot_exists(snapshot_id);
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    json!({ "dry_run": true, "path": snapshot_id, "exists": exists })
+                );
+            } else if exists {
+                println!("Would delete snapshot '{snapshot_id}'");
+            } else {
+                println!("Snapshot '{snapshot_id}' does not exist");
+            }
+            return Ok(());
+        }
+
+        let prompt = format!("Are you sure you want to delete snapshot '{snapshot_id}'?");
+        if !force && !confirm(&prompt, format)? {
+            println!("Deletion cancelled");
+            return Ok(());
+        }
+
+        engine.delete_snapshot(snapshot_id)?;
+        if format == OutputFormat::Json {
+            println!("{}", json!({ "path": snapshot_id, "deleted": true }));
+        } else {
+            println!("✓ Snapshot deleted successfully");
+        }
+        return Ok(());
+    }
+
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let candidates: Vec<DeleteCandidate> = selection
+        .resolve(storage.as_ref())?
+        .into_iter()
+        .map(|path| {
+            let size = engine
+                .get_snapshot_metadata(&path)
+                .ok()
+                .map(|metadata| retention::stored_size(&metadata));
+            DeleteCandidate { path, size }
+        })
+        .collect();
+    let total_size: u64 = candidates.iter().filter_map(|c| c.size).sum();
+    let summary = format!(
+        "{} snapshots ({})",
+        candidates.len(),
+        format_size(total_size)
+    );
+
+    if format == OutputFormat::Table {
+        if candidates.is_empty() {
+            println!("No snapshots match");
+            return Ok(());
+        }
+        let rows: Vec<DeleteRow> = candidates.iter().map(DeleteRow::from).collect();
+        println!("{}", Table::new(rows));
+    }
+
+    let mut failures = Vec::new();
+    if !dry_run && !candidates.is_empty() {
+        if !force
+            && !confirm(
+                &format!("Are you sure you want to delete these {summary}?"),
+                format,
+            )?
+        {
+            println!("Deletion cancelled");
+            return Ok(());
+        }
+        failures = delete_candidates(engine.as_ref(), &candidates, format);
+    }
+    let failed = failures.len();
+
+    if format == OutputFormat::Json {
+        let deleted: Vec<_> = candidates
+            .iter()
+            .filter(|c| failures.iter().all(|(path, _)| *path != c.path))
+            .map(|c| json!({ "path": c.path, "size": c.size }))
+            .collect();
+        let failures: Vec<_> = failures
+            .iter()
+            .map(|(path, error)| json!({ "path": path, "error": error }))
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "dry_run": dry_run,
+                "deleted": deleted,
+                "failed": failures,
+                "deleted_bytes": total_size,
+            })
+        );
+    } else if dry_run {
+        println!("Would delete {summary}");
+    } else {
+        println!("Deleted {} of {summary}", candidates.len() - failed);
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} snapshot deletions failed", candidates.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{local_store_with_corpus, remaining_snapshots};
+
+    #[test]
+    fn test_glob_match() {
+        for (pattern, key, expected) in [
+            ("a/*/0.json.gz", "a/s1/0.json.gz", true),
+            ("a/*/0.json.gz", "a/s1/x/0.json.gz", false),
+            ("a/*", "a/s1/0.json.gz", false),
+            ("a/**", "a/s1/0.json.gz", true),
+            ("**/0.json.gz", "a/s1/0.json.gz", true),
+            ("**/0.json.gz", "0.json.gz", true),
+            ("a/**/0.json.gz", "a/0.json.gz", true),
+            ("a/s?/*.json.gz", "a/s2/0.json.gz", true),
+            ("a/s?/*.json.gz", "a/s10/0.json.gz", false),
+            ("a?s1/0.json.gz", "a/s1/0.json.gz", false),
+            ("*.json.gz", "a/s1/0.json.gz", false),
+            ("b/s1/1.json.gz", "b/s1/1.json.gz", true),
+            ("b/s1/1.json.gz", "b/s1/10.json.gz", false),
+        ] {
+            assert_eq!(glob_match(pattern, key), expected, "{pattern} vs {key}");
+        }
+    }
+
+    #[test]
+    fn test_delete_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        let storage = create_storage_from_config(config).unwrap();
+        let resolve = |selection: Selection| selection.resolve(storage.as_ref());
+
+        assert_eq!(
+            resolve(Selection::Glob("*/s1/*.json.gz".into())).unwrap(),
+            [
+                "a/s1/0.json.gz",
+                "a/s1/1.json.gz",
+                "a/s1/2.json.gz",
+                "b/s1/0.json.gz",
+                "b/s1/1.json.gz"
+            ]
+        );
+        assert_eq!(
+            resolve(Selection::Glob("a/**/0.json.gz".into())).unwrap(),
+            ["a/s1/0.json.gz", "a/s2/0.json.gz"]
+        );
+        assert_eq!(
+            resolve(Selection::Prefix("a/s2/".into())).unwrap(),
+            ["a/s2/0.json.gz"]
+        );
+
+        // Globs never reach outside the storage root
+        for pattern in ["../*", "a/../../*", "/etc/*"] {
+            let err = resolve(Selection::Glob(pattern.into())).unwrap_err();
+            assert!(err.to_string().contains("'..'"), "{err}");
+        }
+        assert!(resolve(Selection::Prefix("../".into())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_delete_dry_run_and_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        let before = remaining_snapshots(&config);
+
+        let selection = Selection::Glob("a/*/*.json.gz".into());
+        delete_snapshots(&config, &selection, true, false, OutputFormat::Table)
+            .await
+            .unwrap();
+        assert_eq!(remaining_snapshots(&config), before);
+
+        delete_snapshots(&config, &selection, false, true, OutputFormat::Table)
+            .await
+            .unwrap();
+        assert_eq!(
+            remaining_snapshots(&config),
+            ["b/s1/0.json.gz", "b/s1/1.json.gz"]
+        );
+    }
+
+    #[test]
+    fn test_delete_candidates_reports_partial_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        // A directory where a snapshot is expected cannot be removed as a file
+        std::fs::create_dir(dir.path().join("a/broken.json.gz")).unwrap();
+        let candidates: Vec<DeleteCandidate> =
+            ["a/s1/0.json.gz", "a/broken.json.gz", "a/s2/0.json.gz"]
+                .into_iter()
+                .map(|path| DeleteCandidate {
+                    path: path.to_string(),
+                    size: None,
+                })
+                .collect();
+
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        let failures = delete_candidates(engine.as_ref(), &candidates, OutputFormat::Table);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "a/broken.json.gz");
+        let remaining = remaining_snapshots(&config);
+        assert!(!remaining.contains(&"a/s1/0.json.gz".to_string()));
+        assert!(!remaining.contains(&"a/s2/0.json.gz".to_string()));
+        assert!(dir.path().join("a/broken.json.gz").is_dir());
+    }
+}
@@ -0,0 +1,211 @@
+//! `persist diff`: metadata differences and agent state changes between two snapshots
+
+use crate::OutputFormat;
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, SnapshotDiff, SnapshotEngineInterface,
+    StateChange,
+};
+use serde_json::json;
+use tracing::info;
+
+/// Engine and key of a snapshot location: a URI as accepted by `persist cp`, or a key
+/// in the configured storage
+fn open_location(
+    storage_config: &StorageConfig,
+    location: &str,
+) -> Result<(Box<dyn SnapshotEngineInterface + Send + Sync>, String), anyhow::Error> {
+    let (config, key) = if location.contains("://") {
+        StorageConfig::from_uri(location)?
+    } else {
+        (storage_config.clone(), location.to_string())
+    };
+    Ok((create_engine_from_config(config)?, key))
+}
+
+pub async fn diff_snapshots(
+    storage_config: &StorageConfig,
+    snapshot_a: &str,
+    snapshot_b: &str,
+    metadata_only: bool,
+    paths_only: bool,
+    max_value_len: usize,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Comparing snapshots: {} -> {}", snapshot_a, snapshot_b);
+
+    let (engine_a, key_a) = open_location(storage_config, snapshot_a)?;
+    let (engine_b, key_b) = open_location(storage_config, snapshot_b)?;
+
+    if metadata_only {
+        let metadata_a = engine_a.get_snapshot_metadata(&key_a)?;
+        let metadata_b = engine_b.get_snapshot_metadata(&key_b)?;
+        let diff = metadata_a.redacted().diff(&metadata_b.redacted());
+        if format == OutputFormat::Json {
+            println!("{}", json!({ "metadata": serde_json::to_value(&diff)? }));
+        } else {
+            println!("{diff}");
+        }
+        return Ok(());
+    }
+
+    // Loading verifies both snapshots
+    let (metadata_a, state_a) = engine_a.load_snapshot(&key_a)?;
+    let (metadata_b, state_b) = engine_b.load_snapshot(&key_b)?;
+    let diff = SnapshotDiff::new(&metadata_a, &state_a, &metadata_b, &state_b);
+
+    match (format, paths_only) {
+        (OutputFormat::Json, true) => {
+            let paths: Vec<&str> = diff.state_changes.iter().map(StateChange::path).collect();
+            println!("{}", json!(paths));
+        }
+        (OutputFormat::Json, false) => println!("{}", serde_json::to_value(&diff)?),
+        (OutputFormat::Table, true) => {
+            for change in &diff.state_changes {
+                println!("{}", change.path());
+            }
+        }
+        (OutputFormat::Table, false) => print!("{}", render_diff(&diff, max_value_len)),
+    }
+
+    Ok(())
+}
+
+/// Human-readable diff: the metadata differences, then one line per state change
+///
+/// Values longer than `max_value_len` characters are truncated.
+fn render_diff(diff: &SnapshotDiff, max_value_len: usize) -> String {
+    let value = |value: &serde_json::Value| {
+        let text = value.to_string();
+        if text.chars().count() > max_value_len {
+            let truncated: String = text.chars().take(max_value_len).collect();
+            format!("{truncated}…")
+        } else {
+            text
+        }
+    };
+
+    let mut out = String::from("Metadata:\n");
+    for line in diff.metadata.to_string().lines() {
+        out.push_str(&format!("  {line}\n"));
+    }
+    if !diff.state_changed {
+        out.push_str("State: identical\n");
+    } else if diff.state_changes.is_empty() {
+        out.push_str("State: changed (not comparable as JSON)\n");
+    } else {
+        out.push_str("State:\n");
+        for change in &diff.state_changes {
+            let line = match change {
+                StateChange::Add { path, value: new } => format!("+ {path}: {}", value(new)),
+                StateChange::Remove { path, old_value } => {
+                    format!("- {path}: {}", value(old_value))
+                }
+                StateChange::Replace {
+                    path,
+                    old_value,
+                    value: new,
+                } => format!("~ {path}: {} -> {}", value(old_value), value(new)),
+            };
+            out.push_str(&format!("  {line}\n"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use persist_core::SnapshotMetadata;
+
+    const STATE_BEFORE: &str = r#"{"config":{"model":"small","tools":["search","calc"]},"memory":["hi","a long message that goes on and on"]}"#;
+    const STATE_AFTER: &str = r#"{"config":{"model":"large","tools":["search"]},"memory":["hi","a long message that goes on and on","new"],"step":3}"#;
+
+    #[test]
+    fn test_render_diff() {
+        let before = SnapshotMetadata::builder("agent-1", "session-1", 1)
+            .tag("env", "dev")
+            .build();
+        let mut after = before.clone();
+        after.snapshot_index = 2;
+        after.tags.insert("env".to_string(), "prod".to_string());
+
+        let diff = SnapshotDiff::new(&before, STATE_BEFORE, &after, STATE_AFTER);
+        assert_eq!(
+            render_diff(&diff, 20),
+            concat!(
+                "Metadata:\n",
+                "  snapshot_index: 1 -> 2\n",
+                "  ~tag env: \"dev\" -> \"prod\"\n",
+                "State:\n",
+                "  ~ /config/model: \"small\" -> \"large\"\n",
+                "  - /config/tools/1: \"calc\"\n",
+                "  + /memory/2: \"new\"\n",
+                "  + /step: 3\n",
+            )
+        );
+
+        let long_value = SnapshotDiff::new(
+            &before,
+            r#"{"note":"short"}"#,
+            &before,
+            r#"{"note":"a long message that goes on and on"}"#,
+        );
+        assert_eq!(
+            render_diff(&long_value, 20),
+            concat!(
+                "Metadata:\n",
+                "  (no metadata differences)\n",
+                "State:\n",
+                "  ~ /note: \"short\" -> \"a long message that…\n",
+            )
+        );
+
+        let identical = SnapshotDiff::new(&before, STATE_BEFORE, &before, STATE_BEFORE);
+        assert!(render_diff(&identical, 20).ends_with("State: identical\n"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_across_locations() {
+        let dir_a = tempfile::tempdir().unwrap();
+        let dir_b = tempfile::tempdir().unwrap();
+        for (dir, state, index) in [(&dir_a, STATE_BEFORE, 0), (&dir_b, STATE_AFTER, 1)] {
+            let mut config = StorageConfig::default_local();
+            config.local_base_path = Some(dir.path().to_path_buf());
+            let engine = create_engine_from_config(config).unwrap();
+            let metadata = SnapshotMetadata::new("agent-1", "session-1", index);
+            engine
+                .save_snapshot(state, &metadata, "snap.json.gz")
+                .unwrap();
+        }
+
+        let mut config = StorageConfig::default_local();
+        config.local_base_path = Some(dir_a.path().to_path_buf());
+        let uri_b = format!("file://{}/snap.json.gz", dir_b.path().display());
+        let (engine_a, key_a) = open_location(&config, "snap.json.gz").unwrap();
+        let (engine_b, key_b) = open_location(&config, &uri_b).unwrap();
+        let (metadata_a, state_a) = engine_a.load_snapshot(&key_a).unwrap();
+        let (metadata_b, state_b) = engine_b.load_snapshot(&key_b).unwrap();
+
+        let diff = SnapshotDiff::new(&metadata_a, &state_a, &metadata_b, &state_b);
+        let paths: Vec<&str> = diff.state_changes.iter().map(StateChange::path).collect();
+        assert_eq!(
+            paths,
+            ["/config/model", "/config/tools/1", "/memory/2", "/step"]
+        );
+        assert!(diff.metadata.changed.contains_key("snapshot_index"));
+
+        for (metadata_only, paths_only) in [(false, false), (false, true), (true, false)] {
+            diff_snapshots(
+                &config,
+                "snap.json.gz",
+                &uri_b,
+                metadata_only,
+                paths_only,
+                80,
+                OutputFormat::Table,
+            )
+            .await
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! `persist doctor`: checks that the configured storage works before a save fails
+//!
+//! Checks run in order, each relying on the ones before it: config and profile
+//! parsing, backend reachability, a probe write, read and delete, available
+//! compression algorithms and, for cloud storage, credential expiry. Checks that
+//! need storage are skipped once the storage cannot be set up or reached.
+
+use crate::settings::{value_name, Settings};
+use crate::{create_storage_config, OutputFormat};
+use chrono::Utc;
+use persist_core::{
+    compressor_for, config::StorageBackend, create_storage_from_config, StorageAdapter,
+};
+use serde_json::json;
+
+/// Credentials expiring sooner than this are warned about
+const CREDENTIAL_EXPIRY_WARNING: chrono::Duration = chrono::Duration::hours(1);
+
+/// Compression algorithms a snapshot may have been written with
+const COMPRESSION_ALGORITHMS: &[&str] = &["gzip", "zstd"];
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// Works for now, but needs attention
+    Warn,
+    /// Critical: commands using the storage will fail
+    Fail,
+    /// Not run because an earlier check failed
+    Skip,
+}
+
+impl Status {
+    fn symbol(self) -> &'static str {
+        match self {
+            Status::Pass => "✓",
+            Status::Warn => "!",
+            Status::Fail => "✗",
+            Status::Skip => "-",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+            Status::Skip => "skip",
+        }
+    }
+}
+
+/// Result of one check, with a hint on how to fix it
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Warn,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Fail,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn skip(name: &'static str, reason: &str) -> Self {
+        Self {
+            name,
+            status: Status::Skip,
+            message: format!("skipped: {reason}"),
+            hint: None,
+        }
+    }
+}
+
+/// All checks of one `persist doctor` run
+#[derive(Debug)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Number of critical checks that failed
+    pub fn failures(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == Status::Fail)
+            .count()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let checks: Vec<_> = self
+            .checks
+            .iter()
+            .map(|check| {
+                json!({
+                    "name": check.name,
+                    "status": check.status.name(),
+                    "message": check.message,
+                    "hint": check.hint,
+                })
+            })
+            .collect();
+        json!({ "healthy": self.failures() == 0, "checks": checks })
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .checks
+            .iter()
+            .map(|check| check.name.len())
+            .max()
+            .unwrap_or(0);
+        for check in &self.checks {
+            writeln!(
+                f,
+                "{} {:width$}  {}",
+                check.status.symbol(),
+                check.name,
+                check.message
+            )?;
+            if let Some(hint) = &check.hint {
+                writeln!(f, "  {:width$}  → {hint}", "")?;
+            }
+        }
+        match self.failures() {
+            0 => write!(f, "All critical checks passed"),
+            1 => write!(f, "1 critical check failed"),
+            n => write!(f, "{n} critical checks failed"),
+        }
+    }
+}
+
+/// Run the checks and print the report; fails if any critical check failed
+///
+/// `settings` is the result of resolving flags, the config file and the environment,
+/// so that a broken config file is reported as a failed check.
+pub fn doctor(
+    settings: Result<Settings, anyhow::Error>,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    // The blocking cloud adapters refuse to start inside the CLI's Tokio runtime
+    let report = std::thread::scope(|scope| {
+        scope
+            .spawn(|| run_checks(settings))
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    });
+
+    match format {
+        OutputFormat::Table => println!("{report}"),
+        OutputFormat::Json => println!("{}", report.to_json()),
+    }
+    match report.failures() {
+        0 => Ok(()),
+        n => anyhow::bail!("{n} of {} doctor checks failed", report.checks.len()),
+    }
+}
+
+/// Run every check in order
+pub fn run_checks(settings: Result<Settings, anyhow::Error>) -> Report {
+    let mut checks = Vec::new();
+
+    let config = settings.and_then(|settings| {
+        let config = create_storage_config(&settings)?;
+        Ok((settings, config))
+    });
+    let config = match config {
+        Ok((settings, config)) => {
+            checks.push(Check::pass("config", describe_settings(&settings)));
+            Some(config)
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "config",
+                format!("{e:#}"),
+                "Fix the config file or flags; `persist config show` lists where each setting comes from",
+            ));
+            None
+        }
+    };
+    let backend = config.as_ref().map(|config| config.backend.clone());
+
+    let storage = match config {
+        Some(config) => {
+            let hint = connectivity_hint(&config.backend);
+            let storage = create_storage_from_config(config)
+                .map_err(|e| format!("Cannot set up storage: {e}"))
+                .and_then(|storage| {
+                    storage.health_check().map_err(|e| e.to_string())?;
+                    Ok(storage)
+                });
+            match storage {
+                Ok(storage) => {
+                    checks.push(Check::pass("connectivity", "Storage is reachable"));
+                    Some(storage)
+                }
+                Err(message) => {
+                    checks.push(Check::fail("connectivity", message, hint));
+                    None
+                }
+            }
+        }
+        None => {
+            checks.push(Check::skip("connectivity", "no usable configuration"));
+            None
+        }
+    };
+
+    checks.push(match &storage {
+        Some(storage) => check_probe(storage.as_ref()),
+        None => Check::skip("probe", "storage is unreachable"),
+    });
+    checks.push(check_compression());
+    if matches!(backend, Some(StorageBackend::S3 | StorageBackend::GCS)) {
+        checks.push(match &storage {
+            Some(storage) => check_credentials(storage.as_ref()),
+            None => Check::skip("credentials", "storage is unreachable"),
+        });
+    }
+
+    Report { checks }
+}
+
+/// Backend, location, config file and profile in use
+fn describe_settings(settings: &Settings) -> String {
+    let location = settings
+        .location
+        .as_ref()
+        .map(|location| location.value.as_str())
+        .unwrap_or_default();
+    let config_file = match &settings.config_file {
+        Some(path) => path.display().to_string(),
+        None => "no config file".to_string(),
+    };
+    let profile = match &settings.profile {
+        Some(profile) => format!("profile {profile}"),
+        None => "no profile".to_string(),
+    };
+    format!(
+        "{} storage at {location} ({config_file}, {profile})",
+        value_name(settings.storage.value)
+    )
+}
+
+fn connectivity_hint(backend: &StorageBackend) -> &'static str {
+    match backend {
+        StorageBackend::Local => {
+            "Create the directory, or point --path or the profile's `path` at an existing one"
+        }
+        StorageBackend::S3 => {
+            "Check the bucket name, region, credentials and network access to the endpoint"
+        }
+        StorageBackend::GCS => {
+            "Check the bucket name, and that the credentials are valid and may read the bucket"
+        }
+    }
+}
+
+/// Write, read back and delete a small object
+fn check_probe(storage: &dyn StorageAdapter) -> Check {
+    const HINT: &str = "Check that the credentials or file permissions allow writing, \
+                        reading and deleting objects";
+    let key = format!(
+        "persist-doctor-{}-{}.probe",
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        std::process::id()
+    );
+    let data = format!("persist doctor probe {key}").into_bytes();
+
+    if let Err(e) = storage.save(&data, &key) {
+        return Check::fail("probe", format!("Cannot write {key}: {e}"), HINT);
+    }
+    let read = storage.load(&key);
+    // Remove the probe even if reading it failed
+    let deleted = storage.delete(&key);
+    match read {
+        Ok(read) if read == data => {}
+        Ok(_) => {
+            return Check::fail(
+                "probe",
+                format!("{key} read back different bytes than were written"),
+                "Check for a proxy or gateway rewriting objects",
+            )
+        }
+        Err(e) => return Check::fail("probe", format!("Cannot read {key}: {e}"), HINT),
+    }
+    match deleted {
+        Ok(()) => Check::pass("probe", "Wrote, read back and deleted a probe object"),
+        Err(e) => Check::fail(
+            "probe",
+            format!("Cannot delete {key}: {e}"),
+            format!("{HINT}; remove {key} by hand"),
+        ),
+    }
+}
+
+/// Compression algorithms this build can read and write
+fn check_compression() -> Check {
+    let sample = b"persist doctor compression check".repeat(16);
+    let mut available = Vec::new();
+    let mut missing = Vec::new();
+    for &algorithm in COMPRESSION_ALGORITHMS {
+        let round_trip = compressor_for(algorithm, None).and_then(|compressor| {
+            let compressed = compressor.compress(&sample)?;
+            compressor.decompress(&compressed)
+        });
+        match round_trip {
+            Ok(data) if data == sample => available.push(algorithm),
+            _ => missing.push(algorithm),
+        }
+    }
+
+    if missing.is_empty() {
+        Check::pass(
+            "compression",
+            format!("Available: {}", available.join(", ")),
+        )
+    } else {
+        Check::warn(
+            "compression",
+            format!(
+                "Available: {}; unavailable: {}",
+                available.join(", "),
+                missing.join(", ")
+            ),
+            "Snapshots compressed with an unavailable algorithm cannot be loaded; \
+             use a persist build with that algorithm's feature enabled",
+        )
+    }
+}
+
+/// Warn about cloud credentials that are about to expire
+fn check_credentials(storage: &dyn StorageAdapter) -> Check {
+    const HINT: &str = "Refresh the credentials (e.g. `aws sso login`), or use a \
+                        long-lived role or service account for unattended saves";
+    match storage.credential_expiry() {
+        Ok(None) => Check::pass(
+            "credentials",
+            "Credentials do not expire or are refreshed automatically",
+        ),
+        Ok(Some(expiry)) => {
+            let remaining = expiry - Utc::now();
+            if remaining <= chrono::Duration::zero() {
+                Check::fail(
+                    "credentials",
+                    format!("Credentials expired at {}", expiry.to_rfc3339()),
+                    HINT,
+                )
+            } else if remaining < CREDENTIAL_EXPIRY_WARNING {
+                Check::warn(
+                    "credentials",
+                    format!(
+                        "Credentials expire in {} minutes, at {}",
+                        remaining.num_minutes(),
+                        expiry.to_rfc3339()
+                    ),
+                    HINT,
+                )
+            } else {
+                Check::pass(
+                    "credentials",
+                    format!("Credentials valid until {}", expiry.to_rfc3339()),
+                )
+            }
+        }
+        Err(e) => Check::fail("credentials", e.to_string(), HINT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cli;
+    use clap::Parser;
+
+    fn settings(path: &std::path::Path) -> Settings {
+        let cli =
+            Cli::try_parse_from(["persist", "--path", path.to_str().unwrap(), "doctor"]).unwrap();
+        Settings::resolve(&cli, |_| None).unwrap()
+    }
+
+    fn statuses(report: &Report) -> Vec<(&'static str, Status)> {
+        report
+            .checks
+            .iter()
+            .map(|check| (check.name, check.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_healthy_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = run_checks(Ok(settings(dir.path())));
+        assert_eq!(
+            statuses(&report),
+            [
+                ("config", Status::Pass),
+                ("connectivity", Status::Pass),
+                ("probe", Status::Pass),
+                ("compression", Status::Pass),
+            ]
+        );
+        // The probe object is gone
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert!(report.to_string().ends_with("All critical checks passed"));
+    }
+
+    #[test]
+    fn test_missing_directory_skips_probe() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let report = run_checks(Ok(settings(&missing)));
+        assert_eq!(
+            statuses(&report),
+            [
+                ("config", Status::Pass),
+                ("connectivity", Status::Fail),
+                ("probe", Status::Skip),
+                ("compression", Status::Pass),
+            ]
+        );
+        assert_eq!(report.failures(), 1);
+        assert!(report.checks[1].hint.is_some());
+        // Nothing was created by the probe
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn test_config_error_is_a_failed_check() {
+        let report = run_checks(Err(anyhow::anyhow!("unknown profile 'prod'")));
+        assert_eq!(report.checks[0].status, Status::Fail);
+        assert!(report.checks[0].message.contains("prod"));
+        assert!(report.checks[1..]
+            .iter()
+            .all(|check| check.status != Status::Fail));
+        assert_eq!(report.to_json()["healthy"], false);
+    }
+
+    /// Storage whose credentials expire at a fixed time
+    struct ExpiringStorage(chrono::DateTime<Utc>);
+
+    impl StorageAdapter for ExpiringStorage {
+        fn save(&self, _data: &[u8], _path: &str) -> persist_core::Result<()> {
+            Ok(())
+        }
+
+        fn load(&self, path: &str) -> persist_core::Result<Vec<u8>> {
+            Err(persist_core::PersistError::storage(format!(
+                "{path} not found"
+            )))
+        }
+
+        fn exists(&self, _path: &str) -> bool {
+            false
+        }
+
+        fn delete(&self, _path: &str) -> persist_core::Result<()> {
+            Ok(())
+        }
+
+        fn credential_expiry(&self) -> persist_core::Result<Option<chrono::DateTime<Utc>>> {
+            Ok(Some(self.0))
+        }
+    }
+
+    #[test]
+    fn test_credential_expiry() {
+        let soon = check_credentials(&ExpiringStorage(Utc::now() + chrono::Duration::minutes(5)));
+        assert_eq!(soon.status, Status::Warn);
+        assert!(soon.message.contains("expire in"));
+
+        let expired =
+            check_credentials(&ExpiringStorage(Utc::now() - chrono::Duration::minutes(5)));
+        assert_eq!(expired.status, Status::Fail);
+
+        let later = check_credentials(&ExpiringStorage(Utc::now() + chrono::Duration::days(1)));
+        assert_eq!(later.status, Status::Pass);
+    }
+}
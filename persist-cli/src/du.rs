@@ -0,0 +1,244 @@
+//! `persist du`: storage used per agent or session
+
+use crate::{format_size, DuSort, GroupBy, OutputFormat};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, create_storage_from_config, SnapshotMetadata,
+    SnapshotQuery, SnapshotSummary, StorageAdapter,
+};
+use serde_json::json;
+use tabled::{Table, Tabled};
+use tracing::info;
+
+/// Group of snapshots whose metadata could not be read, in `persist du`
+const UNREADABLE_GROUP: &str = "(unreadable)";
+
+/// Stored bytes under a prefix, per group and overall
+#[derive(Debug, Default)]
+struct DiskUsage {
+    /// Snapshot count and stored bytes per group
+    groups: std::collections::BTreeMap<String, (usize, u64)>,
+    snapshots: usize,
+    bytes: u64,
+}
+
+impl DiskUsage {
+    /// Size every listed path with a storage stat, attributing it by the metadata the
+    /// query returned for it
+    ///
+    /// Paths without metadata count towards [`UNREADABLE_GROUP`], unless nothing is
+    /// grouped; paths that vanish before they can be sized are left out.
+    fn collect(
+        storage: &dyn StorageAdapter,
+        paths: &[String],
+        summaries: &[SnapshotSummary],
+        group_by: GroupBy,
+    ) -> Self {
+        let owners: std::collections::HashMap<&str, &SnapshotMetadata> = summaries
+            .iter()
+            .map(|summary| (summary.path.as_str(), &summary.metadata))
+            .collect();
+
+        let mut usage = DiskUsage::default();
+        for path in paths {
+            let size = match storage.size(path) {
+                Ok(size) => size,
+                Err(e) => {
+                    tracing::warn!(path = %path, error = %e, "Skipping snapshot that could not be sized");
+                    continue;
+                }
+            };
+            let group = match (owners.get(path.as_str()), group_by) {
+                (_, GroupBy::None) => "all".to_string(),
+                (None, _) => UNREADABLE_GROUP.to_string(),
+                (Some(metadata), GroupBy::Agent) => metadata.agent_id.clone(),
+                (Some(metadata), GroupBy::Session) => {
+                    format!("{}/{}", metadata.agent_id, metadata.session_id)
+                }
+            };
+            let entry = usage.groups.entry(group).or_default();
+            entry.0 += 1;
+            entry.1 += size;
+            usage.snapshots += 1;
+            usage.bytes += size;
+        }
+        usage
+    }
+
+    /// Groups largest first by `sort`, ties broken by name
+    fn sorted(&self, sort: DuSort) -> Vec<(&str, usize, u64)> {
+        let mut groups: Vec<_> = self
+            .groups
+            .iter()
+            .map(|(group, (snapshots, bytes))| (group.as_str(), *snapshots, *bytes))
+            .collect();
+        groups.sort_by(|a, b| {
+            let order = match sort {
+                DuSort::Size => b.2.cmp(&a.2).then(b.1.cmp(&a.1)),
+                DuSort::Count => b.1.cmp(&a.1).then(b.2.cmp(&a.2)),
+            };
+            order.then(a.0.cmp(b.0))
+        });
+        groups
+    }
+
+    /// Share of the total taken by `bytes`, in percent
+    fn percent(&self, bytes: u64) -> f64 {
+        if self.bytes == 0 {
+            0.0
+        } else {
+            bytes as f64 * 100.0 / self.bytes as f64
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct DuRow {
+    #[tabled(rename = "Group")]
+    group: String,
+    #[tabled(rename = "Snapshots")]
+    snapshots: usize,
+    #[tabled(rename = "Size")]
+    size: String,
+    #[tabled(rename = "Percent")]
+    percent: String,
+}
+
+impl DuRow {
+    fn new(usage: &DiskUsage, group: &str, snapshots: usize, bytes: u64, human: bool) -> Self {
+        Self {
+            group: group.to_string(),
+            snapshots,
+            size: if human {
+                format_size(bytes)
+            } else {
+                bytes.to_string()
+            },
+            percent: format!("{:.1}%", usage.percent(bytes)),
+        }
+    }
+}
+
+pub async fn show_disk_usage(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    group_by: GroupBy,
+    sort: DuSort,
+    human: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Measuring storage used under '{}'", prefix);
+
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let mut paths = storage.list(prefix)?;
+    paths.sort();
+    let summaries = if group_by == GroupBy::None {
+        Vec::new()
+    } else {
+        let engine = create_engine_from_config(storage_config.clone())?;
+        engine.query(prefix, &SnapshotQuery::new())?
+    };
+    let usage = DiskUsage::collect(storage.as_ref(), &paths, &summaries, group_by);
+    let groups = if group_by == GroupBy::None {
+        Vec::new()
+    } else {
+        usage.sorted(sort)
+    };
+
+    if format == OutputFormat::Json {
+        let groups: Vec<_> = groups
+            .iter()
+            .map(|(group, snapshots, bytes)| {
+                json!({
+                    "group": group,
+                    "snapshots": snapshots,
+                    "bytes": bytes,
+                    "percent": usage.percent(*bytes),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "groups": groups,
+                "total": { "snapshots": usage.snapshots, "bytes": usage.bytes },
+            })
+        );
+        return Ok(());
+    }
+
+    let mut rows: Vec<DuRow> = groups
+        .iter()
+        .map(|(group, snapshots, bytes)| DuRow::new(&usage, group, *snapshots, *bytes, human))
+        .collect();
+    rows.push(DuRow::new(
+        &usage,
+        "total",
+        usage.snapshots,
+        usage.bytes,
+        human,
+    ));
+    println!("{}", Table::new(rows));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::local_store_with_corpus;
+
+    #[test]
+    fn test_disk_usage_groups_and_sorting() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        std::fs::write(dir.path().join("b/junk.json.gz"), vec![0u8; 300]).unwrap();
+        let storage = create_storage_from_config(config.clone()).unwrap();
+        let engine = create_engine_from_config(config).unwrap();
+        let paths = storage.list("").unwrap();
+        let summaries = engine.query("", &SnapshotQuery::new()).unwrap();
+        let file_size = |path: &str| std::fs::metadata(dir.path().join(path)).unwrap().len();
+        let sum = |paths: &[&str]| paths.iter().copied().map(file_size).sum::<u64>();
+
+        let by_agent = DiskUsage::collect(storage.as_ref(), &paths, &summaries, GroupBy::Agent);
+        let agent_a = sum(&[
+            "a/s1/0.json.gz",
+            "a/s1/1.json.gz",
+            "a/s1/2.json.gz",
+            "a/s2/0.json.gz",
+        ]);
+        let agent_b = sum(&["b/s1/0.json.gz", "b/s1/1.json.gz"]);
+        assert_eq!(by_agent.groups["agent-a"], (4, agent_a));
+        assert_eq!(by_agent.groups["agent-b"], (2, agent_b));
+        assert_eq!(by_agent.groups[UNREADABLE_GROUP], (1, 300));
+        assert_eq!(by_agent.snapshots, 7);
+        assert_eq!(by_agent.bytes, agent_a + agent_b + 300);
+        let percents: f64 = by_agent
+            .groups
+            .values()
+            .map(|(_, bytes)| by_agent.percent(*bytes))
+            .sum();
+        assert!((percents - 100.0).abs() < 1e-9);
+
+        // agent-b holds the largest snapshot, agent-a the most snapshots
+        let by_size: Vec<_> = by_agent.sorted(DuSort::Size).iter().map(|g| g.0).collect();
+        assert_eq!(by_size, ["agent-b", "agent-a", UNREADABLE_GROUP]);
+        let by_count: Vec<_> = by_agent.sorted(DuSort::Count).iter().map(|g| g.0).collect();
+        assert_eq!(by_count, ["agent-a", "agent-b", UNREADABLE_GROUP]);
+
+        let by_session = DiskUsage::collect(storage.as_ref(), &paths, &summaries, GroupBy::Session);
+        assert_eq!(
+            by_session.groups["agent-a/s2"],
+            (1, file_size("a/s2/0.json.gz"))
+        );
+        assert_eq!(by_session.groups.len(), 4);
+
+        let total = DiskUsage::collect(storage.as_ref(), &paths, &[], GroupBy::None);
+        assert_eq!(total.groups["all"], (7, by_agent.bytes));
+
+        let row = DuRow::new(&by_agent, UNREADABLE_GROUP, 1, 300, false);
+        assert_eq!(row.size, "300");
+        let row = DuRow::new(&by_agent, "agent-b", 2, agent_b, true);
+        assert_eq!(row.size, format_size(agent_b));
+        assert_eq!(row.percent, format!("{:.1}%", by_agent.percent(agent_b)));
+    }
+}
@@ -0,0 +1,154 @@
+//! `persist export`: write the agent state of a snapshot as JSON
+
+use crate::progress::Progress;
+use crate::{OutputFormat, SnapshotNotFound};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, CompatibilityMode, PersistError,
+};
+use serde_json::json;
+use std::path::Path;
+use tracing::info;
+
+pub async fn export_snapshot(
+    storage_config: &StorageConfig,
+    snapshot_id: &str,
+    output: Option<&Path>,
+    pretty: bool,
+    metadata_only: bool,
+    mode: CompatibilityMode,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Exporting snapshot: {}", snapshot_id);
+
+    let mut engine = create_engine_from_config(storage_config.clone())?;
+    engine.set_compatibility_mode(mode);
+
+    if !engine.snapshot_exists(snapshot_id) {
+        return Err(SnapshotNotFound(snapshot_id.to_string()).into());
+    }
+    // Loading verifies the integrity of the agent state
+    let progress = Progress::new(1, format);
+    progress.start(snapshot_id);
+    let (metadata, agent_json) =
+        engine.load_snapshot_with_options(snapshot_id, &progress.call_options())?;
+    drop(progress);
+
+    let value = if metadata_only {
+        metadata.redacted().to_json_value()
+    } else {
+        serde_json::from_str(&agent_json)?
+    };
+    let mut json = if pretty {
+        serde_json::to_string_pretty(&value)?
+    } else {
+        serde_json::to_string(&value)?
+    };
+    json.push('\n');
+
+    match output {
+        Some(path) if path != Path::new("-") => {
+            // Output failures exit with the storage code whatever their kind, so that a
+            // missing output directory is not reported like a missing snapshot
+            std::fs::write(path, json).map_err(|e| {
+                PersistError::io_write(
+                    std::io::Error::other(e),
+                    format!("Failed to write {}", path.display()),
+                )
+            })?;
+            info!("Exported snapshot {} to {}", snapshot_id, path.display());
+            if format == OutputFormat::Json {
+                println!("{}", json!({ "path": snapshot_id, "output_file": path }));
+            }
+        }
+        _ => {
+            use std::io::Write;
+            std::io::stdout().write_all(json.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{local_store_with_snapshot, tamper_snapshot};
+    use crate::{exit_code, exit_code_for};
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn test_export_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+        let state_file = dir.path().join("state.json");
+        let metadata_file = dir.path().join("metadata.json");
+
+        export_snapshot(
+            &config,
+            "snap.json.gz",
+            Some(&state_file),
+            false,
+            false,
+            CompatibilityMode::Strict,
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap();
+        export_snapshot(
+            &config,
+            "snap.json.gz",
+            Some(&metadata_file),
+            true,
+            true,
+            CompatibilityMode::Strict,
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap();
+
+        let state = std::fs::read_to_string(&state_file).unwrap();
+        assert_eq!(state, "{\"memory\":[\"hello\"]}\n");
+        let metadata: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&metadata_file).unwrap()).unwrap();
+        assert_eq!(metadata["agent_id"], "agent-1");
+        assert_eq!(metadata["snapshot_index"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_export_failures_have_distinct_exit_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+        let export = |id: &'static str, output: Option<PathBuf>| {
+            let config = config.clone();
+            async move {
+                export_snapshot(
+                    &config,
+                    id,
+                    output.as_deref(),
+                    false,
+                    false,
+                    CompatibilityMode::Strict,
+                    OutputFormat::Table,
+                )
+                .await
+                .unwrap_err()
+            }
+        };
+
+        let missing = export("missing.json.gz", None).await;
+        assert_eq!(exit_code_for(&missing), exit_code::NOT_FOUND);
+
+        let missing_dir = dir.path().join("no-such-dir").join("state.json");
+        let io_error = export("snap.json.gz", Some(missing_dir)).await;
+        assert_eq!(exit_code_for(&io_error), exit_code::STORAGE);
+        assert!(io_error.to_string().contains("no-such-dir"));
+
+        // Writing over a directory fails, but nothing is missing
+        let io_error = export("snap.json.gz", Some(dir.path().to_path_buf())).await;
+        assert_eq!(exit_code_for(&io_error), exit_code::STORAGE);
+
+        tamper_snapshot(dir.path(), "snap.json.gz");
+        let corrupted = export("snap.json.gz", None).await;
+        assert_eq!(exit_code_for(&corrupted), exit_code::INTEGRITY);
+    }
+}
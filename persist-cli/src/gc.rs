@@ -0,0 +1,107 @@
+//! `persist gc`: remove leftovers of interrupted writes and benchmark runs
+
+use crate::bench::bench_leftovers;
+use crate::{format_size, format_timestamp, OutputFormat};
+use persist_core::{config::StorageConfig, create_storage_from_config, StorageAdapter};
+use serde_json::json;
+use tabled::{Table, Tabled};
+use tracing::info;
+
+#[derive(Tabled)]
+struct GarbageRow {
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Size")]
+    size: String,
+    #[tabled(rename = "Modified")]
+    modified: String,
+}
+
+pub async fn collect_garbage(
+    storage_config: &StorageConfig,
+    older_than: std::time::Duration,
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Collecting garbage older than {:?}", older_than);
+
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let mut removed = storage.collect_garbage(older_than, dry_run)?;
+    removed.extend(bench_leftovers(storage_config, older_than, dry_run)?);
+    let reclaimed: u64 = removed.iter().filter_map(|item| item.size).sum();
+
+    if format == OutputFormat::Json {
+        let items: Vec<_> = removed
+            .iter()
+            .map(|item| {
+                json!({ "location": item.location, "size": item.size, "modified": item.modified })
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({ "dry_run": dry_run, "removed": items, "reclaimed_bytes": reclaimed })
+        );
+        return Ok(());
+    }
+
+    if removed.is_empty() {
+        println!("Nothing to clean up");
+        return Ok(());
+    }
+    let rows: Vec<GarbageRow> = removed
+        .iter()
+        .map(|item| GarbageRow {
+            location: item.location.clone(),
+            size: item.size.map_or_else(|| "?".to_string(), format_size),
+            modified: format_timestamp(item.modified.timestamp()),
+        })
+        .collect();
+    println!("{}", Table::new(rows));
+    println!(
+        "{} {} leftovers, {}",
+        if dry_run { "Would remove" } else { "Removed" },
+        removed.len(),
+        if dry_run {
+            format!("reclaiming {}", format_size(reclaimed))
+        } else {
+            format!("reclaimed {}", format_size(reclaimed))
+        }
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_age;
+    use crate::test_support::local_store_with_snapshot;
+
+    #[tokio::test]
+    async fn test_gc_dry_run_and_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+        let stale = dir.path().join(".tmp_persist_stale.tmp");
+        let fresh = dir.path().join(".tmp_persist_fresh.tmp");
+        for (path, age_hours) in [(&stale, 30), (&fresh, 2)] {
+            let file = std::fs::File::create(path).unwrap();
+            file.set_modified(
+                std::time::SystemTime::now() - std::time::Duration::from_secs(age_hours * 3600),
+            )
+            .unwrap();
+        }
+        let day = parse_age("1d").unwrap().to_std().unwrap();
+
+        collect_garbage(&config, day, true, OutputFormat::Json)
+            .await
+            .unwrap();
+        assert!(stale.exists() && fresh.exists());
+
+        collect_garbage(&config, day, false, OutputFormat::Table)
+            .await
+            .unwrap();
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+        assert!(dir.path().join("snap.json.gz").exists());
+    }
+}
@@ -0,0 +1,207 @@
+//! `persist import`: turn a plain JSON agent state into a snapshot
+
+use crate::progress::Progress;
+use crate::{snapshot_json, ImportArgs, OutputFormat, UsageError};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, PersistError, SnapshotMetadata,
+};
+use std::path::Path;
+use tracing::info;
+
+pub async fn import_snapshot(
+    storage_config: &StorageConfig,
+    args: &ImportArgs,
+    stdin: impl std::io::Read,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    let from_stdin = args.file == Path::new("-");
+    let source = if from_stdin {
+        "stdin".to_string()
+    } else {
+        args.file.display().to_string()
+    };
+    info!("Importing agent state from {}", source);
+
+    let agent_json = if from_stdin {
+        let mut input = String::new();
+        let mut stdin = stdin;
+        stdin
+            .read_to_string(&mut input)
+            .map_err(|e| PersistError::io_read(e, "Failed to read agent state from stdin"))?;
+        input
+    } else {
+        std::fs::read_to_string(&args.file)
+            .map_err(|e| PersistError::io_read(e, format!("Failed to read {source}")))?
+    };
+    if let Err(e) = serde_json::from_str::<serde_json::Value>(&agent_json) {
+        return Err(UsageError(format!("{source} is not valid JSON: {e}")).into());
+    }
+
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let index = match args.index {
+        Some(index) => index,
+        None => engine.next_index(&args.agent_id, &args.session_id)?,
+    };
+    let mut builder = SnapshotMetadata::builder(&args.agent_id, &args.session_id, index)
+        .tags(args.tags.iter().cloned());
+    if let Some(description) = &args.description {
+        builder = builder.description(description);
+    }
+    let metadata = builder.build();
+    let path = args
+        .dest
+        .clone()
+        .unwrap_or_else(|| metadata.suggested_filename());
+
+    let progress = Progress::new(1, format);
+    progress.start(&path);
+    let saved = engine.save_snapshot_with_options(
+        agent_json.trim(),
+        &metadata,
+        &path,
+        &progress.call_options(),
+    )?;
+    drop(progress);
+
+    if format == OutputFormat::Json {
+        println!("{}", snapshot_json(&path, &saved));
+    } else {
+        println!("✓ Imported {source} as {path}");
+        for line in saved.to_string().lines() {
+            println!("  {line}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{exit_code, exit_code_for, Cli, Commands};
+    use clap::Parser;
+    use persist_core::{SnapshotQuery, SortOrder};
+
+    fn import_args(args: &[&str]) -> ImportArgs {
+        let cli = Cli::try_parse_from(["persist", "import"].iter().chain(args)).unwrap();
+        let Commands::Import(args) = cli.command else {
+            unreachable!()
+        };
+        args
+    }
+
+    #[tokio::test]
+    async fn test_import_from_file_and_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = StorageConfig::default_local();
+        config.local_base_path = Some(dir.path().to_path_buf());
+        let state_file = dir.path().join("state.json");
+        std::fs::write(&state_file, "{\"memory\":[\"hello\"]}\n").unwrap();
+        let engine = create_engine_from_config(config.clone()).unwrap();
+
+        let args = import_args(&[
+            state_file.to_str().unwrap(),
+            "--agent-id",
+            "agent-1",
+            "--session-id",
+            "session-1",
+            "--index",
+            "4",
+            "--description",
+            "from QA",
+            "--tag",
+            "source=qa",
+            "--dest",
+            "imported/a.json.gz",
+        ]);
+        import_snapshot(&config, &args, std::io::empty(), OutputFormat::Table)
+            .await
+            .unwrap();
+        let (metadata, state) = engine.load_snapshot("imported/a.json.gz").unwrap();
+        assert_eq!(state, r#"{"memory":["hello"]}"#);
+        assert_eq!(metadata.snapshot_index, 4);
+        assert_eq!(metadata.description.as_deref(), Some("from QA"));
+        assert_eq!(metadata.tags["source"], "qa");
+
+        let args = import_args(&[
+            "-",
+            "--agent-id",
+            "agent-1",
+            "--session-id",
+            "session-1",
+            "--dest",
+            "imported/b.json.gz",
+        ]);
+        let stdin = std::io::Cursor::new(r#"{"memory":["piped"]}"#);
+        import_snapshot(&config, &args, stdin, OutputFormat::Table)
+            .await
+            .unwrap();
+        let (metadata, state) = engine.load_snapshot("imported/b.json.gz").unwrap();
+        assert_eq!(state, r#"{"memory":["piped"]}"#);
+        // The index follows the highest stored one of the session
+        assert_eq!(metadata.snapshot_index, 5);
+    }
+
+    #[tokio::test]
+    async fn test_import_generates_destination_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = StorageConfig::default_local();
+        config.local_base_path = Some(dir.path().to_path_buf());
+        let args = import_args(&["-", "--agent-id", "agent-1", "--session-id", "session-1"]);
+
+        for _ in 0..2 {
+            let stdin = std::io::Cursor::new("[1, 2, 3]");
+            import_snapshot(&config, &args, stdin, OutputFormat::Table)
+                .await
+                .unwrap();
+        }
+
+        let engine = create_engine_from_config(config).unwrap();
+        let summaries = engine
+            .query("", &SnapshotQuery::new().sort(SortOrder::IndexAscending))
+            .unwrap();
+        assert_eq!(summaries.len(), 2);
+        for (index, summary) in summaries.iter().enumerate() {
+            assert_eq!(summary.metadata.snapshot_index, index as u64);
+            assert_eq!(summary.path, summary.metadata.suggested_filename());
+            assert!(summary
+                .path
+                .starts_with(&format!("agent-1_session-1_{index}_")));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_invalid_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = StorageConfig::default_local();
+        config.local_base_path = Some(dir.path().to_path_buf());
+        let args = import_args(&[
+            "-",
+            "--agent-id",
+            "agent-1",
+            "--session-id",
+            "session-1",
+            "--dest",
+            "bad.json.gz",
+        ]);
+
+        let stdin = std::io::Cursor::new(r#"{"memory": ["unterminated"#);
+        let err = import_snapshot(&config, &args, stdin, OutputFormat::Table)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("stdin is not valid JSON"), "{err}");
+        assert!(!dir.path().join("bad.json.gz").exists());
+
+        let missing = import_args(&[
+            "/no/such/state.json",
+            "--agent-id",
+            "a",
+            "--session-id",
+            "s",
+        ]);
+        let err = import_snapshot(&config, &missing, std::io::empty(), OutputFormat::Table)
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code_for(&err), exit_code::NOT_FOUND);
+    }
+}
@@ -0,0 +1,266 @@
+//! `persist list` and `persist latest`: find snapshots by their metadata
+
+use crate::{snapshot_json, LatestPrint, NoMatchingSnapshot, OutputFormat, SnapshotInfo};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, create_storage_from_config, PersistError,
+    SnapshotQuery, SnapshotSummary, StorageAdapter,
+};
+use std::path::Path;
+use tabled::Table;
+use tracing::{debug, info};
+
+pub async fn list_snapshots(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    query: &SnapshotQuery,
+    parallel: usize,
+    _detailed: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Listing snapshots from {:?}", storage_config);
+
+    let summaries = if parallel > 1 {
+        let storage = create_storage_from_config(storage_config.clone())?;
+        let paths = storage.list(prefix)?;
+        query_paths(storage_config, &paths, query, parallel)?
+    } else {
+        let engine = create_engine_from_config(storage_config.clone())?;
+        engine.query(prefix, query)?
+    };
+
+    if format == OutputFormat::Json {
+        let snapshots: Vec<_> = summaries
+            .iter()
+            .map(|summary| snapshot_json(&summary.path, &summary.metadata))
+            .collect();
+        println!("{}", serde_json::Value::Array(snapshots));
+        return Ok(());
+    }
+
+    let snapshots: Vec<SnapshotInfo> = summaries.into_iter().map(SnapshotInfo::from).collect();
+
+    if snapshots.is_empty() {
+        println!("No snapshots found");
+    } else {
+        let table = Table::new(snapshots);
+        println!("{table}");
+    }
+
+    Ok(())
+}
+
+/// Read the metadata of `paths` on `parallel` worker threads and apply `query` to it
+///
+/// Like `SnapshotEngine::query`, entries that are not readable snapshots are skipped.
+fn query_paths(
+    storage_config: &StorageConfig,
+    paths: &[String],
+    query: &SnapshotQuery,
+    parallel: usize,
+) -> Result<Vec<SnapshotSummary>, anyhow::Error> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let next = AtomicUsize::new(0);
+    let worker = || -> Result<Vec<(usize, SnapshotSummary)>, anyhow::Error> {
+        let engine = create_engine_from_config(storage_config.clone())?;
+        let mut matches = Vec::new();
+        loop {
+            let i = next.fetch_add(1, Ordering::SeqCst);
+            let Some(path) = paths.get(i) else {
+                break;
+            };
+            match engine.read_snapshot_metadata(path) {
+                Ok(metadata) if query.matches(&metadata) => {
+                    matches.push((
+                        i,
+                        SnapshotSummary {
+                            path: path.clone(),
+                            metadata,
+                        },
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Skipping unreadable snapshot {}: {}", path, e),
+            }
+        }
+        Ok(matches)
+    };
+
+    let mut matches = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..parallel.max(1)).map(|_| scope.spawn(worker)).collect();
+        workers
+            .into_iter()
+            .map(|handle| handle.join().expect("list worker panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    // Listing order, so that ties keep the order a sequential query gives them
+    matches.sort_by_key(|(i, _)| *i);
+    Ok(query.finish(matches.into_iter().map(|(_, summary)| summary).collect()))
+}
+
+pub async fn latest_snapshot(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    agent_id: &str,
+    session_id: Option<&str>,
+    print: LatestPrint,
+    restore_to: Option<&Path>,
+) -> Result<(), anyhow::Error> {
+    info!("Resolving latest snapshot of agent {}", agent_id);
+
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let Some(latest) = engine.latest_snapshot(prefix, agent_id, session_id)? else {
+        let scope = match session_id {
+            Some(session_id) => format!("agent {agent_id}, session {session_id}"),
+            None => format!("agent {agent_id}"),
+        };
+        return Err(NoMatchingSnapshot(scope).into());
+    };
+
+    if let Some(path) = restore_to {
+        // Loading verifies the integrity of the agent state
+        let (_, agent_json) = engine.load_snapshot(&latest.path)?;
+        std::fs::write(path, format!("{agent_json}\n")).map_err(|e| {
+            PersistError::io_write(e, format!("Failed to write {}", path.display()))
+        })?;
+        info!("Exported snapshot {} to {}", latest.path, path.display());
+    }
+
+    match print {
+        LatestPrint::Path => println!("{}", latest.path),
+        LatestPrint::Json => println!("{}", snapshot_json(&latest.path, &latest.metadata)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::local_store_with_corpus;
+    use crate::{Cli, Commands};
+    use clap::Parser;
+
+    /// Paths `persist list ARGS` selects, in order
+    fn list_paths(config: &StorageConfig, args: &[&str]) -> Vec<String> {
+        let cli = Cli::try_parse_from(["persist", "list"].iter().chain(args)).unwrap();
+        let Commands::List {
+            prefix, filters, ..
+        } = cli.command
+        else {
+            unreachable!()
+        };
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        engine
+            .query(&prefix, &filters.into())
+            .unwrap()
+            .into_iter()
+            .map(|summary| summary.path.trim_end_matches(".json.gz").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_list_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        let list = |args: &[&str]| list_paths(&config, args);
+
+        assert_eq!(
+            list(&[
+                "--agent-id",
+                "agent-a",
+                "--session-id",
+                "s1",
+                "--sort",
+                "index"
+            ]),
+            ["a/s1/0", "a/s1/1", "a/s1/2"]
+        );
+        assert_eq!(
+            list(&["--index-from", "1", "--index-to", "1", "--sort", "time"]),
+            ["b/s1/1", "a/s1/1"]
+        );
+        assert_eq!(
+            list(&["--since", "6h", "--sort", "oldest"]),
+            ["a/s2/0", "b/s1/1", "a/s1/2"]
+        );
+        assert_eq!(
+            list(&["--until", "24h", "--tag", "env=dev", "--sort", "oldest"]),
+            ["b/s1/0", "a/s1/1"]
+        );
+        assert_eq!(
+            list(&["--agent", "agent-a", "--tag", "env=prod", "--limit", "2"]),
+            ["a/s1/2", "a/s2/0"]
+        );
+        assert_eq!(
+            list(&["--prefix", "b/", "--since", "2000-01-01T00:00:00Z"]),
+            ["b/s1/1", "b/s1/0"]
+        );
+        assert!(list(&["--agent-id", "agent-b", "--session-id", "s2"]).is_empty());
+    }
+
+    #[test]
+    fn test_list_sort_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+
+        let ascending = list_paths(&config, &["--sort", "size"]);
+        assert_eq!(
+            ascending,
+            ["a/s1/0", "a/s2/0", "b/s1/1", "a/s1/2", "a/s1/1", "b/s1/0"]
+        );
+        let mut descending = list_paths(&config, &["--sort", "size-desc"]);
+        descending.reverse();
+        assert_eq!(descending, ascending);
+    }
+
+    #[test]
+    fn test_list_sort_keys_and_reverse() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        let list = |args: &[&str]| list_paths(&config, args);
+
+        let by_agent = ["a/s1/0", "a/s1/1", "a/s1/2", "a/s2/0", "b/s1/0", "b/s1/1"];
+        assert_eq!(list(&["--sort", "agent"]), by_agent);
+        let mut reversed = by_agent.to_vec();
+        reversed.reverse();
+        assert_eq!(list(&["--sort", "agent", "--reverse"]), reversed);
+
+        // Equal indexes are ordered by path, in both directions
+        assert_eq!(
+            list(&["--sort", "index"]),
+            ["a/s1/0", "a/s2/0", "b/s1/0", "a/s1/1", "b/s1/1", "a/s1/2"]
+        );
+        assert_eq!(
+            list(&["--sort", "index", "--reverse"]),
+            ["a/s1/2", "a/s1/1", "b/s1/1", "a/s1/0", "a/s2/0", "b/s1/0"]
+        );
+
+        assert_eq!(list(&[]), list(&["--sort", "created"]));
+        assert_eq!(
+            list(&["--sort", "created", "--reverse"]),
+            ["b/s1/0", "a/s1/0", "a/s1/1", "a/s2/0", "b/s1/1", "a/s1/2"]
+        );
+
+        // The biggest snapshots, overall and of one agent
+        assert_eq!(
+            list(&["--sort", "size", "--reverse", "--limit", "2"]),
+            ["b/s1/0", "a/s1/1"]
+        );
+        assert_eq!(
+            list(&[
+                "--agent",
+                "agent-a",
+                "--sort",
+                "size",
+                "--reverse",
+                "--limit",
+                "1"
+            ]),
+            ["a/s1/1"]
+        );
+    }
+}
@@ -2,17 +2,47 @@
 Persist CLI - Command-line interface for the Persist agent snapshot system.
 
 This CLI provides utilities for inspecting, managing, and debugging agent snapshots
-stored in various backends (local filesystem, S3).
+stored in various backends (local filesystem, S3, Google Cloud Storage).
 */
 
-use clap::{Parser, Subcommand, ValueEnum};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use persist_core::{
     config::{StorageBackend, StorageConfig},
-    create_engine_from_config, LocalFileStorage, PersistError, SnapshotMetadata, StorageAdapter,
+    retention, CompatibilityMode, LogRotation, LogWriter, ObservabilityGuard, PersistError,
+    RetentionPolicy, SnapshotMetadata, SnapshotQuery, SnapshotSummary, SortOrder,
 };
+use serde::Deserialize;
+use serde_json::json;
+use settings::Settings;
 use std::path::PathBuf;
-use tabled::{Table, Tabled};
-use tracing::{error, info, warn};
+use std::process::ExitCode;
+use tabled::Tabled;
+
+mod bench;
+mod config;
+mod copy;
+mod delete;
+mod diff;
+mod doctor;
+mod du;
+mod export;
+mod gc;
+mod import;
+mod list;
+mod migrate;
+mod object;
+mod progress;
+mod prune;
+mod recompress;
+mod settings;
+mod show;
+mod stats;
+#[cfg(test)]
+mod test_support;
+mod update;
+mod verify;
+mod watch;
 
 #[derive(Parser)]
 #[command(name = "persist")]
@@ -23,19 +53,60 @@ struct Cli {
     #[arg(short, long, global = true)]
     verbose: bool,
 
-    /// Storage backend to use
-    #[arg(short, long, global = true, value_enum, default_value = "disk")]
-    storage: StorageType,
+    /// Never draw progress bars (they are only drawn for table output to a terminal)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Config file [default: ~/.config/persist/config.toml, or PERSIST_CONFIG]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Config file profile to use [default: default_profile of the file, or PERSIST_PROFILE]
+    #[arg(long, global = true)]
+    profile: Option<String>,
 
-    /// Storage path (directory for disk, bucket for S3)
+    /// Storage backend to use [default: disk, or PERSIST_DEFAULT_STORAGE]
+    #[arg(short, long, global = true, value_enum)]
+    storage: Option<StorageType>,
+
+    /// Storage path (directory for disk, bucket for S3 and GCS)
     #[arg(short, long, global = true)]
     path: Option<String>,
 
+    /// Object name prefix within the GCS bucket [default: PERSIST_GCS_PREFIX]
+    #[arg(long, global = true)]
+    gcs_prefix: Option<String>,
+
+    /// Service account key file for GCS [default: GOOGLE_APPLICATION_CREDENTIALS, or
+    /// application default credentials]
+    #[arg(long, global = true)]
+    gcs_credentials: Option<PathBuf>,
+
+    /// Output format; `json` prints one JSON document per command and JSON errors on stderr
+    /// [default: table]
+    #[arg(long, global = true, value_enum)]
+    output: Option<OutputFormat>,
+
+    /// Log line format on stderr; `json` writes one JSON object per line with the event
+    /// fields at the top level [default: json with `--output json`, text otherwise]
+    #[arg(long, global = true, value_enum)]
+    log_format: Option<LogFormat>,
+
+    /// Write logs to this file instead of stderr, appending to it
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+
+    /// When to start a new log file: never, daily, hourly, or at a size such as 100mb
+    /// [default: never]
+    #[arg(long, global = true, requires = "log_file")]
+    log_rotation: Option<LogRotation>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 enum StorageType {
     Disk,
     S3,
@@ -43,6 +114,177 @@ enum StorageType {
     GCS,
 }
 
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    /// Human-readable tables and messages
+    Table,
+    /// Machine-readable JSON on stdout
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum GroupBy {
+    Agent,
+    Session,
+    None,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LatestPrint {
+    /// The storage key alone
+    Path,
+    /// The key and full metadata as JSON
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DuSort {
+    Size,
+    Count,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SortArg {
+    Created,
+    #[value(alias = "time")]
+    Newest,
+    Oldest,
+    Index,
+    IndexDesc,
+    Size,
+    SizeDesc,
+    Agent,
+    None,
+}
+
+impl From<SortArg> for SortOrder {
+    fn from(sort: SortArg) -> Self {
+        match sort {
+            SortArg::Created | SortArg::Newest => SortOrder::NewestFirst,
+            SortArg::Oldest => SortOrder::OldestFirst,
+            SortArg::Index => SortOrder::IndexAscending,
+            SortArg::IndexDesc => SortOrder::IndexDescending,
+            SortArg::Size => SortOrder::SizeAscending,
+            SortArg::SizeDesc => SortOrder::SizeDescending,
+            SortArg::Agent => SortOrder::AgentAscending,
+            SortArg::None => SortOrder::Unsorted,
+        }
+    }
+}
+
+/// Filters of `persist list`, all of which must match
+#[derive(Args, Clone, Debug)]
+struct ListFilters {
+    /// Only list snapshots of this agent
+    #[arg(long, visible_alias = "agent-id")]
+    agent: Option<String>,
+    /// Only list snapshots of this session
+    #[arg(long, visible_alias = "session-id")]
+    session: Option<String>,
+    /// Only list snapshots created at or after this time (RFC 3339, or an age such as 7d)
+    #[arg(long, value_parser = parse_time)]
+    since: Option<DateTime<Utc>>,
+    /// Only list snapshots created before this time (RFC 3339, or an age such as 2h)
+    #[arg(long, value_parser = parse_time)]
+    until: Option<DateTime<Utc>>,
+    /// Only list snapshots with at least this index
+    #[arg(long, visible_alias = "index-from")]
+    min_index: Option<u64>,
+    /// Only list snapshots with at most this index
+    #[arg(long, visible_alias = "index-to")]
+    max_index: Option<u64>,
+    /// Only list snapshots carrying this tag (KEY=VALUE, repeatable)
+    #[arg(long = "tag", value_parser = parse_tag)]
+    tags: Vec<(String, String)>,
+    /// Maximum number of snapshots to list
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Sort order ("created" is newest first; "index", "size" and "agent" are
+    /// ascending); ties are ordered by path
+    #[arg(long, value_enum, default_value = "created")]
+    sort: SortArg,
+    /// Reverse the sort order (ties stay ordered by path)
+    #[arg(long)]
+    reverse: bool,
+}
+
+impl From<ListFilters> for SnapshotQuery {
+    fn from(filters: ListFilters) -> Self {
+        SnapshotQuery {
+            agent_id: filters.agent,
+            session_id: filters.session,
+            created_after: filters.since,
+            created_before: filters.until,
+            min_index: filters.min_index,
+            max_index: filters.max_index,
+            tags: filters.tags.into_iter().collect(),
+            limit: filters.limit,
+            sort: if filters.reverse {
+                SortOrder::from(filters.sort).reversed()
+            } else {
+                filters.sort.into()
+            },
+        }
+    }
+}
+
+/// Arguments of `persist import`
+#[derive(Args, Clone, Debug)]
+struct ImportArgs {
+    /// JSON file holding the agent state ("-" for stdin)
+    file: PathBuf,
+    /// Agent the snapshot belongs to
+    #[arg(long)]
+    agent_id: String,
+    /// Session the snapshot belongs to
+    #[arg(long)]
+    session_id: String,
+    /// Snapshot index (default: one more than the highest stored index of the session)
+    #[arg(long)]
+    index: Option<u64>,
+    /// Human-readable description
+    #[arg(long)]
+    description: Option<String>,
+    /// Tag to set (KEY=VALUE, repeatable)
+    #[arg(long = "tag", value_parser = parse_tag)]
+    tags: Vec<(String, String)>,
+    /// Key to save the snapshot under (default: derived from agent, session, index and time)
+    #[arg(long)]
+    dest: Option<String>,
+}
+
+/// Format compatibility overrides for reading snapshots written by other versions
+#[derive(Args, Clone, Copy, Debug)]
+struct CompatArgs {
+    /// Load snapshots with an incompatible format version without checking it
+    #[arg(long, conflicts_with = "lenient")]
+    force: bool,
+    /// Load snapshots with an incompatible format version, logging a warning
+    #[arg(long)]
+    lenient: bool,
+}
+
+impl CompatArgs {
+    fn mode(self) -> CompatibilityMode {
+        if self.force {
+            CompatibilityMode::Force
+        } else if self.lenient {
+            CompatibilityMode::Warn
+        } else {
+            CompatibilityMode::Strict
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// List all available snapshots
@@ -50,25 +292,353 @@ enum Commands {
         /// Show additional details
         #[arg(short, long)]
         detailed: bool,
+        /// Only list snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Number of snapshots to read metadata from concurrently
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        parallel: u16,
+        #[command(flatten)]
+        filters: ListFilters,
     },
     /// Show details of a specific snapshot
+    #[command(group(clap::ArgGroup::new("state").multiple(true)))]
     Show {
         /// Snapshot identifier (path or key)
         snapshot_id: String,
+        /// Show sensitive metadata fields instead of redacting them
+        #[arg(long)]
+        show_sensitive: bool,
+        /// Print the stored agent state (pretty-printed JSON) instead of the metadata
+        #[arg(long, group = "state")]
+        raw: bool,
+        /// Print only the agent state subtree at this JSON Pointer (e.g. /memory/context)
+        #[arg(long, value_name = "POINTER", group = "state", value_parser = show::parse_pointer)]
+        field: Option<String>,
+        /// Print the agent state on a single line
+        #[arg(long, requires = "state")]
+        compact: bool,
+        /// Load and verify the whole snapshot instead of reading only its metadata
+        ///
+        /// Without it, the metadata is read from the start of the snapshot and neither
+        /// the content hash nor the format version is checked.
+        #[arg(long)]
+        full_verify: bool,
+        #[command(flatten)]
+        compat: CompatArgs,
+    },
+    /// Print the latest snapshot of an agent
+    ///
+    /// Within a session the highest index wins; without --session-id, the most recently
+    /// created snapshot of any session. Prints just the storage key by default, for use
+    /// in scripts such as `persist export "$(persist latest --agent-id bot)"`. Exits
+    /// with the "not found" code if the agent has no snapshot.
+    Latest {
+        /// Agent whose snapshots to consider
+        #[arg(long)]
+        agent_id: String,
+        /// Only consider snapshots of this session
+        #[arg(long)]
+        session_id: Option<String>,
+        /// Only consider snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// What to print (`--output json` implies json)
+        #[arg(long, value_enum, default_value = "path")]
+        print: LatestPrint,
+        /// Also export the agent state of the snapshot to --output-file
+        #[arg(long, requires = "output_file")]
+        restore: bool,
+        /// File to write the agent state to with --restore
+        #[arg(short = 'o', long, requires = "restore")]
+        output_file: Option<PathBuf>,
     },
     /// Verify integrity of a snapshot
     Verify {
         /// Snapshot identifier (path or key)
         snapshot_id: String,
+        #[command(flatten)]
+        compat: CompatArgs,
+    },
+    /// Verify every snapshot under a prefix and report corrupted or unreadable ones
+    ///
+    /// Exits with 1 if any snapshot fails verification, unless --allow-failures is given.
+    VerifyAll {
+        /// Only verify snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Number of snapshots to verify concurrently
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        parallel: u16,
+        /// Stop at the first failure
+        #[arg(long)]
+        fail_fast: bool,
+        /// Exit successfully even if snapshots fail verification
+        #[arg(long)]
+        allow_failures: bool,
+        #[command(flatten)]
+        compat: CompatArgs,
+    },
+    /// Show snapshot counts, sizes and age ranges, overall and per group
+    Stats {
+        /// Only count snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Group snapshots by agent, by agent and session, or not at all
+        #[arg(long, value_enum, default_value = "agent")]
+        group_by: GroupBy,
     },
-    /// Delete a snapshot
+    /// Report storage used per agent or session, with each group's share of the total
+    ///
+    /// Sizes are the stored object sizes, read from storage without downloading
+    /// snapshots; only the metadata needed to attribute each snapshot is read.
+    Du {
+        /// Only count snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Group snapshots by agent, by agent and session, or not at all
+        #[arg(long, value_enum, default_value = "agent")]
+        group_by: GroupBy,
+        /// Order groups by total size or by snapshot count, largest first
+        #[arg(long, value_enum, default_value = "size")]
+        sort: DuSort,
+        /// Print sizes as KB, MB, GB instead of bytes
+        #[arg(long)]
+        human: bool,
+    },
+    /// Print a line for every snapshot added, changed or deleted, until interrupted
+    Watch {
+        /// Only watch snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Time between listings (e.g. 5s, 1m)
+        #[arg(long, default_value = "5s", value_parser = parse_age)]
+        interval: chrono::Duration,
+        /// Shell command to run for each new snapshot, with its path in
+        /// PERSIST_SNAPSHOT_PATH
+        #[arg(long, value_name = "CMD")]
+        exec: Option<String>,
+    },
+    /// Delete a snapshot, or every snapshot under a prefix or matching a glob
     Delete {
+        /// Snapshot identifier (path or key)
+        #[arg(required_unless_present_any = ["prefix", "glob"], conflicts_with_all = ["prefix", "glob"])]
+        snapshot_id: Option<String>,
+        /// Delete every snapshot whose path starts with this prefix
+        #[arg(long, conflicts_with = "glob")]
+        prefix: Option<String>,
+        /// Delete every snapshot whose path matches this pattern; `*` and `?` match
+        /// within a path segment, `**` across segments
+        #[arg(long)]
+        glob: Option<String>,
+        /// List the snapshots that would be deleted without deleting them
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Add, change or remove tags on a snapshot without rewriting its state
+    Tag {
         /// Snapshot identifier (path or key)
         snapshot_id: String,
+        /// Tag to add or change (KEY=VALUE, repeatable)
+        #[arg(long = "set", value_parser = parse_tag)]
+        tags: Vec<(String, String)>,
+        /// Tag key to remove (repeatable)
+        #[arg(long = "unset", visible_alias = "remove")]
+        remove: Vec<String>,
+    },
+    /// Compare two snapshots: metadata differences, then changes to the agent state
+    ///
+    /// Snapshots are keys in the configured storage or, to compare across backends,
+    /// `file://`, `s3://` or `gs://` URIs as accepted by `cp`.
+    Diff {
+        /// First ("before") snapshot identifier (path, key or URI)
+        snapshot_a: String,
+        /// Second ("after") snapshot identifier (path, key or URI)
+        snapshot_b: String,
+        /// Only compare metadata, skipping the agent state comparison
+        #[arg(long)]
+        metadata_only: bool,
+        /// Only print the JSON Pointers of changed state values
+        #[arg(long, conflicts_with = "metadata_only")]
+        paths_only: bool,
+        /// Truncate values longer than this many characters in the human view
+        #[arg(long, default_value_t = 80)]
+        max_value_len: usize,
+    },
+    /// Write the agent state of a snapshot as JSON, after verifying its integrity
+    ///
+    /// Exits with 2 if the snapshot does not exist, 3 if its integrity check fails
+    /// and 4 if the output cannot be written.
+    Export {
+        /// Snapshot identifier (path or key)
+        snapshot_id: String,
+        /// File to write to ("-" for stdout, the default)
+        #[arg(value_name = "OUTPUT", conflicts_with = "output_file")]
+        output_path: Option<PathBuf>,
+        /// File to write to, like OUTPUT
+        #[arg(short = 'o', long)]
+        output_file: Option<PathBuf>,
+        /// Indent the JSON
+        #[arg(long)]
+        pretty: bool,
+        /// Write the snapshot metadata instead of the agent state
+        #[arg(long)]
+        metadata_only: bool,
+        #[command(flatten)]
+        compat: CompatArgs,
+    },
+    /// Download the stored bytes of a snapshot as they are, without decompressing them
+    Get {
+        /// Snapshot identifier (path or key)
+        key: String,
+        /// File to write to ("-" for stdout, the default)
+        #[arg(short = 'o', long)]
+        output_file: Option<PathBuf>,
+        /// Check that the object is a valid snapshot before writing it out
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Upload a snapshot file as it is, after checking that it is a valid snapshot
+    Put {
+        /// Snapshot file to upload (a compressed container, as written by `get`)
+        file: PathBuf,
+        /// Destination key
+        key: String,
+        /// Upload without checking the file
+        #[arg(long, conflicts_with = "force")]
+        no_verify: bool,
+        /// Upload even if the file fails the check
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Copy snapshots within or across storage backends
+    ///
+    /// Locations are `file://path` (or a plain local path), `s3://bucket/key` or
+    /// `gs://bucket/key`; `--storage` and `--path` do not apply.
+    Cp {
+        /// Snapshot to copy, or key prefix with --recursive
+        src: String,
+        /// Destination key; a destination ending in "/" keeps the source file name
+        dst: String,
+        /// Read each copy back and verify its bytes and integrity
+        #[arg(long)]
+        verify: bool,
+        /// Copy every snapshot under the source prefix to the destination prefix
+        #[arg(short, long)]
+        recursive: bool,
+    },
+    /// Delete snapshots according to retention rules
+    ///
+    /// Rules combine: a snapshot is deleted if any rule selects it. `--max-total-size`
+    /// applies last, deleting the oldest snapshots the other rules keep.
+    Prune {
+        /// Only consider snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Keep only the N highest-indexed snapshots of each agent and session
+        #[arg(long, value_name = "N")]
+        keep_last: Option<usize>,
+        /// Delete snapshots older than this age (e.g. 90m, 12h, 30d, 2w)
+        #[arg(long, value_name = "AGE", value_parser = parse_age)]
+        older_than: Option<chrono::Duration>,
+        /// Delete the oldest snapshots until the rest fit in this size (e.g. 500MB, 50GB)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_total_size: Option<u64>,
+        /// Print the snapshots that would be deleted without deleting them
+        #[arg(long)]
+        dry_run: bool,
         /// Skip confirmation prompt
         #[arg(short, long)]
         force: bool,
     },
+    /// Remove leftovers of interrupted writes: stale temporary files of local storage,
+    /// incomplete multipart uploads in S3, and snapshots left behind by `persist bench`
+    Gc {
+        /// Only remove leftovers older than this (e.g. 12h, 1d), so writes in progress
+        /// are left alone
+        #[arg(long, value_name = "AGE", default_value = "1d", value_parser = parse_age)]
+        older_than: chrono::Duration,
+        /// Print what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Measure save and load latency and throughput against the configured storage
+    ///
+    /// Synthetic snapshots are written under "persist-bench/<run id>/", tagged
+    /// `persist-bench=<run id>`, and deleted afterwards unless `--keep` is given.
+    /// Snapshots of interrupted or kept runs are removed by `persist gc`.
+    Bench {
+        /// Size of each synthetic agent state (e.g. 64KB, 1MB)
+        #[arg(long, value_name = "SIZE", default_value = "1MB", value_parser = parse_size)]
+        size: u64,
+        /// Number of snapshots to save and load
+        #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// Number of snapshots to save or load concurrently
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+        parallel: u16,
+        /// Leave the benchmark snapshots in storage
+        #[arg(long)]
+        keep: bool,
+        /// Serve Prometheus metrics on http://127.0.0.1:PORT/metrics while the benchmark
+        /// runs
+        #[arg(long, value_name = "PORT")]
+        metrics_port: Option<u16>,
+    },
+    /// Rewrite snapshots with another compression algorithm, keeping metadata and hashes
+    Recompress {
+        /// Only recompress snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Target compression: gzip, zstd or none, with an optional level (e.g. zstd:19)
+        #[arg(long, value_name = "ALGORITHM[:LEVEL]", value_parser = recompress::parse_compression)]
+        to: recompress::CompressionTarget,
+        /// Number of snapshots to recompress concurrently
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        parallel: u16,
+        /// Estimate the savings from a sample of snapshots without rewriting any
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Migrate snapshots written with an older format version, rewriting them in place
+    MigrateFormat {
+        /// Only migrate snapshots whose path starts with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Major format version to migrate to, or `latest`
+        #[arg(long, value_name = "VERSION", default_value = "latest", value_parser = migrate::parse_format_version)]
+        to: u8,
+        /// Report what would be migrated without rewriting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Turn a plain JSON agent state into a snapshot with metadata and integrity hash
+    Import(ImportArgs),
+    /// Set or clear the description of a snapshot without rewriting its state
+    Describe {
+        /// Snapshot identifier (path or key)
+        snapshot_id: String,
+        /// New description (omit to clear it)
+        description: Option<String>,
+    },
+    /// Check the configuration, storage access and credentials, printing a checklist
+    ///
+    /// Exits with a failure if any critical check fails.
+    Doctor,
+    /// Inspect the config file and the effective settings
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective settings after merging flags, the config file and env vars
+    Show,
 }
 
 #[derive(Tabled)]
@@ -87,324 +657,736 @@ struct SnapshotInfo {
     size: String,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), anyhow::Error> {
-    let cli = Cli::parse();
+impl From<SnapshotSummary> for SnapshotInfo {
+    fn from(summary: SnapshotSummary) -> Self {
+        let size = retention::stored_size(&summary.metadata);
+        SnapshotInfo {
+            id: summary.path,
+            agent_id: summary.metadata.agent_id,
+            session_id: summary.metadata.session_id,
+            index: summary.metadata.snapshot_index,
+            timestamp: format_timestamp(summary.metadata.timestamp.timestamp()),
+            size: format_size(size),
+        }
+    }
+}
 
-    // Initialize logging
-    init_logging(cli.verbose);
+/// JSON object of a snapshot: its path and every metadata field, redacted
+fn snapshot_json(path: &str, metadata: &SnapshotMetadata) -> serde_json::Value {
+    let mut value = metadata.redacted().to_json_value();
+    value["path"] = path.into();
+    value
+}
 
-    // Create storage config
-    let storage_config = create_storage_config(&cli)?;
+/// Exit codes of failures that scripts may want to tell apart
+///
+/// The codes are stable and documented in docs/Configuration.md; never renumber them.
+mod exit_code {
+    /// Any other failure, e.g. some snapshots of a batch operation failed
+    pub const FAILURE: u8 = 1;
+    /// Invalid arguments or input (also used by the argument parser)
+    pub const USAGE: u8 = 2;
+    /// The snapshot, object or field does not exist
+    pub const NOT_FOUND: u8 = 3;
+    /// The snapshot is damaged or fails its integrity check
+    pub const INTEGRITY: u8 = 4;
+    /// Storage denied access, or credentials were rejected
+    pub const PERMISSION: u8 = 5;
+    /// Storage failed or is unreachable; retrying may help
+    pub const STORAGE: u8 = 6;
+    /// The config file, profile or storage settings are unusable
+    pub const CONFIG: u8 = 7;
+    /// Encrypted data could not be decrypted (or encrypted): the key is missing or wrong
+    pub const ENCRYPTION: u8 = 8;
 
-    // Execute command
-    match cli.command {
-        Commands::List { detailed } => list_snapshots(&storage_config, detailed).await?,
-        Commands::Show { snapshot_id } => show_snapshot(&storage_config, &snapshot_id).await?,
-        Commands::Verify { snapshot_id } => verify_snapshot(&storage_config, &snapshot_id).await?,
-        Commands::Delete { snapshot_id, force } => {
-            delete_snapshot(&storage_config, &snapshot_id, force).await?
+    /// Stable name of an exit code, reported as `code` in JSON errors
+    pub fn name(code: u8) -> &'static str {
+        match code {
+            USAGE => "usage",
+            NOT_FOUND => "not_found",
+            INTEGRITY => "integrity",
+            PERMISSION => "permission",
+            STORAGE => "storage",
+            CONFIG => "config",
+            ENCRYPTION => "encryption",
+            _ => "failure",
         }
     }
+}
 
-    Ok(())
+/// The requested snapshot does not exist in the configured storage
+#[derive(Debug)]
+struct SnapshotNotFound(String);
+
+impl std::fmt::Display for SnapshotNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Snapshot not found: {}", self.0)
+    }
 }
 
-fn init_logging(verbose: bool) {
-    let filter = if verbose {
-        tracing_subscriber::EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("debug"))
-    } else {
-        tracing_subscriber::EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"))
-    };
+impl std::error::Error for SnapshotNotFound {}
 
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_target(false)
-        .init();
+/// No snapshot matches what the command looked for
+#[derive(Debug)]
+struct NoMatchingSnapshot(String);
+
+impl std::fmt::Display for NoMatchingSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No snapshot found for {}", self.0)
+    }
 }
 
-fn create_storage_config(cli: &Cli) -> Result<StorageConfig, anyhow::Error> {
-    let backend = match cli.storage {
-        StorageType::Disk => StorageBackend::Local,
-        StorageType::S3 => StorageBackend::S3,
-        StorageType::GCS => StorageBackend::GCS,
-    };
+impl std::error::Error for NoMatchingSnapshot {}
 
-    let path = cli.path.clone().unwrap_or_else(|| match backend {
-        StorageBackend::Local => "./snapshots".to_string(),
-        StorageBackend::S3 => std::env::var("AWS_S3_BUCKET").unwrap_or_else(|_| {
-            eprintln!("Error: AWS_S3_BUCKET environment variable is required for S3 storage");
-            std::process::exit(1);
-        }),
-        StorageBackend::GCS => std::env::var("GCS_BUCKET").unwrap_or_else(|_| {
-            eprintln!("Error: GCS_BUCKET environment variable is required for GCS storage");
-            std::process::exit(1);
-        }),
-    });
+/// Arguments or input the command cannot work with
+#[derive(Debug)]
+struct UsageError(String);
 
-    match backend {
-        StorageBackend::Local => {
-            let mut config = StorageConfig::default_local();
-            config.local_base_path = Some(std::path::PathBuf::from(path));
-            Ok(config)
-        }
-        StorageBackend::S3 => Ok(StorageConfig::s3_with_bucket(path)),
-        StorageBackend::GCS => {
-            // Support additional GCS configuration through environment variables
-            let prefix = std::env::var("PERSIST_GCS_PREFIX").ok();
-            let credentials_path = std::env::var("GOOGLE_APPLICATION_CREDENTIALS")
-                .ok()
-                .map(PathBuf::from);
-
-            if let Some(prefix) = prefix {
-                Ok(StorageConfig::gcs_with_bucket_prefix_and_credentials(
-                    path,
-                    prefix,
-                    credentials_path,
-                ))
-            } else if let Some(creds) = credentials_path {
-                Ok(StorageConfig::gcs_with_bucket_and_credentials(path, creds))
-            } else {
-                Ok(StorageConfig::gcs_with_bucket(path))
-            }
-        }
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
-async fn list_snapshots(
-    storage_config: &StorageConfig,
-    detailed: bool,
-) -> Result<(), anyhow::Error> {
-    info!("Listing snapshots from {:?}", storage_config);
+impl std::error::Error for UsageError {}
 
-    match storage_config.backend {
-        StorageBackend::Local => {
-            let path = storage_config
-                .local_base_path
-                .as_ref()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "./snapshots".to_string());
-            list_local_snapshots(&path, detailed).await
-        }
-        StorageBackend::S3 => {
-            warn!("S3 snapshot listing not yet implemented");
-            Ok(())
-        }
-        StorageBackend::GCS => {
-            warn!("GCS snapshot listing not yet implemented");
-            Ok(())
-        }
+/// The config file, profile or storage settings could not be turned into a storage
+/// configuration
+#[derive(Debug)]
+struct ConfigError(anyhow::Error);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.0)
     }
 }
 
-async fn list_local_snapshots(path: &str, _detailed: bool) -> Result<(), anyhow::Error> {
-    let path = PathBuf::from(path);
-    if !path.exists() {
-        println!("No snapshots directory found at: {}", path.display());
-        return Ok(());
-    }
-
-    let mut snapshots = Vec::new();
-    let storage = LocalFileStorage::new();
-
-    // Read directory contents
-    let entries = std::fs::read_dir(&path)?;
-    for entry in entries {
-        let entry = entry?;
-        let file_path = entry.path();
-
-        if file_path.is_file() {
-            let path_str = file_path.to_string_lossy();
-
-            // Try to load and parse metadata
-            match load_snapshot_metadata(&storage, &path_str) {
-                Ok(metadata) => {
-                    let size = match std::fs::metadata(&file_path) {
-                        Ok(meta) => format_size(meta.len()),
-                        Err(_) => "Unknown".to_string(),
-                    };
-
-                    snapshots.push(SnapshotInfo {
-                        id: file_path
-                            .file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string(),
-                        agent_id: metadata.agent_id.clone(),
-                        session_id: metadata.session_id.clone(),
-                        index: metadata.snapshot_index,
-                        timestamp: format_timestamp(metadata.timestamp.timestamp()),
-                        size,
-                    });
-                }
-                Err(e) => {
-                    warn!("Failed to load metadata for {}: {}", path_str, e);
-                }
-            }
+impl std::error::Error for ConfigError {}
+
+/// A `--field` JSON Pointer that selects nothing in the agent state
+#[derive(Debug)]
+struct FieldNotFound {
+    pointer: String,
+    snapshot_id: String,
+}
+
+impl std::fmt::Display for FieldNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Field {} not found in the agent state of {}",
+            self.pointer, self.snapshot_id
+        )
+    }
+}
+
+impl std::error::Error for FieldNotFound {}
+
+/// Exit code for a failed command
+fn exit_code_for(error: &anyhow::Error) -> u8 {
+    if error.is::<SnapshotNotFound>()
+        || error.is::<NoMatchingSnapshot>()
+        || error.is::<FieldNotFound>()
+    {
+        return exit_code::NOT_FOUND;
+    }
+    if error.is::<UsageError>() {
+        return exit_code::USAGE;
+    }
+    if error.is::<ConfigError>() {
+        return exit_code::CONFIG;
+    }
+    if let Some(error) = error.downcast_ref::<PersistError>() {
+        return persist_exit_code(error);
+    }
+    match error.downcast_ref::<std::io::Error>() {
+        Some(error) => io_exit_code(error),
+        None => exit_code::FAILURE,
+    }
+}
+
+fn persist_exit_code(error: &PersistError) -> u8 {
+    match error {
+        PersistError::IntegrityCheckFailed { .. }
+        | PersistError::Compression(_)
+        | PersistError::Json(_)
+        | PersistError::InvalidFormat(_)
+        | PersistError::MissingMetadata(_) => exit_code::INTEGRITY,
+        PersistError::S3NotFound { .. } | PersistError::GcsNotFound { .. } => exit_code::NOT_FOUND,
+        PersistError::S3AccessDenied { .. } | PersistError::GcsAccessDenied { .. } => {
+            exit_code::PERMISSION
         }
+        PersistError::Io(error) => io_exit_code(error),
+        PersistError::Storage(_)
+        | PersistError::S3UploadError { .. }
+        | PersistError::S3DownloadError { .. }
+        | PersistError::Throttled { .. } => exit_code::STORAGE,
+        PersistError::S3Configuration(_) => exit_code::CONFIG,
+        PersistError::Encryption(_) => exit_code::ENCRYPTION,
+        // Includes paths rejected by storage, e.g. for path traversal
+        PersistError::Validation(_) => exit_code::USAGE,
+        _ => exit_code::FAILURE,
     }
+}
 
-    if snapshots.is_empty() {
-        println!("No snapshots found");
-    } else {
-        snapshots.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        let table = Table::new(snapshots);
-        println!("{table}");
+fn io_exit_code(error: &std::io::Error) -> u8 {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => exit_code::NOT_FOUND,
+        std::io::ErrorKind::PermissionDenied => exit_code::PERMISSION,
+        _ => exit_code::STORAGE,
     }
+}
 
-    Ok(())
+/// Snapshot or object a failure is about, reported as `path` in JSON errors
+fn error_path(error: &anyhow::Error) -> Option<String> {
+    if let Some(SnapshotNotFound(path)) = error.downcast_ref::<SnapshotNotFound>() {
+        return Some(path.clone());
+    }
+    if let Some(FieldNotFound { snapshot_id, .. }) = error.downcast_ref::<FieldNotFound>() {
+        return Some(snapshot_id.clone());
+    }
+    match error.downcast_ref::<PersistError>()? {
+        PersistError::S3NotFound { key, .. }
+        | PersistError::GcsNotFound { key, .. }
+        | PersistError::GcsAccessDenied { key, .. }
+        | PersistError::S3UploadError { key, .. }
+        | PersistError::S3DownloadError { key, .. } => Some(key.clone()),
+        _ => None,
+    }
 }
 
-async fn show_snapshot(
-    storage_config: &StorageConfig,
-    snapshot_id: &str,
-) -> Result<(), anyhow::Error> {
-    info!("Showing snapshot: {}", snapshot_id);
-
-    let engine = create_engine_from_config(storage_config.clone())?;
-
-    match engine.load_snapshot(snapshot_id) {
-        Ok((metadata, _data)) => {
-            println!("Snapshot Details:");
-            println!("  ID: {snapshot_id}");
-            println!("  Agent ID: {}", metadata.agent_id);
-            println!("  Session ID: {}", metadata.session_id);
-            println!("  Index: {}", metadata.snapshot_index);
-            println!(
-                "  Created: {}",
-                format_timestamp(metadata.timestamp.timestamp())
-            );
-            println!("  Format Version: {}", metadata.format_version);
-            println!("  Content Hash: {}", metadata.content_hash);
+impl Commands {
+    /// The one snapshot or object the command works on, if any
+    fn snapshot_path(&self) -> Option<&str> {
+        match self {
+            Commands::Show { snapshot_id, .. }
+            | Commands::Verify { snapshot_id, .. }
+            | Commands::Export { snapshot_id, .. }
+            | Commands::Tag { snapshot_id, .. }
+            | Commands::Describe { snapshot_id, .. } => Some(snapshot_id),
+            Commands::Delete { snapshot_id, .. } => snapshot_id.as_deref(),
+            Commands::Get { key, .. } | Commands::Put { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+}
 
-            if let Some(description) = &metadata.description {
-                println!("  Description: {description}");
-            }
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let settings = Settings::resolve(&cli, |name| std::env::var(name).ok());
+    let format = match &settings {
+        Ok(settings) => settings.output.value,
+        Err(_) => cli.output.unwrap_or(OutputFormat::Table),
+    };
+
+    // Initialize logging
+    let log_format = cli.log_format.unwrap_or(match format {
+        OutputFormat::Table => LogFormat::Text,
+        OutputFormat::Json => LogFormat::Json,
+    });
+    let log_writer = match &cli.log_file {
+        Some(path) => LogWriter::rotating_file(path, cli.log_rotation.unwrap_or_default(), None),
+        None => LogWriter::Stderr,
+    };
+    // The guard is held until exit: dropping it stops writing the log file
+    let (_log_guard, logging) = match init_logging(cli.verbose, log_format, &log_writer) {
+        Ok(guard) => (Some(guard), Ok(())),
+        Err(e) => (None, Err(e)),
+    };
+    progress::set_quiet(cli.quiet);
+    let command_path = cli.command.snapshot_path().map(str::to_string);
+
+    let result = if let Err(e) = logging {
+        Err(e)
+    } else if matches!(cli.command, Commands::Doctor) {
+        // Reports configuration errors as a failed check
+        doctor::doctor(settings, format)
+    } else {
+        match settings {
+            Ok(settings) => run(cli, settings).await,
+            Err(e) => Err(ConfigError(e).into()),
         }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
-            error!("Failed to load snapshot: {}", e);
-            return Err(e.into());
+            let code = exit_code_for(&e);
+            match format {
+                OutputFormat::Table => eprintln!("Error: {e:?}"),
+                OutputFormat::Json => eprintln!(
+                    "{}",
+                    json!({
+                        "error": {
+                            "code": exit_code::name(code),
+                            "exit_code": code,
+                            "message": format!("{e:#}"),
+                            "path": error_path(&e).or(command_path),
+                        }
+                    })
+                ),
+            }
+            ExitCode::from(code)
         }
     }
-
-    Ok(())
 }
 
-async fn verify_snapshot(
-    storage_config: &StorageConfig,
-    snapshot_id: &str,
-) -> Result<(), anyhow::Error> {
-    info!("Verifying snapshot: {}", snapshot_id);
+async fn run(cli: Cli, settings: Settings) -> Result<(), anyhow::Error> {
+    let format = settings.output.value;
+    let command = match cli.command {
+        Commands::Config {
+            command: ConfigCommand::Show,
+        } => return config::show_config(&settings, format),
+        command => command,
+    };
 
-    let engine = create_engine_from_config(storage_config.clone())?;
+    // Create storage config
+    let storage_config = create_storage_config(&settings).map_err(ConfigError)?;
 
-    match engine.load_snapshot(snapshot_id) {
-        Ok((_metadata, _data)) => {
-            println!("✓ Snapshot is valid and integrity check passed");
+    // Execute command
+    match command {
+        Commands::List {
+            detailed,
+            prefix,
+            parallel,
+            filters,
+        } => {
+            let query = filters.into();
+            list::list_snapshots(
+                &storage_config,
+                &prefix,
+                &query,
+                usize::from(parallel),
+                detailed,
+                format,
+            )
+            .await?
         }
-        Err(PersistError::IntegrityCheckFailed { expected, actual }) => {
-            error!("✗ Integrity check failed:");
-            error!("  Expected hash: {}", expected);
-            error!("  Actual hash: {}", actual);
-            return Err(anyhow::anyhow!("Integrity check failed"));
+        Commands::Show {
+            snapshot_id,
+            raw,
+            field,
+            compact,
+            compat,
+            ..
+        } if raw || field.is_some() => {
+            show::show_state(
+                &storage_config,
+                &snapshot_id,
+                field.as_deref(),
+                compact,
+                compat.mode(),
+            )
+            .await?
         }
-        Err(e) => {
-            error!("✗ Failed to verify snapshot: {}", e);
-            return Err(e.into());
+        Commands::Show {
+            snapshot_id,
+            show_sensitive,
+            full_verify,
+            compat,
+            ..
+        } => {
+            show::show_snapshot(
+                &storage_config,
+                &snapshot_id,
+                show_sensitive,
+                full_verify.then(|| compat.mode()),
+                format,
+            )
+            .await?
+        }
+        Commands::Latest {
+            agent_id,
+            session_id,
+            prefix,
+            print,
+            restore: _,
+            output_file,
+        } => {
+            let print = if format == OutputFormat::Json {
+                LatestPrint::Json
+            } else {
+                print
+            };
+            list::latest_snapshot(
+                &storage_config,
+                &prefix,
+                &agent_id,
+                session_id.as_deref(),
+                print,
+                output_file.as_deref(),
+            )
+            .await?
+        }
+        Commands::Verify {
+            snapshot_id,
+            compat,
+        } => verify::verify_snapshot(&storage_config, &snapshot_id, compat.mode(), format).await?,
+        Commands::VerifyAll {
+            prefix,
+            parallel,
+            fail_fast,
+            allow_failures,
+            compat,
+        } => {
+            verify::verify_all(
+                &storage_config,
+                &prefix,
+                usize::from(parallel),
+                fail_fast,
+                allow_failures,
+                compat.mode(),
+                format,
+            )
+            .await?
+        }
+        Commands::Stats { prefix, group_by } => {
+            stats::show_stats(&storage_config, &prefix, group_by, format).await?
         }
+        Commands::Du {
+            prefix,
+            group_by,
+            sort,
+            human,
+        } => du::show_disk_usage(&storage_config, &prefix, group_by, sort, human, format).await?,
+        Commands::Watch {
+            prefix,
+            interval,
+            exec,
+        } => {
+            let interval = interval
+                .to_std()
+                .ok()
+                .filter(|interval| !interval.is_zero())
+                .ok_or_else(|| UsageError("--interval must be positive".to_string()))?;
+            watch::watch_snapshots(&storage_config, &prefix, interval, exec.as_deref(), format)
+                .await?
+        }
+        Commands::Delete {
+            snapshot_id,
+            prefix,
+            glob,
+            dry_run,
+            force,
+        } => {
+            let selection = match (snapshot_id, prefix, glob) {
+                (Some(snapshot_id), ..) => delete::Selection::Path(snapshot_id),
+                (None, Some(prefix), _) => delete::Selection::Prefix(prefix),
+                (None, None, Some(pattern)) => delete::Selection::Glob(pattern),
+                (None, None, None) => unreachable!("clap requires a snapshot, --prefix or --glob"),
+            };
+            delete::delete_snapshots(&storage_config, &selection, dry_run, force, format).await?
+        }
+        Commands::Tag {
+            snapshot_id,
+            tags,
+            remove,
+        } => update::tag_snapshot(&storage_config, &snapshot_id, tags, remove, format).await?,
+        Commands::Diff {
+            snapshot_a,
+            snapshot_b,
+            metadata_only,
+            paths_only,
+            max_value_len,
+        } => {
+            diff::diff_snapshots(
+                &storage_config,
+                &snapshot_a,
+                &snapshot_b,
+                metadata_only,
+                paths_only,
+                max_value_len,
+                format,
+            )
+            .await?
+        }
+        Commands::Import(args) => {
+            import::import_snapshot(&storage_config, &args, std::io::stdin().lock(), format).await?
+        }
+        Commands::Describe {
+            snapshot_id,
+            description,
+        } => update::describe_snapshot(&storage_config, &snapshot_id, description, format).await?,
+        Commands::Cp {
+            src,
+            dst,
+            verify,
+            recursive,
+        } => copy::copy_snapshots(&src, &dst, verify, recursive, format).await?,
+        Commands::Prune {
+            prefix,
+            keep_last,
+            older_than,
+            max_total_size,
+            dry_run,
+            force,
+        } => {
+            let policy = RetentionPolicy {
+                keep_last,
+                older_than,
+                max_total_size,
+            };
+            prune::prune_snapshots(&storage_config, &prefix, &policy, dry_run, force, format)
+                .await?
+        }
+        Commands::Gc {
+            older_than,
+            dry_run,
+        } => {
+            let older_than = older_than
+                .to_std()
+                .map_err(|_| UsageError("--older-than must not be negative".to_string()))?;
+            gc::collect_garbage(&storage_config, older_than, dry_run, format).await?
+        }
+        Commands::Bench {
+            size,
+            count,
+            parallel,
+            keep,
+            metrics_port,
+        } => {
+            let size = usize::try_from(size)
+                .map_err(|_| UsageError("--size is too large for this platform".to_string()))?;
+            bench::bench(
+                &storage_config,
+                size,
+                count as usize,
+                usize::from(parallel),
+                keep,
+                metrics_port,
+                format,
+            )
+            .await?
+        }
+        Commands::Recompress {
+            prefix,
+            to,
+            parallel,
+            dry_run,
+        } => {
+            recompress::recompress_snapshots(
+                &storage_config,
+                &prefix,
+                &to,
+                usize::from(parallel),
+                dry_run,
+                format,
+            )
+            .await?
+        }
+        Commands::MigrateFormat {
+            prefix,
+            to,
+            dry_run,
+        } => migrate::migrate_format(&storage_config, &prefix, to, dry_run, format).await?,
+        Commands::Export {
+            snapshot_id,
+            output_path,
+            output_file,
+            pretty,
+            metadata_only,
+            compat,
+        } => {
+            export::export_snapshot(
+                &storage_config,
+                &snapshot_id,
+                output_file.or(output_path).as_deref(),
+                pretty,
+                metadata_only,
+                compat.mode(),
+                format,
+            )
+            .await?
+        }
+        Commands::Get {
+            key,
+            output_file,
+            verify,
+        } => {
+            object::get_object(
+                &storage_config,
+                &key,
+                output_file.as_deref(),
+                verify,
+                format,
+            )
+            .await?
+        }
+        Commands::Put {
+            file,
+            key,
+            no_verify,
+            force,
+        } => object::put_object(&storage_config, &file, &key, !no_verify, force, format).await?,
+        Commands::Config { .. } => unreachable!("config commands run without storage"),
+        Commands::Doctor => unreachable!("doctor sets up storage itself"),
     }
 
     Ok(())
 }
 
-async fn delete_snapshot(
-    storage_config: &StorageConfig,
-    snapshot_id: &str,
-    force: bool,
-) -> Result<(), anyhow::Error> {
-    if !force {
-        print!("Are you sure you want to delete snapshot '{snapshot_id}'? (y/N): ");
-        use std::io::{self, Write};
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-
-        if !input.trim().to_lowercase().starts_with('y') {
-            println!("Deletion cancelled");
-            return Ok(());
+fn init_logging(
+    verbose: bool,
+    format: LogFormat,
+    writer: &LogWriter,
+) -> Result<ObservabilityGuard, anyhow::Error> {
+    let filter = if verbose {
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("debug"))
+    } else {
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"))
+    };
+
+    // Logs go to stderr or a file so that stdout only carries command output
+    let (make_writer, guard) = writer.make_writer()?;
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_ansi(*writer == LogWriter::Stderr)
+        .with_writer(make_writer);
+    match format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().flatten_event(true).init(),
+    }
+    Ok(guard)
+}
+
+fn create_storage_config(settings: &Settings) -> Result<StorageConfig, anyhow::Error> {
+    let backend = match settings.storage.value {
+        StorageType::Disk => StorageBackend::Local,
+        StorageType::S3 => StorageBackend::S3,
+        StorageType::GCS => StorageBackend::GCS,
+    };
+
+    #[cfg(not(feature = "s3"))]
+    {
+        if backend == StorageBackend::S3 {
+            anyhow::bail!(
+                "S3 storage is not available: persist was built without the 's3' feature"
+            );
+        }
+    }
+    #[cfg(not(feature = "gcs"))]
+    {
+        if backend == StorageBackend::GCS {
+            anyhow::bail!(
+                "GCS storage is not available: persist was built without the 'gcs' feature"
+            );
         }
     }
 
-    let _engine = create_engine_from_config(storage_config.clone())?;
+    let Some(path) = settings.location.as_ref().map(|s| s.value.clone()) else {
+        let (name, var) = match backend {
+            StorageBackend::S3 => ("S3", "AWS_S3_BUCKET"),
+            _ => ("GCS", "GCS_BUCKET"),
+        };
+        anyhow::bail!(
+            "a bucket is required for {name} storage: pass --path, set `bucket` in the config profile or set {var}"
+        );
+    };
 
-    // Get storage adapter to delete
-    match storage_config.backend {
+    match backend {
         StorageBackend::Local => {
-            let storage = LocalFileStorage::new();
-            storage.delete(snapshot_id)?;
-            println!("✓ Snapshot deleted successfully");
+            let mut config = StorageConfig::default_local();
+            config.local_base_path = Some(std::path::PathBuf::from(path));
+            Ok(config)
         }
         StorageBackend::S3 => {
-            #[cfg(feature = "s3")]
-            {
-                use persist_core::S3StorageAdapter;
-                let bucket = storage_config
-                    .s3_bucket
-                    .as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("S3 bucket not configured"))?;
-                let storage = S3StorageAdapter::new(bucket.to_string())?;
-                storage.delete(snapshot_id)?;
-                println!("✓ Snapshot deleted successfully");
-            }
-            #[cfg(not(feature = "s3"))]
-            {
-                return Err(anyhow::anyhow!("S3 support not enabled"));
-            }
+            let mut config = StorageConfig::s3_with_bucket(path);
+            config.s3_region = settings.region.as_ref().map(|s| s.value.clone());
+            Ok(config)
         }
         StorageBackend::GCS => {
-            #[cfg(feature = "gcs")]
-            {
-                use persist_core::GCSStorageAdapter;
-                let bucket = storage_config
-                    .gcs_bucket
-                    .as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("GCS bucket not configured"))?;
-                let prefix = storage_config.gcs_prefix.clone();
-                let credentials_path = storage_config.gcs_credentials_path.clone();
-                let storage = GCSStorageAdapter::new(bucket.to_string(), prefix, credentials_path)?;
-                storage.delete(snapshot_id)?;
-                println!("✓ Snapshot deleted successfully");
-            }
-            #[cfg(not(feature = "gcs"))]
-            {
-                return Err(anyhow::anyhow!("GCS support not enabled"));
-            }
+            let mut config = StorageConfig::gcs_with_bucket(path);
+            config.gcs_prefix = settings.gcs_prefix.as_ref().map(|s| s.value.clone());
+            config.gcs_credentials_path =
+                settings.gcs_credentials.as_ref().map(|s| s.value.clone());
+            Ok(config)
         }
     }
+}
 
-    Ok(())
+fn parse_tag(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("invalid tag '{s}', expected KEY=VALUE"))
 }
 
-fn load_snapshot_metadata(
-    storage: &impl StorageAdapter,
-    path: &str,
-) -> Result<SnapshotMetadata, PersistError> {
-    let data = storage.load(path)?;
+/// Ask on stdin whether to go ahead
+///
+/// JSON output is for automation, so it never prompts and requires `--force` instead.
+fn confirm(prompt: &str, format: OutputFormat) -> Result<bool, anyhow::Error> {
+    if format == OutputFormat::Json {
+        return Err(UsageError(
+            "confirmation required: pass --force with --output json".to_string(),
+        )
+        .into());
+    }
+
+    print!("{prompt} (y/N): ");
+    use std::io::{self, Write};
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
 
-    // Try to decompress and parse
-    use persist_core::compression::{CompressionAdapter, GzipCompressor};
-    let compressor = GzipCompressor::new();
-    let decompressed = compressor.decompress(&data)?;
+    Ok(input.trim().to_lowercase().starts_with('y'))
+}
+
+/// Parse a point in time: RFC 3339, or an age such as `2h` meaning that long ago
+fn parse_time(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = s.parse::<DateTime<Utc>>() {
+        return Ok(time);
+    }
+    parse_age(s)
+        .map(|age| Utc::now() - age)
+        .map_err(|_| format!("invalid time '{s}', expected RFC 3339 or an age such as 7d"))
+}
 
-    // Parse JSON
-    let json: serde_json::Value = serde_json::from_slice(&decompressed)?;
-    let metadata = json["metadata"].clone();
-    let metadata: SnapshotMetadata = serde_json::from_value(metadata)?;
+/// Parse an age such as `90m`, `12h`, `30d` or `2w` (a bare number is in seconds)
+fn parse_age(s: &str) -> Result<chrono::Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("invalid age '{s}', expected e.g. 30d"))?;
+    match unit {
+        "" | "s" => Ok(chrono::Duration::seconds(number)),
+        "m" => Ok(chrono::Duration::minutes(number)),
+        "h" => Ok(chrono::Duration::hours(number)),
+        "d" => Ok(chrono::Duration::days(number)),
+        "w" => Ok(chrono::Duration::weeks(number)),
+        _ => Err(format!(
+            "invalid age unit '{unit}', expected s, m, h, d or w"
+        )),
+    }
+}
 
-    Ok(metadata)
+/// Parse a size such as `512`, `500MB` or `50GB`, with the 1024-based units of [`format_size`]
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{s}', expected e.g. 50GB"))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" | "K" => 1 << 10,
+        "MB" | "M" => 1 << 20,
+        "GB" | "G" => 1 << 30,
+        "TB" | "T" => 1 << 40,
+        other => {
+            return Err(format!(
+                "invalid size unit '{other}', expected B, KB, MB, GB or TB"
+            ))
+        }
+    };
+    Ok((number * multiplier as f64) as u64)
 }
 
 fn format_size(bytes: u64) -> String {
@@ -432,3 +1414,137 @@ fn format_timestamp(timestamp: i64) -> String {
         _ => timestamp.to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn storage_config(args: &[&str]) -> Result<StorageConfig, anyhow::Error> {
+        let cli = Cli::try_parse_from(args).unwrap();
+        create_storage_config(&Settings::resolve(&cli, |_| None)?)
+    }
+
+    #[cfg(feature = "gcs")]
+    #[test]
+    fn test_gcs_storage_config() {
+        let config = storage_config(&[
+            "persist",
+            "--storage",
+            "gcs",
+            "--path",
+            "my-bucket",
+            "--gcs-prefix",
+            "agents/",
+            "--gcs-credentials",
+            "/secrets/key.json",
+            "list",
+        ])
+        .unwrap();
+
+        assert_eq!(config.backend, StorageBackend::GCS);
+        assert_eq!(config.gcs_bucket.as_deref(), Some("my-bucket"));
+        assert_eq!(config.gcs_prefix.as_deref(), Some("agents/"));
+        assert_eq!(
+            config.gcs_credentials_path,
+            Some(PathBuf::from("/secrets/key.json"))
+        );
+    }
+
+    #[cfg(not(feature = "gcs"))]
+    #[test]
+    fn test_gcs_without_feature_names_feature() {
+        let err =
+            storage_config(&["persist", "--storage", "gcs", "--path", "b", "list"]).unwrap_err();
+        assert!(err.to_string().contains("'gcs' feature"), "{err}");
+    }
+
+    #[test]
+    fn test_exit_codes_by_error_class() {
+        let code = |error: anyhow::Error| exit_code_for(&error);
+        assert_eq!(
+            code(NoMatchingSnapshot("agent a".into()).into()),
+            exit_code::NOT_FOUND
+        );
+        assert_eq!(
+            code(PersistError::s3_not_found("b".into(), "k".into()).into()),
+            exit_code::NOT_FOUND
+        );
+        assert_eq!(
+            code(PersistError::validation("Path traversal detected").into()),
+            exit_code::USAGE
+        );
+        assert_eq!(code(UsageError("bad".into()).into()), exit_code::USAGE);
+        assert_eq!(
+            code(PersistError::invalid_format("truncated").into()),
+            exit_code::INTEGRITY
+        );
+        assert_eq!(
+            code(PersistError::s3_access_denied("b".into()).into()),
+            exit_code::PERMISSION
+        );
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            code(PersistError::io_read(denied, "read").into()),
+            exit_code::PERMISSION
+        );
+        assert_eq!(
+            code(PersistError::storage("down").into()),
+            exit_code::STORAGE
+        );
+        assert_eq!(
+            code(PersistError::throttled("slow down", None).into()),
+            exit_code::STORAGE
+        );
+        assert_eq!(
+            code(ConfigError(anyhow::anyhow!("unknown profile")).into()),
+            exit_code::CONFIG
+        );
+        assert_eq!(
+            code(PersistError::encryption("wrong key").into()),
+            exit_code::ENCRYPTION
+        );
+        assert_eq!(code(anyhow::anyhow!("2 of 3 failed")), exit_code::FAILURE);
+
+        let not_found: anyhow::Error = SnapshotNotFound("a/1.json.gz".into()).into();
+        assert_eq!(error_path(&not_found).as_deref(), Some("a/1.json.gz"));
+        assert_eq!(error_path(&anyhow::anyhow!("other")), None);
+    }
+
+    #[test]
+    fn test_parse_age_and_size() {
+        assert_eq!(parse_age("30d").unwrap(), chrono::Duration::days(30));
+        assert_eq!(parse_age("12h").unwrap(), chrono::Duration::hours(12));
+        assert_eq!(parse_age("2w").unwrap(), chrono::Duration::weeks(2));
+        assert_eq!(parse_age("90").unwrap(), chrono::Duration::seconds(90));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("1KB").unwrap(), 1024);
+        assert_eq!(parse_size("50GB").unwrap(), 50 << 30);
+        assert_eq!(parse_size("1.5mb").unwrap(), 3 << 19);
+        assert!(parse_size("GB").is_err());
+        assert!(parse_size("10PB").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("2024-03-01T12:00:00Z").unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+        );
+        let two_hours_ago = Utc::now() - chrono::Duration::hours(2);
+        let parsed = parse_time("2h").unwrap();
+        assert!((parsed - two_hours_ago).num_seconds().abs() <= 1);
+        assert!(parse_time("7d").unwrap() < parsed);
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_disk_storage_config() {
+        let config = storage_config(&["persist", "--path", "/tmp/snaps", "list"]).unwrap();
+        assert_eq!(config.backend, StorageBackend::Local);
+        assert_eq!(config.local_base_path, Some(PathBuf::from("/tmp/snaps")));
+    }
+}
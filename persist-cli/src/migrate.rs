@@ -0,0 +1,323 @@
+//! `persist migrate-format`: rewrite snapshots of older format versions in place
+
+use crate::OutputFormat;
+use persist_core::{
+    compressor_for, config::StorageConfig, create_engine_from_config, create_storage_from_config,
+    detect_algorithm, metadata::METADATA_FORMAT_VERSION, CompressionAdapter, StorageAdapter,
+};
+use serde_json::json;
+use tabled::{Table, Tabled};
+use tracing::info;
+
+/// Parse a `--to` format version: `latest` or a major version number
+pub fn parse_format_version(s: &str) -> Result<u8, String> {
+    if s.eq_ignore_ascii_case("latest") {
+        return Ok(METADATA_FORMAT_VERSION);
+    }
+    let version: u8 = s
+        .parse()
+        .map_err(|_| format!("invalid format version '{s}', expected latest or e.g. 1"))?;
+    if version > METADATA_FORMAT_VERSION {
+        return Err(format!(
+            "unknown format version {version} (latest: {METADATA_FORMAT_VERSION})"
+        ));
+    }
+    Ok(version)
+}
+
+/// What `persist migrate-format` did, or in a dry run would do, with one snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MigrateOutcome {
+    /// Rewritten (or would be) to this "major.minor" version
+    Migrated(String),
+    /// Already at the target version
+    Skipped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+struct MigrateItem {
+    path: String,
+    /// Format version the snapshot was stored with, "unknown" if unreadable
+    version: String,
+    outcome: MigrateOutcome,
+}
+
+#[derive(Tabled)]
+struct MigrateRow {
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Migrated")]
+    migrated: usize,
+    #[tabled(rename = "Skipped")]
+    skipped: usize,
+    #[tabled(rename = "Failed")]
+    failed: usize,
+}
+
+/// Format version recorded in a stored snapshot, as "major.minor", read from the raw
+/// container so it works for snapshots that no longer parse as current metadata
+fn stored_format_version(storage: &dyn StorageAdapter, path: &str) -> Option<String> {
+    let data = storage.load(path).ok()?;
+    let decompressed = compressor_for(detect_algorithm(&data)?, None)
+        .ok()?
+        .decompress(&data)
+        .ok()?;
+    let container: serde_json::Value = serde_json::from_slice(&decompressed).ok()?;
+    let metadata = container.get("metadata")?;
+    let major = metadata.get("format_version")?.as_u64()?;
+    let minor = metadata
+        .get("format_minor_version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0);
+    Some(format!("{major}.{minor}"))
+}
+
+/// Migrate `paths` to major version `target` one at a time
+///
+/// Failures are recorded per snapshot and never stop the run; the engine leaves a
+/// snapshot untouched when its migration fails.
+fn migrate_paths(
+    storage_config: &StorageConfig,
+    paths: &[String],
+    target: u8,
+    dry_run: bool,
+) -> Result<Vec<MigrateItem>, anyhow::Error> {
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let engine = create_engine_from_config(storage_config.clone())?;
+
+    Ok(paths
+        .iter()
+        .map(
+            |path| match engine.migrate_snapshot(path, target, dry_run) {
+                Ok(report) => MigrateItem {
+                    path: path.clone(),
+                    version: report.from,
+                    outcome: if report.migrated {
+                        MigrateOutcome::Migrated(report.to)
+                    } else {
+                        MigrateOutcome::Skipped
+                    },
+                },
+                Err(e) => MigrateItem {
+                    path: path.clone(),
+                    version: stored_format_version(storage.as_ref(), path)
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    outcome: MigrateOutcome::Failed(e.to_string()),
+                },
+            },
+        )
+        .collect())
+}
+
+pub async fn migrate_format(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    target: u8,
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!(
+        "Migrating snapshots under '{}' to format version {}",
+        prefix, target
+    );
+
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let mut paths = storage.list(prefix)?;
+    paths.sort();
+    let items = migrate_paths(storage_config, &paths, target, dry_run)?;
+
+    // Counts per source version: (migrated, skipped, failed)
+    let mut counts: std::collections::BTreeMap<&str, (usize, usize, usize)> =
+        std::collections::BTreeMap::new();
+    let mut failed = Vec::new();
+    for item in &items {
+        let entry = counts.entry(item.version.as_str()).or_default();
+        match &item.outcome {
+            MigrateOutcome::Migrated(_) => entry.0 += 1,
+            MigrateOutcome::Skipped => entry.1 += 1,
+            MigrateOutcome::Failed(error) => {
+                entry.2 += 1;
+                failed.push((item, error.as_str()));
+            }
+        }
+    }
+    let migrated = counts.values().map(|count| count.0).sum::<usize>();
+
+    if format == OutputFormat::Json {
+        let versions: serde_json::Map<String, serde_json::Value> = counts
+            .iter()
+            .map(|(version, (migrated, skipped, failed))| {
+                (
+                    version.to_string(),
+                    json!({ "migrated": migrated, "skipped": skipped, "failed": failed }),
+                )
+            })
+            .collect();
+        let migrated_paths: Vec<_> = items
+            .iter()
+            .filter_map(|item| match &item.outcome {
+                MigrateOutcome::Migrated(to) => {
+                    Some(json!({ "path": item.path, "from": item.version, "to": to }))
+                }
+                _ => None,
+            })
+            .collect();
+        let failures: Vec<_> = failed
+            .iter()
+            .map(|(item, error)| {
+                json!({ "path": item.path, "version": item.version, "error": error })
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "dry_run": dry_run,
+                "target": target,
+                "versions": versions,
+                "migrated": migrated_paths,
+                "failed": failures,
+            })
+        );
+    } else {
+        if !counts.is_empty() {
+            let rows: Vec<MigrateRow> = counts
+                .iter()
+                .map(|(version, (migrated, skipped, failed))| MigrateRow {
+                    version: version.to_string(),
+                    migrated: *migrated,
+                    skipped: *skipped,
+                    failed: *failed,
+                })
+                .collect();
+            println!("{}", Table::new(rows));
+        }
+        for (item, error) in &failed {
+            println!("✗ {}: {error}", item.path);
+        }
+        if dry_run {
+            println!("Would migrate {migrated} snapshots to format version {target}");
+        } else {
+            println!("Migrated {migrated} snapshots to format version {target}");
+        }
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} of {} snapshots failed to migrate",
+            failed.len(),
+            items.len()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{local_store_with_snapshot, tamper_snapshot};
+    use std::path::Path;
+
+    /// Write a gzip-compressed version-0 snapshot, which predates snapshot ids,
+    /// size fields and prefixed content hashes
+    fn write_v0_snapshot(dir: &Path, name: &str, index: u64) {
+        let agent_state = json!({ "memory": ["hello"], "index": index });
+        let hash = persist_core::ContentHash::compute(
+            persist_core::HashAlgorithm::Sha256,
+            agent_state.to_string().as_bytes(),
+        );
+        let container = json!({
+            "metadata": {
+                "agent_id": "agent",
+                "session_id": "session",
+                "snapshot_index": index,
+                "timestamp": "2023-06-01T00:00:00Z",
+                "content_hash": hash.digest,
+                "format_version": 0,
+            },
+            "agent_state": agent_state,
+        });
+        let data = compressor_for("gzip", None)
+            .unwrap()
+            .compress(container.to_string().as_bytes())
+            .unwrap();
+        std::fs::write(dir.join(name), data).unwrap();
+    }
+
+    #[test]
+    fn test_parse_format_version() {
+        assert_eq!(
+            parse_format_version("latest").unwrap(),
+            METADATA_FORMAT_VERSION
+        );
+        assert_eq!(parse_format_version("0").unwrap(), 0);
+        assert!(parse_format_version("99").is_err());
+        assert!(parse_format_version("newest").is_err());
+    }
+
+    #[test]
+    fn test_migrate_format_mixed_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+        write_v0_snapshot(dir.path(), "old0.json.gz", 0);
+        write_v0_snapshot(dir.path(), "old1.json.gz", 1);
+        let paths = ["old0.json.gz", "old1.json.gz", "snap.json.gz"].map(String::from);
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        assert!(engine.load_snapshot("old0.json.gz").is_err());
+
+        let items = migrate_paths(&config, &paths, METADATA_FORMAT_VERSION, true).unwrap();
+        assert!(matches!(items[0].outcome, MigrateOutcome::Migrated(_)));
+        assert_eq!(items[2].outcome, MigrateOutcome::Skipped);
+        assert!(engine.load_snapshot("old0.json.gz").is_err());
+
+        let items = migrate_paths(&config, &paths, METADATA_FORMAT_VERSION, false).unwrap();
+        assert_eq!(items[1].version, "0.0");
+        for path in &paths {
+            let (metadata, _) = engine.load_snapshot(path).unwrap();
+            assert_eq!(metadata.format_version, METADATA_FORMAT_VERSION);
+            assert_eq!(metadata.compression_algorithm, "gzip");
+        }
+        let (_, state) = engine.load_snapshot("old1.json.gz").unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&state).unwrap(),
+            json!({ "memory": ["hello"], "index": 1 })
+        );
+
+        // Already-current snapshots are no-ops
+        let items = migrate_paths(&config, &paths, METADATA_FORMAT_VERSION, false).unwrap();
+        assert!(items
+            .iter()
+            .all(|item| item.outcome == MigrateOutcome::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_format_failure_leaves_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+        write_v0_snapshot(dir.path(), "bad.json.gz", 0);
+        write_v0_snapshot(dir.path(), "good.json.gz", 1);
+        tamper_snapshot(dir.path(), "bad.json.gz");
+        let tampered = std::fs::read(dir.path().join("bad.json.gz")).unwrap();
+
+        let paths = ["bad.json.gz", "good.json.gz"].map(String::from);
+        let items = migrate_paths(&config, &paths, METADATA_FORMAT_VERSION, false).unwrap();
+        assert!(matches!(items[0].outcome, MigrateOutcome::Failed(_)));
+        assert_eq!(items[0].version, "0.0");
+        assert!(matches!(items[1].outcome, MigrateOutcome::Migrated(_)));
+        assert_eq!(
+            std::fs::read(dir.path().join("bad.json.gz")).unwrap(),
+            tampered
+        );
+
+        let result = migrate_format(
+            &config,
+            "",
+            METADATA_FORMAT_VERSION,
+            false,
+            OutputFormat::Json,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
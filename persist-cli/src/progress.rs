@@ -0,0 +1,169 @@
+//! Progress bars for long-running commands
+//!
+//! Bars are drawn on stderr, and only for table output to a terminal without
+//! `--quiet`, so piped and JSON output stays clean. Every [`Progress`] method is a
+//! no-op when bars are disabled.
+
+use crate::OutputFormat;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use persist_core::CallOptions;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Set by `--quiet`
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Suppress progress bars for the rest of the process
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether commands printing `format` should draw progress bars
+pub fn enabled(format: OutputFormat) -> bool {
+    format == OutputFormat::Table
+        && !QUIET.load(Ordering::Relaxed)
+        && std::io::stdout().is_terminal()
+}
+
+/// Item count, current item, bytes transferred and transfer rate of one command
+pub struct Progress {
+    bars: Option<Bars>,
+}
+
+struct Bars {
+    multi: MultiProgress,
+    items: ProgressBar,
+    bytes: ProgressBar,
+}
+
+impl Progress {
+    /// Progress over `total` items, drawn if [`enabled`] for `format`
+    pub fn new(total: usize, format: OutputFormat) -> Self {
+        if enabled(format) {
+            Self::with_target(total, ProgressDrawTarget::stderr())
+        } else {
+            Self::hidden()
+        }
+    }
+
+    /// Progress that is never drawn
+    pub fn hidden() -> Self {
+        Self { bars: None }
+    }
+
+    fn with_target(total: usize, target: ProgressDrawTarget) -> Self {
+        let multi = MultiProgress::with_draw_target(target);
+        let items = multi.add(
+            ProgressBar::new(total as u64).with_style(
+                ProgressStyle::with_template(
+                    "{spinner} [{elapsed_precise}] {bar:30} {pos}/{len} {wide_msg}",
+                )
+                .expect("valid progress template"),
+            ),
+        );
+        let bytes = multi.add(
+            ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("  {bytes} transferred ({binary_bytes_per_sec})")
+                    .expect("valid progress template"),
+            ),
+        );
+        items.enable_steady_tick(Duration::from_millis(100));
+        Self {
+            bars: Some(Bars {
+                multi,
+                items,
+                bytes,
+            }),
+        }
+    }
+
+    /// Show `item` as the one being worked on
+    pub fn start(&self, item: &str) {
+        if let Some(bars) = &self.bars {
+            bars.items.set_message(item.to_string());
+        }
+    }
+
+    /// Count one more item as done
+    pub fn finish_item(&self) {
+        if let Some(bars) = &self.bars {
+            bars.items.inc(1);
+        }
+    }
+
+    /// Count `bytes` more as transferred
+    pub fn add_bytes(&self, bytes: u64) {
+        if let Some(bars) = &self.bars {
+            bars.bytes.inc(bytes);
+        }
+    }
+
+    /// Storage call options feeding the bytes a single call transfers into the bars
+    ///
+    /// Use fresh options for every storage call: the callback turns the call's running
+    /// total into increments.
+    pub fn call_options(&self) -> CallOptions {
+        let Some(bars) = &self.bars else {
+            return CallOptions::default();
+        };
+        let bytes = bars.bytes.clone();
+        let reported = AtomicU64::new(0);
+        CallOptions::default().with_progress(Arc::new(move |transferred, _| {
+            let previous = reported.fetch_max(transferred, Ordering::Relaxed);
+            bytes.inc(transferred.saturating_sub(previous));
+            Ok(())
+        }))
+    }
+
+    /// Print a line to stdout without garbling the bars
+    pub fn println(&self, line: impl std::fmt::Display) {
+        match &self.bars {
+            Some(bars) => bars.multi.suspend(|| println!("{line}")),
+            None => println!("{line}"),
+        }
+    }
+}
+
+impl Drop for Bars {
+    fn drop(&mut self) {
+        self.items.finish_and_clear();
+        self.bytes.finish_and_clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_for_json_and_quiet() {
+        assert!(!enabled(OutputFormat::Json));
+        set_quiet(true);
+        assert!(!enabled(OutputFormat::Table));
+        set_quiet(false);
+
+        let progress = Progress::new(3, OutputFormat::Json);
+        assert!(progress.bars.is_none());
+        assert!(progress.call_options().progress.is_none());
+    }
+
+    #[test]
+    fn test_call_options_count_increments() {
+        let progress = Progress::with_target(1, ProgressDrawTarget::hidden());
+        let bytes = progress.bars.as_ref().unwrap().bytes.clone();
+
+        let options = progress.call_options();
+        for transferred in [10, 30, 30, 50] {
+            options.report_progress(transferred, 50).unwrap();
+        }
+        assert_eq!(bytes.position(), 50);
+
+        // Each call counts from zero again
+        progress.call_options().report_progress(20, 20).unwrap();
+        assert_eq!(bytes.position(), 70);
+        progress.add_bytes(5);
+        assert_eq!(bytes.position(), 75);
+    }
+}
@@ -0,0 +1,221 @@
+//! `persist prune`: delete snapshots according to retention rules
+
+use crate::progress::Progress;
+use crate::{confirm, format_size, snapshot_json, OutputFormat, SnapshotInfo, UsageError};
+use chrono::Utc;
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, retention, RetentionPolicy, SnapshotQuery,
+};
+use serde_json::json;
+use tabled::Table;
+use tracing::info;
+
+pub async fn prune_snapshots(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+    force: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    if policy.is_empty() {
+        return Err(UsageError(
+            "no retention rule given: use --keep-last, --older-than or --max-total-size"
+                .to_string(),
+        )
+        .into());
+    }
+    info!("Pruning snapshots under '{}' with {:?}", prefix, policy);
+
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let snapshots = engine.query(prefix, &SnapshotQuery::new())?;
+    let total = snapshots.len();
+    let plan = policy.plan(snapshots, Utc::now());
+
+    let summary = format!(
+        "{} of {total} snapshots ({}), keeping {} ({})",
+        plan.delete.len(),
+        format_size(plan.delete_size()),
+        plan.keep.len(),
+        format_size(plan.keep_size())
+    );
+
+    let mut failures = Vec::new();
+    // Only the snapshots actually deleted count towards the freed bytes; a dry run
+    // reports what would be freed
+    let mut deleted_bytes = if dry_run { plan.delete_size() } else { 0 };
+    if !dry_run && !plan.delete.is_empty() {
+        if !force
+            && !confirm(
+                &format!("Are you sure you want to delete {summary}?"),
+                format,
+            )?
+        {
+            println!("Prune cancelled");
+            return Ok(());
+        }
+        let progress = Progress::new(plan.delete.len(), format);
+        for snapshot in &plan.delete {
+            progress.start(&snapshot.path);
+            match engine.delete_snapshot(&snapshot.path) {
+                Ok(()) => {
+                    let size = retention::stored_size(&snapshot.metadata);
+                    deleted_bytes += size;
+                    progress.add_bytes(size);
+                    if format == OutputFormat::Table {
+                        progress.println(format_args!("✓ {}", snapshot.path));
+                    }
+                }
+                Err(e) => {
+                    if format == OutputFormat::Table {
+                        progress.println(format_args!("✗ {}: {e}", snapshot.path));
+                    }
+                    failures.push((snapshot.path.as_str(), e.to_string()));
+                }
+            }
+            progress.finish_item();
+        }
+    }
+    let failed = failures.len();
+    let planned = plan.delete.len();
+
+    if format == OutputFormat::Json {
+        let deleted: Vec<_> = plan
+            .delete
+            .iter()
+            .filter(|snapshot| failures.iter().all(|(path, _)| *path != snapshot.path))
+            .map(|snapshot| snapshot_json(&snapshot.path, &snapshot.metadata))
+            .collect();
+        let failures: Vec<_> = failures
+            .iter()
+            .map(|(path, error)| json!({ "path": path, "error": error }))
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "dry_run": dry_run,
+                "deleted": deleted,
+                "failed": failures,
+                "deleted_bytes": deleted_bytes,
+                "kept": plan.keep.len(),
+                "kept_bytes": plan.keep_size(),
+            })
+        );
+    } else if plan.delete.is_empty() {
+        println!("Nothing to prune: all {total} snapshots are kept");
+    } else if dry_run {
+        let doomed: Vec<SnapshotInfo> = plan.delete.into_iter().map(SnapshotInfo::from).collect();
+        println!("{}", Table::new(doomed));
+        println!("Would delete {summary}");
+    } else {
+        let deleted = planned - failed;
+        println!(
+            "Deleted {deleted} snapshots ({}), keeping {}",
+            format_size(deleted_bytes),
+            plan.keep.len()
+        );
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{failed} of {planned} snapshot deletions failed");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::remaining_snapshots;
+    use persist_core::SnapshotMetadata;
+    use std::path::Path;
+
+    /// Local storage config rooted at `dir`, holding snapshots 0..=3 of `agent-1`, one
+    /// day apart with index 3 created today, and snapshot 0 of `agent-2` created today
+    fn local_store_with_history(dir: &Path) -> StorageConfig {
+        let mut config = StorageConfig::default_local();
+        config.local_base_path = Some(dir.to_path_buf());
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        let now = Utc::now();
+        for (agent, index, age_days) in [
+            ("agent-1", 0, 3),
+            ("agent-1", 1, 2),
+            ("agent-1", 2, 1),
+            ("agent-1", 3, 0),
+            ("agent-2", 0, 0),
+        ] {
+            let mut metadata = SnapshotMetadata::new(agent, "session-1", index);
+            metadata.timestamp = now - chrono::Duration::days(age_days);
+            engine
+                .save_snapshot("{}", &metadata, &format!("{agent}/{index}.json.gz"))
+                .unwrap();
+        }
+        config
+    }
+
+    #[tokio::test]
+    async fn test_prune_dry_run_deletes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_history(dir.path());
+        let policy = RetentionPolicy::new().keep_last(1).max_total_size(0);
+
+        prune_snapshots(&config, "", &policy, true, false, OutputFormat::Table)
+            .await
+            .unwrap();
+
+        assert_eq!(remaining_snapshots(&config).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_prune_leaves_survivors() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_history(dir.path());
+
+        let policy = RetentionPolicy::new().older_than(chrono::Duration::hours(36));
+        prune_snapshots(&config, "", &policy, false, true, OutputFormat::Table)
+            .await
+            .unwrap();
+        assert_eq!(
+            remaining_snapshots(&config),
+            [
+                "agent-1/2.json.gz",
+                "agent-1/3.json.gz",
+                "agent-2/0.json.gz"
+            ]
+        );
+
+        let policy = RetentionPolicy::new().keep_last(1);
+        prune_snapshots(
+            &config,
+            "agent-1/",
+            &policy,
+            false,
+            true,
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            remaining_snapshots(&config),
+            ["agent-1/3.json.gz", "agent-2/0.json.gz"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_requires_a_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_history(dir.path());
+        let err = prune_snapshots(
+            &config,
+            "",
+            &RetentionPolicy::new(),
+            false,
+            true,
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("no retention rule"), "{err}");
+        assert_eq!(remaining_snapshots(&config).len(), 5);
+    }
+}
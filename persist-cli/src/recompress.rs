@@ -0,0 +1,494 @@
+//! `persist recompress`: rewrite snapshots with another compression algorithm
+
+use crate::progress::Progress;
+use crate::{format_size, OutputFormat};
+use persist_core::{
+    compressor_for, config::StorageConfig, create_engine_from_config, create_storage_from_config,
+    detect_algorithm, CompressionAdapter, StorageAdapter,
+};
+use serde_json::json;
+use tabled::{Table, Tabled};
+use tracing::info;
+
+/// Compression algorithm and level given to `persist recompress --to`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionTarget {
+    algorithm: String,
+    level: Option<i32>,
+}
+
+impl CompressionTarget {
+    fn compressor(&self) -> Result<Box<dyn CompressionAdapter + Send + Sync>, anyhow::Error> {
+        Ok(compressor_for(&self.algorithm, self.level)?)
+    }
+}
+
+impl std::fmt::Display for CompressionTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.level {
+            Some(level) => write!(f, "{}:{level}", self.algorithm),
+            None => write!(f, "{}", self.algorithm),
+        }
+    }
+}
+
+/// Parse a compression target such as `zstd`, `zstd:19` or `gzip:9`
+pub fn parse_compression(s: &str) -> Result<CompressionTarget, String> {
+    let (algorithm, level) = match s.split_once(':') {
+        Some((algorithm, level)) => {
+            let level = level
+                .parse()
+                .map_err(|_| format!("invalid compression level '{level}'"))?;
+            (algorithm, Some(level))
+        }
+        None => (s, None),
+    };
+    let target = CompressionTarget {
+        algorithm: algorithm.to_ascii_lowercase(),
+        level,
+    };
+    target.compressor().map_err(|e| e.to_string())?;
+    Ok(target)
+}
+
+/// Snapshots a dry run transcodes to estimate the savings of `persist recompress`
+const RECOMPRESS_SAMPLE_SIZE: usize = 20;
+
+/// What `persist recompress` did, or in a dry run would do, with one snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RecompressOutcome {
+    /// Rewritten, or in a dry run transcoded in memory as part of the sample
+    Recompressed {
+        before: usize,
+        after: usize,
+    },
+    /// Would be rewritten, but not part of the dry run sample
+    Pending,
+    /// Already stored with the target algorithm
+    Skipped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+struct RecompressItem {
+    path: String,
+    /// Uncompressed size, for extrapolating dry run samples (0 if unreadable)
+    uncompressed: usize,
+    outcome: RecompressOutcome,
+}
+
+#[derive(Tabled)]
+struct RecompressRow {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Before")]
+    before: String,
+    #[tabled(rename = "After")]
+    after: String,
+    #[tabled(rename = "Saved")]
+    saved: String,
+}
+
+/// Percentage of `before` saved by going down to `after`, for display
+fn savings(before: u64, after: u64) -> String {
+    let saved = before as i64 - after as i64;
+    let percent = if before == 0 {
+        0.0
+    } else {
+        saved as f64 * 100.0 / before as f64
+    };
+    if saved >= 0 {
+        format!("{} ({percent:.1}%)", format_size(saved as u64))
+    } else {
+        format!("-{} ({percent:.1}%)", format_size(saved.unsigned_abs()))
+    }
+}
+
+/// Recompress `paths` with `parallel` workers, each with its own engine
+///
+/// A dry run rewrites nothing: it transcodes an evenly spread sample of up to
+/// [`RECOMPRESS_SAMPLE_SIZE`] snapshots in memory. Failures are recorded per snapshot
+/// and never stop the run. Outcomes are returned in the order of `paths`.
+fn recompress_paths(
+    storage_config: &StorageConfig,
+    paths: &[String],
+    target: &CompressionTarget,
+    parallel: usize,
+    dry_run: bool,
+    progress: &Progress,
+) -> Result<Vec<RecompressItem>, anyhow::Error> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let stride = paths.len().div_ceil(RECOMPRESS_SAMPLE_SIZE).max(1);
+    let next = AtomicUsize::new(0);
+    let worker = || -> Result<Vec<(usize, RecompressItem)>, anyhow::Error> {
+        let storage = create_storage_from_config(storage_config.clone())?;
+        let engine = create_engine_from_config(storage_config.clone())?;
+        let compressor = target.compressor()?;
+        let mut items = Vec::new();
+        loop {
+            let i = next.fetch_add(1, Ordering::SeqCst);
+            let Some(path) = paths.get(i) else {
+                break;
+            };
+            progress.start(path);
+            let metadata = match engine.get_snapshot_metadata(path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    progress.finish_item();
+                    let outcome = RecompressOutcome::Failed(e.to_string());
+                    let path = path.clone();
+                    items.push((
+                        i,
+                        RecompressItem {
+                            path,
+                            uncompressed: 0,
+                            outcome,
+                        },
+                    ));
+                    continue;
+                }
+            };
+            let outcome = if metadata.compression_algorithm == target.algorithm {
+                RecompressOutcome::Skipped
+            } else if !dry_run {
+                match engine.recompress_snapshot(path, compressor.as_ref()) {
+                    Ok(result) => RecompressOutcome::Recompressed {
+                        before: result.before,
+                        after: result.after,
+                    },
+                    Err(e) => RecompressOutcome::Failed(e.to_string()),
+                }
+            } else if i.is_multiple_of(stride) {
+                let transcoded = storage.load(path).and_then(|data| {
+                    let algorithm =
+                        detect_algorithm(&data).unwrap_or(metadata.compression_algorithm.as_str());
+                    let decompressed = compressor_for(algorithm, None)?.decompress(&data)?;
+                    Ok((data.len(), compressor.compress(&decompressed)?.len()))
+                });
+                match transcoded {
+                    Ok((before, after)) => RecompressOutcome::Recompressed { before, after },
+                    Err(e) => RecompressOutcome::Failed(e.to_string()),
+                }
+            } else {
+                RecompressOutcome::Pending
+            };
+            if let RecompressOutcome::Recompressed { before, .. } = outcome {
+                progress.add_bytes(before as u64);
+            }
+            progress.finish_item();
+            items.push((
+                i,
+                RecompressItem {
+                    path: path.clone(),
+                    uncompressed: metadata.uncompressed_size,
+                    outcome,
+                },
+            ));
+        }
+        Ok(items)
+    };
+
+    let mut items = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..parallel.max(1)).map(|_| scope.spawn(worker)).collect();
+        workers
+            .into_iter()
+            .map(|handle| handle.join().expect("recompress worker panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    items.sort_by_key(|(i, _)| *i);
+    Ok(items.into_iter().map(|(_, item)| item).collect())
+}
+
+/// Estimated bytes a dry run would save, extrapolating from the sampled snapshots
+/// by uncompressed size
+fn estimate_savings(items: &[RecompressItem]) -> i64 {
+    let (mut sampled_saved, mut sampled_uncompressed, mut total_uncompressed) = (0i64, 0u64, 0u64);
+    for item in items {
+        match item.outcome {
+            RecompressOutcome::Recompressed { before, after } => {
+                sampled_saved += before as i64 - after as i64;
+                sampled_uncompressed += item.uncompressed as u64;
+                total_uncompressed += item.uncompressed as u64;
+            }
+            RecompressOutcome::Pending => total_uncompressed += item.uncompressed as u64,
+            RecompressOutcome::Skipped | RecompressOutcome::Failed(_) => {}
+        }
+    }
+    if sampled_uncompressed == 0 {
+        return 0;
+    }
+    (sampled_saved as f64 * total_uncompressed as f64 / sampled_uncompressed as f64).round() as i64
+}
+
+pub async fn recompress_snapshots(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    target: &CompressionTarget,
+    parallel: usize,
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Recompressing snapshots under '{}' to {}", prefix, target);
+
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let mut paths = storage.list(prefix)?;
+    paths.sort();
+    let progress = Progress::new(paths.len(), format);
+    let items = recompress_paths(storage_config, &paths, target, parallel, dry_run, &progress)?;
+    drop(progress);
+
+    let mut recompressed = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+    let mut pending = 0;
+    let (mut before_bytes, mut after_bytes) = (0u64, 0u64);
+    for item in &items {
+        match &item.outcome {
+            RecompressOutcome::Recompressed { before, after } => {
+                before_bytes += *before as u64;
+                after_bytes += *after as u64;
+                recompressed.push((item.path.as_str(), *before as u64, *after as u64));
+            }
+            RecompressOutcome::Pending => pending += 1,
+            RecompressOutcome::Skipped => skipped.push(item.path.as_str()),
+            RecompressOutcome::Failed(error) => failed.push((item.path.as_str(), error.as_str())),
+        }
+    }
+
+    if format == OutputFormat::Json {
+        let sizes: Vec<_> = recompressed
+            .iter()
+            .map(|(path, before, after)| json!({ "path": path, "before": before, "after": after }))
+            .collect();
+        let failures: Vec<_> = failed
+            .iter()
+            .map(|(path, error)| json!({ "path": path, "error": error }))
+            .collect();
+        let value = if dry_run {
+            json!({
+                "dry_run": true,
+                "target": target.to_string(),
+                "candidates": recompressed.len() + pending,
+                "sampled": sizes,
+                "skipped": skipped,
+                "failed": failures,
+                "estimated_saved_bytes": estimate_savings(&items),
+            })
+        } else {
+            json!({
+                "dry_run": false,
+                "target": target.to_string(),
+                "recompressed": sizes,
+                "skipped": skipped,
+                "failed": failures,
+                "before_bytes": before_bytes,
+                "after_bytes": after_bytes,
+                "saved_bytes": before_bytes as i64 - after_bytes as i64,
+            })
+        };
+        println!("{value}");
+    } else {
+        if !recompressed.is_empty() {
+            let rows: Vec<RecompressRow> = recompressed
+                .iter()
+                .map(|(path, before, after)| RecompressRow {
+                    path: path.to_string(),
+                    before: format_size(*before),
+                    after: format_size(*after),
+                    saved: savings(*before, *after),
+                })
+                .collect();
+            println!("{}", Table::new(rows));
+        }
+        for (path, error) in &failed {
+            println!("✗ {path}: {error}");
+        }
+        if !skipped.is_empty() {
+            println!(
+                "Skipped {} snapshots already stored as {}",
+                skipped.len(),
+                target.algorithm
+            );
+        }
+        if dry_run {
+            let candidates = recompressed.len() + pending;
+            let estimate = estimate_savings(&items);
+            println!(
+                "Would recompress {candidates} snapshots to {target}; estimated savings {}{} from {} sampled",
+                if estimate < 0 { "-" } else { "" },
+                format_size(estimate.unsigned_abs()),
+                recompressed.len()
+            );
+        } else {
+            println!(
+                "Recompressed {} snapshots to {target}: {} -> {}, saved {}",
+                recompressed.len(),
+                format_size(before_bytes),
+                format_size(after_bytes),
+                savings(before_bytes, after_bytes)
+            );
+        }
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} of {} snapshots failed to recompress",
+            failed.len(),
+            items.len()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::remaining_snapshots;
+    use persist_core::SnapshotMetadata;
+    use std::path::Path;
+
+    /// Store with gzip snapshots `g/0..3` and uncompressed snapshots `n/0..3`
+    fn local_store_with_mixed_compression(dir: &Path) -> StorageConfig {
+        let mut config = StorageConfig::default_local();
+        config.local_base_path = Some(dir.to_path_buf());
+        let gzip = create_engine_from_config(config.clone()).unwrap();
+        let plain = persist_core::SnapshotEngine::new(
+            persist_core::LocalFileStorage::with_base_dir(dir),
+            persist_core::compression::NoCompression::new(),
+        );
+        for index in 0..3 {
+            let state = json!({ "memory": vec!["hello world"; 50], "index": index }).to_string();
+            let metadata = SnapshotMetadata::builder("agent", "session", index)
+                .tag("env", "test")
+                .build();
+            gzip.save_snapshot(&state, &metadata, &format!("g/{index}.json.gz"))
+                .unwrap();
+            plain
+                .save_snapshot(&state, &metadata, &format!("n/{index}.json"))
+                .unwrap();
+        }
+        config
+    }
+
+    fn stored_bytes(dir: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut files = Vec::new();
+        for sub in ["g", "n"] {
+            for entry in std::fs::read_dir(dir.join(sub)).unwrap() {
+                let path = entry.unwrap().path();
+                files.push((path.display().to_string(), std::fs::read(&path).unwrap()));
+            }
+        }
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_parse_compression() {
+        assert_eq!(
+            parse_compression("zstd:19").unwrap(),
+            CompressionTarget {
+                algorithm: "zstd".into(),
+                level: Some(19)
+            }
+        );
+        assert_eq!(parse_compression("GZIP").unwrap().to_string(), "gzip");
+        assert!(parse_compression("zstd:fast").is_err());
+        assert!(parse_compression("gzip:12").is_err());
+        assert!(parse_compression("lz4").is_err());
+    }
+
+    #[test]
+    fn test_recompress_mixed_corpus() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_mixed_compression(dir.path());
+        let paths = remaining_snapshots(&config);
+        assert_eq!(paths.len(), 6);
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        let states: Vec<String> = paths
+            .iter()
+            .map(|path| engine.load_snapshot(path).unwrap().1)
+            .collect();
+
+        let target = parse_compression("zstd:19").unwrap();
+        let items =
+            recompress_paths(&config, &paths, &target, 3, false, &Progress::hidden()).unwrap();
+        for item in &items {
+            let RecompressOutcome::Recompressed { before, after } = item.outcome else {
+                panic!("{item:?}");
+            };
+            if item.path.starts_with("n/") {
+                assert!(after < before, "{item:?}");
+            }
+        }
+        for (path, state) in paths.iter().zip(&states) {
+            engine.verify_snapshot(path).unwrap();
+            let (metadata, loaded) = engine.load_snapshot(path).unwrap();
+            assert_eq!(metadata.compression_algorithm, "zstd");
+            assert_eq!(metadata.tags.get("env").map(String::as_str), Some("test"));
+            assert_eq!(&loaded, state);
+        }
+
+        // A second run finds nothing left to do
+        let items =
+            recompress_paths(&config, &paths, &target, 1, false, &Progress::hidden()).unwrap();
+        assert!(items
+            .iter()
+            .all(|item| item.outcome == RecompressOutcome::Skipped));
+    }
+
+    #[test]
+    fn test_recompress_skips_target_and_reports_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_mixed_compression(dir.path());
+        let mut paths = remaining_snapshots(&config);
+        std::fs::write(dir.path().join("n/broken.json"), b"not a snapshot").unwrap();
+        paths.push("n/broken.json".to_string());
+
+        let target = parse_compression("gzip").unwrap();
+        let items =
+            recompress_paths(&config, &paths, &target, 2, false, &Progress::hidden()).unwrap();
+        let outcome = |path: &str| {
+            let item = items.iter().find(|item| item.path == path).unwrap();
+            item.outcome.clone()
+        };
+        assert_eq!(outcome("g/0.json.gz"), RecompressOutcome::Skipped);
+        assert!(matches!(
+            outcome("n/0.json"),
+            RecompressOutcome::Recompressed { .. }
+        ));
+        assert!(matches!(
+            outcome("n/broken.json"),
+            RecompressOutcome::Failed(_)
+        ));
+        // The failure did not stop the others
+        assert!(matches!(
+            outcome("n/2.json"),
+            RecompressOutcome::Recompressed { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recompress_dry_run_touches_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_mixed_compression(dir.path());
+        let before = stored_bytes(dir.path());
+        let paths = remaining_snapshots(&config);
+
+        let target = parse_compression("zstd").unwrap();
+        let items =
+            recompress_paths(&config, &paths, &target, 2, true, &Progress::hidden()).unwrap();
+        assert!(items
+            .iter()
+            .any(|item| matches!(item.outcome, RecompressOutcome::Recompressed { .. })));
+        assert!(estimate_savings(&items) > 0);
+        recompress_snapshots(&config, "", &target, 1, true, OutputFormat::Json)
+            .await
+            .unwrap();
+        assert_eq!(stored_bytes(dir.path()), before);
+    }
+}
@@ -0,0 +1,579 @@
+/*!
+Config file, profiles and the effective settings of a `persist` invocation.
+
+The config file is `~/.config/persist/config.toml` unless `--config` or
+`PERSIST_CONFIG` names another one. It defines named profiles:
+
+```toml
+# Profile used when --profile is not given (otherwise "default", if defined)
+default_profile = "dev"
+
+[profiles.dev]
+storage = "disk"
+path = "./snapshots"
+
+[profiles.prod]
+storage = "s3"
+bucket = "my-production-snapshots"
+region = "us-west-2"
+output = "json"
+```
+
+The file may also be YAML, when its name ends in `.yaml` or `.yml`. `${NAME}` in the
+file is replaced by the environment variable `NAME`.
+
+Each setting comes from the first source that has it: command-line flags, then the
+selected profile, then environment variables, then the built-in default.
+*/
+
+use crate::{Cli, OutputFormat, StorageType};
+use anyhow::Context;
+use clap::ValueEnum;
+use persist_core::ConfigFormat;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Config file location relative to the home directory
+const DEFAULT_CONFIG_PATH: &str = ".config/persist/config.toml";
+
+/// Profile used when neither `--profile` nor `default_profile` names one
+const DEFAULT_PROFILE: &str = "default";
+
+/// Environment variables holding credentials, which are never displayed
+const SECRET_ENV_VARS: &[&str] = &[
+    "AWS_ACCESS_KEY_ID",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_SESSION_TOKEN",
+];
+
+/// Contents of a config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Profile to use when `--profile` is not given
+    pub default_profile: Option<String>,
+    /// Profiles by name
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Settings of a named profile, all optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Storage backend
+    pub storage: Option<StorageType>,
+    /// Base directory for disk storage
+    pub path: Option<String>,
+    /// Bucket for S3 and GCS storage
+    pub bucket: Option<String>,
+    /// AWS region for S3 storage
+    pub region: Option<String>,
+    /// Object name prefix within the GCS bucket
+    pub prefix: Option<String>,
+    /// Service account key file for GCS
+    pub gcs_credentials: Option<PathBuf>,
+    /// Output format
+    pub output: Option<OutputFormat>,
+}
+
+/// Where the value of a setting came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Flag,
+    Profile,
+    Env(&'static str),
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Flag => write!(f, "flag"),
+            Source::Profile => write!(f, "profile"),
+            Source::Env(name) => write!(f, "env {name}"),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Value of a setting and its source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T, source: Source) -> Self {
+        Self { value, source }
+    }
+}
+
+/// Effective settings after merging flags, the config file and the environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Config file that was read, if any
+    pub config_file: Option<PathBuf>,
+    /// Profile whose values were used, if any
+    pub profile: Option<String>,
+    pub storage: Setting<StorageType>,
+    /// Directory for disk storage, bucket for S3 and GCS (no default for buckets)
+    pub location: Option<Setting<String>>,
+    pub region: Option<Setting<String>>,
+    pub gcs_prefix: Option<Setting<String>>,
+    pub gcs_credentials: Option<Setting<PathBuf>>,
+    pub output: Setting<OutputFormat>,
+    /// Credential environment variables that are set
+    pub secrets: Vec<&'static str>,
+}
+
+impl Settings {
+    /// Merge the flags of `cli`, the selected profile and environment variables
+    /// looked up with `env`
+    pub fn resolve(cli: &Cli, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let env = |name: &str| env(name).filter(|value| !value.is_empty());
+        let config = load_config(cli.config.as_deref(), env)?;
+        let (profile_name, profile) = select_profile(
+            cli.profile.clone().or_else(|| env("PERSIST_PROFILE")),
+            config.as_ref(),
+        )?;
+
+        let env_storage = match env("PERSIST_DEFAULT_STORAGE") {
+            Some(value) => Some((
+                StorageType::from_str(&value, true)
+                    .map_err(|_| anyhow::anyhow!("invalid PERSIST_DEFAULT_STORAGE '{value}'"))?,
+                "PERSIST_DEFAULT_STORAGE",
+            )),
+            None => None,
+        };
+        let storage = pick(cli.storage, profile.storage, env_storage)
+            .unwrap_or(Setting::new(StorageType::Disk, Source::Default));
+
+        let location = match storage.value {
+            StorageType::Disk => pick(
+                cli.path.clone(),
+                profile.path,
+                from_env(env, &["PERSIST_DEFAULT_PATH"]),
+            )
+            .or(Some(Setting::new(
+                "./snapshots".to_string(),
+                Source::Default,
+            ))),
+            StorageType::S3 => pick(
+                cli.path.clone(),
+                profile.bucket,
+                from_env(env, &["AWS_S3_BUCKET", "PERSIST_DEFAULT_PATH"]),
+            ),
+            StorageType::GCS => pick(
+                cli.path.clone(),
+                profile.bucket,
+                from_env(env, &["GCS_BUCKET", "PERSIST_DEFAULT_PATH"]),
+            ),
+        };
+
+        Ok(Self {
+            config_file: config.map(|(path, _)| path),
+            profile: profile_name,
+            storage,
+            location,
+            region: pick(None, profile.region, from_env(env, &["AWS_REGION"])),
+            gcs_prefix: pick(
+                cli.gcs_prefix.clone(),
+                profile.prefix,
+                from_env(env, &["PERSIST_GCS_PREFIX"]),
+            ),
+            gcs_credentials: pick(
+                cli.gcs_credentials.clone(),
+                profile.gcs_credentials,
+                from_env(env, &["GOOGLE_APPLICATION_CREDENTIALS"])
+                    .map(|(value, name)| (PathBuf::from(value), name)),
+            ),
+            output: pick(cli.output, profile.output, None)
+                .unwrap_or(Setting::new(OutputFormat::Table, Source::Default)),
+            secrets: SECRET_ENV_VARS
+                .iter()
+                .copied()
+                .filter(|&name| env(name).is_some())
+                .collect(),
+        })
+    }
+
+    /// Settings that apply to the selected backend as (name, value, source), with
+    /// credentials redacted
+    pub fn rows(&self) -> Vec<(&'static str, String, String)> {
+        fn row<T: ToString>(
+            name: &'static str,
+            setting: &Setting<T>,
+        ) -> (&'static str, String, String) {
+            (name, setting.value.to_string(), setting.source.to_string())
+        }
+
+        let mut rows = vec![(
+            "storage",
+            value_name(self.storage.value),
+            self.storage.source.to_string(),
+        )];
+        let location_name = match self.storage.value {
+            StorageType::Disk => "path",
+            StorageType::S3 | StorageType::GCS => "bucket",
+        };
+        rows.extend(
+            self.location
+                .iter()
+                .map(|setting| row(location_name, setting)),
+        );
+        match self.storage.value {
+            StorageType::Disk => {}
+            StorageType::S3 => {
+                rows.extend(self.region.iter().map(|setting| row("region", setting)));
+                rows.extend(
+                    self.secrets
+                        .iter()
+                        .map(|&name| (name, "********".to_string(), Source::Env(name).to_string())),
+                );
+            }
+            StorageType::GCS => {
+                rows.extend(self.gcs_prefix.iter().map(|setting| row("prefix", setting)));
+                rows.extend(self.gcs_credentials.iter().map(|setting| {
+                    let value = setting.value.display().to_string();
+                    ("gcs_credentials", value, setting.source.to_string())
+                }));
+            }
+        }
+        rows.push((
+            "output",
+            value_name(self.output.value),
+            self.output.source.to_string(),
+        ));
+        rows
+    }
+}
+
+/// Name of an enum value as typed on the command line
+pub fn value_name(value: impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// First of the flag, profile and environment values that is set
+fn pick<T>(
+    flag: Option<T>,
+    profile: Option<T>,
+    env: Option<(T, &'static str)>,
+) -> Option<Setting<T>> {
+    flag.map(|value| Setting::new(value, Source::Flag))
+        .or_else(|| profile.map(|value| Setting::new(value, Source::Profile)))
+        .or_else(|| env.map(|(value, name)| Setting::new(value, Source::Env(name))))
+}
+
+/// Value of the first of `names` that is set in the environment
+fn from_env(
+    env: impl Fn(&str) -> Option<String>,
+    names: &[&'static str],
+) -> Option<(String, &'static str)> {
+    names
+        .iter()
+        .find_map(|&name| env(name).map(|value| (value, name)))
+}
+
+/// Read the config file named by `--config` or `PERSIST_CONFIG`, which must exist, or
+/// the one at the default location, which may be missing
+fn load_config(
+    flag: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<Option<(PathBuf, ConfigFile)>> {
+    let explicit = flag
+        .map(Path::to_path_buf)
+        .or_else(|| env("PERSIST_CONFIG").map(PathBuf::from));
+    let (path, required) = match explicit {
+        Some(path) => (path, true),
+        None => match env("HOME") {
+            Some(home) => (Path::new(&home).join(DEFAULT_CONFIG_PATH), false),
+            None => return Ok(None),
+        },
+    };
+
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("cannot read config file {}", path.display()))
+        }
+    };
+    // Files named without a .yaml or .yml extension are TOML
+    let format = ConfigFormat::from_path(&path).unwrap_or(ConfigFormat::Toml);
+    let file: ConfigFile = format
+        .parse(&text, &env)
+        .with_context(|| format!("malformed config file {}", path.display()))?;
+    Ok(Some((path, file)))
+}
+
+/// Profile named by `--profile` or `PERSIST_PROFILE`, else by `default_profile`, else
+/// the `default` profile if the file defines one
+fn select_profile(
+    requested: Option<String>,
+    config: Option<&(PathBuf, ConfigFile)>,
+) -> anyhow::Result<(Option<String>, Profile)> {
+    let requested = requested.or_else(|| config.and_then(|(_, file)| file.default_profile.clone()));
+    let Some(name) = requested else {
+        let profile = config.and_then(|(_, file)| file.profiles.get(DEFAULT_PROFILE));
+        return Ok(match profile {
+            Some(profile) => (Some(DEFAULT_PROFILE.to_string()), profile.clone()),
+            None => (None, Profile::default()),
+        });
+    };
+
+    let Some((path, file)) = config else {
+        anyhow::bail!("profile '{name}' requested but no config file was found");
+    };
+    match file.profiles.get(&name) {
+        Some(profile) => Ok((Some(name), profile.clone())),
+        None => {
+            let known: Vec<&str> = file.profiles.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "profile '{name}' is not defined in {} (available: {})",
+                path.display(),
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::collections::HashMap;
+
+    const CONFIG: &str = r#"
+default_profile = "dev"
+
+[profiles.dev]
+storage = "disk"
+path = "/from-file"
+
+[profiles.prod]
+storage = "s3"
+bucket = "prod-bucket"
+region = "eu-west-1"
+output = "json"
+"#;
+
+    fn write_config(text: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, text).unwrap();
+        (dir, path)
+    }
+
+    fn resolve(args: &[&str], env: &[(&str, &str)]) -> anyhow::Result<Settings> {
+        let cli = Cli::try_parse_from(
+            std::iter::once("persist")
+                .chain(args.iter().copied())
+                .chain(["list"]),
+        )
+        .unwrap();
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Settings::resolve(&cli, |name| env.get(name).cloned())
+    }
+
+    #[test]
+    fn test_precedence_flag_then_file_then_env() {
+        let (_dir, path) = write_config(CONFIG);
+        let config = path.to_str().unwrap();
+        let env = [("PERSIST_DEFAULT_PATH", "/from-env")];
+
+        let settings = resolve(&["--config", config, "--path", "/from-flag"], &env).unwrap();
+        assert_eq!(
+            settings.location,
+            Some(Setting::new("/from-flag".to_string(), Source::Flag))
+        );
+
+        let settings = resolve(&["--config", config], &env).unwrap();
+        assert_eq!(settings.profile.as_deref(), Some("dev"));
+        assert_eq!(
+            settings.location,
+            Some(Setting::new("/from-file".to_string(), Source::Profile))
+        );
+
+        let settings = resolve(&[], &env).unwrap();
+        assert_eq!(settings.config_file, None);
+        assert_eq!(
+            settings.location,
+            Some(Setting::new(
+                "/from-env".to_string(),
+                Source::Env("PERSIST_DEFAULT_PATH")
+            ))
+        );
+
+        let settings = resolve(&[], &[]).unwrap();
+        assert_eq!(settings.storage.source, Source::Default);
+        assert_eq!(settings.output.source, Source::Default);
+        assert_eq!(
+            settings.location,
+            Some(Setting::new("./snapshots".to_string(), Source::Default))
+        );
+    }
+
+    #[test]
+    fn test_profile_selection() {
+        let (_dir, path) = write_config(CONFIG);
+        let config = path.to_str().unwrap();
+        let env = [("AWS_S3_BUCKET", "env-bucket"), ("AWS_REGION", "us-east-1")];
+
+        let settings = resolve(&["--config", config, "--profile", "prod"], &env).unwrap();
+        assert_eq!(
+            settings.storage,
+            Setting::new(StorageType::S3, Source::Profile)
+        );
+        assert_eq!(settings.location.unwrap().value, "prod-bucket");
+        assert_eq!(settings.region.unwrap().value, "eu-west-1");
+        assert_eq!(settings.output.value, OutputFormat::Json);
+
+        // Flags still win over the profile
+        let settings = resolve(
+            &["--config", config, "--profile", "prod", "--output", "table"],
+            &env,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.output,
+            Setting::new(OutputFormat::Table, Source::Flag)
+        );
+
+        let settings = resolve(
+            &[],
+            &[("PERSIST_CONFIG", config), ("PERSIST_PROFILE", "prod")],
+        )
+        .unwrap();
+        assert_eq!(settings.config_file.as_deref(), Some(path.as_path()));
+        assert_eq!(settings.profile.as_deref(), Some("prod"));
+
+        let err = resolve(&["--config", config, "--profile", "staging"], &[]).unwrap_err();
+        assert!(err.to_string().contains("available: dev, prod"), "{err}");
+        let err = resolve(&["--profile", "prod"], &[]).unwrap_err();
+        assert!(err.to_string().contains("no config file"), "{err}");
+    }
+
+    #[test]
+    fn test_env_storage() {
+        let settings = resolve(
+            &[],
+            &[
+                ("PERSIST_DEFAULT_STORAGE", "gcs"),
+                ("GCS_BUCKET", "gcs-bucket"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            settings.storage,
+            Setting::new(StorageType::GCS, Source::Env("PERSIST_DEFAULT_STORAGE"))
+        );
+        assert_eq!(settings.location.unwrap().source, Source::Env("GCS_BUCKET"));
+
+        let err = resolve(&[], &[("PERSIST_DEFAULT_STORAGE", "tape")]).unwrap_err();
+        assert!(err.to_string().contains("PERSIST_DEFAULT_STORAGE"), "{err}");
+    }
+
+    #[test]
+    fn test_missing_config_file() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path().to_str().unwrap();
+        let settings = resolve(&[], &[("HOME", home)]).unwrap();
+        assert_eq!(settings.config_file, None);
+        assert_eq!(settings.profile, None);
+
+        let missing = format!("{home}/missing.toml");
+        let err = resolve(&["--config", &missing], &[]).unwrap_err();
+        assert!(err.to_string().contains("cannot read config file"), "{err}");
+        assert!(err.downcast_ref::<std::io::Error>().is_some());
+        let err = resolve(&[], &[("PERSIST_CONFIG", &missing)]).unwrap_err();
+        assert!(err.to_string().contains("missing.toml"), "{err}");
+    }
+
+    #[test]
+    fn test_default_location_and_profile() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(DEFAULT_CONFIG_PATH);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "[profiles.default]\npath = \"/home-store\"\n").unwrap();
+
+        let settings = resolve(&[], &[("HOME", home.path().to_str().unwrap())]).unwrap();
+        assert_eq!(settings.config_file, Some(path));
+        assert_eq!(settings.profile.as_deref(), Some("default"));
+        assert_eq!(settings.location.unwrap().value, "/home-store");
+    }
+
+    #[test]
+    fn test_malformed_config_file() {
+        for text in [
+            "[profiles.dev\npath = 1",
+            "[profiles.dev]\nstorage = \"tape\"",
+            "colour = 1",
+        ] {
+            let (_dir, path) = write_config(text);
+            let err = resolve(&["--config", path.to_str().unwrap()], &[]).unwrap_err();
+            assert!(err.to_string().contains("malformed config file"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_yaml_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(
+            &path,
+            "default_profile: prod\nprofiles:\n  prod:\n    storage: s3\n    bucket: ${BUCKET}\n",
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+
+        let settings = resolve(&["--config", config], &[("BUCKET", "yaml-bucket")]).unwrap();
+        assert_eq!(settings.profile.as_deref(), Some("prod"));
+        assert_eq!(
+            settings.location,
+            Some(Setting::new("yaml-bucket".to_string(), Source::Profile))
+        );
+
+        let err = resolve(&["--config", config], &[]).unwrap_err();
+        assert!(err.to_string().contains("malformed config file"), "{err}");
+        assert!(format!("{err:#}").contains("BUCKET"), "{err:#}");
+    }
+
+    #[test]
+    fn test_rows_redact_secrets() {
+        let settings = resolve(
+            &["--storage", "s3", "--path", "bucket"],
+            &[
+                ("AWS_ACCESS_KEY_ID", "AKIAEXAMPLE"),
+                ("AWS_SECRET_ACCESS_KEY", "hunter2"),
+            ],
+        )
+        .unwrap();
+        let rows = settings.rows();
+        assert!(rows.contains(&("storage", "s3".to_string(), "flag".to_string())));
+        assert!(rows.contains(&(
+            "AWS_SECRET_ACCESS_KEY",
+            "********".to_string(),
+            "env AWS_SECRET_ACCESS_KEY".to_string()
+        )));
+        for (_, value, _) in rows {
+            assert!(
+                !value.contains("hunter2") && !value.contains("AKIA"),
+                "{value}"
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! `persist show`: a snapshot's metadata, or its agent state with `--raw` or `--field`
+
+use crate::{FieldNotFound, OutputFormat, SnapshotNotFound};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, CompatibilityMode, PersistError,
+};
+use tracing::{error, info};
+
+/// Print the metadata of a snapshot
+///
+/// With a compatibility `full_verify` mode, the whole snapshot is loaded and verified
+/// first; otherwise only its metadata is read.
+pub async fn show_snapshot(
+    storage_config: &StorageConfig,
+    snapshot_id: &str,
+    show_sensitive: bool,
+    full_verify: Option<CompatibilityMode>,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Showing snapshot: {}", snapshot_id);
+
+    let mut engine = create_engine_from_config(storage_config.clone())?;
+    let loaded = match full_verify {
+        Some(mode) => {
+            engine.set_compatibility_mode(mode);
+            engine
+                .load_snapshot(snapshot_id)
+                .map(|(metadata, _)| metadata)
+        }
+        None => engine.read_snapshot_metadata(snapshot_id),
+    };
+
+    match loaded {
+        Ok(metadata) if format == OutputFormat::Json => {
+            let value = if show_sensitive {
+                // Fails if sensitive values are still encrypted
+                metadata.revealed()?;
+                metadata.to_json_value()
+            } else {
+                metadata.redacted().to_json_value()
+            };
+            println!("{value}");
+        }
+        Ok(metadata) => {
+            let details = if show_sensitive {
+                metadata.revealed()?.to_string()
+            } else {
+                metadata.to_string()
+            };
+            println!("Snapshot Details:");
+            println!("  Path: {snapshot_id}");
+            for line in details.lines() {
+                println!("  {line}");
+            }
+        }
+        Err(e) => {
+            error!("Failed to load snapshot: {}", e);
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a `--field` JSON Pointer (RFC 6901): empty, or starting with `/`
+pub fn parse_pointer(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.starts_with('/') {
+        Ok(s.to_string())
+    } else {
+        Err(format!(
+            "invalid JSON Pointer '{s}', expected it to start with '/' (e.g. /memory/context)"
+        ))
+    }
+}
+
+/// Print the agent state of a snapshot, or the subtree at `field`, as JSON
+///
+/// The state is written straight to stdout rather than formatted into a string first.
+pub async fn show_state(
+    storage_config: &StorageConfig,
+    snapshot_id: &str,
+    field: Option<&str>,
+    compact: bool,
+    mode: CompatibilityMode,
+) -> Result<(), anyhow::Error> {
+    use std::io::Write;
+
+    info!("Showing agent state of snapshot: {}", snapshot_id);
+
+    let mut engine = create_engine_from_config(storage_config.clone())?;
+    engine.set_compatibility_mode(mode);
+
+    if !engine.snapshot_exists(snapshot_id) {
+        return Err(SnapshotNotFound(snapshot_id.to_string()).into());
+    }
+    let (_metadata, agent_json) = engine.load_snapshot(snapshot_id)?;
+    let state: serde_json::Value = serde_json::from_str(&agent_json).map_err(PersistError::Json)?;
+    drop(agent_json);
+
+    let value = match field {
+        Some(pointer) => state.pointer(pointer).ok_or_else(|| FieldNotFound {
+            pointer: pointer.to_string(),
+            snapshot_id: snapshot_id.to_string(),
+        })?,
+        None => &state,
+    };
+
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    if compact {
+        serde_json::to_writer(&mut out, value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut out, value)?;
+    }
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
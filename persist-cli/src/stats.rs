@@ -0,0 +1,245 @@
+//! `persist stats`: snapshot counts, sizes and age ranges, overall and per group
+
+use crate::{format_size, format_timestamp, GroupBy, OutputFormat};
+use chrono::{DateTime, Utc};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, create_storage_from_config, retention,
+    SnapshotEngineInterface, SnapshotMetadata, StorageAdapter,
+};
+use serde_json::json;
+use tabled::{Table, Tabled};
+use tracing::info;
+
+/// Aggregates over a group of snapshots
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SnapshotStats {
+    snapshots: usize,
+    /// Bytes in storage (compressed size, or uncompressed size if not recorded)
+    stored_bytes: u64,
+    uncompressed_bytes: u64,
+    oldest: Option<DateTime<Utc>>,
+    newest: Option<DateTime<Utc>>,
+}
+
+impl SnapshotStats {
+    fn add(&mut self, metadata: &SnapshotMetadata) {
+        self.snapshots += 1;
+        self.stored_bytes += retention::stored_size(metadata);
+        self.uncompressed_bytes += metadata.uncompressed_size as u64;
+        self.oldest = Some(
+            self.oldest
+                .map_or(metadata.timestamp, |t| t.min(metadata.timestamp)),
+        );
+        self.newest = Some(
+            self.newest
+                .map_or(metadata.timestamp, |t| t.max(metadata.timestamp)),
+        );
+    }
+
+    /// Uncompressed bytes per stored byte over the whole group
+    fn compression_ratio(&self) -> Option<f64> {
+        (self.stored_bytes > 0).then(|| self.uncompressed_bytes as f64 / self.stored_bytes as f64)
+    }
+
+    fn to_json(&self, group: &str) -> serde_json::Value {
+        json!({
+            "group": group,
+            "snapshots": self.snapshots,
+            "compressed_bytes": self.stored_bytes,
+            "uncompressed_bytes": self.uncompressed_bytes,
+            "compression_ratio": self.compression_ratio(),
+            "oldest": self.oldest,
+            "newest": self.newest,
+        })
+    }
+
+    fn row(&self, group: &str) -> StatsRow {
+        let time = |t: Option<DateTime<Utc>>| {
+            t.map(|t| format_timestamp(t.timestamp()))
+                .unwrap_or_default()
+        };
+        StatsRow {
+            group: group.to_string(),
+            snapshots: self.snapshots,
+            compressed: format_size(self.stored_bytes),
+            uncompressed: format_size(self.uncompressed_bytes),
+            ratio: self
+                .compression_ratio()
+                .map(|ratio| format!("{ratio:.2}x"))
+                .unwrap_or_default(),
+            oldest: time(self.oldest),
+            newest: time(self.newest),
+        }
+    }
+}
+
+/// Snapshot statistics under a prefix, per group and overall
+#[derive(Debug, Default)]
+struct StatsReport {
+    groups: std::collections::BTreeMap<String, SnapshotStats>,
+    total: SnapshotStats,
+    /// Paths whose metadata could not be read, with the reason
+    unreadable: Vec<(String, String)>,
+}
+
+impl StatsReport {
+    /// Read the metadata of every listed path, grouping readable snapshots
+    fn collect(engine: &dyn SnapshotEngineInterface, paths: &[String], group_by: GroupBy) -> Self {
+        let mut report = StatsReport::default();
+        for path in paths {
+            let metadata = match engine.get_snapshot_metadata(path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    report.unreadable.push((path.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            let group = match group_by {
+                GroupBy::Agent => metadata.agent_id.clone(),
+                GroupBy::Session => format!("{}/{}", metadata.agent_id, metadata.session_id),
+                GroupBy::None => "all".to_string(),
+            };
+            report.groups.entry(group).or_default().add(&metadata);
+            report.total.add(&metadata);
+        }
+        report
+    }
+}
+
+#[derive(Tabled)]
+struct StatsRow {
+    #[tabled(rename = "Group")]
+    group: String,
+    #[tabled(rename = "Snapshots")]
+    snapshots: usize,
+    #[tabled(rename = "Compressed")]
+    compressed: String,
+    #[tabled(rename = "Uncompressed")]
+    uncompressed: String,
+    #[tabled(rename = "Ratio")]
+    ratio: String,
+    #[tabled(rename = "Oldest")]
+    oldest: String,
+    #[tabled(rename = "Newest")]
+    newest: String,
+}
+
+pub async fn show_stats(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    group_by: GroupBy,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Collecting statistics for snapshots under '{}'", prefix);
+
+    // List raw keys rather than querying metadata, so unreadable entries are counted
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let mut paths = storage.list(prefix)?;
+    paths.sort();
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let report = StatsReport::collect(engine.as_ref(), &paths, group_by);
+
+    if format == OutputFormat::Json {
+        let groups: Vec<_> = report
+            .groups
+            .iter()
+            .map(|(group, stats)| stats.to_json(group))
+            .collect();
+        let unreadable: Vec<_> = report
+            .unreadable
+            .iter()
+            .map(|(path, error)| json!({ "path": path, "error": error }))
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "groups": groups,
+                "total": report.total.to_json("total"),
+                "unreadable": unreadable,
+            })
+        );
+        return Ok(());
+    }
+
+    let mut rows: Vec<StatsRow> = Vec::new();
+    if group_by != GroupBy::None {
+        rows.extend(report.groups.iter().map(|(group, stats)| stats.row(group)));
+    }
+    rows.push(report.total.row("total"));
+    println!("{}", Table::new(rows));
+
+    if !report.unreadable.is_empty() {
+        println!("Unreadable snapshots: {}", report.unreadable.len());
+        for (path, error) in &report.unreadable {
+            println!("  ✗ {path}: {error}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::local_store_with_corpus;
+    use persist_core::SnapshotQuery;
+
+    #[test]
+    fn test_stats_groups_and_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        std::fs::write(dir.path().join("b/s1/0.json.gz"), b"not a snapshot").unwrap();
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        let storage = create_storage_from_config(config).unwrap();
+        let paths = storage.list("").unwrap();
+
+        let expected = |agent: Option<&str>, session: Option<&str>| {
+            let mut stats = SnapshotStats::default();
+            for summary in engine.query("", &SnapshotQuery::new()).unwrap() {
+                let metadata = &summary.metadata;
+                if agent.is_none_or(|agent| metadata.agent_id == agent)
+                    && session.is_none_or(|session| metadata.session_id == session)
+                {
+                    stats.add(metadata);
+                }
+            }
+            stats
+        };
+
+        let by_agent = StatsReport::collect(engine.as_ref(), &paths, GroupBy::Agent);
+        assert_eq!(
+            by_agent.groups.keys().collect::<Vec<_>>(),
+            ["agent-a", "agent-b"]
+        );
+        assert_eq!(by_agent.groups["agent-a"].snapshots, 4);
+        assert_eq!(by_agent.groups["agent-a"], expected(Some("agent-a"), None));
+        assert_eq!(by_agent.groups["agent-b"], expected(Some("agent-b"), None));
+        assert_eq!(by_agent.total, expected(None, None));
+        assert_eq!(by_agent.total.snapshots, 5);
+        assert_eq!(by_agent.unreadable.len(), 1);
+        assert_eq!(by_agent.unreadable[0].0, "b/s1/0.json.gz");
+
+        let agent_a = &by_agent.groups["agent-a"];
+        assert!(agent_a.oldest < agent_a.newest);
+        assert!(agent_a.uncompressed_bytes > 0);
+        let ratio = agent_a.compression_ratio().unwrap();
+        let exact = agent_a.uncompressed_bytes as f64 / agent_a.stored_bytes as f64;
+        assert!((ratio - exact).abs() < f64::EPSILON);
+
+        let by_session = StatsReport::collect(engine.as_ref(), &paths, GroupBy::Session);
+        assert_eq!(
+            by_session.groups.keys().collect::<Vec<_>>(),
+            ["agent-a/s1", "agent-a/s2", "agent-b/s1"]
+        );
+        assert_eq!(
+            by_session.groups["agent-a/s1"],
+            expected(Some("agent-a"), Some("s1"))
+        );
+        assert_eq!(by_session.groups["agent-b/s1"].snapshots, 1);
+
+        let ungrouped = StatsReport::collect(engine.as_ref(), &paths, GroupBy::None);
+        assert_eq!(ungrouped.groups.len(), 1);
+        assert_eq!(ungrouped.groups["all"], ungrouped.total);
+        assert!(SnapshotStats::default().compression_ratio().is_none());
+    }
+}
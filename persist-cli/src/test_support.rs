@@ -0,0 +1,85 @@
+//! Snapshot stores shared by the command tests
+
+use chrono::Utc;
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, SnapshotMetadata, SnapshotQuery,
+    StorageAdapter,
+};
+use serde_json::json;
+use std::path::Path;
+
+/// Local storage config rooted at `dir`, holding one snapshot at `snap.json.gz`
+pub fn local_store_with_snapshot(dir: &Path) -> StorageConfig {
+    let mut config = StorageConfig::default_local();
+    config.local_base_path = Some(dir.to_path_buf());
+    let engine = create_engine_from_config(config.clone()).unwrap();
+    let metadata = SnapshotMetadata::new("agent-1", "session-1", 0);
+    engine
+        .save_snapshot(r#"{"memory":["hello"]}"#, &metadata, "snap.json.gz")
+        .unwrap();
+    config
+}
+
+/// Change the agent state stored at `path` under `dir` without updating its hash
+pub fn tamper_snapshot(dir: &Path, path: &str) {
+    use persist_core::{CompressionAdapter, GzipCompressor, LocalFileStorage};
+
+    let storage = LocalFileStorage::with_base_dir(dir);
+    let gzip = GzipCompressor::new();
+    let container = gzip.decompress(&storage.load(path).unwrap()).unwrap();
+    let tampered = String::from_utf8(container)
+        .unwrap()
+        .replace("hello", "jello");
+    storage
+        .save(&gzip.compress(tampered.as_bytes()).unwrap(), path)
+        .unwrap();
+}
+
+pub fn remaining_snapshots(config: &StorageConfig) -> Vec<String> {
+    let engine = create_engine_from_config(config.clone()).unwrap();
+    let mut paths: Vec<String> = engine
+        .query("", &SnapshotQuery::new())
+        .unwrap()
+        .into_iter()
+        .map(|summary| summary.path)
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Local store with snapshots of two agents and sessions, of varied ages, sizes and tags
+pub fn local_store_with_corpus(dir: &Path) -> StorageConfig {
+    let mut config = StorageConfig::default_local();
+    config.local_base_path = Some(dir.to_path_buf());
+    let engine = create_engine_from_config(config.clone()).unwrap();
+    let now = Utc::now();
+    let mut seed = 7u64;
+    for (path, agent, session, index, age_hours, env, len) in [
+        ("a/s1/0", "agent-a", "s1", 0, 72, "prod", 10),
+        ("a/s1/1", "agent-a", "s1", 1, 30, "dev", 2000),
+        ("a/s1/2", "agent-a", "s1", 2, 1, "prod", 500),
+        ("a/s2/0", "agent-a", "s2", 0, 5, "prod", 50),
+        ("b/s1/0", "agent-b", "s1", 0, 200, "dev", 8000),
+        ("b/s1/1", "agent-b", "s1", 1, 3, "prod", 100),
+    ] {
+        // Pseudo-random hex, so that sizes stay apart after compression
+        let data: String = (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                char::from_digit((seed >> 60) as u32, 16).unwrap()
+            })
+            .collect();
+        let mut metadata = SnapshotMetadata::builder(agent, session, index)
+            .tag("env", env)
+            .build();
+        metadata.timestamp = now - chrono::Duration::hours(age_hours);
+        engine
+            .save_snapshot(
+                &json!({ "memory": ["hello", data] }).to_string(),
+                &metadata,
+                &format!("{path}.json.gz"),
+            )
+            .unwrap();
+    }
+    config
+}
@@ -0,0 +1,145 @@
+//! `persist tag` and `persist describe`: update a snapshot's mutable metadata in place
+
+use crate::{snapshot_json, OutputFormat};
+use persist_core::{config::StorageConfig, create_engine_from_config, SnapshotMetadata};
+use tracing::info;
+
+pub async fn tag_snapshot(
+    storage_config: &StorageConfig,
+    snapshot_id: &str,
+    tags: Vec<(String, String)>,
+    remove: Vec<String>,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Updating tags of snapshot: {}", snapshot_id);
+
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let metadata = engine.update_metadata(
+        snapshot_id,
+        Box::new(|metadata: &mut SnapshotMetadata| {
+            for key in &remove {
+                metadata.tags.remove(key);
+            }
+            metadata.tags.extend(tags);
+        }),
+    )?;
+
+    print_updated_metadata(snapshot_id, &metadata, format);
+    Ok(())
+}
+
+pub async fn describe_snapshot(
+    storage_config: &StorageConfig,
+    snapshot_id: &str,
+    description: Option<String>,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Updating description of snapshot: {}", snapshot_id);
+
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let metadata = engine.update_metadata(
+        snapshot_id,
+        Box::new(|metadata: &mut SnapshotMetadata| metadata.description = description),
+    )?;
+
+    print_updated_metadata(snapshot_id, &metadata, format);
+    Ok(())
+}
+
+/// Print the metadata of a snapshot after an in-place update, with sensitive values redacted
+fn print_updated_metadata(snapshot_id: &str, metadata: &SnapshotMetadata, format: OutputFormat) {
+    if format == OutputFormat::Json {
+        println!("{}", snapshot_json(snapshot_id, metadata));
+        return;
+    }
+    println!("✓ Snapshot updated: {snapshot_id}");
+    for line in metadata.to_string().lines() {
+        println!("  {line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::local_store_with_snapshot;
+    use crate::{Cli, Commands};
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn test_tag_and_describe_update_metadata_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_snapshot(dir.path());
+        let engine = create_engine_from_config(config.clone()).unwrap();
+        let (before, state_before) = engine.load_snapshot("snap.json.gz").unwrap();
+
+        let run_command = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["persist"].iter().chain(args)).unwrap();
+            let config = config.clone();
+            async move {
+                match cli.command {
+                    Commands::Tag {
+                        snapshot_id,
+                        tags,
+                        remove,
+                    } => tag_snapshot(&config, &snapshot_id, tags, remove, OutputFormat::Table)
+                        .await
+                        .unwrap(),
+                    Commands::Describe {
+                        snapshot_id,
+                        description,
+                    } => describe_snapshot(&config, &snapshot_id, description, OutputFormat::Table)
+                        .await
+                        .unwrap(),
+                    _ => unreachable!(),
+                }
+            }
+        };
+        let tags = || engine.get_snapshot_metadata("snap.json.gz").unwrap().tags;
+
+        run_command(&[
+            "tag",
+            "snap.json.gz",
+            "--set",
+            "status=known-good",
+            "--set",
+            "phase=pre-migration",
+        ])
+        .await;
+        assert_eq!(tags().len(), 2);
+        assert_eq!(tags()["status"], "known-good");
+
+        run_command(&[
+            "tag",
+            "snap.json.gz",
+            "--set",
+            "status=bad",
+            "--unset",
+            "phase",
+        ])
+        .await;
+        assert_eq!(
+            tags().into_iter().collect::<Vec<_>>(),
+            [("status".to_string(), "bad".to_string())]
+        );
+
+        run_command(&["tag", "snap.json.gz", "--remove", "status"]).await;
+        assert!(tags().is_empty());
+
+        run_command(&["describe", "snap.json.gz", "last good state"]).await;
+        let (after, state_after) = engine.load_snapshot("snap.json.gz").unwrap();
+        assert_eq!(after.description.as_deref(), Some("last good state"));
+        assert_eq!(state_after, state_before);
+        assert_eq!(after.content_hash, before.content_hash);
+        assert_eq!(after.timestamp, before.timestamp);
+        assert_eq!(after.snapshot_id, before.snapshot_id);
+
+        run_command(&["describe", "snap.json.gz"]).await;
+        assert_eq!(
+            engine
+                .get_snapshot_metadata("snap.json.gz")
+                .unwrap()
+                .description,
+            None
+        );
+    }
+}
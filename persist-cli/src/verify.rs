@@ -0,0 +1,367 @@
+//! `persist verify` and `persist verify-all`: integrity checks of stored snapshots
+
+use crate::progress::Progress;
+use crate::OutputFormat;
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, create_storage_from_config,
+    CompatibilityMode, PersistError, StorageAdapter,
+};
+use serde_json::json;
+use tabled::{Table, Tabled};
+use tracing::{error, info};
+
+pub async fn verify_snapshot(
+    storage_config: &StorageConfig,
+    snapshot_id: &str,
+    mode: CompatibilityMode,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Verifying snapshot: {}", snapshot_id);
+
+    let mut engine = create_engine_from_config(storage_config.clone())?;
+    engine.set_compatibility_mode(mode);
+
+    let result = engine.load_snapshot(snapshot_id);
+    if format == OutputFormat::Json {
+        let mut value = json!({ "path": snapshot_id, "valid": result.is_ok() });
+        if let Err(e) = &result {
+            value["error"] = e.to_string().into();
+        }
+        println!("{value}");
+        return result.map(|_| ()).map_err(Into::into);
+    }
+
+    match result {
+        Ok((_metadata, _data)) => {
+            println!("✓ Snapshot is valid and integrity check passed");
+        }
+        Err(PersistError::IntegrityCheckFailed { expected, actual }) => {
+            error!("✗ Integrity check failed:");
+            error!("  Expected hash: {}", expected);
+            error!("  Actual hash: {}", actual);
+            return Err(PersistError::IntegrityCheckFailed { expected, actual }.into());
+        }
+        Err(e) => {
+            error!("✗ Failed to verify snapshot: {}", e);
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Result of verifying one snapshot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VerifyStatus {
+    Ok,
+    /// The snapshot was read but its content is damaged or fails its integrity check
+    Corrupted,
+    /// The snapshot could not be read from storage
+    Unreadable,
+}
+
+impl VerifyStatus {
+    fn of(result: &Result<(), PersistError>) -> Self {
+        match result {
+            Ok(()) => VerifyStatus::Ok,
+            Err(
+                PersistError::IntegrityCheckFailed { .. }
+                | PersistError::Compression(_)
+                | PersistError::Json(_)
+                | PersistError::InvalidFormat(_)
+                | PersistError::MissingMetadata(_),
+            ) => VerifyStatus::Corrupted,
+            Err(_) => VerifyStatus::Unreadable,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            VerifyStatus::Ok => "ok",
+            VerifyStatus::Corrupted => "corrupted",
+            VerifyStatus::Unreadable => "unreadable",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct VerifyOutcome {
+    path: String,
+    status: VerifyStatus,
+    /// Why verification failed
+    reason: Option<String>,
+}
+
+#[derive(Tabled)]
+struct VerifyFailureRow {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Status")]
+    status: &'static str,
+    #[tabled(rename = "Reason")]
+    reason: String,
+}
+
+#[derive(Tabled)]
+struct VerifySummaryRow {
+    #[tabled(rename = "Status")]
+    status: &'static str,
+    #[tabled(rename = "Snapshots")]
+    count: usize,
+}
+
+/// Verify `paths` on `parallel` worker threads, in the order of `paths`
+///
+/// Each worker uses its own engine. With `fail_fast`, workers stop picking up paths
+/// after the first failure, so the outcomes may not cover every path.
+fn verify_paths(
+    storage_config: &StorageConfig,
+    paths: &[String],
+    parallel: usize,
+    fail_fast: bool,
+    mode: CompatibilityMode,
+    progress: &Progress,
+) -> Result<Vec<VerifyOutcome>, anyhow::Error> {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let worker = || -> Result<Vec<(usize, VerifyOutcome)>, anyhow::Error> {
+        let mut engine = create_engine_from_config(storage_config.clone())?;
+        engine.set_compatibility_mode(mode);
+        let mut outcomes = Vec::new();
+        while !(fail_fast && failed.load(Ordering::SeqCst)) {
+            let i = next.fetch_add(1, Ordering::SeqCst);
+            let Some(path) = paths.get(i) else {
+                break;
+            };
+            progress.start(path);
+            // Loading verifies the snapshot, as verify_snapshot does
+            let result = engine
+                .load_snapshot_with_options(path, &progress.call_options())
+                .map(|_| ());
+            progress.finish_item();
+            let status = VerifyStatus::of(&result);
+            if status != VerifyStatus::Ok {
+                failed.store(true, Ordering::SeqCst);
+            }
+            let reason = result.err().map(|e| e.to_string());
+            outcomes.push((
+                i,
+                VerifyOutcome {
+                    path: path.clone(),
+                    status,
+                    reason,
+                },
+            ));
+        }
+        Ok(outcomes)
+    };
+
+    let mut outcomes = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..parallel.max(1)).map(|_| scope.spawn(worker)).collect();
+        workers
+            .into_iter()
+            .map(|handle| handle.join().expect("verify worker panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    outcomes.sort_by_key(|(i, _)| *i);
+    Ok(outcomes.into_iter().map(|(_, outcome)| outcome).collect())
+}
+
+pub async fn verify_all(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    parallel: usize,
+    fail_fast: bool,
+    allow_failures: bool,
+    mode: CompatibilityMode,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    info!("Verifying all snapshots under '{}'", prefix);
+
+    // List raw keys rather than querying metadata, so unreadable entries are reported
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let mut paths = storage.list(prefix)?;
+    paths.sort();
+
+    let progress = Progress::new(paths.len(), format);
+    let outcomes = verify_paths(storage_config, &paths, parallel, fail_fast, mode, &progress)?;
+    drop(progress);
+    let count = |status| outcomes.iter().filter(|o| o.status == status).count();
+    let (ok, corrupted, unreadable) = (
+        count(VerifyStatus::Ok),
+        count(VerifyStatus::Corrupted),
+        count(VerifyStatus::Unreadable),
+    );
+    let skipped = paths.len() - outcomes.len();
+    let failures: Vec<&VerifyOutcome> = outcomes
+        .iter()
+        .filter(|o| o.status != VerifyStatus::Ok)
+        .collect();
+
+    if format == OutputFormat::Json {
+        let failure_json: Vec<_> = failures
+            .iter()
+            .map(|o| json!({ "path": o.path, "status": o.status.as_str(), "reason": o.reason }))
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "total": paths.len(),
+                "ok": ok,
+                "corrupted": corrupted,
+                "unreadable": unreadable,
+                "skipped": skipped,
+                "failures": failure_json,
+            })
+        );
+    } else {
+        if !failures.is_empty() {
+            let rows: Vec<VerifyFailureRow> = failures
+                .iter()
+                .map(|o| VerifyFailureRow {
+                    path: o.path.clone(),
+                    status: o.status.as_str(),
+                    reason: o.reason.clone().unwrap_or_default(),
+                })
+                .collect();
+            println!("{}", Table::new(rows));
+        }
+        let mut summary = vec![
+            VerifySummaryRow {
+                status: "ok",
+                count: ok,
+            },
+            VerifySummaryRow {
+                status: "corrupted",
+                count: corrupted,
+            },
+            VerifySummaryRow {
+                status: "unreadable",
+                count: unreadable,
+            },
+        ];
+        if skipped > 0 {
+            summary.push(VerifySummaryRow {
+                status: "skipped",
+                count: skipped,
+            });
+        }
+        println!("{}", Table::new(summary));
+    }
+
+    if !failures.is_empty() && !allow_failures {
+        anyhow::bail!(
+            "{} of {} snapshots failed verification",
+            failures.len(),
+            paths.len()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{local_store_with_corpus, tamper_snapshot};
+    use crate::{exit_code, exit_code_for};
+
+    #[tokio::test]
+    async fn test_verify_all_reports_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        tamper_snapshot(dir.path(), "a/s1/1.json.gz");
+        std::fs::write(dir.path().join("b/s1/0.json.gz"), b"not a snapshot").unwrap();
+
+        let storage = create_storage_from_config(config.clone()).unwrap();
+        let mut paths = storage.list("").unwrap();
+        paths.sort();
+        let outcomes = verify_paths(
+            &config,
+            &paths,
+            1,
+            false,
+            CompatibilityMode::Strict,
+            &Progress::hidden(),
+        )
+        .unwrap();
+        let failures: Vec<_> = outcomes
+            .iter()
+            .filter(|o| o.status != VerifyStatus::Ok)
+            .map(|o| (o.path.as_str(), o.status))
+            .collect();
+        assert_eq!(outcomes.len(), 6);
+        assert_eq!(
+            failures,
+            [
+                ("a/s1/1.json.gz", VerifyStatus::Corrupted),
+                ("b/s1/0.json.gz", VerifyStatus::Corrupted),
+            ]
+        );
+        assert!(outcomes[1].reason.as_ref().unwrap().contains("Integrity"));
+
+        let verify = |allow_failures| {
+            verify_all(
+                &config,
+                "",
+                2,
+                false,
+                allow_failures,
+                CompatibilityMode::Strict,
+                OutputFormat::Table,
+            )
+        };
+        let err = verify(false).await.unwrap_err();
+        assert_eq!(err.to_string(), "2 of 6 snapshots failed verification");
+        assert_eq!(exit_code_for(&err), exit_code::FAILURE);
+        verify(true).await.unwrap();
+    }
+
+    #[test]
+    fn test_verify_all_parallel_matches_serial() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        tamper_snapshot(dir.path(), "a/s2/0.json.gz");
+        let storage = create_storage_from_config(config.clone()).unwrap();
+        let paths = storage.list("").unwrap();
+
+        let verify = |parallel| {
+            verify_paths(
+                &config,
+                &paths,
+                parallel,
+                false,
+                CompatibilityMode::Strict,
+                &Progress::hidden(),
+            )
+            .unwrap()
+        };
+        let serial = verify(1);
+        assert_eq!(serial.len(), paths.len());
+        assert_eq!(verify(4), serial);
+        assert_eq!(verify(16), serial);
+
+        // Fail-fast stops at the first failure when verifying serially
+        let sorted = {
+            let mut sorted = paths.clone();
+            sorted.sort();
+            sorted
+        };
+        let fail_fast = verify_paths(
+            &config,
+            &sorted,
+            1,
+            true,
+            CompatibilityMode::Strict,
+            &Progress::hidden(),
+        )
+        .unwrap();
+        assert_eq!(fail_fast.last().unwrap().path, "a/s2/0.json.gz");
+        assert_eq!(fail_fast.len(), 4);
+    }
+}
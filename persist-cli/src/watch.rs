@@ -0,0 +1,267 @@
+//! `persist watch`: report snapshots added, changed or deleted under a prefix
+
+use crate::{format_size, snapshot_json, OutputFormat};
+use chrono::{DateTime, Utc};
+use persist_core::{
+    config::StorageConfig, create_engine_from_config, create_storage_from_config, retention,
+    SnapshotEngineInterface, SnapshotMetadata, StorageAdapter,
+};
+use tracing::{error, info};
+
+/// What happened to a snapshot between two listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WatchEventKind {
+    Added,
+    Changed,
+    Deleted,
+}
+
+impl WatchEventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            WatchEventKind::Added => "added",
+            WatchEventKind::Changed => "changed",
+            WatchEventKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// A change seen by `persist watch`; deletions carry the last metadata seen
+#[derive(Debug, Clone)]
+struct WatchEvent {
+    kind: WatchEventKind,
+    path: String,
+    metadata: SnapshotMetadata,
+}
+
+impl WatchEvent {
+    fn to_json(&self, detected_at: DateTime<Utc>) -> serde_json::Value {
+        let mut value = snapshot_json(&self.path, &self.metadata);
+        value["event"] = self.kind.as_str().into();
+        value["detected_at"] = detected_at.to_rfc3339().into();
+        value
+    }
+
+    fn line(&self, detected_at: DateTime<Utc>) -> String {
+        format!(
+            "{} {:<7} {} agent={} session={} index={} size={}",
+            detected_at.format("%Y-%m-%d %H:%M:%S"),
+            self.kind.as_str(),
+            self.path,
+            self.metadata.agent_id,
+            self.metadata.session_id,
+            self.metadata.snapshot_index,
+            format_size(retention::stored_size(&self.metadata))
+        )
+    }
+}
+
+/// Snapshots under a prefix as of the last listing
+struct Watcher {
+    prefix: String,
+    known: std::collections::BTreeMap<String, SnapshotMetadata>,
+}
+
+impl Watcher {
+    fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            known: Default::default(),
+        }
+    }
+
+    /// List the prefix again and report what changed since the last poll
+    ///
+    /// Snapshots whose metadata cannot be read yet (e.g. while being written) are
+    /// left for a later poll.
+    fn poll(
+        &mut self,
+        storage: &dyn StorageAdapter,
+        engine: &dyn SnapshotEngineInterface,
+    ) -> Result<Vec<WatchEvent>, anyhow::Error> {
+        let paths = storage.list(&self.prefix)?;
+        let mut events = Vec::new();
+        let mut current = std::collections::BTreeMap::new();
+        for path in paths {
+            let Ok(metadata) = engine.get_snapshot_metadata(&path) else {
+                if let Some(previous) = self.known.remove(&path) {
+                    current.insert(path, previous);
+                }
+                continue;
+            };
+            let kind = match self.known.remove(&path) {
+                None => Some(WatchEventKind::Added),
+                Some(previous) if previous != metadata => Some(WatchEventKind::Changed),
+                Some(_) => None,
+            };
+            if let Some(kind) = kind {
+                events.push(WatchEvent {
+                    kind,
+                    path: path.clone(),
+                    metadata: metadata.clone(),
+                });
+            }
+            current.insert(path, metadata);
+        }
+        // Whatever was not listed again is gone
+        for (path, metadata) in std::mem::replace(&mut self.known, current) {
+            events.push(WatchEvent {
+                kind: WatchEventKind::Deleted,
+                path,
+                metadata,
+            });
+        }
+        Ok(events)
+    }
+}
+
+/// Run `command` through the shell for a new snapshot, logging failures
+fn run_exec_hook(command: &str, path: &str) {
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("PERSIST_SNAPSHOT_PATH", path)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => error!("--exec command for {} exited with {}", path, status),
+        Err(e) => error!("Failed to run --exec command for {}: {}", path, e),
+    }
+}
+
+pub async fn watch_snapshots(
+    storage_config: &StorageConfig,
+    prefix: &str,
+    interval: std::time::Duration,
+    exec: Option<&str>,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    let storage = create_storage_from_config(storage_config.clone())?;
+    let engine = create_engine_from_config(storage_config.clone())?;
+    let mut watcher = Watcher::new(prefix);
+
+    // The first listing is the baseline; only later changes are reported
+    watcher.poll(storage.as_ref(), engine.as_ref())?;
+    info!(
+        "Watching {} snapshots under '{}' every {:?}",
+        watcher.known.len(),
+        prefix,
+        interval
+    );
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        let events = match watcher.poll(storage.as_ref(), engine.as_ref()) {
+            Ok(events) => events,
+            Err(e) => {
+                // A flaky listing should not end a long-running watch
+                error!("Failed to list snapshots: {:#}", e);
+                continue;
+            }
+        };
+        let detected_at = Utc::now();
+        for event in events {
+            // stdout is line-buffered, so each event reaches pipes as it happens
+            match format {
+                OutputFormat::Table => println!("{}", event.line(detected_at)),
+                OutputFormat::Json => println!("{}", event.to_json(detected_at)),
+            }
+            if let (Some(command), WatchEventKind::Added) = (exec, event.kind) {
+                run_exec_hook(command, &event.path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::local_store_with_corpus;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_watcher_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = local_store_with_corpus(dir.path());
+        let storage = create_storage_from_config(config.clone()).unwrap();
+        let engine = create_engine_from_config(config).unwrap();
+        let mut watcher = Watcher::new("a/");
+        let mut poll = || {
+            watcher
+                .poll(storage.as_ref(), engine.as_ref())
+                .unwrap()
+                .into_iter()
+                .map(|event| (event.kind, event.path))
+                .collect::<Vec<_>>()
+        };
+
+        // The baseline reports everything once, then nothing until the store changes
+        assert_eq!(poll().len(), 4);
+        assert!(poll().is_empty());
+
+        let metadata = SnapshotMetadata::new("agent-a", "s1", 3);
+        engine
+            .save_snapshot(r#"{"memory":[]}"#, &metadata, "a/s1/3.json.gz")
+            .unwrap();
+        engine
+            .save_snapshot(r#"{"memory":[]}"#, &metadata, "b/s1/9.json.gz")
+            .unwrap();
+        assert_eq!(
+            poll(),
+            [(WatchEventKind::Added, "a/s1/3.json.gz".to_string())]
+        );
+
+        engine
+            .update_metadata(
+                "a/s1/0.json.gz",
+                Box::new(|metadata| metadata.description = Some("rerun".into())),
+            )
+            .unwrap();
+        engine.delete_snapshot("a/s2/0.json.gz").unwrap();
+        // A file that is not a readable snapshot yet is not reported
+        std::fs::write(dir.path().join("a/s1/4.json.gz"), b"partial").unwrap();
+        assert_eq!(
+            poll(),
+            [
+                (WatchEventKind::Changed, "a/s1/0.json.gz".to_string()),
+                (WatchEventKind::Deleted, "a/s2/0.json.gz".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watch_event_output() {
+        let mut metadata = SnapshotMetadata::new("agent-a", "s1", 7);
+        metadata.compressed_size = Some(2048);
+        let event = WatchEvent {
+            kind: WatchEventKind::Added,
+            path: "a/s1/7.json.gz".into(),
+            metadata,
+        };
+        let at = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            event.line(at),
+            "2024-06-01 12:00:00 added   a/s1/7.json.gz agent=agent-a session=s1 index=7 size=2.0 KB"
+        );
+        let value = event.to_json(at);
+        assert_eq!(value["event"], "added");
+        assert_eq!(value["path"], "a/s1/7.json.gz");
+        assert_eq!(value["snapshot_index"], 7);
+        assert_eq!(value["detected_at"], "2024-06-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn test_watch_exec_hook_gets_snapshot_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("hook.txt");
+        run_exec_hook(
+            &format!("printf %s \"$PERSIST_SNAPSHOT_PATH\" > '{}'", out.display()),
+            "a/s1/3.json.gz",
+        );
+        assert_eq!(std::fs::read_to_string(out).unwrap(), "a/s1/3.json.gz");
+    }
+}
@@ -0,0 +1,667 @@
+/*!
+Schema tests for `persist --output json`.
+
+Each test runs the `persist` binary against a temporary local store and parses
+stdout (or stderr, for errors) with serde_json.
+*/
+
+use assert_cmd::Command;
+use persist_core::{
+    create_engine_from_config, CompressionAdapter, GzipCompressor, LocalFileStorage,
+    MetadataCipher, SensitiveField, SnapshotEngine, SnapshotMetadata, StorageConfig,
+};
+use serde_json::Value;
+use std::path::Path;
+
+/// Fields every snapshot object carries, named as on `SnapshotMetadata`
+const METADATA_FIELDS: &[&str] = &[
+    "agent_id",
+    "session_id",
+    "snapshot_index",
+    "timestamp",
+    "content_hash",
+    "format_version",
+    "snapshot_id",
+    "uncompressed_size",
+    "compressed_size",
+    "compression_algorithm",
+    "tags",
+];
+
+/// Temporary store holding `a/0.json.gz` and `a/1.json.gz` of agent `agent-1`
+fn store() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = StorageConfig::default_local();
+    config.local_base_path = Some(dir.path().to_path_buf());
+    let engine = create_engine_from_config(config).unwrap();
+    for index in 0..2 {
+        let metadata = SnapshotMetadata::builder("agent-1", "session-1", index)
+            .tag("env", "test")
+            .build();
+        engine
+            .save_snapshot(r#"{"memory":[]}"#, &metadata, &format!("a/{index}.json.gz"))
+            .unwrap();
+    }
+    dir
+}
+
+fn persist(dir: &Path, args: &[&str]) -> Command {
+    let mut cmd = persist_table(dir, &["--output", "json"]);
+    cmd.args(args);
+    cmd
+}
+
+/// `persist` with its default table output
+fn persist_table(dir: &Path, args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin("persist").unwrap();
+    // Keep a config file of the user running the tests out of the way
+    cmd.env("HOME", dir)
+        .env_remove("PERSIST_CONFIG")
+        .env_remove("PERSIST_PROFILE")
+        .arg("--path")
+        .arg(dir)
+        .args(args);
+    cmd
+}
+
+/// Run a command expected to succeed and parse its stdout
+fn json_stdout(dir: &Path, args: &[&str]) -> Value {
+    let output = persist(dir, args)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    serde_json::from_slice(&output).unwrap()
+}
+
+/// Run a command expected to fail with `exit_code` and parse the `error` object of the
+/// last line of its stderr, after any JSON log lines
+fn json_error(dir: &Path, args: &[&str], exit_code: i32) -> Value {
+    let output = persist(dir, args)
+        .assert()
+        .code(exit_code)
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(output).unwrap();
+    let line: Value = serde_json::from_str(stderr.lines().last().unwrap()).unwrap();
+    let error = line["error"].clone();
+    assert_eq!(error["exit_code"], exit_code);
+    assert!(error["code"].is_string(), "{error}");
+    assert!(error["message"].is_string(), "{error}");
+    assert!(error.get("path").is_some(), "{error}");
+    error
+}
+
+fn assert_snapshot_object(value: &Value) {
+    for field in METADATA_FIELDS {
+        assert!(value.get(field).is_some(), "missing {field} in {value}");
+    }
+}
+
+#[test]
+fn test_list() {
+    let dir = store();
+    let list = json_stdout(dir.path(), &["list", "--sort", "index"]);
+
+    let snapshots = list.as_array().unwrap();
+    assert_eq!(snapshots.len(), 2);
+    for (index, snapshot) in snapshots.iter().enumerate() {
+        assert_snapshot_object(snapshot);
+        assert_eq!(snapshot["path"], format!("a/{index}.json.gz"));
+        assert_eq!(snapshot["snapshot_index"], index);
+        assert_eq!(snapshot["tags"]["env"], "test");
+    }
+
+    let empty = json_stdout(dir.path(), &["list", "--agent", "nobody"]);
+    assert_eq!(empty, Value::Array(vec![]));
+}
+
+#[test]
+fn test_show() {
+    let dir = store();
+    let metadata = json_stdout(dir.path(), &["show", "a/1.json.gz"]);
+    assert_snapshot_object(&metadata);
+    assert_eq!(metadata["agent_id"], "agent-1");
+    assert_eq!(metadata["snapshot_index"], 1);
+}
+
+#[test]
+fn test_show_full_verify() {
+    let dir = store();
+    // Tamper with the agent state, leaving the metadata (and its content hash) alone
+    let path = dir.path().join("a/1.json.gz");
+    let gzip = GzipCompressor::new();
+    let mut container: Value =
+        serde_json::from_slice(&gzip.decompress(&std::fs::read(&path).unwrap()).unwrap()).unwrap();
+    container["agent_state"] = serde_json::json!({ "memory": ["tampered"] });
+    let tampered = gzip.compress(container.to_string().as_bytes()).unwrap();
+    std::fs::write(&path, tampered).unwrap();
+
+    // Only the metadata is read by default
+    let metadata = json_stdout(dir.path(), &["show", "a/1.json.gz"]);
+    assert_eq!(metadata["snapshot_index"], 1);
+
+    let error = json_error(dir.path(), &["show", "a/1.json.gz", "--full-verify"], 4);
+    assert_eq!(error["code"], "integrity");
+    json_stdout(dir.path(), &["show", "a/0.json.gz", "--full-verify"]);
+}
+
+#[test]
+fn test_list_parallel() {
+    let dir = store();
+    std::fs::write(dir.path().join("a/bad.json.gz"), b"not a snapshot").unwrap();
+    for parallel in ["1", "4"] {
+        let list = json_stdout(
+            dir.path(),
+            &["list", "--sort", "index-desc", "--parallel", parallel],
+        );
+        let paths: Vec<_> = list
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|snapshot| snapshot["path"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(paths, ["a/1.json.gz", "a/0.json.gz"]);
+    }
+}
+
+#[test]
+fn test_show_raw() {
+    let dir = store();
+    let mut config = StorageConfig::default_local();
+    config.local_base_path = Some(dir.path().to_path_buf());
+    let state = serde_json::json!({
+        "memory": { "context": ["hello", "world"], "turns": 2 },
+        "tools": [],
+    });
+    create_engine_from_config(config)
+        .unwrap()
+        .save_snapshot(
+            &state.to_string(),
+            &SnapshotMetadata::new("agent-1", "session-1", 2),
+            "a/2.json.gz",
+        )
+        .unwrap();
+
+    let output = persist(dir.path(), &["show", "a/2.json.gz", "--raw"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let text = String::from_utf8(output).unwrap();
+    assert!(text.lines().count() > 1, "{text}");
+    assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), state);
+
+    let output = persist(dir.path(), &["show", "a/2.json.gz", "--raw", "--compact"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(String::from_utf8(output).unwrap(), format!("{state}\n"));
+
+    let context = json_stdout(
+        dir.path(),
+        &["show", "a/2.json.gz", "--field", "/memory/context"],
+    );
+    assert_eq!(context, serde_json::json!(["hello", "world"]));
+
+    let error = json_error(
+        dir.path(),
+        &["show", "a/2.json.gz", "--field", "/memory/missing"],
+        3,
+    );
+    assert_eq!(error["code"], "not_found");
+    assert_eq!(error["path"], "a/2.json.gz");
+    assert_eq!(
+        error["message"],
+        "Field /memory/missing not found in the agent state of a/2.json.gz"
+    );
+}
+
+#[test]
+fn test_export_to_stdout() {
+    let dir = store();
+    let stdout = |args: &[&str]| {
+        let output = persist_table(dir.path(), args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let state = stdout(&["export", "a/1.json.gz"]);
+    assert_eq!(state, "{\"memory\":[]}\n");
+    assert_eq!(stdout(&["export", "a/1.json.gz", "-"]), state);
+    assert_eq!(
+        stdout(&["export", "a/1.json.gz", "--output-file", "-"]),
+        state
+    );
+
+    let pretty = stdout(&["export", "a/1.json.gz", "--pretty"]);
+    assert_eq!(pretty, "{\n  \"memory\": []\n}\n");
+}
+
+#[test]
+fn test_verify() {
+    let dir = store();
+    let result = json_stdout(dir.path(), &["verify", "a/0.json.gz"]);
+    assert_eq!(
+        result,
+        serde_json::json!({ "path": "a/0.json.gz", "valid": true })
+    );
+
+    let output = persist(dir.path(), &["verify", "a/missing.json.gz"])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let result: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(result["path"], "a/missing.json.gz");
+    assert_eq!(result["valid"], false);
+    assert!(result["error"].is_string());
+}
+
+#[test]
+fn test_delete() {
+    let dir = store();
+    let result = json_stdout(dir.path(), &["delete", "a/0.json.gz", "--force"]);
+    assert_eq!(
+        result,
+        serde_json::json!({ "path": "a/0.json.gz", "deleted": true })
+    );
+    assert!(!dir.path().join("a/0.json.gz").exists());
+
+    // JSON output never prompts
+    let error = json_error(dir.path(), &["delete", "a/1.json.gz"], 2);
+    assert_eq!(error["code"], "usage");
+    assert!(dir.path().join("a/1.json.gz").exists());
+}
+
+#[test]
+fn test_prune() {
+    let dir = store();
+    let plan = json_stdout(dir.path(), &["prune", "--keep-last", "1", "--dry-run"]);
+    assert_eq!(plan["dry_run"], true);
+    assert_eq!(plan["kept"], 1);
+    assert_eq!(plan["failed"], Value::Array(vec![]));
+    assert!(plan["deleted_bytes"].as_u64().unwrap() > 0);
+    assert!(plan["kept_bytes"].as_u64().unwrap() > 0);
+    let deleted = plan["deleted"].as_array().unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_snapshot_object(&deleted[0]);
+    assert_eq!(deleted[0]["path"], "a/0.json.gz");
+    assert!(dir.path().join("a/0.json.gz").exists());
+
+    let result = json_stdout(dir.path(), &["prune", "--keep-last", "1", "--force"]);
+    assert_eq!(result["dry_run"], false);
+    assert_eq!(result["deleted"][0]["path"], "a/0.json.gz");
+    assert!(!dir.path().join("a/0.json.gz").exists());
+}
+
+#[test]
+fn test_long_operations_print_clean_output() {
+    let dir = store();
+    let copy_dir = tempfile::tempdir().unwrap();
+    let src = format!("{}/a/", dir.path().display());
+    let dst = format!("{}/b/", copy_dir.path().display());
+
+    // With logging off, stderr would only carry progress bars: JSON output has none
+    for args in [
+        vec!["verify-all"],
+        vec!["cp", src.as_str(), dst.as_str(), "--recursive", "--verify"],
+        vec!["recompress", "--to", "gzip"],
+    ] {
+        let output = persist(dir.path(), &args)
+            .env("RUST_LOG", "off")
+            .assert()
+            .success()
+            .get_output()
+            .clone();
+        serde_json::from_slice::<Value>(&output.stdout).unwrap();
+        assert!(output.stderr.is_empty(), "{args:?}");
+    }
+
+    // Neither does table output when stdout is not a terminal
+    let output = persist_table(dir.path(), &["verify-all"])
+        .env("RUST_LOG", "off")
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    assert!(String::from_utf8(output.stdout).unwrap().contains("ok"));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_log_file() {
+    let dir = store();
+    let logs = tempfile::tempdir().unwrap();
+    let log_file = logs.path().join("persist.log");
+    let output = persist(
+        dir.path(),
+        &[
+            "--verbose",
+            "--log-file",
+            log_file.to_str().unwrap(),
+            "--log-rotation",
+            "daily",
+            "verify-all",
+        ],
+    )
+    .env_remove("RUST_LOG")
+    .assert()
+    .success()
+    .get_output()
+    .clone();
+    serde_json::from_slice::<Value>(&output.stdout).unwrap();
+    assert!(output.stderr.is_empty());
+
+    // Daily files are named after the day they were started
+    let names: Vec<String> = std::fs::read_dir(logs.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names.len(), 1, "{names:?}");
+    assert!(names[0].starts_with("persist.log."), "{names:?}");
+    let text = std::fs::read_to_string(logs.path().join(&names[0])).unwrap();
+    assert!(!text.is_empty());
+    for line in text.lines() {
+        serde_json::from_str::<Value>(line).expect("log line is JSON");
+    }
+
+    persist(dir.path(), &["--log-rotation", "weekly", "list"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_latest() {
+    let dir = store();
+    let mut config = StorageConfig::default_local();
+    config.local_base_path = Some(dir.path().to_path_buf());
+    // Ties with a/1.json.gz on index, but is newer
+    create_engine_from_config(config)
+        .unwrap()
+        .save_snapshot(
+            r#"{"memory":["latest"]}"#,
+            &SnapshotMetadata::new("agent-1", "session-1", 1),
+            "b/1.json.gz",
+        )
+        .unwrap();
+
+    let output = persist_table(dir.path(), &["latest", "--agent-id", "agent-1"])
+        .env("RUST_LOG", "off")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(String::from_utf8(output).unwrap(), "b/1.json.gz\n");
+
+    let latest = json_stdout(
+        dir.path(),
+        &[
+            "latest",
+            "--agent-id",
+            "agent-1",
+            "--session-id",
+            "session-1",
+        ],
+    );
+    assert_snapshot_object(&latest);
+    assert_eq!(latest["path"], "b/1.json.gz");
+    assert_eq!(latest["snapshot_index"], 1);
+
+    let state_file = dir.path().join("state.json");
+    persist_table(
+        dir.path(),
+        &["latest", "--agent-id", "agent-1", "--restore", "-o"],
+    )
+    .arg(&state_file)
+    .assert()
+    .success();
+    let state: Value =
+        serde_json::from_str(&std::fs::read_to_string(&state_file).unwrap()).unwrap();
+    assert_eq!(state, serde_json::json!({ "memory": ["latest"] }));
+
+    let error = json_error(dir.path(), &["latest", "--agent-id", "nobody"], 3);
+    assert_eq!(error["code"], "not_found");
+    assert_eq!(error["path"], Value::Null);
+    let error = json_error(
+        dir.path(),
+        &["latest", "--agent-id", "agent-1", "--session-id", "other"],
+        3,
+    );
+    assert_eq!(error["code"], "not_found");
+}
+
+#[test]
+fn test_errors_have_stable_codes() {
+    let dir = store();
+    let error = json_error(dir.path(), &["export", "a/missing.json.gz"], 3);
+    assert_eq!(error["code"], "not_found");
+    assert_eq!(error["path"], "a/missing.json.gz");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("a/missing.json.gz"));
+
+    let error = json_error(dir.path(), &["show", "a/missing.json.gz"], 3);
+    assert_eq!(error["code"], "not_found");
+    assert_eq!(error["path"], "a/missing.json.gz");
+
+    std::fs::write(dir.path().join("a/bad.json.gz"), b"not a snapshot").unwrap();
+    let error = json_error(dir.path(), &["show", "a/bad.json.gz"], 4);
+    assert_eq!(error["code"], "integrity");
+    assert_eq!(error["path"], "a/bad.json.gz");
+
+    let error = json_error(dir.path(), &["show", "../outside.json.gz"], 2);
+    assert_eq!(error["code"], "usage");
+    assert_eq!(error["path"], "../outside.json.gz");
+
+    let error = json_error(dir.path(), &["--profile", "missing", "list"], 7);
+    assert_eq!(error["code"], "config");
+    assert_eq!(error["path"], Value::Null);
+}
+
+/// Cipher reversing each value, standing in for a real metadata key
+struct ReversingCipher;
+
+impl MetadataCipher for ReversingCipher {
+    fn encrypt(&self, plaintext: &str) -> persist_core::Result<String> {
+        Ok(plaintext.chars().rev().collect())
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> persist_core::Result<String> {
+        Ok(ciphertext.chars().rev().collect())
+    }
+}
+
+#[test]
+fn test_encrypted_metadata_without_key() {
+    let dir = store();
+    let engine = SnapshotEngine::new(
+        LocalFileStorage::with_base_dir(dir.path()),
+        GzipCompressor::new(),
+    )
+    .with_metadata_cipher(ReversingCipher);
+    let metadata = SnapshotMetadata::builder("agent-1", "session-1", 2)
+        .description("customer Jane Doe")
+        .sensitive(SensitiveField::Description)
+        .build();
+    engine
+        .save_snapshot(r#"{"memory":[]}"#, &metadata, "a/2.json.gz")
+        .unwrap();
+
+    // Redacted metadata needs no key
+    let shown = json_stdout(dir.path(), &["show", "a/2.json.gz"]);
+    assert_eq!(shown["agent_id"], "agent-1");
+
+    // The CLI has no metadata key, so the encrypted description cannot be revealed
+    let error = json_error(dir.path(), &["show", "a/2.json.gz", "--show-sensitive"], 8);
+    assert_eq!(error["code"], "encryption");
+    assert!(error["message"].as_str().unwrap().contains("encrypted"));
+}
+
+#[test]
+fn test_config_show() {
+    let dir = store();
+    let config = dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        "[profiles.prod]\nstorage = \"s3\"\nbucket = \"prod-bucket\"\nregion = \"eu-west-1\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("persist").unwrap();
+    let output = cmd
+        .env("AWS_SECRET_ACCESS_KEY", "hunter2")
+        .env_remove("AWS_REGION")
+        .args(["--config", config.to_str().unwrap(), "--profile", "prod"])
+        .args(["--output", "json", "config", "show"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let shown: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(shown["profile"], "prod");
+    assert_eq!(shown["config_file"], config.to_str().unwrap());
+    let settings = &shown["settings"];
+    assert_eq!(
+        settings["storage"],
+        serde_json::json!({ "value": "s3", "source": "profile" })
+    );
+    assert_eq!(settings["bucket"]["value"], "prod-bucket");
+    assert_eq!(settings["output"]["source"], "flag");
+    assert_eq!(settings["AWS_SECRET_ACCESS_KEY"]["value"], "********");
+    assert!(!String::from_utf8_lossy(&output).contains("hunter2"));
+}
+
+/// Status of each `persist doctor` check by name
+fn doctor_statuses(report: &Value) -> Vec<(String, String)> {
+    report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|check| {
+            (
+                check["name"].as_str().unwrap().to_string(),
+                check["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn statuses(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    expected
+        .iter()
+        .map(|(name, status)| (name.to_string(), status.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_doctor_healthy_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = json_stdout(dir.path(), &["doctor"]);
+    assert_eq!(report["healthy"], true);
+    assert_eq!(
+        doctor_statuses(&report),
+        statuses(&[
+            ("config", "pass"),
+            ("connectivity", "pass"),
+            ("probe", "pass"),
+            ("compression", "pass"),
+        ])
+    );
+
+    let output = persist_table(dir.path(), &["doctor"])
+        .env("RUST_LOG", "off")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    assert!(
+        output.lines().next().unwrap().starts_with("✓ config"),
+        "{output}"
+    );
+}
+
+#[test]
+fn test_doctor_missing_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing");
+    let output = persist(&missing, &["doctor"])
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["healthy"], false);
+    assert_eq!(
+        doctor_statuses(&report),
+        statuses(&[
+            ("config", "pass"),
+            ("connectivity", "fail"),
+            ("probe", "skip"),
+            ("compression", "pass"),
+        ])
+    );
+    assert!(report["checks"][1]["hint"].is_string());
+    assert!(!missing.exists());
+}
+
+#[cfg(feature = "s3")]
+#[test]
+fn test_doctor_unreachable_s3_endpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        "[profiles.s3]\nstorage = \"s3\"\nbucket = \"doctor-bucket\"\nregion = \"us-east-1\"\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("persist")
+        .unwrap()
+        .env("HOME", dir.path())
+        .env("AWS_ACCESS_KEY_ID", "test")
+        .env("AWS_SECRET_ACCESS_KEY", "test")
+        .env("AWS_REGION", "us-east-1")
+        // Nothing listens on port 1
+        .env("AWS_ENDPOINT_URL", "http://127.0.0.1:1")
+        .env("AWS_EC2_METADATA_DISABLED", "true")
+        .args(["--config", config.to_str().unwrap(), "--profile", "s3"])
+        .args(["--output", "json", "doctor"])
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["healthy"], false);
+    assert_eq!(
+        doctor_statuses(&report),
+        statuses(&[
+            ("config", "pass"),
+            ("connectivity", "fail"),
+            ("probe", "skip"),
+            ("compression", "pass"),
+            ("credentials", "skip"),
+        ])
+    );
+    assert!(report["checks"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("doctor-bucket"));
+}
@@ -5,74 +5,15 @@ These benchmarks help identify bottlenecks and measure performance improvements.
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use persist_core::{
-    compression::NoCompression, create_default_engine, GzipCompressor, LocalFileStorage,
-    SnapshotEngine, SnapshotMetadata,
+    compression::NoCompression, create_default_engine, testdata::synthetic_agent_state,
+    GzipCompressor, LocalFileStorage, SnapshotEngine, SnapshotMetadata,
 };
 use rayon::prelude::*;
-use std::collections::HashMap;
 use tempfile::TempDir;
 
-// Helper function to generate test data of various sizes
+// Synthetic agent state of `size_kb` KiB
 fn generate_test_data(size_kb: usize) -> String {
-    let base_object = serde_json::json!({
-        "type": "benchmark_agent",
-        "config": {
-            "model": "gpt-4",
-            "temperature": 0.7,
-            "max_tokens": 2048
-        },
-        "memory": {
-            "conversation_history": [],
-            "facts": {},
-            "context": ""
-        },
-        "tools": [
-            {"name": "calculator", "description": "Perform mathematical calculations"},
-            {"name": "web_search", "description": "Search the web for information"},
-            {"name": "file_reader", "description": "Read file contents"}
-        ]
-    });
-
-    // Scale up the data to reach desired size
-    let target_bytes = size_kb * 1024;
-    let base_size = serde_json::to_string(&base_object).unwrap().len();
-    let multiplier = (target_bytes / base_size).max(1);
-
-    let mut scaled_object = base_object.clone();
-
-    // Add conversation history to scale up size
-    let conversation: Vec<serde_json::Value> = (0..multiplier)
-        .map(|i| serde_json::json!({
-            "role": if i % 2 == 0 { "user" } else { "assistant" },
-            "content": format!("This is message {} in the conversation history. It contains some realistic text that an agent might encounter during normal operation.", i),
-            "timestamp": 1640995200 + i * 60, // Incremental timestamps
-            "metadata": {
-                "message_id": format!("msg_{}", i),
-                "processing_time_ms": 150 + (i % 100)
-            }
-        }))
-        .collect();
-
-    scaled_object["memory"]["conversation_history"] = serde_json::Value::Array(conversation);
-
-    // Add some facts to memory
-    let facts: HashMap<String, serde_json::Value> = (0..(multiplier / 10).max(1))
-        .map(|i| {
-            (
-                format!("fact_{i}"),
-                serde_json::json!({
-                    "value": format!("This is fact number {i} that the agent has learned"),
-                    "confidence": 0.8 + (i % 20) as f64 * 0.01,
-                    "source": format!("source_{}", i % 5),
-                    "learned_at": 1640995200 + i * 3600
-                }),
-            )
-        })
-        .collect();
-
-    scaled_object["memory"]["facts"] = serde_json::to_value(facts).unwrap();
-
-    serde_json::to_string(&scaled_object).unwrap()
+    synthetic_agent_state(size_kb * 1024)
 }
 
 fn benchmark_save_operations(c: &mut Criterion) {
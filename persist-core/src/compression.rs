@@ -38,6 +38,20 @@ pub trait CompressionAdapter {
     fn algorithm_name(&self) -> &str;
 }
 
+impl<C: CompressionAdapter + ?Sized> CompressionAdapter for Box<C> {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        (**self).compress(data)
+    }
+
+    fn decompress(&self, compressed_data: &[u8]) -> Result<Vec<u8>> {
+        (**self).decompress(compressed_data)
+    }
+
+    fn algorithm_name(&self) -> &str {
+        (**self).algorithm_name()
+    }
+}
+
 /// Gzip compression adapter
 ///
 /// This implementation uses the DEFLATE algorithm (gzip) to compress snapshot data.
@@ -169,6 +183,139 @@ impl CompressionAdapter for NoCompression {
     }
 }
 
+/// Zstandard compression adapter
+///
+/// Zstandard compresses faster than gzip at similar ratios and decompresses much
+/// faster. Levels range from 1 to 22; the default is 3.
+///
+/// # Example
+/// ```rust
+/// use persist_core::{CompressionAdapter, ZstdCompressor};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let compressor = ZstdCompressor::with_level(19);
+/// let compressed = compressor.compress(b"some agent state data to compress")?;
+/// assert_eq!(compressor.decompress(&compressed)?, b"some agent state data to compress");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone)]
+pub struct ZstdCompressor {
+    level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdCompressor {
+    /// Create a new zstd compressor with the default compression level (3)
+    pub fn new() -> Self {
+        Self::with_level(zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Create a new zstd compressor with the specified compression level (1-22)
+    pub fn with_level(level: i32) -> Self {
+        Self { level }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Default for ZstdCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl CompressionAdapter for ZstdCompressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        zstd::stream::encode_all(data, self.level)
+            .map_err(|e| PersistError::compression(format!("Failed to compress data: {e}")))
+    }
+
+    fn decompress(&self, compressed_data: &[u8]) -> Result<Vec<u8>> {
+        zstd::stream::decode_all(compressed_data)
+            .map_err(|e| PersistError::compression(format!("Failed to decompress data: {e}")))
+    }
+
+    fn algorithm_name(&self) -> &str {
+        "zstd"
+    }
+}
+
+/// Name of the algorithm stored snapshot data was compressed with, from its leading bytes
+///
+/// Recognizes gzip and zstd frames and uncompressed JSON (`none`); anything else,
+/// such as the output of a custom adapter, gives `None`.
+pub fn detect_algorithm(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0x1f, 0x8b, ..] => Some("gzip"),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some("zstd"),
+        [b'{', ..] => Some("none"),
+        _ => None,
+    }
+}
+
+/// Decompress as much as possible of stored data that may be cut off at any point
+///
+/// Used to read the beginning of a snapshot from its first bytes alone. Returns `None`
+/// if the data is not in a recognized format or nothing could be decompressed.
+pub(crate) fn decompress_prefix(data: &[u8]) -> Option<Vec<u8>> {
+    let mut reader: Box<dyn Read + '_> = match detect_algorithm(data)? {
+        "gzip" => Box::new(GzDecoder::new(data)),
+        #[cfg(feature = "zstd")]
+        "zstd" => Box::new(zstd::stream::read::Decoder::new(data).ok()?),
+        "none" => return Some(data.to_vec()),
+        _ => return None,
+    };
+    let mut decompressed = Vec::new();
+    // A cut-off stream ends in an error; everything decompressed before it is kept
+    let _ = reader.read_to_end(&mut decompressed);
+    (!decompressed.is_empty()).then_some(decompressed)
+}
+
+#[cfg(feature = "zstd")]
+const AVAILABLE_ALGORITHMS: &[&str] = &["gzip", "zstd", "none"];
+#[cfg(not(feature = "zstd"))]
+const AVAILABLE_ALGORITHMS: &[&str] = &["gzip", "none"];
+
+/// Names of the algorithms [`compressor_for`] accepts in this build
+pub fn available_algorithms() -> &'static [&'static str] {
+    AVAILABLE_ALGORITHMS
+}
+
+/// Built-in compression adapter for an algorithm name
+///
+/// `gzip` takes levels 0-9 and `zstd` levels 1-22 (with the `zstd` feature); `none`
+/// takes no level. Without a level, the algorithm's default is used.
+pub fn compressor_for(
+    algorithm: &str,
+    level: Option<i32>,
+) -> Result<Box<dyn CompressionAdapter + Send + Sync>> {
+    match (algorithm, level) {
+        ("gzip", None) => Ok(Box::new(GzipCompressor::new())),
+        ("gzip", Some(level @ 0..=9)) => Ok(Box::new(GzipCompressor::with_level(level as u32))),
+        ("none", None) => Ok(Box::new(NoCompression::new())),
+        #[cfg(feature = "zstd")]
+        ("zstd", None) => Ok(Box::new(ZstdCompressor::new())),
+        #[cfg(feature = "zstd")]
+        ("zstd", Some(level @ 1..=22)) => Ok(Box::new(ZstdCompressor::with_level(level))),
+        #[cfg(not(feature = "zstd"))]
+        ("zstd", _) => Err(PersistError::validation(format!(
+            "zstd compression is not available: persist-core was built without the 'zstd' feature (available: {})",
+            AVAILABLE_ALGORITHMS.join(", ")
+        ))),
+        (_, Some(level)) if matches!(algorithm, "gzip" | "zstd" | "none") => {
+            Err(PersistError::validation(format!(
+                "Compression level {level} is out of range for {algorithm}"
+            )))
+        }
+        _ => Err(PersistError::validation(format!(
+            "Unknown compression algorithm '{algorithm}' (available: {})",
+            AVAILABLE_ALGORITHMS.join(", ")
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +336,22 @@ mod tests {
         assert_eq!(original_data, decompressed);
     }
 
+    #[test]
+    fn test_decompress_prefix_of_truncated_data() {
+        let original_data: Vec<u8> = (0..50_000u32)
+            .flat_map(|i| i.wrapping_mul(2_654_435_761).to_le_bytes())
+            .collect();
+        let compressed = GzipCompressor::new().compress(&original_data).unwrap();
+
+        let prefix = decompress_prefix(&compressed[..compressed.len() / 4]).unwrap();
+        assert!(!prefix.is_empty() && prefix.len() < original_data.len());
+        assert_eq!(prefix, original_data[..prefix.len()]);
+
+        assert_eq!(decompress_prefix(&compressed).unwrap(), original_data);
+        assert_eq!(decompress_prefix(b"{\"a\""), Some(b"{\"a\"".to_vec()));
+        assert_eq!(decompress_prefix(b"\x00\x01"), None);
+    }
+
     #[test]
     fn test_gzip_compression_levels() {
         let test_data = b"Some test data to compress with different levels".repeat(20);
@@ -259,4 +422,47 @@ mod tests {
         let result = compressor.decompress(invalid_data);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_detect_algorithm() {
+        let data = b"{\"memory\": []}";
+        let gzip = GzipCompressor::new().compress(data).unwrap();
+        assert_eq!(detect_algorithm(&gzip), Some("gzip"));
+        assert_eq!(detect_algorithm(data), Some("none"));
+        assert_eq!(detect_algorithm(b"\x00\x01"), None);
+        assert_eq!(detect_algorithm(b""), None);
+        #[cfg(feature = "zstd")]
+        {
+            let zstd = ZstdCompressor::new().compress(data).unwrap();
+            assert_eq!(detect_algorithm(&zstd), Some("zstd"));
+        }
+    }
+
+    #[test]
+    fn test_compressor_for() {
+        assert_eq!(
+            compressor_for("gzip", Some(9)).unwrap().algorithm_name(),
+            "gzip"
+        );
+        assert_eq!(
+            compressor_for("none", None).unwrap().algorithm_name(),
+            "none"
+        );
+        assert!(compressor_for("gzip", Some(10)).is_err());
+        assert!(compressor_for("none", Some(1)).is_err());
+        let unknown = compressor_for("lz4", None).err().unwrap().to_string();
+        assert!(unknown.contains(&available_algorithms().join(", ")));
+        #[cfg(feature = "zstd")]
+        {
+            let zstd = compressor_for("zstd", Some(19)).unwrap();
+            let compressed = zstd.compress(b"data data data data").unwrap();
+            assert_eq!(
+                zstd.decompress(&compressed).unwrap(),
+                b"data data data data"
+            );
+            assert!(compressor_for("zstd", Some(0)).is_err());
+        }
+        #[cfg(not(feature = "zstd"))]
+        assert!(compressor_for("zstd", None).is_err());
+    }
 }
@@ -3,23 +3,67 @@
 //! This module provides configuration structures and enums for selecting
 //! between different storage backends (Local filesystem, S3, etc.) and
 //! configuring their parameters.
+//!
+//! Configurations can be loaded from TOML or YAML files with
+//! [`StorageConfig::from_file`]:
+//!
+//! ```toml
+//! backend = "s3"
+//! s3_bucket = "agent-snapshots"
+//! s3_region = "us-west-2"
+//!
+//! [s3_credentials]
+//! access_key_id = "${AWS_ACCESS_KEY_ID}"
+//! secret_access_key = "${AWS_SECRET_ACCESS_KEY}"
+//!
+//! [retry]
+//! max_attempts = 5
+//!
+//! [compression]
+//! algorithm = "zstd"
+//! level = 3
+//!
+//! [observability]
+//! log_format = "text"
+//! quiet_success_logs = true
+//! ```
+
+use crate::compression::{compressor_for, CompressionAdapter};
+use crate::observability::{LogFormat, LogRotation, LogWriter, ObservabilityConfig};
+use crate::PersistError;
+use backoff::ExponentialBackoffBuilder;
+use persist_retry::{JitterMode, RetryClassifier, RetryPolicy};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+/// Value serialized in place of secrets
+pub const REDACTED: &str = "<redacted>";
 
 /// Enumeration of supported storage backends
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StorageBackend {
     /// Local filesystem storage
+    #[serde(alias = "local")]
     Local,
     /// Amazon S3 cloud storage
+    #[serde(alias = "s3")]
     S3,
     /// Google Cloud Storage
+    #[serde(alias = "gcs")]
     GCS,
 }
 
 /// Configuration structure for storage backend settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Unknown keys are rejected when deserializing, so that typos in config files are
+/// caught. Serializing shows [`REDACTED`] in place of secrets. Configurations
+/// serialized from the flat shape of early versions (`bucket_name`, `region`,
+/// `endpoint_url`, `access_key_id` and `secret_access_key`) still deserialize.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "StorageConfigFields")]
 pub struct StorageConfig {
     /// The storage backend to use
     pub backend: StorageBackend,
@@ -27,8 +71,23 @@ pub struct StorageConfig {
     pub s3_bucket: Option<String>,
     /// AWS region for S3 operations (optional, defaults to environment)
     pub s3_region: Option<String>,
+    /// S3-compatible endpoint such as MinIO or LocalStack (optional, defaults to
+    /// `AWS_ENDPOINT_URL`, then AWS)
+    pub s3_endpoint_url: Option<String>,
+    /// Address buckets by path (`<endpoint>/<bucket>/<key>`) instead of by subdomain,
+    /// as most S3-compatible services need (defaults to off)
+    pub s3_force_path_style: bool,
+    /// Static S3 credentials (optional, defaults to the AWS credential chain)
+    pub s3_credentials: Option<S3Credentials>,
+    /// Named profile of the AWS config and credentials files (optional, defaults to
+    /// `AWS_PROFILE`, then `default`); `s3_credentials` take precedence over it
+    pub s3_profile: Option<String>,
     /// Base path for local storage (optional, defaults to current directory)
     pub local_base_path: Option<PathBuf>,
+    /// Sync local files and their directory to disk on every write (defaults to off)
+    pub local_durable_writes: bool,
+    /// Unix permission bits for files written to local storage (optional, e.g. 0o600)
+    pub local_file_permissions: Option<u32>,
     /// GCS bucket name (required for GCS backend)
     pub gcs_bucket: Option<String>,
     /// GCS object prefix for organizing snapshots (optional)
@@ -37,6 +96,590 @@ pub struct StorageConfig {
     pub gcs_credentials_path: Option<PathBuf>,
     /// GCS operation timeout in seconds (optional, defaults to 30s)
     pub gcs_timeout_seconds: Option<u64>,
+    /// Retry settings for storage operations (optional, the S3 and GCS adapters use
+    /// their defaults and local storage is not retried)
+    pub retry: Option<RetryConfig>,
+    /// Connect and request timeouts of the storage clients (optional, the clients use
+    /// their defaults)
+    pub timeouts: Option<TimeoutConfig>,
+    /// Compression of the snapshots written by engines created from this configuration
+    /// (optional, defaults to gzip)
+    pub compression: Option<CompressionConfig>,
+    /// Snapshot encryption settings (optional)
+    pub encryption: Option<EncryptionConfig>,
+    /// Logging and tracing settings (optional), see
+    /// [`ObservabilitySettings::observability_config`]
+    pub observability: Option<ObservabilitySettings>,
+}
+
+/// Deserialized form of [`StorageConfig`], which also accepts the keys of
+/// [`LegacyStorageConfig`]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StorageConfigFields {
+    backend: StorageBackend,
+    s3_bucket: Option<String>,
+    #[serde(alias = "region")]
+    s3_region: Option<String>,
+    #[serde(default, alias = "endpoint_url")]
+    s3_endpoint_url: Option<String>,
+    #[serde(default)]
+    s3_force_path_style: bool,
+    #[serde(default)]
+    s3_credentials: Option<S3Credentials>,
+    #[serde(default)]
+    s3_profile: Option<String>,
+    local_base_path: Option<PathBuf>,
+    #[serde(default)]
+    local_durable_writes: bool,
+    #[serde(default)]
+    local_file_permissions: Option<u32>,
+    gcs_bucket: Option<String>,
+    gcs_prefix: Option<String>,
+    gcs_credentials_path: Option<PathBuf>,
+    gcs_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    retry: Option<RetryConfig>,
+    #[serde(default)]
+    timeouts: Option<TimeoutConfig>,
+    #[serde(default)]
+    compression: Option<CompressionConfig>,
+    #[serde(default)]
+    encryption: Option<EncryptionConfig>,
+    #[serde(default)]
+    observability: Option<ObservabilitySettings>,
+    /// Bucket of the flat shape, replaced by `gcs_bucket` for the GCS backend and by
+    /// `s3_bucket` otherwise
+    #[serde(default)]
+    bucket_name: Option<String>,
+    /// Access key id of the flat shape, replaced by `s3_credentials`
+    #[serde(default)]
+    access_key_id: Option<String>,
+    /// Secret access key of the flat shape, replaced by `s3_credentials`
+    #[serde(default)]
+    secret_access_key: Option<String>,
+}
+
+impl TryFrom<StorageConfigFields> for StorageConfig {
+    type Error = String;
+
+    fn try_from(fields: StorageConfigFields) -> Result<Self, Self::Error> {
+        let (mut s3_bucket, mut gcs_bucket) = (fields.s3_bucket, fields.gcs_bucket);
+        if let Some(bucket_name) = fields.bucket_name {
+            let (bucket, key) = match fields.backend {
+                StorageBackend::GCS => (&mut gcs_bucket, "gcs_bucket"),
+                _ => (&mut s3_bucket, "s3_bucket"),
+            };
+            if bucket.is_some() {
+                return Err(format!(
+                    "bucket_name cannot be combined with {key}: rename it to {key}"
+                ));
+            }
+            *bucket = Some(bucket_name);
+        }
+        let s3_credentials = match (
+            fields.s3_credentials,
+            fields.access_key_id,
+            fields.secret_access_key,
+        ) {
+            (credentials, None, None) => credentials,
+            (None, Some(access_key_id), Some(secret_access_key)) => {
+                Some(S3Credentials::new(access_key_id, secret_access_key))
+            }
+            (Some(_), _, _) => {
+                return Err(
+                    "access_key_id and secret_access_key cannot be combined with \
+                            s3_credentials: move them into s3_credentials"
+                        .to_string(),
+                )
+            }
+            _ => {
+                return Err(
+                    "access_key_id and secret_access_key must be given together \
+                            (better, as s3_credentials)"
+                        .to_string(),
+                )
+            }
+        };
+        Ok(StorageConfig {
+            backend: fields.backend,
+            s3_bucket,
+            s3_region: fields.s3_region,
+            s3_endpoint_url: fields.s3_endpoint_url,
+            s3_force_path_style: fields.s3_force_path_style,
+            s3_credentials,
+            s3_profile: fields.s3_profile,
+            local_base_path: fields.local_base_path,
+            local_durable_writes: fields.local_durable_writes,
+            local_file_permissions: fields.local_file_permissions,
+            gcs_bucket,
+            gcs_prefix: fields.gcs_prefix,
+            gcs_credentials_path: fields.gcs_credentials_path,
+            gcs_timeout_seconds: fields.gcs_timeout_seconds,
+            retry: fields.retry,
+            timeouts: fields.timeouts,
+            compression: fields.compression,
+            encryption: fields.encryption,
+            observability: fields.observability,
+        })
+    }
+}
+
+/// The flat configuration of early versions
+///
+/// Convert it with `StorageConfig::from`. `bucket_name` is the GCS bucket of the GCS
+/// backend and the S3 bucket otherwise, and the credentials are used when both keys
+/// are given.
+#[deprecated(
+    since = "0.1.0",
+    note = "use StorageConfig and its constructors, such as StorageConfig::s3_with_bucket"
+)]
+#[derive(Clone, PartialEq, Eq)]
+pub struct LegacyStorageConfig {
+    pub backend: StorageBackend,
+    /// Bucket name, now `s3_bucket`
+    pub bucket_name: Option<String>,
+    /// AWS region, now `s3_region`
+    pub region: Option<String>,
+    /// Access key id, now part of `s3_credentials`
+    pub access_key_id: Option<String>,
+    /// Secret access key, now part of `s3_credentials`
+    pub secret_access_key: Option<String>,
+    /// S3-compatible endpoint, now `s3_endpoint_url`
+    pub endpoint_url: Option<String>,
+}
+
+#[allow(deprecated)]
+impl From<LegacyStorageConfig> for StorageConfig {
+    fn from(legacy: LegacyStorageConfig) -> Self {
+        let mut config = match legacy.backend {
+            StorageBackend::Local => StorageConfig::default_local(),
+            StorageBackend::S3 => StorageConfig::default_s3(),
+            StorageBackend::GCS => StorageConfig::default_gcs(),
+        };
+        if config.backend == StorageBackend::GCS {
+            config.gcs_bucket = legacy.bucket_name;
+        } else {
+            config.s3_bucket = legacy.bucket_name;
+        }
+        config.s3_region = legacy.region;
+        config.s3_endpoint_url = legacy.endpoint_url;
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (legacy.access_key_id, legacy.secret_access_key)
+        {
+            config.s3_credentials = Some(S3Credentials::new(access_key_id, secret_access_key));
+        }
+        config
+    }
+}
+
+/// Static AWS credentials, such as keys for MinIO or temporary STS credentials
+///
+/// The `Debug` output shows the access key id only, so configurations can be logged.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    #[serde(serialize_with = "redact")]
+    pub secret_access_key: String,
+    /// Session token of temporary credentials
+    #[serde(default, serialize_with = "redact_option")]
+    pub session_token: Option<String>,
+}
+
+impl S3Credentials {
+    /// Long-term credentials without a session token
+    pub fn new(access_key_id: impl Into<String>, secret_access_key: impl Into<String>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+        }
+    }
+
+    /// Add the session token of temporary credentials
+    pub fn with_session_token(mut self, session_token: impl Into<String>) -> Self {
+        self.session_token = Some(session_token.into());
+        self
+    }
+}
+
+impl std::fmt::Debug for S3Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .field(
+                "session_token",
+                &self.session_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+/// Serializable retry settings that storage adapters translate into a [`RetryPolicy`]
+///
+/// Operation names used for per-operation overrides are the storage operations:
+/// `save`, `load`, `exists`, `delete` and `list`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Maximum number of attempts, including the first (unlimited if not set)
+    pub max_attempts: Option<usize>,
+    /// Delay before the first retry, in milliseconds
+    pub initial_interval_ms: u64,
+    /// Upper bound for any single delay, in milliseconds
+    pub max_interval_ms: u64,
+    /// Total time after which retrying stops, in milliseconds (unlimited if not set)
+    pub max_elapsed_ms: Option<u64>,
+    /// Operations that are never retried (e.g. `["delete"]`)
+    pub never_retry: Vec<String>,
+    /// Longest a single attempt may run, in milliseconds (unbounded if not set)
+    pub attempt_timeout_ms: Option<u64>,
+    /// Randomization of the delays between attempts
+    pub jitter: RetryJitter,
+}
+
+/// Longest interval or timeout accepted in a configuration: one hour
+const MAX_CONFIGURED_MS: u64 = 60 * 60 * 1000;
+
+/// Most attempts accepted in a retry configuration
+const MAX_CONFIGURED_ATTEMPTS: usize = 100;
+
+/// Operations whose retries can be turned off with `never_retry`
+const RETRIED_OPERATIONS: [&str; 5] = ["save", "load", "exists", "delete", "list"];
+
+/// Randomization of retry delays, see [`JitterMode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryJitter {
+    /// Use the computed delays as they are
+    None,
+    /// Uniformly random between zero and the computed delay
+    #[default]
+    Full,
+    /// Half the computed delay plus a random share of the other half
+    Equal,
+    /// Random between the initial interval and three times the previous delay
+    Decorrelated,
+}
+
+impl From<RetryJitter> for JitterMode {
+    fn from(jitter: RetryJitter) -> Self {
+        match jitter {
+            RetryJitter::None => JitterMode::None,
+            RetryJitter::Full => JitterMode::Full,
+            RetryJitter::Equal => JitterMode::Equal,
+            RetryJitter::Decorrelated => JitterMode::Decorrelated,
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            initial_interval_ms: 500,
+            max_interval_ms: 10_000,
+            max_elapsed_ms: Some(60_000),
+            never_retry: Vec::new(),
+            attempt_timeout_ms: Some(30_000),
+            jitter: RetryJitter::Full,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Build the retry policy for a storage operation
+    pub fn policy_for(&self, operation: &str) -> RetryPolicy {
+        let backoff = ExponentialBackoffBuilder::new()
+            .with_initial_interval(Duration::from_millis(self.initial_interval_ms))
+            .with_max_interval(Duration::from_millis(self.max_interval_ms))
+            .with_max_elapsed_time(self.max_elapsed_ms.map(Duration::from_millis))
+            .build();
+
+        let mut policy = RetryPolicy::from(backoff).with_jitter(self.jitter.into());
+        policy.max_attempts = self.max_attempts;
+        policy.attempt_timeout = self.attempt_timeout_ms.map(Duration::from_millis);
+        if self.never_retry.iter().any(|op| op == operation) {
+            policy.retry_on = RetryClassifier::Never;
+        }
+        policy
+    }
+
+    /// Check that the settings are within sensible bounds
+    pub fn validate(&self) -> crate::Result<()> {
+        match self.max_attempts {
+            Some(0) => {
+                return Err(PersistError::validation(
+                    "retry.max_attempts must be at least 1 (1 turns retries off)",
+                ))
+            }
+            Some(attempts) if attempts > MAX_CONFIGURED_ATTEMPTS => {
+                return Err(PersistError::validation(format!(
+                    "retry.max_attempts must be at most {MAX_CONFIGURED_ATTEMPTS}, got {attempts}"
+                )))
+            }
+            _ => {}
+        }
+        if self.initial_interval_ms == 0 {
+            return Err(PersistError::validation(
+                "retry.initial_interval_ms must be greater than zero",
+            ));
+        }
+        if self.max_interval_ms < self.initial_interval_ms {
+            return Err(PersistError::validation(format!(
+                "retry.max_interval_ms ({}) must not be less than retry.initial_interval_ms ({})",
+                self.max_interval_ms, self.initial_interval_ms
+            )));
+        }
+        check_configured_ms("retry.max_interval_ms", Some(self.max_interval_ms))?;
+        check_configured_ms("retry.max_elapsed_ms", self.max_elapsed_ms)?;
+        check_configured_ms("retry.attempt_timeout_ms", self.attempt_timeout_ms)?;
+        if let Some(op) = self
+            .never_retry
+            .iter()
+            .find(|op| !RETRIED_OPERATIONS.contains(&op.as_str()))
+        {
+            return Err(PersistError::validation(format!(
+                "Unknown operation '{op}' in retry.never_retry: expected one of {}",
+                RETRIED_OPERATIONS.join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Timeouts of the storage clients
+///
+/// The S3 adapter applies both to its SDK client. The GCS adapter bounds each request,
+/// connection included, by `request_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    /// Longest wait for a connection to be established, in milliseconds
+    pub connect_ms: Option<u64>,
+    /// Longest a single request may take, in milliseconds
+    pub request_ms: Option<u64>,
+}
+
+impl TimeoutConfig {
+    /// Connect timeout, if configured
+    pub fn connect(&self) -> Option<Duration> {
+        self.connect_ms.map(Duration::from_millis)
+    }
+
+    /// Request timeout, if configured
+    pub fn request(&self) -> Option<Duration> {
+        self.request_ms.map(Duration::from_millis)
+    }
+
+    /// Check that the timeouts are within sensible bounds
+    pub fn validate(&self) -> crate::Result<()> {
+        check_configured_ms("timeouts.connect_ms", self.connect_ms)?;
+        check_configured_ms("timeouts.request_ms", self.request_ms)
+    }
+}
+
+/// Reject a configured duration of zero or of more than [`MAX_CONFIGURED_MS`]
+fn check_configured_ms(name: &str, value: Option<u64>) -> crate::Result<()> {
+    match value {
+        Some(0) => Err(PersistError::validation(format!(
+            "{name} must be greater than zero (leave it out for no limit or the default)"
+        ))),
+        Some(ms) if ms > MAX_CONFIGURED_MS => Err(PersistError::validation(format!(
+            "{name} must be at most {MAX_CONFIGURED_MS} (one hour), got {ms}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Compression section of a configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionConfig {
+    /// Compression algorithm: `gzip`, `zstd` (with the `zstd` feature) or `none`
+    pub algorithm: String,
+    /// Compression level (optional, defaults to the algorithm's default)
+    pub level: Option<i32>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: "gzip".to_string(),
+            level: None,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// The configured compressor
+    pub fn compressor(&self) -> crate::Result<Box<dyn CompressionAdapter + Send + Sync>> {
+        compressor_for(&self.algorithm, self.level)
+    }
+}
+
+/// Encryption section of a configuration
+///
+/// Snapshot encryption is not available yet: a configuration with a key fails
+/// validation rather than writing unencrypted snapshots.
+#[derive(Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// Snapshot encryption key
+    #[serde(serialize_with = "redact_option")]
+    pub key: Option<String>,
+}
+
+impl std::fmt::Debug for EncryptionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionConfig")
+            .field("key", &self.key.as_ref().map(|_| REDACTED))
+            .finish()
+    }
+}
+
+/// Observability section of a configuration
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObservabilitySettings {
+    /// Format of the log lines: `json` or `text` (optional, defaults to JSON)
+    pub log_format: Option<LogFormat>,
+    /// Filter directives such as "persist=debug,warn" (optional, defaults to `RUST_LOG`)
+    pub log_filter: Option<String>,
+    /// Include the enclosing spans in the log lines
+    pub include_spans: bool,
+    /// File the log lines are written to (optional, defaults to stdout)
+    pub log_file: Option<PathBuf>,
+    /// Rotation of the log file: `never`, `daily`, `hourly` or a size such as `100mb`
+    pub log_rotation: LogRotation,
+    /// Log files kept when rotating, the current one included (optional, all of them)
+    pub max_log_files: Option<usize>,
+    /// Fraction of the spans of each operation that are logged and exported, by span name
+    pub span_sample_ratios: HashMap<String, f64>,
+    /// Spans completing faster than this many milliseconds are dropped
+    pub min_span_duration_ms: u64,
+    /// Log the routine start and success of storage operations at debug level
+    pub quiet_success_logs: bool,
+}
+
+impl ObservabilitySettings {
+    /// Settings for [`init_observability_with`](crate::observability::init_observability_with)
+    pub fn observability_config(&self) -> ObservabilityConfig {
+        let mut config = ObservabilityConfig::default()
+            .with_spans(self.include_spans)
+            .with_min_span_duration(Duration::from_millis(self.min_span_duration_ms))
+            .with_quiet_success_logs(self.quiet_success_logs);
+        if let Some(log_format) = self.log_format {
+            config = config.with_log_format(log_format);
+        }
+        if let Some(log_filter) = &self.log_filter {
+            config = config.with_env_filter(log_filter.clone());
+        }
+        if let Some(log_file) = &self.log_file {
+            config = config.with_writer(LogWriter::rotating_file(
+                log_file,
+                self.log_rotation,
+                self.max_log_files,
+            ));
+        }
+        for (operation, ratio) in &self.span_sample_ratios {
+            config = config.with_span_sample_ratio(operation.clone(), *ratio);
+        }
+        config
+    }
+}
+
+/// Format of a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Format of the file at `path`, by its extension: `.toml`, `.yaml` or `.yml`
+    pub fn from_path(path: &Path) -> crate::Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("toml") => Ok(Self::Toml),
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            _ => Err(PersistError::validation(format!(
+                "Config file {} must have a .toml, .yaml or .yml extension",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Parse `text`, after replacing each `${NAME}` by the value of `env(NAME)`
+    ///
+    /// Values are inserted as they are, before parsing; `$${` stays a literal `${`.
+    /// Referencing a variable `env` does not have is an error.
+    pub fn parse<T: DeserializeOwned>(
+        self,
+        text: &str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> crate::Result<T> {
+        let text = interpolate_env(text, env)?;
+        match self {
+            Self::Toml => toml::from_str(&text)
+                .map_err(|e| PersistError::validation(format!("Invalid TOML: {e}"))),
+            Self::Yaml => serde_yaml::from_str(&text)
+                .map_err(|e| PersistError::validation(format!("Invalid YAML: {e}"))),
+        }
+    }
+}
+
+/// `text` with each `${NAME}` replaced by the value of `env(NAME)`
+fn interpolate_env(text: &str, env: impl Fn(&str) -> Option<String>) -> crate::Result<String> {
+    let mut interpolated = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        // `$${` escapes a literal `${`
+        if rest[..start].ends_with('$') {
+            interpolated.push_str(&rest[..start - 1]);
+            interpolated.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        interpolated.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let end = reference
+            .find('}')
+            .ok_or_else(|| PersistError::validation("Unterminated '${' in config file"))?;
+        let name = &reference[..end];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(PersistError::validation(format!(
+                "Invalid environment variable name '{name}' in config file"
+            )));
+        }
+        let value = env(name).ok_or_else(|| {
+            PersistError::validation(format!(
+                "Environment variable {name} is referenced by the config file but not set"
+            ))
+        })?;
+        interpolated.push_str(&value);
+        rest = &reference[end + 1..];
+    }
+    interpolated.push_str(rest);
+    Ok(interpolated)
+}
+
+/// Serialize a secret as [`REDACTED`]
+fn redact<S: Serializer>(_secret: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+/// Serialize an optional secret as [`REDACTED`] if set
+fn redact_option<S: Serializer>(secret: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match secret {
+        Some(_) => serializer.serialize_some(REDACTED),
+        None => serializer.serialize_none(),
+    }
 }
 
 impl StorageConfig {
@@ -46,11 +689,30 @@ impl StorageConfig {
             backend: StorageBackend::Local,
             s3_bucket: None,
             s3_region: None,
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: None,
             gcs_prefix: None,
             gcs_credentials_path: None,
             gcs_timeout_seconds: None,
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
+        }
+    }
+
+    /// Create a local filesystem configuration storing snapshots under `base_path`
+    pub fn local_with_base_path(base_path: impl Into<PathBuf>) -> Self {
+        StorageConfig {
+            local_base_path: Some(base_path.into()),
+            ..Self::default_local()
         }
     }
 
@@ -60,11 +722,22 @@ impl StorageConfig {
             backend: StorageBackend::S3,
             s3_bucket: Some("persist-default-bucket".to_string()),
             s3_region: None, // Will use AWS environment default
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: None,
             gcs_prefix: None,
             gcs_credentials_path: None,
             gcs_timeout_seconds: None,
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
         }
     }
 
@@ -74,11 +747,22 @@ impl StorageConfig {
             backend: StorageBackend::S3,
             s3_bucket: Some(bucket),
             s3_region: None,
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: None,
             gcs_prefix: None,
             gcs_credentials_path: None,
             gcs_timeout_seconds: None,
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
         }
     }
 
@@ -88,11 +772,22 @@ impl StorageConfig {
             backend: StorageBackend::S3,
             s3_bucket: Some(bucket),
             s3_region: Some(region),
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: None,
             gcs_prefix: None,
             gcs_credentials_path: None,
             gcs_timeout_seconds: None,
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
         }
     }
 
@@ -102,11 +797,22 @@ impl StorageConfig {
             backend: StorageBackend::GCS,
             s3_bucket: None,
             s3_region: None,
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: Some("persist-default-gcs-bucket".to_string()),
             gcs_prefix: None,
             gcs_credentials_path: None,
             gcs_timeout_seconds: Some(30), // Default 30 second timeout
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
         }
     }
 
@@ -116,11 +822,22 @@ impl StorageConfig {
             backend: StorageBackend::GCS,
             s3_bucket: None,
             s3_region: None,
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: Some(bucket),
             gcs_prefix: None,
             gcs_credentials_path: None,
             gcs_timeout_seconds: Some(30),
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
         }
     }
 
@@ -130,11 +847,22 @@ impl StorageConfig {
             backend: StorageBackend::GCS,
             s3_bucket: None,
             s3_region: None,
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: Some(bucket),
             gcs_prefix: None,
             gcs_credentials_path: Some(credentials_path),
             gcs_timeout_seconds: Some(30),
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
         }
     }
 
@@ -148,11 +876,22 @@ impl StorageConfig {
             backend: StorageBackend::GCS,
             s3_bucket: None,
             s3_region: None,
+            s3_endpoint_url: None,
+            s3_force_path_style: false,
+            s3_credentials: None,
+            s3_profile: None,
             local_base_path: None,
+            local_durable_writes: false,
+            local_file_permissions: None,
             gcs_bucket: Some(bucket),
             gcs_prefix: Some(prefix),
             gcs_credentials_path: credentials_path,
             gcs_timeout_seconds: Some(30),
+            retry: None,
+            timeouts: None,
+            compression: None,
+            encryption: None,
+            observability: None,
         }
     }
 
@@ -161,7 +900,7 @@ impl StorageConfig {
     /// Supports formats:
     /// - `s3://bucket-name/path` for S3 storage
     /// - `gs://bucket-name/path` for GCS storage
-    /// - `/local/path` or `./relative/path` for local storage
+    /// - `file://path`, `/local/path` or `./relative/path` for local storage
     ///
     /// Returns the config and the extracted key/path component
     pub fn from_uri(uri: &str) -> Result<(StorageConfig, String), crate::PersistError> {
@@ -193,13 +932,65 @@ impl StorageConfig {
             Ok((config, key))
         } else {
             // Treat as local path
+            let path = uri.strip_prefix("file://").unwrap_or(uri);
             let config = StorageConfig::default_local();
-            Ok((config, uri.to_string()))
+            Ok((config, path.to_string()))
         }
     }
 
+    /// Load a configuration from a TOML or YAML file, by its extension
+    ///
+    /// `${NAME}` in the file is replaced by the environment variable `NAME`, so that
+    /// secrets such as `secret_access_key = "${AWS_SECRET_ACCESS_KEY}"` stay out of
+    /// the file. Unknown keys are rejected, and the configuration is validated.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read, has another extension, references an unset
+    /// environment variable, is malformed, has unknown keys or is invalid.
+    pub fn from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)?;
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            PersistError::io_read(e, format!("Failed to read config file {}", path.display()))
+        })?;
+        let config: Self = format
+            .parse(&text, |name| std::env::var(name).ok())
+            .map_err(|e| match e {
+                PersistError::Validation(message) => {
+                    PersistError::validation(format!("Config file {}: {message}", path.display()))
+                }
+                e => e,
+            })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Compressor of the snapshots written with this configuration (gzip by default)
+    pub fn compressor(&self) -> crate::Result<Box<dyn CompressionAdapter + Send + Sync>> {
+        self.compression.clone().unwrap_or_default().compressor()
+    }
+
     /// Validate the configuration
     pub fn validate(&self) -> crate::Result<()> {
+        if let Some(compression) = &self.compression {
+            compression.compressor()?;
+        }
+        if let Some(retry) = &self.retry {
+            retry.validate()?;
+        }
+        if let Some(timeouts) = &self.timeouts {
+            timeouts.validate()?;
+        }
+        if self
+            .encryption
+            .as_ref()
+            .is_some_and(|encryption| encryption.key.is_some())
+        {
+            return Err(PersistError::validation(
+                "Snapshot encryption is not available in this version of persist: remove the \
+                 encryption key",
+            ));
+        }
         match self.backend {
             StorageBackend::S3 => {
                 if self.s3_bucket.is_none() || self.s3_bucket.as_ref().unwrap().is_empty() {
@@ -207,6 +998,20 @@ impl StorageConfig {
                         "S3 backend requires a valid bucket name",
                     ));
                 }
+                if self.s3_profile.as_ref().is_some_and(|p| p.is_empty()) {
+                    return Err(crate::PersistError::validation(
+                        "S3 profile name must not be empty",
+                    ));
+                }
+                if let Some(credentials) = &self.s3_credentials {
+                    if credentials.access_key_id.is_empty()
+                        || credentials.secret_access_key.is_empty()
+                    {
+                        return Err(crate::PersistError::validation(
+                            "S3 credentials require both an access key id and a secret access key",
+                        ));
+                    }
+                }
             }
             StorageBackend::GCS => {
                 if self.gcs_bucket.is_none() || self.gcs_bucket.as_ref().unwrap().is_empty() {
@@ -214,6 +1019,17 @@ impl StorageConfig {
                         "GCS backend requires a valid bucket name",
                     ));
                 }
+                if let Some(bucket) = self.gcs_bucket.as_ref().filter(|b| b.contains('/')) {
+                    return Err(crate::PersistError::validation(format!(
+                        "GCS bucket name '{bucket}' must not contain '/'; set the object path \
+                         prefix with gcs_prefix"
+                    )));
+                }
+                if self.gcs_timeout_seconds == Some(0) {
+                    return Err(crate::PersistError::validation(
+                        "GCS timeout must be at least one second",
+                    ));
+                }
             }
             StorageBackend::Local => {
                 // Local storage validation can be added here if needed
@@ -239,6 +1055,8 @@ mod tests {
         assert_eq!(config.backend, StorageBackend::Local);
         assert!(config.s3_bucket.is_none());
         assert!(config.local_base_path.is_none());
+        assert!(!config.local_durable_writes);
+        assert!(config.local_file_permissions.is_none());
     }
 
     #[test]
@@ -276,6 +1094,10 @@ mod tests {
         let (config, path) = StorageConfig::from_uri("/local/path/file.json").unwrap();
         assert_eq!(config.backend, StorageBackend::Local);
         assert_eq!(path, "/local/path/file.json");
+
+        let (config, path) = StorageConfig::from_uri("file://./snapshots/a.json.gz").unwrap();
+        assert_eq!(config.backend, StorageBackend::Local);
+        assert_eq!(path, "./snapshots/a.json.gz");
     }
 
     #[test]
@@ -363,6 +1185,456 @@ mod tests {
             .contains("missing bucket name"));
     }
 
+    #[test]
+    fn test_retry_config_policy_for() {
+        let retry = RetryConfig {
+            max_attempts: Some(5),
+            never_retry: vec!["delete".to_string()],
+            ..RetryConfig::default()
+        };
+
+        let save = retry.policy_for("save");
+        assert_eq!(save.max_attempts, Some(5));
+        assert_eq!(save.retry_on, RetryClassifier::Transient);
+        assert_eq!(save.backoff.initial_interval, Duration::from_millis(500));
+        assert_eq!(save.backoff.max_elapsed_time, Some(Duration::from_secs(60)));
+        assert_eq!(save.jitter, JitterMode::Full);
+        assert_eq!(save.attempt_timeout, Some(Duration::from_secs(30)));
+
+        assert_eq!(retry.policy_for("delete").retry_on, RetryClassifier::Never);
+    }
+
+    #[test]
+    fn test_retry_and_timeouts_from_toml() {
+        let text = r#"
+backend = "s3"
+s3_bucket = "b"
+
+[retry]
+max_attempts = 2
+initial_interval_ms = 50
+max_interval_ms = 200
+jitter = "decorrelated"
+
+[timeouts]
+connect_ms = 1000
+request_ms = 5000
+"#;
+        let config: StorageConfig = ConfigFormat::Toml.parse(text, |_: &str| None).unwrap();
+        assert!(config.validate().is_ok());
+        let retry = config.retry.unwrap();
+        assert_eq!(retry.jitter, RetryJitter::Decorrelated);
+        let policy = retry.policy_for("save");
+        assert_eq!(policy.max_attempts, Some(2));
+        assert_eq!(policy.jitter, JitterMode::Decorrelated);
+        assert_eq!(policy.backoff.max_interval, Duration::from_millis(200));
+
+        let timeouts = config.timeouts.unwrap();
+        assert_eq!(timeouts.connect(), Some(Duration::from_secs(1)));
+        assert_eq!(timeouts.request(), Some(Duration::from_secs(5)));
+
+        let error = ConfigFormat::Toml
+            .parse::<StorageConfig>(&text.replace("decorrelated", "sometimes"), |_: &str| None)
+            .unwrap_err();
+        assert!(error.to_string().contains("sometimes"), "{error}");
+    }
+
+    #[test]
+    fn test_retry_and_timeout_bounds() {
+        let rejected = |retry: RetryConfig, expected: &str| {
+            let mut config = StorageConfig::default_local();
+            config.retry = Some(retry);
+            let error = config.validate().unwrap_err().to_string();
+            assert!(error.contains(expected), "{error}");
+        };
+        let retry = RetryConfig::default;
+        rejected(
+            RetryConfig {
+                max_attempts: Some(0),
+                ..retry()
+            },
+            "retry.max_attempts must be at least 1",
+        );
+        rejected(
+            RetryConfig {
+                max_attempts: Some(1000),
+                ..retry()
+            },
+            "retry.max_attempts must be at most 100",
+        );
+        rejected(
+            RetryConfig {
+                initial_interval_ms: 0,
+                ..retry()
+            },
+            "retry.initial_interval_ms",
+        );
+        rejected(
+            RetryConfig {
+                initial_interval_ms: 2_000,
+                max_interval_ms: 1_000,
+                ..retry()
+            },
+            "must not be less than retry.initial_interval_ms",
+        );
+        rejected(
+            RetryConfig {
+                max_elapsed_ms: Some(0),
+                ..retry()
+            },
+            "retry.max_elapsed_ms must be greater than zero",
+        );
+        rejected(
+            RetryConfig {
+                attempt_timeout_ms: Some(24 * 60 * 60 * 1000),
+                ..retry()
+            },
+            "retry.attempt_timeout_ms must be at most",
+        );
+        rejected(
+            RetryConfig {
+                never_retry: vec!["copy".to_string()],
+                ..retry()
+            },
+            "Unknown operation 'copy'",
+        );
+
+        let mut config = StorageConfig::default_local();
+        config.retry = Some(RetryConfig {
+            max_attempts: Some(1),
+            ..retry()
+        });
+        config.timeouts = Some(TimeoutConfig {
+            connect_ms: Some(500),
+            request_ms: None,
+        });
+        assert!(config.validate().is_ok());
+        config.timeouts = Some(TimeoutConfig {
+            connect_ms: None,
+            request_ms: Some(0),
+        });
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("timeouts.request_ms"), "{error}");
+    }
+
+    #[test]
+    fn test_retry_config_deserializes_partial() {
+        let config: StorageConfig = serde_json::from_str(
+            r#"{"backend": "S3", "s3_bucket": "b", "s3_region": null, "local_base_path": null,
+                "gcs_bucket": null, "gcs_prefix": null, "gcs_credentials_path": null,
+                "gcs_timeout_seconds": null, "retry": {"max_attempts": 3}}"#,
+        )
+        .unwrap();
+        let retry = config.retry.unwrap();
+        assert_eq!(retry.max_attempts, Some(3));
+        assert_eq!(retry.max_interval_ms, 10_000);
+        assert_eq!(retry.attempt_timeout_ms, Some(30_000));
+        // Configs written before the local write options existed still load
+        assert!(!config.local_durable_writes);
+        assert!(config.local_file_permissions.is_none());
+        assert!(config.s3_endpoint_url.is_none());
+        assert!(!config.s3_force_path_style);
+        assert!(config.s3_credentials.is_none());
+        assert!(config.s3_profile.is_none());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_legacy_storage_config() {
+        let config = StorageConfig::from(LegacyStorageConfig {
+            backend: StorageBackend::S3,
+            bucket_name: Some("b".to_string()),
+            region: Some("eu-west-1".to_string()),
+            access_key_id: Some("AKIDEXAMPLE".to_string()),
+            secret_access_key: Some("wJalrXUtnFEMI".to_string()),
+            endpoint_url: Some("http://localhost:4566".to_string()),
+        });
+        let mut expected = StorageConfig::s3_with_bucket_and_region("b".into(), "eu-west-1".into());
+        expected.s3_endpoint_url = Some("http://localhost:4566".to_string());
+        expected.s3_credentials = Some(S3Credentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI"));
+        assert_eq!(config, expected);
+
+        let config = StorageConfig::from(LegacyStorageConfig {
+            backend: StorageBackend::Local,
+            bucket_name: None,
+            region: None,
+            access_key_id: None,
+            secret_access_key: None,
+            endpoint_url: None,
+        });
+        assert_eq!(config, StorageConfig::default_local());
+        assert_eq!(
+            StorageConfig::local_with_base_path("/tmp/snapshots").local_base_path,
+            Some(PathBuf::from("/tmp/snapshots"))
+        );
+    }
+
+    #[test]
+    fn test_legacy_serialized_config() {
+        let config: StorageConfig = serde_json::from_str(
+            r#"{"backend": "S3", "bucket_name": "b", "region": "us-east-1",
+                "access_key_id": "AKIDEXAMPLE", "secret_access_key": "wJalrXUtnFEMI",
+                "endpoint_url": "http://localhost:4566"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.s3_bucket.as_deref(), Some("b"));
+        assert_eq!(config.s3_region.as_deref(), Some("us-east-1"));
+        assert_eq!(
+            config.s3_endpoint_url.as_deref(),
+            Some("http://localhost:4566")
+        );
+        assert_eq!(
+            config.s3_credentials,
+            Some(S3Credentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI"))
+        );
+
+        let config: StorageConfig = serde_json::from_str(
+            r#"{"backend": "Local", "bucket_name": null, "region": null,
+                "access_key_id": null, "secret_access_key": null, "endpoint_url": null}"#,
+        )
+        .unwrap();
+        assert_eq!(config, StorageConfig::default_local());
+
+        // Written back in the current shape, which reads back the same
+        let text = serde_json::to_string(&StorageConfig::s3_with_bucket("b".into())).unwrap();
+        assert!(text.contains("\"s3_bucket\":\"b\""));
+        let config: StorageConfig = serde_json::from_str(&text).unwrap();
+        assert_eq!(config, StorageConfig::s3_with_bucket("b".into()));
+
+        let error = serde_json::from_str::<StorageConfig>(
+            r#"{"backend": "S3", "bucket_name": "b", "access_key_id": "AKIDEXAMPLE",
+                "s3_credentials": {"access_key_id": "A", "secret_access_key": "S"}}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("s3_credentials"), "{error}");
+        let error = serde_json::from_str::<StorageConfig>(
+            r#"{"backend": "S3", "bucket_name": "b", "access_key_id": "AKIDEXAMPLE"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("given together"), "{error}");
+        let error = serde_json::from_str::<StorageConfig>(
+            r#"{"backend": "S3", "bucket_name": "b", "s3_bucket": "c"}"#,
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("rename it to s3_bucket"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_legacy_gcs_config_file() {
+        let (_dir, path) = write_config(
+            "persist.yaml",
+            "backend: GCS\nbucket_name: agent-snapshots\nregion: null\n",
+        );
+        let config = StorageConfig::from_file(&path).unwrap();
+        assert_eq!(config.backend, StorageBackend::GCS);
+        assert_eq!(config.gcs_bucket.as_deref(), Some("agent-snapshots"));
+        assert!(config.s3_bucket.is_none());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_s3_credentials_debug_hides_secrets() {
+        let credentials =
+            S3Credentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI").with_session_token("FwoGZXIvYXdz");
+        let mut config = StorageConfig::s3_with_bucket("b".to_string());
+        config.s3_credentials = Some(credentials);
+
+        let debug = format!("{config:?}");
+        assert!(debug.contains("AKIDEXAMPLE"));
+        assert!(!debug.contains("wJalrXUtnFEMI"));
+        assert!(!debug.contains("FwoGZXIvYXdz"));
+        assert!(config.validate().is_ok());
+
+        config.s3_credentials = Some(S3Credentials::new("AKIDEXAMPLE", ""));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_s3_profile() {
+        let mut config = StorageConfig::s3_with_bucket("b".to_string());
+        config.s3_profile = Some("cross-account".to_string());
+        assert!(config.validate().is_ok());
+        config.s3_profile = Some(String::new());
+        assert!(config.validate().is_err());
+
+        let config: StorageConfig = ConfigFormat::Toml
+            .parse(
+                "backend = \"s3\"\ns3_bucket = \"b\"\ns3_profile = \"minio\"\n",
+                |_: &str| None,
+            )
+            .unwrap();
+        assert_eq!(config.s3_profile.as_deref(), Some("minio"));
+    }
+
+    /// Config file named `name` with `text` in a new directory
+    fn write_config(name: &str, text: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, text).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_from_toml_file() {
+        let (_dir, path) = write_config(
+            "persist.toml",
+            r#"
+backend = "s3"
+s3_bucket = "agent-snapshots"
+s3_region = "us-west-2"
+
+[retry]
+max_attempts = 5
+never_retry = ["delete"]
+
+[compression]
+algorithm = "gzip"
+level = 9
+
+[observability]
+log_format = "text"
+log_file = "/var/log/persist/persist.log"
+log_rotation = "100mb"
+span_sample_ratios = { save_snapshot = 0.1 }
+min_span_duration_ms = 5
+quiet_success_logs = true
+"#,
+        );
+        let config = StorageConfig::from_file(&path).unwrap();
+        assert_eq!(config.backend, StorageBackend::S3);
+        assert_eq!(config.s3_bucket.as_deref(), Some("agent-snapshots"));
+        let retry = config.retry.as_ref().unwrap();
+        assert_eq!(retry.max_attempts, Some(5));
+        assert_eq!(retry.initial_interval_ms, 500);
+        assert_eq!(config.compressor().unwrap().algorithm_name(), "gzip");
+
+        let observability = config.observability.unwrap().observability_config();
+        assert_eq!(observability.log_format, LogFormat::Text);
+        assert_eq!(
+            observability.writer,
+            LogWriter::rotating_file(
+                "/var/log/persist/persist.log",
+                LogRotation::SizeMB(100),
+                None
+            )
+        );
+        assert_eq!(observability.span_sample_ratios["save_snapshot"], 0.1);
+        assert_eq!(observability.min_span_duration, Duration::from_millis(5));
+        assert!(observability.quiet_success_logs);
+    }
+
+    #[test]
+    fn test_from_yaml_file() {
+        let (_dir, path) = write_config(
+            "persist.yml",
+            "backend: gcs\ngcs_bucket: agent-snapshots\ngcs_prefix: agents/\ncompression:\n  algorithm: none\nencryption: {}\n",
+        );
+        let config = StorageConfig::from_file(&path).unwrap();
+        assert_eq!(config.backend, StorageBackend::GCS);
+        assert_eq!(config.gcs_prefix.as_deref(), Some("agents/"));
+        assert_eq!(config.compressor().unwrap().algorithm_name(), "none");
+        assert_eq!(config.encryption, Some(EncryptionConfig::default()));
+        assert!(config.observability.is_none());
+    }
+
+    #[test]
+    fn test_from_file_rejects_unknown_keys() {
+        let (_dir, path) = write_config("persist.toml", "backend = \"s3\"\ns3_bukcet = \"b\"\n");
+        let err = StorageConfig::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("persist.toml"), "{err}");
+        assert!(err.contains("unknown field `s3_bukcet`"), "{err}");
+        assert!(err.contains("s3_bucket"), "{err}");
+
+        let (_dir, path) =
+            write_config("persist.yaml", "backend: local\nretry:\n  max_attempt: 3\n");
+        let err = StorageConfig::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("unknown field `max_attempt`"), "{err}");
+
+        let (_dir, path) = write_config("persist.json", "{}");
+        let err = StorageConfig::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains(".toml, .yaml or .yml"), "{err}");
+
+        // Invalid sections are caught by validation
+        let (_dir, path) = write_config(
+            "persist.toml",
+            "backend = \"local\"\n[compression]\nalgorithm = \"lz4\"\n",
+        );
+        assert!(StorageConfig::from_file(&path).is_err());
+        let (_dir, path) = write_config(
+            "persist.toml",
+            "backend = \"local\"\n[encryption]\nkey = \"k\"\n",
+        );
+        let err = StorageConfig::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("encryption"), "{err}");
+    }
+
+    #[test]
+    fn test_env_interpolation() {
+        let text = r#"
+backend = "s3"
+s3_bucket = "b"
+
+[s3_credentials]
+access_key_id = "${AWS_ACCESS_KEY_ID}"
+secret_access_key = "${AWS_SECRET_ACCESS_KEY}"
+session_token = "not-$${interpolated}"
+"#;
+        let env = |name: &str| match name {
+            "AWS_ACCESS_KEY_ID" => Some("AKIDEXAMPLE".to_string()),
+            "AWS_SECRET_ACCESS_KEY" => Some("wJalrXUtnFEMI".to_string()),
+            _ => None,
+        };
+        let config: StorageConfig = ConfigFormat::Toml.parse(text, env).unwrap();
+        let credentials = config.s3_credentials.unwrap();
+        assert_eq!(credentials.access_key_id, "AKIDEXAMPLE");
+        assert_eq!(credentials.secret_access_key, "wJalrXUtnFEMI");
+        assert_eq!(
+            credentials.session_token.as_deref(),
+            Some("not-${interpolated}")
+        );
+
+        let err = ConfigFormat::Toml
+            .parse::<StorageConfig>(text, |_| None)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("AWS_ACCESS_KEY_ID is referenced"),
+            "{err}"
+        );
+
+        // From the process environment
+        std::env::set_var("PERSIST_CONFIG_TEST_BUCKET", "env-bucket");
+        let (_dir, path) = write_config(
+            "persist.yaml",
+            "backend: s3\ns3_bucket: ${PERSIST_CONFIG_TEST_BUCKET}\n",
+        );
+        let config = StorageConfig::from_file(&path).unwrap();
+        assert_eq!(config.s3_bucket.as_deref(), Some("env-bucket"));
+    }
+
+    #[test]
+    fn test_serialize_redacts_secrets() {
+        let mut config = StorageConfig::s3_with_bucket("b".to_string());
+        config.s3_credentials = Some(
+            S3Credentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI").with_session_token("FwoGZXIvYXdz"),
+        );
+        config.encryption = Some(EncryptionConfig {
+            key: Some("hunter2".to_string()),
+        });
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains("AKIDEXAMPLE"));
+        assert!(json.contains(REDACTED));
+        for secret in ["wJalrXUtnFEMI", "FwoGZXIvYXdz", "hunter2"] {
+            assert!(!json.contains(secret), "{json}");
+        }
+        let toml = toml::to_string(&config).unwrap();
+        assert!(!toml.contains("wJalrXUtnFEMI"), "{toml}");
+        assert!(!format!("{config:?}").contains("hunter2"));
+    }
+
     #[test]
     fn test_validate_gcs_config() {
         let mut config = StorageConfig::default_gcs();
@@ -373,5 +1645,18 @@ mod tests {
 
         config.gcs_bucket = Some("".to_string());
         assert!(config.validate().is_err());
+
+        config.gcs_bucket = Some("bucket/agents".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("gcs_prefix"), "{err}");
+
+        let mut config = StorageConfig::gcs_with_bucket_prefix_and_credentials(
+            "bucket".to_string(),
+            "agents/".to_string(),
+            None,
+        );
+        assert!(config.validate().is_ok());
+        config.gcs_timeout_seconds = Some(0);
+        assert!(config.validate().is_err());
     }
 }
@@ -0,0 +1,421 @@
+/*!
+Structured comparison of snapshots.
+
+[`SnapshotMetadata::diff`] compares two metadata records field by field, which is
+cheap and usually enough to explain why two snapshots differ before looking at
+their (potentially large) agent states. [`SnapshotEngine::diff_snapshots`](crate::SnapshotEngine::diff_snapshots)
+combines it with a comparison of the stored agent states, listed as JSON-patch-style
+[`StateChange`]s.
+*/
+
+use crate::SnapshotMetadata;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Before and after values of a changed field
+///
+/// Unset optional fields are represented as JSON `null`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+/// Field-level differences between two snapshot metadata records
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MetadataDiff {
+    /// Changed fields other than tags, keyed by field name
+    pub changed: BTreeMap<String, FieldChange>,
+    /// Tags present only in the second record
+    pub tags_added: BTreeMap<String, String>,
+    /// Tags present only in the first record
+    pub tags_removed: BTreeMap<String, String>,
+    /// Tags present in both records with different values
+    pub tags_changed: BTreeMap<String, FieldChange>,
+}
+
+impl MetadataDiff {
+    /// Whether the two records are identical
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+            && self.tags_added.is_empty()
+            && self.tags_removed.is_empty()
+            && self.tags_changed.is_empty()
+    }
+}
+
+impl std::fmt::Display for MetadataDiff {
+    /// One line per difference: `field: before -> after`, `+tag k=v`, `-tag k=v`, `~tag k: a -> b`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "(no metadata differences)");
+        }
+
+        let mut lines = Vec::new();
+        for (field, change) in &self.changed {
+            lines.push(format!("{field}: {} -> {}", change.before, change.after));
+        }
+        for (key, value) in &self.tags_added {
+            lines.push(format!("+tag {key}={value}"));
+        }
+        for (key, value) in &self.tags_removed {
+            lines.push(format!("-tag {key}={value}"));
+        }
+        for (key, change) in &self.tags_changed {
+            lines.push(format!("~tag {key}: {} -> {}", change.before, change.after));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// One change between two agent states, in the style of a JSON Patch operation
+///
+/// `path` is a JSON Pointer (RFC 6901). Unlike JSON Patch, removals and replacements
+/// also carry the value they discard, so a change can be reviewed on its own.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum StateChange {
+    /// A value present only in the second state
+    Add {
+        path: String,
+        value: serde_json::Value,
+    },
+    /// A value present only in the first state
+    Remove {
+        path: String,
+        old_value: serde_json::Value,
+    },
+    /// A value that differs between the states
+    Replace {
+        path: String,
+        old_value: serde_json::Value,
+        value: serde_json::Value,
+    },
+}
+
+impl StateChange {
+    /// JSON Pointer of the changed value
+    pub fn path(&self) -> &str {
+        match self {
+            StateChange::Add { path, .. }
+            | StateChange::Remove { path, .. }
+            | StateChange::Replace { path, .. } => path,
+        }
+    }
+}
+
+/// Differences between two stored snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotDiff {
+    /// Metadata differences
+    pub metadata: MetadataDiff,
+    /// Whether the agent states differ
+    pub state_changed: bool,
+    /// Changes from the first agent state to the second; empty if either state is not
+    /// valid JSON, in which case only `state_changed` is meaningful
+    pub state_changes: Vec<StateChange>,
+}
+
+impl SnapshotDiff {
+    /// Compare two loaded snapshots
+    ///
+    /// Sensitive metadata fields are compared in redacted form, so a change shows up
+    /// without disclosing either value.
+    pub fn new(
+        metadata_a: &SnapshotMetadata,
+        state_a: &str,
+        metadata_b: &SnapshotMetadata,
+        state_b: &str,
+    ) -> Self {
+        let state_changes = match (
+            serde_json::from_str::<serde_json::Value>(state_a),
+            serde_json::from_str::<serde_json::Value>(state_b),
+        ) {
+            (Ok(a), Ok(b)) => diff_json(&a, &b),
+            _ => Vec::new(),
+        };
+        SnapshotDiff {
+            metadata: metadata_a.redacted().diff(&metadata_b.redacted()),
+            state_changed: state_a != state_b,
+            state_changes,
+        }
+    }
+}
+
+/// List the changes that turn `before` into `after`
+///
+/// Objects are compared key by key and arrays index by index, so an element inserted
+/// into the middle of an array shows up as replacements of the elements after it plus
+/// an addition at the end. Changes are ordered by path, except that trailing array
+/// removals come highest index first so they can be applied in order.
+pub fn diff_json(before: &serde_json::Value, after: &serde_json::Value) -> Vec<StateChange> {
+    let mut changes = Vec::new();
+    diff_value("", before, after, &mut changes);
+    changes
+}
+
+fn diff_value(
+    path: &str,
+    before: &serde_json::Value,
+    after: &serde_json::Value,
+    changes: &mut Vec<StateChange>,
+) {
+    use serde_json::Value;
+
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: std::collections::BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let child = format!("{path}/{}", escape_pointer(key));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_value(&child, x, y, changes),
+                    (Some(x), None) => changes.push(StateChange::Remove {
+                        path: child,
+                        old_value: x.clone(),
+                    }),
+                    (None, Some(y)) => changes.push(StateChange::Add {
+                        path: child,
+                        value: y.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                diff_value(&format!("{path}/{i}"), x, y, changes);
+            }
+            for (i, y) in b.iter().enumerate().skip(a.len()) {
+                changes.push(StateChange::Add {
+                    path: format!("{path}/{i}"),
+                    value: y.clone(),
+                });
+            }
+            for (i, x) in a.iter().enumerate().skip(b.len()).rev() {
+                changes.push(StateChange::Remove {
+                    path: format!("{path}/{i}"),
+                    old_value: x.clone(),
+                });
+            }
+        }
+        (a, b) if a != b => changes.push(StateChange::Replace {
+            path: path.to_string(),
+            old_value: a.clone(),
+            value: b.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Escape a key for use as a JSON Pointer reference token
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+impl SnapshotMetadata {
+    /// Compare this metadata with `other`, listing every changed field
+    ///
+    /// # Example
+    /// ```rust
+    /// use persist_core::SnapshotMetadata;
+    ///
+    /// let before = SnapshotMetadata::builder("agent", "session", 1).tag("env", "dev").build();
+    /// let mut after = before.clone();
+    /// after.snapshot_index = 2;
+    /// after.tags.insert("env".to_string(), "prod".to_string());
+    ///
+    /// let diff = before.diff(&after);
+    /// assert!(diff.changed.contains_key("snapshot_index"));
+    /// assert!(diff.tags_changed.contains_key("env"));
+    /// ```
+    pub fn diff(&self, other: &SnapshotMetadata) -> MetadataDiff {
+        let mut diff = MetadataDiff::default();
+
+        let before = field_map(self);
+        let after = field_map(other);
+        for (field, before_value) in &before {
+            let after_value = after.get(field).unwrap_or(&serde_json::Value::Null);
+            if before_value != after_value {
+                diff.changed.insert(
+                    field.clone(),
+                    FieldChange {
+                        before: before_value.clone(),
+                        after: after_value.clone(),
+                    },
+                );
+            }
+        }
+
+        for (key, value) in &self.tags {
+            match other.tags.get(key) {
+                None => {
+                    diff.tags_removed.insert(key.clone(), value.clone());
+                }
+                Some(other_value) if other_value != value => {
+                    diff.tags_changed.insert(
+                        key.clone(),
+                        FieldChange {
+                            before: value.clone().into(),
+                            after: other_value.clone().into(),
+                        },
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in &other.tags {
+            if !self.tags.contains_key(key) {
+                diff.tags_added.insert(key.clone(), value.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+/// Serialized fields of a metadata record, excluding tags (diffed separately)
+fn field_map(metadata: &SnapshotMetadata) -> serde_json::Map<String, serde_json::Value> {
+    let mut fields = match serde_json::to_value(metadata) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    fields.remove("tags");
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_identical_metadata_has_empty_diff() {
+        let metadata = SnapshotMetadata::builder("agent", "session", 3)
+            .tag("env", "prod")
+            .build();
+
+        let diff = metadata.diff(&metadata.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "(no metadata differences)");
+    }
+
+    #[test]
+    fn test_tag_changes_are_distinguished() {
+        let before = SnapshotMetadata::builder("agent", "session", 0)
+            .tag("keep", "same")
+            .tag("env", "dev")
+            .tag("old", "x")
+            .build();
+        let mut after = before.clone();
+        after.tags.remove("old");
+        after.tags.insert("env".to_string(), "prod".to_string());
+        after.tags.insert("new".to_string(), "y".to_string());
+
+        let diff = before.diff(&after);
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.tags_added["new"], "y");
+        assert_eq!(diff.tags_removed["old"], "x");
+        assert_eq!(diff.tags_changed["env"].before, json!("dev"));
+        assert_eq!(diff.tags_changed["env"].after, json!("prod"));
+        assert!(!diff.tags_changed.contains_key("keep"));
+    }
+
+    #[test]
+    fn test_state_diff_nested_objects() {
+        let before = json!({
+            "config": { "model": "small", "temperature": 0.2, "stale": true },
+            "name": "agent",
+        });
+        let after = json!({
+            "config": { "model": "large", "temperature": 0.2, "a/b~c": 1 },
+            "name": "agent",
+        });
+
+        assert_eq!(
+            diff_json(&before, &after),
+            vec![
+                StateChange::Add {
+                    path: "/config/a~1b~0c".to_string(),
+                    value: json!(1),
+                },
+                StateChange::Replace {
+                    path: "/config/model".to_string(),
+                    old_value: json!("small"),
+                    value: json!("large"),
+                },
+                StateChange::Remove {
+                    path: "/config/stale".to_string(),
+                    old_value: json!(true),
+                },
+            ]
+        );
+        assert!(diff_json(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_state_diff_arrays() {
+        let before = json!({ "memory": ["a", "b", "c", "d"], "steps": [{ "n": 1 }] });
+        let after = json!({ "memory": ["a", "x"], "steps": [{ "n": 2 }, { "n": 3 }] });
+
+        let changes = diff_json(&before, &after);
+        let paths: Vec<&str> = changes.iter().map(StateChange::path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/memory/1",
+                "/memory/3",
+                "/memory/2",
+                "/steps/0/n",
+                "/steps/1"
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&changes[1]).unwrap(),
+            json!({ "op": "remove", "path": "/memory/3", "old_value": "d" })
+        );
+
+        // A changed root of a different type is one replacement
+        assert_eq!(
+            diff_json(&json!([1]), &json!({ "a": 1 })),
+            vec![StateChange::Replace {
+                path: String::new(),
+                old_value: json!([1]),
+                value: json!({ "a": 1 }),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_diff_of_non_json_state() {
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        let diff = SnapshotDiff::new(&metadata, "not json", &metadata, "still not json");
+        assert!(diff.state_changed);
+        assert!(diff.state_changes.is_empty());
+        assert!(diff.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_field_and_optional_transitions() {
+        let before = SnapshotMetadata::new("agent", "session", 1);
+        let mut after = before.clone();
+        after.snapshot_index = 2;
+        after.description = Some("annotated".to_string());
+        after.compressed_size = Some(128);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.changed.len(), 3);
+        assert_eq!(diff.changed["snapshot_index"].before, json!(1));
+        assert_eq!(diff.changed["snapshot_index"].after, json!(2));
+        assert_eq!(diff.changed["description"].before, serde_json::Value::Null);
+        assert_eq!(diff.changed["description"].after, json!("annotated"));
+
+        let reverse = after.diff(&before);
+        assert_eq!(
+            reverse.changed["compressed_size"].after,
+            serde_json::Value::Null
+        );
+
+        let value = serde_json::to_value(&diff).unwrap();
+        assert_eq!(value["changed"]["compressed_size"]["after"], json!(128));
+    }
+}
@@ -2,6 +2,8 @@
 Error types for the Persist core engine.
 */
 
+use persist_retry::RetryableError;
+use std::io::ErrorKind;
 use thiserror::Error;
 
 /// Result type used throughout the Persist core.
@@ -30,6 +32,10 @@ pub enum PersistError {
     #[error("Invalid snapshot format: {0}")]
     InvalidFormat(String),
 
+    /// Snapshot uses an older format version that must be migrated before it can be read
+    #[error("Snapshot format version {found} needs migration to {current} before it can be read")]
+    NeedsMigration { found: String, current: String },
+
     /// Missing required metadata fields
     #[error("Missing required metadata field: {0}")]
     MissingMetadata(String),
@@ -66,9 +72,46 @@ pub enum PersistError {
     #[error("S3 configuration error: {0}")]
     S3Configuration(String),
 
+    /// GCS object not found
+    #[error("State not found in GCS (bucket: {bucket}, key: {key})")]
+    GcsNotFound { bucket: String, key: String },
+
+    /// GCS access denied
+    #[error("Access denied to GCS (bucket: {bucket}, key: {key}): check IAM permissions")]
+    GcsAccessDenied { bucket: String, key: String },
+
     /// Validation errors
     #[error("Validation error: {0}")]
     Validation(String),
+
+    /// Snapshot was produced by an incompatible agent framework or framework version
+    #[error("Framework mismatch: snapshot was created with {actual}, but {expected} is required")]
+    FrameworkMismatch { expected: String, actual: String },
+
+    /// Snapshot index is not greater than the latest existing index for its agent/session
+    #[error("Snapshot index {index} for {agent_id}/{session_id} is not after the latest existing index {latest}")]
+    IndexOutOfOrder {
+        agent_id: String,
+        session_id: String,
+        index: u64,
+        latest: u64,
+    },
+
+    /// The storage service throttled the request (HTTP 429/503)
+    #[error("Storage throttled: {message}")]
+    Throttled {
+        message: String,
+        /// Server-provided delay before retrying (from a `Retry-After` header)
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// Sensitive metadata could not be encrypted, decrypted or revealed
+    #[error("Metadata encryption error: {0}")]
+    Encryption(String),
+
+    /// The caller stopped the operation, e.g. from a progress callback
+    #[error("Operation aborted: {0}")]
+    Aborted(String),
 }
 
 impl PersistError {
@@ -87,6 +130,24 @@ impl PersistError {
         Self::Validation(msg.into())
     }
 
+    /// Create a new metadata encryption error
+    pub fn encryption<S: Into<String>>(msg: S) -> Self {
+        Self::Encryption(msg.into())
+    }
+
+    /// Create a new error for an operation the caller stopped
+    pub fn aborted<S: Into<String>>(msg: S) -> Self {
+        Self::Aborted(msg.into())
+    }
+
+    /// Create a new throttling error with an optional server-provided retry delay
+    pub fn throttled<S: Into<String>>(msg: S, retry_after: Option<std::time::Duration>) -> Self {
+        Self::Throttled {
+            message: msg.into(),
+            retry_after,
+        }
+    }
+
     /// Create a new invalid format error
     pub fn invalid_format<S: Into<String>>(msg: S) -> Self {
         Self::InvalidFormat(msg.into())
@@ -128,6 +189,16 @@ impl PersistError {
         Self::S3AccessDenied { bucket }
     }
 
+    /// Create a new GCS not found error
+    pub fn gcs_not_found(bucket: String, key: String) -> Self {
+        Self::GcsNotFound { bucket, key }
+    }
+
+    /// Create a new GCS access denied error
+    pub fn gcs_access_denied(bucket: String, key: String) -> Self {
+        Self::GcsAccessDenied { bucket, key }
+    }
+
     /// Create a new S3 configuration error
     pub fn s3_configuration<S: Into<String>>(msg: S) -> Self {
         Self::S3Configuration(msg.into())
@@ -153,3 +224,234 @@ impl PersistError {
         Self::Io(enhanced_error)
     }
 }
+
+/// Message fragments that mark a storage failure as transient (network problems,
+/// throttling and server-side errors reported by S3 or GCS)
+const TRANSIENT_MESSAGE_PATTERNS: &[&str] = &[
+    // Network/timeout related errors
+    "timed out",
+    "timeout",
+    "dispatch",
+    "connection",
+    "network",
+    // Adapter-classified errors (e.g. GCS rate limits and server errors)
+    "transient error",
+    // AWS service errors that are retryable
+    "InternalError",
+    "ServiceUnavailable",
+    "SlowDown",
+    "RequestTimeout",
+    "ThrottledException",
+    "ProvisionedThroughputExceededException",
+    // HTTP status codes that indicate transient issues
+    "503",
+    "502",
+    "500",
+    "504",
+    "429",
+    "408",
+];
+
+/// Whether an I/O error kind indicates a condition that may clear on retry
+fn is_transient_io_kind(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::ConnectionRefused
+            | ErrorKind::NotConnected
+            | ErrorKind::BrokenPipe
+    )
+}
+
+fn is_transient_message(msg: &str) -> bool {
+    TRANSIENT_MESSAGE_PATTERNS
+        .iter()
+        .any(|pattern| msg.contains(pattern))
+}
+
+/// Whether an operation that failed with `error` may succeed if retried
+///
+/// This is the single classification used by all storage adapters. Data, format and
+/// validation errors are never transient; I/O errors are classified by kind, and
+/// storage errors by the network, throttling and server-error conditions they report.
+pub fn is_transient_error(error: &PersistError) -> bool {
+    match error {
+        PersistError::Io(err) => is_transient_io_kind(err.kind()),
+        PersistError::Storage(msg) => is_transient_message(msg),
+        PersistError::Throttled { .. } => true,
+        PersistError::S3UploadError { source, .. }
+        | PersistError::S3DownloadError { source, .. } => {
+            source
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|err| is_transient_io_kind(err.kind()))
+                || is_transient_message(&source.to_string())
+        }
+        PersistError::S3NotFound { .. }
+        | PersistError::S3AccessDenied { .. }
+        | PersistError::S3Configuration(_)
+        | PersistError::GcsNotFound { .. }
+        | PersistError::GcsAccessDenied { .. }
+        | PersistError::Json(_)
+        | PersistError::Compression(_)
+        | PersistError::IntegrityCheckFailed { .. }
+        | PersistError::InvalidFormat(_)
+        | PersistError::NeedsMigration { .. }
+        | PersistError::MissingMetadata(_)
+        | PersistError::Validation(_)
+        | PersistError::FrameworkMismatch { .. }
+        | PersistError::IndexOutOfOrder { .. }
+        | PersistError::Encryption(_)
+        | PersistError::Aborted(_) => false,
+    }
+}
+
+impl RetryableError for PersistError {
+    fn is_transient(&self) -> bool {
+        is_transient_error(self)
+    }
+
+    fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            PersistError::Throttled { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn io(kind: ErrorKind) -> std::io::Error {
+        std::io::Error::new(kind, "test")
+    }
+
+    #[test]
+    fn test_transient_classification() {
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let cases: Vec<(PersistError, bool)> = vec![
+            (PersistError::Io(io(ErrorKind::TimedOut)), true),
+            (PersistError::Io(io(ErrorKind::ConnectionReset)), true),
+            (PersistError::Io(io(ErrorKind::Interrupted)), true),
+            (PersistError::Io(io(ErrorKind::NotFound)), false),
+            (PersistError::Io(io(ErrorKind::PermissionDenied)), false),
+            (PersistError::Json(json_error), false),
+            (PersistError::compression("corrupt gzip stream"), false),
+            (
+                PersistError::IntegrityCheckFailed {
+                    expected: "a".to_string(),
+                    actual: "b".to_string(),
+                },
+                false,
+            ),
+            (PersistError::invalid_format("bad header"), false),
+            (
+                PersistError::NeedsMigration {
+                    found: "0.9".to_string(),
+                    current: "1.0".to_string(),
+                },
+                false,
+            ),
+            (PersistError::MissingMetadata("agent_id".to_string()), false),
+            (
+                PersistError::storage("S3 get_object request timed out (key: test)"),
+                true,
+            ),
+            (
+                PersistError::storage("S3 put_object request failed to dispatch"),
+                true,
+            ),
+            (
+                PersistError::storage("S3 service error (SlowDown): reduce your request rate"),
+                true,
+            ),
+            (
+                PersistError::storage("GCS rate limit exceeded for object 'k' (transient error)"),
+                true,
+            ),
+            (PersistError::storage("Access denied to S3"), false),
+            (
+                PersistError::throttled("S3 put_object throttled", None),
+                true,
+            ),
+            (PersistError::storage("GCS object not found: k"), false),
+            (
+                PersistError::gcs_not_found("bucket".to_string(), "k".to_string()),
+                false,
+            ),
+            (
+                PersistError::gcs_access_denied("bucket".to_string(), "k".to_string()),
+                false,
+            ),
+            (
+                PersistError::s3_upload_error(
+                    io(ErrorKind::TimedOut),
+                    "bucket".to_string(),
+                    "key".to_string(),
+                ),
+                true,
+            ),
+            (
+                PersistError::s3_download_error(
+                    std::io::Error::other("S3 service error (ServiceUnavailable): retry"),
+                    "bucket".to_string(),
+                    "key".to_string(),
+                ),
+                true,
+            ),
+            (
+                PersistError::s3_download_error(
+                    std::io::Error::other("S3 service error (InvalidObjectState): archived"),
+                    "bucket".to_string(),
+                    "key".to_string(),
+                ),
+                false,
+            ),
+            (
+                PersistError::s3_not_found("bucket".to_string(), "key".to_string()),
+                false,
+            ),
+            (PersistError::s3_access_denied("bucket".to_string()), false),
+            (PersistError::s3_configuration("bucket not found"), false),
+            (PersistError::validation("Invalid input"), false),
+            (
+                PersistError::FrameworkMismatch {
+                    expected: "langchain".to_string(),
+                    actual: "crewai".to_string(),
+                },
+                false,
+            ),
+            (
+                PersistError::IndexOutOfOrder {
+                    agent_id: "a".to_string(),
+                    session_id: "s".to_string(),
+                    index: 1,
+                    latest: 2,
+                },
+                false,
+            ),
+            (PersistError::encryption("wrong key"), false),
+            (PersistError::aborted("progress callback failed"), false),
+        ];
+
+        for (error, transient) in cases {
+            assert_eq!(is_transient_error(&error), transient, "{error}");
+            assert_eq!(error.is_transient(), transient, "{error}");
+            assert_eq!(error.is_permanent(), !transient, "{error}");
+        }
+    }
+
+    #[test]
+    fn test_throttled_retry_after() {
+        let hint = std::time::Duration::from_secs(2);
+        assert_eq!(
+            PersistError::throttled("slow down", Some(hint)).retry_after(),
+            Some(hint)
+        );
+        assert_eq!(PersistError::storage("timed out").retry_after(), None);
+    }
+}
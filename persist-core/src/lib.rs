@@ -43,38 +43,98 @@ let (restored_metadata, restored_data) = engine.load_snapshot("/path/to/snapshot
 
 pub mod compression;
 pub mod config;
+pub mod diff;
 pub mod error;
 pub mod metadata;
 #[cfg(test)]
 mod metadata_tests;
+pub mod migration;
 pub mod observability;
+pub mod query;
+pub mod retention;
+pub mod sensitive;
 pub mod snapshot;
 pub mod storage;
+pub mod testdata;
 
-pub use compression::{CompressionAdapter, GzipCompressor};
-pub use config::{StorageBackend, StorageConfig};
-pub use error::{PersistError, Result};
-pub use metadata::SnapshotMetadata;
+pub use compression::{
+    available_algorithms, compressor_for, detect_algorithm, CompressionAdapter, GzipCompressor,
+};
+
+#[cfg(feature = "zstd")]
+pub use compression::ZstdCompressor;
+#[allow(deprecated)]
+pub use config::LegacyStorageConfig;
+pub use config::{
+    CompressionConfig, ConfigFormat, EncryptionConfig, ObservabilitySettings, RetryConfig,
+    RetryJitter, S3Credentials, StorageBackend, StorageConfig, TimeoutConfig,
+};
+pub use diff::{diff_json, MetadataDiff, SnapshotDiff, StateChange};
+pub use error::{is_transient_error, PersistError, Result};
+pub use metadata::{
+    CompatibilityMode, ContentHash, HashAlgorithm, SnapshotMetadata, SnapshotMetadataBuilder,
+};
+pub use migration::MigrationReport;
+pub use persist_retry::{
+    JitterMode, Operation, OperationKind, RetryClassifier, RetryEvent, RetryPolicy,
+};
+pub use query::{SnapshotQuery, SnapshotSummary, SortOrder};
+pub use retention::{PrunePlan, RetentionPolicy};
+pub use sensitive::{MetadataCipher, SensitiveField};
 
 #[cfg(feature = "metrics")]
 pub use observability::{
-    init_default_observability, init_observability, MetricsTimer, PersistMetrics,
+    init_default_observability, init_observability, InFlightOperation, MetricsSnapshot,
+    MetricsTimer, OperationLabels, PersistMetrics,
+};
+pub use observability::{
+    init_observability_with, shutdown_observability, EventSink, FailureEventDetails, HealthReport,
+    HealthStatus, LabelKind, LogFormat, LogRotation, LogWriter, MemoryEventSink,
+    ObservabilityConfig, ObservabilityGuard, OtlpConfig, PersistEvent, SnapshotEventDetails,
+    TracingEventSink, EVENT_SCHEMA_VERSION,
 };
 
 pub use snapshot::{
-    create_default_engine, create_engine_from_config, SnapshotEngine, SnapshotEngineInterface,
+    copy_snapshot, create_default_engine, create_engine_from_config, create_storage_from_config,
+    Recompressed, SnapshotEngine, SnapshotEngineInterface, DEFAULT_HEALTH_CACHE_TTL,
 };
 
+#[cfg(feature = "metrics")]
+pub use snapshot::create_engine_from_config_with_metrics;
+
 #[cfg(feature = "s3")]
 pub use snapshot::create_s3_engine;
 
 #[cfg(feature = "gcs")]
 pub use snapshot::create_gcs_engine;
 
-pub use storage::{LocalFileStorage, StorageAdapter};
+pub use storage::{
+    available_backends, CallOptions, GarbageItem, LocalFileStorage, ProgressCallback,
+    RetryingStorage, StorageAdapter,
+};
 
 #[cfg(feature = "s3")]
 pub use storage::S3StorageAdapter;
 
 #[cfg(feature = "gcs")]
 pub use storage::GCSStorageAdapter;
+
+/// Version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Cargo features this build of the crate was compiled with
+pub fn enabled_features() -> Vec<&'static str> {
+    [
+        ("local", cfg!(feature = "local")),
+        ("s3", cfg!(feature = "s3")),
+        ("gcs", cfg!(feature = "gcs")),
+        ("async-rt", cfg!(feature = "async-rt")),
+        ("metrics", cfg!(feature = "metrics")),
+        ("otel", cfg!(feature = "otel")),
+        ("zstd", cfg!(feature = "zstd")),
+        ("cli", cfg!(feature = "cli")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
@@ -2,15 +2,253 @@
 Snapshot metadata management and schema definition.
 */
 
+use crate::sensitive::SensitiveField;
 use crate::{PersistError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
 
-/// Current metadata format version for compatibility tracking
+/// Current metadata format major version for compatibility tracking
+///
+/// Readers accept any snapshot with the same major version, regardless of minor version.
 pub const METADATA_FORMAT_VERSION: u8 = 1;
 
+/// Current metadata format minor version, bumped for additive (backward-compatible) changes
+pub const METADATA_FORMAT_MINOR_VERSION: u8 = 1;
+
+/// Oldest major format version that can still be migrated to the current format
+pub const MIN_MIGRATABLE_FORMAT_VERSION: u8 = 0;
+
+/// Maximum serialized size of [`SnapshotMetadata::extra`], in bytes
+pub const MAX_EXTRA_METADATA_BYTES: usize = 64 * 1024;
+
+/// Field names used by the core metadata schema
+///
+/// Keys in [`SnapshotMetadata::extra`] must not use these names. Future core fields
+/// are never namespaced, so extension keys should carry a namespace (`"myapp.key"` or
+/// `"x-key"`) to stay clear of fields added later.
+pub const RESERVED_METADATA_KEYS: &[&str] = &[
+    "agent_id",
+    "session_id",
+    "snapshot_index",
+    "timestamp",
+    "content_hash",
+    "format_version",
+    "format_minor_version",
+    "snapshot_id",
+    "description",
+    "uncompressed_size",
+    "compressed_size",
+    "compression_algorithm",
+    "framework",
+    "framework_version",
+    "state_schema_version",
+    "tags",
+    "parent_snapshot_id",
+    "expires_at",
+    "sensitive_fields",
+];
+
+/// Generate a new time-ordered snapshot id
+///
+/// Ids are UUIDv7 strings: lexicographic order matches creation order, including
+/// for ids generated in rapid succession within the same process.
+pub fn generate_snapshot_id() -> String {
+    Uuid::now_v7().to_string()
+}
+
+/// Extract the creation time embedded in a time-ordered snapshot id
+///
+/// Returns `None` for ids that don't embed a timestamp, such as the random
+/// (UUIDv4) ids written by older versions; those ids remain valid on load.
+///
+/// # Example
+/// ```rust
+/// use persist_core::metadata::{generate_snapshot_id, snapshot_id_timestamp};
+///
+/// let id = generate_snapshot_id();
+/// assert!(snapshot_id_timestamp(&id).is_some());
+/// assert!(snapshot_id_timestamp("0b9c2c9e-6c7a-4c35-9d1f-3f1e5b0a7d21").is_none());
+/// ```
+pub fn snapshot_id_timestamp(snapshot_id: &str) -> Option<DateTime<Utc>> {
+    let uuid = Uuid::parse_str(snapshot_id).ok()?;
+    if uuid.get_version() != Some(uuid::Version::SortRand) {
+        return None;
+    }
+    let (secs, nanos) = uuid.get_timestamp()?.to_unix();
+    DateTime::from_timestamp(i64::try_from(secs).ok()?, nanos)
+}
+
+/// Hash algorithms supported for snapshot content hashes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Name used as the `content_hash` prefix
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    /// Compute the lowercase hex digest of `data`
+    pub fn digest(&self, data: &[u8]) -> String {
+        match self {
+            HashAlgorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                hasher.update(data);
+                format!("{:x}", hasher.finalize())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = PersistError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            other => Err(PersistError::invalid_format(format!(
+                "Unknown content hash algorithm '{other}' (supported: sha256)"
+            ))),
+        }
+    }
+}
+
+/// An algorithm-labelled content hash, stored as `<algorithm>:<hex digest>`
+///
+/// # Example
+/// ```rust
+/// use persist_core::metadata::{ContentHash, HashAlgorithm};
+///
+/// let hash = ContentHash::compute(HashAlgorithm::Sha256, b"test data");
+/// let parsed = ContentHash::parse(&hash.format()).unwrap();
+/// assert_eq!(parsed, hash);
+///
+/// // Unprefixed values are legacy SHA-256 hex digests
+/// let legacy = ContentHash::parse(&hash.digest).unwrap();
+/// assert_eq!(legacy.algorithm, HashAlgorithm::Sha256);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentHash {
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+}
+
+impl ContentHash {
+    /// Hash `data` with the given algorithm
+    pub fn compute(algorithm: HashAlgorithm, data: &[u8]) -> Self {
+        Self {
+            algorithm,
+            digest: algorithm.digest(data),
+        }
+    }
+
+    /// Parse a stored `content_hash` value
+    ///
+    /// Values without an algorithm prefix are treated as legacy SHA-256 hex digests.
+    ///
+    /// # Errors
+    /// * `PersistError::InvalidFormat` - If the prefix names an unknown algorithm
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.split_once(':') {
+            Some((algorithm, digest)) => Ok(Self {
+                algorithm: algorithm.parse()?,
+                digest: digest.to_string(),
+            }),
+            None => Ok(Self {
+                algorithm: HashAlgorithm::Sha256,
+                digest: value.to_string(),
+            }),
+        }
+    }
+
+    /// Format as `<algorithm>:<hex digest>`
+    pub fn format(&self) -> String {
+        format!("{}:{}", self.algorithm.name(), self.digest)
+    }
+
+    /// Check whether `data` hashes to this value
+    pub fn matches(&self, data: &[u8]) -> bool {
+        self.algorithm
+            .digest(data)
+            .eq_ignore_ascii_case(&self.digest)
+    }
+}
+
+impl std::fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format())
+    }
+}
+
+/// Compatibility of a snapshot's format version with this reader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Same major version; can be read directly
+    Compatible,
+    /// Older major version that must be migrated before it can be read
+    NeedsMigration,
+    /// Newer major version, or too old to migrate
+    Unsupported,
+}
+
+impl Compatibility {
+    /// Classify a major format version against the current reader
+    pub fn of_version(format_version: u8) -> Self {
+        if format_version == METADATA_FORMAT_VERSION {
+            Compatibility::Compatible
+        } else if (MIN_MIGRATABLE_FORMAT_VERSION..METADATA_FORMAT_VERSION).contains(&format_version)
+        {
+            Compatibility::NeedsMigration
+        } else {
+            Compatibility::Unsupported
+        }
+    }
+}
+
+impl std::fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compatibility::Compatible => write!(f, "compatible"),
+            Compatibility::NeedsMigration => write!(f, "needs migration"),
+            Compatibility::Unsupported => write!(f, "unsupported"),
+        }
+    }
+}
+
+/// How the engine reacts to snapshots whose format version is not directly readable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompatibilityMode {
+    /// Reject incompatible snapshots with an error
+    #[default]
+    Strict,
+    /// Log a warning and load the snapshot on a best-effort basis
+    Warn,
+    /// Skip the format version check entirely (integrity is still verified)
+    Force,
+}
+
+impl std::str::FromStr for CompatibilityMode {
+    type Err = PersistError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "strict" => Ok(CompatibilityMode::Strict),
+            "warn" | "lenient" => Ok(CompatibilityMode::Warn),
+            "force" => Ok(CompatibilityMode::Force),
+            other => Err(PersistError::validation(format!(
+                "Unknown compatibility mode '{other}'. Expected one of: strict, warn, force"
+            ))),
+        }
+    }
+}
+
 /// Comprehensive metadata for each snapshot providing traceability and integrity verification
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SnapshotMetadata {
@@ -26,13 +264,20 @@ pub struct SnapshotMetadata {
     /// ISO 8601 timestamp when the snapshot was created
     pub timestamp: DateTime<Utc>,
 
-    /// SHA-256 hash of the agent state payload for integrity verification
+    /// Algorithm-prefixed hash of the agent state payload (`sha256:<hex>`); unprefixed
+    /// values written by older versions are SHA-256 hex digests
     pub content_hash: String,
 
-    /// Format version for compatibility (current: 1)
+    /// Major format version for compatibility (current: 1)
     pub format_version: u8,
 
+    /// Minor format version; differences within the same major version are additive
+    #[serde(default)]
+    pub format_minor_version: u8,
+
     /// Unique identifier for this specific snapshot
+    ///
+    /// New snapshots get a time-ordered UUIDv7; older snapshots may carry a random UUIDv4.
     pub snapshot_id: String,
 
     /// Optional human-readable description
@@ -46,6 +291,38 @@ pub struct SnapshotMetadata {
 
     /// Compression algorithm used
     pub compression_algorithm: String,
+
+    /// Agent framework that produced the state (e.g., "langchain", "llamaindex")
+    pub framework: Option<String>,
+
+    /// Version of the agent framework that produced the state
+    pub framework_version: Option<String>,
+
+    /// Application-defined version of the agent state schema
+    pub state_schema_version: Option<u32>,
+
+    /// Free-form key/value tags for organizing and filtering snapshots
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+
+    /// Snapshot ID of the snapshot this one was derived from, if any
+    pub parent_snapshot_id: Option<String>,
+
+    /// Time after which the snapshot may be removed by retention tooling
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Fields whose values are encrypted at rest (when a cipher is configured) and
+    /// redacted in display output
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub sensitive_fields: BTreeSet<SensitiveField>,
+
+    /// Fields not known to this version, preserved verbatim across load/save cycles
+    ///
+    /// Applications and newer writers may store additional top-level keys here. Keys
+    /// must not collide with [`RESERVED_METADATA_KEYS`] and should be namespaced; the
+    /// serialized size is capped at [`MAX_EXTRA_METADATA_BYTES`].
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl SnapshotMetadata {
@@ -76,14 +353,50 @@ impl SnapshotMetadata {
             timestamp: Utc::now(),
             content_hash: String::new(), // Will be set when computing hash
             format_version: METADATA_FORMAT_VERSION,
-            snapshot_id: Uuid::new_v4().to_string(),
+            format_minor_version: METADATA_FORMAT_MINOR_VERSION,
+            snapshot_id: generate_snapshot_id(),
             description: None,
             uncompressed_size: 0,  // Will be set when processing data
             compressed_size: None, // Will be set after compression
             compression_algorithm: "gzip".to_string(), // Default compression
+            framework: None,
+            framework_version: None,
+            state_schema_version: None,
+            tags: BTreeMap::new(),
+            parent_snapshot_id: None,
+            expires_at: None,
+            sensitive_fields: BTreeSet::new(),
+            extra: serde_json::Map::new(),
         }
     }
 
+    /// Start building metadata from the caller-settable fields
+    ///
+    /// Engine-computed fields (content hash, sizes, compression algorithm) are
+    /// filled in by `SnapshotEngine::save_snapshot`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use persist_core::SnapshotMetadata;
+    ///
+    /// let metadata = SnapshotMetadata::builder("agent_1", "session_1", 0)
+    ///     .description("Before tool call")
+    ///     .tag("env", "prod")
+    ///     .build();
+    /// assert_eq!(metadata.tags.get("env").map(String::as_str), Some("prod"));
+    /// ```
+    pub fn builder<S1, S2>(
+        agent_id: S1,
+        session_id: S2,
+        snapshot_index: u64,
+    ) -> SnapshotMetadataBuilder
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        SnapshotMetadataBuilder::new(agent_id, session_id, snapshot_index)
+    }
+
     /// Create metadata with all fields specified (useful for testing or custom scenarios)
     pub fn with_all_fields<S1, S2, S3, S4>(
         agent_id: S1,
@@ -106,11 +419,20 @@ impl SnapshotMetadata {
             timestamp: Utc::now(),
             content_hash: content_hash.into(),
             format_version: METADATA_FORMAT_VERSION,
-            snapshot_id: Uuid::new_v4().to_string(),
+            format_minor_version: METADATA_FORMAT_MINOR_VERSION,
+            snapshot_id: generate_snapshot_id(),
             description: None,
             uncompressed_size,
             compressed_size: None,
             compression_algorithm: compression_algorithm.into(),
+            framework: None,
+            framework_version: None,
+            state_schema_version: None,
+            tags: BTreeMap::new(),
+            parent_snapshot_id: None,
+            expires_at: None,
+            sensitive_fields: BTreeSet::new(),
+            extra: serde_json::Map::new(),
         }
     }
 
@@ -120,6 +442,32 @@ impl SnapshotMetadata {
         self
     }
 
+    /// Record the agent framework and its version
+    ///
+    /// # Example
+    /// ```rust
+    /// use persist_core::SnapshotMetadata;
+    ///
+    /// let metadata = SnapshotMetadata::new("agent_1", "session_1", 0)
+    ///     .with_framework("langchain", "0.3.1");
+    /// assert_eq!(metadata.framework.as_deref(), Some("langchain"));
+    /// ```
+    pub fn with_framework<S1, S2>(mut self, framework: S1, version: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.framework = Some(framework.into());
+        self.framework_version = Some(version.into());
+        self
+    }
+
+    /// Set the application-defined agent state schema version
+    pub fn with_state_schema_version(mut self, version: u32) -> Self {
+        self.state_schema_version = Some(version);
+        self
+    }
+
     /// Set the content hash from agent data
     ///
     /// # Arguments
@@ -127,24 +475,51 @@ impl SnapshotMetadata {
     ///
     /// # Returns
     /// Updated metadata with computed hash and uncompressed size
+    #[deprecated(
+        since = "0.1.0",
+        note = "the content hash is computed by SnapshotEngine::save_snapshot"
+    )]
     pub fn with_content_hash(mut self, agent_data: &[u8]) -> Self {
-        self.content_hash = Self::compute_hash(agent_data);
-        self.uncompressed_size = agent_data.len();
+        self.set_content_hash(agent_data);
         self
     }
 
     /// Set the compressed size after compression
+    #[deprecated(
+        since = "0.1.0",
+        note = "the compressed size is computed by SnapshotEngine::save_snapshot"
+    )]
     pub fn with_compressed_size(mut self, compressed_size: usize) -> Self {
-        self.compressed_size = Some(compressed_size);
+        self.set_compressed_size(compressed_size);
         self
     }
 
     /// Set the compression algorithm
+    #[deprecated(
+        since = "0.1.0",
+        note = "the compression algorithm is recorded by SnapshotEngine::save_snapshot"
+    )]
     pub fn with_compression_algorithm<S: Into<String>>(mut self, algorithm: S) -> Self {
-        self.compression_algorithm = algorithm.into();
+        self.set_compression_algorithm(algorithm);
         self
     }
 
+    /// Compute and record the content hash and uncompressed size of the agent data
+    pub(crate) fn set_content_hash(&mut self, agent_data: &[u8]) {
+        self.content_hash = ContentHash::compute(HashAlgorithm::Sha256, agent_data).format();
+        self.uncompressed_size = agent_data.len();
+    }
+
+    /// Record the size of the compressed snapshot
+    pub(crate) fn set_compressed_size(&mut self, compressed_size: usize) {
+        self.compressed_size = Some(compressed_size);
+    }
+
+    /// Record the compression algorithm used for the snapshot
+    pub(crate) fn set_compression_algorithm<S: Into<String>>(&mut self, algorithm: S) {
+        self.compression_algorithm = algorithm.into();
+    }
+
     /// Compute SHA-256 hash of the provided data
     ///
     /// # Arguments
@@ -153,26 +528,39 @@ impl SnapshotMetadata {
     /// # Returns
     /// Hexadecimal string representation of the SHA-256 hash
     pub fn compute_hash(data: &[u8]) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        format!("{:x}", hasher.finalize())
+        HashAlgorithm::Sha256.digest(data)
+    }
+
+    /// Parse the stored `content_hash`, treating unprefixed values as legacy SHA-256
+    pub fn parsed_content_hash(&self) -> Result<ContentHash> {
+        ContentHash::parse(&self.content_hash)
+    }
+
+    /// Content hash in its algorithm-prefixed form, falling back to the raw stored value
+    pub fn display_content_hash(&self) -> String {
+        self.parsed_content_hash()
+            .map(|hash| hash.format())
+            .unwrap_or_else(|_| self.content_hash.clone())
     }
 
     /// Verify the integrity of agent data against the stored hash
     ///
+    /// The algorithm is taken from the hash prefix; unprefixed hashes are legacy SHA-256.
+    ///
     /// # Arguments
     /// * `agent_data` - The agent data to verify
     ///
     /// # Returns
-    /// Ok(()) if the hash matches, Err(PersistError::IntegrityCheckFailed) otherwise
+    /// Ok(()) if the hash matches, Err(PersistError::IntegrityCheckFailed) otherwise,
+    /// or Err(PersistError::InvalidFormat) if the hash algorithm is unknown
     pub fn verify_integrity(&self, agent_data: &[u8]) -> Result<()> {
-        let computed_hash = Self::compute_hash(agent_data);
-        if computed_hash == self.content_hash {
+        let expected = self.parsed_content_hash()?;
+        if expected.matches(agent_data) {
             Ok(())
         } else {
             Err(PersistError::IntegrityCheckFailed {
-                expected: self.content_hash.clone(),
-                actual: computed_hash,
+                expected: expected.format(),
+                actual: ContentHash::compute(expected.algorithm, agent_data).format(),
             })
         }
     }
@@ -191,12 +579,101 @@ impl SnapshotMetadata {
         if self.snapshot_id.is_empty() {
             return Err(PersistError::validation("snapshot_id cannot be empty"));
         }
+        if let Some(key) = self
+            .extra
+            .keys()
+            .find(|key| RESERVED_METADATA_KEYS.contains(&key.as_str()))
+        {
+            return Err(PersistError::validation(format!(
+                "extra metadata key '{key}' is reserved for a core field"
+            )));
+        }
+        if !self.extra.is_empty() {
+            let size = serde_json::to_vec(&self.extra)?.len();
+            if size > MAX_EXTRA_METADATA_BYTES {
+                return Err(PersistError::validation(format!(
+                    "extra metadata is {size} bytes, exceeding the limit of {MAX_EXTRA_METADATA_BYTES} bytes"
+                )));
+            }
+        }
         Ok(())
     }
 
-    /// Check if this metadata is compatible with the current format version
+    /// Check if this metadata can be read directly by the current format version
     pub fn is_compatible(&self) -> bool {
-        self.format_version <= METADATA_FORMAT_VERSION
+        self.compatibility() == Compatibility::Compatible
+    }
+
+    /// Classify this metadata's format version against the current reader
+    ///
+    /// Snapshots with the same major version are compatible regardless of minor
+    /// version. Older major versions down to `MIN_MIGRATABLE_FORMAT_VERSION` need
+    /// migration; anything else is unsupported.
+    ///
+    /// # Example
+    /// ```rust
+    /// use persist_core::metadata::Compatibility;
+    /// use persist_core::SnapshotMetadata;
+    ///
+    /// let metadata = SnapshotMetadata::new("agent_1", "session_1", 0);
+    /// assert_eq!(metadata.compatibility(), Compatibility::Compatible);
+    /// ```
+    pub fn compatibility(&self) -> Compatibility {
+        Compatibility::of_version(self.format_version)
+    }
+
+    /// Unique identifier for this snapshot
+    pub fn snapshot_id(&self) -> &str {
+        &self.snapshot_id
+    }
+
+    /// Creation time embedded in the snapshot id, if it is time-ordered
+    pub fn snapshot_id_timestamp(&self) -> Option<DateTime<Utc>> {
+        snapshot_id_timestamp(&self.snapshot_id)
+    }
+
+    /// Format version as a "major.minor" string
+    pub fn format_version_string(&self) -> String {
+        format!("{}.{}", self.format_version, self.format_minor_version)
+    }
+
+    /// One-line summary suitable for log lines and listings
+    ///
+    /// Format: `{agent_id}/{session_id}#{snapshot_index} ({snapshot_id}, {timestamp}, {size} bytes)`
+    pub fn summary(&self) -> String {
+        format!(
+            "{}/{}#{} ({}, {}, {} bytes)",
+            self.agent_id,
+            self.session_id,
+            self.snapshot_index,
+            self.snapshot_id,
+            self.timestamp.to_rfc3339(),
+            self.uncompressed_size
+        )
+    }
+
+    /// Export every field as a JSON object, plus derived fields
+    ///
+    /// In addition to the serialized fields, the object includes `hash_algorithm`,
+    /// `format_version_string` and `compatibility`, so consumers don't need to
+    /// recompute them. `content_hash` is always in its algorithm-prefixed form.
+    pub fn to_json_value(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            if let Ok(hash) = self.parsed_content_hash() {
+                object.insert("content_hash".to_string(), hash.format().into());
+                object.insert("hash_algorithm".to_string(), hash.algorithm.name().into());
+            }
+            object.insert(
+                "format_version_string".to_string(),
+                self.format_version_string().into(),
+            );
+            object.insert(
+                "compatibility".to_string(),
+                self.compatibility().to_string().into(),
+            );
+        }
+        value
     }
 
     /// Generate a suggested filename for this snapshot
@@ -211,6 +688,326 @@ impl SnapshotMetadata {
     }
 }
 
+impl std::fmt::Display for SnapshotMetadata {
+    /// Stable multi-line summary listing every field; unset optional fields print as `-`
+    ///
+    /// Sensitive fields are redacted; use [`SnapshotMetadata::revealed`] to show them.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.sensitive_fields.is_empty() {
+            self.write_fields(f)
+        } else {
+            self.redacted().write_fields(f)
+        }
+    }
+}
+
+impl SnapshotMetadata {
+    /// Write the display lines for every field as stored, without redaction
+    pub(crate) fn write_fields(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn or_dash(value: Option<String>) -> String {
+            value.unwrap_or_else(|| "-".to_string())
+        }
+
+        let framework = self
+            .framework
+            .as_ref()
+            .map(|name| match &self.framework_version {
+                Some(version) => format!("{name} {version}"),
+                None => name.clone(),
+            });
+        let tags = if self.tags.is_empty() {
+            None
+        } else {
+            Some(
+                self.tags
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        };
+
+        writeln!(f, "Snapshot ID: {}", self.snapshot_id)?;
+        writeln!(f, "Agent ID: {}", self.agent_id)?;
+        writeln!(f, "Session ID: {}", self.session_id)?;
+        writeln!(f, "Index: {}", self.snapshot_index)?;
+        writeln!(f, "Created: {}", self.timestamp.to_rfc3339())?;
+        writeln!(
+            f,
+            "Format Version: {} ({})",
+            self.format_version_string(),
+            self.compatibility()
+        )?;
+        writeln!(f, "Content Hash: {}", self.display_content_hash())?;
+        writeln!(f, "Compression: {}", self.compression_algorithm)?;
+        writeln!(f, "Uncompressed Size: {} bytes", self.uncompressed_size)?;
+        writeln!(
+            f,
+            "Compressed Size: {}",
+            or_dash(self.compressed_size.map(|size| format!("{size} bytes")))
+        )?;
+        writeln!(f, "Description: {}", or_dash(self.description.clone()))?;
+        writeln!(f, "Framework: {}", or_dash(framework))?;
+        writeln!(
+            f,
+            "State Schema Version: {}",
+            or_dash(self.state_schema_version.map(|v| v.to_string()))
+        )?;
+        writeln!(f, "Parent: {}", or_dash(self.parent_snapshot_id.clone()))?;
+        writeln!(
+            f,
+            "Expires: {}",
+            or_dash(self.expires_at.map(|t| t.to_rfc3339()))
+        )?;
+        write!(f, "Tags: {}", or_dash(tags))
+    }
+}
+
+/// Builder for [`SnapshotMetadata`] exposing only the fields a caller may set
+///
+/// The agent ID, session ID and snapshot index are required up front; everything
+/// else is optional. Fields computed during save are not settable here.
+#[derive(Debug, Clone)]
+pub struct SnapshotMetadataBuilder {
+    agent_id: String,
+    session_id: String,
+    snapshot_index: u64,
+    description: Option<String>,
+    tags: BTreeMap<String, String>,
+    parent_snapshot_id: Option<String>,
+    ttl: Option<std::time::Duration>,
+    framework: Option<(String, String)>,
+    state_schema_version: Option<u32>,
+    sensitive_fields: BTreeSet<SensitiveField>,
+}
+
+impl SnapshotMetadataBuilder {
+    /// Create a builder with the required identifying fields
+    pub fn new<S1, S2>(agent_id: S1, session_id: S2, snapshot_index: u64) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Self {
+            agent_id: agent_id.into(),
+            session_id: session_id.into(),
+            snapshot_index,
+            description: None,
+            tags: BTreeMap::new(),
+            parent_snapshot_id: None,
+            ttl: None,
+            framework: None,
+            state_schema_version: None,
+            sensitive_fields: BTreeSet::new(),
+        }
+    }
+
+    /// Set a human-readable description
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add a single key/value tag
+    pub fn tag<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add several key/value tags at once
+    pub fn tags<I, K, V>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.tags
+            .extend(tags.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Record the snapshot this one was derived from
+    pub fn parent<S: Into<String>>(mut self, parent_snapshot_id: S) -> Self {
+        self.parent_snapshot_id = Some(parent_snapshot_id.into());
+        self
+    }
+
+    /// Mark the snapshot as expiring `ttl` after its creation time
+    pub fn ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Record the agent framework and its version
+    pub fn framework<S1: Into<String>, S2: Into<String>>(mut self, name: S1, version: S2) -> Self {
+        self.framework = Some((name.into(), version.into()));
+        self
+    }
+
+    /// Set the application-defined agent state schema version
+    pub fn state_schema_version(mut self, version: u32) -> Self {
+        self.state_schema_version = Some(version);
+        self
+    }
+
+    /// Mark a field as sensitive: encrypted at rest when the engine has a metadata
+    /// cipher, and redacted in display output
+    pub fn sensitive(mut self, field: SensitiveField) -> Self {
+        self.sensitive_fields.insert(field);
+        self
+    }
+
+    /// Build the metadata, stamping the creation time and a fresh snapshot ID
+    pub fn build(self) -> SnapshotMetadata {
+        let mut metadata =
+            SnapshotMetadata::new(self.agent_id, self.session_id, self.snapshot_index);
+        metadata.description = self.description;
+        metadata.tags = self.tags;
+        metadata.parent_snapshot_id = self.parent_snapshot_id;
+        metadata.expires_at = self
+            .ttl
+            .and_then(|ttl| chrono::Duration::from_std(ttl).ok())
+            .and_then(|ttl| metadata.timestamp.checked_add_signed(ttl));
+        if let Some((name, version)) = self.framework {
+            metadata.framework = Some(name);
+            metadata.framework_version = Some(version);
+        }
+        metadata.state_schema_version = self.state_schema_version;
+        metadata.sensitive_fields = self.sensitive_fields;
+        metadata
+    }
+}
+
+/// Policy used to decide whether a snapshot's framework version is acceptable on restore
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameworkCompatPolicy {
+    /// Do not check the framework at all
+    #[default]
+    Ignore,
+    /// Require the same framework name, any version
+    SameFramework,
+    /// Require the same framework and the same major version
+    SameMajor,
+    /// Require the same framework and the same major.minor version
+    SameMinor,
+    /// Require the same framework and an identical version string
+    Exact,
+}
+
+impl std::str::FromStr for FrameworkCompatPolicy {
+    type Err = PersistError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ignore" | "none" => Ok(Self::Ignore),
+            "framework" | "same_framework" => Ok(Self::SameFramework),
+            "major" | "same_major" => Ok(Self::SameMajor),
+            "minor" | "same_minor" => Ok(Self::SameMinor),
+            "exact" => Ok(Self::Exact),
+            other => Err(PersistError::validation(format!(
+                "Invalid framework compatibility policy '{other}'. Must be one of: ignore, framework, major, minor, exact"
+            ))),
+        }
+    }
+}
+
+/// Framework expected by the code restoring a snapshot, plus the policy used to compare it
+///
+/// # Example
+/// ```rust
+/// use persist_core::metadata::{FrameworkCompatPolicy, FrameworkRequirement};
+/// use persist_core::SnapshotMetadata;
+///
+/// let requirement = FrameworkRequirement::new("langchain", "0.3.4", FrameworkCompatPolicy::SameMinor);
+/// let metadata = SnapshotMetadata::new("agent_1", "session_1", 0).with_framework("langchain", "0.3.1");
+/// assert!(requirement.check(&metadata).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameworkRequirement {
+    /// Expected framework name
+    pub framework: String,
+    /// Installed framework version
+    pub version: String,
+    /// How strictly the versions must match
+    pub policy: FrameworkCompatPolicy,
+}
+
+impl FrameworkRequirement {
+    /// Create a new framework requirement
+    pub fn new<S1, S2>(framework: S1, version: S2, policy: FrameworkCompatPolicy) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Self {
+            framework: framework.into(),
+            version: version.into(),
+            policy,
+        }
+    }
+
+    /// Check a snapshot's metadata against this requirement
+    ///
+    /// Snapshots that do not record a framework (e.g., written by older versions)
+    /// are accepted, since there is nothing to compare against.
+    ///
+    /// # Returns
+    /// Ok(()) if compatible, Err(PersistError::FrameworkMismatch) otherwise
+    pub fn check(&self, metadata: &SnapshotMetadata) -> Result<()> {
+        if self.policy == FrameworkCompatPolicy::Ignore {
+            return Ok(());
+        }
+
+        let Some(framework) = metadata.framework.as_deref() else {
+            return Ok(());
+        };
+
+        let snapshot_version = metadata.framework_version.as_deref().unwrap_or("");
+        let mismatch = || PersistError::FrameworkMismatch {
+            expected: format!("{} {}", self.framework, self.version),
+            actual: format!("{framework} {snapshot_version}")
+                .trim_end()
+                .to_string(),
+        };
+
+        if !framework.eq_ignore_ascii_case(&self.framework) {
+            return Err(mismatch());
+        }
+
+        let compatible = match self.policy {
+            FrameworkCompatPolicy::Ignore | FrameworkCompatPolicy::SameFramework => true,
+            FrameworkCompatPolicy::SameMajor => {
+                version_prefix(snapshot_version, 1) == version_prefix(&self.version, 1)
+            }
+            FrameworkCompatPolicy::SameMinor => {
+                version_prefix(snapshot_version, 2) == version_prefix(&self.version, 2)
+            }
+            FrameworkCompatPolicy::Exact => snapshot_version == self.version,
+        };
+
+        if compatible {
+            Ok(())
+        } else {
+            Err(mismatch())
+        }
+    }
+}
+
+/// Extract the first `parts` numeric components of a dotted version string
+fn version_prefix(version: &str, parts: usize) -> Vec<u64> {
+    version
+        .split(['.', '-', '+'])
+        .take(parts)
+        .map(|part| {
+            part.chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,7 +1037,8 @@ mod tests {
     #[test]
     fn test_integrity_verification() {
         let data = b"test data";
-        let metadata = SnapshotMetadata::new("agent", "session", 0).with_content_hash(data);
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.set_content_hash(data);
 
         // Should pass with same data
         assert!(metadata.verify_integrity(data).is_ok());
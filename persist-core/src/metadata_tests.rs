@@ -4,7 +4,12 @@ Comprehensive tests for metadata functionality including edge cases and error co
 
 #[cfg(test)]
 mod tests {
-    use crate::metadata::SnapshotMetadata;
+    use crate::metadata::{
+        generate_snapshot_id, snapshot_id_timestamp, Compatibility, ContentHash,
+        FrameworkCompatPolicy, FrameworkRequirement, HashAlgorithm, SnapshotMetadata,
+        SnapshotMetadataBuilder, MAX_EXTRA_METADATA_BYTES, METADATA_FORMAT_VERSION,
+    };
+    use crate::PersistError;
 
     #[test]
     fn test_metadata_creation() {
@@ -212,4 +217,389 @@ mod tests {
             assert_eq!(metadata.snapshot_index, i as u64);
         }
     }
+
+    #[test]
+    fn test_metadata_framework_fields() {
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        assert!(metadata.framework.is_none());
+        assert!(metadata.framework_version.is_none());
+        assert!(metadata.state_schema_version.is_none());
+
+        let metadata = metadata
+            .with_framework("langchain", "0.3.1")
+            .with_state_schema_version(2);
+        assert_eq!(metadata.framework.as_deref(), Some("langchain"));
+        assert_eq!(metadata.framework_version.as_deref(), Some("0.3.1"));
+        assert_eq!(metadata.state_schema_version, Some(2));
+
+        let json = serde_json::to_string(&metadata).unwrap();
+        let deserialized: SnapshotMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(metadata, deserialized);
+    }
+
+    #[test]
+    fn test_metadata_deserializes_without_framework_fields() {
+        // Metadata written before framework tracking existed
+        let legacy = r#"{
+            "agent_id": "agent",
+            "session_id": "session",
+            "snapshot_index": 3,
+            "timestamp": "2024-01-01T00:00:00Z",
+            "content_hash": "abc",
+            "format_version": 1,
+            "snapshot_id": "id",
+            "description": null,
+            "uncompressed_size": 10,
+            "compressed_size": null,
+            "compression_algorithm": "gzip"
+        }"#;
+
+        let metadata: SnapshotMetadata = serde_json::from_str(legacy).unwrap();
+        assert_eq!(metadata.snapshot_index, 3);
+        assert!(metadata.framework.is_none());
+        assert!(metadata.framework_version.is_none());
+        assert!(metadata.state_schema_version.is_none());
+    }
+
+    #[test]
+    fn test_framework_policy_enforcement() {
+        let metadata =
+            SnapshotMetadata::new("agent", "session", 0).with_framework("langchain", "0.1.20");
+
+        let check = |framework: &str, version: &str, policy| {
+            FrameworkRequirement::new(framework, version, policy).check(&metadata)
+        };
+
+        assert!(check("llamaindex", "0.1.20", FrameworkCompatPolicy::Ignore).is_ok());
+        assert!(check("langchain", "9.9.9", FrameworkCompatPolicy::SameFramework).is_ok());
+        assert!(matches!(
+            check("llamaindex", "0.1.20", FrameworkCompatPolicy::SameFramework),
+            Err(PersistError::FrameworkMismatch { .. })
+        ));
+        assert!(check("langchain", "0.3.0", FrameworkCompatPolicy::SameMajor).is_ok());
+        assert!(check("langchain", "1.0.0", FrameworkCompatPolicy::SameMajor).is_err());
+        assert!(check("langchain", "0.1.5", FrameworkCompatPolicy::SameMinor).is_ok());
+        assert!(check("langchain", "0.3.0", FrameworkCompatPolicy::SameMinor).is_err());
+        assert!(check("langchain", "0.1.20", FrameworkCompatPolicy::Exact).is_ok());
+        assert!(check("langchain", "0.1.21", FrameworkCompatPolicy::Exact).is_err());
+    }
+
+    #[test]
+    fn test_framework_policy_accepts_untagged_snapshots() {
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        let requirement =
+            FrameworkRequirement::new("langchain", "0.3.0", FrameworkCompatPolicy::Exact);
+        assert!(requirement.check(&metadata).is_ok());
+    }
+
+    #[test]
+    fn test_framework_policy_from_str() {
+        assert_eq!(
+            "minor".parse::<FrameworkCompatPolicy>().unwrap(),
+            FrameworkCompatPolicy::SameMinor
+        );
+        assert_eq!(
+            "EXACT".parse::<FrameworkCompatPolicy>().unwrap(),
+            FrameworkCompatPolicy::Exact
+        );
+        assert!("loose".parse::<FrameworkCompatPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_builder_matches_legacy_construction() {
+        let legacy = SnapshotMetadata::new("agent", "session", 7).with_description("checkpoint");
+        let mut built = SnapshotMetadata::builder("agent", "session", 7)
+            .description("checkpoint")
+            .build();
+
+        // Creation time and snapshot ID are generated per call
+        built.timestamp = legacy.timestamp;
+        built.snapshot_id = legacy.snapshot_id.clone();
+
+        assert_eq!(
+            serde_json::to_string(&legacy).unwrap(),
+            serde_json::to_string(&built).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_builder_optional_fields() {
+        let metadata = SnapshotMetadataBuilder::new("agent", "session", 1)
+            .tag("env", "prod")
+            .tags([("team", "search"), ("env", "staging")])
+            .parent("parent-id")
+            .ttl(std::time::Duration::from_secs(3600))
+            .framework("langchain", "0.3.1")
+            .state_schema_version(4)
+            .build();
+
+        assert_eq!(metadata.tags.len(), 2);
+        assert_eq!(metadata.tags["env"], "staging");
+        assert_eq!(metadata.tags["team"], "search");
+        assert_eq!(metadata.parent_snapshot_id.as_deref(), Some("parent-id"));
+        assert_eq!(
+            metadata.expires_at,
+            Some(metadata.timestamp + chrono::Duration::hours(1))
+        );
+        assert_eq!(metadata.framework.as_deref(), Some("langchain"));
+        assert_eq!(metadata.framework_version.as_deref(), Some("0.3.1"));
+        assert_eq!(metadata.state_schema_version, Some(4));
+
+        // Engine-computed fields are left for save_snapshot to fill in
+        assert!(metadata.content_hash.is_empty());
+        assert!(metadata.compressed_size.is_none());
+    }
+
+    #[test]
+    fn test_compatibility_same_major_any_minor() {
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        assert_eq!(metadata.compatibility(), Compatibility::Compatible);
+
+        metadata.format_minor_version = 0;
+        assert_eq!(metadata.compatibility(), Compatibility::Compatible);
+
+        metadata.format_minor_version = 250;
+        assert_eq!(metadata.compatibility(), Compatibility::Compatible);
+        assert!(metadata.is_compatible());
+    }
+
+    #[test]
+    fn test_compatibility_older_major_needs_migration() {
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.format_version = METADATA_FORMAT_VERSION - 1;
+        metadata.format_minor_version = 3;
+
+        assert_eq!(metadata.compatibility(), Compatibility::NeedsMigration);
+        assert!(!metadata.is_compatible());
+        assert_eq!(
+            metadata.format_version_string(),
+            format!("{}.3", METADATA_FORMAT_VERSION - 1)
+        );
+    }
+
+    #[test]
+    fn test_compatibility_newer_major_unsupported() {
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.format_version = METADATA_FORMAT_VERSION + 1;
+
+        assert_eq!(metadata.compatibility(), Compatibility::Unsupported);
+        assert!(!metadata.is_compatible());
+    }
+
+    #[test]
+    fn test_missing_minor_version_defaults_to_zero() {
+        let mut value = serde_json::to_value(SnapshotMetadata::new("agent", "session", 0)).unwrap();
+        value
+            .as_object_mut()
+            .unwrap()
+            .remove("format_minor_version");
+
+        let metadata: SnapshotMetadata = serde_json::from_value(value).unwrap();
+        assert_eq!(metadata.format_minor_version, 0);
+        assert_eq!(metadata.compatibility(), Compatibility::Compatible);
+    }
+
+    fn fixed_metadata() -> SnapshotMetadata {
+        let mut metadata = SnapshotMetadata::builder("agent", "session", 3)
+            .description("nightly")
+            .tag("env", "prod")
+            .tag("owner", "ops")
+            .parent("parent-id")
+            .framework("langchain", "0.2.1")
+            .state_schema_version(2)
+            .build();
+        metadata.snapshot_id = "snap-id".to_string();
+        metadata.timestamp = chrono::DateTime::parse_from_rfc3339("2024-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        metadata.content_hash = "abc123".to_string();
+        metadata.uncompressed_size = 42;
+        metadata.compressed_size = Some(20);
+        metadata
+    }
+
+    #[test]
+    fn test_display_lists_every_field() {
+        let expected = format!(
+            "Snapshot ID: snap-id
+Agent ID: agent
+Session ID: session
+Index: 3
+Created: 2024-01-02T03:04:05+00:00
+Format Version: {}.{} (compatible)
+Content Hash: sha256:abc123
+Compression: gzip
+Uncompressed Size: 42 bytes
+Compressed Size: 20 bytes
+Description: nightly
+Framework: langchain 0.2.1
+State Schema Version: 2
+Parent: parent-id
+Expires: -
+Tags: env=prod, owner=ops",
+            METADATA_FORMAT_VERSION,
+            crate::metadata::METADATA_FORMAT_MINOR_VERSION
+        );
+
+        assert_eq!(fixed_metadata().to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_unset_fields_use_dash() {
+        let output = SnapshotMetadata::new("agent", "session", 0).to_string();
+
+        assert!(output.contains("Description: -"));
+        assert!(output.contains("Framework: -"));
+        assert!(output.contains("Parent: -"));
+        assert!(output.ends_with("Tags: -"));
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            fixed_metadata().summary(),
+            "agent/session#3 (snap-id, 2024-01-02T03:04:05+00:00, 42 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_to_json_value_includes_every_field() {
+        let metadata = fixed_metadata();
+        let value = metadata.to_json_value();
+        let object = value.as_object().unwrap();
+
+        for field in serde_json::to_value(&metadata)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+        {
+            assert!(object.contains_key(field), "missing field {field}");
+        }
+        assert_eq!(object["hash_algorithm"], "sha256");
+        assert_eq!(object["compatibility"], "compatible");
+        assert_eq!(object["tags"]["env"], "prod");
+        assert_eq!(object["parent_snapshot_id"], "parent-id");
+        assert_eq!(object["compressed_size"], 20);
+    }
+
+    #[test]
+    fn test_snapshot_ids_are_monotonic() {
+        let ids: Vec<String> = (0..10_000).map(|_| generate_snapshot_id()).collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_snapshot_id_timestamp_roundtrip() {
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        let embedded = metadata.snapshot_id_timestamp().unwrap();
+
+        assert_eq!(metadata.snapshot_id(), metadata.snapshot_id);
+        assert!((metadata.timestamp - embedded).num_milliseconds().abs() < 1000);
+        assert_eq!(
+            uuid::Uuid::parse_str(metadata.snapshot_id())
+                .unwrap()
+                .to_string(),
+            metadata.snapshot_id
+        );
+    }
+
+    #[test]
+    fn test_legacy_snapshot_id_accepted() {
+        let mut value = serde_json::to_value(SnapshotMetadata::new("agent", "session", 0)).unwrap();
+        value["snapshot_id"] = "0b9c2c9e-6c7a-4c35-9d1f-3f1e5b0a7d21".into();
+        value["content_hash"] = "abc".into();
+
+        let metadata: SnapshotMetadata = serde_json::from_value(value).unwrap();
+        assert!(metadata.validate().is_ok());
+        assert_eq!(metadata.snapshot_id_timestamp(), None);
+        assert_eq!(snapshot_id_timestamp("not-a-uuid"), None);
+    }
+
+    #[test]
+    fn test_content_hash_is_prefixed_and_roundtrips() {
+        let data = b"agent state";
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.set_content_hash(data);
+
+        assert!(metadata.content_hash.starts_with("sha256:"));
+        let parsed = ContentHash::parse(&metadata.content_hash).unwrap();
+        assert_eq!(parsed.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(parsed.format(), metadata.content_hash);
+        assert!(metadata.verify_integrity(data).is_ok());
+    }
+
+    #[test]
+    fn test_legacy_unprefixed_hash_still_verifies() {
+        let data = b"agent state";
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.content_hash = SnapshotMetadata::compute_hash(data);
+
+        assert!(metadata.verify_integrity(data).is_ok());
+        assert_eq!(
+            metadata.display_content_hash(),
+            format!("sha256:{}", metadata.content_hash)
+        );
+
+        match metadata.verify_integrity(b"tampered") {
+            Err(PersistError::IntegrityCheckFailed { expected, actual }) => {
+                assert!(expected.starts_with("sha256:"));
+                assert!(actual.starts_with("sha256:"));
+            }
+            other => panic!("expected integrity failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_hash_algorithm_errors() {
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.content_hash = "blake3:abcdef".to_string();
+
+        let err = metadata.verify_integrity(b"data").unwrap_err();
+        assert!(matches!(err, PersistError::InvalidFormat(_)));
+        assert!(err.to_string().contains("blake3"));
+        assert!(ContentHash::parse("md5:abc").is_err());
+    }
+
+    #[test]
+    fn test_unknown_fields_roundtrip() {
+        let mut metadata = SnapshotMetadata::new("agent", "session", 3);
+        metadata.content_hash = SnapshotMetadata::compute_hash(b"data");
+        let mut original = serde_json::to_value(metadata).unwrap();
+        let object = original.as_object_mut().unwrap();
+        object.insert("x-origin".to_string(), serde_json::json!("replica-2"));
+        object.insert(
+            "myapp.lineage".to_string(),
+            serde_json::json!({"runs": [1, 2, 3], "nested": {"ok": true}}),
+        );
+
+        let metadata: SnapshotMetadata = serde_json::from_value(original.clone()).unwrap();
+        assert_eq!(metadata.extra.len(), 2);
+        assert_eq!(metadata.extra["x-origin"], "replica-2");
+        assert!(metadata.validate().is_ok());
+
+        let reserialized = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(reserialized, original);
+    }
+
+    #[test]
+    fn test_extra_metadata_limits() {
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.content_hash = SnapshotMetadata::compute_hash(b"data");
+
+        metadata.extra.insert(
+            "x-blob".to_string(),
+            "a".repeat(MAX_EXTRA_METADATA_BYTES).into(),
+        );
+        let err = metadata.validate().unwrap_err();
+        assert!(err.to_string().contains("exceeding the limit"));
+
+        metadata.extra.clear();
+        metadata
+            .extra
+            .insert("description".to_string(), "shadow".into());
+        let err = metadata.validate().unwrap_err();
+        assert!(err.to_string().contains("reserved"));
+    }
 }
@@ -0,0 +1,254 @@
+/*!
+Format-version migrations for stored snapshots.
+
+Each [`MigrationStep`] rewrites a snapshot container, as raw JSON, from one major
+format version to the next. [`migrate_container`] runs the chain from the version a
+snapshot was written with up to a target version, which is normally
+[`METADATA_FORMAT_VERSION`]. Snapshots already at the target major version are left
+alone, whatever their minor version, since minor versions only add optional fields.
+
+Engines apply the chain to stored snapshots with
+[`SnapshotEngine::migrate_snapshot`](crate::SnapshotEngine::migrate_snapshot).
+*/
+
+use crate::metadata::{
+    ContentHash, METADATA_FORMAT_MINOR_VERSION, METADATA_FORMAT_VERSION,
+    MIN_MIGRATABLE_FORMAT_VERSION,
+};
+use crate::{PersistError, Result};
+use serde_json::{Map, Value};
+
+/// One link of the migration chain, from major version `from` to `from + 1`
+pub struct MigrationStep {
+    /// Major format version the step reads
+    pub from: u8,
+    /// Short description of what the step changes
+    pub description: &'static str,
+    /// Rewrite the container's metadata; the version fields are stamped afterwards
+    apply: fn(&mut Map<String, Value>, &Value) -> Result<()>,
+}
+
+/// Every known migration step, ordered by source version
+pub const MIGRATIONS: &[MigrationStep] = &[MigrationStep {
+    from: 0,
+    description:
+        "prefix the content hash with its algorithm and fill in snapshot id, size and compression",
+    apply: migrate_v0_to_v1,
+}];
+
+/// Outcome of migrating one snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Format version the snapshot was stored with, as "major.minor"
+    pub from: String,
+    /// Format version after migration, as "major.minor"
+    pub to: String,
+    /// Whether the snapshot was (or, in a dry run, would be) rewritten
+    pub migrated: bool,
+}
+
+/// Steps leading from major version `from` to `to`
+///
+/// # Errors
+/// * `PersistError::Validation` - If `to` is older than `from` (a downgrade), newer
+///   than the current format, or no step covers part of the range
+pub fn migration_path(from: u8, to: u8) -> Result<Vec<&'static MigrationStep>> {
+    if to > METADATA_FORMAT_VERSION {
+        return Err(PersistError::validation(format!(
+            "Unknown target format version {to} (current: {METADATA_FORMAT_VERSION})"
+        )));
+    }
+    if from > to {
+        return Err(PersistError::validation(format!(
+            "Refusing to downgrade snapshot from format version {from} to {to}"
+        )));
+    }
+    if !(MIN_MIGRATABLE_FORMAT_VERSION..).contains(&from) {
+        return Err(PersistError::validation(format!(
+            "Format version {from} is too old to migrate (oldest supported: {MIN_MIGRATABLE_FORMAT_VERSION})"
+        )));
+    }
+
+    (from..to)
+        .map(|version| {
+            MIGRATIONS
+                .iter()
+                .find(|step| step.from == version)
+                .ok_or_else(|| {
+                    PersistError::validation(format!(
+                        "No migration from format version {version} to {}",
+                        version + 1
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Migrate a parsed snapshot container to major version `to`, in place
+///
+/// Containers already at `to` are not modified and are reported with
+/// `migrated: false`. The content hash is not checked here; callers verify the
+/// result before storing it.
+pub fn migrate_container(container: &mut Value, to: u8) -> Result<MigrationReport> {
+    let (agent_state, metadata) = match container {
+        Value::Object(fields) => {
+            let agent_state = fields.get("agent_state").cloned().unwrap_or(Value::Null);
+            match fields.get_mut("metadata") {
+                Some(Value::Object(metadata)) => (agent_state, metadata),
+                _ => {
+                    return Err(PersistError::invalid_format(
+                        "Snapshot container has no metadata object",
+                    ))
+                }
+            }
+        }
+        _ => {
+            return Err(PersistError::invalid_format(
+                "Snapshot container is not a JSON object",
+            ))
+        }
+    };
+
+    let from = version_field(metadata, "format_version")?
+        .ok_or_else(|| PersistError::invalid_format("Snapshot metadata has no format_version"))?;
+    let from_minor = version_field(metadata, "format_minor_version")?.unwrap_or(0);
+    let from_string = format!("{from}.{from_minor}");
+
+    let steps = migration_path(from, to)?;
+    if steps.is_empty() {
+        return Ok(MigrationReport {
+            to: from_string.clone(),
+            from: from_string,
+            migrated: false,
+        });
+    }
+
+    for step in steps {
+        (step.apply)(metadata, &agent_state)?;
+    }
+
+    let to_minor = if to == METADATA_FORMAT_VERSION {
+        METADATA_FORMAT_MINOR_VERSION
+    } else {
+        0
+    };
+    metadata.insert("format_version".to_string(), to.into());
+    metadata.insert("format_minor_version".to_string(), to_minor.into());
+
+    Ok(MigrationReport {
+        from: from_string,
+        to: format!("{to}.{to_minor}"),
+        migrated: true,
+    })
+}
+
+/// Read a version number field, if present
+fn version_field(metadata: &Map<String, Value>, name: &str) -> Result<Option<u8>> {
+    match metadata.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .and_then(|version| u8::try_from(version).ok())
+            .map(Some)
+            .ok_or_else(|| PersistError::invalid_format(format!("Invalid {name}: {value}"))),
+    }
+}
+
+/// Version 0 stored bare SHA-256 hex digests and predates snapshot ids and the
+/// size and compression fields
+fn migrate_v0_to_v1(metadata: &mut Map<String, Value>, agent_state: &Value) -> Result<()> {
+    let content_hash = metadata
+        .get("content_hash")
+        .and_then(Value::as_str)
+        .ok_or_else(|| PersistError::invalid_format("Snapshot metadata has no content_hash"))?;
+    let content_hash = ContentHash::parse(content_hash)?.format();
+    metadata.insert("content_hash".to_string(), content_hash.into());
+
+    if !metadata.contains_key("snapshot_id") {
+        metadata.insert(
+            "snapshot_id".to_string(),
+            crate::metadata::generate_snapshot_id().into(),
+        );
+    }
+    if !metadata.contains_key("uncompressed_size") {
+        let size = serde_json::to_string(agent_state)
+            .map_err(PersistError::Json)?
+            .len();
+        metadata.insert("uncompressed_size".to_string(), size.into());
+    }
+    // Placeholder; the engine records the algorithm the snapshot is rewritten with
+    metadata
+        .entry("compression_algorithm")
+        .or_insert_with(|| "gzip".into());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::HashAlgorithm;
+    use serde_json::json;
+
+    fn v0_container() -> Value {
+        let agent_state = json!({"type": "test_agent", "memory": ["hi"]});
+        let hash = ContentHash::compute(HashAlgorithm::Sha256, agent_state.to_string().as_bytes());
+        json!({
+            "metadata": {
+                "agent_id": "agent",
+                "session_id": "session",
+                "snapshot_index": 3,
+                "timestamp": "2023-06-01T00:00:00Z",
+                "content_hash": hash.digest,
+                "format_version": 0,
+            },
+            "agent_state": agent_state,
+        })
+    }
+
+    #[test]
+    fn test_migrate_v0_container() {
+        let mut container = v0_container();
+        let report = migrate_container(&mut container, METADATA_FORMAT_VERSION).unwrap();
+
+        assert!(report.migrated);
+        assert_eq!(report.from, "0.0");
+        assert_eq!(
+            report.to,
+            format!("{METADATA_FORMAT_VERSION}.{METADATA_FORMAT_MINOR_VERSION}")
+        );
+
+        let metadata: crate::SnapshotMetadata =
+            serde_json::from_value(container["metadata"].clone()).unwrap();
+        assert_eq!(metadata.format_version, METADATA_FORMAT_VERSION);
+        assert!(metadata.content_hash.starts_with("sha256:"));
+        assert!(metadata.snapshot_id_timestamp().is_some());
+        metadata
+            .verify_integrity(container["agent_state"].to_string().as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_current_container_is_untouched() {
+        let mut container = v0_container();
+        container["metadata"]["format_version"] = METADATA_FORMAT_VERSION.into();
+        let before = container.clone();
+
+        let report = migrate_container(&mut container, METADATA_FORMAT_VERSION).unwrap();
+        assert!(!report.migrated);
+        assert_eq!(container, before);
+    }
+
+    #[test]
+    fn test_migration_path_rejects_downgrade_and_unknown_versions() {
+        assert!(migration_path(METADATA_FORMAT_VERSION, 0).is_err());
+        assert!(migration_path(0, METADATA_FORMAT_VERSION + 1).is_err());
+        assert_eq!(
+            migration_path(0, METADATA_FORMAT_VERSION).unwrap().len(),
+            usize::from(METADATA_FORMAT_VERSION)
+        );
+
+        let mut container = v0_container();
+        container["metadata"]["format_version"] = (METADATA_FORMAT_VERSION + 1).into();
+        assert!(migrate_container(&mut container, METADATA_FORMAT_VERSION).is_err());
+    }
+}
@@ -7,15 +7,41 @@ This module provides comprehensive observability features including:
 - Trace exporters (Jaeger, console)
 */
 
+#[cfg(feature = "otel")]
+use opentelemetry::trace::TracerProvider as _;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider, TracerProviderBuilder};
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::Resource;
 #[cfg(feature = "metrics")]
-use prometheus::{Counter, Encoder, Histogram, Registry, TextEncoder};
+use prometheus::core::Collector;
 #[cfg(feature = "metrics")]
+use prometheus::{
+    Counter, CounterVec, Encoder, Histogram, HistogramOpts, HistogramVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "metrics")]
+use std::collections::{BTreeMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::{IsTerminal, Write};
+#[cfg(feature = "metrics")]
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(any(feature = "metrics", feature = "otel"))]
 use std::sync::OnceLock;
+use std::sync::{Arc, Mutex};
 #[cfg(feature = "metrics")]
+use std::thread::JoinHandle;
 use std::time::Instant;
 use tracing::subscriber::set_global_default;
 // use tracing_opentelemetry::OpenTelemetryLayer; // Temporarily disabled
-use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{registry::LookupSpan, Layer};
 use tracing_subscriber::{EnvFilter, Registry as TracingRegistry};
 
 use crate::{PersistError, Result};
@@ -25,39 +51,111 @@ use crate::{PersistError, Result};
 static METRICS: OnceLock<PersistMetrics> = OnceLock::new();
 
 /// Metrics collection for Persist operations
+///
+/// Clones share the same counters and registry, so engines and adapters can each hold
+/// one. Those not given metrics record into [`PersistMetrics::global`].
 #[cfg(feature = "metrics")]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PersistMetrics {
     // S3 operation metrics
     pub s3_requests_total: Counter,
     pub s3_errors_total: Counter,
     pub s3_latency_seconds: Histogram,
+    // Deprecated alias of `retry_attempts_total{backend="s3"}`, without first attempts
     pub s3_retries_total: Counter,
 
     // GCS operation metrics
     pub gcs_requests_total: Counter,
     pub gcs_errors_total: Counter,
     pub gcs_latency_seconds: Histogram,
+    // Deprecated alias of `retry_attempts_total{backend="gcs"}`, without first attempts
     pub gcs_retries_total: Counter,
     pub gcs_transfer_size_bytes: Histogram,
 
+    // Bytes moved by the cloud adapters, labelled by direction ("upload" or "download")
+    pub s3_bytes_total: CounterVec,
+    pub gcs_bytes_total: CounterVec,
+    // Cloud saves and loads in progress, labelled by backend
+    pub in_flight_operations: IntGaugeVec,
+
+    // Local storage operation metrics, labelled by operation
+    pub local_requests_total: CounterVec,
+    pub local_errors_total: CounterVec,
+    pub local_latency_seconds: HistogramVec,
+
+    // End-to-end engine metrics, whatever the storage backend
+    pub engine_operation_duration_seconds: HistogramVec,
+    pub engine_payload_bytes: HistogramVec,
+
     // State size metrics
     pub state_size_bytes: Histogram,
 
+    // Retry metrics from the shared retry layer, labelled by backend and operation
+    pub retry_attempts_total: CounterVec,
+    pub retry_exhausted_total: CounterVec,
+    pub retry_success_after_retry_total: CounterVec,
+
+    // Label values replaced by the cardinality guard, labelled by label name
+    pub metric_labels_clamped_total: CounterVec,
+
+    // Configured labels of the engine operation metrics
+    engine_labels: Arc<EngineLabels>,
+
     // Prometheus registry for scraping
     registry: Registry,
 }
 
+/// Value of the agent and session ID labels when the ID is not known, as for deletions
+#[cfg(feature = "metrics")]
+const UNKNOWN_LABEL_VALUE: &str = "unknown";
+
+/// Value of a label past the cardinality guard's limit of distinct values
+#[cfg(feature = "metrics")]
+const OVERFLOW_LABEL_VALUE: &str = "other";
+
+/// Labels of the engine operation metrics, and the agent and session IDs seen so far
+#[cfg(feature = "metrics")]
+#[derive(Debug)]
+struct EngineLabels {
+    kinds: Vec<LabelKind>,
+    max_values: usize,
+    seen: Mutex<HashMap<LabelKind, HashSet<String>>>,
+}
+
 #[cfg(feature = "metrics")]
 impl PersistMetrics {
-    /// Initialize new metrics instance
-    fn new() -> Result<Self> {
-        // Create Prometheus registry
-        let registry = Registry::new();
+    /// Metrics in a registry of their own
+    ///
+    /// Unlike [`global`](Self::global), the counters start at zero and only move for
+    /// the engines and adapters given these metrics, which keeps tests isolated.
+    pub fn new() -> Result<Self> {
+        Self::with_registry(Registry::new(), None)
+    }
+
+    /// Metrics registered in `registry`, their names prefixed with `{prefix}_` if given
+    ///
+    /// Lets an application expose Persist's metrics next to its own, e.g.
+    /// `myapp_persist_s3_requests_total` with prefix "myapp". Fails if `registry`
+    /// already holds metrics of the same names.
+    pub fn with_registry(registry: Registry, prefix: Option<&str>) -> Result<Self> {
+        Self::with_config(registry, prefix, &ObservabilityConfig::default())
+    }
+
+    /// Metrics registered in `registry`, with the engine operation labels and
+    /// cardinality limit of `config`
+    pub fn with_config(
+        registry: Registry,
+        prefix: Option<&str>,
+        config: &ObservabilityConfig,
+    ) -> Result<Self> {
+        let name = |metric: &str| match prefix {
+            Some(prefix) => format!("{prefix}_{metric}"),
+            None => metric.to_string(),
+        };
 
         // Initialize metrics
         let s3_requests_total = Counter::new(
-            "persist_s3_requests_total",
+            name("persist_s3_requests_total"),
             "Total S3 requests made by Persist",
         )
         .map_err(|e| {
@@ -65,7 +163,7 @@ impl PersistMetrics {
         })?;
 
         let s3_errors_total = Counter::new(
-            "persist_s3_errors_total",
+            name("persist_s3_errors_total"),
             "Total S3 request errors in Persist",
         )
         .map_err(|e| {
@@ -73,7 +171,7 @@ impl PersistMetrics {
         })?;
 
         let s3_latency_seconds = Histogram::with_opts(prometheus::HistogramOpts::new(
-            "persist_s3_latency_seconds",
+            name("persist_s3_latency_seconds"),
             "Duration of S3 operations in seconds",
         ))
         .map_err(|e| {
@@ -81,8 +179,9 @@ impl PersistMetrics {
         })?;
 
         let s3_retries_total = Counter::new(
-            "persist_s3_retries_total",
-            "Total S3 retry attempts in Persist",
+            name("persist_s3_retries_total"),
+            "Total S3 retry attempts in Persist (deprecated: use \
+             persist_retry_attempts_total{backend=\"s3\"}, this alias will be removed)",
         )
         .map_err(|e| {
             PersistError::storage(format!("Failed to create s3_retries_total metric: {e}"))
@@ -90,7 +189,7 @@ impl PersistMetrics {
 
         // Initialize GCS metrics
         let gcs_requests_total = Counter::new(
-            "persist_gcs_requests_total",
+            name("persist_gcs_requests_total"),
             "Total GCS requests made by Persist",
         )
         .map_err(|e| {
@@ -98,7 +197,7 @@ impl PersistMetrics {
         })?;
 
         let gcs_errors_total = Counter::new(
-            "persist_gcs_errors_total",
+            name("persist_gcs_errors_total"),
             "Total GCS request errors in Persist",
         )
         .map_err(|e| {
@@ -106,7 +205,7 @@ impl PersistMetrics {
         })?;
 
         let gcs_latency_seconds = Histogram::with_opts(prometheus::HistogramOpts::new(
-            "persist_gcs_latency_seconds",
+            name("persist_gcs_latency_seconds"),
             "Duration of GCS operations in seconds",
         ))
         .map_err(|e| {
@@ -114,15 +213,16 @@ impl PersistMetrics {
         })?;
 
         let gcs_retries_total = Counter::new(
-            "persist_gcs_retries_total",
-            "Total GCS retry attempts in Persist",
+            name("persist_gcs_retries_total"),
+            "Total GCS retry attempts in Persist (deprecated: use \
+             persist_retry_attempts_total{backend=\"gcs\"}, this alias will be removed)",
         )
         .map_err(|e| {
             PersistError::storage(format!("Failed to create gcs_retries_total metric: {e}"))
         })?;
 
         let gcs_transfer_size_bytes = Histogram::with_opts(prometheus::HistogramOpts::new(
-            "persist_gcs_transfer_size_bytes",
+            name("persist_gcs_transfer_size_bytes"),
             "Size of data transferred in GCS operations",
         ))
         .map_err(|e| {
@@ -131,14 +231,137 @@ impl PersistMetrics {
             ))
         })?;
 
+        let bytes_counter = |metric_name: String, help: &str| {
+            CounterVec::new(Opts::new(metric_name.as_str(), help), &["direction"]).map_err(|e| {
+                PersistError::storage(format!("Failed to create {metric_name} metric: {e}"))
+            })
+        };
+        let s3_bytes_total = bytes_counter(
+            name("persist_s3_bytes_total"),
+            "Total bytes uploaded to and downloaded from S3 by Persist",
+        )?;
+        let gcs_bytes_total = bytes_counter(
+            name("persist_gcs_bytes_total"),
+            "Total bytes uploaded to and downloaded from GCS by Persist",
+        )?;
+        let in_flight_operations = IntGaugeVec::new(
+            Opts::new(
+                name("persist_in_flight_operations"),
+                "Cloud storage saves and loads in progress",
+            ),
+            &["backend"],
+        )
+        .map_err(|e| {
+            PersistError::storage(format!("Failed to create in_flight_operations metric: {e}"))
+        })?;
+
+        // Local storage metrics; the operation label only takes the few operation names
+        let local_counter = |metric_name: String, help: &str| {
+            CounterVec::new(Opts::new(metric_name.as_str(), help), &["operation"]).map_err(|e| {
+                PersistError::storage(format!("Failed to create {metric_name} metric: {e}"))
+            })
+        };
+        let local_requests_total = local_counter(
+            name("persist_local_requests_total"),
+            "Total local storage operations made by Persist",
+        )?;
+        let local_errors_total = local_counter(
+            name("persist_local_errors_total"),
+            "Total failed local storage operations in Persist",
+        )?;
+        let local_latency_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                name("persist_local_latency_seconds"),
+                "Duration of local storage operations in seconds",
+            ),
+            &["operation"],
+        )
+        .map_err(|e| {
+            PersistError::storage(format!(
+                "Failed to create local_latency_seconds metric: {e}"
+            ))
+        })?;
+
+        // Engine metrics: the configured labels, then outcome (success or error)
+        let mut operation_labels: Vec<&str> = config
+            .metric_labels
+            .iter()
+            .map(|kind| kind.label_name())
+            .collect();
+        operation_labels.push("outcome");
+        let engine_operation_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                name("persist_engine_operation_duration_seconds"),
+                "Duration of snapshot engine operations in seconds, including serialization, \
+                 hashing and compression",
+            ),
+            &operation_labels,
+        )
+        .map_err(|e| {
+            PersistError::storage(format!(
+                "Failed to create engine_operation_duration_seconds metric: {e}"
+            ))
+        })?;
+        let engine_payload_bytes =
+            HistogramVec::new(
+                HistogramOpts::new(
+                    name("persist_engine_payload_bytes"),
+                    "Size of snapshot payloads in bytes, before (raw) and after (compressed) \
+                 compression",
+                )
+                .buckets(prometheus::exponential_buckets(256.0, 4.0, 12).map_err(
+                    |e| PersistError::storage(format!("Failed to create payload buckets: {e}")),
+                )?),
+                &["stage"],
+            )
+            .map_err(|e| {
+                PersistError::storage(format!("Failed to create engine_payload_bytes metric: {e}"))
+            })?;
+
         let state_size_bytes = Histogram::with_opts(prometheus::HistogramOpts::new(
-            "persist_state_size_bytes",
+            name("persist_state_size_bytes"),
             "Size of agent state in bytes",
         ))
         .map_err(|e| {
             PersistError::storage(format!("Failed to create state_size_bytes metric: {e}"))
         })?;
 
+        let retry_counter = |metric_name: String, help: &str| {
+            CounterVec::new(
+                Opts::new(metric_name.as_str(), help),
+                &["backend", "operation"],
+            )
+            .map_err(|e| {
+                PersistError::storage(format!("Failed to create {metric_name} metric: {e}"))
+            })
+        };
+        let retry_attempts_total = retry_counter(
+            name("persist_retry_attempts_total"),
+            "Total attempts (first tries and retries) of retried storage operations",
+        )?;
+        let retry_exhausted_total = retry_counter(
+            name("persist_retry_exhausted_total"),
+            "Total storage operations that gave up after retrying transient failures",
+        )?;
+        let retry_success_after_retry_total = retry_counter(
+            name("persist_retry_success_after_retry_total"),
+            "Total storage operations that succeeded after at least one retry",
+        )?;
+
+        let metric_labels_clamped_total = CounterVec::new(
+            Opts::new(
+                name("persist_metric_labels_clamped_total"),
+                "Label values recorded as \"other\" after the limit of distinct values was \
+                 reached",
+            ),
+            &["label"],
+        )
+        .map_err(|e| {
+            PersistError::storage(format!(
+                "Failed to create metric_labels_clamped_total metric: {e}"
+            ))
+        })?;
+
         // Register metrics with the registry
         registry
             .register(Box::new(s3_requests_total.clone()))
@@ -201,6 +424,46 @@ impl PersistMetrics {
                 PersistError::storage(format!("Failed to register gcs_transfer_size_bytes: {e}"))
             })?;
 
+        for counter in [&s3_bytes_total, &gcs_bytes_total] {
+            registry.register(Box::new(counter.clone())).map_err(|e| {
+                PersistError::storage(format!("Failed to register transfer metric: {e}"))
+            })?;
+        }
+        registry
+            .register(Box::new(in_flight_operations.clone()))
+            .map_err(|e| {
+                PersistError::storage(format!("Failed to register in_flight_operations: {e}"))
+            })?;
+
+        for counter in [&local_requests_total, &local_errors_total] {
+            registry.register(Box::new(counter.clone())).map_err(|e| {
+                PersistError::storage(format!("Failed to register local metric: {e}"))
+            })?;
+        }
+        registry
+            .register(Box::new(local_latency_seconds.clone()))
+            .map_err(|e| {
+                PersistError::storage(format!("Failed to register local_latency_seconds: {e}"))
+            })?;
+        for histogram in [&engine_operation_duration_seconds, &engine_payload_bytes] {
+            registry
+                .register(Box::new(histogram.clone()))
+                .map_err(|e| {
+                    PersistError::storage(format!("Failed to register engine metric: {e}"))
+                })?;
+        }
+
+        for counter in [
+            &retry_attempts_total,
+            &retry_exhausted_total,
+            &retry_success_after_retry_total,
+            &metric_labels_clamped_total,
+        ] {
+            registry.register(Box::new(counter.clone())).map_err(|e| {
+                PersistError::storage(format!("Failed to register retry metric: {e}"))
+            })?;
+        }
+
         Ok(Self {
             s3_requests_total,
             s3_errors_total,
@@ -211,7 +474,24 @@ impl PersistMetrics {
             gcs_latency_seconds,
             gcs_retries_total,
             gcs_transfer_size_bytes,
+            s3_bytes_total,
+            gcs_bytes_total,
+            in_flight_operations,
+            local_requests_total,
+            local_errors_total,
+            local_latency_seconds,
+            engine_operation_duration_seconds,
+            engine_payload_bytes,
             state_size_bytes,
+            retry_attempts_total,
+            retry_exhausted_total,
+            retry_success_after_retry_total,
+            metric_labels_clamped_total,
+            engine_labels: Arc::new(EngineLabels {
+                kinds: config.metric_labels.clone(),
+                max_values: config.max_label_values,
+                seen: Mutex::new(HashMap::new()),
+            }),
             registry,
         })
     }
@@ -221,6 +501,89 @@ impl PersistMetrics {
         METRICS.get_or_init(|| Self::new().expect("Failed to initialize Persist metrics"))
     }
 
+    /// Initialize the global metrics with the engine operation labels of `config`
+    ///
+    /// Fails if the global metrics were already initialized, for example by an earlier
+    /// [`global`](Self::global) call, with other labels or another limit.
+    pub fn init_global(config: &ObservabilityConfig) -> Result<&'static PersistMetrics> {
+        if METRICS.get().is_none() {
+            let metrics = Self::with_config(Registry::new(), None, config)?;
+            let _ = METRICS.set(metrics);
+        }
+        let global = Self::global();
+        if global.engine_labels.kinds != config.metric_labels
+            || global.engine_labels.max_values != config.max_label_values
+        {
+            return Err(PersistError::validation(
+                "The global metrics were already initialized with other metric labels",
+            ));
+        }
+        Ok(global)
+    }
+
+    /// Registry the metrics are registered in
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Start a timer of an S3 operation recording into these metrics
+    pub fn start_s3_operation(&self, operation: impl Into<String>) -> MetricsTimer {
+        MetricsTimer::start(self, operation, Self::record_s3_request)
+    }
+
+    /// Start a timer of a GCS operation recording into these metrics
+    pub fn start_gcs_operation(&self, operation: impl Into<String>) -> MetricsTimer {
+        MetricsTimer::start(self, operation, Self::record_gcs_request)
+    }
+
+    /// Start a timer of a local storage operation recording into these metrics
+    pub fn start_local_operation(&self, operation: impl Into<String>) -> MetricsTimer {
+        MetricsTimer::start(self, operation, Self::record_local_request)
+    }
+
+    /// Current counter values, and the number of recorded engine operations
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let count = |counter: &Counter| counter.get() as u64;
+        let counters =
+            |vec: &CounterVec| labelled(vec, |metric| metric.get_counter().value() as u64);
+        let mut engine_operations = BTreeMap::new();
+        for family in self.engine_operation_duration_seconds.collect() {
+            for metric in family.get_metric() {
+                let label = |name: &str| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .find(|label| label.name() == name)
+                        .map_or_else(String::new, |label| label.value().to_string())
+                };
+                *engine_operations
+                    .entry((label("operation"), label("outcome")))
+                    .or_default() += metric.get_histogram().sample_count();
+            }
+        }
+        MetricsSnapshot {
+            s3_requests: count(&self.s3_requests_total),
+            s3_errors: count(&self.s3_errors_total),
+            s3_retries: count(&self.s3_retries_total),
+            gcs_requests: count(&self.gcs_requests_total),
+            gcs_errors: count(&self.gcs_errors_total),
+            gcs_retries: count(&self.gcs_retries_total),
+            s3_bytes: by_label(counters(&self.s3_bytes_total)),
+            gcs_bytes: by_label(counters(&self.gcs_bytes_total)),
+            in_flight: by_label(labelled(&self.in_flight_operations, |metric| {
+                metric.get_gauge().value() as u64
+            })),
+            local_requests: by_label(counters(&self.local_requests_total)),
+            local_errors: by_label(counters(&self.local_errors_total)),
+            engine_operations,
+            retry_attempts: by_label_pair(counters(&self.retry_attempts_total)),
+            retry_exhausted: by_label_pair(counters(&self.retry_exhausted_total)),
+            retry_success_after_retry: by_label_pair(counters(
+                &self.retry_success_after_retry_total,
+            )),
+        }
+    }
+
     /// Record an S3 request
     pub fn record_s3_request(&self, _operation: &str) {
         self.s3_requests_total.inc();
@@ -237,8 +600,10 @@ impl PersistMetrics {
     }
 
     /// Record an S3 retry
-    pub fn record_s3_retry(&self, _operation: &str) {
-        self.s3_retries_total.inc();
+    #[deprecated(note = "retries are counted by `record_retry_attempt`; \
+                         persist_s3_retries_total will be removed in the next release")]
+    pub fn record_s3_retry(&self, operation: &str) {
+        self.record_storage_retry(crate::StorageBackend::S3, operation);
     }
 
     /// Record a GCS request
@@ -257,8 +622,10 @@ impl PersistMetrics {
     }
 
     /// Record a GCS retry
-    pub fn record_gcs_retry(&self, _operation: &str) {
-        self.gcs_retries_total.inc();
+    #[deprecated(note = "retries are counted by `record_retry_attempt`; \
+                         persist_gcs_retries_total will be removed in the next release")]
+    pub fn record_gcs_retry(&self, operation: &str) {
+        self.record_storage_retry(crate::StorageBackend::GCS, operation);
     }
 
     /// Record GCS transfer size
@@ -266,11 +633,176 @@ impl PersistMetrics {
         self.gcs_transfer_size_bytes.observe(size_bytes);
     }
 
+    /// Record `size_bytes` moved to or from S3, `direction` being "upload" or "download"
+    pub fn record_s3_bytes(&self, direction: &str, size_bytes: usize) {
+        self.s3_bytes_total
+            .with_label_values(&[direction])
+            .inc_by(size_bytes as f64);
+    }
+
+    /// Record `size_bytes` moved to or from GCS, `direction` being "upload" or "download"
+    pub fn record_gcs_bytes(&self, direction: &str, size_bytes: usize) {
+        self.gcs_bytes_total
+            .with_label_values(&[direction])
+            .inc_by(size_bytes as f64);
+    }
+
+    /// Count an operation of `backend` ("s3" or "gcs") as in flight until the returned
+    /// guard is dropped
+    pub fn track_in_flight(&self, backend: &str) -> InFlightOperation {
+        let gauge = self.in_flight_operations.with_label_values(&[backend]);
+        gauge.inc();
+        InFlightOperation { gauge }
+    }
+
+    /// Record a local storage operation
+    pub fn record_local_request(&self, operation: &str) {
+        self.local_requests_total
+            .with_label_values(&[operation])
+            .inc();
+    }
+
+    /// Record a failed local storage operation
+    pub fn record_local_error(&self, operation: &str) {
+        self.local_errors_total
+            .with_label_values(&[operation])
+            .inc();
+    }
+
+    /// Record local storage operation latency
+    pub fn record_local_latency(&self, operation: &str, duration: std::time::Duration) {
+        self.local_latency_seconds
+            .with_label_values(&[operation])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record the duration of a snapshot engine operation and whether it succeeded
+    pub fn record_engine_operation(
+        &self,
+        operation: &str,
+        duration: std::time::Duration,
+        succeeded: bool,
+    ) {
+        self.record_engine_operation_with(&OperationLabels::new(operation), duration, succeeded);
+    }
+
+    /// Record the duration and outcome of a snapshot engine operation, labelled with
+    /// the configured labels of `labels`
+    pub fn record_engine_operation_with(
+        &self,
+        labels: &OperationLabels<'_>,
+        duration: std::time::Duration,
+        succeeded: bool,
+    ) {
+        let mut values: Vec<String> = self
+            .engine_labels
+            .kinds
+            .iter()
+            .map(|&kind| match kind {
+                LabelKind::Operation => labels.operation.to_string(),
+                LabelKind::Backend => labels.backend.to_string(),
+                LabelKind::AgentId => self.bounded_label(kind, labels.agent_id),
+                LabelKind::SessionId => self.bounded_label(kind, labels.session_id),
+            })
+            .collect();
+        values.push(if succeeded { "success" } else { "error" }.to_string());
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        self.engine_operation_duration_seconds
+            .with_label_values(&values)
+            .observe(duration.as_secs_f64());
+    }
+
+    /// `value` of an unbounded label, or "other" once the limit of distinct values of
+    /// `kind` is reached and `value` is not one of them
+    fn bounded_label(&self, kind: LabelKind, value: Option<&str>) -> String {
+        let Some(value) = value else {
+            return UNKNOWN_LABEL_VALUE.to_string();
+        };
+        let mut seen = self
+            .engine_labels
+            .seen
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let values = seen.entry(kind).or_default();
+        if values.contains(value) || values.len() < self.engine_labels.max_values {
+            values.insert(value.to_string());
+            return value.to_string();
+        }
+        let clamped = self
+            .metric_labels_clamped_total
+            .with_label_values(&[kind.label_name()]);
+        if clamped.get() == 0.0 {
+            tracing::warn!(
+                label = kind.label_name(),
+                limit = self.engine_labels.max_values,
+                "Too many distinct metric label values, recording new ones as \"other\""
+            );
+        }
+        clamped.inc();
+        OVERFLOW_LABEL_VALUE.to_string()
+    }
+
+    /// Record the size of a snapshot payload at a `stage`, "raw" or "compressed"
+    pub fn record_engine_payload(&self, stage: &str, size_bytes: usize) {
+        self.engine_payload_bytes
+            .with_label_values(&[stage])
+            .observe(size_bytes as f64);
+    }
+
     /// Record state size
     pub fn record_state_size(&self, size_bytes: usize) {
         self.state_size_bytes.observe(size_bytes as f64);
     }
 
+    /// Record an attempt of a retried storage operation
+    pub fn record_retry_attempt(&self, backend: &str, operation: &str) {
+        self.retry_attempts_total
+            .with_label_values(&[backend, operation])
+            .inc();
+    }
+
+    /// Record a storage operation that gave up after retrying
+    pub fn record_retry_exhausted(&self, backend: &str, operation: &str) {
+        self.retry_exhausted_total
+            .with_label_values(&[backend, operation])
+            .inc();
+    }
+
+    /// Record a storage operation that succeeded after retrying
+    pub fn record_retry_success(&self, backend: &str, operation: &str) {
+        self.retry_success_after_retry_total
+            .with_label_values(&[backend, operation])
+            .inc();
+    }
+
+    /// Count a retry of a `backend` operation through the retry metrics sink, so the
+    /// `persist_retry_*` metrics and the old per-backend counters agree
+    ///
+    /// Operation names other than the standard kinds are counted as `other`.
+    fn record_storage_retry(&self, backend: crate::StorageBackend, operation: &str) {
+        let kind = match operation {
+            "save" => persist_retry::OperationKind::Save,
+            "load" => persist_retry::OperationKind::Load,
+            "exists" => persist_retry::OperationKind::Exists,
+            "delete" => persist_retry::OperationKind::Delete,
+            "list" => persist_retry::OperationKind::List,
+            _ => persist_retry::OperationKind::Other("other"),
+        };
+        if let Some(sink) = storage_retry_metrics_with(self, backend) {
+            // Any attempt after the first is a retry
+            sink.record_attempt(&persist_retry::Operation::new(kind), 2);
+        }
+    }
+
+    /// Count a retry of `backend` in its deprecated per-backend counter
+    fn record_legacy_retry(&self, backend: &str) {
+        match backend {
+            "s3" => self.s3_retries_total.inc(),
+            "gcs" => self.gcs_retries_total.inc(),
+            _ => {}
+        }
+    }
+
     /// Gather metrics in Prometheus format
     pub fn gather_metrics(&self) -> Result<String> {
         let encoder = TextEncoder::new();
@@ -286,176 +818,3032 @@ impl PersistMetrics {
     }
 }
 
+/// Values of every child of `collector` by label values, in label name order
+#[cfg(feature = "metrics")]
+fn labelled(
+    collector: &impl Collector,
+    value: impl Fn(&prometheus::proto::Metric) -> u64,
+) -> BTreeMap<Vec<String>, u64> {
+    collector
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .map(|metric| {
+            let labels = metric
+                .get_label()
+                .iter()
+                .map(|label| label.value().to_string())
+                .collect();
+            (labels, value(metric))
+        })
+        .collect()
+}
+
+/// Values keyed by their only label value
+#[cfg(feature = "metrics")]
+fn by_label(values: BTreeMap<Vec<String>, u64>) -> BTreeMap<String, u64> {
+    values
+        .into_iter()
+        .map(|(mut labels, value)| (labels.swap_remove(0), value))
+        .collect()
+}
+
+/// Values keyed by their two label values
+#[cfg(feature = "metrics")]
+fn by_label_pair(values: BTreeMap<Vec<String>, u64>) -> BTreeMap<(String, String), u64> {
+    values
+        .into_iter()
+        .map(|(labels, value)| ((labels[0].clone(), labels[1].clone()), value))
+        .collect()
+}
+
+/// Values of the configurable labels of a snapshot engine operation
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy)]
+pub struct OperationLabels<'a> {
+    /// "save", "load", "verify" or "delete"
+    pub operation: &'a str,
+    /// Storage backend, as given by [`StorageAdapter::backend_name`](crate::StorageAdapter::backend_name)
+    pub backend: &'a str,
+    /// Agent of the snapshot, if known
+    pub agent_id: Option<&'a str>,
+    /// Session of the snapshot, if known
+    pub session_id: Option<&'a str>,
+}
+
+#[cfg(feature = "metrics")]
+impl<'a> OperationLabels<'a> {
+    /// Labels of `operation` on an unknown backend and snapshot
+    pub fn new(operation: &'a str) -> Self {
+        Self {
+            operation,
+            backend: UNKNOWN_LABEL_VALUE,
+            agent_id: None,
+            session_id: None,
+        }
+    }
+}
+
+/// Counter values of a [`PersistMetrics`] at one point, for assertions
+///
+/// Labelled counters are keyed by their label values; labels never recorded are
+/// absent rather than zero.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub s3_requests: u64,
+    pub s3_errors: u64,
+    pub s3_retries: u64,
+    pub gcs_requests: u64,
+    pub gcs_errors: u64,
+    pub gcs_retries: u64,
+    /// Bytes moved to and from S3 by direction ("upload" or "download")
+    pub s3_bytes: BTreeMap<String, u64>,
+    /// Bytes moved to and from GCS by direction
+    pub gcs_bytes: BTreeMap<String, u64>,
+    /// Cloud operations in progress by backend; backends that had any stay at zero
+    pub in_flight: BTreeMap<String, u64>,
+    /// Local storage operations by operation ("save", "load", "exists", "delete")
+    pub local_requests: BTreeMap<String, u64>,
+    /// Failed local storage operations by operation
+    pub local_errors: BTreeMap<String, u64>,
+    /// Engine operations by operation and outcome ("success" or "error"), summed over
+    /// the other labels; the operation is "" when it is not a configured label
+    pub engine_operations: BTreeMap<(String, String), u64>,
+    /// Attempts of retried operations by backend and operation
+    pub retry_attempts: BTreeMap<(String, String), u64>,
+    /// Retried operations that gave up, by backend and operation
+    pub retry_exhausted: BTreeMap<(String, String), u64>,
+    /// Operations that succeeded after retrying, by backend and operation
+    pub retry_success_after_retry: BTreeMap<(String, String), u64>,
+}
+
+/// A cloud storage operation counted as in flight, until dropped
+#[cfg(feature = "metrics")]
+#[must_use = "the operation stops counting as in flight when the guard is dropped"]
+pub struct InFlightOperation {
+    gauge: prometheus::IntGauge,
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for InFlightOperation {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
 /// Metrics timer helper for measuring operation durations
 #[cfg(feature = "metrics")]
 pub struct MetricsTimer {
     start: Instant,
     operation: String,
+    metrics: PersistMetrics,
 }
 
 #[cfg(feature = "metrics")]
 impl MetricsTimer {
     /// Start a new timer for the given operation
     pub fn new(operation: impl Into<String>) -> Self {
-        let operation = operation.into();
-        PersistMetrics::global().record_s3_request(&operation);
-
-        Self {
-            start: Instant::now(),
-            operation,
-        }
+        Self::start_s3_operation(operation)
     }
 
     /// Start a new timer for S3 operations
     pub fn start_s3_operation(operation: impl Into<String>) -> Self {
-        let operation = operation.into();
-        PersistMetrics::global().record_s3_request(&operation);
-
-        Self {
-            start: Instant::now(),
-            operation,
-        }
+        PersistMetrics::global().start_s3_operation(operation)
     }
 
     /// Start a new timer for GCS operations
     pub fn start_gcs_operation(operation: impl Into<String>) -> Self {
+        PersistMetrics::global().start_gcs_operation(operation)
+    }
+
+    /// Start a new timer for local storage operations
+    ///
+    /// `operation` becomes a label value, so it must come from a small fixed set such as
+    /// "save" or "load", never a path.
+    pub fn start_local_operation(operation: impl Into<String>) -> Self {
+        PersistMetrics::global().start_local_operation(operation)
+    }
+
+    /// Timer recording into `metrics`, after `record` counted the request
+    fn start(
+        metrics: &PersistMetrics,
+        operation: impl Into<String>,
+        record: fn(&PersistMetrics, &str),
+    ) -> Self {
         let operation = operation.into();
-        PersistMetrics::global().record_gcs_request(&operation);
+        record(metrics, &operation);
 
         Self {
             start: Instant::now(),
             operation,
+            metrics: metrics.clone(),
         }
     }
 
     /// Complete the timer, recording success latency
     pub fn finish(self) {
         let duration = self.start.elapsed();
-        PersistMetrics::global().record_s3_latency(&self.operation, duration);
+        self.metrics.record_s3_latency(&self.operation, duration);
     }
 
     /// Complete the timer with an error, recording both latency and error
     pub fn finish_with_error(self) {
         let duration = self.start.elapsed();
-        PersistMetrics::global().record_s3_latency(&self.operation, duration);
-        PersistMetrics::global().record_s3_error(&self.operation);
+        self.metrics.record_s3_latency(&self.operation, duration);
+        self.metrics.record_s3_error(&self.operation);
     }
 
     /// Complete the timer for GCS operation, recording success latency
     pub fn finish_gcs(self) {
         let duration = self.start.elapsed();
-        PersistMetrics::global().record_gcs_latency(&self.operation, duration);
+        self.metrics.record_gcs_latency(&self.operation, duration);
     }
 
     /// Complete the GCS timer with an error, recording both latency and error
     pub fn finish_gcs_with_error(self) {
         let duration = self.start.elapsed();
-        PersistMetrics::global().record_gcs_latency(&self.operation, duration);
-        PersistMetrics::global().record_gcs_error(&self.operation);
+        self.metrics.record_gcs_latency(&self.operation, duration);
+        self.metrics.record_gcs_error(&self.operation);
+    }
+
+    /// Complete the timer for a local storage operation, recording its latency and,
+    /// if it `failed`, an error
+    pub fn finish_local(self, failed: bool) {
+        self.metrics
+            .record_local_latency(&self.operation, self.start.elapsed());
+        if failed {
+            self.metrics.record_local_error(&self.operation);
+        }
     }
 
     /// Record a retry for this operation
+    #[deprecated(note = "retries are counted by the retry layer's metrics sink")]
     pub fn record_retry(&self) {
-        PersistMetrics::global().record_s3_retry(&self.operation);
+        self.metrics
+            .record_storage_retry(crate::StorageBackend::S3, &self.operation);
     }
 
     /// Record a GCS retry for this operation
+    #[deprecated(note = "retries are counted by the retry layer's metrics sink")]
     pub fn record_gcs_retry(&self) {
-        PersistMetrics::global().record_gcs_retry(&self.operation);
+        self.metrics
+            .record_storage_retry(crate::StorageBackend::GCS, &self.operation);
     }
 }
 
-/// Initialize the global observability system
-///
-/// This function sets up:
-/// - Structured logging with JSON output
-/// - OpenTelemetry tracing
-/// - Metrics collection
-/// - Optional Jaeger trace export
+/// Built-in retry hook for a cloud storage backend
 ///
-/// # Arguments
-/// * `enable_jaeger` - Whether to enable Jaeger tracing export
-/// * `jaeger_endpoint` - Optional Jaeger endpoint (defaults to localhost:14268)
+/// Counts every retry through the sink of [`storage_retry_metrics`], under the
+/// `persist_retry_*` metrics and the deprecated `persist_s3_retries_total` or
+/// `persist_gcs_retries_total` counter, when the `metrics` feature is enabled. Storage
+/// adapters no longer install it: they use the sink directly.
+#[cfg(feature = "metrics")]
+#[deprecated(note = "use `storage_retry_metrics`, which also updates the old counters")]
+pub fn storage_retry_hook(backend: crate::StorageBackend) -> Option<persist_retry::RetryHook> {
+    storage_retry_hook_from(storage_retry_metrics(backend)?)
+}
+
+/// Built-in retry hook for a cloud storage backend, recording into `metrics`
+#[cfg(feature = "metrics")]
+#[deprecated(note = "use `storage_retry_metrics_with`, which also updates the old counters")]
+pub fn storage_retry_hook_with(
+    metrics: &PersistMetrics,
+    backend: crate::StorageBackend,
+) -> Option<persist_retry::RetryHook> {
+    storage_retry_hook_from(storage_retry_metrics_with(metrics, backend)?)
+}
+
+/// Retry hook passing each retry on to `sink` as the attempt after the failed one
+#[cfg(feature = "metrics")]
+fn storage_retry_hook_from(
+    sink: std::sync::Arc<dyn persist_retry::RetryMetricsSink>,
+) -> Option<persist_retry::RetryHook> {
+    Some(std::sync::Arc::new(
+        move |event: persist_retry::RetryEvent| {
+            sink.record_attempt(&event.operation, event.attempt + 1)
+        },
+    ))
+}
+
+/// Built-in retry hook for a cloud storage backend (none without the `metrics` feature)
+#[cfg(not(feature = "metrics"))]
+#[deprecated(note = "use `storage_retry_metrics`")]
+pub fn storage_retry_hook(_backend: crate::StorageBackend) -> Option<persist_retry::RetryHook> {
+    None
+}
+
+/// [`RetryMetricsSink`](persist_retry::RetryMetricsSink) recording into
+/// [`PersistMetrics`] under the `persist_retry_*` counters
 ///
-/// # Returns
-/// Result indicating success or failure of initialization
-pub fn init_observability(enable_jaeger: bool, _jaeger_endpoint: Option<String>) -> Result<()> {
-    // Initialize metrics (this sets up the global meter provider)
-    #[cfg(feature = "metrics")]
-    PersistMetrics::global();
+/// Counts are labelled with the operation kind, never its target, to keep label
+/// cardinality bounded. Retries of the `s3` and `gcs` backends are also counted in
+/// the deprecated `persist_s3_retries_total` and `persist_gcs_retries_total`.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone)]
+pub struct PersistRetryMetrics {
+    backend: &'static str,
+    metrics: PersistMetrics,
+}
+
+#[cfg(feature = "metrics")]
+impl PersistRetryMetrics {
+    /// Sink labelling every count with `backend` (e.g. `s3`)
+    pub fn new(backend: &'static str) -> Self {
+        Self::with_metrics(PersistMetrics::global().clone(), backend)
+    }
 
-    // Build the tracing subscriber with JSON formatting
-    let fmt_layer = tracing_subscriber::fmt::layer()
-        .json()
-        .with_target(false)
-        .with_current_span(false);
+    /// Sink recording into `metrics` instead of the global metrics
+    pub fn with_metrics(metrics: PersistMetrics, backend: &'static str) -> Self {
+        Self { backend, metrics }
+    }
+}
 
-    // For now, we'll focus on console tracing and metrics
-    // OpenTelemetry Jaeger integration can be added later when version compatibility is resolved
-    if enable_jaeger {
-        tracing::warn!(
-            "Jaeger tracing is temporarily disabled due to version compatibility issues"
-        );
+#[cfg(feature = "metrics")]
+impl persist_retry::RetryMetricsSink for PersistRetryMetrics {
+    fn record_attempt(&self, operation: &persist_retry::Operation, attempt: usize) {
+        self.metrics
+            .record_retry_attempt(self.backend, operation.kind.as_str());
+        if attempt > 1 {
+            self.metrics.record_legacy_retry(self.backend);
+        }
     }
 
-    // Initialize tracing subscriber with console output
-    let subscriber = TracingRegistry::default()
-        .with(EnvFilter::from_default_env().add_directive("persist=info".parse().unwrap()))
-        .with(fmt_layer);
+    fn record_exhausted(&self, operation: &persist_retry::Operation, _attempts: usize) {
+        self.metrics
+            .record_retry_exhausted(self.backend, operation.kind.as_str());
+    }
 
-    set_global_default(subscriber).map_err(|e| {
-        PersistError::storage(format!("Failed to set global tracing subscriber: {e}"))
-    })?;
+    fn record_success_after_retry(&self, operation: &persist_retry::Operation, _attempts: usize) {
+        self.metrics
+            .record_retry_success(self.backend, operation.kind.as_str());
+    }
+}
 
-    tracing::info!("Persist observability system initialized");
-    Ok(())
+/// Retry metrics sink for a storage backend, installed by adapters on their policies
+#[cfg(feature = "metrics")]
+pub fn storage_retry_metrics(
+    backend: crate::StorageBackend,
+) -> Option<std::sync::Arc<dyn persist_retry::RetryMetricsSink>> {
+    storage_retry_metrics_with(PersistMetrics::global(), backend)
 }
 
-/// Initialize observability with default settings
-pub fn init_default_observability() -> Result<()> {
-    init_observability(false, None)
+/// Retry metrics sink for a storage backend, recording into `metrics`
+#[cfg(feature = "metrics")]
+pub fn storage_retry_metrics_with(
+    metrics: &PersistMetrics,
+    backend: crate::StorageBackend,
+) -> Option<std::sync::Arc<dyn persist_retry::RetryMetricsSink>> {
+    let label = match backend {
+        crate::StorageBackend::Local => "local",
+        crate::StorageBackend::S3 => "s3",
+        crate::StorageBackend::GCS => "gcs",
+    };
+    Some(std::sync::Arc::new(PersistRetryMetrics::with_metrics(
+        metrics.clone(),
+        label,
+    )))
 }
 
-#[cfg(all(test, feature = "metrics"))]
-mod tests {
-    use super::*;
+/// Retry metrics sink for a storage backend (none without the `metrics` feature)
+#[cfg(not(feature = "metrics"))]
+pub fn storage_retry_metrics(
+    _backend: crate::StorageBackend,
+) -> Option<std::sync::Arc<dyn persist_retry::RetryMetricsSink>> {
+    None
+}
 
-    #[test]
-    fn test_metrics_initialization() {
-        let metrics = PersistMetrics::global();
+/// Content type of the Prometheus text exposition format
+#[cfg(feature = "metrics")]
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
 
-        // Test that we can record metrics without panicking
-        metrics.record_s3_request("put_object");
-        metrics.record_s3_error("get_object");
-        metrics.record_s3_latency("put_object", std::time::Duration::from_millis(100));
-        metrics.record_s3_retry("put_object");
-        metrics.record_state_size(1024);
-    }
+/// Handle of the HTTP server started by [`serve_metrics`]
+///
+/// The server stops when the handle is shut down or dropped.
+#[cfg(feature = "metrics")]
+pub struct MetricsServerHandle {
+    addr: SocketAddr,
+    server: Arc<tiny_http::Server>,
+    thread: Option<JoinHandle<()>>,
+}
 
-    #[test]
-    fn test_metrics_timer() {
-        let timer = MetricsTimer::new("test_operation");
-        std::thread::sleep(std::time::Duration::from_millis(1));
-        timer.finish();
+#[cfg(feature = "metrics")]
+impl MetricsServerHandle {
+    /// Address the server listens on, with the actual port when bound to port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
 
-        // Test error case
-        let timer = MetricsTimer::new("test_error");
-        timer.finish_with_error();
+    /// Stop accepting requests and wait for the server thread to finish
+    pub fn shutdown(mut self) {
+        self.stop();
     }
 
-    #[test]
-    fn test_metrics_gathering() {
-        let metrics = PersistMetrics::global();
+    fn stop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
 
-        // Record some test metrics
-        metrics.record_s3_request("test");
-        metrics.record_s3_error("test");
+#[cfg(feature = "metrics")]
+impl Drop for MetricsServerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
 
-        // Gather metrics - should not panic
-        let result = metrics.gather_metrics();
-        assert!(result.is_ok());
+/// Readiness check of the metrics server: the health of an engine, deep or not
+#[cfg(feature = "metrics")]
+pub type ReadinessCheck = Box<dyn Fn(bool) -> HealthReport + Send + 'static>;
 
-        let metrics_text = result.unwrap();
-        assert!(metrics_text.contains("persist_s3_requests_total"));
+/// Serve the global [`PersistMetrics`] over HTTP on a background thread
+///
+/// `GET /metrics` returns them in the Prometheus text format and `GET /healthz`
+/// returns "ok"; anything else is a 404. Bind to port 0 to pick a free port, then read
+/// it from [`MetricsServerHandle::local_addr`]. See [`serve_metrics_with_readiness`]
+/// to also answer readiness probes.
+///
+/// # Example
+/// ```rust,no_run
+/// # fn main() -> persist_core::Result<()> {
+/// let server = persist_core::observability::serve_metrics("127.0.0.1:9464".parse().unwrap())?;
+/// // ... save and load snapshots ...
+/// server.shutdown();
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "metrics")]
+pub fn serve_metrics(addr: SocketAddr) -> Result<MetricsServerHandle> {
+    start_metrics_server(addr, None)
+}
+
+/// Serve the global [`PersistMetrics`] like [`serve_metrics`], and the result of
+/// `readiness` on `GET /readyz`
+///
+/// `/readyz` answers 200 with the JSON [`HealthReport`] when healthy and 503 when not.
+/// It runs a shallow check, or a deep one with `?deep=true`. Give it an engine's
+/// [`crate::SnapshotEngine::health`], whose results are cached, so that frequent
+/// probes do not each reach the storage backend.
+///
+/// # Example
+/// ```rust,no_run
+/// # fn main() -> persist_core::Result<()> {
+/// use persist_core::{GzipCompressor, LocalFileStorage, SnapshotEngine};
+/// use std::sync::Arc;
+///
+/// let engine = Arc::new(SnapshotEngine::new(LocalFileStorage::new(), GzipCompressor::new()));
+/// let probed = Arc::clone(&engine);
+/// let server = persist_core::observability::serve_metrics_with_readiness(
+///     "0.0.0.0:9464".parse().unwrap(),
+///     move |deep| probed.health(deep),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "metrics")]
+pub fn serve_metrics_with_readiness(
+    addr: SocketAddr,
+    readiness: impl Fn(bool) -> HealthReport + Send + 'static,
+) -> Result<MetricsServerHandle> {
+    start_metrics_server(addr, Some(Box::new(readiness)))
+}
+
+#[cfg(feature = "metrics")]
+fn start_metrics_server(
+    addr: SocketAddr,
+    readiness: Option<ReadinessCheck>,
+) -> Result<MetricsServerHandle> {
+    let metrics = PersistMetrics::global();
+    let server = tiny_http::Server::http(addr).map_err(|e| {
+        PersistError::storage(format!("Failed to start metrics server on {addr}: {e}"))
+    })?;
+    let addr = server.server_addr().to_ip().unwrap_or(addr);
+    let server = Arc::new(server);
+
+    let requests = Arc::clone(&server);
+    let thread = std::thread::Builder::new()
+        .name("persist-metrics".to_string())
+        .spawn(move || {
+            // Ends once `unblock` is called
+            for request in requests.incoming_requests() {
+                let response = metrics_response(metrics, readiness.as_ref(), &request);
+                if let Err(e) = request.respond(response) {
+                    tracing::debug!("Failed to answer metrics request: {}", e);
+                }
+            }
+        })
+        .map_err(|e| PersistError::storage(format!("Failed to start metrics server: {e}")))?;
+
+    tracing::info!("Serving metrics on http://{}/metrics", addr);
+    Ok(MetricsServerHandle {
+        addr,
+        server,
+        thread: Some(thread),
+    })
+}
+
+/// Response of the metrics server to `request`
+#[cfg(feature = "metrics")]
+fn metrics_response(
+    metrics: &PersistMetrics,
+    readiness: Option<&ReadinessCheck>,
+    request: &tiny_http::Request,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let text = |status: u16, body: String, content_type: &str| {
+        let header = tiny_http::Header::from_bytes("Content-Type", content_type)
+            .expect("static header is valid");
+        tiny_http::Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header)
+    };
+    if !matches!(
+        request.method(),
+        tiny_http::Method::Get | tiny_http::Method::Head
+    ) {
+        return text(405, "method not allowed\n".to_string(), "text/plain");
+    }
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    match path {
+        "/metrics" => match metrics.gather_metrics() {
+            Ok(body) => text(200, body, METRICS_CONTENT_TYPE),
+            Err(e) => text(500, format!("{e}\n"), "text/plain"),
+        },
+        "/healthz" => text(200, "ok\n".to_string(), "text/plain"),
+        "/readyz" => match readiness {
+            Some(readiness) => {
+                let deep = query
+                    .split('&')
+                    .any(|pair| matches!(pair, "deep" | "deep=true" | "deep=1"));
+                let report = readiness(deep);
+                let status = if report.is_healthy() { 200 } else { 503 };
+                text(
+                    status,
+                    format!("{}\n", report.to_json()),
+                    "application/json",
+                )
+            }
+            None => text(404, "no readiness check\n".to_string(), "text/plain"),
+        },
+        _ => text(404, "not found\n".to_string(), "text/plain"),
+    }
+}
+
+/// Outcome of a storage health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// The check passed: snapshots can be stored
+    Healthy,
+    /// The check failed; the report's `last_error` says why
+    Unhealthy,
+}
+
+/// Result of a health check of an engine's storage, from
+/// [`crate::SnapshotEngine::health`]
+///
+/// In JSON the latency is `latency_ms`, in fractional milliseconds:
+///
+/// ```json
+/// {"backend": "s3", "status": "healthy", "deep": false, "latency_ms": 12.5,
+///  "last_error": null, "checked_at": "2026-10-16T09:30:00Z"}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Storage backend checked, as in [`crate::StorageAdapter::backend_name`]
+    pub backend: String,
+    pub status: HealthStatus,
+    /// Whether the check wrote, read back and deleted a probe object
+    pub deep: bool,
+    /// Duration of the check
+    #[serde(rename = "latency_ms", with = "duration_millis")]
+    pub latency: std::time::Duration,
+    /// Error of the check, when unhealthy
+    pub last_error: Option<String>,
+    /// When the check ran; cached reports keep the time of the check they come from
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl HealthReport {
+    /// Report of a check of `backend` that took `latency` and ended with `result`
+    pub fn new(
+        backend: impl Into<String>,
+        deep: bool,
+        latency: std::time::Duration,
+        result: &Result<()>,
+    ) -> Self {
+        let (status, last_error) = match result {
+            Ok(()) => (HealthStatus::Healthy, None),
+            Err(e) => (HealthStatus::Unhealthy, Some(e.to_string())),
+        };
+        Self {
+            backend: backend.into(),
+            status,
+            deep,
+            latency,
+            last_error,
+            checked_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Healthy
+    }
+
+    /// The report as a JSON value
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("health reports serialize to JSON")
+    }
+}
+
+/// (De)serialization of a duration as fractional milliseconds
+mod duration_millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_nanos() as f64 / 1e6)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let millis = f64::deserialize(deserializer)?;
+        if !millis.is_finite() || millis < 0.0 {
+            return Err(serde::de::Error::custom(format!(
+                "invalid duration of {millis} ms"
+            )));
+        }
+        Ok(Duration::from_nanos((millis * 1e6).round() as u64))
+    }
+}
+
+/// Version of the JSON schema of [`PersistEvent`], written as `schema_version` by
+/// [`PersistEvent::to_json`]
+///
+/// Adding event types or fields keeps the version; renaming or removing them bumps it.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Snapshot lifecycle event, passed to the [`EventSink`]s of an engine
+///
+/// In JSON the type is the `event` field, next to the fields of the event and the
+/// `schema_version`:
+///
+/// ```json
+/// {"schema_version": 1, "event": "snapshot_deleted", "path": "agent.json.gz", "duration_ms": 0.21}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PersistEvent {
+    /// A snapshot was saved
+    SnapshotCreated(SnapshotEventDetails),
+    /// A snapshot was loaded and passed its integrity check
+    SnapshotLoaded(SnapshotEventDetails),
+    /// A snapshot was verified without being returned
+    SnapshotVerified(SnapshotEventDetails),
+    /// A snapshot was deleted
+    SnapshotDeleted { path: String, duration_ms: f64 },
+    /// A snapshot failed its integrity check while being loaded or verified
+    IntegrityFailure(FailureEventDetails),
+    /// Any other failed operation
+    OperationFailed(FailureEventDetails),
+}
+
+/// Fields of the events of a successful snapshot save, load or verification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotEventDetails {
+    pub path: String,
+    pub agent_id: String,
+    pub session_id: String,
+    pub snapshot_index: u64,
+    /// Size of the agent state JSON
+    pub raw_bytes: u64,
+    /// Size of the snapshot as stored, after compression
+    pub stored_bytes: u64,
+    pub duration_ms: f64,
+}
+
+/// Fields of the events of a failed operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureEventDetails {
+    /// Engine operation: "save", "load", "verify" or "delete"
+    pub operation: String,
+    pub path: String,
+    /// Kind of the error, such as "storage" or "integrity_check_failed"
+    pub error_kind: String,
+    /// Error message
+    pub error: String,
+    pub duration_ms: f64,
+}
+
+impl PersistEvent {
+    /// Event of the engine `operation` on `path` failing with `error`
+    pub fn failure(operation: &str, path: &str, error: &PersistError, duration_ms: f64) -> Self {
+        let details = FailureEventDetails {
+            operation: operation.to_string(),
+            path: path.to_string(),
+            error_kind: error_kind(error).to_string(),
+            error: error.to_string(),
+            duration_ms,
+        };
+        match error {
+            PersistError::IntegrityCheckFailed { .. } => Self::IntegrityFailure(details),
+            _ => Self::OperationFailed(details),
+        }
+    }
+
+    /// Name of the event type, as in the `event` field of its JSON
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SnapshotCreated(_) => "snapshot_created",
+            Self::SnapshotLoaded(_) => "snapshot_loaded",
+            Self::SnapshotVerified(_) => "snapshot_verified",
+            Self::SnapshotDeleted { .. } => "snapshot_deleted",
+            Self::IntegrityFailure(_) => "integrity_failure",
+            Self::OperationFailed(_) => "operation_failed",
+        }
+    }
+
+    /// Details of a successful save, load or verification
+    pub fn snapshot_details(&self) -> Option<&SnapshotEventDetails> {
+        match self {
+            Self::SnapshotCreated(details)
+            | Self::SnapshotLoaded(details)
+            | Self::SnapshotVerified(details) => Some(details),
+            _ => None,
+        }
+    }
+
+    /// JSON of the event, with its `schema_version`
+    pub fn to_json(&self) -> serde_json::Value {
+        #[derive(Serialize)]
+        struct Versioned<'a> {
+            schema_version: u32,
+            #[serde(flatten)]
+            event: &'a PersistEvent,
+        }
+
+        serde_json::to_value(Versioned {
+            schema_version: EVENT_SCHEMA_VERSION,
+            event: self,
+        })
+        .expect("events serialize to JSON")
+    }
+
+    /// Parse the JSON of an event written with this or an earlier schema version
+    pub fn from_json(value: serde_json::Value) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            schema_version: u32,
+            #[serde(flatten)]
+            event: PersistEvent,
+        }
+
+        let versioned: Versioned = serde_json::from_value(value)?;
+        if versioned.schema_version > EVENT_SCHEMA_VERSION {
+            return Err(PersistError::validation(format!(
+                "Event schema version {} is newer than the supported version \
+                 {EVENT_SCHEMA_VERSION}",
+                versioned.schema_version
+            )));
+        }
+        Ok(versioned.event)
+    }
+}
+
+/// Stable snake_case name of the kind of `error`, for events
+fn error_kind(error: &PersistError) -> &'static str {
+    match error {
+        PersistError::Io(_) => "io",
+        PersistError::Json(_) => "json",
+        PersistError::Compression(_) => "compression",
+        PersistError::IntegrityCheckFailed { .. } => "integrity_check_failed",
+        PersistError::InvalidFormat(_) => "invalid_format",
+        PersistError::NeedsMigration { .. } => "needs_migration",
+        PersistError::MissingMetadata(_) => "missing_metadata",
+        PersistError::Storage(_) => "storage",
+        PersistError::S3UploadError { .. } => "s3_upload",
+        PersistError::S3DownloadError { .. } => "s3_download",
+        PersistError::S3NotFound { .. } | PersistError::GcsNotFound { .. } => "not_found",
+        PersistError::S3AccessDenied { .. } | PersistError::GcsAccessDenied { .. } => {
+            "access_denied"
+        }
+        PersistError::S3Configuration(_) => "s3_configuration",
+        PersistError::Validation(_) => "validation",
+        PersistError::FrameworkMismatch { .. } => "framework_mismatch",
+        PersistError::IndexOutOfOrder { .. } => "index_out_of_order",
+        PersistError::Throttled { .. } => "throttled",
+        PersistError::Encryption(_) => "encryption",
+        PersistError::Aborted(_) => "aborted",
+    }
+}
+
+/// Receiver of the lifecycle events of an engine, such as a message bus publisher
+///
+/// Sinks are called synchronously after each operation, so slow ones should hand
+/// the event off to a queue.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: PersistEvent);
+}
+
+/// Sink logging events as JSON to the `persist::events` tracing target, failures as
+/// warnings
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingEventSink;
+
+impl EventSink for TracingEventSink {
+    fn emit(&self, event: PersistEvent) {
+        let json = event.to_json();
+        match &event {
+            PersistEvent::IntegrityFailure(_) | PersistEvent::OperationFailed(_) => {
+                tracing::warn!(target: "persist::events", event = %json, "{}", event.name())
+            }
+            _ => tracing::info!(target: "persist::events", event = %json, "{}", event.name()),
+        }
+    }
+}
+
+/// Sink keeping events in memory, for tests
+///
+/// Clones share the same buffer: keep one and give the other to the engine.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryEventSink {
+    events: Arc<Mutex<Vec<PersistEvent>>>,
+}
+
+impl MemoryEventSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events emitted so far, oldest first
+    pub fn events(&self) -> Vec<PersistEvent> {
+        self.buffer().clone()
+    }
+
+    /// Remove and return the events emitted so far
+    pub fn take(&self) -> Vec<PersistEvent> {
+        std::mem::take(&mut *self.buffer())
+    }
+
+    fn buffer(&self) -> std::sync::MutexGuard<'_, Vec<PersistEvent>> {
+        self.events
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl EventSink for MemoryEventSink {
+    fn emit(&self, event: PersistEvent) {
+        self.buffer().push(event);
+    }
+}
+
+/// Format of the log lines written by [`init_observability_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line, with the event fields at the top level
+    #[default]
+    Json,
+}
+
+/// Destination of the log lines written by [`init_observability_with`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LogWriter {
+    /// Standard output
+    #[default]
+    Stdout,
+    /// Standard error
+    Stderr,
+    /// Append to this file, creating it and its directory if needed
+    ///
+    /// Lines are written by a background thread; keep the [`ObservabilityGuard`] until
+    /// exit so that the last ones are flushed.
+    File {
+        path: PathBuf,
+        /// When to start a new file
+        rotation: LogRotation,
+        /// Log files kept, including the current one; older ones are deleted (default:
+        /// all are kept)
+        max_files: Option<usize>,
+    },
+}
+
+impl LogWriter {
+    /// Append to the file at `path`, without rotation
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File {
+            path: path.into(),
+            rotation: LogRotation::Never,
+            max_files: None,
+        }
+    }
+
+    /// Append to the file at `path`, rotated as `rotation` says and keeping at most
+    /// `max_files` files
+    pub fn rotating_file(
+        path: impl Into<PathBuf>,
+        rotation: LogRotation,
+        max_files: Option<usize>,
+    ) -> Self {
+        Self::File {
+            path: path.into(),
+            rotation,
+            max_files,
+        }
+    }
+
+    /// Opened destination, with the guard flushing it for files
+    ///
+    /// [`init_observability_with`] calls this; it is public for applications that
+    /// build their own subscriber.
+    pub fn make_writer(&self) -> Result<(BoxMakeWriter, ObservabilityGuard)> {
+        let (path, rotation, max_files) = match self {
+            Self::Stdout => {
+                return Ok((
+                    BoxMakeWriter::new(std::io::stdout),
+                    ObservabilityGuard::default(),
+                ))
+            }
+            Self::Stderr => {
+                return Ok((
+                    BoxMakeWriter::new(std::io::stderr),
+                    ObservabilityGuard::default(),
+                ))
+            }
+            Self::File {
+                path,
+                rotation,
+                max_files,
+            } => (path, *rotation, *max_files),
+        };
+        if max_files == Some(0) {
+            return Err(PersistError::validation(
+                "Log files to keep must be at least 1",
+            ));
+        }
+        let open_error = |e: &dyn std::fmt::Display| {
+            PersistError::storage(format!("Failed to open log file {}: {e}", path.display()))
+        };
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => std::path::Path::new("."),
+        };
+        std::fs::create_dir_all(directory).map_err(|e| open_error(&e))?;
+
+        let writer: Box<dyn std::io::Write + Send> = match rotation {
+            LogRotation::Never => Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| open_error(&e))?,
+            ),
+            LogRotation::Daily | LogRotation::Hourly => {
+                let file_name = path
+                    .file_name()
+                    .ok_or_else(|| open_error(&"the path has no file name"))?;
+                let mut builder = tracing_appender::rolling::RollingFileAppender::builder()
+                    .rotation(if rotation == LogRotation::Daily {
+                        tracing_appender::rolling::Rotation::DAILY
+                    } else {
+                        tracing_appender::rolling::Rotation::HOURLY
+                    })
+                    .filename_prefix(file_name.to_string_lossy());
+                if let Some(max_files) = max_files {
+                    builder = builder.max_log_files(max_files);
+                }
+                Box::new(builder.build(directory).map_err(|e| open_error(&e))?)
+            }
+            LogRotation::SizeMB(0) => {
+                return Err(PersistError::validation(
+                    "Log file size limit must be at least 1 MB",
+                ))
+            }
+            LogRotation::SizeMB(megabytes) => Box::new(
+                SizeRotatingFile::open(path.clone(), megabytes.saturating_mul(1 << 20), max_files)
+                    .map_err(|e| open_error(&e))?,
+            ),
+        };
+        // Blocks rather than drops lines when the writer thread falls behind
+        let (writer, guard) = tracing_appender::non_blocking::NonBlockingBuilder::default()
+            .lossy(false)
+            .thread_name("persist-log-writer")
+            .finish(writer);
+        Ok((
+            BoxMakeWriter::new(writer),
+            ObservabilityGuard {
+                _log_writer: Some(guard),
+            },
+        ))
+    }
+}
+
+/// When a log file is rotated: closed and replaced by a new one
+///
+/// Files rotated by time are named after the configured file name and the date, such
+/// as `persist.log.2026-10-16` or `persist.log.2026-10-16-09`. Files rotated by size
+/// keep the configured name for the current file and are renamed `persist.log.1`
+/// (the newest), `persist.log.2` and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum LogRotation {
+    /// A single file that grows forever
+    #[default]
+    Never,
+    /// A new file every day, at midnight UTC
+    Daily,
+    /// A new file every hour
+    Hourly,
+    /// A new file once the current one would exceed this many megabytes
+    SizeMB(u64),
+}
+
+impl std::str::FromStr for LogRotation {
+    type Err = PersistError;
+
+    /// "never", "daily", "hourly", or a size such as "100mb"
+    fn from_str(s: &str) -> Result<Self> {
+        let lower = s.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "never" => Ok(Self::Never),
+            "daily" => Ok(Self::Daily),
+            "hourly" => Ok(Self::Hourly),
+            size => size
+                .strip_suffix("mb")
+                .and_then(|megabytes| megabytes.trim().parse().ok())
+                .filter(|&megabytes| megabytes > 0)
+                .map(Self::SizeMB)
+                .ok_or_else(|| {
+                    PersistError::validation(format!(
+                        "Invalid log rotation '{s}': expected never, daily, hourly or a size \
+                         such as 100mb"
+                    ))
+                }),
+        }
+    }
+}
+
+impl TryFrom<String> for LogRotation {
+    type Error = PersistError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<LogRotation> for String {
+    fn from(rotation: LogRotation) -> Self {
+        rotation.to_string()
+    }
+}
+
+impl std::fmt::Display for LogRotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Never => f.write_str("never"),
+            Self::Daily => f.write_str("daily"),
+            Self::Hourly => f.write_str("hourly"),
+            Self::SizeMB(megabytes) => write!(f, "{megabytes}mb"),
+        }
+    }
+}
+
+/// Keeps logging set up by [`init_observability_with`] working; hold it until exit
+///
+/// Log files are written by a background thread. Dropping the guard flushes the lines
+/// not written yet and stops the thread, after which file log lines are lost.
+#[derive(Debug, Default)]
+#[must_use = "dropping the guard stops writing log files"]
+pub struct ObservabilityGuard {
+    /// Never read: holding it keeps the non-blocking writer's thread alive
+    _log_writer: Option<tracing_appender::non_blocking::WorkerGuard>,
+}
+
+/// Log file rotated once it reaches a size, with numbered older files
+struct SizeRotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: Option<usize>,
+    file: std::fs::File,
+    written: u64,
+}
+
+impl SizeRotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, max_files: Option<usize>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            file,
+            written,
+        })
+    }
+
+    /// Path of the `n`th newest rotated file
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{n}"));
+        path.into()
+    }
+
+    /// Shift the rotated files by one, deleting those beyond `max_files`, and start a
+    /// new current file
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let keep = self.max_files.map(|max_files| max_files - 1);
+        let mut rotated = 0;
+        while self.rotated_path(rotated + 1).exists() {
+            rotated += 1;
+        }
+        for n in (1..=rotated).rev() {
+            if keep.is_some_and(|keep| n >= keep) {
+                std::fs::remove_file(self.rotated_path(n))?;
+            } else {
+                std::fs::rename(self.rotated_path(n), self.rotated_path(n + 1))?;
+            }
+        }
+        if keep == Some(0) {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        *self = Self::open(self.path.clone(), self.max_bytes, self.max_files)?;
+        Ok(())
+    }
+}
+
+// The writer thread writes each log line with a single call, so lines are never split
+// between files
+impl std::io::Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Label the engine operation metrics can carry, besides their outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LabelKind {
+    /// Engine operation: "save", "load", "verify" or "delete"
+    Operation,
+    /// Storage backend, such as "local" or "s3"
+    Backend,
+    /// Agent ID of the snapshot; subject to the cardinality guard
+    AgentId,
+    /// Session ID of the snapshot; subject to the cardinality guard
+    SessionId,
+}
+
+impl LabelKind {
+    /// Prometheus label name
+    pub fn label_name(self) -> &'static str {
+        match self {
+            Self::Operation => "operation",
+            Self::Backend => "backend",
+            Self::AgentId => "agent_id",
+            Self::SessionId => "session_id",
+        }
+    }
+}
+
+/// Settings of [`init_observability_with`]
+#[derive(Debug, Clone)]
+pub struct ObservabilityConfig {
+    /// Format of the log lines (default: JSON)
+    pub log_format: LogFormat,
+    /// Include the spans an event happened in: the current span and the span list in
+    /// JSON, span close events with their duration in text (default: false)
+    pub include_spans: bool,
+    /// Filter directives such as "persist=debug,warn"; by default `RUST_LOG`, with at
+    /// least `info` for persist
+    pub env_filter: Option<String>,
+    /// Where the log lines go (default: stdout)
+    pub writer: LogWriter,
+    /// Export spans to an OpenTelemetry collector, alongside the logs (needs the `otel`
+    /// feature)
+    pub otlp: Option<OtlpConfig>,
+    /// Labels of the engine operation metrics (default: operation and backend)
+    pub metric_labels: Vec<LabelKind>,
+    /// Distinct agent or session IDs recorded as label values before new ones are
+    /// recorded as "other" (default: 1000)
+    pub max_label_values: usize,
+    /// Fraction of the spans of an operation, by span name such as "save_snapshot",
+    /// that are logged and exported, from 0.0 to 1.0; operations not listed keep all
+    /// their spans. Spans with a warning, an error or an `error` field are always kept
+    pub span_sample_ratios: HashMap<String, f64>,
+    /// Spans completing faster than this are neither logged nor exported, unless they
+    /// have a warning or an error (default: zero, keeping them all)
+    pub min_span_duration: std::time::Duration,
+    /// Log the routine start and success of storage operations at debug level instead
+    /// of info; warnings and errors keep their level (default: false)
+    pub quiet_success_logs: bool,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            log_format: LogFormat::default(),
+            include_spans: false,
+            env_filter: None,
+            writer: LogWriter::default(),
+            otlp: None,
+            metric_labels: vec![LabelKind::Operation, LabelKind::Backend],
+            max_label_values: 1000,
+            span_sample_ratios: HashMap::new(),
+            min_span_duration: std::time::Duration::ZERO,
+            quiet_success_logs: false,
+        }
+    }
+}
+
+impl ObservabilityConfig {
+    /// Write log lines in `log_format`
+    pub fn with_log_format(mut self, log_format: LogFormat) -> Self {
+        self.log_format = log_format;
+        self
+    }
+
+    /// Include the enclosing spans in the log lines
+    pub fn with_spans(mut self, include_spans: bool) -> Self {
+        self.include_spans = include_spans;
+        self
+    }
+
+    /// Filter log lines with these directives instead of `RUST_LOG`
+    pub fn with_env_filter(mut self, env_filter: impl Into<String>) -> Self {
+        self.env_filter = Some(env_filter.into());
+        self
+    }
+
+    /// Write log lines to `writer`
+    pub fn with_writer(mut self, writer: LogWriter) -> Self {
+        self.writer = writer;
+        self
+    }
+
+    /// Export spans to an OpenTelemetry collector
+    pub fn with_otlp(mut self, otlp: OtlpConfig) -> Self {
+        self.otlp = Some(otlp);
+        self
+    }
+
+    /// Label the engine operation metrics with `metric_labels`
+    pub fn with_metric_labels(
+        mut self,
+        metric_labels: impl IntoIterator<Item = LabelKind>,
+    ) -> Self {
+        self.metric_labels = metric_labels.into_iter().collect();
+        self
+    }
+
+    /// Record at most `max_label_values` distinct agent or session IDs as label values
+    pub fn with_max_label_values(mut self, max_label_values: usize) -> Self {
+        self.max_label_values = max_label_values;
+        self
+    }
+
+    /// Log and export this fraction of the spans named `operation`
+    pub fn with_span_sample_ratio(mut self, operation: impl Into<String>, ratio: f64) -> Self {
+        self.span_sample_ratios.insert(operation.into(), ratio);
+        self
+    }
+
+    /// Drop the spans completing faster than `min_span_duration`
+    pub fn with_min_span_duration(mut self, min_span_duration: std::time::Duration) -> Self {
+        self.min_span_duration = min_span_duration;
+        self
+    }
+
+    /// Log the routine start and success of storage operations at debug level
+    pub fn with_quiet_success_logs(mut self, quiet_success_logs: bool) -> Self {
+        self.quiet_success_logs = quiet_success_logs;
+        self
+    }
+
+    /// Sampler of the logged and exported spans, if any span may be dropped
+    fn span_sampler(&self) -> Result<Option<Arc<SpanSampler>>> {
+        for (operation, ratio) in &self.span_sample_ratios {
+            if !(0.0..=1.0).contains(ratio) {
+                return Err(PersistError::validation(format!(
+                    "Span sample ratio of '{operation}' must be between 0.0 and 1.0, got {ratio}"
+                )));
+            }
+        }
+        let ratios: HashMap<String, f64> = self
+            .span_sample_ratios
+            .iter()
+            .filter(|(_, ratio)| **ratio < 1.0)
+            .map(|(operation, ratio)| (operation.clone(), *ratio))
+            .collect();
+        if ratios.is_empty() && self.min_span_duration.is_zero() {
+            return Ok(None);
+        }
+        Ok(Some(Arc::new(SpanSampler {
+            ratios,
+            min_duration: self.min_span_duration,
+            draws: AtomicU64::new(0),
+        })))
+    }
+
+    /// Filter of the log lines
+    fn filter(&self) -> Result<EnvFilter> {
+        match &self.env_filter {
+            Some(directives) => EnvFilter::try_new(directives).map_err(|e| {
+                PersistError::validation(format!("Invalid log filter '{directives}': {e}"))
+            }),
+            None => Ok(EnvFilter::from_default_env()
+                .add_directive("persist=info".parse().expect("static directive is valid"))),
+        }
+    }
+
+    /// Whether text log lines are colored: only on a terminal
+    fn ansi(&self) -> bool {
+        self.log_format == LogFormat::Text
+            && match self.writer {
+                LogWriter::Stdout => std::io::stdout().is_terminal(),
+                LogWriter::Stderr => std::io::stderr().is_terminal(),
+                LogWriter::File { .. } => false,
+            }
+    }
+
+    /// Layer formatting events as configured and writing them to `writer`
+    fn fmt_layer<S>(&self, writer: BoxMakeWriter, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi)
+            .with_target(false);
+        match self.log_format {
+            LogFormat::Json => layer
+                .json()
+                .flatten_event(true)
+                .with_current_span(self.include_spans)
+                .with_span_list(self.include_spans)
+                .boxed(),
+            LogFormat::Text if self.include_spans => layer.with_span_events(FmtSpan::CLOSE).boxed(),
+            LogFormat::Text => layer.boxed(),
+        }
+    }
+}
+
+/// Whether routine success logs are at debug level, see
+/// [`ObservabilityConfig::quiet_success_logs`]
+static QUIET_SUCCESS_LOGS: AtomicBool = AtomicBool::new(false);
+
+/// Whether routine success logs are at debug level instead of info
+pub(crate) fn quiet_success_logs() -> bool {
+    QUIET_SUCCESS_LOGS.load(Ordering::Relaxed)
+}
+
+/// Log the routine start or success of an operation: at info level, or at debug level
+/// with [`ObservabilityConfig::quiet_success_logs`]
+macro_rules! success_log {
+    ($($arg:tt)+) => {
+        if $crate::observability::quiet_success_logs() {
+            tracing::debug!($($arg)+)
+        } else {
+            tracing::info!($($arg)+)
+        }
+    };
+}
+pub(crate) use success_log;
+
+/// Which spans are logged and exported
+#[derive(Debug)]
+struct SpanSampler {
+    /// Sample ratio by span name, for the names sampled below 1.0
+    ratios: HashMap<String, f64>,
+    /// Shortest duration of the kept spans
+    min_duration: std::time::Duration,
+    /// Counter the sampling draws are derived from
+    draws: AtomicU64,
+}
+
+impl SpanSampler {
+    /// Whether a new span named `name` is sampled
+    fn sample(&self, name: &str) -> bool {
+        let Some(&ratio) = self.ratios.get(name) else {
+            return true;
+        };
+        // SplitMix64 of a counter: evenly spread draws without a random number generator
+        let mut z = self
+            .draws
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        ((z >> 11) as f64 / (1u64 << 53) as f64) < ratio
+    }
+}
+
+/// Sampling state of a span, kept in its extensions
+#[derive(Debug)]
+struct SpanSample {
+    sampled: bool,
+    failed: bool,
+    created: Instant,
+}
+
+/// Visitor finding an `error` field
+struct ErrorField(bool);
+
+impl tracing::field::Visit for ErrorField {
+    fn record_debug(&mut self, field: &tracing::field::Field, _value: &dyn std::fmt::Debug) {
+        self.0 |= field.name() == "error";
+    }
+}
+
+/// Layer passing to `inner` the close of the spans kept by `sampler` only
+///
+/// Spans are sampled when they are created, and their failures are tracked until they
+/// close: a warning or error event marks the span it happened in and its ancestors.
+/// As the fmt layer writes span durations and the OpenTelemetry layer exports spans
+/// when they close, the other spans are neither logged nor exported.
+struct Sampled<L> {
+    inner: L,
+    sampler: Option<Arc<SpanSampler>>,
+}
+
+impl<L> Sampled<L> {
+    fn new(inner: L, sampler: Option<Arc<SpanSampler>>) -> Self {
+        Self { inner, sampler }
+    }
+
+    /// Mark the span `id` and its ancestors as failed
+    fn mark_failed<S>(id: &tracing::span::Id, ctx: &Context<'_, S>)
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        for span in ctx.span_scope(id).into_iter().flatten() {
+            if let Some(sample) = span.extensions_mut().get_mut::<SpanSample>() {
+                sample.failed = true;
+            }
+        }
+    }
+}
+
+impl<S, L> Layer<S> for Sampled<L>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    L: Layer<S>,
+{
+    fn on_register_dispatch(&self, subscriber: &tracing::Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(
+        &self,
+        metadata: &'static tracing::Metadata<'static>,
+    ) -> tracing::subscriber::Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &tracing::Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        if let (Some(sampler), Some(span)) = (&self.sampler, ctx.span(id)) {
+            let mut extensions = span.extensions_mut();
+            // The fmt and OpenTelemetry layers share the sampling of a span
+            if extensions.get_mut::<SpanSample>().is_none() {
+                let mut error = ErrorField(false);
+                attrs.record(&mut error);
+                extensions.insert(SpanSample {
+                    sampled: sampler.sample(attrs.metadata().name()),
+                    failed: error.0,
+                    created: Instant::now(),
+                });
+            }
+        }
+        self.inner.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: Context<'_, S>,
+    ) {
+        if self.sampler.is_some() {
+            let mut error = ErrorField(false);
+            values.record(&mut error);
+            if error.0 {
+                Self::mark_failed(id, &ctx);
+            }
+        }
+        self.inner.on_record(id, values, ctx);
+    }
+
+    fn on_follows_from(
+        &self,
+        id: &tracing::span::Id,
+        follows: &tracing::span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        self.inner.on_follows_from(id, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+        if self.sampler.is_some() && *event.metadata().level() <= tracing::Level::WARN {
+            if let Some(span) = ctx.event_span(event) {
+                Self::mark_failed(&span.id(), &ctx);
+            }
+        }
+        self.inner.on_event(event, ctx);
+    }
+
+    fn on_enter(&self, id: &tracing::span::Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &tracing::span::Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: tracing::span::Id, ctx: Context<'_, S>) {
+        if let (Some(sampler), Some(span)) = (&self.sampler, ctx.span(&id)) {
+            let kept = match span.extensions().get::<SpanSample>() {
+                Some(sample) => {
+                    sample.failed
+                        || (sample.sampled && sample.created.elapsed() >= sampler.min_duration)
+                }
+                None => true,
+            };
+            if !kept {
+                return;
+            }
+        }
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &tracing::span::Id, new: &tracing::span::Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    unsafe fn downcast_raw(&self, id: std::any::TypeId) -> Option<*const ()> {
+        if id == std::any::TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else {
+            // SAFETY: forwarded to the inner layer, which upholds the same contract
+            unsafe { self.inner.downcast_raw(id) }
+        }
+    }
+}
+
+/// Where and how spans are exported over OTLP
+///
+/// Spans are sent over HTTP with protobuf encoding, in batches from a background
+/// thread; call [`shutdown_observability`] before exiting to flush the last batch.
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Traces endpoint of the collector, e.g. "http://localhost:4318/v1/traces"
+    pub endpoint: String,
+    /// Extra HTTP headers sent with every export, such as an API key
+    pub headers: HashMap<String, String>,
+    /// `service.name` resource attribute of the exported spans (default: "persist")
+    pub service_name: String,
+    /// Fraction of new traces that are sampled, from 0.0 to 1.0 (default: 1.0); traces
+    /// started by a sampled parent always are
+    pub sampling_ratio: f64,
+}
+
+impl OtlpConfig {
+    /// Export every trace to `endpoint` as service "persist"
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            headers: HashMap::new(),
+            service_name: "persist".to_string(),
+            sampling_ratio: 1.0,
+        }
+    }
+
+    /// Send an HTTP header with every export
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the `service.name` of the exported spans
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = service_name.into();
+        self
+    }
+
+    /// Sample this fraction of new traces
+    pub fn with_sampling_ratio(mut self, sampling_ratio: f64) -> Self {
+        self.sampling_ratio = sampling_ratio;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.endpoint.is_empty() {
+            return Err(PersistError::validation("OTLP endpoint must not be empty"));
+        }
+        if !(0.0..=1.0).contains(&self.sampling_ratio) {
+            return Err(PersistError::validation(format!(
+                "OTLP sampling ratio must be between 0.0 and 1.0, got {}",
+                self.sampling_ratio
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Tracer provider of the OTLP export, kept to flush it on shutdown
+#[cfg(feature = "otel")]
+static TRACER_PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Tracer provider builder with the sampler and resource of `config`
+#[cfg(feature = "otel")]
+fn tracer_provider_builder(config: &OtlpConfig) -> TracerProviderBuilder {
+    SdkTracerProvider::builder()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sampling_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+}
+
+/// Layer turning tracing spans into OpenTelemetry spans of `provider`
+///
+/// Span fields, such as the `path`, `size`, `bucket` and `key` of the instrumented
+/// storage calls, become span attributes, and nested spans keep their parents.
+#[cfg(feature = "otel")]
+fn otel_layer<S>(provider: &SdkTracerProvider) -> impl Layer<S>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("persist-core"))
+}
+
+/// Tracer provider exporting to the OTLP endpoint of `config`
+#[cfg(feature = "otel")]
+fn otlp_tracer_provider(config: &OtlpConfig) -> Result<SdkTracerProvider> {
+    use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(config.endpoint.clone())
+        .with_headers(config.headers.clone())
+        .build()
+        .map_err(|e| {
+            PersistError::validation(format!(
+                "Failed to create OTLP exporter for {}: {e}",
+                config.endpoint
+            ))
+        })?;
+    Ok(tracer_provider_builder(config)
+        .with_batch_exporter(exporter)
+        .build())
+}
+
+/// Initialize the global observability system
+///
+/// This function sets up:
+/// - Structured logging with JSON output
+/// - OpenTelemetry tracing
+/// - Metrics collection
+/// - Optional Jaeger trace export
+///
+/// # Arguments
+/// * `enable_jaeger` - Whether to enable Jaeger tracing export
+/// * `jaeger_endpoint` - Optional Jaeger endpoint (defaults to localhost:14268)
+///
+/// # Returns
+/// Result indicating success or failure of initialization
+pub fn init_observability(enable_jaeger: bool, _jaeger_endpoint: Option<String>) -> Result<()> {
+    // Logs go to stdout, which needs no guard
+    let _ = init_observability_with(&ObservabilityConfig::default())?;
+
+    // Jaeger accepts OTLP: use `init_observability_with` and an `OtlpConfig` instead
+    if enable_jaeger {
+        tracing::warn!("Jaeger export is not supported; export to Jaeger over OTLP instead");
+    }
+    Ok(())
+}
+
+/// Initialize the global observability system with `config`
+///
+/// Sets up the logs of `config` (JSON lines on stdout filtered by `RUST_LOG`, with at
+/// least `info` for persist, by default), metrics collection with the `metrics`
+/// feature, and the OTLP span export of `config.otlp`.
+///
+/// Hold the returned guard until the process exits: with a [`LogWriter::File`],
+/// dropping it stops writing the log file.
+///
+/// # Errors
+/// Fails if a global tracing subscriber is already set, if the log filter is invalid,
+/// if the log file cannot be opened, if the OTLP settings are invalid, or if OTLP
+/// export is requested without the `otel` feature.
+///
+/// # Example
+/// ```rust,no_run
+/// use persist_core::observability::{init_observability_with, ObservabilityConfig, OtlpConfig};
+///
+/// # fn main() -> persist_core::Result<()> {
+/// let otlp = OtlpConfig::new("http://localhost:4318/v1/traces")
+///     .with_service_name("agent-runner")
+///     .with_sampling_ratio(0.1);
+/// let _guard = init_observability_with(&ObservabilityConfig::default().with_otlp(otlp))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn init_observability_with(config: &ObservabilityConfig) -> Result<ObservabilityGuard> {
+    // Initialize the global metrics with the configured labels
+    #[cfg(feature = "metrics")]
+    PersistMetrics::init_global(config)?;
+
+    let filter = config.filter()?;
+    let sampler = config.span_sampler()?;
+    let (writer, guard) = config.writer.make_writer()?;
+    let subscriber = TracingRegistry::default().with(filter).with(Sampled::new(
+        config.fmt_layer(writer, config.ansi()),
+        sampler.clone(),
+    ));
+
+    match &config.otlp {
+        #[cfg(feature = "otel")]
+        Some(otlp) => {
+            otlp.validate()?;
+            let provider = otlp_tracer_provider(otlp)?;
+            let subscriber = subscriber.with(Sampled::new(otel_layer(&provider), sampler));
+            set_global_default(subscriber).map_err(|e| {
+                PersistError::storage(format!("Failed to set global tracing subscriber: {e}"))
+            })?;
+            let _ = TRACER_PROVIDER.set(provider);
+        }
+        #[cfg(not(feature = "otel"))]
+        Some(otlp) => {
+            otlp.validate()?;
+            return Err(PersistError::validation(
+                "OTLP export is not available: persist-core was compiled without the 'otel' \
+                 feature",
+            ));
+        }
+        None => set_global_default(subscriber).map_err(|e| {
+            PersistError::storage(format!("Failed to set global tracing subscriber: {e}"))
+        })?,
+    }
+
+    QUIET_SUCCESS_LOGS.store(config.quiet_success_logs, Ordering::Relaxed);
+    tracing::info!("Persist observability system initialized");
+    Ok(guard)
+}
+
+/// Flush and stop the OTLP span export started by [`init_observability_with`]
+///
+/// Spans are exported in batches, so call this before the process exits. Does nothing
+/// without OTLP export.
+pub fn shutdown_observability() -> Result<()> {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        provider
+            .shutdown()
+            .map_err(|e| PersistError::storage(format!("Failed to flush OTLP spans: {e}")))?;
+    }
+    Ok(())
+}
+
+/// Initialize observability with default settings
+pub fn init_default_observability() -> Result<()> {
+    init_observability(false, None)
+}
+
+/// Object metadata tying an uploaded object to the span that wrote it
+///
+/// With an OpenTelemetry layer installed (`otel` feature), `persist-trace-id` and
+/// `persist-span-id` hold the OpenTelemetry IDs; otherwise `persist-span-id` holds the
+/// tracing span ID. Empty outside of any span.
+pub(crate) fn trace_metadata() -> HashMap<String, String> {
+    let span = tracing::Span::current();
+    #[cfg(feature = "otel")]
+    {
+        use opentelemetry::trace::TraceContextExt;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let context = span.context();
+        let span_context = context.span().span_context().clone();
+        if span_context.is_valid() {
+            return HashMap::from([
+                (
+                    "persist-trace-id".to_string(),
+                    span_context.trace_id().to_string(),
+                ),
+                (
+                    "persist-span-id".to_string(),
+                    span_context.span_id().to_string(),
+                ),
+            ]);
+        }
+    }
+    span.id()
+        .map(|id| {
+            HashMap::from([(
+                "persist-span-id".to_string(),
+                format!("{:016x}", id.into_u64()),
+            )])
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    /// Expected values of a counter with one label
+    fn counts(pairs: &[(&str, u64)]) -> BTreeMap<String, u64> {
+        pairs
+            .iter()
+            .map(|&(label, count)| (label.to_string(), count))
+            .collect()
+    }
+
+    #[test]
+    fn test_metrics_initialization() {
+        let metrics = PersistMetrics::global();
+
+        // Test that we can record metrics without panicking
+        metrics.record_s3_request("put_object");
+        metrics.record_s3_error("get_object");
+        metrics.record_s3_latency("put_object", std::time::Duration::from_millis(100));
+        metrics.record_retry_attempt("s3", "save");
+        metrics.record_state_size(1024);
+    }
+
+    #[test]
+    fn test_retry_metrics_sink_through_flaky_operation() {
+        let metrics = PersistMetrics::new().unwrap();
+        let policy = persist_retry::RetryPolicy::from(
+            backoff::ExponentialBackoffBuilder::new()
+                .with_initial_interval(std::time::Duration::from_millis(1))
+                .with_max_interval(std::time::Duration::from_millis(1))
+                .with_randomization_factor(0.0)
+                .build(),
+        )
+        .with_max_attempts(3)
+        .with_metrics(std::sync::Arc::new(PersistRetryMetrics::with_metrics(
+            metrics.clone(),
+            "test",
+        )));
+
+        // Succeeds on the second attempt
+        persist_retry::retry_blocking("flaky", &policy, |attempt| {
+            if attempt < 2 {
+                Err(PersistError::storage("connection reset"))
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap();
+
+        // Never succeeds: three attempts, then exhausted
+        let result: Result<()> = persist_retry::retry_blocking("flaky", &policy, |_| {
+            Err(PersistError::storage("timed out"))
+        });
+        assert!(result.is_err());
+
+        let key = ("test".to_string(), "flaky".to_string());
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.retry_attempts, BTreeMap::from([(key.clone(), 5)]));
+        assert_eq!(snapshot.retry_exhausted, BTreeMap::from([(key.clone(), 1)]));
+        assert_eq!(
+            snapshot.retry_success_after_retry,
+            BTreeMap::from([(key, 1)])
+        );
+    }
+
+    #[test]
+    fn test_legacy_retry_counters() {
+        let metrics = PersistMetrics::new().unwrap();
+        let policy = |backend| {
+            persist_retry::RetryPolicy::from(
+                backoff::ExponentialBackoffBuilder::new()
+                    .with_initial_interval(std::time::Duration::from_millis(1))
+                    .with_max_interval(std::time::Duration::from_millis(1))
+                    .build(),
+            )
+            .with_max_attempts(3)
+            .with_metrics(storage_retry_metrics_with(&metrics, backend).unwrap())
+        };
+        let flaky = |policy: &persist_retry::RetryPolicy| {
+            let _ = persist_retry::retry_blocking("load", policy, |attempt| {
+                if attempt < 3 {
+                    Err(PersistError::storage("connection reset"))
+                } else {
+                    Ok(())
+                }
+            });
+        };
+        flaky(&policy(crate::StorageBackend::GCS));
+        flaky(&policy(crate::StorageBackend::S3));
+        flaky(&policy(crate::StorageBackend::S3));
+
+        // The old counters count retries, without first attempts
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.retry_attempts,
+            BTreeMap::from([
+                (("gcs".to_string(), "load".to_string()), 3),
+                (("s3".to_string(), "load".to_string()), 6),
+            ])
+        );
+        assert_eq!(metrics.gcs_retries_total.get(), 2.0);
+        assert_eq!(metrics.s3_retries_total.get(), 4.0);
+        let text = metrics.gather_metrics().unwrap();
+        assert!(text.contains(
+            "# HELP persist_gcs_retries_total Total GCS retry attempts in Persist (deprecated"
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_metrics_retry_hook() {
+        let metrics = PersistMetrics::global();
+        let before = metrics.gcs_retries_total.get();
+
+        let hook = storage_retry_hook(crate::StorageBackend::GCS).unwrap();
+        hook(persist_retry::RetryEvent {
+            operation: "load".into(),
+            attempt: 1,
+            error: "timed out".to_string(),
+            delay: std::time::Duration::from_millis(10),
+        });
+
+        assert!(metrics.gcs_retries_total.get() > before);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_retry_recorders_update_both_counters() {
+        let metrics = PersistMetrics::new().unwrap();
+        let hook = storage_retry_hook_with(&metrics, crate::StorageBackend::S3).unwrap();
+        hook(persist_retry::RetryEvent {
+            operation: "save".into(),
+            attempt: 1,
+            error: "timed out".to_string(),
+            delay: std::time::Duration::from_millis(10),
+        });
+        metrics.record_s3_retry("load");
+        metrics.record_gcs_retry("load");
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.retry_attempts,
+            BTreeMap::from([
+                (("gcs".to_string(), "load".to_string()), 1),
+                (("s3".to_string(), "load".to_string()), 1),
+                (("s3".to_string(), "save".to_string()), 1),
+            ])
+        );
+        assert_eq!(metrics.s3_retries_total.get(), 2.0);
+        assert_eq!(metrics.gcs_retries_total.get(), 1.0);
+    }
+
+    #[test]
+    fn test_metrics_timer() {
+        let timer = MetricsTimer::new("test_operation");
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        timer.finish();
+
+        // Test error case
+        let timer = MetricsTimer::new("test_error");
+        timer.finish_with_error();
+    }
+
+    /// Status code and body of `GET path` on a metrics server
+    fn http_get(addr: SocketAddr, path: &str) -> (u16, String) {
+        use std::io::{Read, Write};
+
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn test_serve_metrics() {
+        let server = serve_metrics("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = server.local_addr();
+        assert_ne!(addr.port(), 0);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("agent.json.gz");
+        let path = path.to_str().unwrap();
+        let engine = crate::SnapshotEngine::new(
+            crate::LocalFileStorage::new(),
+            crate::GzipCompressor::new(),
+        );
+        let metadata = crate::SnapshotMetadata::new("agent", "session", 0);
+        engine
+            .save_snapshot(r#"{"state": 1}"#, &metadata, path)
+            .unwrap();
+        engine.load_snapshot(path).unwrap();
+
+        let (status, body) = http_get(addr, "/metrics");
+        assert_eq!(status, 200);
+        assert!(body.contains("# TYPE persist_local_requests_total counter"));
+        assert!(body.contains("# TYPE persist_local_latency_seconds histogram"));
+        assert!(body.contains("# TYPE persist_state_size_bytes histogram"));
+        assert_eq!(http_get(addr, "/metrics?format=text").0, 200);
+        assert_eq!(http_get(addr, "/healthz"), (200, "ok\n".to_string()));
+        assert_eq!(http_get(addr, "/missing").0, 404);
+        assert_eq!(http_get(addr, "/readyz").0, 404);
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_serve_metrics_readiness() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let engine = Arc::new(crate::SnapshotEngine::new(
+            crate::LocalFileStorage::with_base_dir(dir.path()),
+            crate::GzipCompressor::new(),
+        ));
+        let server = serve_metrics_with_readiness("127.0.0.1:0".parse().unwrap(), {
+            let engine = Arc::clone(&engine);
+            move |deep| engine.health(deep)
+        })
+        .unwrap();
+        let addr = server.local_addr();
+
+        let report = |path: &str| {
+            let (status, body) = http_get(addr, path);
+            (status, serde_json::from_str::<HealthReport>(&body).unwrap())
+        };
+        let (status, shallow) = report("/readyz");
+        assert_eq!(status, 200);
+        assert_eq!(shallow.backend, "local");
+        assert_eq!(shallow.status, HealthStatus::Healthy);
+        assert!(!shallow.deep);
+        let (status, deep) = report("/readyz?deep=true");
+        assert_eq!(status, 200);
+        assert!(deep.deep);
+
+        // The storage directory disappears, but the cached reports stay valid
+        drop(dir);
+        assert_eq!(report("/readyz").1, shallow);
+        server.shutdown();
+
+        let missing = tempfile::TempDir::new().unwrap().path().to_path_buf();
+        let engine = crate::SnapshotEngine::new(
+            crate::LocalFileStorage::with_base_dir(missing),
+            crate::GzipCompressor::new(),
+        );
+        let server = serve_metrics_with_readiness("127.0.0.1:0".parse().unwrap(), move |deep| {
+            engine.health(deep)
+        })
+        .unwrap();
+        let (status, body) = http_get(server.local_addr(), "/readyz");
+        assert_eq!(status, 503);
+        let unhealthy: HealthReport = serde_json::from_str(&body).unwrap();
+        assert_eq!(unhealthy.status, HealthStatus::Unhealthy);
+        assert!(unhealthy
+            .last_error
+            .unwrap()
+            .contains("Cannot access storage directory"));
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_local_storage_metrics() {
+        use crate::StorageAdapter;
+
+        let metrics = PersistMetrics::new().unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let storage =
+            crate::LocalFileStorage::with_base_dir(dir.path()).with_metrics(metrics.clone());
+        storage.save(b"data", "agent.json").unwrap();
+        assert_eq!(storage.load("agent.json").unwrap(), b"data");
+        assert!(storage.exists("agent.json"));
+        storage.delete("agent.json").unwrap();
+        assert!(storage.load("agent.json").is_err());
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.local_requests,
+            counts(&[("delete", 1), ("exists", 1), ("load", 2), ("save", 1)])
+        );
+        assert_eq!(snapshot.local_errors, counts(&[("load", 1)]));
+        let timings = |op: &str| {
+            metrics
+                .local_latency_seconds
+                .with_label_values(&[op])
+                .get_sample_count()
+        };
+        assert_eq!(timings("load"), 2);
+        assert_eq!(timings("save"), 1);
+
+        let text = metrics.gather_metrics().unwrap();
+        assert!(text.contains(r#"persist_local_requests_total{operation="save"} 1"#));
+        assert!(text.contains(r#"persist_local_errors_total{operation="load"} 1"#));
+        assert!(text.contains(r#"persist_local_latency_seconds_count{operation="load"} 2"#));
+        assert!(!text.contains("agent.json"));
+    }
+
+    #[test]
+    fn test_engine_metrics() {
+        let metrics = PersistMetrics::new().unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let engine = crate::SnapshotEngine::new(
+            crate::LocalFileStorage::with_base_dir(dir.path()),
+            crate::GzipCompressor::new(),
+        )
+        .with_metrics(metrics.clone());
+        let metadata = crate::SnapshotMetadata::new("agent", "session", 0);
+        engine
+            .save_snapshot(r#"{"state": 1}"#, &metadata, "agent.json.gz")
+            .unwrap();
+        engine.load_snapshot("agent.json.gz").unwrap();
+        engine.verify_snapshot("agent.json.gz").unwrap();
+        engine.delete_snapshot("agent.json.gz").unwrap();
+        assert!(engine.load_snapshot("agent.json.gz").is_err());
+
+        let operations: BTreeMap<_, _> = [
+            ("delete", "success"),
+            ("load", "error"),
+            ("load", "success"),
+            ("save", "success"),
+            ("verify", "success"),
+        ]
+        .into_iter()
+        .map(|(operation, outcome)| ((operation.to_string(), outcome.to_string()), 1))
+        .collect();
+        assert_eq!(metrics.snapshot().engine_operations, operations);
+        // Raw and compressed sizes of the save, the load and the verification
+        let payloads = |stage: &str| {
+            metrics
+                .engine_payload_bytes
+                .with_label_values(&[stage])
+                .get_sample_count()
+        };
+        assert_eq!((payloads("raw"), payloads("compressed")), (3, 3));
+
+        let text = metrics.gather_metrics().unwrap();
+        assert!(text.contains(
+            r#"persist_engine_operation_duration_seconds_count{backend="local",operation="load",outcome="error"} 1"#
+        ));
+        assert!(text.contains(r#"persist_engine_payload_bytes_bucket{stage="compressed""#));
+    }
+
+    /// Label names of the engine operation metric of `metrics`
+    fn operation_label_names(metrics: &PersistMetrics) -> Vec<Vec<String>> {
+        metrics
+            .engine_operation_duration_seconds
+            .collect()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .map(|metric| {
+                metric
+                    .get_label()
+                    .iter()
+                    .map(|label| label.name().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_metric_labels() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let save_with = |config: ObservabilityConfig| {
+            let metrics = PersistMetrics::with_config(Registry::new(), None, &config).unwrap();
+            let engine = crate::SnapshotEngine::new(
+                crate::LocalFileStorage::with_base_dir(dir.path()),
+                crate::GzipCompressor::new(),
+            )
+            .with_metrics(metrics.clone());
+            let metadata = crate::SnapshotMetadata::new("agent", "session", 0);
+            engine
+                .save_snapshot(r#"{"state": 1}"#, &metadata, "agent.json.gz")
+                .unwrap();
+            engine.delete_snapshot("agent.json.gz").unwrap();
+            metrics
+        };
+
+        let metrics = save_with(ObservabilityConfig::default());
+        assert_eq!(
+            operation_label_names(&metrics),
+            vec![vec!["backend", "operation", "outcome"]; 2]
+        );
+
+        let metrics = save_with(ObservabilityConfig::default().with_metric_labels([
+            LabelKind::Operation,
+            LabelKind::AgentId,
+            LabelKind::SessionId,
+        ]));
+        assert_eq!(
+            operation_label_names(&metrics),
+            vec![vec!["agent_id", "operation", "outcome", "session_id"]; 2]
+        );
+        let text = metrics.gather_metrics().unwrap();
+        assert!(text.contains(
+            r#"persist_engine_operation_duration_seconds_count{agent_id="agent",operation="save",outcome="success",session_id="session"} 1"#
+        ));
+        // Deletions do not read the snapshot, so its IDs are unknown
+        assert!(text.contains(
+            r#"persist_engine_operation_duration_seconds_count{agent_id="unknown",operation="delete",outcome="success",session_id="unknown"} 1"#
+        ));
+
+        let metrics = save_with(ObservabilityConfig::default().with_metric_labels([]));
+        assert_eq!(operation_label_names(&metrics), vec![vec!["outcome"]]);
+        assert_eq!(
+            metrics.snapshot().engine_operations,
+            BTreeMap::from([((String::new(), "success".to_string()), 2)])
+        );
+    }
+
+    #[test]
+    fn test_metric_label_cardinality_guard() {
+        let config = ObservabilityConfig::default()
+            .with_metric_labels([LabelKind::AgentId])
+            .with_max_label_values(3);
+        let metrics = PersistMetrics::with_config(Registry::new(), None, &config).unwrap();
+        let record = |agent_id: &str| {
+            let labels = OperationLabels {
+                agent_id: Some(agent_id),
+                ..OperationLabels::new("save")
+            };
+            metrics.record_engine_operation_with(
+                &labels,
+                std::time::Duration::from_millis(1),
+                true,
+            );
+        };
+        for i in 0..5 {
+            record(&format!("agent-{i}"));
+        }
+        // Agents seen before the limit keep their own label value
+        record("agent-1");
+
+        let samples = |agent_id: &str| {
+            metrics
+                .engine_operation_duration_seconds
+                .with_label_values(&[agent_id, "success"])
+                .get_sample_count()
+        };
+        assert_eq!(
+            ["agent-0", "agent-1", "agent-2", "other"].map(samples),
+            [1, 2, 1, 2]
+        );
+        assert_eq!(
+            labelled(&metrics.engine_operation_duration_seconds, |_| 0).len(),
+            4
+        );
+        assert_eq!(
+            metrics
+                .metric_labels_clamped_total
+                .with_label_values(&["agent_id"])
+                .get(),
+            2.0
+        );
+    }
+
+    #[test]
+    fn test_isolated_metrics() {
+        use crate::StorageAdapter;
+
+        let first = PersistMetrics::new().unwrap();
+        let second = PersistMetrics::new().unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let storage =
+            crate::LocalFileStorage::with_base_dir(dir.path()).with_metrics(first.clone());
+        storage.save(b"data", "agent.json").unwrap();
+        storage.load("agent.json").unwrap();
+        first.record_s3_request("put_object");
+
+        let snapshot = first.snapshot();
+        assert_eq!(snapshot.local_requests.values().sum::<u64>(), 2);
+        assert_eq!(snapshot.s3_requests, 1);
+        assert_eq!(second.snapshot(), MetricsSnapshot::default());
+    }
+
+    #[test]
+    fn test_transfer_metrics() {
+        let metrics = PersistMetrics::new().unwrap();
+        metrics.record_s3_bytes("upload", 1000);
+        metrics.record_s3_bytes("upload", 24);
+        metrics.record_gcs_bytes("download", 512);
+
+        let first = metrics.track_in_flight("s3");
+        let second = metrics.track_in_flight("s3");
+        assert_eq!(metrics.snapshot().in_flight, counts(&[("s3", 2)]));
+        drop(first);
+        drop(second);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.s3_bytes, counts(&[("upload", 1024)]));
+        assert_eq!(snapshot.gcs_bytes, counts(&[("download", 512)]));
+        assert_eq!(snapshot.in_flight, counts(&[("s3", 0)]));
+
+        let text = metrics.gather_metrics().unwrap();
+        assert!(text.contains(r#"persist_s3_bytes_total{direction="upload"} 1024"#));
+        assert!(text.contains(r#"persist_gcs_bytes_total{direction="download"} 512"#));
+        assert!(text.contains(r#"persist_in_flight_operations{backend="s3"} 0"#));
+    }
+
+    #[test]
+    fn test_metrics_in_shared_registry() {
+        let registry = Registry::new();
+        let metrics = PersistMetrics::with_registry(registry.clone(), Some("myapp")).unwrap();
+        metrics.record_gcs_request("load");
+
+        let names: Vec<String> = registry
+            .gather()
+            .iter()
+            .map(|family| family.name().to_string())
+            .collect();
+        assert!(names.contains(&"myapp_persist_gcs_requests_total".to_string()));
+        assert!(names.iter().all(|name| name.starts_with("myapp_persist_")));
+        assert_eq!(metrics.snapshot().gcs_requests, 1);
+
+        // The names are taken: a second set in the same registry would collide
+        assert!(PersistMetrics::with_registry(registry.clone(), Some("myapp")).is_err());
+        assert!(PersistMetrics::with_registry(registry, Some("other")).is_ok());
+    }
+
+    #[test]
+    fn test_metrics_gathering() {
+        let metrics = PersistMetrics::global();
+
+        // Record some test metrics
+        metrics.record_s3_request("test");
+        metrics.record_s3_error("test");
+
+        // Gather metrics - should not panic
+        let result = metrics.gather_metrics();
+        assert!(result.is_ok());
+
+        let metrics_text = result.unwrap();
+        assert!(metrics_text.contains("persist_s3_requests_total"));
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod otel_tests {
+    use super::*;
+    use opentelemetry::trace::SpanId;
+    use opentelemetry_sdk::trace::{InMemorySpanExporterBuilder, SpanData};
+
+    /// String value of the attribute `key` of `span`
+    fn attribute(span: &SpanData, key: &str) -> Option<String> {
+        span.attributes
+            .iter()
+            .find(|attribute| attribute.key.as_str() == key)
+            .map(|attribute| attribute.value.to_string())
+    }
+
+    #[test]
+    fn test_save_and_load_spans() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = tracer_provider_builder(&OtlpConfig::new("http://unused"))
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = TracingRegistry::default().with(otel_layer(&provider));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("agent.json.gz");
+        let path = path.to_str().unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let engine = crate::SnapshotEngine::new(
+                crate::LocalFileStorage::new(),
+                crate::GzipCompressor::new(),
+            );
+            let metadata = crate::SnapshotMetadata::new("agent", "session", 0);
+            engine
+                .save_snapshot(r#"{"state": 1}"#, &metadata, path)
+                .unwrap();
+            engine.load_snapshot(path).unwrap();
+        });
+        provider.force_flush().unwrap();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let span = |name: &str, parent: Option<SpanId>| {
+            spans
+                .iter()
+                .find(|span| span.name == name && parent.is_none_or(|id| span.parent_span_id == id))
+                .unwrap_or_else(|| panic!("no {name} span in {spans:?}"))
+        };
+
+        let save = span("save_snapshot", None);
+        assert_eq!(attribute(save, "path").as_deref(), Some(path));
+        assert_eq!(attribute(save, "agent_id").as_deref(), Some("agent"));
+        assert_eq!(attribute(save, "size").as_deref(), Some("12"));
+        // The storage call is a child of the engine call
+        let stored = span("save_with_options", Some(save.span_context.span_id()));
+        assert_eq!(attribute(stored, "path").as_deref(), Some(path));
+        assert_eq!(stored.span_context.trace_id(), save.span_context.trace_id());
+
+        let load = span("load_snapshot", None);
+        assert_eq!(attribute(load, "path").as_deref(), Some(path));
+        span("load_with_options", Some(load.span_context.span_id()));
+    }
+
+    #[test]
+    fn test_otel_trace_metadata() {
+        let provider = tracer_provider_builder(&OtlpConfig::new("http://unused")).build();
+        let subscriber = TracingRegistry::default().with(otel_layer(&provider));
+
+        let (metadata, trace_id) = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("upload");
+            let _entered = span.enter();
+            let trace_id = {
+                use opentelemetry::trace::TraceContextExt;
+                use tracing_opentelemetry::OpenTelemetrySpanExt;
+                span.context().span().span_context().trace_id()
+            };
+            (trace_metadata(), trace_id)
+        });
+        assert_eq!(metadata["persist-trace-id"], trace_id.to_string());
+        assert_eq!(metadata["persist-span-id"].len(), 16);
+    }
+
+    #[test]
+    fn test_otlp_config_validation() {
+        assert!(OtlpConfig::new("http://localhost:4318/v1/traces")
+            .validate()
+            .is_ok());
+        assert!(OtlpConfig::new("").validate().is_err());
+        assert!(OtlpConfig::new("http://localhost:4318")
+            .with_sampling_ratio(1.5)
+            .validate()
+            .is_err());
+    }
+}
+
+#[cfg(test)]
+mod trace_metadata_tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_metadata() {
+        assert!(trace_metadata().is_empty());
+
+        let metadata = tracing::subscriber::with_default(TracingRegistry::default(), || {
+            let span = tracing::info_span!("upload");
+            let _entered = span.enter();
+            let id = span.id().unwrap().into_u64();
+            assert_eq!(trace_metadata()["persist-span-id"], format!("{id:016x}"));
+            trace_metadata()
+        });
+        assert!(!metadata.contains_key("persist-trace-id"));
+    }
+}
+
+#[cfg(test)]
+mod log_tests {
+    use super::*;
+    use crate::{LocalFileStorage, StorageAdapter};
+    use std::io::Write;
+
+    /// Writer collecting log lines in memory
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    /// Log lines of a local save of "agent.json" under `config`
+    fn save_logs(config: &ObservabilityConfig) -> Vec<String> {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = TracingRegistry::default()
+            .with(config.filter().unwrap())
+            .with(config.fmt_layer(BoxMakeWriter::new(move || writer.clone()), false));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            LocalFileStorage::with_base_dir(dir.path())
+                .save(b"data", "agent.json")
+                .unwrap();
+        });
+        captured.lines()
+    }
+
+    #[test]
+    fn test_json_logs() {
+        let config = ObservabilityConfig::default().with_env_filter("persist=info");
+        let lines = save_logs(&config);
+        let events: Vec<serde_json::Value> = lines
+            .iter()
+            .map(|line| serde_json::from_str(line).expect("log line is JSON"))
+            .collect();
+
+        let event = events
+            .iter()
+            .find(|event| event["message"] == "Starting local storage save operation")
+            .unwrap_or_else(|| panic!("no save event in {lines:?}"));
+        // Fields are flattened next to the message
+        assert_eq!(event["path"], "agent.json");
+        assert_eq!(event["size"], 4);
+        assert_eq!(event["has_base_dir"], "true");
+        assert_eq!(event["level"], "INFO");
+        assert!(event.get("fields").is_none());
+        assert!(event.get("span").is_none());
+    }
+
+    #[test]
+    fn test_json_logs_with_spans() {
+        let config = ObservabilityConfig::default()
+            .with_env_filter("persist=info")
+            .with_spans(true);
+        let lines = save_logs(&config);
+        let event: serde_json::Value = lines
+            .iter()
+            .map(|line| serde_json::from_str(line).expect("log line is JSON"))
+            .find(|event: &serde_json::Value| {
+                event["message"] == "Starting local storage save operation"
+            })
+            .unwrap_or_else(|| panic!("no save event in {lines:?}"));
+        assert_eq!(event["span"]["name"], "save_with_options");
+        assert_eq!(event["span"]["path"], "agent.json");
+        assert_eq!(event["spans"][0]["name"], "save_with_options");
+    }
+
+    #[test]
+    fn test_text_logs() {
+        let config = ObservabilityConfig::default()
+            .with_log_format(LogFormat::Text)
+            .with_env_filter("persist=info");
+        let lines = save_logs(&config);
+        let line = lines
+            .iter()
+            .find(|line| line.contains("Starting local storage save operation"))
+            .unwrap_or_else(|| panic!("no save event in {lines:?}"));
+        assert!(serde_json::from_str::<serde_json::Value>(line).is_err());
+        assert!(line.contains("path=agent.json"));
+    }
+
+    #[test]
+    fn test_invalid_env_filter() {
+        let config = ObservabilityConfig::default().with_env_filter("persist=loud");
+        assert!(config.filter().is_err());
+    }
+
+    /// Log `count` events of about 300 bytes to the writer of `config`, and flush them
+    fn write_logs(config: &ObservabilityConfig, count: usize) {
+        let (writer, guard) = config.writer.make_writer().unwrap();
+        let subscriber = TracingRegistry::default()
+            .with(config.filter().unwrap())
+            .with(config.fmt_layer(writer, false));
+        let padding = "x".repeat(256);
+        tracing::subscriber::with_default(subscriber, || {
+            for n in 0..count {
+                tracing::info!(n, padding = %padding, "Migrated snapshot");
+            }
+        });
+        drop(guard);
+    }
+
+    /// Names of the files in `dir`, sorted
+    fn file_names(dir: &std::path::Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_size_rotated_log_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("logs").join("persist.log");
+        let config = ObservabilityConfig::default()
+            .with_env_filter("info")
+            .with_writer(LogWriter::rotating_file(
+                &path,
+                LogRotation::SizeMB(1),
+                Some(3),
+            ));
+        // About 4 MB: rotated 3 times, the oldest file deleted
+        write_logs(&config, 12_000);
+
+        let logs = dir.path().join("logs");
+        assert_eq!(
+            file_names(&logs),
+            ["persist.log", "persist.log.1", "persist.log.2"]
+        );
+        let numbers = |name: &str| -> Vec<u64> {
+            let text = std::fs::read_to_string(logs.join(name)).unwrap();
+            assert!(text.len() <= 1 << 20, "{name} has {} bytes", text.len());
+            assert!(text.ends_with('\n'));
+            text.lines()
+                .map(|line| {
+                    let event: serde_json::Value =
+                        serde_json::from_str(line).expect("log line is JSON");
+                    event["n"].as_u64().unwrap()
+                })
+                .collect()
+        };
+        let (older, newer, current) = (
+            numbers("persist.log.2"),
+            numbers("persist.log.1"),
+            numbers("persist.log"),
+        );
+        // No line is lost or split between the files that are kept
+        let kept: Vec<u64> = [older, newer, current].concat();
+        assert_eq!(kept.last(), Some(&11_999));
+        assert!(kept.windows(2).all(|pair| pair[1] == pair[0] + 1));
+    }
+
+    #[test]
+    fn test_daily_log_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = ObservabilityConfig::default()
+            .with_log_format(LogFormat::Text)
+            .with_env_filter("info")
+            .with_writer(LogWriter::rotating_file(
+                dir.path().join("persist.log"),
+                LogRotation::Daily,
+                Some(7),
+            ));
+        write_logs(&config, 10);
+
+        let names = file_names(dir.path());
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(names.len(), 1, "{names:?}");
+        assert!(names[0].starts_with("persist.log."), "{names:?}");
+        assert!(names[0].ends_with(&today), "{names:?}");
+        let text = std::fs::read_to_string(dir.path().join(&names[0])).unwrap();
+        assert_eq!(text.lines().count(), 10);
+        assert!(text.lines().all(|line| line.contains("Migrated snapshot")));
+    }
+
+    #[test]
+    fn test_log_file_appends() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("persist.log");
+        let config = ObservabilityConfig::default()
+            .with_env_filter("info")
+            .with_writer(LogWriter::file(&path));
+        write_logs(&config, 2);
+        write_logs(&config, 3);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 5);
+
+        let invalid = LogWriter::rotating_file(&path, LogRotation::SizeMB(0), None);
+        assert!(invalid.make_writer().is_err());
+        let invalid = LogWriter::rotating_file(&path, LogRotation::Daily, Some(0));
+        assert!(invalid.make_writer().is_err());
+    }
+
+    #[test]
+    fn test_parse_log_rotation() {
+        for (text, rotation) in [
+            ("never", LogRotation::Never),
+            ("Daily", LogRotation::Daily),
+            ("hourly", LogRotation::Hourly),
+            ("100mb", LogRotation::SizeMB(100)),
+            ("5MB", LogRotation::SizeMB(5)),
+        ] {
+            assert_eq!(text.parse::<LogRotation>().unwrap(), rotation);
+        }
+        assert_eq!(LogRotation::SizeMB(100).to_string(), "100mb");
+        for invalid in ["weekly", "0mb", "mb", "-5mb", "10gb"] {
+            assert!(invalid.parse::<LogRotation>().is_err(), "{invalid}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod sampling_tests {
+    use super::*;
+
+    /// Layer counting the closed spans by name
+    #[derive(Clone, Default)]
+    struct ClosedSpans(Arc<Mutex<HashMap<String, usize>>>);
+
+    impl<S> Layer<S> for ClosedSpans
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        fn on_close(&self, id: tracing::span::Id, ctx: Context<'_, S>) {
+            let name = ctx.span(&id).unwrap().name().to_string();
+            *self.0.lock().unwrap().entry(name).or_default() += 1;
+        }
+    }
+
+    impl ClosedSpans {
+        fn count(&self, name: &str) -> usize {
+            self.0
+                .lock()
+                .unwrap()
+                .get(name)
+                .copied()
+                .unwrap_or_default()
+        }
+    }
+
+    /// Spans closed while running `f` under `config`
+    fn closed_spans(config: &ObservabilityConfig, f: impl FnOnce()) -> ClosedSpans {
+        let closed = ClosedSpans::default();
+        let subscriber = TracingRegistry::default()
+            .with(Sampled::new(closed.clone(), config.span_sampler().unwrap()));
+        tracing::subscriber::with_default(subscriber, f);
+        closed
+    }
+
+    #[test]
+    fn test_span_sample_ratio() {
+        let config = ObservabilityConfig::default().with_span_sample_ratio("save_snapshot", 0.1);
+        let closed = closed_spans(&config, || {
+            for _ in 0..2000 {
+                let _save = tracing::info_span!("save_snapshot").entered();
+                let _load = tracing::info_span!("load_snapshot").entered();
+            }
+        });
+        let sampled = closed.count("save_snapshot");
+        assert!(
+            (120..=280).contains(&sampled),
+            "{sampled} of 2000 spans sampled"
+        );
+        // Operations without a ratio keep all their spans
+        assert_eq!(closed.count("load_snapshot"), 2000);
+    }
+
+    #[test]
+    fn test_failed_spans_are_kept() {
+        let config = ObservabilityConfig::default().with_span_sample_ratio("save_snapshot", 0.1);
+        let closed = closed_spans(&config, || {
+            for i in 0..100 {
+                let save = tracing::info_span!("save_snapshot", error = tracing::field::Empty);
+                let _save = save.enter();
+                match i % 3 {
+                    0 => tracing::error!("Failed to save snapshot"),
+                    1 => {
+                        // A warning in a child span marks the save as failed
+                        let _write = tracing::info_span!("write").entered();
+                        tracing::warn!("Retrying write");
+                    }
+                    _ => {
+                        save.record("error", "timed out");
+                    }
+                }
+            }
+        });
+        assert_eq!(closed.count("save_snapshot"), 100);
+        assert_eq!(closed.count("write"), 33);
+    }
+
+    #[test]
+    fn test_min_span_duration() {
+        let config = ObservabilityConfig::default()
+            .with_min_span_duration(std::time::Duration::from_millis(20));
+        let closed = closed_spans(&config, || {
+            for _ in 0..10 {
+                let _fast = tracing::info_span!("fast").entered();
+            }
+            let _slow = tracing::info_span!("slow").entered();
+            std::thread::sleep(std::time::Duration::from_millis(25));
+            let _failed = tracing::info_span!("failed").entered();
+            tracing::error!("Failed quickly");
+        });
+        assert_eq!(closed.count("fast"), 0);
+        assert_eq!(closed.count("slow"), 1);
+        assert_eq!(closed.count("failed"), 1);
+    }
+
+    #[test]
+    fn test_default_keeps_all_spans() {
+        let config = ObservabilityConfig::default();
+        assert!(config.span_sampler().unwrap().is_none());
+        let closed = closed_spans(&config, || {
+            for _ in 0..100 {
+                let _save = tracing::info_span!("save_snapshot").entered();
+            }
+        });
+        assert_eq!(closed.count("save_snapshot"), 100);
+
+        assert!(ObservabilityConfig::default()
+            .with_span_sample_ratio("save_snapshot", 1.5)
+            .span_sampler()
+            .is_err());
+    }
+
+    #[test]
+    fn test_failed_engine_spans_are_kept() {
+        let config = ObservabilityConfig::default().with_span_sample_ratio("load_snapshot", 0.0);
+        let dir = tempfile::TempDir::new().unwrap();
+        let closed = closed_spans(&config, || {
+            let engine = crate::SnapshotEngine::new(
+                crate::LocalFileStorage::with_base_dir(dir.path()),
+                crate::GzipCompressor::new(),
+            );
+            let metadata = crate::SnapshotMetadata::new("agent", "session", 0);
+            engine
+                .save_snapshot(r#"{"state": 1}"#, &metadata, "agent.json.gz")
+                .unwrap();
+            engine.load_snapshot("agent.json.gz").unwrap();
+            assert!(engine.load_snapshot("missing.json.gz").is_err());
+        });
+        // Only the failed load is kept
+        assert_eq!(closed.count("save_snapshot"), 1);
+        assert_eq!(closed.count("load_snapshot"), 1);
+    }
+}
+
+#[cfg(test)]
+mod health_tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn test_health_report_json() {
+        let report = HealthReport {
+            backend: "s3".to_string(),
+            status: HealthStatus::Unhealthy,
+            deep: true,
+            latency: Duration::from_micros(12_500),
+            last_error: Some("Storage error: bucket not found".to_string()),
+            checked_at: "2026-10-16T09:30:00Z".parse().unwrap(),
+        };
+        let json = report.to_json();
+        assert_eq!(
+            json,
+            json!({
+                "backend": "s3",
+                "status": "unhealthy",
+                "deep": true,
+                "latency_ms": 12.5,
+                "last_error": "Storage error: bucket not found",
+                "checked_at": "2026-10-16T09:30:00Z",
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<HealthReport>(json).unwrap(),
+            report
+        );
+
+        let healthy = HealthReport::new("local", false, Duration::ZERO, &Ok(()));
+        assert!(healthy.is_healthy());
+        assert_eq!(healthy.to_json()["last_error"], serde_json::Value::Null);
+
+        let negative = json!({
+            "backend": "s3",
+            "status": "healthy",
+            "deep": false,
+            "latency_ms": -1.0,
+            "last_error": null,
+            "checked_at": "2026-10-16T09:30:00Z",
+        });
+        assert!(serde_json::from_value::<HealthReport>(negative).is_err());
+    }
+}
+
+#[cfg(test)]
+mod event_tests {
+    use super::*;
+    use crate::{CompressionAdapter, GzipCompressor, LocalFileStorage, SnapshotEngine};
+    use serde_json::json;
+
+    /// JSON of `event` with its duration checked and zeroed
+    fn stable_json(event: &PersistEvent) -> serde_json::Value {
+        let mut json = event.to_json();
+        assert!(json["duration_ms"].as_f64().unwrap() >= 0.0, "{json}");
+        json["duration_ms"] = json!(0.0);
+        json
+    }
+
+    #[test]
+    fn test_event_sequence() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let events = MemoryEventSink::new();
+        let second = MemoryEventSink::new();
+        let engine = SnapshotEngine::new(
+            LocalFileStorage::with_base_dir(dir.path()),
+            GzipCompressor::new(),
+        )
+        .with_event_sink(TracingEventSink)
+        .with_event_sink(events.clone())
+        .with_event_sink(second.clone());
+
+        let metadata = crate::SnapshotMetadata::new("agent", "session", 3);
+        let saved = engine
+            .save_snapshot(r#"{"state": 1}"#, &metadata, "agent.json.gz")
+            .unwrap();
+        let stored_bytes = saved.compressed_size.unwrap() as u64;
+        engine.load_snapshot("agent.json.gz").unwrap();
+
+        // Tamper with the agent state, leaving the stored hash as it was
+        let compressor = GzipCompressor::new();
+        let stored = engine.load_stored("agent.json.gz").unwrap();
+        let mut container: serde_json::Value =
+            serde_json::from_slice(&compressor.decompress(&stored).unwrap()).unwrap();
+        container["agent_state"] = json!({"state": 2});
+        let tampered = compressor
+            .compress(container.to_string().as_bytes())
+            .unwrap();
+        engine.save_stored(&tampered, "agent.json.gz").unwrap();
+        assert!(engine.verify_snapshot("agent.json.gz").is_err());
+
+        engine.delete_snapshot("agent.json.gz").unwrap();
+
+        let emitted = events.take();
+        let names: Vec<_> = emitted.iter().map(PersistEvent::name).collect();
+        assert_eq!(
+            names,
+            [
+                "snapshot_created",
+                "snapshot_loaded",
+                "integrity_failure",
+                "snapshot_deleted"
+            ]
+        );
+        assert_eq!(second.events(), emitted);
+        assert!(events.events().is_empty());
+
+        assert_eq!(
+            stable_json(&emitted[0]),
+            json!({
+                "schema_version": 1,
+                "event": "snapshot_created",
+                "path": "agent.json.gz",
+                "agent_id": "agent",
+                "session_id": "session",
+                "snapshot_index": 3,
+                "raw_bytes": 11,
+                "stored_bytes": stored_bytes,
+                "duration_ms": 0.0,
+            })
+        );
+        assert_eq!(
+            stable_json(&emitted[1]),
+            json!({
+                "schema_version": 1,
+                "event": "snapshot_loaded",
+                "path": "agent.json.gz",
+                "agent_id": "agent",
+                "session_id": "session",
+                "snapshot_index": 3,
+                "raw_bytes": 11,
+                "stored_bytes": stored_bytes,
+                "duration_ms": 0.0,
+            })
+        );
+
+        let failure = stable_json(&emitted[2]);
+        let message = failure["error"].as_str().unwrap().to_string();
+        assert!(message.starts_with("Integrity check failed"), "{message}");
+        assert_eq!(
+            failure,
+            json!({
+                "schema_version": 1,
+                "event": "integrity_failure",
+                "operation": "verify",
+                "path": "agent.json.gz",
+                "error_kind": "integrity_check_failed",
+                "error": message,
+                "duration_ms": 0.0,
+            })
+        );
+        assert_eq!(
+            stable_json(&emitted[3]),
+            json!({
+                "schema_version": 1,
+                "event": "snapshot_deleted",
+                "path": "agent.json.gz",
+                "duration_ms": 0.0,
+            })
+        );
+
+        // Events read back from their JSON are the same
+        for event in emitted {
+            assert_eq!(PersistEvent::from_json(event.to_json()).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn test_operation_failed_event() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let events = MemoryEventSink::new();
+        let engine = SnapshotEngine::new(
+            LocalFileStorage::with_base_dir(dir.path()),
+            GzipCompressor::new(),
+        )
+        .with_event_sink(events.clone());
+
+        assert!(engine.load_snapshot("missing.json.gz").is_err());
+        match events.events().as_slice() {
+            [PersistEvent::OperationFailed(details)] => {
+                assert_eq!(details.operation, "load");
+                assert_eq!(details.path, "missing.json.gz");
+            }
+            other => panic!("unexpected events {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_newer_event_schema_is_rejected() {
+        let json = json!({
+            "schema_version": EVENT_SCHEMA_VERSION + 1,
+            "event": "snapshot_deleted",
+            "path": "agent.json.gz",
+            "duration_ms": 1.5,
+        });
+        assert!(PersistEvent::from_json(json).is_err());
     }
 }
@@ -0,0 +1,362 @@
+/*!
+Structured queries over stored snapshot metadata.
+
+A [`SnapshotQuery`] describes which snapshots to select (agent, session, creation time,
+index range and tags), how to order them and how many to return. It is evaluated by
+[`SnapshotEngine::query`](crate::SnapshotEngine::query) against the storage listing,
+reading only the metadata of each candidate snapshot.
+
+# Example
+```rust,no_run
+use chrono::{Duration, Utc};
+use persist_core::{create_default_engine, SnapshotQuery, SortOrder};
+
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let engine = create_default_engine();
+let query = SnapshotQuery::new()
+    .agent_id("agent_1")
+    .created_after(Utc::now() - Duration::days(1))
+    .min_index(5)
+    .sort(SortOrder::NewestFirst)
+    .limit(20);
+
+for summary in engine.query("snapshots/", &query)? {
+    println!("{} {}", summary.path, summary.metadata.summary());
+}
+# Ok(())
+# }
+```
+*/
+
+use crate::{retention::stored_size, PersistError, SnapshotMetadata};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Ordering applied to query results
+///
+/// Snapshots that compare equal on the sort key are ordered by path, so that the
+/// same query always returns the same order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Most recently created first
+    #[default]
+    NewestFirst,
+    /// Least recently created first
+    OldestFirst,
+    /// Lowest snapshot index first
+    IndexAscending,
+    /// Highest snapshot index first
+    IndexDescending,
+    /// Smallest stored size first
+    SizeAscending,
+    /// Largest stored size first
+    SizeDescending,
+    /// By agent, then session and index, in ascending order
+    AgentAscending,
+    /// By agent, then session and index, in descending order
+    AgentDescending,
+    /// Storage listing order; the only order that can stop reading once the limit is hit
+    Unsorted,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = PersistError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "newest" | "newest_first" | "created" | "created_desc" => Ok(SortOrder::NewestFirst),
+            "oldest" | "oldest_first" | "created_asc" => Ok(SortOrder::OldestFirst),
+            "index" | "index_asc" => Ok(SortOrder::IndexAscending),
+            "index_desc" => Ok(SortOrder::IndexDescending),
+            "size" | "size_asc" => Ok(SortOrder::SizeAscending),
+            "size_desc" => Ok(SortOrder::SizeDescending),
+            "agent" | "agent_asc" => Ok(SortOrder::AgentAscending),
+            "agent_desc" => Ok(SortOrder::AgentDescending),
+            "none" | "unsorted" => Ok(SortOrder::Unsorted),
+            other => Err(PersistError::validation(format!(
+                "Unknown sort order '{other}'. Expected one of: newest, oldest, index, index_desc, size, size_desc, agent, agent_desc, none"
+            ))),
+        }
+    }
+}
+
+impl SortOrder {
+    /// The same sort key in the opposite direction
+    ///
+    /// Ties stay ordered by path; `Unsorted` stays unsorted.
+    pub fn reversed(self) -> Self {
+        match self {
+            SortOrder::NewestFirst => SortOrder::OldestFirst,
+            SortOrder::OldestFirst => SortOrder::NewestFirst,
+            SortOrder::IndexAscending => SortOrder::IndexDescending,
+            SortOrder::IndexDescending => SortOrder::IndexAscending,
+            SortOrder::SizeAscending => SortOrder::SizeDescending,
+            SortOrder::SizeDescending => SortOrder::SizeAscending,
+            SortOrder::AgentAscending => SortOrder::AgentDescending,
+            SortOrder::AgentDescending => SortOrder::AgentAscending,
+            SortOrder::Unsorted => SortOrder::Unsorted,
+        }
+    }
+
+    /// Compare two snapshots on the sort key alone
+    fn compare(self, a: &SnapshotMetadata, b: &SnapshotMetadata) -> Ordering {
+        fn agent(m: &SnapshotMetadata) -> (&str, &str, u64) {
+            (&m.agent_id, &m.session_id, m.snapshot_index)
+        }
+        match self {
+            SortOrder::NewestFirst => b.timestamp.cmp(&a.timestamp),
+            SortOrder::OldestFirst => a.timestamp.cmp(&b.timestamp),
+            SortOrder::IndexAscending => a.snapshot_index.cmp(&b.snapshot_index),
+            SortOrder::IndexDescending => b.snapshot_index.cmp(&a.snapshot_index),
+            SortOrder::SizeAscending => stored_size(a).cmp(&stored_size(b)),
+            SortOrder::SizeDescending => stored_size(b).cmp(&stored_size(a)),
+            SortOrder::AgentAscending => agent(a).cmp(&agent(b)),
+            SortOrder::AgentDescending => agent(b).cmp(&agent(a)),
+            SortOrder::Unsorted => Ordering::Equal,
+        }
+    }
+}
+
+/// A snapshot selected by a query: its storage path and metadata
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotSummary {
+    /// Storage path the snapshot can be loaded from
+    pub path: String,
+    /// Metadata recorded with the snapshot
+    pub metadata: SnapshotMetadata,
+}
+
+/// Filters, ordering and limit for a cross-snapshot metadata query
+///
+/// All filters are optional and combined with AND. Time bounds are inclusive for
+/// `created_after` and exclusive for `created_before`; index bounds are inclusive.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotQuery {
+    pub agent_id: Option<String>,
+    pub session_id: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub min_index: Option<u64>,
+    pub max_index: Option<u64>,
+    pub tags: BTreeMap<String, String>,
+    pub limit: Option<usize>,
+    pub sort: SortOrder,
+}
+
+impl SnapshotQuery {
+    /// Create a query that matches every snapshot, newest first
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match snapshots of this agent
+    pub fn agent_id(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self
+    }
+
+    /// Only match snapshots of this session
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Only match snapshots created at or after `time`
+    pub fn created_after(mut self, time: DateTime<Utc>) -> Self {
+        self.created_after = Some(time);
+        self
+    }
+
+    /// Only match snapshots created strictly before `time`
+    pub fn created_before(mut self, time: DateTime<Utc>) -> Self {
+        self.created_before = Some(time);
+        self
+    }
+
+    /// Only match snapshots with an index of at least `index`
+    pub fn min_index(mut self, index: u64) -> Self {
+        self.min_index = Some(index);
+        self
+    }
+
+    /// Only match snapshots with an index of at most `index`
+    pub fn max_index(mut self, index: u64) -> Self {
+        self.max_index = Some(index);
+        self
+    }
+
+    /// Only match snapshots carrying this tag with this value
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Return at most `limit` snapshots
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Order the results
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Check whether a snapshot's metadata satisfies every filter of this query
+    pub fn matches(&self, metadata: &SnapshotMetadata) -> bool {
+        self.agent_id
+            .as_ref()
+            .is_none_or(|agent_id| &metadata.agent_id == agent_id)
+            && self
+                .session_id
+                .as_ref()
+                .is_none_or(|session_id| &metadata.session_id == session_id)
+            && self
+                .created_after
+                .is_none_or(|after| metadata.timestamp >= after)
+            && self
+                .created_before
+                .is_none_or(|before| metadata.timestamp < before)
+            && self
+                .min_index
+                .is_none_or(|min| metadata.snapshot_index >= min)
+            && self
+                .max_index
+                .is_none_or(|max| metadata.snapshot_index <= max)
+            && self
+                .tags
+                .iter()
+                .all(|(key, value)| metadata.tags.get(key) == Some(value))
+    }
+
+    /// Whether enough matches have been collected to stop reading further snapshots
+    pub(crate) fn is_satisfied(&self, matched: usize) -> bool {
+        self.sort == SortOrder::Unsorted && self.limit.is_some_and(|limit| matched >= limit)
+    }
+
+    /// Apply the sort order and limit to a set of matching snapshots
+    ///
+    /// For callers that read and filter snapshots themselves, e.g. concurrently.
+    pub fn finish(&self, mut results: Vec<SnapshotSummary>) -> Vec<SnapshotSummary> {
+        if self.sort != SortOrder::Unsorted {
+            results.sort_by(|a, b| {
+                self.sort
+                    .compare(&a.metadata, &b.metadata)
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+        }
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_query_matches_everything() {
+        let metadata = SnapshotMetadata::new("agent", "session", 7);
+        assert!(SnapshotQuery::new().matches(&metadata));
+    }
+
+    #[test]
+    fn test_tag_filter_requires_exact_value() {
+        let metadata = SnapshotMetadata::builder("agent", "session", 0)
+            .tag("env", "prod")
+            .build();
+
+        assert!(SnapshotQuery::new().tag("env", "prod").matches(&metadata));
+        assert!(!SnapshotQuery::new().tag("env", "dev").matches(&metadata));
+        assert!(!SnapshotQuery::new().tag("team", "ops").matches(&metadata));
+    }
+
+    #[test]
+    fn test_sort_order_from_str() {
+        assert_eq!(
+            "newest".parse::<SortOrder>().unwrap(),
+            SortOrder::NewestFirst
+        );
+        assert_eq!(
+            "OLDEST".parse::<SortOrder>().unwrap(),
+            SortOrder::OldestFirst
+        );
+        assert_eq!(
+            "index_desc".parse::<SortOrder>().unwrap(),
+            SortOrder::IndexDescending
+        );
+        assert_eq!(
+            "size_desc".parse::<SortOrder>().unwrap(),
+            SortOrder::SizeDescending
+        );
+        assert_eq!("none".parse::<SortOrder>().unwrap(), SortOrder::Unsorted);
+        assert_eq!(
+            "created".parse::<SortOrder>().unwrap(),
+            SortOrder::NewestFirst
+        );
+        assert_eq!(
+            "agent".parse::<SortOrder>().unwrap(),
+            SortOrder::AgentAscending
+        );
+        assert!("sideways".parse::<SortOrder>().is_err());
+    }
+
+    fn summary(path: &str, agent: &str, index: u64, minute: i64, size: usize) -> SnapshotSummary {
+        let base = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut metadata = SnapshotMetadata::new(agent, "session", index);
+        metadata.timestamp = base + chrono::Duration::minutes(minute);
+        metadata.compressed_size = Some(size);
+        SnapshotSummary {
+            path: path.to_string(),
+            metadata,
+        }
+    }
+
+    fn sorted_paths(sort: SortOrder, limit: Option<usize>) -> Vec<String> {
+        // Timestamps 2 and 10 are shared, as are sizes 300 and indexes 1
+        let results = vec![
+            summary("d", "bot", 1, 2, 300),
+            summary("b", "ant", 3, 10, 100),
+            summary("a", "cat", 1, 10, 900),
+            summary("c", "ant", 2, 2, 300),
+        ];
+        let query = SnapshotQuery {
+            sort,
+            limit,
+            ..SnapshotQuery::new()
+        };
+        query.finish(results).into_iter().map(|s| s.path).collect()
+    }
+
+    #[test]
+    fn test_finish_orders_by_key_then_path() {
+        let cases = [
+            (SortOrder::NewestFirst, ["a", "b", "c", "d"]),
+            (SortOrder::OldestFirst, ["c", "d", "a", "b"]),
+            (SortOrder::IndexAscending, ["a", "d", "c", "b"]),
+            (SortOrder::IndexDescending, ["b", "c", "a", "d"]),
+            (SortOrder::SizeAscending, ["b", "c", "d", "a"]),
+            (SortOrder::SizeDescending, ["a", "c", "d", "b"]),
+            (SortOrder::AgentAscending, ["c", "b", "d", "a"]),
+            (SortOrder::AgentDescending, ["a", "d", "b", "c"]),
+            (SortOrder::Unsorted, ["d", "b", "a", "c"]),
+        ];
+        for (sort, expected) in cases {
+            assert_eq!(sorted_paths(sort, None), expected, "{sort:?}");
+            assert_eq!(sort.reversed().reversed(), sort);
+        }
+        // Ties stay in path order when the direction is reversed
+        assert_eq!(
+            sorted_paths(SortOrder::NewestFirst.reversed(), None),
+            ["c", "d", "a", "b"]
+        );
+        assert_eq!(sorted_paths(SortOrder::SizeDescending, Some(2)), ["a", "c"]);
+    }
+}
@@ -0,0 +1,252 @@
+/*!
+Retention rules for pruning stored snapshots.
+
+A [`RetentionPolicy`] decides which snapshots of a listing to delete. Rules are
+optional and combine: a snapshot is deleted if any rule selects it.
+
+# Example
+```rust,no_run
+use chrono::{Duration, Utc};
+use persist_core::{create_default_engine, RetentionPolicy, SnapshotQuery};
+
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let engine = create_default_engine();
+let policy = RetentionPolicy::new()
+    .keep_last(10)
+    .older_than(Duration::days(30));
+
+let snapshots = engine.query("snapshots/", &SnapshotQuery::new())?;
+let plan = policy.plan(snapshots, Utc::now());
+for summary in &plan.delete {
+    engine.delete_snapshot(&summary.path)?;
+}
+# Ok(())
+# }
+```
+*/
+
+use crate::{SnapshotMetadata, SnapshotSummary};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Rules selecting snapshots to delete
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Keep only this many snapshots (highest indexes) per agent and session
+    pub keep_last: Option<usize>,
+    /// Delete snapshots created longer ago than this
+    pub older_than: Option<Duration>,
+    /// Delete the oldest remaining snapshots until their total stored size fits
+    pub max_total_size: Option<u64>,
+}
+
+/// Outcome of applying a [`RetentionPolicy`] to a set of snapshots
+#[derive(Debug, Clone, Default)]
+pub struct PrunePlan {
+    /// Snapshots that survive, sorted by path
+    pub keep: Vec<SnapshotSummary>,
+    /// Snapshots to delete, sorted by path
+    pub delete: Vec<SnapshotSummary>,
+}
+
+impl RetentionPolicy {
+    /// Create a policy without rules, which deletes nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep only the `count` highest-indexed snapshots of each agent and session
+    pub fn keep_last(mut self, count: usize) -> Self {
+        self.keep_last = Some(count);
+        self
+    }
+
+    /// Delete snapshots created more than `age` before the time of pruning
+    pub fn older_than(mut self, age: Duration) -> Self {
+        self.older_than = Some(age);
+        self
+    }
+
+    /// Delete the oldest snapshots until the survivors take at most `bytes`
+    pub fn max_total_size(mut self, bytes: u64) -> Self {
+        self.max_total_size = Some(bytes);
+        self
+    }
+
+    /// Whether the policy has no rules
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none() && self.older_than.is_none() && self.max_total_size.is_none()
+    }
+
+    /// Split `snapshots` into survivors and snapshots to delete as of `now`
+    ///
+    /// `keep_last` and `older_than` are applied first; `max_total_size` then deletes
+    /// the oldest survivors until the rest fit.
+    pub fn plan(&self, snapshots: Vec<SnapshotSummary>, now: DateTime<Utc>) -> PrunePlan {
+        let mut doomed = BTreeSet::new();
+
+        if let Some(keep_last) = self.keep_last {
+            let mut streams: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+            for (i, summary) in snapshots.iter().enumerate() {
+                let metadata = &summary.metadata;
+                streams
+                    .entry((&metadata.agent_id, &metadata.session_id))
+                    .or_default()
+                    .push(i);
+            }
+            for mut stream in streams.into_values() {
+                stream.sort_by_key(|&i| {
+                    let metadata = &snapshots[i].metadata;
+                    std::cmp::Reverse((metadata.snapshot_index, metadata.timestamp))
+                });
+                doomed.extend(stream.into_iter().skip(keep_last));
+            }
+        }
+
+        if let Some(age) = self.older_than {
+            let cutoff = now - age;
+            doomed
+                .extend((0..snapshots.len()).filter(|&i| snapshots[i].metadata.timestamp < cutoff));
+        }
+
+        if let Some(max_total_size) = self.max_total_size {
+            let mut survivors: Vec<usize> = (0..snapshots.len())
+                .filter(|i| !doomed.contains(i))
+                .collect();
+            survivors.sort_by_key(|&i| snapshots[i].metadata.timestamp);
+            let mut total: u64 = survivors
+                .iter()
+                .map(|&i| stored_size(&snapshots[i].metadata))
+                .sum();
+            for i in survivors {
+                if total <= max_total_size {
+                    break;
+                }
+                total -= stored_size(&snapshots[i].metadata);
+                doomed.insert(i);
+            }
+        }
+
+        let mut plan = PrunePlan::default();
+        for (i, summary) in snapshots.into_iter().enumerate() {
+            if doomed.contains(&i) {
+                plan.delete.push(summary);
+            } else {
+                plan.keep.push(summary);
+            }
+        }
+        plan.keep.sort_by(|a, b| a.path.cmp(&b.path));
+        plan.delete.sort_by(|a, b| a.path.cmp(&b.path));
+        plan
+    }
+}
+
+impl PrunePlan {
+    /// Total stored size of the snapshots to delete, in bytes
+    pub fn delete_size(&self) -> u64 {
+        self.delete.iter().map(|s| stored_size(&s.metadata)).sum()
+    }
+
+    /// Total stored size of the surviving snapshots, in bytes
+    pub fn keep_size(&self) -> u64 {
+        self.keep.iter().map(|s| stored_size(&s.metadata)).sum()
+    }
+}
+
+/// Bytes a snapshot takes in storage: its compressed size, if recorded
+pub fn stored_size(metadata: &SnapshotMetadata) -> u64 {
+    metadata
+        .compressed_size
+        .unwrap_or(metadata.uncompressed_size) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()
+    }
+
+    /// Snapshot `index` of `agent`, created `age_days` before [`now`], taking `size` bytes
+    fn snapshot(agent: &str, index: u64, age_days: i64, size: usize) -> SnapshotSummary {
+        let mut metadata = SnapshotMetadata::new(agent, "session", index);
+        metadata.timestamp = now() - Duration::days(age_days);
+        metadata.compressed_size = Some(size);
+        SnapshotSummary {
+            path: format!("{agent}/{index}.json.gz"),
+            metadata,
+        }
+    }
+
+    /// Corpus of two agents: `a` with indexes 0..=3 and `b` with indexes 0..=1
+    fn corpus() -> Vec<SnapshotSummary> {
+        vec![
+            snapshot("a", 0, 40, 100),
+            snapshot("a", 1, 30, 100),
+            snapshot("a", 2, 20, 100),
+            snapshot("a", 3, 10, 100),
+            snapshot("b", 0, 35, 300),
+            snapshot("b", 1, 5, 300),
+        ]
+    }
+
+    fn paths(summaries: &[SnapshotSummary]) -> Vec<&str> {
+        summaries.iter().map(|s| s.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_empty_policy_keeps_everything() {
+        let plan = RetentionPolicy::new().plan(corpus(), now());
+        assert!(RetentionPolicy::new().is_empty());
+        assert!(plan.delete.is_empty());
+        assert_eq!(plan.keep.len(), 6);
+    }
+
+    #[test]
+    fn test_keep_last_per_agent_and_session() {
+        let plan = RetentionPolicy::new().keep_last(1).plan(corpus(), now());
+        assert_eq!(paths(&plan.keep), ["a/3.json.gz", "b/1.json.gz"]);
+        assert_eq!(
+            paths(&plan.delete),
+            ["a/0.json.gz", "a/1.json.gz", "a/2.json.gz", "b/0.json.gz"]
+        );
+        assert_eq!(plan.delete_size(), 600);
+        assert_eq!(plan.keep_size(), 400);
+    }
+
+    #[test]
+    fn test_older_than() {
+        let plan = RetentionPolicy::new()
+            .older_than(Duration::days(30))
+            .plan(corpus(), now());
+        assert_eq!(paths(&plan.delete), ["a/0.json.gz", "b/0.json.gz"]);
+    }
+
+    #[test]
+    fn test_max_total_size_deletes_oldest_first() {
+        let plan = RetentionPolicy::new()
+            .max_total_size(500)
+            .plan(corpus(), now());
+        // 1000 bytes in total; dropping a/0 (100), b/0 (300) and a/1 (100) leaves 500
+        assert_eq!(
+            paths(&plan.delete),
+            ["a/0.json.gz", "a/1.json.gz", "b/0.json.gz"]
+        );
+        assert_eq!(plan.keep_size(), 500);
+    }
+
+    #[test]
+    fn test_combined_rules() {
+        let plan = RetentionPolicy::new()
+            .keep_last(3)
+            .older_than(Duration::days(32))
+            .max_total_size(400)
+            .plan(corpus(), now());
+        // keep_last drops a/0, older_than drops b/0; a/1..=3 and b/1 take 600 bytes,
+        // so a/1 and a/2 go as the oldest survivors
+        assert_eq!(paths(&plan.keep), ["a/3.json.gz", "b/1.json.gz"]);
+        assert_eq!(plan.delete.len(), 4);
+    }
+}
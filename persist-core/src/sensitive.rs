@@ -0,0 +1,306 @@
+/*!
+Protection of sensitive metadata fields.
+
+Descriptions and tags often carry customer identifiers. Fields listed in
+[`SnapshotMetadata::sensitive_fields`] are:
+
+- encrypted at rest when the engine is configured with a [`MetadataCipher`]
+  (see [`SnapshotEngine::with_metadata_cipher`](crate::SnapshotEngine::with_metadata_cipher)),
+  and decrypted again on load by an engine holding the same cipher;
+- redacted to a short hash in display output ([`Display`](std::fmt::Display), CLI `show`,
+  Python metadata dicts) unless explicitly revealed.
+
+Snapshots with encrypted metadata still load without the cipher: the sensitive values
+simply stay encrypted, and revealing them fails with `PersistError::Encryption`.
+
+# Example
+```rust
+use persist_core::sensitive::SensitiveField;
+use persist_core::SnapshotMetadata;
+
+let metadata = SnapshotMetadata::builder("agent", "session", 0)
+    .description("refund for customer Jane Doe")
+    .sensitive(SensitiveField::Description)
+    .build();
+
+assert!(!metadata.to_string().contains("Jane Doe"));
+assert!(metadata.revealed().unwrap().to_string().contains("Jane Doe"));
+```
+*/
+
+use crate::{metadata::HashAlgorithm, PersistError, Result, SnapshotMetadata};
+use serde::{Deserialize, Serialize};
+
+/// Prefix marking a stored metadata value as ciphertext
+pub const ENCRYPTED_VALUE_PREFIX: &str = "enc:";
+
+/// Number of digest characters shown in redacted values
+const REDACTED_DIGEST_LEN: usize = 12;
+
+/// Metadata fields that can be marked sensitive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveField {
+    /// The snapshot description
+    Description,
+    /// All tag values (tag keys stay in plaintext so they remain listable)
+    Tags,
+}
+
+impl SensitiveField {
+    /// Field name as stored in metadata
+    pub fn name(&self) -> &'static str {
+        match self {
+            SensitiveField::Description => "description",
+            SensitiveField::Tags => "tags",
+        }
+    }
+}
+
+impl std::str::FromStr for SensitiveField {
+    type Err = PersistError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "description" => Ok(SensitiveField::Description),
+            "tags" => Ok(SensitiveField::Tags),
+            other => Err(PersistError::validation(format!(
+                "Unknown sensitive field '{other}'. Expected one of: description, tags"
+            ))),
+        }
+    }
+}
+
+/// Port for encrypting sensitive metadata values
+///
+/// Implementations must return printable ciphertext (e.g. base64 or hex) that
+/// `decrypt` accepts back. The engine adds [`ENCRYPTED_VALUE_PREFIX`] itself.
+pub trait MetadataCipher: Send + Sync {
+    /// Encrypt a plaintext metadata value
+    fn encrypt(&self, plaintext: &str) -> Result<String>;
+
+    /// Decrypt a value previously returned by `encrypt`
+    fn decrypt(&self, ciphertext: &str) -> Result<String>;
+}
+
+/// Display adapter showing sensitive fields unredacted
+///
+/// Created by [`SnapshotMetadata::revealed`].
+pub struct RevealedMetadata<'a>(&'a SnapshotMetadata);
+
+impl RevealedMetadata<'_> {
+    /// The underlying metadata, with sensitive values in plaintext
+    pub fn metadata(&self) -> &SnapshotMetadata {
+        self.0
+    }
+}
+
+impl std::fmt::Display for RevealedMetadata<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.write_fields(f)
+    }
+}
+
+/// Whether a stored metadata value is ciphertext
+fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_VALUE_PREFIX)
+}
+
+/// Replace a value with a short hash that identifies it without disclosing it
+fn redact(value: &str) -> String {
+    let digest = HashAlgorithm::Sha256.digest(value.as_bytes());
+    format!(
+        "[redacted {}:{}]",
+        HashAlgorithm::Sha256.name(),
+        &digest[..REDACTED_DIGEST_LEN]
+    )
+}
+
+impl SnapshotMetadata {
+    /// Whether `field` is marked sensitive
+    pub fn is_sensitive(&self, field: SensitiveField) -> bool {
+        self.sensitive_fields.contains(&field)
+    }
+
+    /// Whether any sensitive value is currently stored as ciphertext
+    pub fn has_encrypted_fields(&self) -> bool {
+        self.sensitive_values().into_iter().any(|v| is_encrypted(v))
+    }
+
+    /// Encrypt every sensitive value that is still in plaintext
+    pub fn encrypt_sensitive(&mut self, cipher: &dyn MetadataCipher) -> Result<()> {
+        for value in self.sensitive_values_mut() {
+            if !is_encrypted(value) {
+                *value = format!("{ENCRYPTED_VALUE_PREFIX}{}", cipher.encrypt(value)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt every sensitive value stored as ciphertext
+    ///
+    /// # Errors
+    /// * `PersistError::Encryption` - If the cipher cannot decrypt a value (e.g. wrong key)
+    pub fn decrypt_sensitive(&mut self, cipher: &dyn MetadataCipher) -> Result<()> {
+        for value in self.sensitive_values_mut() {
+            if let Some(ciphertext) = value.strip_prefix(ENCRYPTED_VALUE_PREFIX) {
+                *value = cipher.decrypt(ciphertext)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy of this metadata with every sensitive value replaced by a short hash
+    pub fn redacted(&self) -> SnapshotMetadata {
+        let mut redacted = self.clone();
+        for value in redacted.sensitive_values_mut() {
+            *value = redact(value);
+        }
+        redacted
+    }
+
+    /// Display this metadata with sensitive values shown in plaintext
+    ///
+    /// # Errors
+    /// * `PersistError::Encryption` - If sensitive values are still encrypted, i.e. the
+    ///   snapshot was loaded without the metadata cipher
+    pub fn revealed(&self) -> Result<RevealedMetadata<'_>> {
+        if self.has_encrypted_fields() {
+            return Err(PersistError::encryption(
+                "Sensitive metadata is encrypted; configure the metadata encryption key to reveal it",
+            ));
+        }
+        Ok(RevealedMetadata(self))
+    }
+
+    /// Current values of all sensitive fields
+    pub(crate) fn sensitive_values(&self) -> Vec<&String> {
+        let mut values = Vec::new();
+        if self.is_sensitive(SensitiveField::Description) {
+            values.extend(self.description.as_ref());
+        }
+        if self.is_sensitive(SensitiveField::Tags) {
+            values.extend(self.tags.values());
+        }
+        values
+    }
+
+    /// Mutable access to all sensitive values (borrows fields disjointly)
+    fn sensitive_values_mut(&mut self) -> Vec<&mut String> {
+        let mut values = Vec::new();
+        if self.sensitive_fields.contains(&SensitiveField::Description) {
+            values.extend(self.description.as_mut());
+        }
+        if self.sensitive_fields.contains(&SensitiveField::Tags) {
+            values.extend(self.tags.values_mut());
+        }
+        values
+    }
+}
+
+/// Reversible test cipher (XOR with a single-byte key, hex encoded)
+#[cfg(test)]
+pub(crate) struct XorCipher(pub u8);
+
+#[cfg(test)]
+impl MetadataCipher for XorCipher {
+    fn encrypt(&self, plaintext: &str) -> Result<String> {
+        Ok(plaintext
+            .bytes()
+            .map(|b| format!("{:02x}", b ^ self.0))
+            .collect())
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String> {
+        let bytes = (0..ciphertext.len())
+            .step_by(2)
+            .map(|i| {
+                ciphertext
+                    .get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .map(|b| b ^ self.0)
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| PersistError::encryption("malformed ciphertext"))?;
+        String::from_utf8(bytes).map_err(|_| PersistError::encryption("decryption failed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensitive_metadata() -> SnapshotMetadata {
+        SnapshotMetadata::builder("agent", "session", 0)
+            .description("customer Jane Doe")
+            .tag("customer", "acme-corp")
+            .sensitive(SensitiveField::Description)
+            .sensitive(SensitiveField::Tags)
+            .build()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let original = sensitive_metadata();
+        let mut metadata = original.clone();
+
+        metadata.encrypt_sensitive(&XorCipher(0x5a)).unwrap();
+        assert!(metadata.has_encrypted_fields());
+        assert!(metadata
+            .description
+            .as_deref()
+            .unwrap()
+            .starts_with(ENCRYPTED_VALUE_PREFIX));
+        assert!(!serde_json::to_string(&metadata).unwrap().contains("acme"));
+
+        metadata.decrypt_sensitive(&XorCipher(0x5a)).unwrap();
+        assert_eq!(metadata, original);
+    }
+
+    #[test]
+    fn test_display_redacts_sensitive_fields() {
+        let metadata = sensitive_metadata();
+        let output = metadata.to_string();
+
+        assert!(!output.contains("Jane Doe"));
+        assert!(!output.contains("acme-corp"));
+        assert!(output.contains("Description: [redacted sha256:"));
+        assert!(output.contains("customer=[redacted sha256:"));
+
+        let revealed = metadata.revealed().unwrap().to_string();
+        assert!(revealed.contains("Description: customer Jane Doe"));
+        assert!(revealed.contains("customer=acme-corp"));
+    }
+
+    #[test]
+    fn test_reveal_without_key_fails() {
+        let mut metadata = sensitive_metadata();
+        metadata.encrypt_sensitive(&XorCipher(0x5a)).unwrap();
+
+        assert!(matches!(
+            metadata.revealed(),
+            Err(PersistError::Encryption(_))
+        ));
+        // Redacted display still works without the key
+        assert!(metadata
+            .to_string()
+            .contains("Description: [redacted sha256:"));
+    }
+
+    #[test]
+    fn test_non_sensitive_fields_untouched() {
+        let mut metadata = SnapshotMetadata::builder("agent", "session", 0)
+            .description("public note")
+            .tag("env", "prod")
+            .sensitive(SensitiveField::Tags)
+            .build();
+        metadata.encrypt_sensitive(&XorCipher(1)).unwrap();
+
+        assert_eq!(metadata.description.as_deref(), Some("public note"));
+        assert!(metadata.tags["env"].starts_with(ENCRYPTED_VALUE_PREFIX));
+        assert_eq!(
+            "tags".parse::<SensitiveField>().unwrap(),
+            SensitiveField::Tags
+        );
+    }
+}
@@ -5,13 +5,33 @@ This module contains the core business logic for creating and restoring snapshot
 orchestrating the metadata, compression, and storage components.
 */
 
+use crate::observability::{EventSink, HealthReport, PersistEvent, SnapshotEventDetails};
+#[cfg(feature = "metrics")]
+use crate::observability::{OperationLabels, PersistMetrics};
 use crate::{
-    compression::CompressionAdapter, storage::StorageAdapter, PersistError, Result,
-    SnapshotMetadata,
+    compression::{self, CompressionAdapter},
+    diff::SnapshotDiff,
+    metadata::{
+        Compatibility, CompatibilityMode, FrameworkRequirement, METADATA_FORMAT_MINOR_VERSION,
+        METADATA_FORMAT_VERSION,
+    },
+    migration::{self, MigrationReport},
+    query::{SnapshotQuery, SnapshotSummary, SortOrder},
+    sensitive::MetadataCipher,
+    storage::{CallOptions, StorageAdapter},
+    PersistError, Result, SnapshotMetadata,
 };
 use serde_json;
 #[cfg(feature = "gcs")]
 use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How long [`SnapshotEngine::health`] reuses a health report unless configured otherwise
+pub const DEFAULT_HEALTH_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Prefix of the probe objects written by deep health checks
+const HEALTH_PROBE_PREFIX: &str = ".persist-health-probe-";
 
 /// Container for the complete snapshot data (metadata + agent state)
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -20,6 +40,66 @@ struct SnapshotContainer {
     agent_state: serde_json::Value,
 }
 
+/// Container view that deserializes only the metadata, skipping over the agent state
+#[derive(serde::Deserialize)]
+struct MetadataOnlyContainer {
+    metadata: SnapshotMetadata,
+}
+
+/// Directory-like prefix of a storage path (everything up to and including the last `/`)
+pub(crate) fn parent_prefix(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(idx) => &path[..=idx],
+        None => "",
+    }
+}
+
+/// Event fields of the snapshot at `path`, stored in `stored_bytes`
+fn event_details(
+    path: &str,
+    metadata: &SnapshotMetadata,
+    stored_bytes: usize,
+    duration_ms: f64,
+) -> SnapshotEventDetails {
+    SnapshotEventDetails {
+        path: path.to_string(),
+        agent_id: metadata.agent_id.clone(),
+        session_id: metadata.session_id.clone(),
+        snapshot_index: metadata.snapshot_index,
+        raw_bytes: metadata.uncompressed_size as u64,
+        stored_bytes: stored_bytes as u64,
+        duration_ms,
+    }
+}
+
+/// Current metadata format version as a "major.minor" string
+fn current_format_version() -> String {
+    format!("{METADATA_FORMAT_VERSION}.{METADATA_FORMAT_MINOR_VERSION}")
+}
+
+/// Major and minor format version of an unparsed snapshot container, where present
+fn raw_format_version(container: &serde_json::Value) -> (Option<u8>, Option<u8>) {
+    let version = |name| {
+        container
+            .get("metadata")?
+            .get(name)?
+            .as_u64()
+            .and_then(|version| u8::try_from(version).ok())
+    };
+    (version("format_version"), version("format_minor_version"))
+}
+
+/// Outcome of [`SnapshotEngine::recompress_snapshot`]
+#[derive(Debug, Clone)]
+pub struct Recompressed {
+    /// Metadata as now stored, with the new compression algorithm and size
+    pub metadata: SnapshotMetadata,
+    /// Stored size before recompression, in bytes
+    pub before: usize,
+    /// Stored size after recompression, in bytes
+    pub after: usize,
+}
+
 /// Main engine for snapshot and restore operations
 ///
 /// This is the primary interface for the core functionality. It orchestrates
@@ -53,6 +133,16 @@ where
 {
     storage: S,
     compressor: C,
+    framework_requirement: Option<FrameworkRequirement>,
+    strict_index: bool,
+    metadata_cipher: Option<Box<dyn MetadataCipher>>,
+    compatibility_mode: CompatibilityMode,
+    #[cfg(feature = "metrics")]
+    metrics: Option<PersistMetrics>,
+    event_sinks: Vec<Box<dyn EventSink>>,
+    health_cache_ttl: Duration,
+    /// Latest shallow and deep health reports, with when they were made
+    health_cache: Mutex<[Option<(Instant, HealthReport)>; 2]>,
 }
 
 impl<S, C> SnapshotEngine<S, C>
@@ -69,6 +159,187 @@ where
         Self {
             storage,
             compressor,
+            framework_requirement: None,
+            strict_index: false,
+            metadata_cipher: None,
+            compatibility_mode: CompatibilityMode::Strict,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            event_sinks: Vec::new(),
+            health_cache_ttl: DEFAULT_HEALTH_CACHE_TTL,
+            health_cache: Mutex::new([None, None]),
+        }
+    }
+
+    /// Enforce a framework compatibility check on every load
+    ///
+    /// Snapshots whose recorded framework or framework version does not satisfy
+    /// the requirement are rejected with `PersistError::FrameworkMismatch`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use persist_core::metadata::{FrameworkCompatPolicy, FrameworkRequirement};
+    /// use persist_core::{GzipCompressor, LocalFileStorage, SnapshotEngine};
+    ///
+    /// let engine = SnapshotEngine::new(LocalFileStorage::new(), GzipCompressor::new())
+    ///     .with_framework_requirement(FrameworkRequirement::new(
+    ///         "langchain",
+    ///         "0.3.4",
+    ///         FrameworkCompatPolicy::SameMinor,
+    ///     ));
+    /// ```
+    pub fn with_framework_requirement(mut self, requirement: FrameworkRequirement) -> Self {
+        self.framework_requirement = Some(requirement);
+        self
+    }
+
+    /// Reject saves whose index does not advance the agent/session history
+    ///
+    /// When enabled, `save_snapshot` looks up the latest existing index for the
+    /// snapshot's agent and session among snapshots in the same directory as the
+    /// target path, and fails with `PersistError::IndexOutOfOrder` unless the new
+    /// index is strictly greater. The check is not atomic with the write: two
+    /// concurrent writers can still both pass it.
+    pub fn with_strict_index(mut self, strict: bool) -> Self {
+        self.strict_index = strict;
+        self
+    }
+
+    /// Choose how snapshots with an incompatible format version are handled on load
+    ///
+    /// `CompatibilityMode::Strict` (the default) rejects them. `Warn` logs a warning and
+    /// proceeds if the container can still be parsed, and `Force` proceeds silently;
+    /// both are intended for data recovery. Integrity verification always applies.
+    pub fn with_compatibility_mode(mut self, mode: CompatibilityMode) -> Self {
+        self.compatibility_mode = mode;
+        self
+    }
+
+    /// Change the compatibility mode of an existing engine
+    pub fn set_compatibility_mode(&mut self, mode: CompatibilityMode) {
+        self.compatibility_mode = mode;
+    }
+
+    /// Encrypt sensitive metadata fields at rest with `cipher`
+    ///
+    /// Fields listed in `SnapshotMetadata::sensitive_fields` are encrypted on save and
+    /// decrypted on load. Engines without a cipher can still load such snapshots; the
+    /// sensitive values are then returned encrypted.
+    pub fn with_metadata_cipher(mut self, cipher: impl MetadataCipher + 'static) -> Self {
+        self.metadata_cipher = Some(Box::new(cipher));
+        self
+    }
+
+    /// Record engine operations in `metrics` instead of [`PersistMetrics::global`]
+    ///
+    /// Only the engine's own metrics move; give the storage adapter the same metrics to
+    /// also record its operations there.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: PersistMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Send the lifecycle events of saves, loads, verifications and deletions to `sink`
+    ///
+    /// Engines can have several sinks; each gets every event, in the order the sinks
+    /// were added.
+    ///
+    /// # Example
+    /// ```rust
+    /// use persist_core::observability::{MemoryEventSink, TracingEventSink};
+    /// use persist_core::{GzipCompressor, LocalFileStorage, SnapshotEngine};
+    ///
+    /// let events = MemoryEventSink::new();
+    /// let engine = SnapshotEngine::new(LocalFileStorage::new(), GzipCompressor::new())
+    ///     .with_event_sink(TracingEventSink)
+    ///     .with_event_sink(events.clone());
+    /// ```
+    pub fn with_event_sink(mut self, sink: impl EventSink + 'static) -> Self {
+        self.event_sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Reuse health reports for `ttl` (default [`DEFAULT_HEALTH_CACHE_TTL`])
+    ///
+    /// Readiness probes of many replicas would otherwise each reach the storage
+    /// backend; `Duration::ZERO` checks every time.
+    pub fn with_health_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.health_cache_ttl = ttl;
+        self
+    }
+
+    /// Run the engine `operation` on `path`, recording its duration and outcome in
+    /// [`PersistMetrics`] and sending its event to the event sinks
+    ///
+    /// `event` builds the event of a success; failures are reported with
+    /// [`PersistEvent::failure`]. `operation` is a metric label, so it must be one of a
+    /// few fixed names. The agent and session labels come from `known` metadata, given
+    /// before the operation runs, or else from the event of a success.
+    fn record_operation<T>(
+        &self,
+        operation: &str,
+        path: &str,
+        known: Option<&SnapshotMetadata>,
+        run: impl FnOnce() -> Result<T>,
+        event: impl FnOnce(&T, f64) -> PersistEvent,
+    ) -> Result<T> {
+        let start = std::time::Instant::now();
+        let result = run();
+        let elapsed = start.elapsed();
+        let duration_ms = elapsed.as_secs_f64() * 1000.0;
+        let event = match &result {
+            Ok(value) => event(value, duration_ms),
+            Err(e) => {
+                // Keeps the failed save or load span when spans are sampled
+                tracing::Span::current().record("error", tracing::field::display(e));
+                PersistEvent::failure(operation, path, e, duration_ms)
+            }
+        };
+
+        #[cfg(feature = "metrics")]
+        {
+            let details = event.snapshot_details();
+            let labels = OperationLabels {
+                operation,
+                backend: self.storage.backend_name(),
+                agent_id: known
+                    .map(|metadata| metadata.agent_id.as_str())
+                    .or(details.map(|details| details.agent_id.as_str())),
+                session_id: known
+                    .map(|metadata| metadata.session_id.as_str())
+                    .or(details.map(|details| details.session_id.as_str())),
+            };
+            self.metrics()
+                .record_engine_operation_with(&labels, elapsed, result.is_ok());
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = known;
+
+        for sink in &self.event_sinks {
+            sink.emit(event.clone());
+        }
+        result
+    }
+
+    /// Record the raw (agent state JSON) and compressed (stored) sizes of a snapshot
+    fn record_payload(&self, raw_size: usize, compressed_size: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            let metrics = self.metrics();
+            metrics.record_engine_payload("raw", raw_size);
+            metrics.record_engine_payload("compressed", compressed_size);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = (raw_size, compressed_size);
+    }
+
+    /// Metrics the engine operations are recorded in
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> &PersistMetrics {
+        match &self.metrics {
+            Some(metrics) => metrics,
+            None => PersistMetrics::global(),
         }
     }
 
@@ -95,12 +366,46 @@ where
     /// * `PersistError::Json` - If the agent JSON is invalid
     /// * `PersistError::Compression` - If compression fails
     /// * `PersistError::Storage` - If saving to storage fails
-    #[tracing::instrument(level = "info", skip(self, agent_json), fields(agent_id = %metadata.agent_id, session_id = %metadata.session_id, path = %path, size = agent_json.len()))]
+    #[tracing::instrument(level = "info", skip(self, agent_json), fields(agent_id = %metadata.agent_id, session_id = %metadata.session_id, path = %path, size = agent_json.len(), error = tracing::field::Empty))]
     pub fn save_snapshot(
         &self,
         agent_json: &str,
         metadata: &SnapshotMetadata,
         path: &str,
+    ) -> Result<SnapshotMetadata> {
+        self.save_snapshot_with_options(agent_json, metadata, path, &CallOptions::default())
+    }
+
+    /// [`save_snapshot`](Self::save_snapshot) with per-call storage options
+    ///
+    /// The options apply to the storage write; their progress callback sees the
+    /// compressed bytes written.
+    pub fn save_snapshot_with_options(
+        &self,
+        agent_json: &str,
+        metadata: &SnapshotMetadata,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<SnapshotMetadata> {
+        self.record_operation(
+            "save",
+            path,
+            Some(metadata),
+            || self.write_snapshot(agent_json, metadata, path, options),
+            |saved, duration_ms| {
+                let stored_bytes = saved.compressed_size.unwrap_or_default();
+                PersistEvent::SnapshotCreated(event_details(path, saved, stored_bytes, duration_ms))
+            },
+        )
+    }
+
+    /// Body of [`save_snapshot_with_options`](Self::save_snapshot_with_options)
+    fn write_snapshot(
+        &self,
+        agent_json: &str,
+        metadata: &SnapshotMetadata,
+        path: &str,
+        options: &CallOptions,
     ) -> Result<SnapshotMetadata> {
         // Parse and validate the agent JSON
         let agent_state: serde_json::Value =
@@ -112,33 +417,45 @@ where
 
         // Update metadata with content hash and size information (using normalized JSON)
         let agent_bytes = normalized_agent_json.as_bytes();
-        let mut updated_metadata = metadata
-            .clone()
-            .with_content_hash(agent_bytes)
-            .with_compression_algorithm(self.compressor.algorithm_name());
+        let mut updated_metadata = metadata.clone();
+        updated_metadata.set_content_hash(agent_bytes);
+        updated_metadata.set_compression_algorithm(self.compressor.algorithm_name());
 
         // Validate metadata
         updated_metadata.validate()?;
 
-        // Create the snapshot container
+        // Reject out-of-order indices in strict mode
+        if self.strict_index {
+            if let Some(latest) = self.latest_index(
+                parent_prefix(path),
+                &updated_metadata.agent_id,
+                &updated_metadata.session_id,
+            )? {
+                if updated_metadata.snapshot_index <= latest {
+                    return Err(PersistError::IndexOutOfOrder {
+                        agent_id: updated_metadata.agent_id.clone(),
+                        session_id: updated_metadata.session_id.clone(),
+                        index: updated_metadata.snapshot_index,
+                        latest,
+                    });
+                }
+            }
+        }
+
+        // Create the snapshot container, encrypting sensitive metadata if configured
+        let mut stored_metadata = updated_metadata.clone();
+        if let Some(cipher) = &self.metadata_cipher {
+            stored_metadata.encrypt_sensitive(cipher.as_ref())?;
+        }
         let container = SnapshotContainer {
-            metadata: updated_metadata.clone(),
+            metadata: stored_metadata,
             agent_state,
         };
 
-        // Serialize the container to JSON
-        let container_json = serde_json::to_string(&container).map_err(PersistError::Json)?;
-
-        // Compress the JSON data
-        let compressed_data = self.compressor.compress(container_json.as_bytes())?;
-
-        // Update metadata with compressed size
-        updated_metadata = updated_metadata.with_compressed_size(compressed_data.len());
-
-        // Save to storage
-        self.storage
-            .save(&compressed_data, path)
-            .map_err(|e| PersistError::Storage(format!("Failed to save snapshot: {e}")))?;
+        // Serialize, compress and save the container, then record the compressed size
+        let compressed_size = self.write_container(&container, path, options)?;
+        updated_metadata.set_compressed_size(compressed_size);
+        self.record_payload(normalized_agent_json.len(), compressed_size);
 
         Ok(updated_metadata)
     }
@@ -163,43 +480,473 @@ where
     /// * `PersistError::Storage` - If loading from storage fails
     /// * `PersistError::Compression` - If decompression fails
     /// * `PersistError::Json` - If JSON parsing fails
-    /// * `PersistError::InvalidFormat` - If the snapshot format is incompatible
+    /// * `PersistError::NeedsMigration` - If the snapshot uses an older, migratable format
+    /// * `PersistError::InvalidFormat` - If the snapshot format is unsupported
     /// * `PersistError::IntegrityCheckFailed` - If the content hash doesn't match
-    #[tracing::instrument(level = "info", skip(self), fields(path = %path))]
+    /// * `PersistError::FrameworkMismatch` - If a framework requirement is configured and not met
+    #[tracing::instrument(level = "info", skip(self), fields(path = %path, error = tracing::field::Empty))]
     pub fn load_snapshot(&self, path: &str) -> Result<(SnapshotMetadata, String)> {
-        // Load compressed data from storage
+        self.load_snapshot_with_options(path, &CallOptions::default())
+    }
+
+    /// [`load_snapshot`](Self::load_snapshot) with per-call storage options
+    ///
+    /// The options apply to the storage read; their progress callback sees the
+    /// compressed bytes read.
+    pub fn load_snapshot_with_options(
+        &self,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<(SnapshotMetadata, String)> {
+        self.record_operation(
+            "load",
+            path,
+            None,
+            || self.read_snapshot(path, options),
+            |(metadata, _, stored_bytes), duration_ms| {
+                PersistEvent::SnapshotLoaded(event_details(
+                    path,
+                    metadata,
+                    *stored_bytes,
+                    duration_ms,
+                ))
+            },
+        )
+        .map(|(metadata, agent_json, _)| (metadata, agent_json))
+    }
+
+    /// Body of [`load_snapshot_with_options`](Self::load_snapshot_with_options), also
+    /// returning the stored size
+    fn read_snapshot(
+        &self,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<(SnapshotMetadata, String, usize)> {
+        let (container, stored_size) = self.load_container(path, options)?;
+
+        // Convert agent state back to JSON string (normalized format)
+        let agent_json =
+            serde_json::to_string(&container.agent_state).map_err(PersistError::Json)?;
+
+        // Verify integrity
+        container.metadata.verify_integrity(agent_json.as_bytes())?;
+        self.record_payload(agent_json.len(), stored_size);
+
+        // Enforce framework compatibility if configured
+        if let Some(requirement) = &self.framework_requirement {
+            requirement.check(&container.metadata)?;
+        }
+
+        let mut metadata = container.metadata;
+        if let Some(cipher) = &self.metadata_cipher {
+            metadata.decrypt_sensitive(cipher.as_ref())?;
+        }
+
+        Ok((metadata, agent_json, stored_size))
+    }
+
+    /// Check raw snapshot bytes as [`load_snapshot`](Self::load_snapshot) would, without
+    /// storing or loading anything
+    ///
+    /// The data must decompress, parse as a snapshot container with a compatible format
+    /// version, pass the integrity check and meet the framework requirement, if any. This
+    /// lets tools that move stored objects around as opaque bytes validate them first.
+    ///
+    /// # Arguments
+    /// * `data` - Snapshot bytes as stored (compressed container)
+    ///
+    /// # Returns
+    /// The snapshot metadata as stored (sensitive fields are not decrypted)
+    pub fn verify_container(&self, data: &[u8]) -> Result<SnapshotMetadata> {
+        let container = self.parse_container(data, "<bytes>")?;
+        let agent_json =
+            serde_json::to_string(&container.agent_state).map_err(PersistError::Json)?;
+        container.metadata.verify_integrity(agent_json.as_bytes())?;
+        if let Some(requirement) = &self.framework_requirement {
+            requirement.check(&container.metadata)?;
+        }
+        Ok(container.metadata)
+    }
+
+    /// Update the mutable metadata of a stored snapshot in place
+    ///
+    /// Only `description`, `tags` and `expires_at` may be changed by `update`; the agent
+    /// state, `content_hash`, `timestamp` and all other fields are preserved. The snapshot
+    /// is verified before being rewritten, and the rewrite goes through the storage
+    /// adapter's `save`, which must overwrite atomically.
+    ///
+    /// # Arguments
+    /// * `path` - Storage path of the snapshot to update
+    /// * `update` - Closure applying the changes to the metadata
+    ///
+    /// # Returns
+    /// The updated metadata
+    ///
+    /// # Errors
+    /// * `PersistError::Validation` - If `update` changes any other field
+    /// * `PersistError::Storage` - If the storage adapter cannot overwrite atomically
+    /// * Any error `load_snapshot` can return for the existing snapshot
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use persist_core::create_default_engine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let engine = create_default_engine();
+    /// engine.update_metadata("/path/to/snapshot.json.gz", |metadata| {
+    ///     metadata.description = Some("last good state before the regression".to_string());
+    ///     metadata.tags.insert("status".to_string(), "good".to_string());
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(level = "info", skip(self, update), fields(path = %path))]
+    pub fn update_metadata(
+        &self,
+        path: &str,
+        update: impl FnOnce(&mut SnapshotMetadata),
+    ) -> Result<SnapshotMetadata> {
+        if !self.storage.atomic_overwrite() {
+            return Err(PersistError::storage(
+                "In-place metadata updates are not supported: the storage backend cannot overwrite snapshots atomically",
+            ));
+        }
+
+        let mut container = self.read_container(path)?;
+        let agent_json =
+            serde_json::to_string(&container.agent_state).map_err(PersistError::Json)?;
+        container.metadata.verify_integrity(agent_json.as_bytes())?;
+        if let Some(cipher) = &self.metadata_cipher {
+            container.metadata.decrypt_sensitive(cipher.as_ref())?;
+        }
+
+        let mut updated = container.metadata.clone();
+        update(&mut updated);
+
+        // Everything except the mutable fields must be left untouched
+        let mut unchanged = updated.clone();
+        unchanged.description = container.metadata.description.clone();
+        unchanged.tags = container.metadata.tags.clone();
+        unchanged.expires_at = container.metadata.expires_at;
+        if unchanged != container.metadata {
+            return Err(PersistError::validation(
+                "Only description, tags and expires_at can be updated on an existing snapshot",
+            ));
+        }
+
+        // Without the cipher, encrypted sensitive values can't be re-encrypted consistently
+        if self.metadata_cipher.is_none()
+            && container.metadata.has_encrypted_fields()
+            && updated.sensitive_values() != container.metadata.sensitive_values()
+        {
+            return Err(PersistError::encryption(
+                "Updating sensitive fields of a snapshot with encrypted metadata requires the metadata encryption key",
+            ));
+        }
+
+        let mut metadata = updated.clone();
+        if let Some(cipher) = &self.metadata_cipher {
+            updated.encrypt_sensitive(cipher.as_ref())?;
+        }
+        container.metadata = updated;
+
+        // Keep the algorithm the snapshot is stored with, which may not be the engine's
+        let algorithm = container.metadata.compression_algorithm.clone();
+        let compressed_size = self.write_container_as(&container, path, &algorithm)?;
+
+        metadata.set_compressed_size(compressed_size);
+        Ok(metadata)
+    }
+
+    /// Rewrite a stored snapshot with another compression algorithm
+    ///
+    /// The agent state and metadata are kept as they are, apart from the recorded
+    /// compression algorithm, so the content hash still verifies. Sensitive fields
+    /// are not decrypted. Like [`update_metadata`](Self::update_metadata), this
+    /// overwrites the snapshot in place, which needs a storage backend with atomic
+    /// overwrites.
+    ///
+    /// # Arguments
+    /// * `path` - Storage path of the snapshot
+    /// * `compressor` - Compression to store the snapshot with from now on
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use persist_core::{create_default_engine, GzipCompressor};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let engine = create_default_engine();
+    /// let result = engine.recompress_snapshot("snapshots/agent1.json.gz", &GzipCompressor::max())?;
+    /// println!("{} -> {} bytes", result.before, result.after);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(level = "info", skip(self, compressor), fields(path = %path))]
+    pub fn recompress_snapshot(
+        &self,
+        path: &str,
+        compressor: &dyn CompressionAdapter,
+    ) -> Result<Recompressed> {
+        if !self.storage.atomic_overwrite() {
+            return Err(PersistError::storage(
+                "In-place recompression is not supported: the storage backend cannot overwrite snapshots atomically",
+            ));
+        }
+
+        let (mut container, before) = self.load_container(path, &CallOptions::default())?;
+        let agent_json =
+            serde_json::to_string(&container.agent_state).map_err(PersistError::Json)?;
+        container.metadata.verify_integrity(agent_json.as_bytes())?;
+
+        container
+            .metadata
+            .set_compression_algorithm(compressor.algorithm_name());
+        let after =
+            self.write_container_with(&container, path, compressor, &CallOptions::default())?;
+
+        let mut metadata = container.metadata;
+        metadata.set_compressed_size(after);
+        Ok(Recompressed {
+            metadata,
+            before,
+            after,
+        })
+    }
+
+    /// Migrate a stored snapshot to another major format version, in place
+    ///
+    /// The snapshot's format version is read from the raw container and the
+    /// [migration chain](crate::migration) is run up to `target`, usually
+    /// [`METADATA_FORMAT_VERSION`]. The migrated container must pass the integrity
+    /// check before it replaces the original, so a failed migration leaves the stored
+    /// snapshot as it was. Snapshots already at `target` are not rewritten, and older
+    /// targets are refused rather than downgrading. The snapshot keeps the compression
+    /// it is stored with. With `dry_run`, everything except the write is done.
+    ///
+    /// # Arguments
+    /// * `path` - Storage path of the snapshot
+    /// * `target` - Major format version to migrate to
+    /// * `dry_run` - Report what would change without writing
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use persist_core::create_default_engine;
+    /// use persist_core::metadata::METADATA_FORMAT_VERSION;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let engine = create_default_engine();
+    /// let report = engine.migrate_snapshot("snapshots/old.json.gz", METADATA_FORMAT_VERSION, false)?;
+    /// println!("{} -> {}", report.from, report.to);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(level = "info", skip(self), fields(path = %path))]
+    pub fn migrate_snapshot(
+        &self,
+        path: &str,
+        target: u8,
+        dry_run: bool,
+    ) -> Result<MigrationReport> {
+        if !dry_run && !self.storage.atomic_overwrite() {
+            return Err(PersistError::storage(
+                "In-place migration is not supported: the storage backend cannot overwrite snapshots atomically",
+            ));
+        }
+
         let compressed_data = self
             .storage
             .load(path)
             .map_err(|e| PersistError::Storage(format!("Failed to load snapshot: {e}")))?;
+        let algorithm = compression::detect_algorithm(&compressed_data)
+            .unwrap_or_else(|| self.compressor.algorithm_name())
+            .to_string();
+        let mut raw: serde_json::Value =
+            serde_json::from_slice(&self.decompress(&compressed_data)?)
+                .map_err(PersistError::Json)?;
+
+        let report = migration::migrate_container(&mut raw, target)?;
+        if !report.migrated {
+            return Ok(report);
+        }
+
+        let mut container: SnapshotContainer =
+            serde_json::from_value(raw).map_err(PersistError::Json)?;
+        let agent_json =
+            serde_json::to_string(&container.agent_state).map_err(PersistError::Json)?;
+        container.metadata.verify_integrity(agent_json.as_bytes())?;
+        container.metadata.set_compression_algorithm(&algorithm);
+        if dry_run {
+            return Ok(report);
+        }
+
+        self.write_container_as(&container, path, &algorithm)?;
+        tracing::info!(path = %path, from = %report.from, to = %report.to, "Migrated snapshot format");
+        Ok(report)
+    }
+
+    /// Compare two stored snapshots
+    ///
+    /// Both snapshots are fully loaded and verified. The result lists every metadata
+    /// difference and the changes between the agent states. Sensitive fields are
+    /// compared in redacted form, so a change shows up without disclosing either value.
+    ///
+    /// # Arguments
+    /// * `path_a` - Storage path of the first ("before") snapshot
+    /// * `path_b` - Storage path of the second ("after") snapshot
+    pub fn diff_snapshots(&self, path_a: &str, path_b: &str) -> Result<SnapshotDiff> {
+        let (metadata_a, state_a) = self.load_snapshot(path_a)?;
+        let (metadata_b, state_b) = self.load_snapshot(path_b)?;
+
+        Ok(SnapshotDiff::new(
+            &metadata_a,
+            &state_a,
+            &metadata_b,
+            &state_b,
+        ))
+    }
+
+    /// Load, decompress and parse a snapshot container, checking format compatibility
+    fn read_container(&self, path: &str) -> Result<SnapshotContainer> {
+        self.load_container(path, &CallOptions::default())
+            .map(|(container, _)| container)
+    }
+
+    /// [`read_container`](Self::read_container), also returning the stored size in bytes
+    fn load_container(
+        &self,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<(SnapshotContainer, usize)> {
+        // Load compressed data from storage
+        let compressed_data = self
+            .storage
+            .load_with_options(path, options)
+            .map_err(|e| PersistError::Storage(format!("Failed to load snapshot: {e}")))?;
+        let stored_size = compressed_data.len();
+
+        let container = self.parse_container(&compressed_data, path)?;
+        Ok((container, stored_size))
+    }
 
+    /// Decompress and parse stored snapshot bytes, checking format compatibility
+    ///
+    /// `path` is only used to report where the data came from.
+    fn parse_container(&self, compressed_data: &[u8], path: &str) -> Result<SnapshotContainer> {
         // Decompress the data
-        let decompressed_data = self.compressor.decompress(&compressed_data)?;
+        let decompressed_data = self.decompress(compressed_data)?;
 
         // Parse the JSON container
         let container_json = String::from_utf8(decompressed_data)
             .map_err(|e| PersistError::invalid_format(format!("Invalid UTF-8 in snapshot: {e}")))?;
 
-        let container: SnapshotContainer =
+        let raw: serde_json::Value =
             serde_json::from_str(&container_json).map_err(PersistError::Json)?;
 
-        // Check format compatibility
-        if !container.metadata.is_compatible() {
-            return Err(PersistError::invalid_format(format!(
-                "Incompatible snapshot format version: {} (current: {})",
-                container.metadata.format_version,
-                crate::metadata::METADATA_FORMAT_VERSION
-            )));
+        // Check format compatibility (same major version, any minor version) on the raw
+        // JSON, since older formats may not deserialize into the current container
+        let (format_version, format_minor_version) = raw_format_version(&raw);
+        let compatibility =
+            format_version.map_or(Compatibility::Compatible, Compatibility::of_version);
+        let found = format!(
+            "{}.{}",
+            format_version.unwrap_or_default(),
+            format_minor_version.unwrap_or_default()
+        );
+        let parse = |raw| -> Result<SnapshotContainer> {
+            serde_json::from_value(raw).map_err(PersistError::Json)
+        };
+        if compatibility == Compatibility::Compatible
+            || self.compatibility_mode == CompatibilityMode::Force
+        {
+            return parse(raw);
+        }
+        if self.compatibility_mode == CompatibilityMode::Warn {
+            tracing::warn!(
+                path = %path,
+                format_version = %found,
+                current = %current_format_version(),
+                compatibility = %compatibility,
+                "Loading snapshot with incompatible format version"
+            );
+            return parse(raw);
         }
+        match compatibility {
+            Compatibility::NeedsMigration => Err(PersistError::NeedsMigration {
+                found,
+                current: current_format_version(),
+            }),
+            _ => Err(PersistError::invalid_format(format!(
+                "Unsupported snapshot format version: {found} (current: {})",
+                current_format_version()
+            ))),
+        }
+    }
 
-        // Convert agent state back to JSON string (normalized format)
-        let agent_json =
-            serde_json::to_string(&container.agent_state).map_err(PersistError::Json)?;
+    /// Decompress stored data with the algorithm it was written with
+    ///
+    /// Data from the engine's own compressor, or in a format that can't be recognized,
+    /// goes to the engine's compressor; recognized gzip, zstd or uncompressed data
+    /// from another engine goes to the matching built-in adapter.
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match compression::detect_algorithm(data) {
+            Some(algorithm) if algorithm != self.compressor.algorithm_name() => {
+                compression::compressor_for(algorithm, None)?.decompress(data)
+            }
+            _ => self.compressor.decompress(data),
+        }
+    }
 
-        // Verify integrity
-        container.metadata.verify_integrity(agent_json.as_bytes())?;
+    /// Serialize, compress and save a snapshot container, returning the compressed size
+    fn write_container(
+        &self,
+        container: &SnapshotContainer,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<usize> {
+        self.write_container_with(container, path, &self.compressor, options)
+    }
+
+    /// [`write_container`](Self::write_container) with another compressor
+    fn write_container_with(
+        &self,
+        container: &SnapshotContainer,
+        path: &str,
+        compressor: &dyn CompressionAdapter,
+        options: &CallOptions,
+    ) -> Result<usize> {
+        // Serialize the container to JSON
+        let container_json = serde_json::to_string(container).map_err(PersistError::Json)?;
+
+        // Compress the JSON data
+        let compressed_data = compressor.compress(container_json.as_bytes())?;
+
+        // Save to storage
+        self.storage
+            .save_with_options(&compressed_data, path, options)
+            .map_err(|e| PersistError::Storage(format!("Failed to save snapshot: {e}")))?;
+
+        Ok(compressed_data.len())
+    }
 
-        Ok((container.metadata, agent_json))
+    /// [`write_container`](Self::write_container) with the named built-in algorithm,
+    /// or the engine's own compressor if it uses that algorithm
+    fn write_container_as(
+        &self,
+        container: &SnapshotContainer,
+        path: &str,
+        algorithm: &str,
+    ) -> Result<usize> {
+        if algorithm == self.compressor.algorithm_name() {
+            self.write_container(container, path, &CallOptions::default())
+        } else {
+            let compressor = compression::compressor_for(algorithm, None)?;
+            self.write_container_with(
+                container,
+                path,
+                compressor.as_ref(),
+                &CallOptions::default(),
+            )
+        }
     }
 
     /// Check if a snapshot exists at the specified path
@@ -221,9 +968,37 @@ where
     /// # Returns
     /// Result indicating success or failure
     pub fn delete_snapshot(&self, path: &str) -> Result<()> {
-        self.storage
-            .delete(path)
-            .map_err(|e| PersistError::Storage(format!("Failed to delete snapshot: {e}")))
+        self.record_operation(
+            "delete",
+            path,
+            None,
+            || {
+                self.storage
+                    .delete(path)
+                    .map_err(|e| PersistError::Storage(format!("Failed to delete snapshot: {e}")))
+            },
+            |_, duration_ms| PersistEvent::SnapshotDeleted {
+                path: path.to_string(),
+                duration_ms,
+            },
+        )
+    }
+
+    /// Stored bytes of a snapshot, exactly as the storage backend holds them
+    ///
+    /// Nothing is decompressed or checked. Together with [`save_stored`](Self::save_stored)
+    /// this copies snapshots between engines without recompressing them.
+    pub fn load_stored(&self, path: &str) -> Result<Vec<u8>> {
+        self.storage.load(path)
+    }
+
+    /// Store snapshot bytes as they are, such as those returned by
+    /// [`load_stored`](Self::load_stored)
+    ///
+    /// The bytes are not checked; use [`verify_container`](Self::verify_container) first
+    /// to reject data that is not a readable snapshot.
+    pub fn save_stored(&self, data: &[u8], path: &str) -> Result<()> {
+        self.storage.save(data, path)
     }
 
     /// Get metadata from a snapshot without loading the full agent data
@@ -255,9 +1030,254 @@ where
     /// # Returns
     /// Result indicating if the snapshot is valid
     pub fn verify_snapshot(&self, path: &str) -> Result<()> {
-        let _ = self.load_snapshot(path)?;
-        Ok(())
+        self.record_operation(
+            "verify",
+            path,
+            None,
+            || self.read_snapshot(path, &CallOptions::default()),
+            |(metadata, _, stored_bytes), duration_ms| {
+                PersistEvent::SnapshotVerified(event_details(
+                    path,
+                    metadata,
+                    *stored_bytes,
+                    duration_ms,
+                ))
+            },
+        )
+        .map(|_| ())
     }
+
+    /// Query snapshot metadata across all snapshots stored under a prefix
+    ///
+    /// Candidates come from the storage listing; for each one only the metadata is
+    /// parsed (no integrity check or agent state reconstruction). Entries that are not
+    /// readable snapshots are skipped. Sorted queries must read every candidate before
+    /// applying the limit; `SortOrder::Unsorted` stops as soon as the limit is reached.
+    ///
+    /// # Arguments
+    /// * `prefix` - Storage path prefix to search under
+    /// * `query` - Filters, ordering and limit to apply
+    ///
+    /// # Returns
+    /// The matching snapshots in the requested order
+    ///
+    /// # Errors
+    /// * `PersistError::Storage` - If the storage backend cannot list its contents
+    pub fn query(&self, prefix: &str, query: &SnapshotQuery) -> Result<Vec<SnapshotSummary>> {
+        let mut results = Vec::new();
+
+        for path in self.storage.list(prefix)? {
+            if query.is_satisfied(results.len()) {
+                break;
+            }
+            match self.read_snapshot_metadata(&path) {
+                Ok(metadata) if query.matches(&metadata) => {
+                    results.push(SnapshotSummary { path, metadata });
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::debug!(path = %path, error = %e, "Skipping unreadable snapshot");
+                }
+            }
+        }
+
+        Ok(query.finish(results))
+    }
+
+    /// Next snapshot index for an agent/session, derived from all stored snapshots
+    ///
+    /// Returns 0 when the session has no snapshots yet, otherwise one more than the
+    /// highest existing index. The result is not reserved: concurrent callers may
+    /// receive the same index, so pair this with strict mode (`with_strict_index`)
+    /// if duplicates must be rejected.
+    pub fn next_index(&self, agent_id: &str, session_id: &str) -> Result<u64> {
+        self.next_index_under("", agent_id, session_id)
+    }
+
+    /// Next snapshot index for an agent/session, considering only snapshots under `prefix`
+    pub fn next_index_under(&self, prefix: &str, agent_id: &str, session_id: &str) -> Result<u64> {
+        Ok(self
+            .latest_index(prefix, agent_id, session_id)?
+            .map_or(0, |latest| latest + 1))
+    }
+
+    /// Highest stored snapshot index for an agent/session under `prefix`, if any
+    fn latest_index(&self, prefix: &str, agent_id: &str, session_id: &str) -> Result<Option<u64>> {
+        Ok(self
+            .latest_snapshot(prefix, agent_id, Some(session_id))?
+            .map(|summary| summary.metadata.snapshot_index))
+    }
+
+    /// Latest snapshot of an agent under `prefix`, if it has any
+    ///
+    /// Within one session (`session_id` given) the highest snapshot index wins, as
+    /// indices only grow within a session; across sessions the most recently created
+    /// snapshot wins. Ties go to the later timestamp (or higher index), then to the
+    /// greatest path, so the choice is stable.
+    ///
+    /// # Errors
+    /// * `PersistError::Storage` - If the storage backend cannot list its contents
+    pub fn latest_snapshot(
+        &self,
+        prefix: &str,
+        agent_id: &str,
+        session_id: Option<&str>,
+    ) -> Result<Option<SnapshotSummary>> {
+        let mut query = SnapshotQuery::new()
+            .agent_id(agent_id)
+            .sort(SortOrder::Unsorted);
+        if let Some(session_id) = session_id {
+            query = query.session_id(session_id);
+        }
+        let by_index = session_id.is_some();
+        Ok(self.query(prefix, &query)?.into_iter().max_by(|a, b| {
+            let (a_meta, b_meta) = (&a.metadata, &b.metadata);
+            let index = a_meta.snapshot_index.cmp(&b_meta.snapshot_index);
+            let timestamp = a_meta.timestamp.cmp(&b_meta.timestamp);
+            let order = if by_index {
+                index.then(timestamp)
+            } else {
+                timestamp.then(index)
+            };
+            order.then_with(|| a.path.cmp(&b.path))
+        }))
+    }
+
+    /// Read the metadata of a stored snapshot without loading or verifying its agent state
+    ///
+    /// The metadata is stored ahead of the agent state, so only the first bytes of
+    /// the snapshot are fetched from storage; snapshots whose metadata does not fit
+    /// in them are read in full. Unlike
+    /// [`get_snapshot_metadata`](Self::get_snapshot_metadata), neither the content
+    /// hash nor the format version is checked.
+    ///
+    /// # Arguments
+    /// * `path` - Storage path of the snapshot
+    ///
+    /// # Returns
+    /// The snapshot metadata, with sensitive fields decrypted
+    pub fn read_snapshot_metadata(&self, path: &str) -> Result<SnapshotMetadata> {
+        let head = self.storage.load_prefix(path, METADATA_READ_SIZE)?;
+        // A short read is the whole snapshot
+        let complete = (head.len() as u64) < METADATA_READ_SIZE;
+        let leading = if complete {
+            None
+        } else {
+            compression::decompress_prefix(&head).and_then(|json| leading_metadata(&json))
+        };
+
+        let mut metadata = match leading {
+            Some(metadata) => metadata,
+            None => {
+                let compressed_data = if complete {
+                    head
+                } else {
+                    self.storage.load(path)?
+                };
+                let decompressed_data = self.decompress(&compressed_data)?;
+                let container: MetadataOnlyContainer =
+                    serde_json::from_slice(&decompressed_data).map_err(PersistError::Json)?;
+                container.metadata
+            }
+        };
+        if let Some(cipher) = &self.metadata_cipher {
+            metadata.decrypt_sensitive(cipher.as_ref())?;
+        }
+        Ok(metadata)
+    }
+
+    /// Whether the engine can store snapshots right now
+    ///
+    /// A shallow check runs the storage adapter's
+    /// [`health_check`](StorageAdapter::health_check), which validates its
+    /// configuration with at most one cheap request. A deep check also writes a small
+    /// probe object, reads it back and deletes it. Failures are reported, not
+    /// returned, with the error in [`HealthReport::last_error`].
+    ///
+    /// Reports are cached for the [health cache TTL](Self::with_health_cache_ttl),
+    /// separately for shallow and deep checks. Concurrent callers wait for a running
+    /// check instead of starting their own.
+    pub fn health(&self, deep: bool) -> HealthReport {
+        let mut cache = self
+            .health_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let cached = &mut cache[usize::from(deep)];
+        if let Some((checked, report)) = cached {
+            if checked.elapsed() < self.health_cache_ttl {
+                return report.clone();
+            }
+        }
+
+        let start = Instant::now();
+        let result =
+            self.storage
+                .health_check()
+                .and_then(|()| if deep { self.probe_storage() } else { Ok(()) });
+        let report = HealthReport::new(self.storage.backend_name(), deep, start.elapsed(), &result);
+        if let Err(e) = &result {
+            tracing::warn!(backend = %report.backend, deep, error = %e, "Storage health check failed");
+        }
+        *cached = Some((start, report.clone()));
+        report
+    }
+
+    /// Write, read back and delete a probe object
+    fn probe_storage(&self) -> Result<()> {
+        let path = format!("{HEALTH_PROBE_PREFIX}{}", uuid::Uuid::new_v4());
+        let data = path.as_bytes();
+        self.storage.save(data, &path)?;
+        let read = self.storage.load(&path);
+        let deleted = self.storage.delete(&path);
+        if read? != data {
+            return Err(PersistError::storage(format!(
+                "Health probe {path} read back different data"
+            )));
+        }
+        deleted
+    }
+}
+
+/// Bytes read from the start of a snapshot to get its metadata
+///
+/// Compressed metadata takes well under a kilobyte unless it carries large tags or
+/// descriptions.
+const METADATA_READ_SIZE: u64 = 16 * 1024;
+
+/// Metadata of a snapshot from the beginning of its serialized container
+///
+/// `json` may be cut off anywhere after the metadata, as it is when only the first
+/// bytes of a snapshot were read. `None` if the metadata is not complete.
+fn leading_metadata(json: &[u8]) -> Option<SnapshotMetadata> {
+    struct MetadataVisitor<'a>(&'a mut Option<SnapshotMetadata>);
+
+    impl<'de> serde::de::Visitor<'de> for MetadataVisitor<'_> {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a snapshot container")
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(
+            self,
+            mut map: A,
+        ) -> std::result::Result<(), A::Error> {
+            while let Some(key) = map.next_key::<String>()? {
+                if key == "metadata" {
+                    *self.0 = Some(map.next_value()?);
+                    return Ok(());
+                }
+                map.next_value::<serde::de::IgnoredAny>()?;
+            }
+            Ok(())
+        }
+    }
+
+    let mut metadata = None;
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    // Deserialization fails on the cut-off rest of the container, after the metadata
+    let _ = serde::Deserializer::deserialize_map(&mut deserializer, MetadataVisitor(&mut metadata));
+    metadata
 }
 
 /// Convenience function to create a snapshot engine with default components
@@ -354,7 +1374,8 @@ pub fn create_gcs_engine(
 ///
 /// This function provides a unified interface for creating engines with different
 /// storage backends based on configuration. It automatically selects the appropriate
-/// storage adapter (Local or S3) based on the provided StorageConfig.
+/// storage adapter (Local or S3) based on the provided StorageConfig, and compresses
+/// snapshots as its `compression` section says (gzip by default).
 ///
 /// # Arguments
 /// * `config` - Storage configuration specifying backend and parameters
@@ -377,29 +1398,124 @@ pub fn create_gcs_engine(
 /// ```
 pub fn create_engine_from_config(
     config: crate::config::StorageConfig,
-) -> Result<Box<dyn SnapshotEngineInterface>> {
+) -> Result<Box<dyn SnapshotEngineInterface + Send + Sync>> {
+    let compressor = config.compressor()?;
+    let storage = create_storage_from_config(config)?;
+    let engine = SnapshotEngine::new(storage, compressor);
+    Ok(Box::new(engine))
+}
+
+/// Create a snapshot engine based on storage configuration, recording the engine and
+/// storage operations in `metrics` instead of [`PersistMetrics::global`]
+///
+/// # Example
+/// ```rust
+/// use persist_core::{create_engine_from_config_with_metrics, PersistMetrics, StorageConfig};
+///
+/// let metrics = PersistMetrics::with_registry(prometheus::Registry::new(), Some("myapp"))?;
+/// let engine = create_engine_from_config_with_metrics(StorageConfig::default_local(), metrics)?;
+/// # Ok::<(), persist_core::PersistError>(())
+/// ```
+#[cfg(feature = "metrics")]
+pub fn create_engine_from_config_with_metrics(
+    config: crate::config::StorageConfig,
+    metrics: PersistMetrics,
+) -> Result<Box<dyn SnapshotEngineInterface + Send + Sync>> {
+    let compressor = config.compressor()?;
+    let storage = storage_from_config(config, Some(metrics.clone()))?;
+    let engine = SnapshotEngine::new(storage, compressor).with_metrics(metrics);
+    Ok(Box::new(engine))
+}
+
+/// Create the storage adapter selected by a storage configuration
+///
+/// This is the adapter [`create_engine_from_config`] wraps in an engine. Tools that
+/// move raw snapshot bytes between backends use it directly. The adapter can be
+/// shared between threads.
+///
+/// # Arguments
+/// * `config` - Storage configuration specifying backend and parameters
+pub fn create_storage_from_config(
+    config: crate::config::StorageConfig,
+) -> Result<Box<dyn StorageAdapter + Send + Sync>> {
+    storage_from_config(config, Default::default())
+}
+
+/// Metrics for the adapters built by [`storage_from_config`], if not the global ones
+#[cfg(feature = "metrics")]
+type AdapterMetrics = Option<PersistMetrics>;
+#[cfg(not(feature = "metrics"))]
+type AdapterMetrics = ();
+
+/// The storage adapter selected by `config`, recording into `metrics`
+fn storage_from_config(
+    config: crate::config::StorageConfig,
+    metrics: AdapterMetrics,
+) -> Result<Box<dyn StorageAdapter + Send + Sync>> {
     use crate::config::StorageBackend;
+    #[cfg(not(feature = "metrics"))]
+    let () = metrics;
 
     config.validate()?;
 
     match config.backend {
         StorageBackend::Local => {
-            let storage = if let Some(base_path) = config.local_base_path {
+            let mut storage = if let Some(base_path) = config.local_base_path {
                 crate::storage::local::LocalFileStorage::with_base_dir(base_path)
             } else {
                 crate::storage::local::LocalFileStorage::new()
             };
-            let engine = SnapshotEngine::new(storage, crate::compression::GzipCompressor::new());
-            Ok(Box::new(engine))
+            storage = storage.with_durable_writes(config.local_durable_writes);
+            if let Some(permissions) = config.local_file_permissions {
+                storage = storage.with_file_permissions(permissions);
+            }
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = metrics {
+                storage = storage.with_metrics(metrics);
+            }
+            match config.retry {
+                Some(retry) => Ok(Box::new(
+                    crate::storage::RetryingStorage::from_retry_config(storage, retry),
+                )),
+                None => Ok(Box::new(storage)),
+            }
         }
         #[cfg(feature = "s3")]
         StorageBackend::S3 => {
             let bucket = config.s3_bucket.ok_or_else(|| {
                 PersistError::validation("S3 bucket name is required for S3 backend")
             })?;
-            let storage = crate::storage::S3StorageAdapter::new(bucket)?;
-            let engine = SnapshotEngine::new(storage, crate::compression::GzipCompressor::new());
-            Ok(Box::new(engine))
+            let mut builder = crate::storage::S3StorageAdapter::builder()
+                .bucket(bucket)
+                .force_path_style(config.s3_force_path_style);
+            if let Some(region) = config.s3_region {
+                builder = builder.region(region);
+            }
+            if let Some(endpoint) = config.s3_endpoint_url {
+                builder = builder.endpoint(endpoint);
+            }
+            if let Some(credentials) = config.s3_credentials {
+                builder = builder.credentials(credentials);
+            }
+            if let Some(profile) = config.s3_profile {
+                builder = builder.profile(profile);
+            }
+            if let Some(retry) = config.retry {
+                builder = builder.retry_config(retry);
+            }
+            if let Some(timeouts) = config.timeouts {
+                if let Some(connect) = timeouts.connect() {
+                    builder = builder.connect_timeout(connect);
+                }
+                if let Some(request) = timeouts.request() {
+                    builder = builder.timeout(request);
+                }
+            }
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = metrics {
+                builder = builder.metrics(metrics);
+            }
+            Ok(Box::new(builder.build()?))
         }
         #[cfg(feature = "gcs")]
         StorageBackend::GCS => {
@@ -408,9 +1524,23 @@ pub fn create_engine_from_config(
             })?;
             let prefix = config.gcs_prefix;
             let credentials_path = config.gcs_credentials_path;
-            let storage = crate::storage::GCSStorageAdapter::new(bucket, prefix, credentials_path)?;
-            let engine = SnapshotEngine::new(storage, crate::compression::GzipCompressor::new());
-            Ok(Box::new(engine))
+            let mut storage =
+                crate::storage::GCSStorageAdapter::new(bucket, prefix, credentials_path)?;
+            if let Some(retry) = config.retry {
+                storage = storage.with_retry_config(retry);
+            }
+            let request_timeout = config.timeouts.and_then(|timeouts| timeouts.request());
+            if let Some(timeout) = request_timeout.or(config
+                .gcs_timeout_seconds
+                .map(std::time::Duration::from_secs))
+            {
+                storage = storage.with_request_timeout(timeout);
+            }
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = metrics {
+                storage = storage.with_metrics(metrics);
+            }
+            Ok(Box::new(storage))
         }
         #[cfg(not(feature = "s3"))]
         StorageBackend::S3 => Err(PersistError::validation(
@@ -423,6 +1553,46 @@ pub fn create_engine_from_config(
     }
 }
 
+/// Copy a snapshot between two engines, which may use different storage backends
+///
+/// The stored bytes are copied as they are: nothing is recompressed and the metadata is
+/// not rewritten. With `verify`, the target engine checks the bytes with
+/// [`verify_container`](SnapshotEngine::verify_container) before anything is written, so
+/// a damaged source never reaches the target, and the copy is read back and compared
+/// afterwards.
+///
+/// # Arguments
+/// * `source` - Engine holding the snapshot
+/// * `target` - Engine to copy it to; may be `source` itself
+/// * `src_path` - Storage path of the snapshot in `source`
+/// * `dst_path` - Storage path of the copy in `target`
+/// * `verify` - Verify the snapshot before and after writing it
+///
+/// # Returns
+/// The metadata of the copy as stored (sensitive fields are not decrypted)
+pub fn copy_snapshot(
+    source: &dyn SnapshotEngineInterface,
+    target: &dyn SnapshotEngineInterface,
+    src_path: &str,
+    dst_path: &str,
+    verify: bool,
+) -> Result<SnapshotMetadata> {
+    let data = source.load_stored(src_path)?;
+    if !verify {
+        target.save_stored(&data, dst_path)?;
+        return target.read_snapshot_metadata(dst_path);
+    }
+
+    let metadata = target.verify_container(&data)?;
+    target.save_stored(&data, dst_path)?;
+    if target.load_stored(dst_path)? != data {
+        return Err(PersistError::storage(format!(
+            "Copy at '{dst_path}' differs from its source"
+        )));
+    }
+    Ok(metadata)
+}
+
 /// Trait for snapshot engine operations to enable dynamic dispatch
 ///
 /// This trait allows using different storage and compression backends
@@ -435,11 +1605,50 @@ pub trait SnapshotEngineInterface {
         metadata: &SnapshotMetadata,
         path: &str,
     ) -> Result<SnapshotMetadata>;
+    fn save_snapshot_with_options(
+        &self,
+        agent_json: &str,
+        metadata: &SnapshotMetadata,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<SnapshotMetadata>;
     fn load_snapshot(&self, path: &str) -> Result<(SnapshotMetadata, String)>;
+    fn load_snapshot_with_options(
+        &self,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<(SnapshotMetadata, String)>;
     fn snapshot_exists(&self, path: &str) -> bool;
     fn delete_snapshot(&self, path: &str) -> Result<()>;
+    fn load_stored(&self, path: &str) -> Result<Vec<u8>>;
+    fn save_stored(&self, data: &[u8], path: &str) -> Result<()>;
     fn get_snapshot_metadata(&self, path: &str) -> Result<SnapshotMetadata>;
+    fn read_snapshot_metadata(&self, path: &str) -> Result<SnapshotMetadata>;
     fn verify_snapshot(&self, path: &str) -> Result<()>;
+    fn verify_container(&self, data: &[u8]) -> Result<SnapshotMetadata>;
+    fn query(&self, prefix: &str, query: &SnapshotQuery) -> Result<Vec<SnapshotSummary>>;
+    fn update_metadata(
+        &self,
+        path: &str,
+        update: Box<dyn FnOnce(&mut SnapshotMetadata) + '_>,
+    ) -> Result<SnapshotMetadata>;
+    fn recompress_snapshot(
+        &self,
+        path: &str,
+        compressor: &dyn CompressionAdapter,
+    ) -> Result<Recompressed>;
+    fn migrate_snapshot(&self, path: &str, target: u8, dry_run: bool) -> Result<MigrationReport>;
+    fn diff_snapshots(&self, path_a: &str, path_b: &str) -> Result<SnapshotDiff>;
+    fn next_index(&self, agent_id: &str, session_id: &str) -> Result<u64>;
+    fn next_index_under(&self, prefix: &str, agent_id: &str, session_id: &str) -> Result<u64>;
+    fn latest_snapshot(
+        &self,
+        prefix: &str,
+        agent_id: &str,
+        session_id: Option<&str>,
+    ) -> Result<Option<SnapshotSummary>>;
+    fn set_compatibility_mode(&mut self, mode: CompatibilityMode);
+    fn health(&self, deep: bool) -> HealthReport;
 }
 
 impl<S, C> SnapshotEngineInterface for SnapshotEngine<S, C>
@@ -456,10 +1665,28 @@ where
         self.save_snapshot(agent_json, metadata, path)
     }
 
+    fn save_snapshot_with_options(
+        &self,
+        agent_json: &str,
+        metadata: &SnapshotMetadata,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<SnapshotMetadata> {
+        self.save_snapshot_with_options(agent_json, metadata, path, options)
+    }
+
     fn load_snapshot(&self, path: &str) -> Result<(SnapshotMetadata, String)> {
         self.load_snapshot(path)
     }
 
+    fn load_snapshot_with_options(
+        &self,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<(SnapshotMetadata, String)> {
+        self.load_snapshot_with_options(path, options)
+    }
+
     fn snapshot_exists(&self, path: &str) -> bool {
         self.snapshot_exists(path)
     }
@@ -468,19 +1695,90 @@ where
         self.delete_snapshot(path)
     }
 
+    fn load_stored(&self, path: &str) -> Result<Vec<u8>> {
+        self.load_stored(path)
+    }
+
+    fn save_stored(&self, data: &[u8], path: &str) -> Result<()> {
+        self.save_stored(data, path)
+    }
+
     fn get_snapshot_metadata(&self, path: &str) -> Result<SnapshotMetadata> {
         self.get_snapshot_metadata(path)
     }
 
+    fn read_snapshot_metadata(&self, path: &str) -> Result<SnapshotMetadata> {
+        self.read_snapshot_metadata(path)
+    }
+
     fn verify_snapshot(&self, path: &str) -> Result<()> {
         self.verify_snapshot(path)
     }
+
+    fn verify_container(&self, data: &[u8]) -> Result<SnapshotMetadata> {
+        self.verify_container(data)
+    }
+
+    fn query(&self, prefix: &str, query: &SnapshotQuery) -> Result<Vec<SnapshotSummary>> {
+        self.query(prefix, query)
+    }
+
+    fn update_metadata(
+        &self,
+        path: &str,
+        update: Box<dyn FnOnce(&mut SnapshotMetadata) + '_>,
+    ) -> Result<SnapshotMetadata> {
+        self.update_metadata(path, update)
+    }
+
+    fn recompress_snapshot(
+        &self,
+        path: &str,
+        compressor: &dyn CompressionAdapter,
+    ) -> Result<Recompressed> {
+        self.recompress_snapshot(path, compressor)
+    }
+
+    fn migrate_snapshot(&self, path: &str, target: u8, dry_run: bool) -> Result<MigrationReport> {
+        self.migrate_snapshot(path, target, dry_run)
+    }
+
+    fn diff_snapshots(&self, path_a: &str, path_b: &str) -> Result<SnapshotDiff> {
+        self.diff_snapshots(path_a, path_b)
+    }
+
+    fn next_index(&self, agent_id: &str, session_id: &str) -> Result<u64> {
+        self.next_index(agent_id, session_id)
+    }
+
+    fn next_index_under(&self, prefix: &str, agent_id: &str, session_id: &str) -> Result<u64> {
+        self.next_index_under(prefix, agent_id, session_id)
+    }
+
+    fn latest_snapshot(
+        &self,
+        prefix: &str,
+        agent_id: &str,
+        session_id: Option<&str>,
+    ) -> Result<Option<SnapshotSummary>> {
+        self.latest_snapshot(prefix, agent_id, session_id)
+    }
+
+    fn set_compatibility_mode(&mut self, mode: CompatibilityMode) {
+        self.set_compatibility_mode(mode)
+    }
+
+    fn health(&self, deep: bool) -> HealthReport {
+        self.health(deep)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{compression::NoCompression, storage::MemoryStorage};
+    use crate::observability::HealthStatus;
+    use crate::{compression::NoCompression, query::SortOrder, storage::MemoryStorage};
+    use chrono::{DateTime, Utc};
 
     fn create_test_engine() -> SnapshotEngine<MemoryStorage, NoCompression> {
         SnapshotEngine::new(MemoryStorage::new(), NoCompression::new())
@@ -594,6 +1892,793 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_framework_requirement_enforced_on_load() {
+        use crate::metadata::{FrameworkCompatPolicy, FrameworkRequirement};
+
+        let storage = MemoryStorage::new();
+        let writer = SnapshotEngine::new(storage.clone(), NoCompression::new());
+        let metadata = SnapshotMetadata::new("test_agent", "test_session", 0)
+            .with_framework("langchain", "0.1.20");
+        writer
+            .save_snapshot(r#"{"type": "test_agent"}"#, &metadata, "fw.json.gz")
+            .unwrap();
+
+        let strict =
+            SnapshotEngine::new(storage.clone(), NoCompression::new()).with_framework_requirement(
+                FrameworkRequirement::new("langchain", "0.3.0", FrameworkCompatPolicy::SameMinor),
+            );
+        assert!(matches!(
+            strict.load_snapshot("fw.json.gz"),
+            Err(PersistError::FrameworkMismatch { .. })
+        ));
+
+        let lenient =
+            SnapshotEngine::new(storage, NoCompression::new()).with_framework_requirement(
+                FrameworkRequirement::new("langchain", "0.3.0", FrameworkCompatPolicy::SameMajor),
+            );
+        assert!(lenient.load_snapshot("fw.json.gz").is_ok());
+    }
+
+    fn store_with_format_version(storage: &MemoryStorage, path: &str, format_version: u8) {
+        let agent_state = serde_json::json!({"type": "test_agent"});
+        let mut metadata = SnapshotMetadata::new("test_agent", "test_session", 0);
+        metadata.set_content_hash(agent_state.to_string().as_bytes());
+        metadata.format_version = format_version;
+
+        let container = serde_json::json!({ "metadata": metadata, "agent_state": agent_state });
+        storage
+            .save(container.to_string().as_bytes(), path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_load_distinguishes_migration_from_unsupported() {
+        let storage = MemoryStorage::new();
+        store_with_format_version(&storage, "old.json", METADATA_FORMAT_VERSION - 1);
+        store_with_format_version(&storage, "new.json", METADATA_FORMAT_VERSION + 1);
+        let engine = SnapshotEngine::new(storage, NoCompression::new());
+
+        assert!(matches!(
+            engine.load_snapshot("old.json"),
+            Err(PersistError::NeedsMigration { .. })
+        ));
+        assert!(matches!(
+            engine.load_snapshot("new.json"),
+            Err(PersistError::InvalidFormat(_))
+        ));
+    }
+
+    /// Store a version-0 snapshot: bare hex content hash, no snapshot id or size fields
+    fn store_v0_snapshot(storage: &MemoryStorage, path: &str, agent_state: serde_json::Value) {
+        let hash = crate::ContentHash::compute(
+            crate::HashAlgorithm::Sha256,
+            agent_state.to_string().as_bytes(),
+        );
+        let container = serde_json::json!({
+            "metadata": {
+                "agent_id": "test_agent",
+                "session_id": "test_session",
+                "snapshot_index": 0,
+                "timestamp": "2023-06-01T00:00:00Z",
+                "content_hash": hash.digest,
+                "format_version": 0,
+            },
+            "agent_state": agent_state,
+        });
+        storage
+            .save(container.to_string().as_bytes(), path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_verify_container_bytes() {
+        let engine = create_test_engine();
+        let metadata = SnapshotMetadata::new("test_agent", "test_session", 0);
+        engine
+            .save_snapshot(r#"{"memory": ["hello"]}"#, &metadata, "snap.json")
+            .unwrap();
+        let data = engine.storage.load("snap.json").unwrap();
+
+        let verified = engine.verify_container(&data).unwrap();
+        assert_eq!(verified.snapshot_id(), metadata.snapshot_id());
+
+        let tampered = String::from_utf8(data.clone())
+            .unwrap()
+            .replace("hello", "jello");
+        assert!(matches!(
+            engine.verify_container(tampered.as_bytes()),
+            Err(PersistError::IntegrityCheckFailed { .. })
+        ));
+        assert!(engine.verify_container(&data[..data.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_copy_snapshot_between_engines() {
+        let source = create_test_engine();
+        let target = SnapshotEngine::new(
+            MemoryStorage::new(),
+            crate::compression::GzipCompressor::new(),
+        );
+        let metadata = SnapshotMetadata::new("test_agent", "test_session", 0);
+        source
+            .save_snapshot(r#"{"memory": ["hello"]}"#, &metadata, "scratch/snap.json")
+            .unwrap();
+
+        let copied = copy_snapshot(
+            &source,
+            &target,
+            "scratch/snap.json",
+            "kept/snap.json",
+            true,
+        )
+        .unwrap();
+        assert_eq!(copied.snapshot_id(), metadata.snapshot_id());
+        // Copied as stored, so still uncompressed even though the target would gzip
+        assert_eq!(
+            target.load_stored("kept/snap.json").unwrap(),
+            source.load_stored("scratch/snap.json").unwrap()
+        );
+        assert_eq!(
+            target.load_snapshot("kept/snap.json").unwrap().1,
+            r#"{"memory":["hello"]}"#
+        );
+
+        // A damaged source is rejected before anything is written
+        let tampered = String::from_utf8(source.load_stored("scratch/snap.json").unwrap())
+            .unwrap()
+            .replace("hello", "jello");
+        source
+            .save_stored(tampered.as_bytes(), "scratch/snap.json")
+            .unwrap();
+        assert!(matches!(
+            copy_snapshot(&source, &target, "scratch/snap.json", "bad/snap.json", true),
+            Err(PersistError::IntegrityCheckFailed { .. })
+        ));
+        assert!(!target.snapshot_exists("bad/snap.json"));
+
+        // Without verification the bytes are copied as they are
+        copy_snapshot(
+            &source,
+            &target,
+            "scratch/snap.json",
+            "bad/snap.json",
+            false,
+        )
+        .unwrap();
+        assert!(target.verify_snapshot("bad/snap.json").is_err());
+    }
+
+    #[test]
+    fn test_migrate_snapshot_from_v0() {
+        let storage = MemoryStorage::new();
+        store_v0_snapshot(&storage, "old.json", serde_json::json!({"memory": ["hi"]}));
+        let engine = SnapshotEngine::new(storage.clone(), NoCompression::new());
+        assert!(matches!(
+            engine.load_snapshot("old.json"),
+            Err(PersistError::NeedsMigration { .. })
+        ));
+
+        let dry_run = engine
+            .migrate_snapshot("old.json", METADATA_FORMAT_VERSION, true)
+            .unwrap();
+        assert!(dry_run.migrated);
+        assert!(engine.load_snapshot("old.json").is_err());
+
+        let report = engine
+            .migrate_snapshot("old.json", METADATA_FORMAT_VERSION, false)
+            .unwrap();
+        assert_eq!(report.from, "0.0");
+        assert_eq!(report.to, current_format_version());
+
+        let (metadata, agent_json) = engine.load_snapshot("old.json").unwrap();
+        assert_eq!(metadata.compression_algorithm, "none");
+        assert_eq!(agent_json, r#"{"memory":["hi"]}"#);
+
+        // Running it again is a no-op
+        let stored = storage.load("old.json").unwrap();
+        let again = engine
+            .migrate_snapshot("old.json", METADATA_FORMAT_VERSION, false)
+            .unwrap();
+        assert!(!again.migrated);
+        assert_eq!(storage.load("old.json").unwrap(), stored);
+    }
+
+    #[test]
+    fn test_failed_migration_leaves_snapshot_intact() {
+        let storage = MemoryStorage::new();
+        store_v0_snapshot(&storage, "old.json", serde_json::json!({"memory": ["hi"]}));
+        let tampered = String::from_utf8(storage.load("old.json").unwrap())
+            .unwrap()
+            .replace("hi", "bye");
+        storage.save(tampered.as_bytes(), "old.json").unwrap();
+        store_with_format_version(&storage, "new.json", METADATA_FORMAT_VERSION + 1);
+        let engine = SnapshotEngine::new(storage.clone(), NoCompression::new());
+
+        assert!(matches!(
+            engine.migrate_snapshot("old.json", METADATA_FORMAT_VERSION, false),
+            Err(PersistError::IntegrityCheckFailed { .. })
+        ));
+        assert_eq!(storage.load("old.json").unwrap(), tampered.as_bytes());
+
+        // Newer snapshots are never downgraded
+        let newer = storage.load("new.json").unwrap();
+        assert!(engine
+            .migrate_snapshot("new.json", METADATA_FORMAT_VERSION, false)
+            .is_err());
+        assert_eq!(storage.load("new.json").unwrap(), newer);
+    }
+
+    /// Build an engine holding 200 snapshots: 4 agents, 2 sessions each, indices 0..25,
+    /// one minute apart, with every third snapshot tagged `env=prod`
+    fn create_query_corpus() -> (SnapshotEngine<MemoryStorage, NoCompression>, DateTime<Utc>) {
+        let engine = create_test_engine();
+        let base = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        for i in 0..200u64 {
+            let env = if i % 3 == 0 { "prod" } else { "dev" };
+            let mut metadata = SnapshotMetadata::builder(
+                format!("agent{}", i % 4),
+                format!("s{}", (i / 4) % 2),
+                i / 8,
+            )
+            .tag("env", env)
+            .build();
+            metadata.timestamp = base + chrono::Duration::minutes(i as i64);
+            engine
+                .save_snapshot(r#"{"n": 1}"#, &metadata, &format!("corpus/{i:03}.json"))
+                .unwrap();
+        }
+        engine
+            .save_snapshot(
+                r#"{"n": 1}"#,
+                &SnapshotMetadata::new("agent0", "s0", 0),
+                "elsewhere/outside.json",
+            )
+            .unwrap();
+
+        (engine, base)
+    }
+
+    #[test]
+    fn test_query_corpus_filters() {
+        let (engine, base) = create_query_corpus();
+        let count = |query: SnapshotQuery| engine.query("corpus/", &query).unwrap().len();
+
+        assert_eq!(count(SnapshotQuery::new()), 200);
+        assert_eq!(count(SnapshotQuery::new().agent_id("agent1")), 50);
+        assert_eq!(count(SnapshotQuery::new().session_id("s1")), 100);
+        assert_eq!(
+            count(
+                SnapshotQuery::new()
+                    .created_after(base + chrono::Duration::minutes(10))
+                    .created_before(base + chrono::Duration::minutes(20))
+            ),
+            10
+        );
+        assert_eq!(count(SnapshotQuery::new().min_index(5)), 160);
+        assert_eq!(count(SnapshotQuery::new().min_index(5).max_index(9)), 40);
+        assert_eq!(count(SnapshotQuery::new().tag("env", "prod")), 67);
+        assert_eq!(count(SnapshotQuery::new().limit(20)), 20);
+        assert_eq!(engine.query("", &SnapshotQuery::new()).unwrap().len(), 201);
+    }
+
+    #[test]
+    fn test_query_corpus_combined_filters_and_order() {
+        let (engine, base) = create_query_corpus();
+        let query = SnapshotQuery::new()
+            .agent_id("agent2")
+            .created_after(base + chrono::Duration::minutes(50))
+            .created_before(base + chrono::Duration::minutes(150))
+            .min_index(10)
+            .limit(5);
+
+        let results = engine.query("corpus/", &query).unwrap();
+        assert_eq!(results.len(), 5);
+        for summary in &results {
+            assert!(query.matches(&summary.metadata));
+        }
+        assert!(results
+            .windows(2)
+            .all(|w| w[0].metadata.timestamp > w[1].metadata.timestamp));
+        // Newest matching snapshot is number 146 (agent2, index 18)
+        assert_eq!(results[0].path, "corpus/146.json");
+
+        let oldest = engine
+            .query("corpus/", &query.clone().sort(SortOrder::OldestFirst))
+            .unwrap();
+        assert_eq!(oldest[0].path, "corpus/082.json");
+
+        let by_index = engine
+            .query(
+                "corpus/",
+                &SnapshotQuery::new()
+                    .tag("env", "prod")
+                    .session_id("s0")
+                    .sort(SortOrder::IndexDescending),
+            )
+            .unwrap();
+        assert!(by_index
+            .windows(2)
+            .all(|w| w[0].metadata.snapshot_index >= w[1].metadata.snapshot_index));
+        assert!(by_index
+            .iter()
+            .all(|s| s.metadata.session_id == "s0" && s.metadata.tags["env"] == "prod"));
+    }
+
+    #[test]
+    fn test_query_unsorted_stops_at_limit() {
+        let (engine, _) = create_query_corpus();
+        let results = engine
+            .query(
+                "corpus/",
+                &SnapshotQuery::new().sort(SortOrder::Unsorted).limit(3),
+            )
+            .unwrap();
+
+        let paths: Vec<_> = results.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["corpus/000.json", "corpus/001.json", "corpus/002.json"]
+        );
+    }
+
+    #[test]
+    fn test_update_metadata_preserves_state_and_hash() {
+        let engine = create_test_engine();
+        let agent_json = r#"{"memory": ["a", "b"], "n": 1}"#;
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        let saved = engine
+            .save_snapshot(agent_json, &metadata, "snap.json")
+            .unwrap();
+        let (_, state_before) = engine.load_snapshot("snap.json").unwrap();
+
+        let updated = engine
+            .update_metadata("snap.json", |metadata| {
+                metadata.description = Some("known good".to_string());
+                metadata
+                    .tags
+                    .insert("status".to_string(), "good".to_string());
+            })
+            .unwrap();
+
+        let (loaded, state_after) = engine.load_snapshot("snap.json").unwrap();
+        assert_eq!(state_after, state_before);
+        assert_eq!(loaded.content_hash, saved.content_hash);
+        assert_eq!(loaded.timestamp, saved.timestamp);
+        assert_eq!(loaded.snapshot_id, saved.snapshot_id);
+        assert_eq!(loaded.description.as_deref(), Some("known good"));
+        assert_eq!(loaded.tags["status"], "good");
+        assert_eq!(updated.tags, loaded.tags);
+    }
+
+    #[test]
+    fn test_extra_metadata_survives_save_load_and_update() {
+        let engine = create_test_engine();
+        let mut metadata = SnapshotMetadata::new("agent", "session", 0);
+        metadata.extra.insert(
+            "x-origin".to_string(),
+            serde_json::json!({"host": "replica-2", "attempt": 3}),
+        );
+        engine
+            .save_snapshot(r#"{"n": 1}"#, &metadata, "snap.json")
+            .unwrap();
+
+        engine
+            .update_metadata("snap.json", |metadata| {
+                metadata.description = Some("annotated".to_string());
+            })
+            .unwrap();
+
+        let (loaded, _) = engine.load_snapshot("snap.json").unwrap();
+        assert_eq!(loaded.extra, metadata.extra);
+        assert_eq!(loaded.description.as_deref(), Some("annotated"));
+    }
+
+    #[test]
+    fn test_sensitive_metadata_encrypted_at_rest() {
+        use crate::sensitive::{SensitiveField, XorCipher};
+
+        let storage = MemoryStorage::new();
+        let engine = SnapshotEngine::new(storage.clone(), NoCompression::new())
+            .with_metadata_cipher(XorCipher(0x42));
+        let metadata = SnapshotMetadata::builder("agent", "session", 0)
+            .description("customer Jane Doe")
+            .tag("env", "prod")
+            .sensitive(SensitiveField::Description)
+            .build();
+        engine
+            .save_snapshot(r#"{"n": 1}"#, &metadata, "snap.json")
+            .unwrap();
+
+        // Stored bytes don't contain the plaintext
+        let stored = storage.load("snap.json").unwrap();
+        assert!(!String::from_utf8(stored).unwrap().contains("Jane Doe"));
+
+        // The keyed engine decrypts transparently
+        let (loaded, _) = engine.load_snapshot("snap.json").unwrap();
+        assert_eq!(loaded.description.as_deref(), Some("customer Jane Doe"));
+        assert_eq!(loaded.tags["env"], "prod");
+
+        // Without the key, loading works but revealing and editing sensitive fields fail
+        let keyless = SnapshotEngine::new(storage, NoCompression::new());
+        let (encrypted, _) = keyless.load_snapshot("snap.json").unwrap();
+        assert!(encrypted.has_encrypted_fields());
+        assert!(matches!(
+            encrypted.revealed(),
+            Err(PersistError::Encryption(_))
+        ));
+        assert!(keyless
+            .update_metadata("snap.json", |m| m.expires_at = Some(chrono::Utc::now()))
+            .is_ok());
+        assert!(matches!(
+            keyless.update_metadata("snap.json", |m| m.description = None),
+            Err(PersistError::Encryption(_))
+        ));
+    }
+
+    /// Store a valid snapshot, then rewrite its format version to a future major version
+    fn create_future_version_fixture(storage: &MemoryStorage) {
+        let engine = SnapshotEngine::new(storage.clone(), NoCompression::new());
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        engine
+            .save_snapshot(r#"{"n": 1}"#, &metadata, "future.json")
+            .unwrap();
+
+        let mut container: serde_json::Value =
+            serde_json::from_slice(&storage.load("future.json").unwrap()).unwrap();
+        container["metadata"]["format_version"] = serde_json::json!(METADATA_FORMAT_VERSION + 1);
+        container["metadata"]["field_from_the_future"] = serde_json::json!(true);
+        storage
+            .save(&serde_json::to_vec(&container).unwrap(), "future.json")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_compatibility_modes_with_future_version() {
+        let storage = MemoryStorage::new();
+        create_future_version_fixture(&storage);
+
+        let strict = SnapshotEngine::new(storage.clone(), NoCompression::new());
+        assert!(matches!(
+            strict.load_snapshot("future.json"),
+            Err(PersistError::InvalidFormat(_))
+        ));
+
+        for mode in [CompatibilityMode::Warn, CompatibilityMode::Force] {
+            let engine = SnapshotEngine::new(storage.clone(), NoCompression::new())
+                .with_compatibility_mode(mode);
+            let (metadata, state) = engine.load_snapshot("future.json").unwrap();
+            assert_eq!(metadata.format_version, METADATA_FORMAT_VERSION + 1);
+            assert_eq!(state, r#"{"n":1}"#);
+        }
+    }
+
+    #[test]
+    fn test_force_mode_still_verifies_integrity() {
+        let storage = MemoryStorage::new();
+        create_future_version_fixture(&storage);
+
+        let mut container: serde_json::Value =
+            serde_json::from_slice(&storage.load("future.json").unwrap()).unwrap();
+        container["agent_state"]["n"] = serde_json::json!(2);
+        storage
+            .save(&serde_json::to_vec(&container).unwrap(), "future.json")
+            .unwrap();
+
+        let mut engine = SnapshotEngine::new(storage, NoCompression::new());
+        engine.set_compatibility_mode(CompatibilityMode::Force);
+        assert!(matches!(
+            engine.load_snapshot("future.json"),
+            Err(PersistError::IntegrityCheckFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_update_metadata_rejects_immutable_fields() {
+        let engine = create_test_engine();
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        engine
+            .save_snapshot(r#"{"n": 1}"#, &metadata, "snap.json")
+            .unwrap();
+
+        let result = engine.update_metadata("snap.json", |metadata| {
+            metadata.snapshot_index = 9;
+        });
+        assert!(matches!(result, Err(PersistError::Validation(_))));
+
+        let (loaded, _) = engine.load_snapshot("snap.json").unwrap();
+        assert_eq!(loaded.snapshot_index, 0);
+    }
+
+    #[test]
+    fn test_update_metadata_requires_atomic_overwrite() {
+        /// Storage that forwards to memory but does not claim atomic overwrites
+        struct NonAtomicStorage(MemoryStorage);
+
+        impl StorageAdapter for NonAtomicStorage {
+            fn save(&self, data: &[u8], path: &str) -> Result<()> {
+                self.0.save(data, path)
+            }
+            fn load(&self, path: &str) -> Result<Vec<u8>> {
+                self.0.load(path)
+            }
+            fn exists(&self, path: &str) -> bool {
+                self.0.exists(path)
+            }
+            fn delete(&self, path: &str) -> Result<()> {
+                self.0.delete(path)
+            }
+        }
+
+        let engine =
+            SnapshotEngine::new(NonAtomicStorage(MemoryStorage::new()), NoCompression::new());
+        let metadata = SnapshotMetadata::new("agent", "session", 0);
+        engine
+            .save_snapshot(r#"{"n": 1}"#, &metadata, "snap.json")
+            .unwrap();
+
+        let result = engine.update_metadata("snap.json", |metadata| {
+            metadata.description = Some("known good".to_string());
+        });
+        assert!(
+            matches!(result, Err(PersistError::Storage(_))),
+            "{result:?}"
+        );
+        let (loaded, _) = engine.load_snapshot("snap.json").unwrap();
+        assert_eq!(loaded.description, None);
+    }
+
+    /// Storage that forwards to memory and counts the bytes read from it
+    #[derive(Default)]
+    struct CountingStorage {
+        inner: MemoryStorage,
+        bytes_read: std::sync::atomic::AtomicU64,
+    }
+
+    impl CountingStorage {
+        fn take_bytes_read(&self) -> u64 {
+            self.bytes_read
+                .swap(0, std::sync::atomic::Ordering::Relaxed)
+        }
+
+        fn count(&self, data: Result<Vec<u8>>) -> Result<Vec<u8>> {
+            if let Ok(data) = &data {
+                self.bytes_read
+                    .fetch_add(data.len() as u64, std::sync::atomic::Ordering::Relaxed);
+            }
+            data
+        }
+    }
+
+    impl StorageAdapter for CountingStorage {
+        fn save(&self, data: &[u8], path: &str) -> Result<()> {
+            self.inner.save(data, path)
+        }
+        fn load(&self, path: &str) -> Result<Vec<u8>> {
+            self.count(self.inner.load(path))
+        }
+        fn load_prefix(&self, path: &str, max_len: u64) -> Result<Vec<u8>> {
+            self.count(self.inner.load_prefix(path, max_len))
+        }
+        fn exists(&self, path: &str) -> bool {
+            self.inner.exists(path)
+        }
+        fn delete(&self, path: &str) -> Result<()> {
+            self.inner.delete(path)
+        }
+        fn list(&self, prefix: &str) -> Result<Vec<String>> {
+            self.inner.list(prefix)
+        }
+    }
+
+    /// Agent state of about `len` bytes that barely compresses
+    fn incompressible_agent_json(len: usize) -> String {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let words: Vec<String> = (0..len / 19)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                format!("{state:016x}")
+            })
+            .collect();
+        serde_json::json!({ "memory": words }).to_string()
+    }
+
+    #[test]
+    fn test_read_snapshot_metadata_reads_only_the_start() {
+        let engine = SnapshotEngine::new(
+            CountingStorage::default(),
+            crate::compression::GzipCompressor::new(),
+        );
+        let metadata = SnapshotMetadata::new("agent", "session", 3).with_description("large");
+        engine
+            .save_snapshot(
+                &incompressible_agent_json(4 << 20),
+                &metadata,
+                "large.json.gz",
+            )
+            .unwrap();
+
+        let (loaded, _) = engine.load_snapshot("large.json.gz").unwrap();
+        let full_read = engine.storage.take_bytes_read();
+
+        let read = engine.read_snapshot_metadata("large.json.gz").unwrap();
+        let metadata_read = engine.storage.take_bytes_read();
+
+        assert_eq!(read.snapshot_index, 3);
+        assert_eq!(read.description.as_deref(), Some("large"));
+        assert_eq!(read.content_hash, loaded.content_hash);
+        assert!(
+            metadata_read * 100 < full_read,
+            "read {metadata_read} of {full_read} bytes"
+        );
+    }
+
+    #[test]
+    fn test_read_snapshot_metadata_falls_back_for_large_metadata() {
+        let engine = SnapshotEngine::new(
+            CountingStorage::default(),
+            crate::compression::GzipCompressor::new(),
+        );
+        let description = incompressible_agent_json(4 * METADATA_READ_SIZE as usize);
+        let metadata = SnapshotMetadata::new("agent", "session", 0).with_description(&description);
+        engine
+            .save_snapshot(
+                &incompressible_agent_json(1 << 20),
+                &metadata,
+                "snap.json.gz",
+            )
+            .unwrap();
+
+        let read = engine.read_snapshot_metadata("snap.json.gz").unwrap();
+        assert_eq!(read.description.as_deref(), Some(description.as_str()));
+        assert!(engine.storage.take_bytes_read() > METADATA_READ_SIZE);
+
+        // Small snapshots are read in one go
+        engine
+            .save_snapshot(
+                r#"{"n": 1}"#,
+                &SnapshotMetadata::new("agent", "session", 1),
+                "small.json.gz",
+            )
+            .unwrap();
+        assert_eq!(
+            engine
+                .read_snapshot_metadata("small.json.gz")
+                .unwrap()
+                .snapshot_index,
+            1
+        );
+        assert!(engine.storage.take_bytes_read() < METADATA_READ_SIZE);
+    }
+
+    #[test]
+    fn test_next_index_empty_history() {
+        let engine = create_test_engine();
+        assert_eq!(engine.next_index("agent", "session").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_next_index_contiguous_increments() {
+        let engine = create_test_engine();
+
+        for expected in 0..5 {
+            let index = engine.next_index("agent", "session").unwrap();
+            assert_eq!(index, expected);
+            let metadata = SnapshotMetadata::new("agent", "session", index);
+            engine
+                .save_snapshot(r#"{"n": 1}"#, &metadata, &format!("agent/{index}.json"))
+                .unwrap();
+        }
+
+        // Other sessions are tracked independently
+        assert_eq!(engine.next_index("agent", "other").unwrap(), 0);
+        assert_eq!(
+            engine
+                .next_index_under("agent/", "agent", "session")
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            engine
+                .next_index_under("missing/", "agent", "session")
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_latest_snapshot_breaks_ties() {
+        let engine = create_test_engine();
+        let now = chrono::Utc::now();
+        let save = |session: &str, index: u64, age_minutes: i64, path: &str| {
+            let mut metadata = SnapshotMetadata::new("agent", session, index);
+            metadata.timestamp = now - chrono::Duration::minutes(age_minutes);
+            engine
+                .save_snapshot(r#"{"n": 1}"#, &metadata, path)
+                .unwrap();
+        };
+        save("s1", 4, 30, "s1/4.json");
+        save("s1", 5, 20, "s1/5a.json");
+        save("s1", 5, 10, "s1/5b.json");
+        save("s1", 5, 10, "s1/5c.json");
+        save("s2", 1, 5, "s2/1.json");
+
+        let latest = |session: Option<&str>| {
+            engine
+                .latest_snapshot("", "agent", session)
+                .unwrap()
+                .map(|summary| summary.path)
+        };
+        // Same index: newer timestamp, then greater path
+        assert_eq!(latest(Some("s1")).as_deref(), Some("s1/5c.json"));
+        // Across sessions the newest snapshot wins, despite its lower index
+        assert_eq!(latest(None).as_deref(), Some("s2/1.json"));
+        assert_eq!(latest(Some("s3")), None);
+        assert_eq!(engine.next_index("agent", "s1").unwrap(), 6);
+    }
+
+    #[test]
+    fn test_strict_index_rejects_duplicates() {
+        let engine = create_test_engine().with_strict_index(true);
+        let agent_json = r#"{"n": 1}"#;
+
+        engine
+            .save_snapshot(
+                agent_json,
+                &SnapshotMetadata::new("agent", "session", 3),
+                "a/3.json",
+            )
+            .unwrap();
+
+        for index in [3, 1] {
+            let result = engine.save_snapshot(
+                agent_json,
+                &SnapshotMetadata::new("agent", "session", index),
+                &format!("a/{index}-retry.json"),
+            );
+            assert!(matches!(
+                result,
+                Err(PersistError::IndexOutOfOrder { latest: 3, .. })
+            ));
+        }
+
+        engine
+            .save_snapshot(
+                agent_json,
+                &SnapshotMetadata::new("agent", "session", 4),
+                "a/4.json",
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_next_index_may_collide() {
+        // next_index does not reserve the index it returns: concurrent callers
+        // observing the same history receive the same value.
+        let engine = create_test_engine();
+        engine
+            .save_snapshot(
+                r#"{"n": 1}"#,
+                &SnapshotMetadata::new("agent", "session", 0),
+                "0.json",
+            )
+            .unwrap();
+
+        let indices: Vec<u64> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| engine.next_index("agent", "session").unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert!(indices.iter().all(|&index| index == 1));
+    }
+
     #[test]
     fn test_with_real_compression() {
         use crate::compression::GzipCompressor;
@@ -617,4 +2702,180 @@ mod tests {
         let loaded_value: serde_json::Value = serde_json::from_str(&loaded_json).unwrap();
         assert_eq!(original_value, loaded_value);
     }
+
+    #[test]
+    fn test_gcs_config_validated_before_building() {
+        let config = crate::StorageConfig::gcs_with_bucket(String::new());
+        let err = create_engine_from_config(config).err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("GCS backend requires a valid bucket name"),
+            "{err}"
+        );
+
+        let config = crate::StorageConfig::gcs_with_bucket("bucket/agents".to_string());
+        let err = create_storage_from_config(config).err().unwrap();
+        assert!(err.to_string().contains("gcs_prefix"), "{err}");
+    }
+
+    #[cfg(not(feature = "gcs"))]
+    #[test]
+    fn test_gcs_backend_without_feature() {
+        let config = crate::StorageConfig::gcs_with_bucket("bucket".to_string());
+        let err = create_engine_from_config(config).err().unwrap();
+        assert!(matches!(err, PersistError::Validation(_)));
+        assert!(
+            err.to_string().contains("Enable the 'gcs' feature"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_recompress_snapshot() {
+        let storage = MemoryStorage::new();
+        let plain = SnapshotEngine::new(storage.clone(), NoCompression::new());
+        let gzip = SnapshotEngine::new(storage.clone(), crate::GzipCompressor::new());
+        let agent_json = r#"{"memory": ["hello", "hello", "hello", "hello", "hello"]}"#;
+        let metadata = SnapshotMetadata::builder("agent", "session", 0)
+            .tag("env", "test")
+            .build();
+        let saved = plain
+            .save_snapshot(agent_json, &metadata, "a.json")
+            .unwrap();
+
+        // Either engine reads what the other wrote
+        let result = plain
+            .recompress_snapshot("a.json", &crate::GzipCompressor::max())
+            .unwrap();
+        assert_eq!(result.before, saved.compressed_size.unwrap());
+        assert!(result.after < result.before);
+        assert_eq!(result.metadata.compression_algorithm, "gzip");
+        assert_eq!(result.metadata.content_hash, saved.content_hash);
+
+        for engine in [&plain as &dyn SnapshotEngineInterface, &gzip] {
+            let (loaded, state) = engine.load_snapshot("a.json").unwrap();
+            assert_eq!(loaded.compression_algorithm, "gzip");
+            assert_eq!(loaded.tags, saved.tags);
+            assert_eq!(loaded.snapshot_id, saved.snapshot_id);
+            let state: serde_json::Value = serde_json::from_str(&state).unwrap();
+            assert_eq!(
+                state,
+                serde_json::from_str::<serde_json::Value>(agent_json).unwrap()
+            );
+        }
+
+        // Metadata updates keep the stored algorithm rather than the engine's
+        plain
+            .update_metadata("a.json", |metadata| metadata.description = Some("x".into()))
+            .unwrap();
+        let stored = storage.load("a.json").unwrap();
+        assert_eq!(compression::detect_algorithm(&stored), Some("gzip"));
+        gzip.verify_snapshot("a.json").unwrap();
+    }
+
+    /// Storage in memory that counts health checks and can fail writes
+    #[derive(Default)]
+    struct ProbedStorage {
+        inner: MemoryStorage,
+        health_checks: std::sync::atomic::AtomicUsize,
+        fail_saves: std::sync::atomic::AtomicBool,
+    }
+
+    impl ProbedStorage {
+        fn health_checks(&self) -> usize {
+            self.health_checks
+                .load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
+
+    impl StorageAdapter for ProbedStorage {
+        fn save(&self, data: &[u8], path: &str) -> Result<()> {
+            if self.fail_saves.load(std::sync::atomic::Ordering::Relaxed) {
+                return Err(PersistError::storage("disk full"));
+            }
+            self.inner.save(data, path)
+        }
+        fn load(&self, path: &str) -> Result<Vec<u8>> {
+            self.inner.load(path)
+        }
+        fn exists(&self, path: &str) -> bool {
+            self.inner.exists(path)
+        }
+        fn delete(&self, path: &str) -> Result<()> {
+            self.inner.delete(path)
+        }
+        fn list(&self, prefix: &str) -> Result<Vec<String>> {
+            self.inner.list(prefix)
+        }
+        fn health_check(&self) -> Result<()> {
+            self.health_checks
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_health_reports_are_cached() {
+        let engine = SnapshotEngine::new(ProbedStorage::default(), NoCompression::new());
+        let shallow = engine.health(false);
+        assert!(shallow.is_healthy());
+        assert_eq!(shallow.backend, "custom");
+        assert!(!shallow.deep);
+        assert_eq!(shallow.last_error, None);
+        assert_eq!(engine.health(false), shallow);
+        assert_eq!(engine.storage.health_checks(), 1);
+
+        // Deep checks are cached separately and leave no probe behind
+        let deep = engine.health(true);
+        assert!(deep.is_healthy() && deep.deep);
+        assert_eq!(engine.health(true), deep);
+        assert_eq!(engine.storage.health_checks(), 2);
+        assert_eq!(engine.storage.list("").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_expired_health_reports_are_refreshed() {
+        let engine = SnapshotEngine::new(ProbedStorage::default(), NoCompression::new())
+            .with_health_cache_ttl(Duration::from_millis(20));
+        let first = engine.health(false);
+        std::thread::sleep(Duration::from_millis(30));
+        let second = engine.health(false);
+        assert_eq!(engine.storage.health_checks(), 2);
+        assert!(second.checked_at > first.checked_at);
+
+        let uncached = SnapshotEngine::new(ProbedStorage::default(), NoCompression::new())
+            .with_health_cache_ttl(Duration::ZERO);
+        for _ in 0..3 {
+            uncached.health(false);
+        }
+        assert_eq!(uncached.storage.health_checks(), 3);
+    }
+
+    #[test]
+    fn test_deep_health_check_failure() {
+        let engine = SnapshotEngine::new(ProbedStorage::default(), NoCompression::new())
+            .with_health_cache_ttl(Duration::ZERO);
+        engine
+            .storage
+            .fail_saves
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+
+        // Only the probe write fails
+        assert!(engine.health(false).is_healthy());
+        let report = engine.health(true);
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        assert!(report.deep);
+        assert_eq!(
+            report.last_error.as_deref(),
+            Some("Storage error: disk full")
+        );
+
+        engine
+            .storage
+            .fail_saves
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        let recovered = engine.health(true);
+        assert!(recovered.is_healthy());
+        assert_eq!(recovered.last_error, None);
+    }
 }
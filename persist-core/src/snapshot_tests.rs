@@ -381,19 +381,12 @@ mod tests {
 #[cfg(test)]
 mod integration_tests {
     use super::*;
-    use crate::config::{StorageConfig, StorageBackend};
+    use crate::config::StorageConfig;
     use crate::snapshot::{create_engine_from_config, create_default_engine};
 
     #[test]
     fn test_engine_from_config_local() {
-        let config = StorageConfig {
-            backend: StorageBackend::Local,
-            bucket_name: None,
-            region: None,
-            access_key_id: None,
-            secret_access_key: None,
-            endpoint_url: None,
-        };
+        let config = StorageConfig::default_local();
         
         let engine = create_engine_from_config(config).unwrap();
         
@@ -52,15 +52,21 @@ use std::sync::Arc;
 #[cfg(feature = "gcs")]
 use tokio::runtime::Runtime;
 #[cfg(feature = "gcs")]
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info};
 
 #[cfg(feature = "gcs")]
-use super::StorageAdapter;
+use super::{CallOptions, StorageAdapter};
 #[cfg(feature = "gcs")]
 #[cfg(feature = "metrics")]
-use crate::observability::MetricsTimer;
+use crate::observability::PersistMetrics;
 #[cfg(feature = "gcs")]
-use crate::{PersistError, Result};
+use crate::observability::{success_log, trace_metadata};
+#[cfg(feature = "gcs")]
+use crate::{config::RetryConfig, PersistError, Result};
+#[cfg(feature = "gcs")]
+use persist_retry::{
+    attempt_with_timeout, retry_blocking, JitterMode, Operation, OperationKind, RetryPolicy,
+};
 
 /// Google Cloud Storage adapter
 ///
@@ -94,6 +100,10 @@ pub struct GCSStorageAdapter {
     bucket: String,
     prefix: Option<String>,
     runtime: Arc<Runtime>,
+    retry_config: Option<RetryConfig>,
+    request_timeout: Option<std::time::Duration>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<PersistMetrics>,
 }
 
 #[cfg(feature = "gcs")]
@@ -195,9 +205,73 @@ impl GCSStorageAdapter {
             bucket,
             prefix,
             runtime: Arc::new(runtime),
+            retry_config: None,
+            request_timeout: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         })
     }
 
+    /// Use the given retry settings instead of the adapter defaults
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = Some(retry_config);
+        self
+    }
+
+    /// Abandon each request, connecting included, after `timeout` (the shorter of this
+    /// and the retry settings' attempt timeout applies)
+    pub fn with_request_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Record operations in `metrics` instead of [`PersistMetrics::global`]
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: PersistMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Metrics the operations and retries are recorded in
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> &PersistMetrics {
+        match &self.metrics {
+            Some(metrics) => metrics,
+            None => PersistMetrics::global(),
+        }
+    }
+
+    /// Retry policy for a storage operation (`save`, `load`, ...)
+    fn retry_policy(&self, operation: &str) -> RetryPolicy {
+        let mut policy = match &self.retry_config {
+            Some(retry_config) => retry_config.policy_for(operation),
+            None => RetryPolicy::from(ExponentialBackoff {
+                max_elapsed_time: Some(std::time::Duration::from_secs(60)),
+                max_interval: std::time::Duration::from_secs(30),
+                ..Default::default()
+            })
+            .with_jitter(JitterMode::Full)
+            .with_attempt_timeout(std::time::Duration::from_secs(30)),
+        };
+        if let Some(timeout) = self.request_timeout {
+            policy.attempt_timeout =
+                Some(policy.attempt_timeout.map_or(timeout, |t| t.min(timeout)));
+        }
+        #[cfg(feature = "metrics")]
+        {
+            policy.metrics = crate::observability::storage_retry_metrics_with(
+                self.metrics(),
+                crate::StorageBackend::GCS,
+            );
+        }
+        policy
+    }
+
+    /// Retried operation of `kind` on `key`, targeting `bucket/key` in retry errors
+    fn operation(&self, kind: OperationKind, key: &str) -> Operation {
+        Operation::new(kind).with_target(format!("{}/{key}", self.bucket))
+    }
+
     /// Validate bucket name according to GCS naming rules
     fn validate_bucket_name(bucket: &str) -> Result<()> {
         if bucket.is_empty() {
@@ -233,6 +307,90 @@ impl GCSStorageAdapter {
             None => key.to_string(),
         }
     }
+
+    /// Download an object, up to and including byte `last_byte` if given, retrying
+    /// until `options.deadline` at the latest
+    fn download(
+        &self,
+        path: &str,
+        last_byte: Option<u64>,
+        options: &CallOptions,
+    ) -> Result<Vec<u8>> {
+        #[cfg(feature = "metrics")]
+        let _timer = self.metrics().start_gcs_operation("load");
+        #[cfg(feature = "metrics")]
+        let _in_flight = self.metrics().track_in_flight("gcs");
+
+        let key = self.build_object_path(path);
+        success_log!(bucket=%self.bucket, key=%key, "Loading snapshot from GCS");
+
+        // Use the configured exponential backoff
+        let mut policy = self.retry_policy("load");
+        policy.deadline = options.deadline;
+        if policy.deadline_passed() {
+            return Err(PersistError::storage(format!(
+                "GCS load deadline exceeded before the first attempt (key: {key})"
+            )));
+        }
+        let operation = self.operation(OperationKind::Load, &key);
+        let result = retry_blocking(operation, &policy, |_attempt| {
+            let bucket = self.bucket.clone();
+            let key_for_async = key.clone();
+            let client = self.client.clone();
+
+            let download = async move {
+                use google_cloud_storage::http::objects::download::Range;
+                use google_cloud_storage::http::objects::get::GetObjectRequest;
+
+                let req = GetObjectRequest {
+                    bucket,
+                    object: key_for_async,
+                    ..Default::default()
+                };
+
+                let range = Range(last_byte.map(|_| 0), last_byte);
+                client.download_object(&req, &range).await
+            };
+            let attempt = self
+                .runtime
+                .block_on(attempt_with_timeout(policy.attempt_timeout, download));
+            match attempt {
+                Some(result) => {
+                    result.map_err(|e| map_gcs_error("download_object", &e, &self.bucket, &key))
+                }
+                None => Err(attempt_timed_out(
+                    "download_object",
+                    &key,
+                    policy.attempt_timeout,
+                )),
+            }
+        });
+
+        match result {
+            Ok(data) => {
+                debug!(
+                    "Downloaded {} bytes from gs://{}/{}",
+                    data.len(),
+                    self.bucket,
+                    key
+                );
+                #[cfg(feature = "metrics")]
+                {
+                    self.metrics().record_gcs_request("load");
+                    self.metrics().record_gcs_bytes("download", data.len());
+                }
+                success_log!(bucket=%self.bucket, key=%key, bytes_downloaded=data.len(), "Loaded snapshot from GCS");
+                options.report_progress(data.len() as u64, data.len() as u64)?;
+                Ok(data)
+            }
+            Err(err) => {
+                error!(bucket=%self.bucket, key=%key, error=?err, "Failed to load snapshot from GCS");
+                #[cfg(feature = "metrics")]
+                self.metrics().record_gcs_error("load");
+                Err(err)
+            }
+        }
+    }
 }
 
 #[cfg(feature = "gcs")]
@@ -242,69 +400,79 @@ impl StorageAdapter for GCSStorageAdapter {
     /// Uploads the data as an object to the configured GCS bucket.
     /// Includes retry logic for transient failures.
     fn save(&self, data: &[u8], path: &str) -> Result<()> {
+        self.save_with_options(data, path, &CallOptions::default())
+    }
+
+    /// Save snapshot data to GCS, retrying until `options.deadline` at the latest
+    fn save_with_options(&self, data: &[u8], path: &str, options: &CallOptions) -> Result<()> {
+        #[cfg(feature = "metrics")]
+        let _timer = self.metrics().start_gcs_operation("save");
         #[cfg(feature = "metrics")]
-        let _timer = MetricsTimer::start_gcs_operation("save");
+        let _in_flight = self.metrics().track_in_flight("gcs");
 
         let key = self.build_object_path(path);
-        info!(bucket=%self.bucket, key=%key, size=%data.len(), "Saving snapshot to GCS");
+        success_log!(bucket=%self.bucket, key=%key, size=%data.len(), "Saving snapshot to GCS");
 
         // Convert to Bytes to avoid copying on each retry
         let data_bytes = Bytes::copy_from_slice(data);
 
-        // Use proper exponential backoff instead of manual sleep
-        let backoff = ExponentialBackoff {
-            max_elapsed_time: Some(std::time::Duration::from_secs(60)),
-            max_interval: std::time::Duration::from_secs(30),
-            ..Default::default()
-        };
-
-        let bucket = self.bucket.clone();
-        let key_str = key.clone();
-        let client = self.client.clone();
-
-        let result = {
-            let bucket_clone = bucket.clone();
-            let key_clone = key_str.clone();
-
-            backoff::retry(backoff, || {
-                let bucket = bucket_clone.clone();
-                let key_for_async = key_clone.clone();
-                let data_owned = data_bytes.clone();
-                let client = client.clone();
+        // Use the configured exponential backoff
+        let mut policy = self.retry_policy("save");
+        policy.deadline = options.deadline;
+        if policy.deadline_passed() {
+            return Err(PersistError::storage(format!(
+                "GCS save deadline exceeded before the first attempt (key: {key})"
+            )));
+        }
+        // Objects written inside a span record it in their metadata, which takes a
+        // multipart upload
+        let metadata = trace_metadata();
+        let operation = self.operation(OperationKind::Save, &key);
+        let result = retry_blocking(operation, &policy, |_attempt| {
+            let bucket = self.bucket.clone();
+            let key_for_async = key.clone();
+            let data_owned = data_bytes.clone();
+            let client = self.client.clone();
+            let metadata = metadata.clone();
+
+            let upload = async move {
+                use google_cloud_storage::http::objects::upload::{
+                    Media, UploadObjectRequest, UploadType,
+                };
+                use google_cloud_storage::http::objects::Object;
 
-                let result = self.runtime.block_on(async move {
-                    use google_cloud_storage::http::objects::upload::{
-                        Media, UploadObjectRequest, UploadType,
-                    };
+                let req = UploadObjectRequest {
+                    bucket,
+                    ..Default::default()
+                };
 
-                    let req = UploadObjectRequest {
-                        bucket: bucket.clone(),
+                let upload_type = if metadata.is_empty() {
+                    UploadType::Simple(Media::new(key_for_async))
+                } else {
+                    UploadType::Multipart(Box::new(Object {
+                        name: key_for_async,
+                        metadata: Some(metadata),
                         ..Default::default()
-                    };
-
-                    let upload_type = UploadType::Simple(Media::new(key_for_async.clone()));
-                    client
-                        .upload_object(&req, data_owned.to_vec(), &upload_type)
-                        .await
-                });
-
-                match result {
-                    Ok(_) => Ok(()),
-                    Err(e) if is_retryable_error(&e) => {
-                        warn!(
-                            bucket=%bucket_clone,
-                            key=%key_clone,
-                            error=?e,
-                            "GCS save failed, retrying..."
-                        );
-                        #[cfg(feature = "metrics")]
-                        crate::observability::PersistMetrics::global().record_gcs_retry("save");
-                        Err(backoff::Error::transient(e))
-                    }
-                    Err(e) => Err(backoff::Error::permanent(e)),
+                    }))
+                };
+                client
+                    .upload_object(&req, data_owned.to_vec(), &upload_type)
+                    .await
+            };
+            let attempt = self
+                .runtime
+                .block_on(attempt_with_timeout(policy.attempt_timeout, upload));
+            match attempt {
+                Some(result) => {
+                    result.map_err(|e| map_gcs_error("upload_object", &e, &self.bucket, &key))
                 }
-            })
-        };
+                None => Err(attempt_timed_out(
+                    "upload_object",
+                    &key,
+                    policy.attempt_timeout,
+                )),
+            }
+        });
 
         match result {
             Ok(_) => {
@@ -313,14 +481,17 @@ impl StorageAdapter for GCSStorageAdapter {
                     self.bucket, key
                 );
                 #[cfg(feature = "metrics")]
-                crate::observability::PersistMetrics::global().record_gcs_request("save");
-                Ok(())
+                {
+                    self.metrics().record_gcs_request("save");
+                    self.metrics().record_gcs_bytes("upload", data.len());
+                }
+                success_log!(bucket=%self.bucket, key=%key, bytes_uploaded=data.len(), "Saved snapshot to GCS");
+                options.report_progress(data.len() as u64, data.len() as u64)
             }
-            Err(backoff::Error::Permanent(e)) | Err(backoff::Error::Transient { err: e, .. }) => {
-                let err = map_gcs_error("upload_object", &e, &key);
+            Err(err) => {
                 error!(bucket=%self.bucket, key=%key, error=?err, "Failed to save snapshot to GCS");
                 #[cfg(feature = "metrics")]
-                crate::observability::PersistMetrics::global().record_gcs_error("save");
+                self.metrics().record_gcs_error("save");
                 Err(err)
             }
         }
@@ -331,82 +502,20 @@ impl StorageAdapter for GCSStorageAdapter {
     /// Downloads the object data from the configured GCS bucket.
     /// Includes retry logic for transient failures.
     fn load(&self, path: &str) -> Result<Vec<u8>> {
-        #[cfg(feature = "metrics")]
-        let _timer = MetricsTimer::start_gcs_operation("load");
-
-        let key = self.build_object_path(path);
-        info!(bucket=%self.bucket, key=%key, "Loading snapshot from GCS");
-
-        // Use proper exponential backoff
-        let backoff = ExponentialBackoff {
-            max_elapsed_time: Some(std::time::Duration::from_secs(60)),
-            max_interval: std::time::Duration::from_secs(30),
-            ..Default::default()
-        };
-
-        let bucket = self.bucket.clone();
-        let key_str = key.clone();
-        let client = self.client.clone();
-
-        let result = {
-            let bucket_clone = bucket.clone();
-            let key_clone = key_str.clone();
-
-            backoff::retry(backoff, || {
-                let bucket = bucket_clone.clone();
-                let key_for_async = key_clone.clone();
-                let client = client.clone();
-
-                let result = self.runtime.block_on(async move {
-                    use google_cloud_storage::http::objects::get::GetObjectRequest;
+        self.load_with_options(path, &CallOptions::default())
+    }
 
-                    let req = GetObjectRequest {
-                        bucket: bucket.clone(),
-                        object: key_for_async.clone(),
-                        ..Default::default()
-                    };
-
-                    client.download_object(&req, &Default::default()).await
-                });
-
-                match result {
-                    Ok(data) => Ok(data),
-                    Err(e) if is_retryable_error(&e) => {
-                        warn!(
-                            bucket=%bucket_clone,
-                            key=%key_clone,
-                            error=?e,
-                            "GCS load failed, retrying..."
-                        );
-                        #[cfg(feature = "metrics")]
-                        crate::observability::PersistMetrics::global().record_gcs_retry("load");
-                        Err(backoff::Error::transient(e))
-                    }
-                    Err(e) => Err(backoff::Error::permanent(e)),
-                }
-            })
-        };
+    /// Load snapshot data from GCS, retrying until `options.deadline` at the latest
+    fn load_with_options(&self, path: &str, options: &CallOptions) -> Result<Vec<u8>> {
+        self.download(path, None, options)
+    }
 
-        match result {
-            Ok(data) => {
-                debug!(
-                    "Downloaded {} bytes from gs://{}/{}",
-                    data.len(),
-                    self.bucket,
-                    key
-                );
-                #[cfg(feature = "metrics")]
-                crate::observability::PersistMetrics::global().record_gcs_request("load");
-                Ok(data)
-            }
-            Err(backoff::Error::Permanent(e)) | Err(backoff::Error::Transient { err: e, .. }) => {
-                let err = map_gcs_error("download_object", &e, &key);
-                error!(bucket=%self.bucket, key=%key, error=?err, "Failed to load snapshot from GCS");
-                #[cfg(feature = "metrics")]
-                crate::observability::PersistMetrics::global().record_gcs_error("load");
-                Err(err)
-            }
+    /// Load the first `max_len` bytes of a snapshot with a ranged download
+    fn load_prefix(&self, path: &str, max_len: u64) -> Result<Vec<u8>> {
+        if max_len == 0 {
+            return Ok(Vec::new());
         }
+        self.download(path, Some(max_len - 1), &CallOptions::default())
     }
 
     /// Check if a snapshot exists at the specified GCS location
@@ -432,13 +541,37 @@ impl StorageAdapter for GCSStorageAdapter {
         result.is_ok()
     }
 
+    /// Object size from its metadata, without downloading the object
+    fn size(&self, path: &str) -> Result<u64> {
+        let key = self.build_object_path(path);
+        let bucket = self.bucket.clone();
+        let key_str = key.to_string();
+        let client = self.client.clone();
+
+        let result = self.runtime.block_on(async move {
+            use google_cloud_storage::http::objects::get::GetObjectRequest;
+
+            let req = GetObjectRequest {
+                bucket,
+                object: key_str,
+                ..Default::default()
+            };
+            client.get_object(&req).await
+        });
+
+        match result {
+            Ok(object) => Ok(object.size.max(0) as u64),
+            Err(e) => Err(map_gcs_error("get_object", &e, &self.bucket, &key)),
+        }
+    }
+
     /// Delete a snapshot from GCS
     fn delete(&self, path: &str) -> Result<()> {
         #[cfg(feature = "metrics")]
-        let _timer = MetricsTimer::start_gcs_operation("delete");
+        let _timer = self.metrics().start_gcs_operation("delete");
 
         let key = self.build_object_path(path);
-        info!(bucket=%self.bucket, key=%key, "Deleting snapshot from GCS");
+        success_log!(bucket=%self.bucket, key=%key, "Deleting snapshot from GCS");
 
         let bucket = self.bucket.clone();
         let key_str = key.to_string();
@@ -463,14 +596,74 @@ impl StorageAdapter for GCSStorageAdapter {
                     self.bucket, key
                 );
                 #[cfg(feature = "metrics")]
-                crate::observability::PersistMetrics::global().record_gcs_request("delete");
+                self.metrics().record_gcs_request("delete");
                 Ok(())
             }
             Err(e) => {
-                let err = map_gcs_error("delete_object", &e, &key);
+                let err = map_gcs_error("delete_object", &e, &self.bucket, &key);
                 error!(bucket=%self.bucket, key=%key, error=?err, "Failed to delete snapshot from GCS");
                 #[cfg(feature = "metrics")]
-                crate::observability::PersistMetrics::global().record_gcs_error("delete");
+                self.metrics().record_gcs_error("delete");
+                Err(err)
+            }
+        }
+    }
+
+    /// List snapshots in GCS whose key (relative to the adapter prefix) starts with `prefix`
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let full_prefix = self.build_object_path(prefix);
+        let strip = full_prefix.len() - prefix.len();
+        let bucket = self.bucket.clone();
+        let client = self.client.clone();
+        let list_prefix = full_prefix.clone();
+
+        let result = self.runtime.block_on(async move {
+            use google_cloud_storage::http::objects::list::ListObjectsRequest;
+
+            let mut names = Vec::new();
+            let mut page_token = None;
+            loop {
+                let req = ListObjectsRequest {
+                    bucket: bucket.clone(),
+                    prefix: Some(list_prefix.clone()),
+                    page_token: page_token.take(),
+                    ..Default::default()
+                };
+                let response = match client.list_objects(&req).await {
+                    Ok(response) => response,
+                    Err(e) => return Err(e),
+                };
+                names.extend(
+                    response
+                        .items
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|o| o.name),
+                );
+                match response.next_page_token {
+                    Some(token) => page_token = Some(token),
+                    None => break,
+                }
+            }
+            Ok(names)
+        });
+
+        match result {
+            Ok(names) => {
+                let mut keys: Vec<String> = names
+                    .into_iter()
+                    .filter_map(|name| name.get(strip..).map(str::to_string))
+                    .collect();
+                keys.sort();
+                #[cfg(feature = "metrics")]
+                self.metrics().record_gcs_request("list");
+                Ok(keys)
+            }
+            Err(e) => {
+                let err = map_gcs_error("list_objects", &e, &self.bucket, &full_prefix);
+                error!(bucket=%self.bucket, prefix=%full_prefix, error=?err, "Failed to list snapshots in GCS");
+                #[cfg(feature = "metrics")]
+                self.metrics().record_gcs_error("list");
                 Err(err)
             }
         }
@@ -478,6 +671,38 @@ impl StorageAdapter for GCSStorageAdapter {
 
     // Note: Streaming upload/download methods will be added in a future update
     // when the async trait architecture is properly implemented
+
+    /// Bucket metadata request, made once
+    fn health_check(&self) -> Result<()> {
+        let bucket = self.bucket.clone();
+        let client = self.client.clone();
+
+        let result = self.runtime.block_on(async move {
+            use google_cloud_storage::http::buckets::get::GetBucketRequest;
+
+            let req = GetBucketRequest {
+                bucket,
+                ..Default::default()
+            };
+            client.get_bucket(&req).await
+        });
+
+        result.map(|_| ()).map_err(|e| {
+            PersistError::storage(format!(
+                "Failed to access GCS bucket '{}': {e}",
+                self.bucket
+            ))
+        })
+    }
+
+    /// An upload replaces the whole object; GCS never exposes a partial upload
+    fn atomic_overwrite(&self) -> bool {
+        true
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "gcs"
+    }
 }
 
 #[cfg(feature = "gcs")]
@@ -496,48 +721,12 @@ impl Drop for GCSStorageAdapter {
     }
 }
 
-/// Check if a GCS error is retryable using structured error inspection
-#[cfg(feature = "gcs")]
-fn is_retryable_error(error: &google_cloud_storage::http::Error) -> bool {
-    use google_cloud_storage::http::Error;
-
-    match error {
-        // Use structured error matching instead of string matching
-        Error::HttpClient(err) => {
-            // Network-related errors are retryable
-            err.to_string().contains("timeout")
-                || err.to_string().contains("connection")
-                || err.to_string().contains("network")
-        }
-        Error::Response(response) => {
-            // Check if response contains retryable status codes
-            let response_str = response.to_string();
-            response_str.contains("429") || // Rate limited
-            response_str.contains("500") || // Internal server error
-            response_str.contains("502") || // Bad gateway
-            response_str.contains("503") || // Service unavailable
-            response_str.contains("504") // Gateway timeout
-        }
-        Error::TokenSource(_) => false, // Auth errors are not retryable
-        _ => {
-            // Fallback to string matching for other error types
-            let error_str = error.to_string();
-            error_str.contains("timeout")
-                || error_str.contains("connection")
-                || error_str.contains("network")
-                || error_str.contains("500")
-                || error_str.contains("502")
-                || error_str.contains("503")
-                || error_str.contains("504")
-        }
-    }
-}
-
 /// Map GCS errors to PersistError with structured error classification
 #[cfg(feature = "gcs")]
 fn map_gcs_error(
     operation: &str,
     error: &google_cloud_storage::http::Error,
+    bucket: &str,
     key: &str,
 ) -> PersistError {
     use google_cloud_storage::http::Error;
@@ -546,11 +735,9 @@ fn map_gcs_error(
         Error::Response(response) => {
             let response_str = response.to_string();
             if response_str.contains("404") {
-                PersistError::storage(format!("GCS object not found: {key}"))
+                PersistError::gcs_not_found(bucket.to_string(), key.to_string())
             } else if response_str.contains("401") || response_str.contains("403") {
-                PersistError::storage(format!(
-                    "GCS permission denied for object '{key}': Ensure you have proper IAM permissions. Response: {response_str}"
-                ))
+                PersistError::gcs_access_denied(bucket.to_string(), key.to_string())
             } else if response_str.contains("409") {
                 PersistError::storage(format!("GCS conflict for object '{key}': {response_str}"))
             } else if response_str.contains("412") {
@@ -558,9 +745,12 @@ fn map_gcs_error(
                     "GCS precondition failed for object '{key}': {response_str}"
                 ))
             } else if response_str.contains("429") {
-                PersistError::storage(format!(
-                    "GCS rate limit exceeded for object '{key}' (transient error): {response_str}"
-                ))
+                // Error responses from the GCS client carry no headers, so there is no
+                // Retry-After hint to pass on
+                PersistError::throttled(
+                    format!("GCS rate limit exceeded for object '{key}': {response_str}"),
+                    None,
+                )
             } else if response_str.contains("500")
                 || response_str.contains("502")
                 || response_str.contains("503")
@@ -588,6 +778,19 @@ fn map_gcs_error(
     }
 }
 
+/// Error for a GCS request abandoned after the policy's per-attempt timeout
+#[cfg(feature = "gcs")]
+fn attempt_timed_out(
+    operation: &str,
+    key: &str,
+    timeout: Option<std::time::Duration>,
+) -> PersistError {
+    PersistError::storage(format!(
+        "GCS {operation} attempt timed out after {:?} for object '{key}'",
+        timeout.unwrap_or_default()
+    ))
+}
+
 // When GCS feature is disabled, provide a stub implementation
 #[cfg(not(feature = "gcs"))]
 pub struct GCSStorageAdapter;
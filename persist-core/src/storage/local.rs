@@ -39,15 +39,19 @@ let storage = LocalFileStorage::with_base_dir("/var/persist/snapshots")
 ```
 */
 
-use super::StorageAdapter;
+use super::{CallOptions, GarbageItem, StorageAdapter};
+use crate::observability::success_log;
 #[cfg(feature = "metrics")]
-use crate::observability::MetricsTimer;
+use crate::observability::PersistMetrics;
 use crate::{PersistError, Result};
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 
+/// Bytes between progress reports when streaming large files
+const PROGRESS_CHUNK_SIZE: usize = 64 * 1024;
+
 /// Enterprise-grade local filesystem storage adapter
 ///
 /// This implementation provides secure, atomic, and durable storage on the local filesystem
@@ -85,6 +89,9 @@ pub struct LocalFileStorage {
     durable_writes: bool,
     /// Optional file permissions mask (e.g., 0o600 for owner-only read/write)
     file_permissions: Option<u32>,
+    /// Metrics to record operations in, instead of the global metrics
+    #[cfg(feature = "metrics")]
+    metrics: Option<PersistMetrics>,
 }
 
 impl LocalFileStorage {
@@ -101,6 +108,8 @@ impl LocalFileStorage {
             base_dir: None,
             durable_writes: false,
             file_permissions: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         }
     }
 
@@ -129,6 +138,8 @@ impl LocalFileStorage {
             base_dir: Some(base_dir.as_ref().to_path_buf()),
             durable_writes: false,
             file_permissions: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         }
     }
 
@@ -162,6 +173,22 @@ impl LocalFileStorage {
         self
     }
 
+    /// Record operations in `metrics` instead of [`PersistMetrics::global`]
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: PersistMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Metrics the operations are recorded in
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> &PersistMetrics {
+        match &self.metrics {
+            Some(metrics) => metrics,
+            None => PersistMetrics::global(),
+        }
+    }
+
     /// Resolve and validate the full path for a given storage path
     ///
     /// This method performs security validation to prevent path traversal attacks
@@ -380,17 +407,32 @@ impl LocalFileStorage {
     ///
     /// This method uses buffered I/O to handle large files without loading
     /// everything into memory at once.
-    fn stream_read(&self, path: &Path) -> Result<Vec<u8>> {
+    fn stream_read(&self, path: &Path, size: u64, options: &CallOptions) -> Result<Vec<u8>> {
         let file = File::open(path).map_err(|e| {
             PersistError::io_read(e, format!("Failed to open file {}", path.display()))
         })?;
 
         let mut reader = BufReader::new(file);
-        let mut buffer = Vec::new();
-
-        reader.read_to_end(&mut buffer).map_err(|e| {
-            PersistError::io_read(e, format!("Failed to read file {}", path.display()))
-        })?;
+        let mut buffer = Vec::with_capacity(usize::try_from(size).unwrap_or(0));
+        let mut chunk = vec![0u8; PROGRESS_CHUNK_SIZE];
+
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(PersistError::io_read(
+                        e,
+                        format!("Failed to read file {}", path.display()),
+                    ))
+                }
+            };
+            buffer.extend_from_slice(&chunk[..read]);
+            // The file may have grown since its size was read
+            let transferred = buffer.len() as u64;
+            options.report_progress(transferred, size.max(transferred))?;
+        }
 
         Ok(buffer)
     }
@@ -398,7 +440,7 @@ impl LocalFileStorage {
     /// Stream write large file data for efficient I/O
     ///
     /// This method uses the atomic write approach but with streaming for large files.
-    fn stream_write(&self, target_path: &Path, data: &[u8]) -> Result<()> {
+    fn stream_write(&self, target_path: &Path, data: &[u8], options: &CallOptions) -> Result<()> {
         let parent_dir = target_path.parent().ok_or_else(|| {
             PersistError::validation("Target path has no parent directory".to_string())
         })?;
@@ -418,9 +460,24 @@ impl LocalFileStorage {
 
         // Use buffered writer for efficient I/O
         let mut writer = BufWriter::new(tmp_file);
-        writer.write_all(data).map_err(|e| {
-            PersistError::io_write(e, "Failed to write data to temporary file".to_string())
-        })?;
+        let mut written = 0u64;
+        for chunk in data.chunks(PROGRESS_CHUNK_SIZE) {
+            let result = writer
+                .write_all(chunk)
+                .map_err(|e| {
+                    PersistError::io_write(e, "Failed to write data to temporary file".to_string())
+                })
+                .and_then(|()| {
+                    written += chunk.len() as u64;
+                    options.report_progress(written, data.len() as u64)
+                });
+            if let Err(e) = result {
+                // Nothing was published yet, so an aborted write leaves no trace
+                drop(writer);
+                let _ = fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        }
 
         // Ensure all data is written and synced
         let file = writer.into_inner().map_err(|e| {
@@ -472,21 +529,10 @@ impl LocalFileStorage {
 
         Ok(())
     }
-}
-
-impl Default for LocalFileStorage {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl StorageAdapter for LocalFileStorage {
-    #[tracing::instrument(level = "info", skip(self, data), fields(path = %path, size = data.len(), durable = %self.durable_writes))]
-    fn save(&self, data: &[u8], path: &str) -> Result<()> {
-        #[cfg(feature = "metrics")]
-        let _timer = MetricsTimer::new("local_storage_save");
 
-        info!(
+    /// Write `data` to the file of `path`; the body of `save_with_options`
+    fn write_file(&self, data: &[u8], path: &str, options: &CallOptions) -> Result<()> {
+        success_log!(
             path = %path,
             size = data.len(),
             durable_writes = %self.durable_writes,
@@ -513,13 +559,14 @@ impl StorageAdapter for LocalFileStorage {
                 threshold = STREAMING_THRESHOLD,
                 "Using streaming write for large file"
             );
-            self.stream_write(&full_path, data)?;
+            self.stream_write(&full_path, data, options)?;
         } else {
             debug!(size = data.len(), "Using atomic write for file");
             self.atomic_write(&full_path, data)?;
+            options.report_progress(data.len() as u64, data.len() as u64)?;
         }
 
-        info!(
+        success_log!(
             path = %path,
             resolved_path = %full_path.display(),
             size = data.len(),
@@ -529,12 +576,9 @@ impl StorageAdapter for LocalFileStorage {
         Ok(())
     }
 
-    #[tracing::instrument(level = "info", skip(self), fields(path = %path))]
-    fn load(&self, path: &str) -> Result<Vec<u8>> {
-        #[cfg(feature = "metrics")]
-        let _timer = MetricsTimer::new("local_storage_load");
-
-        info!(
+    /// Read the file of `path`; the body of `load_with_options`
+    fn read_file(&self, path: &str, options: &CallOptions) -> Result<Vec<u8>> {
+        success_log!(
             path = %path,
             has_base_dir = %self.base_dir.is_some(),
             "Starting local storage load operation"
@@ -587,15 +631,17 @@ impl StorageAdapter for LocalFileStorage {
                 threshold = STREAMING_THRESHOLD,
                 "Using streaming read for large file"
             );
-            self.stream_read(&full_path)?
+            self.stream_read(&full_path, file_size, options)?
         } else {
             debug!(size = file_size, "Using direct read for file");
-            fs::read(&full_path).map_err(|e| {
+            let data = fs::read(&full_path).map_err(|e| {
                 PersistError::io_read(e, format!("Failed to read file {}", full_path.display()))
-            })?
+            })?;
+            options.report_progress(data.len() as u64, data.len() as u64)?;
+            data
         };
 
-        info!(
+        success_log!(
             path = %path,
             resolved_path = %full_path.display(),
             size = data.len(),
@@ -605,8 +651,117 @@ impl StorageAdapter for LocalFileStorage {
         Ok(data)
     }
 
+    /// Delete the file of `path`, if any; the body of `delete`
+    fn delete_file(&self, path: &str) -> Result<()> {
+        success_log!(
+            path = %path,
+            has_base_dir = %self.base_dir.is_some(),
+            "Starting local storage delete operation"
+        );
+
+        // Resolve and validate path (includes security checks)
+        let full_path = self.resolve_path(path)?;
+
+        debug!(
+            resolved_path = %full_path.display(),
+            "Path resolved and validated for deletion"
+        );
+
+        if full_path.exists() {
+            // Additional security check - don't delete symlinks
+            if full_path.is_symlink() {
+                warn!(
+                    path = %path,
+                    resolved_path = %full_path.display(),
+                    "Refusing to delete symlink for security reasons"
+                );
+                return Err(PersistError::validation(format!(
+                    "Path {path} resolves to a symlink, which cannot be deleted for security reasons"
+                )));
+            }
+
+            fs::remove_file(&full_path).map_err(|e| {
+                PersistError::io_write(
+                    e,
+                    format!("Failed to delete snapshot {}", full_path.display()),
+                )
+            })?;
+
+            success_log!(
+                path = %path,
+                resolved_path = %full_path.display(),
+                "Successfully deleted snapshot from local storage"
+            );
+        } else {
+            debug!(
+                path = %path,
+                resolved_path = %full_path.display(),
+                "File does not exist, delete operation is no-op"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for LocalFileStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StorageAdapter for LocalFileStorage {
+    fn save(&self, data: &[u8], path: &str) -> Result<()> {
+        self.save_with_options(data, path, &CallOptions::default())
+    }
+
+    #[tracing::instrument(level = "info", skip(self, data, options), fields(path = %path, size = data.len(), durable = %self.durable_writes))]
+    fn save_with_options(&self, data: &[u8], path: &str, options: &CallOptions) -> Result<()> {
+        #[cfg(feature = "metrics")]
+        let timer = self.metrics().start_local_operation("save");
+        let result = self.write_file(data, path, options);
+        #[cfg(feature = "metrics")]
+        timer.finish_local(result.is_err());
+        result
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        self.load_with_options(path, &CallOptions::default())
+    }
+
+    #[tracing::instrument(level = "info", skip(self, options), fields(path = %path))]
+    fn load_with_options(&self, path: &str, options: &CallOptions) -> Result<Vec<u8>> {
+        #[cfg(feature = "metrics")]
+        let timer = self.metrics().start_local_operation("load");
+        let result = self.read_file(path, options);
+        #[cfg(feature = "metrics")]
+        timer.finish_local(result.is_err());
+        result
+    }
+
+    fn load_prefix(&self, path: &str, max_len: u64) -> Result<Vec<u8>> {
+        let full_path = self.resolve_path(path)?;
+        if full_path.is_symlink() {
+            return Err(PersistError::validation(format!(
+                "Path {path} resolves to a symlink, which is not allowed for security reasons"
+            )));
+        }
+
+        let file = File::open(&full_path).map_err(|e| {
+            PersistError::io_read(e, format!("Failed to open file {}", full_path.display()))
+        })?;
+        let mut data = Vec::new();
+        file.take(max_len).read_to_end(&mut data).map_err(|e| {
+            PersistError::io_read(e, format!("Failed to read file {}", full_path.display()))
+        })?;
+        Ok(data)
+    }
+
     #[tracing::instrument(level = "debug", skip(self), fields(path = %path))]
     fn exists(&self, path: &str) -> bool {
+        #[cfg(feature = "metrics")]
+        let timer = self.metrics().start_local_operation("exists");
+
         debug!(
             path = %path,
             has_base_dir = %self.base_dir.is_some(),
@@ -636,65 +791,196 @@ impl StorageAdapter for LocalFileStorage {
                 false
             });
 
+        #[cfg(feature = "metrics")]
+        timer.finish_local(false);
         exists
     }
 
     #[tracing::instrument(level = "info", skip(self), fields(path = %path))]
     fn delete(&self, path: &str) -> Result<()> {
         #[cfg(feature = "metrics")]
-        let _timer = MetricsTimer::new("local_storage_delete");
+        let timer = self.metrics().start_local_operation("delete");
+        let result = self.delete_file(path);
+        #[cfg(feature = "metrics")]
+        timer.finish_local(result.is_err());
+        result
+    }
 
-        info!(
-            path = %path,
-            has_base_dir = %self.base_dir.is_some(),
-            "Starting local storage delete operation"
-        );
+    #[tracing::instrument(level = "debug", skip(self), fields(prefix = %prefix))]
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        if self.base_dir.is_some() && !prefix.is_empty() {
+            self.validate_path_security(prefix)?;
+        }
 
-        // Resolve and validate path (includes security checks)
+        // Walk the directory containing the prefix; the remainder is matched by string
+        let dir_part = match prefix.rfind('/') {
+            Some(idx) => &prefix[..=idx],
+            None => "",
+        };
+        let root = match &self.base_dir {
+            Some(base) => base.join(dir_part),
+            None if dir_part.is_empty() => PathBuf::from("."),
+            None => PathBuf::from(dir_part),
+        };
+
+        let mut paths = Vec::new();
+        if root.is_dir() {
+            self.collect_files(&root, dir_part, &mut paths)?;
+        }
+        paths.retain(|path| path.starts_with(prefix));
+        paths.sort();
+
+        debug!(prefix = %prefix, count = paths.len(), "Listed local snapshots");
+        Ok(paths)
+    }
+
+    fn size(&self, path: &str) -> Result<u64> {
         let full_path = self.resolve_path(path)?;
+        let metadata = fs::symlink_metadata(&full_path).map_err(|e| {
+            PersistError::io_read(e, format!("Failed to stat {}", full_path.display()))
+        })?;
+        if !metadata.is_file() {
+            return Err(PersistError::validation(format!(
+                "Path {path} is not a regular file"
+            )));
+        }
+        Ok(metadata.len())
+    }
 
-        debug!(
-            resolved_path = %full_path.display(),
-            "Path resolved and validated for deletion"
-        );
+    /// Removes temporary files left behind by writes that never got renamed into place
+    fn collect_garbage(
+        &self,
+        older_than: std::time::Duration,
+        dry_run: bool,
+    ) -> Result<Vec<GarbageItem>> {
+        let root = self.base_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+        let mut temp_files = Vec::new();
+        if root.is_dir() {
+            collect_temp_files(&root, &mut temp_files)?;
+        }
 
-        if full_path.exists() {
-            // Additional security check - don't delete symlinks
-            if full_path.is_symlink() {
-                warn!(
-                    path = %path,
-                    resolved_path = %full_path.display(),
-                    "Refusing to delete symlink for security reasons"
-                );
-                return Err(PersistError::validation(format!(
-                    "Path {path} resolves to a symlink, which cannot be deleted for security reasons"
-                )));
+        let now = std::time::SystemTime::now();
+        let mut removed = Vec::new();
+        for (path, metadata) in temp_files {
+            let modified = metadata.modified().map_err(|e| {
+                PersistError::io_read(e, format!("Failed to stat {}", path.display()))
+            })?;
+            // Files from the future (clock skew) are treated as fresh
+            let age = now.duration_since(modified).unwrap_or_default();
+            if age < older_than {
+                continue;
             }
+            if !dry_run {
+                match fs::remove_file(&path) {
+                    Ok(()) => {}
+                    // Renamed into place or removed by someone else meanwhile
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => {
+                        return Err(PersistError::io_write(
+                            e,
+                            format!("Failed to remove {}", path.display()),
+                        ))
+                    }
+                }
+                info!(path = %path.display(), "Removed stale temporary file");
+            }
+            removed.push(GarbageItem {
+                location: path.display().to_string(),
+                size: Some(metadata.len()),
+                modified: modified.into(),
+            });
+        }
+        Ok(removed)
+    }
 
-            fs::remove_file(&full_path).map_err(|e| {
-                PersistError::io_write(
-                    e,
-                    format!("Failed to delete snapshot {}", full_path.display()),
-                )
+    /// Checks that the base directory exists; writes would otherwise create it
+    fn health_check(&self) -> Result<()> {
+        let Some(base) = &self.base_dir else {
+            return Ok(());
+        };
+        let metadata = fs::metadata(base).map_err(|e| {
+            PersistError::io_read(
+                e,
+                format!("Cannot access storage directory {}", base.display()),
+            )
+        })?;
+        if !metadata.is_dir() {
+            return Err(PersistError::storage(format!(
+                "Storage path {} is not a directory",
+                base.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Writes go to a temporary file that is renamed over the target
+    fn atomic_overwrite(&self) -> bool {
+        true
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "local"
+    }
+}
+
+impl LocalFileStorage {
+    /// Recursively collect regular files under `dir`, as storage paths starting with `key_prefix`
+    ///
+    /// Symlinks and in-flight temporary files are skipped.
+    fn collect_files(&self, dir: &Path, key_prefix: &str, out: &mut Vec<String>) -> Result<()> {
+        let entries = fs::read_dir(dir).map_err(|e| {
+            PersistError::io_read(e, format!("Failed to list directory {}", dir.display()))
+        })?;
+
+        for entry in entries {
+            let entry = entry.map_err(|e| {
+                PersistError::io_read(e, format!("Failed to list directory {}", dir.display()))
+            })?;
+            let file_type = entry.file_type().map_err(|e| {
+                PersistError::io_read(e, format!("Failed to stat {}", entry.path().display()))
             })?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let key = format!("{key_prefix}{name}");
 
-            info!(
-                path = %path,
-                resolved_path = %full_path.display(),
-                "Successfully deleted snapshot from local storage"
-            );
-        } else {
-            debug!(
-                path = %path,
-                resolved_path = %full_path.display(),
-                "File does not exist, delete operation is no-op"
-            );
+            if file_type.is_dir() {
+                self.collect_files(&entry.path(), &format!("{key}/"), out)?;
+            } else if file_type.is_file() && !is_temp_file_name(&name) {
+                out.push(key);
+            }
         }
 
         Ok(())
     }
 }
 
+/// Whether `name` is a temporary file of [`LocalFileStorage`]'s atomic writes
+fn is_temp_file_name(name: &str) -> bool {
+    name.starts_with(".tmp_persist_") && name.ends_with(".tmp")
+}
+
+/// Recursively collect the temporary files under `dir`, without following symlinks
+fn collect_temp_files(dir: &Path, out: &mut Vec<(PathBuf, fs::Metadata)>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        PersistError::io_read(e, format!("Failed to list directory {}", dir.display()))
+    })?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| {
+            PersistError::io_read(e, format!("Failed to list directory {}", dir.display()))
+        })?;
+        let metadata = entry.metadata().map_err(|e| {
+            PersistError::io_read(e, format!("Failed to stat {}", entry.path().display()))
+        })?;
+        if metadata.is_dir() {
+            collect_temp_files(&entry.path(), out)?;
+        } else if metadata.is_file() && is_temp_file_name(&entry.file_name().to_string_lossy()) {
+            out.push((entry.path(), metadata));
+        }
+    }
+
+    Ok(())
+}
+
 /// Helper function to provide atomic load_if_exists operation
 ///
 /// This addresses the TOCTOU (Time-of-Check-Time-of-Use) race condition
@@ -753,6 +1039,165 @@ mod tests {
         assert!(!storage.exists(path));
     }
 
+    #[test]
+    fn test_list_with_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::with_base_dir(temp_dir.path());
+
+        storage.save(b"a", "agent1/s1.json.gz").unwrap();
+        storage.save(b"b", "agent1/nested/s2.json.gz").unwrap();
+        storage.save(b"c", "agent2/s1.json.gz").unwrap();
+
+        assert_eq!(
+            storage.list("agent1/").unwrap(),
+            vec!["agent1/nested/s2.json.gz", "agent1/s1.json.gz"]
+        );
+        assert_eq!(storage.list("agent").unwrap().len(), 3);
+        assert!(storage.list("missing/").unwrap().is_empty());
+        assert!(storage.list("../").is_err());
+    }
+
+    #[test]
+    fn test_size_without_loading() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::with_base_dir(temp_dir.path());
+
+        storage.save(b"twelve bytes", "agent1/s1.json.gz").unwrap();
+        assert_eq!(storage.size("agent1/s1.json.gz").unwrap(), 12);
+        assert!(storage.size("agent1/missing.json.gz").is_err());
+        assert!(storage.size("agent1").is_err());
+        assert!(storage.size("../outside.json.gz").is_err());
+    }
+
+    #[test]
+    fn test_health_check_requires_base_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(LocalFileStorage::with_base_dir(temp_dir.path())
+            .health_check()
+            .is_ok());
+
+        let missing = LocalFileStorage::with_base_dir(temp_dir.path().join("missing"));
+        let error = missing.health_check().unwrap_err();
+        assert!(error.to_string().contains("missing"), "{error}");
+
+        let file = temp_dir.path().join("file");
+        fs::write(&file, b"data").unwrap();
+        assert!(LocalFileStorage::with_base_dir(&file)
+            .health_check()
+            .is_err());
+    }
+
+    #[test]
+    fn test_progress_reports_increasing_byte_counts() {
+        use std::sync::{Arc, Mutex};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::with_base_dir(temp_dir.path());
+        let data = vec![7u8; 3 * 1024 * 1024 + 5];
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let options = CallOptions::default().with_progress(Arc::new(move |bytes, total| {
+            sink.lock().unwrap().push((bytes, total));
+            Ok(())
+        }));
+
+        storage
+            .save_with_options(&data, "agent1/large.json.gz", &options)
+            .unwrap();
+        let saved = std::mem::take(&mut *reports.lock().unwrap());
+        let loaded_data = storage
+            .load_with_options("agent1/large.json.gz", &options)
+            .unwrap();
+        let loaded = reports.lock().unwrap().clone();
+        assert_eq!(loaded_data, data);
+
+        for counts in [saved, loaded] {
+            assert!(counts.len() > 1, "large transfers report more than once");
+            assert!(counts.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            assert!(counts.iter().all(|&(_, total)| total == data.len() as u64));
+            assert_eq!(counts.last().unwrap().0, data.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_progress_error_aborts_streaming_write() {
+        use std::sync::Arc;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::with_base_dir(temp_dir.path());
+        let data = vec![7u8; 3 * 1024 * 1024];
+        let options = CallOptions::default().with_progress(Arc::new(|bytes, _| {
+            if bytes > 1024 * 1024 {
+                Err(PersistError::storage("stopped"))
+            } else {
+                Ok(())
+            }
+        }));
+
+        let err = storage
+            .save_with_options(&data, "agent1/large.json.gz", &options)
+            .unwrap_err();
+        assert!(err.to_string().contains("stopped"));
+        assert!(!storage.exists("agent1/large.json.gz"));
+        // The temporary file is removed as well
+        let leftovers = fs::read_dir(temp_dir.path().join("agent1"))
+            .unwrap()
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    /// Create a temporary-looking file under `dir`, last written `age` ago
+    fn plant_temp_file(dir: &Path, name: &str, age: std::time::Duration) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = File::create(&path).unwrap();
+        file.set_len(64).unwrap();
+        file.set_modified(std::time::SystemTime::now() - age)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_collect_garbage_removes_only_stale_temp_files() {
+        const HOUR: std::time::Duration = std::time::Duration::from_secs(3600);
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::with_base_dir(temp_dir.path());
+        storage.save(b"snapshot", "agent1/s1.json.gz").unwrap();
+
+        let stale = plant_temp_file(temp_dir.path(), "agent1/.tmp_persist_a1.tmp", 48 * HOUR);
+        let nested = plant_temp_file(temp_dir.path(), "x/y/.tmp_persist_b2.tmp", 30 * HOUR);
+        let fresh = plant_temp_file(temp_dir.path(), "agent1/.tmp_persist_c3.tmp", HOUR);
+        let other = plant_temp_file(temp_dir.path(), "agent1/notes.tmp", 48 * HOUR);
+
+        // A dry run reports without removing
+        let found = storage.collect_garbage(24 * HOUR, true).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|item| item.size == Some(64)));
+        assert!(stale.exists() && nested.exists());
+
+        // A threshold above every file's age selects nothing
+        assert!(storage
+            .collect_garbage(72 * HOUR, false)
+            .unwrap()
+            .is_empty());
+        assert!(stale.exists());
+
+        let mut removed: Vec<_> = storage
+            .collect_garbage(24 * HOUR, false)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.location)
+            .collect();
+        removed.sort();
+        let mut expected = vec![stale.display().to_string(), nested.display().to_string()];
+        expected.sort();
+        assert_eq!(removed, expected);
+        assert!(!stale.exists() && !nested.exists());
+        assert!(fresh.exists() && other.exists());
+        assert_eq!(storage.load("agent1/s1.json.gz").unwrap(), b"snapshot");
+    }
+
     #[test]
     fn test_local_file_storage_nested_directories() {
         let temp_dir = TempDir::new().unwrap();
@@ -9,19 +9,22 @@ storage details, making it easy to add new storage backends.
 #[cfg(feature = "gcs")]
 pub mod gcs;
 pub mod local;
+pub mod retrying;
 #[cfg(feature = "s3")]
 pub mod s3;
 
 use crate::Result;
 use async_trait::async_trait;
 use futures::io::AsyncRead;
+use std::sync::Arc;
+use std::time::Instant;
 
 #[cfg(feature = "async-rt")]
 use once_cell::sync::Lazy;
 #[cfg(feature = "async-rt")]
-use std::sync::Arc;
-#[cfg(feature = "async-rt")]
 use tokio::runtime::Runtime;
+#[cfg(feature = "async-rt")]
+use tracing::Instrument;
 
 #[cfg(feature = "async-rt")]
 static GLOBAL_RT: Lazy<Runtime> = Lazy::new(|| {
@@ -32,6 +35,65 @@ static GLOBAL_RT: Lazy<Runtime> = Lazy::new(|| {
         .expect("Failed to create global async runtime")
 });
 
+/// Callback receiving the bytes transferred so far by a storage call and its total size
+///
+/// Counts never decrease within a call; the last report has `transferred == total`.
+/// Returning an error aborts the call, which then fails with that error.
+pub type ProgressCallback = Arc<dyn Fn(u64, u64) -> Result<()> + Send + Sync>;
+
+/// Per-call options for storage operations
+#[derive(Clone, Default)]
+pub struct CallOptions {
+    /// Give up (including retries) once this point in time has passed
+    pub deadline: Option<Instant>,
+    /// Report bytes transferred while the call runs
+    pub progress: Option<ProgressCallback>,
+}
+
+impl std::fmt::Debug for CallOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallOptions")
+            .field("deadline", &self.deadline)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl CallOptions {
+    /// Options with a deadline for the call
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Options reporting transferred bytes to `progress`
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Report `transferred` of `total` bytes to the progress callback, if any
+    ///
+    /// Fails with the callback's error, if it returns one; the caller should stop.
+    pub fn report_progress(&self, transferred: u64, total: u64) -> Result<()> {
+        match &self.progress {
+            Some(progress) => progress(transferred, total),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Leftover of an interrupted write, found by [`StorageAdapter::collect_garbage`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GarbageItem {
+    /// Where the leftover lives: a file path, or an object key and upload id
+    pub location: String,
+    /// Bytes it takes up, if known
+    pub size: Option<u64>,
+    /// When it was last written
+    pub modified: chrono::DateTime<chrono::Utc>,
+}
+
 /// Storage abstraction for saving and loading snapshot data
 ///
 /// This trait defines the interface that all storage implementations must provide.
@@ -57,6 +119,35 @@ pub trait StorageAdapter {
     /// The loaded data bytes or an error
     fn load(&self, path: &str) -> Result<Vec<u8>>;
 
+    /// Save snapshot data with per-call options
+    ///
+    /// Backends that retry failed requests stop retrying at `options.deadline`. The
+    /// default implementation calls `save` and reports progress once it completes.
+    fn save_with_options(&self, data: &[u8], path: &str, options: &CallOptions) -> Result<()> {
+        self.save(data, path)?;
+        options.report_progress(data.len() as u64, data.len() as u64)
+    }
+
+    /// Load snapshot data with per-call options
+    ///
+    /// Backends that retry failed requests stop retrying at `options.deadline`. The
+    /// default implementation calls `load` and reports progress once it completes.
+    fn load_with_options(&self, path: &str, options: &CallOptions) -> Result<Vec<u8>> {
+        let data = self.load(path)?;
+        options.report_progress(data.len() as u64, data.len() as u64)?;
+        Ok(data)
+    }
+
+    /// Load at most the first `max_len` bytes of the snapshot at `path`
+    ///
+    /// Fewer bytes come back only when the snapshot is shorter. Backends read just the
+    /// requested range; the default implementation loads the snapshot and truncates it.
+    fn load_prefix(&self, path: &str, max_len: u64) -> Result<Vec<u8>> {
+        let mut data = self.load(path)?;
+        data.truncate(usize::try_from(max_len).unwrap_or(usize::MAX));
+        Ok(data)
+    }
+
     /// Check if a snapshot exists at the specified location
     ///
     /// # Arguments
@@ -74,6 +165,139 @@ pub trait StorageAdapter {
     /// # Returns
     /// Result indicating success or failure
     fn delete(&self, path: &str) -> Result<()>;
+
+    /// List the storage paths of all snapshots whose path starts with `prefix`
+    ///
+    /// Returned paths can be passed straight back to `load`. Backends that cannot
+    /// enumerate their contents return a storage error.
+    ///
+    /// # Arguments
+    /// * `prefix` - Path prefix to filter by (an empty prefix lists everything)
+    ///
+    /// # Returns
+    /// The matching paths in lexicographic order
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let _ = prefix;
+        Err(crate::PersistError::storage(
+            "Listing is not supported by this storage backend",
+        ))
+    }
+
+    /// Stored size in bytes of the snapshot at `path`
+    ///
+    /// Backends read the size from the object's metadata without downloading it; the
+    /// default implementation loads the snapshot and measures it.
+    fn size(&self, path: &str) -> Result<u64> {
+        self.load(path).map(|data| data.len() as u64)
+    }
+
+    /// Remove leftovers of interrupted writes that are older than `older_than`
+    ///
+    /// The age threshold keeps writes that are still in progress safe. With `dry_run`,
+    /// leftovers are only reported. Backends whose writes leave nothing behind use the
+    /// default implementation, which finds nothing.
+    ///
+    /// # Returns
+    /// The leftovers removed (or, with `dry_run`, that would be removed)
+    fn collect_garbage(
+        &self,
+        older_than: std::time::Duration,
+        dry_run: bool,
+    ) -> Result<Vec<GarbageItem>> {
+        let _ = (older_than, dry_run);
+        Ok(Vec::new())
+    }
+
+    /// Check that the backend is reachable and its configured location usable
+    ///
+    /// Makes a single request without retries, so that misconfiguration surfaces
+    /// quickly. The default implementation reports a healthy backend.
+    fn health_check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// When the credentials the backend signs requests with expire
+    ///
+    /// `None` when they do not expire, are refreshed automatically, or the backend
+    /// cannot tell, which is what the default implementation returns.
+    fn credential_expiry(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        Ok(None)
+    }
+
+    /// Whether `save` replaces an existing snapshot atomically
+    ///
+    /// Readers of an atomically overwritten path see either the old or the new data,
+    /// never a partial write. In-place metadata updates require it.
+    fn atomic_overwrite(&self) -> bool {
+        false
+    }
+
+    /// Short name of the backend, such as "local" or "s3", for metric labels
+    fn backend_name(&self) -> &'static str {
+        "custom"
+    }
+}
+
+impl<S: StorageAdapter + ?Sized> StorageAdapter for Box<S> {
+    fn save(&self, data: &[u8], path: &str) -> Result<()> {
+        (**self).save(data, path)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        (**self).load(path)
+    }
+
+    fn save_with_options(&self, data: &[u8], path: &str, options: &CallOptions) -> Result<()> {
+        (**self).save_with_options(data, path, options)
+    }
+
+    fn load_with_options(&self, path: &str, options: &CallOptions) -> Result<Vec<u8>> {
+        (**self).load_with_options(path, options)
+    }
+
+    fn load_prefix(&self, path: &str, max_len: u64) -> Result<Vec<u8>> {
+        (**self).load_prefix(path, max_len)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        (**self).exists(path)
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        (**self).delete(path)
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        (**self).list(prefix)
+    }
+
+    fn size(&self, path: &str) -> Result<u64> {
+        (**self).size(path)
+    }
+
+    fn collect_garbage(
+        &self,
+        older_than: std::time::Duration,
+        dry_run: bool,
+    ) -> Result<Vec<GarbageItem>> {
+        (**self).collect_garbage(older_than, dry_run)
+    }
+
+    fn health_check(&self) -> Result<()> {
+        (**self).health_check()
+    }
+
+    fn credential_expiry(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        (**self).credential_expiry()
+    }
+
+    fn atomic_overwrite(&self) -> bool {
+        (**self).atomic_overwrite()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        (**self).backend_name()
+    }
 }
 
 /// Async storage abstraction for save and load operations
@@ -138,41 +362,61 @@ impl<A: AsyncStorageAdapter> BlockingStorage<A> {
     }
 }
 
+// The futures are instrumented with the caller's span, so the adapter's spans stay
+// children of the engine call that made them wherever the runtime polls them
 #[cfg(feature = "async-rt")]
 impl<A: AsyncStorageAdapter> StorageAdapter for BlockingStorage<A> {
     fn save(&self, data: &[u8], path: &str) -> Result<()> {
         let data_owned = data.to_vec();
         let reader = futures::io::Cursor::new(data_owned);
-        GLOBAL_RT.block_on(self.inner.save(reader, path))
+        GLOBAL_RT.block_on(self.inner.save(reader, path).in_current_span())
     }
 
     fn load(&self, path: &str) -> Result<Vec<u8>> {
         use futures::io::AsyncReadExt;
 
-        GLOBAL_RT.block_on(async {
-            let mut reader = self.inner.load(path).await?;
-            let mut data = Vec::new();
-            reader
-                .read_to_end(&mut data)
-                .await
-                .map_err(|e| crate::PersistError::storage(format!("Failed to read data: {e}")))?;
-            Ok(data)
-        })
+        GLOBAL_RT.block_on(
+            async {
+                let mut reader = self.inner.load(path).await?;
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data).await.map_err(|e| {
+                    crate::PersistError::storage(format!("Failed to read data: {e}"))
+                })?;
+                Ok(data)
+            }
+            .in_current_span(),
+        )
     }
 
     fn exists(&self, path: &str) -> bool {
-        GLOBAL_RT.block_on(self.inner.exists(path)).unwrap_or(false)
+        GLOBAL_RT
+            .block_on(self.inner.exists(path).in_current_span())
+            .unwrap_or(false)
     }
 
     fn delete(&self, path: &str) -> Result<()> {
-        GLOBAL_RT.block_on(self.inner.delete(path))
+        GLOBAL_RT.block_on(self.inner.delete(path).in_current_span())
     }
 }
 
+/// Names of the storage backends [`crate::create_storage_from_config`] can build in this
+/// build
+pub fn available_backends() -> Vec<&'static str> {
+    [
+        ("local", true),
+        ("s3", cfg!(feature = "s3")),
+        ("gcs", cfg!(feature = "gcs")),
+    ]
+    .into_iter()
+    .filter_map(|(name, available)| available.then_some(name))
+    .collect()
+}
+
 // Re-export types for convenience
 #[cfg(feature = "gcs")]
 pub use gcs::GCSStorageAdapter;
 pub use local::LocalFileStorage;
+pub use retrying::RetryingStorage;
 #[cfg(feature = "s3")]
 pub use s3::S3StorageAdapter;
 
@@ -181,6 +425,7 @@ pub use s3::S3StorageAdapter;
 /// This implementation stores snapshots in memory using a HashMap.
 /// Useful for unit testing without touching the filesystem.
 #[cfg(test)]
+#[derive(Clone)]
 pub struct MemoryStorage {
     data: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, Vec<u8>>>>,
 }
@@ -227,4 +472,23 @@ impl StorageAdapter for MemoryStorage {
         storage.remove(path);
         Ok(())
     }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let storage = self.data.lock().unwrap();
+        let mut paths: Vec<String> = storage
+            .keys()
+            .filter(|path| path.starts_with(prefix))
+            .cloned()
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn size(&self, path: &str) -> Result<u64> {
+        self.load(path).map(|data| data.len() as u64)
+    }
+
+    fn atomic_overwrite(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,321 @@
+/*!
+Retrying wrapper for storage adapters without retries of their own.
+
+The S3 and GCS adapters retry their requests themselves. [`RetryingStorage`] gives any
+other adapter, such as local storage on a network file system or a custom adapter, the
+same retry layer, and the same `persist_retry_*` metrics.
+*/
+
+use crate::config::RetryConfig;
+#[cfg(feature = "metrics")]
+use crate::observability::{PersistMetrics, PersistRetryMetrics};
+use crate::storage::{CallOptions, GarbageItem, StorageAdapter};
+use crate::{PersistError, Result};
+use persist_retry::{retry_blocking, Operation, OperationKind, RetryPolicy};
+use std::time::Instant;
+
+/// Storage adapter retrying the transient failures of another one
+///
+/// Saves, loads, deletions, listings and size lookups of the inner adapter are retried
+/// under the policy; `exists`, health checks and garbage collection are not. With the
+/// `metrics` feature, attempts are counted in `persist_retry_*` with the inner
+/// adapter's [`backend_name`](StorageAdapter::backend_name) as `backend`, in
+/// [`PersistMetrics::global`] unless the policy already has a metrics sink or
+/// [`with_metrics`](Self::with_metrics) is used.
+///
+/// # Example
+/// ```rust
+/// use persist_core::{LocalFileStorage, RetryPolicy, RetryingStorage};
+///
+/// let storage = RetryingStorage::new(
+///     LocalFileStorage::with_base_dir("/mnt/nfs/snapshots"),
+///     RetryPolicy::default().with_max_attempts(3),
+/// );
+/// ```
+pub struct RetryingStorage<S: StorageAdapter> {
+    inner: S,
+    policy: RetryPolicy,
+    retry_config: Option<RetryConfig>,
+}
+
+impl<S: StorageAdapter> RetryingStorage<S> {
+    /// Retry the operations of `inner` under `policy`
+    pub fn new(inner: S, policy: RetryPolicy) -> Self {
+        #[cfg(feature = "metrics")]
+        let policy = match policy.metrics {
+            Some(_) => policy,
+            None => policy.with_metrics(std::sync::Arc::new(PersistRetryMetrics::new(
+                inner.backend_name(),
+            ))),
+        };
+        Self {
+            inner,
+            policy,
+            retry_config: None,
+        }
+    }
+
+    /// Retry the operations of `inner` under the policies of `retry_config`, which may
+    /// differ by operation (see [`RetryConfig::never_retry`])
+    pub fn from_retry_config(inner: S, retry_config: RetryConfig) -> Self {
+        let mut storage = Self::new(inner, retry_config.policy_for(""));
+        storage.retry_config = Some(retry_config);
+        storage
+    }
+
+    /// Count retries in `metrics` instead of the global metrics
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: PersistMetrics) -> Self {
+        self.policy.metrics = Some(std::sync::Arc::new(PersistRetryMetrics::with_metrics(
+            metrics,
+            self.inner.backend_name(),
+        )));
+        self
+    }
+
+    /// The wrapped adapter
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Run `op` on `path` under the policy, giving up at `deadline` if one is given
+    fn retry<T>(
+        &self,
+        kind: OperationKind,
+        path: &str,
+        deadline: Option<Instant>,
+        op: impl FnMut(usize) -> Result<T>,
+    ) -> Result<T> {
+        let mut policy = match &self.retry_config {
+            Some(retry_config) => RetryPolicy {
+                metrics: self.policy.metrics.clone(),
+                ..retry_config.policy_for(kind.as_str())
+            },
+            None => self.policy.clone(),
+        };
+        if deadline.is_some() {
+            policy.deadline = deadline;
+        }
+        if policy.deadline_passed() {
+            return Err(PersistError::storage(format!(
+                "Storage {kind} deadline exceeded before the first attempt (path: {path})"
+            )));
+        }
+        retry_blocking(Operation::new(kind).with_target(path), &policy, op)
+    }
+}
+
+impl<S: StorageAdapter> StorageAdapter for RetryingStorage<S> {
+    fn save(&self, data: &[u8], path: &str) -> Result<()> {
+        self.retry(OperationKind::Save, path, None, |_| {
+            self.inner.save(data, path)
+        })
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        self.retry(OperationKind::Load, path, None, |_| self.inner.load(path))
+    }
+
+    fn save_with_options(&self, data: &[u8], path: &str, options: &CallOptions) -> Result<()> {
+        self.retry(OperationKind::Save, path, options.deadline, |_| {
+            self.inner.save_with_options(data, path, options)
+        })
+    }
+
+    fn load_with_options(&self, path: &str, options: &CallOptions) -> Result<Vec<u8>> {
+        self.retry(OperationKind::Load, path, options.deadline, |_| {
+            self.inner.load_with_options(path, options)
+        })
+    }
+
+    fn load_prefix(&self, path: &str, max_len: u64) -> Result<Vec<u8>> {
+        self.retry(OperationKind::Load, path, None, |_| {
+            self.inner.load_prefix(path, max_len)
+        })
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        self.retry(OperationKind::Delete, path, None, |_| {
+            self.inner.delete(path)
+        })
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.retry(OperationKind::List, prefix, None, |_| {
+            self.inner.list(prefix)
+        })
+    }
+
+    fn size(&self, path: &str) -> Result<u64> {
+        self.retry(OperationKind::Other("size"), path, None, |_| {
+            self.inner.size(path)
+        })
+    }
+
+    fn collect_garbage(
+        &self,
+        older_than: std::time::Duration,
+        dry_run: bool,
+    ) -> Result<Vec<GarbageItem>> {
+        self.inner.collect_garbage(older_than, dry_run)
+    }
+
+    fn health_check(&self) -> Result<()> {
+        self.inner.health_check()
+    }
+
+    fn credential_expiry(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        self.inner.credential_expiry()
+    }
+
+    fn atomic_overwrite(&self) -> bool {
+        self.inner.atomic_overwrite()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.inner.backend_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Storage in memory whose saves and loads time out a set number of times
+    #[derive(Default)]
+    struct FlakyStorage {
+        inner: MemoryStorage,
+        failing_saves: AtomicUsize,
+        failing_loads: AtomicUsize,
+        calls: AtomicUsize,
+    }
+
+    impl FlakyStorage {
+        fn failing(saves: usize, loads: usize) -> Self {
+            Self {
+                failing_saves: AtomicUsize::new(saves),
+                failing_loads: AtomicUsize::new(loads),
+                ..Self::default()
+            }
+        }
+
+        /// Fail with a timeout while `failures` is not down to zero
+        fn flaky(&self, failures: &AtomicUsize) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let remaining = failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                failures.store(remaining - 1, Ordering::SeqCst);
+                return Err(PersistError::Io(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "timed out",
+                )));
+            }
+            Ok(())
+        }
+    }
+
+    impl StorageAdapter for FlakyStorage {
+        fn save(&self, data: &[u8], path: &str) -> Result<()> {
+            self.flaky(&self.failing_saves)?;
+            self.inner.save(data, path)
+        }
+        fn load(&self, path: &str) -> Result<Vec<u8>> {
+            self.flaky(&self.failing_loads)?;
+            self.inner.load(path)
+        }
+        fn exists(&self, path: &str) -> bool {
+            self.inner.exists(path)
+        }
+        fn delete(&self, path: &str) -> Result<()> {
+            self.inner.delete(path)
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::from(
+            backoff::ExponentialBackoffBuilder::new()
+                .with_initial_interval(std::time::Duration::from_millis(1))
+                .with_max_interval(std::time::Duration::from_millis(1))
+                .with_randomization_factor(0.0)
+                .build(),
+        )
+        .with_max_attempts(3)
+    }
+
+    #[test]
+    fn test_retries_transient_failures() {
+        let storage = RetryingStorage::new(FlakyStorage::failing(2, 3), policy());
+        storage.save(b"data", "a.json").unwrap();
+        assert_eq!(storage.inner().calls.load(Ordering::SeqCst), 3);
+
+        // Three attempts, then the error of the last one
+        let error = storage.load("a.json").unwrap_err();
+        assert!(matches!(error, PersistError::Io(_)), "{error}");
+        assert_eq!(storage.inner().calls.load(Ordering::SeqCst), 6);
+
+        // Permanent failures are not retried
+        let error = storage.load("missing.json").unwrap_err();
+        assert!(error.to_string().contains("missing.json"), "{error}");
+        assert_eq!(storage.inner().calls.load(Ordering::SeqCst), 7);
+
+        let past = CallOptions {
+            deadline: Some(Instant::now()),
+            ..CallOptions::default()
+        };
+        assert!(storage.save_with_options(b"data", "b.json", &past).is_err());
+        assert_eq!(storage.inner().calls.load(Ordering::SeqCst), 7);
+    }
+
+    #[test]
+    fn test_retry_config_attempts() {
+        let retry_config = RetryConfig {
+            max_attempts: Some(4),
+            initial_interval_ms: 1,
+            max_interval_ms: 1,
+            never_retry: vec!["load".to_string()],
+            jitter: crate::config::RetryJitter::None,
+            ..RetryConfig::default()
+        };
+        let storage =
+            RetryingStorage::from_retry_config(FlakyStorage::failing(10, 10), retry_config);
+        assert!(storage.save(b"data", "a.json").is_err());
+        assert_eq!(storage.inner().calls.load(Ordering::SeqCst), 4);
+
+        // Loads are never retried
+        assert!(storage.load("a.json").is_err());
+        assert_eq!(storage.inner().calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_retry_metrics() {
+        use std::collections::BTreeMap;
+
+        let metrics = PersistMetrics::new().unwrap();
+        let storage = RetryingStorage::new(FlakyStorage::failing(1, 5), policy())
+            .with_metrics(metrics.clone());
+        storage.save(b"data", "a.json").unwrap();
+        assert!(storage.load("a.json").is_err());
+
+        let key = |operation: &str| ("custom".to_string(), operation.to_string());
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.retry_attempts,
+            BTreeMap::from([(key("load"), 3), (key("save"), 2)])
+        );
+        assert_eq!(snapshot.retry_exhausted, BTreeMap::from([(key("load"), 1)]));
+        assert_eq!(
+            snapshot.retry_success_after_retry,
+            BTreeMap::from([(key("save"), 1)])
+        );
+        // Only the S3 and GCS retries have deprecated per-backend counters
+        assert_eq!(metrics.s3_retries_total.get(), 0.0);
+        assert_eq!(metrics.gcs_retries_total.get(), 0.0);
+    }
+}
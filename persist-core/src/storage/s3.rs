@@ -17,7 +17,7 @@ Includes comprehensive error handling, retry logic, memory optimization, and fle
 - **Builder Pattern**: Flexible configuration via `S3StorageAdapterBuilder`
 - **Environment Configuration**: Supports `PERSIST_S3_MAX_RETRIES` and `PERSIST_S3_TIMEOUT` env vars
 - **Access Control**: Distinguishes between "not found" (404) and "permission denied" (403) errors
-- **Enhanced Metrics**: Ready for storage bytes total recording (TODO: awaiting metrics API)
+- **Transfer Metrics**: Counts the bytes uploaded and downloaded, and the operations in flight
 
 # Usage
 
@@ -49,19 +49,28 @@ let adapter = S3StorageAdapter::builder()
 */
 
 use aws_config::SdkConfig;
+use aws_sdk_s3::config::SharedCredentialsProvider;
 use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::operation::{RequestId, RequestIdExt};
 use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::Client as S3Client;
 use backoff::ExponentialBackoff;
 use bytes::Bytes;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::runtime::Runtime;
 use tracing::{debug, error, info, warn};
 
-use super::StorageAdapter;
+use super::{CallOptions, GarbageItem, StorageAdapter};
+use crate::config::{RetryConfig, S3Credentials};
 #[cfg(feature = "metrics")]
-use crate::observability::MetricsTimer;
+use crate::observability::PersistMetrics;
+use crate::observability::{success_log, trace_metadata};
 use crate::{PersistError, Result};
+use persist_retry::{
+    attempt_with_timeout, retry_blocking, JitterMode, Operation, OperationKind, RetryPolicy,
+};
 
 /// Amazon S3 storage adapter
 ///
@@ -96,6 +105,11 @@ pub struct S3StorageAdapter {
     client: S3Client,
     bucket: String,
     runtime: Arc<Runtime>,
+    retry_config: Option<RetryConfig>,
+    /// Credentials provider the client was configured with, for expiry checks
+    credentials_provider: Option<SharedCredentialsProvider>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<PersistMetrics>,
 }
 
 /// Builder for S3StorageAdapter with configurable options
@@ -104,8 +118,15 @@ pub struct S3StorageAdapterBuilder {
     bucket: Option<String>,
     endpoint: Option<String>,
     region: Option<String>,
+    credentials: Option<S3Credentials>,
+    profile: Option<String>,
+    force_path_style: bool,
     max_retries: Option<u32>,
     timeout: Option<std::time::Duration>,
+    connect_timeout: Option<std::time::Duration>,
+    retry_config: Option<RetryConfig>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<PersistMetrics>,
 }
 
 impl Default for S3StorageAdapterBuilder {
@@ -121,8 +142,15 @@ impl S3StorageAdapterBuilder {
             bucket: None,
             endpoint: None,
             region: None,
+            credentials: None,
+            profile: None,
+            force_path_style: false,
             max_retries: None,
             timeout: None,
+            connect_timeout: None,
+            retry_config: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         }
     }
 
@@ -144,18 +172,58 @@ impl S3StorageAdapterBuilder {
         self
     }
 
+    /// Use static credentials instead of the AWS credential provider chain
+    pub fn credentials(mut self, credentials: S3Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Read settings and credentials from a named profile of the AWS config files
+    /// instead of `AWS_PROFILE` (credentials set with [`credentials`](Self::credentials)
+    /// still take precedence)
+    pub fn profile<S: Into<String>>(mut self, profile: S) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Address buckets by path instead of by subdomain (needed by MinIO and most
+    /// other S3-compatible services)
+    pub fn force_path_style(mut self, force_path_style: bool) -> Self {
+        self.force_path_style = force_path_style;
+        self
+    }
+
     /// Set maximum number of retries (also reads from PERSIST_S3_MAX_RETRIES env var)
     pub fn max_retries(mut self, max_retries: u32) -> Self {
         self.max_retries = Some(max_retries);
         self
     }
 
+    /// Set the retry policy settings (takes precedence over `max_retries`)
+    pub fn retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = Some(retry_config);
+        self
+    }
+
     /// Set request timeout (also reads from PERSIST_S3_TIMEOUT env var)
     pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
         self.timeout = Some(timeout);
         self
     }
 
+    /// Set the timeout for establishing connections
+    pub fn connect_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Record operations in `metrics` instead of [`PersistMetrics::global`]
+    #[cfg(feature = "metrics")]
+    pub fn metrics(mut self, metrics: PersistMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Build the S3StorageAdapter
     pub fn build(self) -> Result<S3StorageAdapter> {
         let bucket = self.bucket.ok_or_else(|| {
@@ -191,6 +259,10 @@ impl S3StorageAdapterBuilder {
         let sdk_config = runtime.block_on(async {
             let mut config_loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
 
+            if let Some(profile) = &self.profile {
+                config_loader = config_loader.profile_name(profile);
+            }
+
             if let Some(region) = &self.region {
                 config_loader = config_loader.region(aws_config::Region::new(region.clone()));
             }
@@ -199,6 +271,29 @@ impl S3StorageAdapterBuilder {
                 config_loader = config_loader.endpoint_url(endpoint);
             }
 
+            if timeout.is_some() || self.connect_timeout.is_some() {
+                let mut timeouts = aws_config::timeout::TimeoutConfig::builder();
+                if let Some(connect_timeout) = self.connect_timeout {
+                    timeouts = timeouts.connect_timeout(connect_timeout);
+                }
+                if let Some(timeout) = timeout {
+                    timeouts = timeouts.operation_attempt_timeout(timeout);
+                }
+                config_loader = config_loader.timeout_config(timeouts.build());
+            }
+
+            // Explicit settings take precedence over the environment
+            if let Some(credentials) = &self.credentials {
+                config_loader =
+                    config_loader.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                        &credentials.access_key_id,
+                        &credentials.secret_access_key,
+                        credentials.session_token.clone(),
+                        None,
+                        "persist",
+                    ));
+            }
+
             config_loader.load().await
         });
 
@@ -209,21 +304,41 @@ impl S3StorageAdapterBuilder {
             ));
         }
 
-        let client = S3Client::new(&sdk_config);
+        let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            .force_path_style(self.force_path_style)
+            .build();
+        let client = S3Client::from_conf(s3_config);
+        let credentials_provider = sdk_config.credentials_provider();
 
+        // Credentials are left out: only whether they were given is logged
         info!(
             bucket = %bucket,
             endpoint = ?self.endpoint,
             region = ?self.region,
+            explicit_credentials = self.credentials.is_some(),
+            profile = ?self.profile,
+            force_path_style = self.force_path_style,
             max_retries = ?max_retries,
             timeout = ?timeout,
+            connect_timeout = ?self.connect_timeout,
             "Initialized S3 storage adapter via builder"
         );
 
+        let retry_config = self.retry_config.or_else(|| {
+            max_retries.map(|retries| RetryConfig {
+                max_attempts: Some(retries as usize + 1),
+                ..RetryConfig::default()
+            })
+        });
+
         Ok(S3StorageAdapter {
             client,
             bucket,
             runtime: Arc::new(runtime),
+            retry_config,
+            credentials_provider,
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics,
         })
     }
 }
@@ -273,6 +388,7 @@ impl S3StorageAdapter {
         }
 
         let client = S3Client::new(&sdk_config);
+        let credentials_provider = sdk_config.credentials_provider();
 
         info!(bucket = %bucket, "Initialized S3 storage adapter");
 
@@ -280,6 +396,10 @@ impl S3StorageAdapter {
             client,
             bucket,
             runtime: Arc::new(runtime),
+            retry_config: None,
+            credentials_provider,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         })
     }
 
@@ -304,6 +424,7 @@ impl S3StorageAdapter {
         })?;
 
         let client = S3Client::new(&config);
+        let credentials_provider = config.credentials_provider();
 
         info!(bucket = %bucket, "Initialized S3 storage adapter with custom config");
 
@@ -311,66 +432,102 @@ impl S3StorageAdapter {
             client,
             bucket,
             runtime: Arc::new(runtime),
+            retry_config: None,
+            credentials_provider,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         })
     }
 
+    /// Use the given retry settings instead of the adapter defaults
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = Some(retry_config);
+        self
+    }
+
+    /// Record operations in `metrics` instead of [`PersistMetrics::global`]
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: PersistMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Metrics the operations and retries are recorded in
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> &PersistMetrics {
+        match &self.metrics {
+            Some(metrics) => metrics,
+            None => PersistMetrics::global(),
+        }
+    }
+
     /// Get the bucket name
     pub fn bucket(&self) -> &str {
         &self.bucket
     }
 
-    /// Perform S3 save operation with retry logic using exponential backoff
-    fn save_with_retry(&self, data: &[u8], key: &str) -> Result<()> {
-        // Convert to Bytes once to avoid copying data on each retry
-        let data_bytes = Bytes::copy_from_slice(data);
-
-        // Use proper exponential backoff with jitter
-        let backoff = ExponentialBackoff {
-            max_elapsed_time: Some(std::time::Duration::from_secs(300)), // 5 minutes max
-            max_interval: std::time::Duration::from_secs(30), // Max 30 seconds between retries
-            ..ExponentialBackoff::default()
+    /// Retry policy for a storage operation (`save`, `load`, ...)
+    fn retry_policy(&self, operation: &str) -> RetryPolicy {
+        let mut policy = match &self.retry_config {
+            Some(retry_config) => retry_config.policy_for(operation),
+            None => RetryPolicy::from(ExponentialBackoff {
+                max_elapsed_time: Some(std::time::Duration::from_secs(300)), // 5 minutes max
+                max_interval: std::time::Duration::from_secs(30), // Max 30 seconds between retries
+                ..ExponentialBackoff::default()
+            })
+            .with_jitter(JitterMode::Full)
+            .with_attempt_timeout(std::time::Duration::from_secs(30)),
         };
+        #[cfg(feature = "metrics")]
+        {
+            policy.metrics = crate::observability::storage_retry_metrics_with(
+                self.metrics(),
+                crate::StorageBackend::S3,
+            );
+        }
+        policy
+    }
 
-        let bucket = self.bucket.clone();
-        let key_str = key.to_string();
-
-        let result = backoff::retry(backoff, || {
-            let data_for_retry = data_bytes.clone();
-            let bucket_clone = bucket.clone();
-            let key_clone = key_str.clone();
+    /// Retried operation of `kind` on `key`, targeting `bucket/key` in retry errors
+    fn operation(&self, kind: OperationKind, key: &str) -> Operation {
+        Operation::new(kind).with_target(format!("{}/{key}", self.bucket))
+    }
 
-            match self.save_once_bytes(&data_for_retry, &key_clone) {
-                Ok(()) => Ok(()),
-                Err(e) if is_transient_error(&e) => {
-                    warn!(
-                        bucket = %bucket_clone,
-                        key = %key_clone,
-                        error = %e,
-                        "S3 save attempt failed, retrying..."
-                    );
-                    // Record retry metric
-                    #[cfg(feature = "metrics")]
-                    crate::observability::PersistMetrics::global().record_s3_retry("put_object");
+    /// Perform S3 save operation with retry logic using exponential backoff
+    fn save_with_retry(&self, data: &[u8], key: &str, deadline: Option<Instant>) -> Result<()> {
+        #[cfg(feature = "metrics")]
+        let _in_flight = self.metrics().track_in_flight("s3");
 
-                    Err(backoff::Error::transient(e))
-                }
-                Err(e) => Err(backoff::Error::permanent(e)),
-            }
-        });
+        // Convert to Bytes once to avoid copying data on each retry
+        let data_bytes = Bytes::copy_from_slice(data);
 
-        match result {
-            Ok(()) => Ok(()),
-            Err(backoff::Error::Permanent(e)) | Err(backoff::Error::Transient { err: e, .. }) => {
-                Err(e)
-            }
+        // Use the configured exponential backoff with jitter
+        let mut policy = self.retry_policy("save");
+        policy.deadline = deadline;
+        if policy.deadline_passed() {
+            return Err(PersistError::storage(format!(
+                "S3 save deadline exceeded before the first attempt (key: {key})"
+            )));
         }
+        retry_blocking(
+            self.operation(OperationKind::Save, key),
+            &policy,
+            |_attempt| self.save_once_bytes(&data_bytes, key, policy.attempt_timeout),
+        )
     }
 
     /// Perform a single S3 save operation using Bytes for efficient memory handling
-    #[tracing::instrument(level = "debug", skip(self, data), fields(bucket = %self.bucket, key = %key, size = data.len()))]
-    fn save_once_bytes(&self, data: &Bytes, key: &str) -> Result<()> {
+    ///
+    /// The request is abandoned after `attempt_timeout`, if set.
+    #[tracing::instrument(level = "debug", skip(self, data), fields(bucket = %self.bucket, key = %key, size = data.len(), request_id = tracing::field::Empty, extended_request_id = tracing::field::Empty))]
+    fn save_once_bytes(
+        &self,
+        data: &Bytes,
+        key: &str,
+        attempt_timeout: Option<std::time::Duration>,
+    ) -> Result<()> {
         #[cfg(feature = "metrics")]
-        let timer = MetricsTimer::new("put_object");
+        let timer = self.metrics().start_s3_operation("put_object");
 
         debug!(
             bucket = %self.bucket,
@@ -379,18 +536,29 @@ impl S3StorageAdapter {
             "Starting S3 put_object operation"
         );
 
-        let result = self.runtime.block_on(async {
-            self.client
-                .put_object()
-                .bucket(&self.bucket)
-                .key(key)
-                .body(ByteStream::from(data.clone()))
-                .send()
-                .await
-        });
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_metadata(Some(trace_metadata()))
+            .body(ByteStream::from(data.clone()))
+            .send();
+        let result = match self
+            .runtime
+            .block_on(attempt_with_timeout(attempt_timeout, request))
+        {
+            Some(result) => result.map_err(|e| map_s3_error("put_object", e, key, &self.bucket)),
+            None => Err(PersistError::s3_upload_error(
+                attempt_timed_out("put_object", attempt_timeout),
+                self.bucket.clone(),
+                key.to_string(),
+            )),
+        };
 
         match result {
-            Ok(_) => {
+            Ok(output) => {
+                record_request_ids(output.request_id(), output.extended_request_id());
                 debug!(
                     bucket = %self.bucket,
                     key = %key,
@@ -400,17 +568,11 @@ impl S3StorageAdapter {
                 #[cfg(feature = "metrics")]
                 {
                     timer.finish();
-                    // TODO: Add storage bytes total metric when available
-                    // crate::observability::PersistMetrics::global().record_storage_bytes_total(
-                    //     "s3",
-                    //     "put",
-                    //     data.len() as u64,
-                    // );
+                    self.metrics().record_s3_bytes("upload", data.len());
                 }
                 Ok(())
             }
-            Err(e) => {
-                let mapped_error = map_s3_error("put_object", e, key, &self.bucket);
+            Err(mapped_error) => {
                 error!(
                     bucket = %self.bucket,
                     key = %key,
@@ -429,57 +591,62 @@ impl S3StorageAdapter {
     #[tracing::instrument(level = "debug", skip(self, data), fields(bucket = %self.bucket, key = %key, size = data.len()))]
     fn save_once(&self, data: &[u8], key: &str) -> Result<()> {
         let data_bytes = Bytes::copy_from_slice(data);
-        self.save_once_bytes(&data_bytes, key)
+        self.save_once_bytes(&data_bytes, key, None)
     }
 
     /// Perform S3 load operation with retry logic using exponential backoff
-    fn load_with_retry(&self, key: &str) -> Result<Vec<u8>> {
-        // Use proper exponential backoff with jitter
-        let backoff = ExponentialBackoff {
-            max_elapsed_time: Some(std::time::Duration::from_secs(300)), // 5 minutes max
-            max_interval: std::time::Duration::from_secs(30), // Max 30 seconds between retries
-            ..ExponentialBackoff::default()
-        };
-
-        let bucket = self.bucket.clone();
-        let key_str = key.to_string();
-
-        let result = backoff::retry(backoff, || {
-            let bucket_clone = bucket.clone();
-            let key_clone = key_str.clone();
-
-            match self.load_once(&key_clone) {
-                Ok(data) => Ok(data),
-                Err(e) if is_transient_error(&e) => {
-                    warn!(
-                        bucket = %bucket_clone,
-                        key = %key_clone,
-                        error = %e,
-                        "S3 load attempt failed, retrying..."
-                    );
-                    // Record retry metric
-                    #[cfg(feature = "metrics")]
-                    crate::observability::PersistMetrics::global().record_s3_retry("get_object");
-
-                    Err(backoff::Error::transient(e))
-                }
-                Err(e) => Err(backoff::Error::permanent(e)),
-            }
-        });
-
-        match result {
-            Ok(data) => Ok(data),
-            Err(backoff::Error::Permanent(e)) | Err(backoff::Error::Transient { err: e, .. }) => {
-                Err(e)
-            }
+    ///
+    /// With `range` (an HTTP range such as `bytes=0-1023`), only that part of the
+    /// object is downloaded.
+    fn load_with_retry(
+        &self,
+        key: &str,
+        range: Option<&str>,
+        options: &CallOptions,
+    ) -> Result<Vec<u8>> {
+        #[cfg(feature = "metrics")]
+        let _in_flight = self.metrics().track_in_flight("s3");
+
+        // Use the configured exponential backoff with jitter
+        let mut policy = self.retry_policy("load");
+        policy.deadline = options.deadline;
+        if policy.deadline_passed() {
+            return Err(PersistError::storage(format!(
+                "S3 load deadline exceeded before the first attempt (key: {key})"
+            )));
         }
+        // A retried download starts over; only report bytes past the furthest point
+        // reached so far, so counts never go backwards
+        let reported = AtomicU64::new(0);
+        let progress = |transferred: u64, total: u64| {
+            if transferred > reported.fetch_max(transferred, Ordering::Relaxed) {
+                options.report_progress(transferred, total)
+            } else {
+                Ok(())
+            }
+        };
+        retry_blocking(
+            self.operation(OperationKind::Load, key),
+            &policy,
+            |_attempt| self.load_once(key, range, policy.attempt_timeout, &progress),
+        )
     }
 
     /// Perform a single S3 load operation
-    #[tracing::instrument(level = "debug", skip(self), fields(bucket = %self.bucket, key = %key))]
-    fn load_once(&self, key: &str) -> Result<Vec<u8>> {
+    ///
+    /// The request is abandoned after `attempt_timeout`, if set. The body is read
+    /// chunk by chunk, reporting the bytes received so far and the object size to
+    /// `progress`; an error from `progress` stops the download.
+    #[tracing::instrument(level = "debug", skip(self, progress), fields(bucket = %self.bucket, key = %key, request_id = tracing::field::Empty, extended_request_id = tracing::field::Empty))]
+    fn load_once(
+        &self,
+        key: &str,
+        range: Option<&str>,
+        attempt_timeout: Option<std::time::Duration>,
+        progress: &dyn Fn(u64, u64) -> Result<()>,
+    ) -> Result<Vec<u8>> {
         #[cfg(feature = "metrics")]
-        let timer = MetricsTimer::new("get_object");
+        let timer = self.metrics().start_s3_operation("get_object");
 
         debug!(
             bucket = %self.bucket,
@@ -487,23 +654,54 @@ impl S3StorageAdapter {
             "Starting S3 get_object operation"
         );
 
-        let result = self.runtime.block_on(async {
-            self.client
-                .get_object()
-                .bucket(&self.bucket)
-                .key(key)
-                .send()
-                .await
-        });
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_range(range.map(str::to_string))
+            .send();
+        let result = match self
+            .runtime
+            .block_on(attempt_with_timeout(attempt_timeout, request))
+        {
+            Some(result) => result.map_err(|e| map_s3_error("get_object", e, key, &self.bucket)),
+            None => Err(PersistError::s3_download_error(
+                attempt_timed_out("get_object", attempt_timeout),
+                self.bucket.clone(),
+                key.to_string(),
+            )),
+        };
 
         match result {
             Ok(output) => {
-                // Collect the response body stream into bytes
-                let bytes_result = self.runtime.block_on(async { output.body.collect().await });
+                record_request_ids(output.request_id(), output.extended_request_id());
+                // Read the response body stream chunk by chunk
+                let size = output
+                    .content_length
+                    .and_then(|length| u64::try_from(length).ok());
+                let mut body = output.body;
+                let mut aborted = None;
+                let bytes_result = self.runtime.block_on(async {
+                    let mut bytes = Vec::new();
+                    while let Some(chunk) = body.try_next().await? {
+                        bytes.extend_from_slice(&chunk);
+                        let transferred = bytes.len() as u64;
+                        if let Err(e) = progress(transferred, size.unwrap_or(0).max(transferred)) {
+                            aborted = Some(e);
+                            break;
+                        }
+                    }
+                    Ok::<_, aws_sdk_s3::primitives::ByteStreamError>(bytes)
+                });
+                if let Some(e) = aborted {
+                    #[cfg(feature = "metrics")]
+                    timer.finish_with_error();
+                    return Err(e);
+                }
 
                 match bytes_result {
-                    Ok(data) => {
-                        let bytes = data.into_bytes().to_vec();
+                    Ok(bytes) => {
                         debug!(
                             bucket = %self.bucket,
                             key = %key,
@@ -513,9 +711,7 @@ impl S3StorageAdapter {
                         #[cfg(feature = "metrics")]
                         {
                             timer.finish();
-                            // TODO: Add storage bytes total metric when available
-                            // crate::observability::PersistMetrics::global()
-                            //     .record_storage_bytes_total("s3", "get", bytes.len() as u64);
+                            self.metrics().record_s3_bytes("download", bytes.len());
                         }
                         Ok(bytes)
                     }
@@ -532,8 +728,7 @@ impl S3StorageAdapter {
                     }
                 }
             }
-            Err(e) => {
-                let mapped_error = map_s3_error("get_object", e, key, &self.bucket);
+            Err(mapped_error) => {
                 error!(
                     bucket = %self.bucket,
                     key = %key,
@@ -549,9 +744,13 @@ impl S3StorageAdapter {
 }
 
 impl StorageAdapter for S3StorageAdapter {
-    #[tracing::instrument(level = "info", skip(self, data), fields(bucket = %self.bucket, key = %path, size = data.len()))]
     fn save(&self, data: &[u8], path: &str) -> Result<()> {
-        info!(
+        self.save_with_options(data, path, &CallOptions::default())
+    }
+
+    #[tracing::instrument(level = "info", skip(self, data, options), fields(bucket = %self.bucket, key = %path, size = data.len()))]
+    fn save_with_options(&self, data: &[u8], path: &str, options: &CallOptions) -> Result<()> {
+        success_log!(
             bucket = %self.bucket,
             key = %path,
             size = data.len(),
@@ -560,19 +759,45 @@ impl StorageAdapter for S3StorageAdapter {
 
         // Record state size metric
         #[cfg(feature = "metrics")]
-        crate::observability::PersistMetrics::global().record_state_size(data.len());
+        self.metrics().record_state_size(data.len());
 
-        self.save_with_retry(data, path)
+        self.save_with_retry(data, path, options.deadline)?;
+        success_log!(
+            bucket = %self.bucket,
+            key = %path,
+            bytes_uploaded = data.len(),
+            "Saved snapshot to S3"
+        );
+        options.report_progress(data.len() as u64, data.len() as u64)
     }
 
-    #[tracing::instrument(level = "info", skip(self), fields(bucket = %self.bucket, key = %path))]
     fn load(&self, path: &str) -> Result<Vec<u8>> {
-        info!(
+        self.load_with_options(path, &CallOptions::default())
+    }
+
+    #[tracing::instrument(level = "info", skip(self, options), fields(bucket = %self.bucket, key = %path))]
+    fn load_with_options(&self, path: &str, options: &CallOptions) -> Result<Vec<u8>> {
+        success_log!(
             bucket = %self.bucket,
             key = %path,
             "Loading snapshot from S3"
         );
-        self.load_with_retry(path)
+        let data = self.load_with_retry(path, None, options)?;
+        success_log!(
+            bucket = %self.bucket,
+            key = %path,
+            bytes_downloaded = data.len(),
+            "Loaded snapshot from S3"
+        );
+        Ok(data)
+    }
+
+    fn load_prefix(&self, path: &str, max_len: u64) -> Result<Vec<u8>> {
+        if max_len == 0 {
+            return Ok(Vec::new());
+        }
+        let range = format!("bytes=0-{}", max_len - 1);
+        self.load_with_retry(path, Some(&range), &CallOptions::default())
     }
 
     fn exists(&self, path: &str) -> bool {
@@ -640,7 +865,7 @@ impl StorageAdapter for S3StorageAdapter {
     }
 
     fn delete(&self, path: &str) -> Result<()> {
-        info!(
+        success_log!(
             bucket = %self.bucket,
             key = %path,
             "Deleting snapshot from S3"
@@ -676,6 +901,210 @@ impl StorageAdapter for S3StorageAdapter {
             }
         }
     }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        debug!(
+            bucket = %self.bucket,
+            prefix = %prefix,
+            "Listing snapshots in S3"
+        );
+
+        let result = self.runtime.block_on(async {
+            let mut keys = Vec::new();
+            let mut pages = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .into_paginator()
+                .send();
+
+            while let Some(page) = pages.next().await {
+                let page = match page {
+                    Ok(page) => page,
+                    Err(e) => return Err(e),
+                };
+                keys.extend(
+                    page.contents()
+                        .iter()
+                        .filter_map(|object| object.key().map(str::to_string)),
+                );
+            }
+            Ok(keys)
+        });
+
+        match result {
+            Ok(mut keys) => {
+                keys.sort();
+                Ok(keys)
+            }
+            Err(e) => {
+                let mapped_error = map_s3_error("list_objects_v2", e, prefix, &self.bucket);
+                error!(
+                    bucket = %self.bucket,
+                    prefix = %prefix,
+                    error = ?mapped_error,
+                    "Failed to list snapshots in S3"
+                );
+                Err(mapped_error)
+            }
+        }
+    }
+
+    /// Object size from a HEAD request, without downloading the object
+    fn size(&self, path: &str) -> Result<u64> {
+        debug!(bucket = %self.bucket, key = %path, "Reading S3 object size");
+
+        let result = self.runtime.block_on(async {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(path)
+                .send()
+                .await
+        });
+
+        match result {
+            Ok(head) => Ok(head.content_length().unwrap_or(0).max(0) as u64),
+            Err(e) => Err(map_s3_error("head_object", e, path, &self.bucket)),
+        }
+    }
+
+    /// HEAD request on the bucket, made once
+    fn health_check(&self) -> Result<()> {
+        debug!(bucket = %self.bucket, "Checking S3 bucket access");
+
+        let result = self
+            .runtime
+            .block_on(async { self.client.head_bucket().bucket(&self.bucket).send().await });
+
+        result
+            .map(|_| ())
+            .map_err(|e| map_s3_error("head_bucket", e, "", &self.bucket))
+    }
+
+    /// Expiry of the credentials resolved by the AWS provider chain
+    fn credential_expiry(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        use aws_sdk_s3::config::ProvideCredentials;
+
+        let Some(provider) = &self.credentials_provider else {
+            return Ok(None);
+        };
+        let credentials = self
+            .runtime
+            .block_on(provider.provide_credentials())
+            .map_err(|e| {
+                PersistError::storage(format!("Failed to resolve AWS credentials: {e}"))
+            })?;
+        Ok(credentials.expiry().map(chrono::DateTime::from))
+    }
+
+    /// Aborts incomplete multipart uploads, whose parts are billed until aborted
+    fn collect_garbage(
+        &self,
+        older_than: std::time::Duration,
+        dry_run: bool,
+    ) -> Result<Vec<GarbageItem>> {
+        // A threshold beyond the representable time range selects nothing
+        let Some(cutoff) = chrono::Duration::from_std(older_than)
+            .ok()
+            .and_then(|age| chrono::Utc::now().checked_sub_signed(age))
+        else {
+            return Ok(Vec::new());
+        };
+        let bucket = self.bucket.as_str();
+
+        self.runtime.block_on(async {
+            let mut stale = Vec::new();
+            let (mut key_marker, mut upload_id_marker) = (None, None);
+            loop {
+                let page = self
+                    .client
+                    .list_multipart_uploads()
+                    .bucket(bucket)
+                    .set_key_marker(key_marker.take())
+                    .set_upload_id_marker(upload_id_marker.take())
+                    .send()
+                    .await
+                    .map_err(|e| map_s3_error("list_multipart_uploads", e, "", bucket))?;
+                for upload in page.uploads() {
+                    let (Some(key), Some(upload_id), Some(initiated)) =
+                        (upload.key(), upload.upload_id(), upload.initiated())
+                    else {
+                        continue;
+                    };
+                    let Some(initiated) =
+                        chrono::DateTime::from_timestamp(initiated.secs(), initiated.subsec_nanos())
+                    else {
+                        continue;
+                    };
+                    if initiated <= cutoff {
+                        stale.push((key.to_string(), upload_id.to_string(), initiated));
+                    }
+                }
+                if page.is_truncated() != Some(true) {
+                    break;
+                }
+                key_marker = page.next_key_marker().map(str::to_string);
+                upload_id_marker = page.next_upload_id_marker().map(str::to_string);
+            }
+
+            let mut removed = Vec::new();
+            for (key, upload_id, initiated) in stale {
+                // Parts uploaded so far are what aborting reclaims
+                let mut size = 0u64;
+                let mut part_marker = None;
+                loop {
+                    let parts = self
+                        .client
+                        .list_parts()
+                        .bucket(bucket)
+                        .key(&key)
+                        .upload_id(&upload_id)
+                        .set_part_number_marker(part_marker.take())
+                        .send()
+                        .await
+                        .map_err(|e| map_s3_error("list_parts", e, &key, bucket))?;
+                    size += parts
+                        .parts()
+                        .iter()
+                        .map(|part| part.size().unwrap_or(0).max(0) as u64)
+                        .sum::<u64>();
+                    if parts.is_truncated() != Some(true) {
+                        break;
+                    }
+                    part_marker = parts.next_part_number_marker().map(str::to_string);
+                }
+
+                if !dry_run {
+                    self.client
+                        .abort_multipart_upload()
+                        .bucket(bucket)
+                        .key(&key)
+                        .upload_id(&upload_id)
+                        .send()
+                        .await
+                        .map_err(|e| map_s3_error("abort_multipart_upload", e, &key, bucket))?;
+                    info!(bucket = %bucket, key = %key, upload_id = %upload_id, "Aborted incomplete multipart upload");
+                }
+                removed.push(GarbageItem {
+                    location: format!("{key} (upload {upload_id})"),
+                    size: Some(size),
+                    modified: initiated,
+                });
+            }
+            Ok(removed)
+        })
+    }
+
+    /// A PUT replaces the whole object; S3 never exposes a partial upload
+    fn atomic_overwrite(&self) -> bool {
+        true
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "s3"
+    }
 }
 
 /// Implement graceful shutdown for S3StorageAdapter
@@ -687,7 +1116,46 @@ impl Drop for S3StorageAdapter {
     }
 }
 
+/// I/O error for an S3 request abandoned after the policy's per-attempt timeout
+fn attempt_timed_out(op: &str, timeout: Option<std::time::Duration>) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!(
+            "S3 {op} attempt timed out after {:?}",
+            timeout.unwrap_or_default()
+        ),
+    )
+}
+
+/// Record the AWS request IDs of an S3 response on the current span, for the spans
+/// declaring `request_id` and `extended_request_id` fields
+fn record_request_ids(request_id: Option<&str>, extended_request_id: Option<&str>) {
+    let span = tracing::Span::current();
+    if let Some(request_id) = request_id {
+        span.record("request_id", request_id);
+    }
+    if let Some(extended_request_id) = extended_request_id {
+        span.record("extended_request_id", extended_request_id);
+    }
+}
+
+/// " (request id: ..., extended request id: ...)" for error messages, or "" when S3
+/// returned neither
+fn request_ids_suffix(request_id: Option<&str>, extended_request_id: Option<&str>) -> String {
+    match (request_id, extended_request_id) {
+        (Some(id), Some(extended)) => {
+            format!(" (request id: {id}, extended request id: {extended})")
+        }
+        (Some(id), None) => format!(" (request id: {id})"),
+        (None, Some(extended)) => format!(" (extended request id: {extended})"),
+        (None, None) => String::new(),
+    }
+}
+
 /// Map AWS SDK errors to PersistError with appropriate context
+///
+/// The request IDs of the response, if any, are recorded on the current span and
+/// appended to the messages of errors that carry one.
 fn map_s3_error<E: ProvideErrorMetadata + std::fmt::Debug>(
     op: &str,
     error: aws_sdk_s3::error::SdkError<E>,
@@ -696,6 +1164,8 @@ fn map_s3_error<E: ProvideErrorMetadata + std::fmt::Debug>(
 ) -> PersistError {
     use aws_sdk_s3::error::SdkError;
 
+    record_request_ids(error.request_id(), error.extended_request_id());
+    let request = request_ids_suffix(error.request_id(), error.extended_request_id());
     match &error {
         SdkError::DispatchFailure(dispatch_err) => {
             let error = std::io::Error::other(format!(
@@ -727,7 +1197,8 @@ fn map_s3_error<E: ProvideErrorMetadata + std::fmt::Debug>(
             }
         }
         SdkError::ResponseError(response_err) => {
-            let error = std::io::Error::other(format!("S3 {op} response error: {response_err:?}"));
+            let error =
+                std::io::Error::other(format!("S3 {op} response error{request}: {response_err:?}"));
             match op {
                 "put_object" => {
                     PersistError::s3_upload_error(error, bucket.to_string(), key.to_string())
@@ -735,15 +1206,32 @@ fn map_s3_error<E: ProvideErrorMetadata + std::fmt::Debug>(
                 "get_object" => {
                     PersistError::s3_download_error(error, bucket.to_string(), key.to_string())
                 }
-                _ => PersistError::storage(format!("S3 {op} response error for {bucket}/{key}")),
+                _ => PersistError::storage(format!(
+                    "S3 {op} response error for {bucket}/{key}{request}"
+                )),
             }
         }
         SdkError::ServiceError(service_err) => {
+            let status = service_err.raw().status().as_u16();
+            if status == 429 || status == 503 {
+                let retry_after = service_err
+                    .raw()
+                    .headers()
+                    .get("retry-after")
+                    .and_then(persist_retry::parse_retry_after);
+                return PersistError::throttled(
+                    format!(
+                        "S3 {op} throttled with HTTP {status} for {bucket}/{key}{request}: {}",
+                        service_err.err().message().unwrap_or("Unknown error")
+                    ),
+                    retry_after,
+                );
+            }
             if let Some(code) = service_err.err().code() {
                 match code {
-                    "NoSuchBucket" => {
-                        PersistError::s3_configuration(format!("S3 bucket '{bucket}' not found"))
-                    }
+                    "NoSuchBucket" => PersistError::s3_configuration(format!(
+                        "S3 bucket '{bucket}' not found{request}"
+                    )),
                     "NoSuchKey" => PersistError::s3_not_found(bucket.to_string(), key.to_string()),
                     "AccessDenied" | "Forbidden" => {
                         PersistError::s3_access_denied(bucket.to_string())
@@ -753,7 +1241,7 @@ fn map_s3_error<E: ProvideErrorMetadata + std::fmt::Debug>(
                     )),
                     _ => {
                         let msg = format!(
-                            "S3 service error ({}): {}",
+                            "S3 service error ({}){request}: {}",
                             code,
                             service_err.err().message().unwrap_or("Unknown error")
                         );
@@ -773,46 +1261,13 @@ fn map_s3_error<E: ProvideErrorMetadata + std::fmt::Debug>(
                     }
                 }
             } else {
-                PersistError::storage(format!("S3 {op} service error: {service_err:?}"))
+                PersistError::storage(format!("S3 {op} service error{request}: {service_err:?}"))
             }
         }
         _ => PersistError::storage(format!("S3 {op} error: {error}")),
     }
 }
 
-/// Check if an error is transient and should be retried
-fn is_transient_error(error: &PersistError) -> bool {
-    match error {
-        PersistError::Storage(msg) => {
-            // Check for specific transient error patterns with better structure
-            // Network/timeout related errors
-            let network_errors = msg.contains("timed out")
-                || msg.contains("timeout")
-                || msg.contains("dispatch")
-                || msg.contains("connection")
-                || msg.contains("network");
-
-            // AWS service errors that are retryable
-            let service_errors = msg.contains("InternalError")
-                || msg.contains("ServiceUnavailable")
-                || msg.contains("SlowDown")
-                || msg.contains("RequestTimeout")
-                || msg.contains("ThrottledException")
-                || msg.contains("ProvisionedThroughputExceededException");
-
-            // HTTP status codes that indicate transient issues
-            let http_errors = msg.contains("503") // Service Unavailable
-                || msg.contains("502") // Bad Gateway
-                || msg.contains("500") // Internal Server Error
-                || msg.contains("429") // Too Many Requests
-                || msg.contains("408"); // Request Timeout
-
-            network_errors || service_errors || http_errors
-        }
-        _ => false,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -861,8 +1316,185 @@ mod tests {
     //     // This test was causing compilation issues in CI
     // }
 
+    /// Endpoint of an S3 stand-in serving path-style PUT and GET from memory
+    #[cfg(feature = "metrics")]
+    fn fake_s3_endpoint() -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        std::thread::spawn(move || {
+            let mut objects = std::collections::HashMap::new();
+            for mut request in server.incoming_requests() {
+                let path = request.url().split('?').next().unwrap().to_string();
+                let response = match request.method() {
+                    tiny_http::Method::Put => {
+                        let mut body = Vec::new();
+                        request.as_reader().read_to_end(&mut body).unwrap();
+                        objects.insert(path, body);
+                        tiny_http::Response::from_data(Vec::new())
+                    }
+                    tiny_http::Method::Get => match objects.get(&path) {
+                        Some(body) => tiny_http::Response::from_data(body.clone()),
+                        None => tiny_http::Response::from_data(Vec::new()).with_status_code(404),
+                    },
+                    _ => tiny_http::Response::from_data(Vec::new()).with_status_code(405),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        format!("http://127.0.0.1:{port}")
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_transfer_bytes_metrics() {
+        use std::collections::BTreeMap;
+
+        let metrics = PersistMetrics::new().unwrap();
+        let adapter = S3StorageAdapterBuilder::new()
+            .bucket("test-bucket")
+            .endpoint(fake_s3_endpoint())
+            .region("us-east-1")
+            .credentials(S3Credentials {
+                access_key_id: "test".to_string(),
+                secret_access_key: "test".to_string(),
+                session_token: None,
+            })
+            .force_path_style(true)
+            .max_retries(0)
+            .metrics(metrics.clone())
+            .build()
+            .unwrap();
+
+        let data = vec![7u8; 1000];
+        adapter.save(&data, "agent/snapshot.json.gz").unwrap();
+        assert_eq!(
+            metrics.snapshot().s3_bytes,
+            BTreeMap::from([("upload".to_string(), 1000)])
+        );
+        assert_eq!(adapter.load("agent/snapshot.json.gz").unwrap(), data);
+        adapter.load("agent/snapshot.json.gz").unwrap();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.s3_bytes,
+            BTreeMap::from([("download".to_string(), 2000), ("upload".to_string(), 1000)])
+        );
+        assert_eq!(snapshot.in_flight, BTreeMap::from([("s3".to_string(), 0)]));
+        assert_eq!(snapshot.s3_requests, 3);
+
+        // A failed load moves no bytes
+        assert!(adapter.load("agent/missing.json.gz").is_err());
+        assert_eq!(metrics.snapshot().s3_bytes, snapshot.s3_bytes);
+
+        let text = metrics.gather_metrics().unwrap();
+        assert!(text.contains(r#"persist_s3_bytes_total{direction="download"} 2000"#));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_storage_from_config_fields() {
+        let mut config = crate::StorageConfig::s3_with_bucket("test-bucket".to_string());
+        config.s3_region = Some("us-east-1".to_string());
+        config.s3_endpoint_url = Some(fake_s3_endpoint());
+        config.s3_force_path_style = true;
+        // The explicit credentials win over the profile, which does not exist
+        config.s3_credentials = Some(S3Credentials::new("test", "test"));
+        config.s3_profile = Some("persist-missing-profile".to_string());
+
+        let storage = crate::create_storage_from_config(config).unwrap();
+        storage.save(b"data", "agent/snapshot.json.gz").unwrap();
+        assert_eq!(storage.load("agent/snapshot.json.gz").unwrap(), b"data");
+    }
+
+    /// S3 error response with `code`, carrying request IDs "REQ123" and "EXT456"
+    fn service_error(
+        status: u16,
+        code: &str,
+    ) -> aws_sdk_s3::error::SdkError<aws_sdk_s3::operation::put_object::PutObjectError> {
+        let mut raw = aws_smithy_runtime_api::http::Response::new(
+            aws_smithy_runtime_api::http::StatusCode::try_from(status).unwrap(),
+            ByteStream::from_static(b"").into_inner(),
+        );
+        raw.headers_mut().insert("x-amz-request-id", "REQ123");
+        raw.headers_mut().insert("x-amz-id-2", "EXT456");
+        let error = aws_sdk_s3::operation::put_object::PutObjectError::generic(
+            aws_sdk_s3::error::ErrorMetadata::builder()
+                .code(code)
+                .message("We encountered an internal error")
+                .build(),
+        );
+        aws_sdk_s3::error::SdkError::service_error(error, raw)
+    }
+
+    /// Layer keeping the values recorded on spans after their creation
+    struct RecordedFields(Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for RecordedFields {
+        fn on_record(
+            &self,
+            _span: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = self.0.lock().unwrap();
+            values.record(
+                &mut |field: &tracing::field::Field, value: &dyn std::fmt::Debug| {
+                    fields.push((field.name().to_string(), format!("{value:?}")));
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_request_ids_in_errors_and_spans() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let fields = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(RecordedFields(fields.clone()));
+        let error = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "put",
+                request_id = tracing::field::Empty,
+                extended_request_id = tracing::field::Empty
+            );
+            let _entered = span.enter();
+            map_s3_error(
+                "put_object",
+                service_error(500, "InternalError"),
+                "agent.json.gz",
+                "bucket",
+            )
+        });
+
+        assert!(
+            error
+                .to_string()
+                .contains("(request id: REQ123, extended request id: EXT456)"),
+            "{error}"
+        );
+        assert_eq!(
+            *fields.lock().unwrap(),
+            [
+                ("request_id".to_string(), r#""REQ123""#.to_string()),
+                ("extended_request_id".to_string(), r#""EXT456""#.to_string()),
+            ]
+        );
+
+        // Throttling keeps its own error, with the IDs in its message
+        let throttled = map_s3_error(
+            "put_object",
+            service_error(503, "SlowDown"),
+            "agent.json.gz",
+            "bucket",
+        );
+        assert!(matches!(throttled, PersistError::Throttled { .. }));
+        assert!(throttled.to_string().contains("request id: REQ123"));
+    }
+
     #[test]
     fn test_is_transient_error() {
+        use crate::is_transient_error;
+
         let timeout_error = PersistError::storage("S3 get_object request timed out (key: test)");
         assert!(is_transient_error(&timeout_error));
 
@@ -875,6 +1507,25 @@ mod tests {
         let other_error = PersistError::validation("Invalid input");
         assert!(!is_transient_error(&other_error));
     }
+
+    #[test]
+    fn test_credential_expiry_from_sdk_config() {
+        use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+
+        let expiry = std::time::SystemTime::now() + std::time::Duration::from_secs(300);
+        let credentials = Credentials::new("access", "secret", None, Some(expiry), "test");
+        let config = SdkConfig::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(SharedCredentialsProvider::new(credentials))
+            .build();
+        let adapter = S3StorageAdapter::with_config("test-bucket".to_string(), config).unwrap();
+
+        assert_eq!(
+            adapter.credential_expiry().unwrap(),
+            Some(chrono::DateTime::from(expiry))
+        );
+    }
 }
 
 // Additional S3 tests are included inline above in the main tests module
@@ -284,14 +284,13 @@ mod s3_tests {
     // These are integration tests that should be run in a proper testing environment
 
     fn create_test_s3_config() -> StorageConfig {
-        StorageConfig {
-            backend: crate::config::StorageBackend::S3,
-            bucket_name: Some("test-persist-bucket".to_string()),
-            region: Some("us-east-1".to_string()),
-            access_key_id: std::env::var("AWS_ACCESS_KEY_ID").ok(),
-            secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY").ok(),
-            endpoint_url: std::env::var("AWS_ENDPOINT_URL").ok(),
-        }
+        // Credentials come from the AWS credential chain
+        let mut config = StorageConfig::s3_with_bucket_and_region(
+            "test-persist-bucket".to_string(),
+            "us-east-1".to_string(),
+        );
+        config.s3_endpoint_url = std::env::var("AWS_ENDPOINT_URL").ok();
+        config
     }
 
     #[tokio::test]
@@ -339,16 +338,9 @@ mod s3_tests {
     #[test]
     fn test_s3_storage_config_validation() {
         // Test invalid config
-        let invalid_config = StorageConfig {
-            backend: crate::config::StorageBackend::S3,
-            bucket_name: None, // Missing bucket name
-            region: Some("us-east-1".to_string()),
-            access_key_id: None,
-            secret_access_key: None,
-            endpoint_url: None,
-        };
-        
-        // This should fail during adapter creation
-        // Note: The actual test depends on how S3StorageAdapter handles invalid configs
+        let mut invalid_config = create_test_s3_config();
+        invalid_config.s3_bucket = None; // Missing bucket name
+
+        assert!(invalid_config.validate().is_err());
     }
 }
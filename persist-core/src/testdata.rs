@@ -0,0 +1,116 @@
+/*!
+Synthetic agent states for benchmarks.
+
+The generated state looks like a typical conversational agent: model settings, a
+tool list, a conversation history and learned facts. The history and facts are
+scaled so that the serialized state comes close to a requested size. Used by the
+criterion benchmarks and by `persist bench`.
+*/
+
+use serde_json::{json, Map, Value};
+
+/// Share of the scaled part of the state taken up by facts; the rest is history
+const FACT_SHARE: usize = 10;
+
+/// Text repeated to pad the state to the requested size
+const FILLER: &str = "The agent keeps notes about the task at hand. ";
+
+/// Serialized synthetic agent state of `size` bytes
+///
+/// States smaller than the fixed part (a few hundred bytes) are not possible; for
+/// those the fixed part alone is returned.
+pub fn synthetic_agent_state(size: usize) -> String {
+    let mut state = json!({
+        "type": "benchmark_agent",
+        "config": {
+            "model": "gpt-4",
+            "temperature": 0.7,
+            "max_tokens": 2048
+        },
+        "memory": {
+            "conversation_history": [],
+            "facts": {},
+            "context": ""
+        },
+        "tools": [
+            {"name": "calculator", "description": "Perform mathematical calculations"},
+            {"name": "web_search", "description": "Search the web for information"},
+            {"name": "file_reader", "description": "Read file contents"}
+        ]
+    });
+
+    // Entries grow with their number, so size them at an index no entry reaches
+    // (`scaled`) to stay below the target, then pad the context to reach it exactly
+    let scaled = size.saturating_sub(state.to_string().len());
+    let largest = scaled as u64;
+    let fact_size = (largest..largest + 20)
+        .map(|i| format!("\"fact_{i}\":{},", fact(i)).len())
+        .max()
+        .unwrap_or(1);
+    let message_size = (largest..largest + 2)
+        .map(|i| message(i).to_string().len() + 1)
+        .max()
+        .unwrap_or(1);
+    let facts = scaled / FACT_SHARE / fact_size;
+    let messages = (scaled - facts * fact_size) / message_size;
+
+    state["memory"]["conversation_history"] = (0..messages as u64).map(message).collect();
+    state["memory"]["facts"] = Value::Object(
+        (0..facts as u64)
+            .map(|i| (format!("fact_{i}"), fact(i)))
+            .collect::<Map<_, _>>(),
+    );
+    let padding = size.saturating_sub(state.to_string().len());
+    state["memory"]["context"] = FILLER
+        .chars()
+        .cycle()
+        .take(padding)
+        .collect::<String>()
+        .into();
+    state.to_string()
+}
+
+/// Message `i` of the conversation history
+fn message(i: u64) -> Value {
+    json!({
+        "role": if i.is_multiple_of(2) { "user" } else { "assistant" },
+        "content": format!("This is message {i} in the conversation history. It contains some realistic text that an agent might encounter during normal operation."),
+        "timestamp": 1640995200 + i * 60,
+        "metadata": {
+            "message_id": format!("msg_{i}"),
+            "processing_time_ms": 150 + (i % 100)
+        }
+    })
+}
+
+/// Learned fact `i`
+fn fact(i: u64) -> Value {
+    json!({
+        "value": format!("This is fact number {i} that the agent has learned"),
+        "confidence": 0.8 + (i % 20) as f64 * 0.01,
+        "source": format!("source_{}", i % 5),
+        "learned_at": 1640995200 + i * 3600
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_state_size() {
+        for size in [4 * 1024, 64 * 1024, 1024 * 1024] {
+            let state = synthetic_agent_state(size);
+            assert_eq!(state.len(), size);
+            let value: Value = serde_json::from_str(&state).unwrap();
+            assert!(!value["memory"]["conversation_history"]
+                .as_array()
+                .unwrap()
+                .is_empty());
+        }
+
+        // Tiny sizes still give a valid state
+        let tiny: Value = serde_json::from_str(&synthetic_agent_state(0)).unwrap();
+        assert_eq!(tiny["type"], "benchmark_agent");
+    }
+}
@@ -4,10 +4,20 @@ Type stubs for the persist Python module.
 This file provides type annotations for IDE support and static type checking.
 """
 
-from typing import Any
+import builtins
+import os
+from collections.abc import AsyncIterator, Awaitable, Callable, Iterable, Iterator, Sequence
+from datetime import datetime, timedelta
+from typing import Any, Literal, overload
 
 __version__: str
 
+__features__: frozenset[str]
+"""Cargo features the storage engine was compiled with, e.g. frozenset({"local", "zstd"})."""
+
+COMPRESSION_ALGORITHMS: list[str]
+"""Compression algorithms this build can write, e.g. ["gzip", "none"]."""
+
 class PersistError(Exception):
     """Base exception for Persist operations."""
 
@@ -33,16 +43,181 @@ class PersistCompressionError(PersistError):
 
     pass
 
+class PersistValidationError(PersistError):
+    """Raised for invalid input, such as a path that escapes base_dir."""
+
+    pass
+
+class PersistFrameworkMismatchError(PersistError):
+    """Raised when a snapshot was created with an incompatible agent framework version."""
+
+    pass
+
+class PersistCompatibilityError(PersistFrameworkMismatchError):
+    """Raised by restore with check_compatibility="error" when a snapshot was created with
+    another LangChain major.minor version than the installed one."""
+
+    pass
+
+class SnapshotMetadata:
+    """Metadata of a stored snapshot, as returned by get_metadata and list_snapshots.
+
+    Instances are immutable. Unset optional fields are None, and sensitive fields
+    hold "[redacted sha256:...]" unless the metadata was fetched with reveal=True.
+    Item access (metadata["agent_id"]) reads the dictionary form of to_dict, so
+    code written against the earlier dictionary results keeps working.
+
+    Example:
+        >>> metadata = persist.get_metadata("snapshots/agent1.json.gz")
+        >>> print(metadata.agent_id, metadata.timestamp.isoformat())
+    """
+
+    @property
+    def path(self) -> str | None:
+        """Storage path/key, for metadata from list_snapshots and the latest_* functions."""
+        ...
+    @property
+    def snapshot_id(self) -> str:
+        """Unique snapshot identifier."""
+        ...
+    @property
+    def agent_id(self) -> str: ...
+    @property
+    def session_id(self) -> str: ...
+    @property
+    def snapshot_index(self) -> int:
+        """Sequence number within the session."""
+        ...
+    @property
+    def timestamp(self) -> datetime:
+        """Creation time, timezone-aware in UTC."""
+        ...
+    @property
+    def format_version(self) -> int: ...
+    @property
+    def format_minor_version(self) -> int: ...
+    @property
+    def format_version_string(self) -> str:
+        """Format version as "major.minor"."""
+        ...
+    @property
+    def compatibility(self) -> str:
+        """"compatible", "needs migration" or "unsupported"."""
+        ...
+    @property
+    def content_hash(self) -> str:
+        """Algorithm-prefixed hash of the content, e.g. "sha256:<hex>"."""
+        ...
+    @property
+    def hash_algorithm(self) -> str | None: ...
+    @property
+    def compression_algorithm(self) -> str: ...
+    @property
+    def uncompressed_size(self) -> int: ...
+    @property
+    def compressed_size(self) -> int | None: ...
+    @property
+    def description(self) -> str | None: ...
+    @property
+    def framework(self) -> str | None: ...
+    @property
+    def framework_version(self) -> str | None: ...
+    @property
+    def state_schema_version(self) -> int | None: ...
+    @property
+    def tags(self) -> dict[str, str]:
+        """A copy of the tags; changing it does not change the metadata."""
+        ...
+    @property
+    def parent_snapshot_id(self) -> str | None: ...
+    @property
+    def expires_at(self) -> datetime | None:
+        """Expiry time, timezone-aware in UTC."""
+        ...
+    @property
+    def sensitive_fields(self) -> list[str]:
+        """Fields redacted unless reveal=True."""
+        ...
+    @property
+    def extra(self) -> dict[str, Any]:
+        """Fields not known to this version, such as "persist.payload"."""
+        ...
+    def to_dict(self) -> dict[str, Any]:
+        """The metadata as a dictionary, as returned with as_dict=True.
+
+        It holds the fields above, with timestamp as seconds since the Unix epoch,
+        expires_at in RFC 3339 and fields from extra at the top level;
+        sensitive_fields and path are only present if set.
+        """
+        ...
+    def __getitem__(self, key: str) -> Any: ...
+    def __contains__(self, key: object) -> bool: ...
+    def keys(self) -> list[str]: ...
+    def get(self, key: str, default: Any = None) -> Any: ...
+
+class VerificationResult:
+    """Outcome of verifying one snapshot, returned by verify and verify_many.
+
+    valid is False if the snapshot is missing, cannot be decompressed or parsed,
+    has an incompatible format version or fails its integrity check; error then
+    says why. Hashes and the format version are None when the snapshot's metadata
+    could not be read. Truthy exactly when valid.
+    """
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def valid(self) -> bool: ...
+    @property
+    def error(self) -> str | None:
+        """Why verification failed."""
+        ...
+    @property
+    def expected_hash(self) -> str | None:
+        """Content hash recorded in the metadata, as "<algorithm>:<hex digest>"."""
+        ...
+    @property
+    def actual_hash(self) -> str | None:
+        """Hash of the stored agent state, if it could be computed."""
+        ...
+    @property
+    def format_version(self) -> str | None:
+        """Format version as "<major>.<minor>"."""
+        ...
+    @property
+    def compatible(self) -> bool | None:
+        """Whether this version reads the format directly, without migration."""
+        ...
+    def __bool__(self) -> bool: ...
+
 def snapshot(
     agent: Any,
     path: str,
     agent_id: str = "default_agent",
     session_id: str = "default_session",
-    snapshot_index: int = 0,
+    snapshot_index: int | Literal["auto"] = 0,
     description: str | None = None,
+    *,
     storage_mode: str | None = None,
     s3_bucket: str | None = None,
     s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    sensitive: list[Literal["description", "tags"]] | None = None,
+    compression: Literal["gzip", "zstd", "none"] | None = None,
+    compression_level: int | None = None,
+    serializer: Callable[[Any], str] | Literal["pickle"] | None = None,
+    tags: dict[str, str] | None = None,
+    progress_callback: Callable[[int, int], object] | None = None,
 ) -> None:
     """
     Save an agent snapshot with configurable storage backend.
@@ -55,14 +230,55 @@ def snapshot(
         path: Storage path/key for the snapshot
         agent_id: Unique identifier for the agent (default: "default_agent")
         session_id: Session identifier (default: "default_session")
-        snapshot_index: Sequence number for this snapshot (default: 0)
+        snapshot_index: Sequence number for this snapshot (default: 0), or "auto" to use
+            one more than the highest existing index for this agent/session in the same
+            directory
         description: Human-readable description of the snapshot
-        storage_mode: Storage backend - "local" or "s3" (default: "local")
-        s3_bucket: S3 bucket name (required for S3 mode)
-        s3_region: S3 region (optional, uses AWS environment default)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        sensitive: Metadata fields to treat as sensitive; they are redacted in
+            returned metadata unless reveal=True is passed
+        compression: Compression algorithm - "gzip", "zstd" or "none" (default:
+            PERSIST_COMPRESSION, then "gzip"); restoring detects the algorithm, so any
+            snapshot can be read back
+        compression_level: Codec level - 0-9 for gzip, 1-22 for zstd (default: the
+            codec's own)
+        serializer: Callable used instead of LangChain's dumps; it receives the agent
+            and must return a JSON string. Exceptions it raises propagate unchanged.
+            "pickle" pickles the agent instead, for objects that cannot be serialized
+            otherwise. WARNING: unpickling can run arbitrary code, so such snapshots
+            are only restored with allow_pickle=True; never use it for snapshots that
+            others can write to
+        tags: str to str tags, at most 64 with keys up to 128 and values up to 256
+            bytes long; list_snapshots can filter on them
+        progress_callback: Callable receiving (bytes_done, bytes_total) of the storage
+            write, at most every 100 ms and once it completes. An exception it raises
+            aborts the write and propagates unchanged
 
     Raises:
         PersistError: If saving fails
+        PersistConfigurationError: If serializer is a string other than "pickle"
+        TypeError: If a tag key or value is not a str
+        ValueError: If the tags exceed their size limits
         PersistConfigurationError: If configuration is invalid
         PersistS3Error: If S3 operations fail
         PersistCompressionError: If compression fails
@@ -87,9 +303,29 @@ def snapshot(
 
 def restore(
     path: str,
+    secrets_map: dict[str, Any] | None = None,
+    *,
     storage_mode: str | None = None,
     s3_bucket: str | None = None,
     s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    framework_policy: str | None = None,
+    strict_format: bool = True,
+    deserializer: Callable[..., Any] | None = None,
+    progress_callback: Callable[[int, int], object] | None = None,
+    allow_pickle: bool = False,
+    pickle_allowlist: Iterable[str] | None = None,
+    check_compatibility: Literal["error", "warn", "ignore"] = "warn",
 ) -> Any:
     """
     Restore an agent from a snapshot.
@@ -99,9 +335,49 @@ def restore(
 
     Args:
         path: Storage path/key of the snapshot to restore
-        storage_mode: Storage backend - "local" or "s3" (default: "local")
-        s3_bucket: S3 bucket name (required for S3 mode)
-        s3_region: S3 region (optional, uses AWS environment default)
+        secrets_map: Secrets/API keys to inject into the restored agent
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        framework_policy: Compare the snapshot's framework version with the installed
+            LangChain: "ignore", "framework", "major", "minor" or "exact" (default: no check)
+        strict_format: Reject snapshots with an incompatible format version (default: True).
+            Pass False to load them on a best-effort basis, e.g. for data recovery; the
+            integrity check still applies
+        deserializer: Callable used instead of LangChain's loads; it receives the JSON
+            string, plus secrets_map when one is given, and returns the restored agent.
+            Exceptions it raises propagate unchanged
+        progress_callback: Callable receiving (bytes_done, bytes_total) of the storage
+            read, at most every 100 ms and once it completes. An exception it raises
+            aborts the read and propagates unchanged
+        allow_pickle: Unpickle snapshots saved with serializer="pickle" (default:
+            False). WARNING: unpickling can run arbitrary code; only allow it for
+            snapshots from a trusted source
+        pickle_allowlist: "module.name" globals, such as "myagents.Agent", that
+            unpickling may load; any other raises pickle.UnpicklingError
+        check_compatibility: What to do when the snapshot records another LangChain
+            major.minor version than the installed one, read at call time: "error",
+            "warn" (a UserWarning naming both versions) or "ignore" (default: "warn").
+            Snapshots without a recorded version are not checked; get_metadata exposes
+            the recorded framework and framework_version
 
     Returns:
         The restored agent object
@@ -109,6 +385,10 @@ def restore(
     Raises:
         PersistError: If restoration fails
         PersistIntegrityError: If integrity verification fails
+        PersistFrameworkMismatchError: If the framework version violates framework_policy
+        PersistCompatibilityError: If check_compatibility is "error" and the LangChain
+            versions differ
+        PersistValidationError: If the snapshot was pickled and allow_pickle is not set
         PersistConfigurationError: If configuration is invalid
         PersistS3Error: If S3 operations fail
         PersistCompressionError: If decompression fails
@@ -129,36 +409,497 @@ def restore(
     """
     ...
 
+def snapshot_many(
+    items: Iterable[dict[str, Any]],
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    compression: Literal["gzip", "zstd", "none"] | None = None,
+    compression_level: int | None = None,
+    serializer: Callable[[Any], str] | Literal["pickle"] | None = None,
+    parallel: int = 8,
+    as_dict: bool = False,
+) -> list[dict[str, Any]]:
+    """
+    Save several snapshots with one storage engine.
+
+    Each agent is serialized in turn, then the snapshots are written concurrently, at
+    most `parallel` at a time, with the GIL released. A failing item does not stop the
+    others: its exception is returned in its place instead of being raised.
+
+    Args:
+        items: Dicts with the state to save under exactly one of "agent" (serialized
+            like snapshot does) or "state_json" (saved like snapshot_json does), the
+            "path", and optionally "agent_id", "session_id", "snapshot_index",
+            "description", "sensitive" and "tags", as for snapshot. An "auto"
+            snapshot_index is resolved when the item is written, so items of the same
+            agent and session should not both use it
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        compression: Compression algorithm - "gzip", "zstd" or "none" (default:
+            PERSIST_COMPRESSION, then "gzip")
+        compression_level: Codec level - 0-9 for gzip, 1-22 for zstd (default: the
+            codec's own)
+        serializer: Serializer for the "agent" items, as for snapshot
+        parallel: Maximum number of concurrent writes (default: 8)
+        as_dict: Return the metadata as plain dictionaries instead of SnapshotMetadata
+
+    Returns:
+        One {"path", "metadata", "error"} dict per item, in the order of items: the
+        saved snapshot's metadata with error None, or metadata None with the exception
+        the item raised
+
+    Raises:
+        PersistConfigurationError: If configuration is invalid
+        ValueError: If parallel is 0
+
+    Example:
+        >>> results = persist.snapshot_many([
+        ...     {"agent": agent, "path": "agent1/s1.json.gz", "agent_id": "agent1"},
+        ...     {"state_json": {"step": 3}, "path": "agent2/s1.json.gz"},
+        ... ])
+        >>> failed = [result for result in results if result["error"] is not None]
+    """
+    ...
+
+def restore_many(
+    paths: Sequence[str],
+    secrets_map: dict[str, Any] | None = None,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    framework_policy: str | None = None,
+    strict_format: bool = True,
+    deserializer: Callable[..., Any] | None = None,
+    allow_pickle: bool = False,
+    pickle_allowlist: Iterable[str] | None = None,
+    parallel: int = 8,
+    as_dict: bool = False,
+    check_compatibility: Literal["error", "warn", "ignore"] = "warn",
+) -> list[dict[str, Any]]:
+    """
+    Restore several snapshots with one storage engine.
+
+    The snapshots are read concurrently, at most `parallel` at a time, with the GIL
+    released, then deserialized in turn like restore does. A failing snapshot does not
+    stop the others: its exception is returned in its place instead of being raised.
+
+    Args:
+        paths: Storage paths/keys of the snapshots to restore
+        secrets_map: Secrets/API keys to inject into the restored agents
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        framework_policy: Framework version check, as for restore
+        strict_format: Reject snapshots with an incompatible format version, as for
+            restore (default: True)
+        deserializer: Callable used instead of LangChain's loads, as for restore
+        allow_pickle: Unpickle snapshots saved with serializer="pickle", as for restore
+            (default: False)
+        pickle_allowlist: Globals unpickling may load, as for restore
+        parallel: Maximum number of concurrent reads (default: 8)
+        as_dict: Return the metadata as plain dictionaries instead of SnapshotMetadata
+        check_compatibility: LangChain version check, as for restore (default: "warn")
+
+    Returns:
+        One {"path", "agent", "metadata", "error"} dict per path, in the order of paths:
+        error is None, or the exception restoring raised with agent and metadata None
+
+    Raises:
+        PersistConfigurationError: If configuration or framework_policy is invalid
+        ValueError: If parallel is 0
+
+    Example:
+        >>> results = persist.restore_many(["agent1/s1.json.gz", "agent2/s1.json.gz"])
+        >>> agents = [result["agent"] for result in results if result["error"] is None]
+    """
+    ...
+
+def snapshot_json(
+    state_json: str | Any,
+    path: str,
+    agent_id: str = "default_agent",
+    session_id: str = "default_session",
+    snapshot_index: int | Literal["auto"] = 0,
+    description: str | None = None,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    sensitive: list[Literal["description", "tags"]] | None = None,
+    compression: Literal["gzip", "zstd", "none"] | None = None,
+    compression_level: int | None = None,
+    framework: tuple[str, str] | None = None,
+    tags: dict[str, str] | None = None,
+) -> None:
+    """
+    Save a JSON agent state without going through LangChain.
+
+    Args:
+        state_json: The state as a JSON string, or any value json.dumps accepts
+        path: Storage path/key for the snapshot
+        agent_id, session_id, snapshot_index, description, sensitive: As for snapshot
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        compression: Compression algorithm - "gzip", "zstd" or "none" (default:
+            PERSIST_COMPRESSION, then "gzip"); restoring detects the algorithm, so any
+            snapshot can be read back
+        compression_level: Codec level - 0-9 for gzip, 1-22 for zstd (default: the
+            codec's own)
+        framework: (name, version) of the framework the state comes from, recorded in
+            the snapshot metadata
+        tags: str to str tags, as for snapshot
+
+    Raises:
+        PersistError: If the state is not valid JSON or cannot be serialized
+        PersistConfigurationError: If the compression algorithm or level is invalid
+        TypeError: If a tag key or value is not a str
+        ValueError: If the tags exceed their size limits
+
+    Example:
+        >>> persist.snapshot_json({"step": 3}, "agent1/state.json.gz")
+    """
+    ...
+
+def restore_json(
+    path: str,
+    raw: bool = False,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    strict_format: bool = True,
+) -> Any:
+    """
+    Restore a JSON agent state without going through LangChain.
+
+    Works on any JSON snapshot, including ones written by snapshot.
+
+    Args:
+        path: Storage path/key of the snapshot to restore
+        raw: Return the state as a JSON string instead of parsing it (default: False)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        strict_format: Reject snapshots with an incompatible format version (default: True)
+
+    Returns:
+        The parsed state (usually a dict), or its JSON text when raw=True
+
+    Raises:
+        PersistValidationError: If the snapshot holds raw bytes saved with snapshot_bytes
+    """
+    ...
+
+def snapshot_bytes(
+    data: bytes | bytearray | memoryview,
+    path: str,
+    agent_id: str = "default_agent",
+    session_id: str = "default_session",
+    snapshot_index: int | Literal["auto"] = 0,
+    description: str | None = None,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    sensitive: list[Literal["description", "tags"]] | None = None,
+    compression: Literal["gzip", "zstd", "none"] | None = None,
+    compression_level: int | None = None,
+    tags: dict[str, str] | None = None,
+    progress_callback: Callable[[int, int], object] | None = None,
+) -> None:
+    """
+    Save already-serialized state, such as protobuf or msgpack, as raw bytes.
+
+    The bytes are stored base64-encoded inside the usual snapshot container, so
+    compression and integrity checks apply. The metadata records the payload as
+    "persist.payload": "bytes", and restore/restore_json refuse such snapshots.
+
+    Args:
+        data: The payload
+        path: Storage path/key for the snapshot
+        agent_id, session_id, snapshot_index, description, sensitive, tags: As for snapshot
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        compression: Compression algorithm - "gzip", "zstd" or "none" (default:
+            PERSIST_COMPRESSION, then "gzip")
+        compression_level: Codec level - 0-9 for gzip, 1-22 for zstd (default: the
+            codec's own)
+        progress_callback: Callable receiving (bytes_done, bytes_total), as for snapshot
+
+    Raises:
+        TypeError: If data is not bytes-like, or the tags are not str to str
+
+    Example:
+        >>> persist.snapshot_bytes(state.SerializeToString(), "agent1/state.bin.gz")
+        >>> data = persist.restore_bytes("agent1/state.bin.gz")
+    """
+    ...
+
+def restore_bytes(
+    path: str,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    strict_format: bool = True,
+    progress_callback: Callable[[int, int], object] | None = None,
+) -> bytes:
+    """
+    Restore the bytes saved by snapshot_bytes, exactly as they were passed.
+
+    Args:
+        path: Storage path/key of the snapshot to restore
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        strict_format: Reject snapshots with an incompatible format version (default: True)
+        progress_callback: Callable receiving (bytes_done, bytes_total), as for restore
+
+    Returns:
+        The original payload
+
+    Raises:
+        PersistValidationError: If the snapshot holds agent JSON rather than bytes
+    """
+    ...
+
+@overload
 def get_metadata(
     path: str,
+    *,
     storage_mode: str | None = None,
     s3_bucket: str | None = None,
     s3_region: str | None = None,
-) -> dict[str, str | int | float]:
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[False] = False,
+) -> SnapshotMetadata:
     """
     Get metadata for a snapshot without loading the full snapshot.
 
     Args:
         path: Storage path/key of the snapshot
-        storage_mode: Storage backend - "local" or "s3" (default: "local")
-        s3_bucket: S3 bucket name (required for S3 mode)
-        s3_region: S3 region (optional, uses AWS environment default)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        reveal: Return sensitive fields unredacted instead of as "[redacted sha256:...]"
+        as_dict: Return a plain dictionary instead of SnapshotMetadata
 
     Returns:
-        Dictionary containing snapshot metadata with keys:
-        - agent_id: Agent identifier
-        - session_id: Session identifier
-        - snapshot_index: Sequence number
-        - timestamp: Unix timestamp when snapshot was created
-        - format_version: Snapshot format version
-        - content_hash: SHA-256 hash of the content
-        - compression_algorithm: Compression algorithm used
-        - description: Optional description (if present)
-        - compressed_size: Size of compressed data (if available)
-        - snapshot_id: Unique snapshot identifier (if present)
+        The snapshot's SnapshotMetadata, or with as_dict=True its dictionary form
+        (see SnapshotMetadata.to_dict)
 
     Raises:
-        PersistError: If metadata retrieval fails
+        PersistError: If metadata retrieval fails, or reveal=True is passed for a
+            snapshot whose sensitive fields are encrypted
         PersistConfigurationError: If configuration is invalid
         PersistS3Error: If S3 operations fail
         IOError: If I/O operations fail
@@ -166,15 +907,459 @@ def get_metadata(
 
     Example:
         >>> metadata = persist.get_metadata("snapshots/agent1.json.gz")
-        >>> print(f"Agent: {metadata['agent_id']}, Created: {metadata['timestamp']}")
+        >>> print(f"Agent: {metadata.agent_id}, Created: {metadata.timestamp}")
+    """
+    ...
+@overload
+def get_metadata(
+    path: str,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[True],
+) -> dict[str, Any]: ...
+
+@overload
+def update_metadata(
+    path: str,
+    description: str | None = None,
+    tags: dict[str, str] | None = None,
+    remove_tags: list[str] | None = None,
+    expires_at: float | None = None,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[False] = False,
+) -> SnapshotMetadata:
+    """
+    Update the description, tags or expiry of a snapshot without rewriting its state.
+
+    The agent state, content hash and creation timestamp are left untouched.
+
+    Args:
+        path: Storage path/key of the snapshot
+        description: New description (unchanged if None)
+        tags: Tags to add or overwrite
+        remove_tags: Tag keys to remove
+        expires_at: New expiry as a Unix timestamp (unchanged if None)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        reveal: Return sensitive fields unredacted
+        as_dict: Return a plain dictionary instead of SnapshotMetadata
+
+    Returns:
+        The updated metadata, in the same form as get_metadata
+
+    Raises:
+        PersistIntegrityError: If the existing snapshot fails verification
+        PersistError: If the snapshot cannot be read or rewritten
+
+    Example:
+        >>> persist.update_metadata("snapshots/agent1.json.gz", tags={"status": "good"})
+    """
+    ...
+@overload
+def update_metadata(
+    path: str,
+    description: str | None = None,
+    tags: dict[str, str] | None = None,
+    remove_tags: list[str] | None = None,
+    expires_at: float | None = None,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[True],
+) -> dict[str, Any]: ...
+
+@overload
+def list_snapshots(
+    prefix: str = "",
+    agent_id: str | None = None,
+    session_id: str | None = None,
+    since: float | None = None,
+    until: float | None = None,
+    min_index: int | None = None,
+    max_index: int | None = None,
+    tags: dict[str, str] | None = None,
+    limit: int | None = None,
+    sort: str = "newest",
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[False] = False,
+) -> list[SnapshotMetadata]:
+    """
+    List snapshots matching a metadata query.
+
+    Args:
+        prefix: Only consider snapshots whose path starts with this prefix
+        agent_id: Only match snapshots of this agent
+        session_id: Only match snapshots of this session
+        since: Only match snapshots created at or after this Unix timestamp
+        until: Only match snapshots created before this Unix timestamp
+        min_index: Only match snapshots with at least this index
+        max_index: Only match snapshots with at most this index
+        tags: Only match snapshots carrying all of these tags
+        limit: Maximum number of snapshots to return
+        sort: "newest", "oldest", "index", "index_desc", "size", "size_desc", "agent",
+            "agent_desc" or "none" (default: "newest")
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        reveal: Return sensitive fields unredacted
+        as_dict: Return plain dictionaries instead of SnapshotMetadata
+
+    Returns:
+        List of metadata (as returned by get_metadata), each with its path set.
+        A prefix nothing is stored under gives an empty list; files under it that
+        are not snapshots are skipped.
+
+    Raises:
+        PersistConfigurationError: If the sort order or a timestamp is invalid
+        PersistError: If the storage backend cannot be listed
+
+    Example:
+        >>> recent = persist.list_snapshots("snapshots/", agent_id="agent1", limit=20)
+        >>> print([s.path for s in recent])
+    """
+    ...
+@overload
+def list_snapshots(
+    prefix: str = "",
+    agent_id: str | None = None,
+    session_id: str | None = None,
+    since: float | None = None,
+    until: float | None = None,
+    min_index: int | None = None,
+    max_index: int | None = None,
+    tags: dict[str, str] | None = None,
+    limit: int | None = None,
+    sort: str = "newest",
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[True],
+) -> list[dict[str, Any]]: ...
+
+@overload
+def restore_latest(
+    agent_id: str,
+    session_id: str | None = None,
+    prefix: str = "",
+    secrets_map: dict[str, Any] | None = None,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    framework_policy: str | None = None,
+    strict_format: bool = True,
+    deserializer: Callable[..., Any] | None = None,
+    as_dict: Literal[False] = False,
+    allow_pickle: bool = False,
+    pickle_allowlist: Iterable[str] | None = None,
+    check_compatibility: Literal["error", "warn", "ignore"] = "warn",
+) -> tuple[Any, SnapshotMetadata]:
+    """
+    Restore the latest snapshot of an agent.
+
+    Within one session the snapshot with the highest index is the latest; across
+    sessions the most recently created one is. Ties go to the later timestamp (or
+    higher index), then to the greatest path, as for the CLI's `persist latest`.
+
+    Args:
+        agent_id: Agent whose latest snapshot to restore
+        session_id: Only consider snapshots of this session (default: all sessions)
+        prefix: Only consider snapshots whose path starts with this prefix
+        secrets_map: Secrets/API keys to inject into the restored agent
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        framework_policy: As for restore
+        strict_format: As for restore
+        deserializer: As for restore
+        as_dict: Return the metadata as a plain dictionary instead of SnapshotMetadata
+        allow_pickle, pickle_allowlist, check_compatibility: As for restore
+
+    Returns:
+        A tuple of the restored agent and the snapshot's metadata (as returned by
+        get_metadata) with its path set
+
+    Raises:
+        FileNotFoundError: If the agent has no matching snapshot
+        PersistCompatibilityError: If check_compatibility is "error" and the LangChain
+            versions differ
+        PersistError: If restoration fails
+
+    Example:
+        >>> agent, metadata = persist.restore_latest("agent1", session_id="session1")
+        >>> print(metadata.path, metadata.snapshot_index)
     """
     ...
+@overload
+def restore_latest(
+    agent_id: str,
+    session_id: str | None = None,
+    prefix: str = "",
+    secrets_map: dict[str, Any] | None = None,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    framework_policy: str | None = None,
+    strict_format: bool = True,
+    deserializer: Callable[..., Any] | None = None,
+    as_dict: Literal[True],
+    allow_pickle: bool = False,
+    pickle_allowlist: Iterable[str] | None = None,
+    check_compatibility: Literal["error", "warn", "ignore"] = "warn",
+) -> tuple[Any, dict[str, Any]]: ...
+
+@overload
+def latest_metadata(
+    agent_id: str,
+    session_id: str | None = None,
+    prefix: str = "",
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[False] = False,
+) -> SnapshotMetadata:
+    """
+    Get the metadata of the latest snapshot of an agent without loading it.
+
+    The latest snapshot is resolved as for restore_latest.
+
+    Args:
+        agent_id: Agent whose latest snapshot to describe
+        session_id: Only consider snapshots of this session (default: all sessions)
+        prefix: Only consider snapshots whose path starts with this prefix
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        reveal: Return sensitive fields unredacted
+        as_dict: Return a plain dictionary instead of SnapshotMetadata
+
+    Returns:
+        Metadata (as returned by get_metadata) with its path set
+
+    Raises:
+        FileNotFoundError: If the agent has no matching snapshot
+    """
+    ...
+@overload
+def latest_metadata(
+    agent_id: str,
+    session_id: str | None = None,
+    prefix: str = "",
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    reveal: bool = False,
+    as_dict: Literal[True],
+) -> dict[str, Any]: ...
 
 def verify_snapshot(
     path: str,
+    *,
     storage_mode: str | None = None,
     s3_bucket: str | None = None,
     s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
 ) -> None:
     """
     Verify the integrity of a snapshot.
@@ -184,9 +1369,27 @@ def verify_snapshot(
 
     Args:
         path: Storage path/key of the snapshot to verify
-        storage_mode: Storage backend - "local" or "s3" (default: "local")
-        s3_bucket: S3 bucket name (required for S3 mode)
-        s3_region: S3 region (optional, uses AWS environment default)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
 
     Raises:
         PersistIntegrityError: If verification fails or snapshot is corrupted
@@ -201,20 +1404,171 @@ def verify_snapshot(
     """
     ...
 
+def verify(
+    path: str,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+) -> VerificationResult:
+    """
+    Verify a snapshot and report the outcome instead of raising.
+
+    Args:
+        path: Storage path/key of the snapshot to verify
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+
+    Returns:
+        A VerificationResult; a missing, corrupted or unreadable snapshot gives
+        valid=False with the reason in error
+
+    Raises:
+        PersistError: If storage cannot be used, e.g. access is denied
+
+    Example:
+        >>> result = persist.verify("snapshots/agent1.json.gz")
+        >>> if not result:
+        ...     print(result.error, result.expected_hash, result.actual_hash)
+    """
+    ...
+
+def verify_many(
+    paths: Sequence[str],
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    parallel: int = 8,
+) -> list[VerificationResult]:
+    """
+    Verify several snapshots with one storage engine.
+
+    Snapshots are verified concurrently, at most `parallel` at a time.
+
+    Args:
+        paths: Storage paths/keys of the snapshots to verify
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        parallel: Maximum number of concurrent verifications (default: 8)
+
+    Returns:
+        One VerificationResult per path, in the order of paths
+
+    Raises:
+        PersistError: If storage cannot be used, e.g. access is denied
+        ValueError: If parallel is 0
+
+    Example:
+        >>> results = persist.verify_many(["run1/a.json.gz", "run1/b.json.gz"])
+        >>> broken = [result.path for result in results if not result.valid]
+    """
+    ...
+
 def snapshot_exists(
     path: str,
+    *,
     storage_mode: str | None = None,
     s3_bucket: str | None = None,
     s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
 ) -> bool:
     """
     Check if a snapshot exists.
 
     Args:
         path: Storage path/key to check
-        storage_mode: Storage backend - "local" or "s3" (default: "local")
-        s3_bucket: S3 bucket name (required for S3 mode)
-        s3_region: S3 region (optional, uses AWS environment default)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
 
     Returns:
         True if the snapshot exists, False otherwise
@@ -229,18 +1583,48 @@ def snapshot_exists(
 
 def delete_snapshot(
     path: str,
+    *,
     storage_mode: str | None = None,
     s3_bucket: str | None = None,
     s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
 ) -> None:
     """
     Delete a snapshot.
 
     Args:
         path: Storage path/key of the snapshot to delete
-        storage_mode: Storage backend - "local" or "s3" (default: "local")
-        s3_bucket: S3 bucket name (required for S3 mode)
-        s3_region: S3 region (optional, uses AWS environment default)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
 
     Raises:
         PersistError: If deletion fails
@@ -255,3 +1639,1011 @@ def delete_snapshot(
         >>> print("Snapshot deleted!")
     """
     ...
+
+def delete_snapshots(
+    paths: Sequence[str],
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    parallel: int = 8,
+) -> dict[str, PersistError | None]:
+    """
+    Delete several snapshots with one storage engine.
+
+    Deletions run concurrently, at most `parallel` at a time. A failed deletion
+    does not raise or stop the others; it is reported in the result instead.
+
+    Args:
+        paths: Storage paths/keys of the snapshots to delete
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        parallel: Maximum number of concurrent deletions (default: 8)
+
+    Returns:
+        Dictionary mapping each path to None if it was deleted, or to the
+        PersistError that deleting it raised
+
+    Raises:
+        ValueError: If parallel is 0
+
+    Example:
+        >>> results = persist.delete_snapshots(["run1/a.json.gz", "run1/b.json.gz"])
+        >>> failed = {path: e for path, e in results.items() if e is not None}
+    """
+    ...
+
+def delete_prefix(
+    prefix: str,
+    dry_run: bool = False,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    parallel: int = 8,
+) -> dict[str, PersistError | None]:
+    """
+    Delete every snapshot whose path starts with a prefix.
+
+    The prefix is listed first and only the readable snapshots found are deleted;
+    other files under it are left alone. With base_dir (or a GCS prefix) the
+    listing cannot reach outside it.
+
+    Args:
+        prefix: Path/key prefix of the snapshots to delete
+        dry_run: Only report what would be deleted (default: False)
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        parallel: Maximum number of concurrent deletions (default: 8)
+
+    Returns:
+        Dictionary mapping each matched path to None if it was (or with dry_run
+        would be) deleted, or to the PersistError that deleting it raised
+
+    Raises:
+        PersistValidationError: If the prefix escapes base_dir; nothing is deleted
+        ValueError: If parallel is 0
+
+    Example:
+        >>> doomed = persist.delete_prefix("experiments/run-7/", dry_run=True)
+        >>> persist.delete_prefix("experiments/run-7/")
+    """
+    ...
+
+def prune(
+    prefix: str = "",
+    keep_last: int | None = None,
+    older_than: float | timedelta | None = None,
+    max_total_bytes: int | None = None,
+    dry_run: bool = False,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    parallel: int = 8,
+) -> dict[str, Any]:
+    """
+    Delete the snapshots under a prefix that a retention policy rejects.
+
+    The rules are those of `persist prune` and combine the same way: keep_last and
+    older_than select snapshots first, then max_total_bytes deletes the oldest
+    survivors until the rest fit. At least one rule is required.
+
+    Args:
+        prefix: Path/key prefix of the snapshots to consider (default: all)
+        keep_last: Keep only this many snapshots (highest indexes) per agent and
+            session
+        older_than: Delete snapshots created longer ago than this, in seconds or as
+            a datetime.timedelta
+        max_total_bytes: Delete the oldest snapshots until the rest take at most
+            this many stored bytes
+        dry_run: Only report what would be deleted (default: False)
+        storage_mode, s3_bucket, ...: The storage arguments of delete_prefix
+        parallel: Maximum number of concurrent deletions (default: 8)
+
+    Returns:
+        Dictionary with "deleted", the sorted paths that were (or with dry_run
+        would be) deleted; "failed", mapping paths that could not be deleted to the
+        PersistError raised; "reclaimed_bytes", the stored size of the deleted
+        snapshots; "kept" and "kept_bytes" for the survivors; and "dry_run"
+
+    Raises:
+        ValueError: If no rule is given, older_than is negative or parallel is 0
+        TypeError: If older_than is neither a number nor a timedelta
+
+    Example:
+        >>> persist.prune("runs/7/", keep_last=5)
+        >>> persist.prune("scratch/", older_than=timedelta(days=7), dry_run=True)
+    """
+    ...
+
+@overload
+def copy_snapshot(
+    src_path: str,
+    dst_path: str,
+    src_storage: PersistClient | dict[str, Any] | None = None,
+    dst_storage: PersistClient | dict[str, Any] | None = None,
+    verify: bool = True,
+    reveal: bool = False,
+    as_dict: Literal[False] = False,
+) -> SnapshotMetadata:
+    """
+    Copy a snapshot, possibly to another storage backend.
+
+    The stored bytes are copied as they are: the snapshot is not recompressed and
+    its metadata is not rewritten, so the copy keeps its timestamp and content hash.
+
+    Args:
+        src_path: Storage path/key of the snapshot to copy
+        dst_path: Storage path/key of the copy
+        src_storage: Where the snapshot is - a PersistClient, a dict of PersistClient
+            arguments such as {"storage_mode": "s3", "s3_bucket": "my-bucket"}, or
+            None for the defaults
+        dst_storage: Where the copy goes, given like src_storage
+        verify: Check the snapshot before writing the copy, then read the copy back
+            and compare it (default: True); with False the bytes are copied unchecked
+        reveal: Return sensitive fields unredacted
+        as_dict: Return a plain dictionary instead of SnapshotMetadata
+
+    Returns:
+        Metadata of the copy
+
+    Raises:
+        PersistError: If the source snapshot cannot be read
+        PersistIntegrityError: If verification fails; nothing is written
+        TypeError: If src_storage or dst_storage has an unsupported type
+
+    Example:
+        >>> persist.copy_snapshot(
+        ...     "scratch/agent1.json.gz",
+        ...     "agents/agent1.json.gz",
+        ...     src_storage={"base_dir": "/tmp/scratch"},
+        ...     dst_storage={"storage_mode": "s3", "s3_bucket": "my-bucket"},
+        ... )
+    """
+    ...
+@overload
+def copy_snapshot(
+    src_path: str,
+    dst_path: str,
+    src_storage: PersistClient | dict[str, Any] | None = None,
+    dst_storage: PersistClient | dict[str, Any] | None = None,
+    verify: bool = True,
+    reveal: bool = False,
+    *,
+    as_dict: Literal[True],
+) -> dict[str, Any]: ...
+
+@overload
+def move_snapshot(
+    src_path: str,
+    dst_path: str,
+    src_storage: PersistClient | dict[str, Any] | None = None,
+    dst_storage: PersistClient | dict[str, Any] | None = None,
+    verify: bool = True,
+    reveal: bool = False,
+    as_dict: Literal[False] = False,
+) -> SnapshotMetadata:
+    """
+    Move a snapshot, possibly to another storage backend.
+
+    Copies the snapshot like copy_snapshot, then deletes the source. The source is
+    only deleted once the copy is complete and, with verify, verified; if anything
+    fails the source is left untouched. Takes the same arguments as copy_snapshot.
+
+    Returns:
+        Metadata of the snapshot at its new location
+
+    Raises:
+        PersistError: If the source snapshot cannot be read
+        PersistIntegrityError: If verification fails; nothing is written or deleted
+        PersistValidationError: If source and destination are the same snapshot
+
+    Example:
+        >>> persist.move_snapshot("scratch/agent1.json.gz", "kept/agent1.json.gz")
+    """
+    ...
+@overload
+def move_snapshot(
+    src_path: str,
+    dst_path: str,
+    src_storage: PersistClient | dict[str, Any] | None = None,
+    dst_storage: PersistClient | dict[str, Any] | None = None,
+    verify: bool = True,
+    reveal: bool = False,
+    *,
+    as_dict: Literal[True],
+) -> dict[str, Any]: ...
+
+def diff_snapshots(
+    path_a: str,
+    path_b: str,
+    a_storage: PersistClient | dict[str, Any] | None = None,
+    b_storage: PersistClient | dict[str, Any] | None = None,
+    max_value_len: int | None = None,
+    paths_only: bool = False,
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+) -> dict[str, Any]:
+    """
+    Compare two snapshots.
+
+    Both snapshots are loaded (and so verified). Metadata is compared field by
+    field, with sensitive fields compared in redacted form; agent states are
+    compared as JSON, objects key by key and arrays index by index.
+
+    Args:
+        path_a: Storage path/key of the first snapshot
+        path_b: Storage path/key of the second snapshot
+        a_storage: Where the first snapshot is, if not in the storage given by the
+            other arguments - a PersistClient or a dict of PersistClient arguments
+        b_storage: Where the second snapshot is, given like a_storage
+        max_value_len: Replace values whose JSON text is longer than this by a
+            string of its first max_value_len characters followed by "…"
+            (default: no truncation)
+        paths_only: List only the JSON Pointers of the changed state values
+            (default: False)
+        storage_mode, s3_bucket, ...: The storage arguments of PersistClient, used
+            for the snapshots without a_storage/b_storage
+
+    Returns:
+        Dictionary with "metadata", mapping each changed field to
+        {"old": ..., "new": ...} (changed tags are grouped the same way under
+        "tags", with None for a missing tag), "state_changed", and "state", a list
+        of {"op", "path", "old", "new"} changes with op one of "add", "remove" and
+        "replace" and path a JSON Pointer. "state" is empty if the snapshots are
+        identical, or if either state is not JSON, in which case only
+        "state_changed" tells whether they differ.
+
+    Raises:
+        PersistError: If either snapshot cannot be loaded
+        TypeError: If a_storage or b_storage has an unsupported type
+
+    Example:
+        >>> diff = persist.diff_snapshots("agent1/v1.json.gz", "agent1/v2.json.gz")
+        >>> for change in diff["state"]:
+        ...     print(change["op"], change["path"])
+    """
+    ...
+
+def effective_config(
+    *,
+    storage_mode: str | None = None,
+    s3_bucket: str | None = None,
+    s3_region: str | None = None,
+    s3_endpoint_url: str | None = None,
+    s3_access_key_id: str | None = None,
+    s3_secret_access_key: str | None = None,
+    s3_session_token: str | None = None,
+    s3_force_path_style: bool = False,
+    gcs_bucket: str | None = None,
+    gcs_prefix: str | None = None,
+    gcs_credentials_path: str | os.PathLike[str] | None = None,
+    base_dir: str | os.PathLike[str] | None = None,
+    durable_writes: bool = False,
+    file_permissions: int | None = None,
+    compression: str | None = None,
+    compression_level: int | None = None,
+) -> dict[str, Any]:
+    """
+    Show the configuration that calls with these arguments would use.
+
+    Merges the arguments with the PERSIST_STORAGE_MODE, PERSIST_S3_BUCKET,
+    PERSIST_S3_REGION, PERSIST_BASE_DIR and PERSIST_COMPRESSION environment
+    variables and the built-in defaults, the same way every other function does.
+    Arguments always take precedence over the environment. Nothing is created or
+    contacted, and secrets are left out.
+
+    Args:
+        The storage arguments of PersistClient, plus compression and
+        compression_level
+
+    Returns:
+        Dictionary with storage_mode, s3_bucket, s3_region, s3_endpoint_url,
+        s3_force_path_style, s3_access_key_id, gcs_bucket, gcs_prefix,
+        gcs_credentials_path, base_dir, durable_writes, file_permissions,
+        compression and compression_level; settings of other backends are None
+
+    Raises:
+        PersistConfigurationError: If an argument or environment variable is invalid
+
+    Example:
+        >>> persist.effective_config()["storage_mode"]
+        'local'
+    """
+    ...
+
+def info() -> dict[str, Any]:
+    """
+    Describe what this build of persist supports.
+
+    Everything is determined when the extension is compiled, so it always matches the
+    installed wheel.
+
+    Returns:
+        Dictionary with version, core_version, storage_backends (e.g. ["local", "s3"]),
+        compression_algorithms, metadata_format_version ("major.minor"), and the
+        booleans metrics and async_runtime
+
+    Example:
+        >>> "local" in persist.info()["storage_backends"]
+        True
+    """
+    ...
+
+def init_logging(level: str = "info", logger_name: str = "persist", json: bool = False) -> None:
+    """
+    Forward the log events of the Rust core to Python's logging.
+
+    Every event at or above level becomes a LogRecord of the logger logger_name,
+    with the Rust module in its target attribute and the event's fields (including
+    those of enclosing spans, such as path) as further attributes; fields named
+    like a LogRecord attribute get a field_ prefix. Events caused by logging
+    handlers that call back into Persist are dropped. Calling it again changes the
+    level, logger or format instead of installing a second bridge.
+
+    Args:
+        level: Lowest level forwarded - "trace", "debug", "info", "warning", "error"
+            or "off" (default: "info"); trace events are logged at level 5
+        logger_name: Name of the logger receiving the records (default: "persist")
+        json: Make each message a JSON object with message, level, target and
+            fields (default: False)
+
+    Raises:
+        ValueError: If the level is invalid
+        RuntimeError: If another tracing subscriber is already installed
+
+    Example:
+        >>> logging.basicConfig(level=logging.INFO)
+        >>> persist.init_logging("info")
+    """
+    ...
+
+class AutoSnapshotter:
+    """
+    Saves a snapshot of an agent every every_steps calls of maybe_snapshot or every
+    every_seconds, whichever comes first.
+
+    Snapshots go to "{prefix}{agent_id}/{session_id}/snapshot-{index:06}.json.gz",
+    with indexes continuing from the highest one already stored there. With
+    keep_last, older snapshots of the agent and session are deleted after each new
+    one. Failures are logged as warnings of the "persist" logger and retried on the
+    next call, unless strict is set, in which case they are raised.
+
+    Args:
+        client: PersistClient to save with, a dict of PersistClient arguments, or None
+            for the defaults; its serializer is used for the agent
+        agent_id: Agent identifier of the snapshots
+        session_id: Session identifier of the snapshots
+        every_steps: Save after this many calls of maybe_snapshot
+        every_seconds: Save once this many seconds passed since the last snapshot
+        keep_last: Keep only this many snapshots of the agent and session
+        prefix: Storage prefix of the snapshots (default: "")
+        strict: Raise failures instead of logging them (default: False)
+        clock: Callable returning the current time in seconds (default:
+            time.monotonic)
+
+    Raises:
+        ValueError: If neither every_steps nor every_seconds is given, or a value
+            is not positive
+
+    Example:
+        >>> snapshotter = persist.AutoSnapshotter(client, "agent1", "session1", every_steps=10)
+        >>> for observation in stream:
+        ...     agent.observe(observation)
+        ...     snapshotter.maybe_snapshot(agent)
+    """
+
+    def __init__(
+        self,
+        client: PersistClient | dict[str, Any] | None,
+        agent_id: str,
+        session_id: str,
+        every_steps: int | None = None,
+        every_seconds: float | None = None,
+        keep_last: int | None = None,
+        prefix: str = "",
+        strict: bool = False,
+        clock: Callable[[], float] | None = None,
+    ) -> None: ...
+    @property
+    def agent_id(self) -> str: ...
+    @property
+    def session_id(self) -> str: ...
+    def maybe_snapshot(self, agent: Any, force: bool = False) -> str | None:
+        """
+        Count a step of the agent loop and save a snapshot of agent if one is due, or
+        right away with force.
+
+        Returns:
+            The path of the new snapshot, or None if none was due or it failed
+        """
+        ...
+
+def auto_snapshot(
+    client: PersistClient | dict[str, Any] | None,
+    agent_id: str,
+    session_id: str,
+    every_steps: int | None = None,
+    every_seconds: float | None = None,
+    keep_last: int | None = None,
+    prefix: str = "",
+    strict: bool = False,
+    clock: Callable[[], float] | None = None,
+    agent_arg: str = "agent",
+) -> Callable[[Callable[..., Any]], Callable[..., Any]]:
+    """
+    Decorator saving a snapshot of an agent argument after calls of the decorated
+    function.
+
+    After each call, the function's agent_arg parameter is passed to
+    AutoSnapshotter.maybe_snapshot; the other arguments are those of AutoSnapshotter.
+    The wrapper exposes its AutoSnapshotter as snapshotter.
+
+    Raises:
+        TypeError: If the decorated function has no agent_arg parameter
+
+    Example:
+        >>> @persist.auto_snapshot(client, "agent1", "session1", every_steps=10)
+        ... def step(agent, observation):
+        ...     agent.observe(observation)
+    """
+    ...
+
+class PersistClient:
+    """
+    Client holding one storage engine for any number of snapshot operations.
+
+    Creating an engine sets up the storage backend (for S3, an async runtime and an
+    AWS client), so a client reused across calls is much cheaper than the module-level
+    functions, which create one per call. A client can be shared between threads.
+
+    Args:
+        storage_mode: Storage backend - "local", "s3" or "gcs" (default:
+            PERSIST_STORAGE_MODE, then "local")
+        s3_bucket: S3 bucket name (required for S3 mode; default: PERSIST_S3_BUCKET)
+        s3_region: S3 region (optional, uses PERSIST_S3_REGION, then the AWS
+            environment)
+        s3_endpoint_url: S3-compatible endpoint such as MinIO (optional, uses
+            AWS_ENDPOINT_URL)
+        s3_access_key_id: Static S3 access key id (optional, uses the AWS credential
+            chain); takes precedence over the environment
+        s3_secret_access_key: Secret for s3_access_key_id; never logged or included
+            in error messages
+        s3_session_token: Session token of temporary STS credentials (optional)
+        s3_force_path_style: Address S3 buckets by path, as MinIO needs (default: False)
+        gcs_bucket: GCS bucket name (required for GCS mode)
+        gcs_prefix: Optional object prefix within the GCS bucket
+        gcs_credentials_path: Service account JSON file (optional, uses Application
+            Default Credentials)
+        base_dir: Directory for local snapshots; paths become keys relative to it
+            and may not escape it (local mode only; default: PERSIST_BASE_DIR)
+        durable_writes: Sync local files to disk before returning (default: False)
+        file_permissions: Unix permission bits for new local files, e.g. 0o600
+        compression: Compression for new snapshots - "gzip", "zstd" or "none"
+            (default: PERSIST_COMPRESSION, then "gzip"); "zstd" needs the extension
+            built with the zstd feature
+        compression_level: Codec level - 0-9 for gzip, 1-22 for zstd (default: the
+            codec's own)
+        encryption_key: Reserved for snapshot encryption, which is not available
+            yet; passing a key raises PersistConfigurationError
+        strict_format: Reject snapshots with an incompatible format version
+            (default: True)
+        serializer, deserializer: Default callbacks for snapshot and restore, used
+            instead of LangChain; arguments passed to those methods take precedence.
+            serializer may also be "pickle"
+
+    Example:
+        >>> with persist.PersistClient(storage_mode="s3", s3_bucket="my-bucket") as client:
+        ...     client.snapshot(agent, "agent1/session1/snapshot.json.gz", agent_id="agent1")
+        ...     for snap in client.list("agent1/"):
+        ...         print(snap.path)
+    """
+
+    def __init__(
+        self,
+        *,
+        storage_mode: str | None = None,
+        s3_bucket: str | None = None,
+        s3_region: str | None = None,
+        s3_endpoint_url: str | None = None,
+        s3_access_key_id: str | None = None,
+        s3_secret_access_key: str | None = None,
+        s3_session_token: str | None = None,
+        s3_force_path_style: bool = False,
+        gcs_bucket: str | None = None,
+        gcs_prefix: str | None = None,
+        gcs_credentials_path: str | os.PathLike[str] | None = None,
+        base_dir: str | os.PathLike[str] | None = None,
+        durable_writes: bool = False,
+        file_permissions: int | None = None,
+        compression: str | None = None,
+        compression_level: int | None = None,
+        encryption_key: str | None = None,
+        strict_format: bool = True,
+        serializer: Callable[[Any], str] | Literal["pickle"] | None = None,
+        deserializer: Callable[..., Any] | None = None,
+    ) -> None: ...
+    def snapshot(
+        self,
+        agent: Any,
+        path: str,
+        agent_id: str = "default_agent",
+        session_id: str = "default_session",
+        snapshot_index: int | Literal["auto"] = 0,
+        description: str | None = None,
+        sensitive: list[Literal["description", "tags"]] | None = None,
+        serializer: Callable[[Any], str] | Literal["pickle"] | None = None,
+        tags: dict[str, str] | None = None,
+        progress_callback: Callable[[int, int], object] | None = None,
+    ) -> None:
+        """Save an agent snapshot (see the module-level snapshot)."""
+        ...
+    def restore(
+        self,
+        path: str,
+        secrets_map: dict[str, Any] | None = None,
+        framework_policy: str | None = None,
+        deserializer: Callable[..., Any] | None = None,
+        progress_callback: Callable[[int, int], object] | None = None,
+        allow_pickle: bool = False,
+        pickle_allowlist: Iterable[str] | None = None,
+        check_compatibility: Literal["error", "warn", "ignore"] = "warn",
+    ) -> Any:
+        """Restore an agent from a snapshot (see the module-level restore)."""
+        ...
+    def snapshot_many(
+        self,
+        items: Iterable[dict[str, Any]],
+        serializer: Callable[[Any], str] | Literal["pickle"] | None = None,
+        parallel: int = 8,
+        as_dict: bool = False,
+    ) -> builtins.list[dict[str, Any]]:
+        """Save several snapshots (see the module-level snapshot_many)."""
+        ...
+    def restore_many(
+        self,
+        paths: Sequence[str],
+        secrets_map: dict[str, Any] | None = None,
+        framework_policy: str | None = None,
+        deserializer: Callable[..., Any] | None = None,
+        allow_pickle: bool = False,
+        pickle_allowlist: Iterable[str] | None = None,
+        parallel: int = 8,
+        as_dict: bool = False,
+        check_compatibility: Literal["error", "warn", "ignore"] = "warn",
+    ) -> builtins.list[dict[str, Any]]:
+        """Restore several snapshots (see the module-level restore_many)."""
+        ...
+    def snapshot_json(
+        self,
+        state_json: str | Any,
+        path: str,
+        agent_id: str = "default_agent",
+        session_id: str = "default_session",
+        snapshot_index: int | Literal["auto"] = 0,
+        description: str | None = None,
+        sensitive: list[Literal["description", "tags"]] | None = None,
+        framework: tuple[str, str] | None = None,
+        tags: dict[str, str] | None = None,
+    ) -> None:
+        """Save a JSON agent state (see the module-level snapshot_json)."""
+        ...
+    def restore_json(self, path: str, raw: bool = False) -> Any:
+        """Restore a JSON agent state (see the module-level restore_json)."""
+        ...
+    def snapshot_bytes(
+        self,
+        data: bytes | bytearray | memoryview,
+        path: str,
+        agent_id: str = "default_agent",
+        session_id: str = "default_session",
+        snapshot_index: int | Literal["auto"] = 0,
+        description: str | None = None,
+        sensitive: list[Literal["description", "tags"]] | None = None,
+        tags: dict[str, str] | None = None,
+        progress_callback: Callable[[int, int], object] | None = None,
+    ) -> None:
+        """Save raw bytes (see the module-level snapshot_bytes)."""
+        ...
+    def restore_bytes(
+        self, path: str, progress_callback: Callable[[int, int], object] | None = None
+    ) -> bytes:
+        """Restore raw bytes (see the module-level restore_bytes)."""
+        ...
+    @overload
+    def get_metadata(
+        self,
+        path: str,
+        reveal: bool = False,
+        as_dict: Literal[False] = False,
+    ) -> SnapshotMetadata:
+        """Get the metadata of a snapshot (see the module-level get_metadata)."""
+        ...
+    @overload
+    def get_metadata(
+        self,
+        path: str,
+        reveal: bool = False,
+        *,
+        as_dict: Literal[True],
+    ) -> dict[str, Any]: ...
+    @overload
+    def update_metadata(
+        self,
+        path: str,
+        description: str | None = None,
+        tags: dict[str, str] | None = None,
+        remove_tags: list[str] | None = None,
+        expires_at: float | None = None,
+        reveal: bool = False,
+        as_dict: Literal[False] = False,
+    ) -> SnapshotMetadata:
+        """Update snapshot metadata in place (see the module-level update_metadata)."""
+        ...
+    @overload
+    def update_metadata(
+        self,
+        path: str,
+        description: str | None = None,
+        tags: dict[str, str] | None = None,
+        remove_tags: list[str] | None = None,
+        expires_at: float | None = None,
+        reveal: bool = False,
+        *,
+        as_dict: Literal[True],
+    ) -> dict[str, Any]: ...
+    @overload
+    def list(
+        self,
+        prefix: str = "",
+        agent_id: str | None = None,
+        session_id: str | None = None,
+        since: float | None = None,
+        until: float | None = None,
+        min_index: int | None = None,
+        max_index: int | None = None,
+        tags: dict[str, str] | None = None,
+        limit: int | None = None,
+        sort: str = "newest",
+        reveal: bool = False,
+        as_dict: Literal[False] = False,
+    ) -> builtins.list[SnapshotMetadata]:
+        """List snapshots matching a metadata query (see list_snapshots)."""
+        ...
+    @overload
+    def list(
+        self,
+        prefix: str = "",
+        agent_id: str | None = None,
+        session_id: str | None = None,
+        since: float | None = None,
+        until: float | None = None,
+        min_index: int | None = None,
+        max_index: int | None = None,
+        tags: dict[str, str] | None = None,
+        limit: int | None = None,
+        sort: str = "newest",
+        reveal: bool = False,
+        *,
+        as_dict: Literal[True],
+    ) -> builtins.list[dict[str, Any]]: ...
+    @overload
+    def restore_latest(
+        self,
+        agent_id: str,
+        session_id: str | None = None,
+        prefix: str = "",
+        secrets_map: dict[str, Any] | None = None,
+        framework_policy: str | None = None,
+        deserializer: Callable[..., Any] | None = None,
+        as_dict: Literal[False] = False,
+        allow_pickle: bool = False,
+        pickle_allowlist: Iterable[str] | None = None,
+        check_compatibility: Literal["error", "warn", "ignore"] = "warn",
+    ) -> tuple[Any, SnapshotMetadata]:
+        """Restore the latest snapshot of an agent (see the module-level restore_latest)."""
+        ...
+    @overload
+    def restore_latest(
+        self,
+        agent_id: str,
+        session_id: str | None = None,
+        prefix: str = "",
+        secrets_map: dict[str, Any] | None = None,
+        framework_policy: str | None = None,
+        deserializer: Callable[..., Any] | None = None,
+        *,
+        as_dict: Literal[True],
+        allow_pickle: bool = False,
+        pickle_allowlist: Iterable[str] | None = None,
+        check_compatibility: Literal["error", "warn", "ignore"] = "warn",
+    ) -> tuple[Any, dict[str, Any]]: ...
+    @overload
+    def latest_metadata(
+        self,
+        agent_id: str,
+        session_id: str | None = None,
+        prefix: str = "",
+        reveal: bool = False,
+        as_dict: Literal[False] = False,
+    ) -> SnapshotMetadata:
+        """Get the latest snapshot's metadata (see the module-level latest_metadata)."""
+        ...
+    @overload
+    def latest_metadata(
+        self,
+        agent_id: str,
+        session_id: str | None = None,
+        prefix: str = "",
+        reveal: bool = False,
+        *,
+        as_dict: Literal[True],
+    ) -> dict[str, Any]: ...
+    def verify(self, path: str) -> None:
+        """Verify the integrity of a snapshot, raising if it fails."""
+        ...
+    def verify_result(self, path: str) -> VerificationResult:
+        """Verify a snapshot without raising (see the module-level verify)."""
+        ...
+    def verify_many(
+        self, paths: Sequence[str], parallel: int = 8
+    ) -> builtins.list[VerificationResult]:
+        """Verify several snapshots (see the module-level verify_many)."""
+        ...
+    def exists(self, path: str) -> bool:
+        """Check whether a snapshot exists."""
+        ...
+    def delete(self, path: str) -> None:
+        """Delete a snapshot."""
+        ...
+    def delete_many(
+        self, paths: Sequence[str], parallel: int = 8
+    ) -> dict[str, PersistError | None]:
+        """Delete several snapshots (see the module-level delete_snapshots)."""
+        ...
+    def delete_prefix(
+        self, prefix: str, dry_run: bool = False, parallel: int = 8
+    ) -> dict[str, PersistError | None]:
+        """Delete every snapshot under a prefix (see the module-level delete_prefix)."""
+        ...
+    def prune(
+        self,
+        prefix: str = "",
+        keep_last: int | None = None,
+        older_than: float | timedelta | None = None,
+        max_total_bytes: int | None = None,
+        dry_run: bool = False,
+        parallel: int = 8,
+    ) -> dict[str, Any]:
+        """Apply a retention policy under a prefix (see the module-level prune)."""
+        ...
+    def async_snapshot(self, *args: Any, **kwargs: Any) -> Awaitable[None]:
+        """Awaitable snapshot, run on the event loop's default executor."""
+        ...
+    def async_restore(self, *args: Any, **kwargs: Any) -> Awaitable[Any]:
+        """Awaitable restore, run on the event loop's default executor."""
+        ...
+    def async_get_metadata(self, *args: Any, **kwargs: Any) -> Awaitable[SnapshotMetadata]:
+        """Awaitable get_metadata, run on the event loop's default executor."""
+        ...
+    def async_list(
+        self, *args: Any, **kwargs: Any
+    ) -> Awaitable[builtins.list[SnapshotMetadata]]:
+        """Awaitable list, run on the event loop's default executor."""
+        ...
+    def close(self) -> None:
+        """Release the storage engine; later calls raise PersistError."""
+        ...
+    def __enter__(self) -> PersistClient: ...
+    def __exit__(self, exc_type: Any, exc_value: Any, traceback: Any) -> bool: ...
+
+class aio:
+    """Asyncio variants of the module-level functions.
+
+    Each takes the same arguments as its synchronous counterpart and runs it on
+    the event loop's default executor, so storage I/O does not block the loop.
+
+    Example:
+        >>> import persist.aio
+        >>> await persist.aio.snapshot(agent, "agent1/snapshot.json.gz")
+    """
+
+    @staticmethod
+    def snapshot(*args: Any, **kwargs: Any) -> Awaitable[None]: ...
+    @staticmethod
+    def restore(*args: Any, **kwargs: Any) -> Awaitable[Any]: ...
+    @staticmethod
+    def get_metadata(*args: Any, **kwargs: Any) -> Awaitable[SnapshotMetadata]: ...
+    @staticmethod
+    def list_snapshots(*args: Any, **kwargs: Any) -> Awaitable[list[SnapshotMetadata]]: ...
+
+class integrations:
+    """Helpers for the state of agent frameworks other than LangChain.
+
+    Framework libraries are imported when a helper is first used.
+    """
+
+    class llamaindex:
+        """LlamaIndex indexes, saved as the dictionary form of their StorageContext.
+
+        Example:
+            >>> from persist.integrations import llamaindex
+            >>> llamaindex.snapshot_index(index, "rag/index.json.gz", agent_id="rag")
+            >>> index = llamaindex.restore_index("rag/index.json.gz")
+        """
+
+        @staticmethod
+        def snapshot_index(index: Any, path: str, **kwargs: Any) -> None:
+            """Save an index; kwargs are the options of persist.snapshot_json.
+
+            The metadata records the framework as "llamaindex" with the installed
+            version. Raises ImportError if LlamaIndex is not installed.
+            """
+            ...
+        @staticmethod
+        def restore_index(path: str, **kwargs: Any) -> Any:
+            """Restore an index saved with snapshot_index; kwargs are storage options."""
+            ...
+
+    class langgraph:
+        """LangGraph checkpoint saver storing each checkpoint as a Persist snapshot.
+
+        Example:
+            >>> from persist.integrations.langgraph import PersistCheckpointer
+            >>> graph = builder.compile(checkpointer=PersistCheckpointer(prefix="graphs/"))
+            >>> graph.invoke(inputs, {"configurable": {"thread_id": "user-42"}})
+        """
+
+        class PersistCheckpointer:
+            """A LangGraph BaseCheckpointSaver backed by PersistClient.
+
+            Snapshots use the thread id as agent_id and the checkpoint namespace as
+            session_id, and carry the checkpoint config in their tags. Supports the
+            sync and async checkpointer APIs. Accessing the class raises ImportError
+            if LangGraph is not installed.
+            """
+
+            client: PersistClient
+            prefix: str
+
+            def __init__(
+                self,
+                *,
+                prefix: str = "",
+                client: PersistClient | None = None,
+                serde: Any = None,
+                **storage_kwargs: Any,
+            ) -> None: ...
+            def get_tuple(self, config: dict[str, Any]) -> Any: ...
+            def list(
+                self,
+                config: dict[str, Any] | None,
+                *,
+                filter: dict[str, Any] | None = None,
+                before: dict[str, Any] | None = None,
+                limit: int | None = None,
+            ) -> Iterator[Any]: ...
+            def put(
+                self,
+                config: dict[str, Any],
+                checkpoint: dict[str, Any],
+                metadata: dict[str, Any],
+                new_versions: dict[str, Any],
+            ) -> dict[str, Any]: ...
+            def put_writes(
+                self,
+                config: dict[str, Any],
+                writes: Sequence[tuple[str, Any]],
+                task_id: str,
+                task_path: str = "",
+            ) -> None: ...
+            def delete_thread(self, thread_id: str) -> None: ...
+            async def aget_tuple(self, config: dict[str, Any]) -> Any: ...
+            def alist(
+                self,
+                config: dict[str, Any] | None,
+                *,
+                filter: dict[str, Any] | None = None,
+                before: dict[str, Any] | None = None,
+                limit: int | None = None,
+            ) -> AsyncIterator[Any]: ...
+            async def aput(
+                self,
+                config: dict[str, Any],
+                checkpoint: dict[str, Any],
+                metadata: dict[str, Any],
+                new_versions: dict[str, Any],
+            ) -> dict[str, Any]: ...
+            async def aput_writes(
+                self,
+                config: dict[str, Any],
+                writes: Sequence[tuple[str, Any]],
+                task_id: str,
+                task_path: str = "",
+            ) -> None: ...
+            async def adelete_thread(self, thread_id: str) -> None: ...
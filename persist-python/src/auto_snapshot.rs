@@ -0,0 +1,331 @@
+/*!
+The `persist.AutoSnapshotter` class and the `persist.auto_snapshot` decorator, which
+checkpoint an agent every so many steps or seconds.
+
+Snapshots go to `{prefix}{agent_id}/{session_id}/snapshot-{index:06}.json.gz`. Indexes
+continue from the highest one already stored there, so a restarted loop carries on
+where the previous run stopped.
+*/
+
+use crate::PersistClient;
+use chrono::Utc;
+use persist_core::{RetentionPolicy, SnapshotQuery};
+use pyo3::exceptions::PyValueError;
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyDict, PyModule};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Decorator factory wrapping a function with an `AutoSnapshotter`
+const DECORATOR_SOURCE: &std::ffi::CStr = c_str!(
+    r#"
+import functools
+import inspect
+
+
+def decorator(snapshotter, agent_arg):
+    def decorate(func):
+        signature = inspect.signature(func)
+        if agent_arg not in signature.parameters:
+            raise TypeError(f"{func.__qualname__} has no parameter {agent_arg!r}")
+
+        @functools.wraps(func)
+        def wrapper(*args, **kwargs):
+            bound = signature.bind(*args, **kwargs)
+            bound.apply_defaults()
+            result = func(*args, **kwargs)
+            snapshotter.maybe_snapshot(bound.arguments[agent_arg])
+            return result
+
+        wrapper.snapshotter = snapshotter
+        return wrapper
+
+    return decorate
+"#
+);
+
+static DECORATOR_MODULE: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+
+/// When the next snapshot is due
+struct Schedule {
+    /// Calls of `maybe_snapshot` since the last snapshot
+    steps: u64,
+    /// Clock reading at the last snapshot, or at creation
+    last_time: f64,
+    /// Index of the next snapshot; read from storage before the first one
+    next_index: Option<u64>,
+    /// Set while a snapshot is saved, so that concurrent calls do not start another
+    saving: bool,
+}
+
+/// Saves a snapshot of an agent every `every_steps` calls of `maybe_snapshot` or
+/// every `every_seconds`, whichever comes first
+///
+/// With `keep_last`, older snapshots of the agent and session are deleted after each
+/// new one. Failures are logged to the "persist" logger and retried on the next call,
+/// unless `strict` is set, in which case they are raised.
+#[pyclass(frozen, module = "persist", name = "AutoSnapshotter")]
+pub(crate) struct AutoSnapshotter {
+    client: Py<PersistClient>,
+    #[pyo3(get)]
+    agent_id: String,
+    #[pyo3(get)]
+    session_id: String,
+    every_steps: Option<u64>,
+    every_seconds: Option<f64>,
+    keep_last: Option<usize>,
+    /// Directory of the snapshots, ending in "/"
+    directory: String,
+    strict: bool,
+    /// Callable returning the current time in seconds
+    clock: PyObject,
+    schedule: Mutex<Schedule>,
+}
+
+impl AutoSnapshotter {
+    fn schedule(&self) -> MutexGuard<'_, Schedule> {
+        self.schedule.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Save `agent` with the next index, returning that index and the snapshot path
+    fn save(
+        &self,
+        py: Python<'_>,
+        agent: &Bound<'_, PyAny>,
+        next_index: Option<u64>,
+    ) -> PyResult<(u64, String)> {
+        let client = self.client.get();
+        let index = match next_index {
+            Some(index) => index,
+            None => client.with_engine(py, |engine| {
+                engine.next_index_under(&self.directory, &self.agent_id, &self.session_id)
+            })?,
+        };
+        let path = format!("{}snapshot-{index:06}.json.gz", self.directory);
+        let snapshot_index = index.into_pyobject(py)?;
+        client.snapshot(
+            py,
+            agent,
+            &path,
+            &self.agent_id,
+            &self.session_id,
+            Some(snapshot_index.as_any()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )?;
+        Ok((index, path))
+    }
+
+    /// Delete the snapshots of the agent and session beyond the `keep_last` newest
+    fn prune(&self, py: Python<'_>) -> PyResult<()> {
+        let Some(keep_last) = self.keep_last else {
+            return Ok(());
+        };
+        let query = SnapshotQuery::new()
+            .agent_id(&self.agent_id)
+            .session_id(&self.session_id);
+        self.client.get().with_engine(py, |engine| {
+            let snapshots = engine.query(&self.directory, &query)?;
+            let plan = RetentionPolicy::new()
+                .keep_last(keep_last)
+                .plan(snapshots, Utc::now());
+            for summary in plan.delete {
+                engine.delete_snapshot(&summary.path)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Log `error` as a warning, or raise it when strict
+    fn failed(&self, py: Python<'_>, action: &str, error: PyErr) -> PyResult<()> {
+        if self.strict {
+            return Err(error);
+        }
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("exc_info", error.value(py))?;
+        py.import("logging")?
+            .call_method1("getLogger", ("persist",))?
+            .call_method(
+                "warning",
+                (
+                    "%s of agent %s, session %s failed: %s",
+                    action,
+                    &self.agent_id,
+                    &self.session_id,
+                    error.value(py),
+                ),
+                Some(&kwargs),
+            )?;
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl AutoSnapshotter {
+    #[new]
+    #[pyo3(signature = (client, agent_id, session_id, every_steps=None, every_seconds=None, keep_last=None, prefix="", strict=false, clock=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        py: Python<'_>,
+        client: Option<&Bound<'_, PyAny>>,
+        agent_id: String,
+        session_id: String,
+        every_steps: Option<u64>,
+        every_seconds: Option<f64>,
+        keep_last: Option<usize>,
+        prefix: &str,
+        strict: bool,
+        clock: Option<PyObject>,
+    ) -> PyResult<Self> {
+        if every_steps.is_none() && every_seconds.is_none() {
+            return Err(PyValueError::new_err(
+                "AutoSnapshotter needs every_steps or every_seconds",
+            ));
+        }
+        if every_steps == Some(0) {
+            return Err(PyValueError::new_err("every_steps must be at least 1"));
+        }
+        if every_seconds.is_some_and(|seconds| seconds.is_nan() || seconds <= 0.0) {
+            return Err(PyValueError::new_err("every_seconds must be positive"));
+        }
+        if keep_last == Some(0) {
+            return Err(PyValueError::new_err("keep_last must be at least 1"));
+        }
+
+        let clock = match clock {
+            Some(clock) => clock,
+            None => py.import("time")?.getattr("monotonic")?.unbind(),
+        };
+        let last_time = clock.call0(py)?.extract(py)?;
+        Ok(Self {
+            client: PersistClient::for_storage(py, "client", client)?.unbind(),
+            directory: format!("{prefix}{agent_id}/{session_id}/"),
+            agent_id,
+            session_id,
+            every_steps,
+            every_seconds,
+            keep_last,
+            strict,
+            clock,
+            schedule: Mutex::new(Schedule {
+                steps: 0,
+                last_time,
+                next_index: None,
+                saving: false,
+            }),
+        })
+    }
+
+    /// Count a step of the agent loop and save a snapshot of `agent` if one is due,
+    /// or right away with `force`
+    ///
+    /// Returns the path of the new snapshot, or None if none was due or it failed.
+    #[pyo3(signature = (agent, force=false))]
+    fn maybe_snapshot(
+        &self,
+        py: Python<'_>,
+        agent: &Bound<'_, PyAny>,
+        force: bool,
+    ) -> PyResult<Option<String>> {
+        let now: f64 = self.clock.call0(py)?.extract(py)?;
+        // The lock is not held while saving: that releases the GIL, and another thread
+        // taking it could then block on the lock
+        let next_index = {
+            let mut schedule = self.schedule();
+            schedule.steps += 1;
+            let due = force
+                || self
+                    .every_steps
+                    .is_some_and(|every| schedule.steps >= every)
+                || self
+                    .every_seconds
+                    .is_some_and(|every| now - schedule.last_time >= every);
+            if !due || schedule.saving {
+                return Ok(None);
+            }
+            schedule.saving = true;
+            schedule.next_index
+        };
+
+        let saved = self.save(py, agent, next_index);
+        let mut schedule = self.schedule();
+        schedule.saving = false;
+        let (index, path) = match saved {
+            Ok(saved) => saved,
+            Err(e) => {
+                drop(schedule);
+                self.failed(py, "Auto-snapshot", e)?;
+                return Ok(None);
+            }
+        };
+        schedule.steps = 0;
+        schedule.last_time = now;
+        schedule.next_index = Some(index + 1);
+        drop(schedule);
+
+        if let Err(e) = self.prune(py) {
+            self.failed(py, "Pruning auto-snapshots", e)?;
+        }
+        Ok(Some(path))
+    }
+}
+
+/// Decorator saving a snapshot of an agent argument after calls of the decorated function
+///
+/// The function's `agent_arg` parameter is passed to `AutoSnapshotter.maybe_snapshot`
+/// after each call; the other arguments are those of `AutoSnapshotter`. The wrapper
+/// exposes its `AutoSnapshotter` as `snapshotter`.
+///
+/// # Example
+/// ```python
+/// @persist.auto_snapshot(client, "agent1", "session1", every_steps=10)
+/// def step(agent, observation):
+///     agent.observe(observation)
+/// ```
+#[pyfunction]
+#[pyo3(signature = (client, agent_id, session_id, every_steps=None, every_seconds=None, keep_last=None, prefix="", strict=false, clock=None, agent_arg="agent"))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn auto_snapshot(
+    py: Python<'_>,
+    client: Option<&Bound<'_, PyAny>>,
+    agent_id: String,
+    session_id: String,
+    every_steps: Option<u64>,
+    every_seconds: Option<f64>,
+    keep_last: Option<usize>,
+    prefix: &str,
+    strict: bool,
+    clock: Option<PyObject>,
+    agent_arg: &str,
+) -> PyResult<PyObject> {
+    let snapshotter = AutoSnapshotter::new(
+        py,
+        client,
+        agent_id,
+        session_id,
+        every_steps,
+        every_seconds,
+        keep_last,
+        prefix,
+        strict,
+        clock,
+    )?;
+    let module = DECORATOR_MODULE.get_or_try_init(py, || {
+        PyModule::from_code(
+            py,
+            DECORATOR_SOURCE,
+            c_str!("persist/_auto_snapshot.py"),
+            c_str!("persist._auto_snapshot"),
+        )
+        .map(Bound::unbind)
+    })?;
+    Ok(module
+        .bind(py)
+        .getattr("decorator")?
+        .call1((Py::new(py, snapshotter)?, agent_arg))?
+        .unbind())
+}
@@ -0,0 +1,113 @@
+/*!
+Conversion of snapshot diffs into the dictionaries returned by `diff_snapshots`.
+*/
+
+use persist_core::{SnapshotDiff, StateChange};
+use pyo3::prelude::*;
+use serde_json::{json, Map, Value};
+
+/// How values are shown in a converted diff
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DiffFormat {
+    /// Longest JSON text shown for a value; longer values become a truncated string
+    pub max_value_len: Option<usize>,
+    /// List only the paths of the state changes
+    pub paths_only: bool,
+}
+
+impl DiffFormat {
+    /// `value`, or the start of its JSON text followed by "…" if that is too long
+    fn value(&self, value: &Value) -> Value {
+        let Some(max_len) = self.max_value_len else {
+            return value.clone();
+        };
+        let text = value.to_string();
+        if text.chars().count() <= max_len {
+            return value.clone();
+        }
+        let truncated: String = text.chars().take(max_len).collect();
+        Value::String(format!("{truncated}…"))
+    }
+
+    fn change(&self, old: Option<&Value>, new: Option<&Value>) -> Value {
+        json!({
+            "old": old.map(|old| self.value(old)),
+            "new": new.map(|new| self.value(new)),
+        })
+    }
+
+    /// `{"metadata": ..., "state_changed": ..., "state": [...]}` for `diff`
+    ///
+    /// Metadata fields map to `{"old", "new"}`, with changed tags grouped under `"tags"`
+    /// (None on the side where a tag is missing). State changes are
+    /// `{"op", "path", "old", "new"}`, or just their paths with `paths_only`.
+    fn to_json(self, diff: &SnapshotDiff) -> Value {
+        let mut metadata = Map::new();
+        for (field, change) in &diff.metadata.changed {
+            metadata.insert(
+                field.clone(),
+                self.change(Some(&change.before), Some(&change.after)),
+            );
+        }
+
+        let mut tags = Map::new();
+        for (key, value) in &diff.metadata.tags_added {
+            tags.insert(
+                key.clone(),
+                self.change(None, Some(&Value::String(value.clone()))),
+            );
+        }
+        for (key, value) in &diff.metadata.tags_removed {
+            tags.insert(
+                key.clone(),
+                self.change(Some(&Value::String(value.clone())), None),
+            );
+        }
+        for (key, change) in &diff.metadata.tags_changed {
+            tags.insert(
+                key.clone(),
+                self.change(Some(&change.before), Some(&change.after)),
+            );
+        }
+        if !tags.is_empty() {
+            metadata.insert("tags".to_string(), Value::Object(tags));
+        }
+
+        let state: Vec<Value> = diff
+            .state_changes
+            .iter()
+            .map(|change| {
+                if self.paths_only {
+                    return Value::String(change.path().to_string());
+                }
+                let (op, path, old, new) = match change {
+                    StateChange::Add { path, value } => ("add", path, None, Some(value)),
+                    StateChange::Remove { path, old_value } => {
+                        ("remove", path, Some(old_value), None)
+                    }
+                    StateChange::Replace {
+                        path,
+                        old_value,
+                        value,
+                    } => ("replace", path, Some(old_value), Some(value)),
+                };
+                let mut entry = self.change(old, new);
+                entry["op"] = op.into();
+                entry["path"] = path.as_str().into();
+                entry
+            })
+            .collect();
+
+        json!({
+            "metadata": metadata,
+            "state_changed": diff.state_changed,
+            "state": state,
+        })
+    }
+
+    /// `diff` as a Python dictionary
+    pub(crate) fn to_dict(self, py: Python<'_>, diff: &SnapshotDiff) -> PyResult<PyObject> {
+        let text = self.to_json(diff).to_string();
+        Ok(py.import("json")?.call_method1("loads", (text,))?.unbind())
+    }
+}
@@ -0,0 +1,163 @@
+/*!
+Helpers for snapshotting the state of agent frameworks other than LangChain.
+
+Each framework gets a submodule of `persist.integrations`. Framework libraries are
+imported when a helper is first used, so `persist` itself does not depend on them.
+*/
+
+use pyo3::exceptions::PyImportError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule};
+
+/// LlamaIndex indexes, saved as the dictionary form of their `StorageContext`
+mod llamaindex {
+    use super::*;
+
+    /// Import `llama_index.core`, explaining how to install it when missing
+    fn llama_index_core(py: Python<'_>) -> PyResult<Bound<'_, PyModule>> {
+        py.import("llama_index.core").map_err(|_| {
+            PyImportError::new_err(
+                "persist.integrations.llamaindex requires LlamaIndex: pip install llama-index-core",
+            )
+        })
+    }
+
+    /// Save a LlamaIndex index as a snapshot
+    ///
+    /// The index's storage context must be serializable with `to_dict()`, as the
+    /// default in-memory stores are. The snapshot metadata records the framework as
+    /// "llamaindex" with the installed version.
+    ///
+    /// # Arguments
+    /// * `index` - The index to save
+    /// * `path` - Storage path/key for the snapshot
+    /// * `**kwargs` - Metadata, storage and compression options of `persist.snapshot_json`
+    #[pyfunction]
+    #[pyo3(signature = (index, path, **kwargs))]
+    pub(super) fn snapshot_index(
+        index: &Bound<'_, PyAny>,
+        path: &str,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        let py = index.py();
+        let version: String = llama_index_core(py)?.getattr("__version__")?.extract()?;
+
+        let state = PyDict::new(py);
+        state.set_item("index_id", index.getattr("index_id")?)?;
+        state.set_item(
+            "storage_context",
+            index.getattr("storage_context")?.call_method0("to_dict")?,
+        )?;
+
+        let kwargs = match kwargs {
+            Some(kwargs) => kwargs.copy()?,
+            None => PyDict::new(py),
+        };
+        kwargs.set_item("framework", ("llamaindex", version))?;
+        wrap_pyfunction!(crate::snapshot_json, py)?.call((state, path), Some(&kwargs))?;
+        Ok(())
+    }
+
+    /// Restore a LlamaIndex index saved with `snapshot_index`
+    ///
+    /// Components that are not part of the storage context, such as the embedding
+    /// model, come from LlamaIndex's global `Settings` as usual.
+    ///
+    /// # Arguments
+    /// * `path` - Storage path/key of the snapshot
+    /// * `**kwargs` - Storage options of `persist.restore_json`
+    #[pyfunction]
+    #[pyo3(signature = (path, **kwargs))]
+    pub(super) fn restore_index(
+        py: Python<'_>,
+        path: &str,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let core = llama_index_core(py)?;
+        let state = wrap_pyfunction!(crate::restore_json, py)?.call((path,), kwargs)?;
+
+        let storage_context = core
+            .getattr("StorageContext")?
+            .call_method1("from_dict", (state.get_item("storage_context")?,))?;
+        let load_kwargs = PyDict::new(py);
+        load_kwargs.set_item("index_id", state.get_item("index_id")?)?;
+        let index = core
+            .getattr("load_index_from_storage")?
+            .call((storage_context,), Some(&load_kwargs))?;
+        Ok(index.unbind())
+    }
+
+    pub(super) fn register(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+        let module = PyModule::new(parent.py(), "llamaindex")?;
+        module.add_function(wrap_pyfunction!(snapshot_index, &module)?)?;
+        module.add_function(wrap_pyfunction!(restore_index, &module)?)?;
+        super::add_submodule(parent, &module, "persist.integrations.llamaindex")
+    }
+}
+
+/// LangGraph checkpoint saver, implemented in Python on top of `PersistClient`
+///
+/// The classes subclass LangGraph's `BaseCheckpointSaver`, so they are defined by
+/// Python source compiled into the extension. It runs on first attribute access
+/// through the module's `__getattr__`, which keeps `import persist` free of LangGraph.
+mod langgraph {
+    use super::*;
+    use pyo3::exceptions::PyAttributeError;
+    use pyo3::ffi::c_str;
+    use pyo3::sync::GILOnceCell;
+
+    /// Names defined by the embedded source that the module exposes
+    const EXPORTS: &[&str] = &["PersistCheckpointer"];
+
+    static SOURCE_MODULE: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+
+    /// Resolve the checkpointer classes, running the embedded source on first use
+    #[pyfunction]
+    fn __getattr__(py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        if !EXPORTS.contains(&name) {
+            return Err(PyAttributeError::new_err(format!(
+                "module 'persist.integrations.langgraph' has no attribute '{name}'"
+            )));
+        }
+        let module = SOURCE_MODULE.get_or_try_init(py, || {
+            PyModule::from_code(
+                py,
+                c_str!(include_str!("integrations/langgraph.py")),
+                c_str!("persist/integrations/langgraph.py"),
+                c_str!("persist.integrations._langgraph"),
+            )
+            .map(Bound::unbind)
+        })?;
+        Ok(module.bind(py).getattr(name)?.unbind())
+    }
+
+    pub(super) fn register(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+        let module = PyModule::new(parent.py(), "langgraph")?;
+        module.add_function(wrap_pyfunction!(__getattr__, &module)?)?;
+        module.add("__all__", EXPORTS.to_vec())?;
+        super::add_submodule(parent, &module, "persist.integrations.langgraph")
+    }
+}
+
+/// Add `module` to `parent` and make it importable as `qualified_name`
+fn add_submodule(
+    parent: &Bound<'_, PyModule>,
+    module: &Bound<'_, PyModule>,
+    qualified_name: &str,
+) -> PyResult<()> {
+    parent.add_submodule(module)?;
+    // Extension submodules are not importable by dotted name unless registered
+    parent
+        .py()
+        .import("sys")?
+        .getattr("modules")?
+        .set_item(qualified_name, module)
+}
+
+/// Add the `persist.integrations` package to the `persist` module
+pub(crate) fn register(persist: &Bound<'_, PyModule>) -> PyResult<()> {
+    let integrations = PyModule::new(persist.py(), "integrations")?;
+    llamaindex::register(&integrations)?;
+    langgraph::register(&integrations)?;
+    add_submodule(persist, &integrations, "persist.integrations")
+}
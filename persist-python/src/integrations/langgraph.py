@@ -0,0 +1,304 @@
+"""LangGraph checkpoint saver that stores checkpoints as Persist snapshots.
+
+This source is compiled into the extension and executed the first time a class of
+``persist.integrations.langgraph`` is accessed, so importing ``persist`` does not
+import LangGraph.
+
+Layout under ``prefix``::
+
+    <thread_id>/ns-<checkpoint_ns>/checkpoints/<checkpoint_id>.json.gz
+    <thread_id>/ns-<checkpoint_ns>/writes/<checkpoint_id>/<task_id>.json.gz
+
+Path segments are percent-encoded. Each snapshot uses the thread id as its
+``agent_id`` and the checkpoint namespace as its ``session_id``, and carries the
+checkpoint config in its tags. Checkpoints and writes are encoded with the saver's
+``serde`` and stored as base64, so they round-trip byte for byte.
+"""
+
+import asyncio
+import base64
+import functools
+from urllib.parse import quote
+
+import persist
+
+try:
+    from langgraph.checkpoint.base import (
+        WRITES_IDX_MAP,
+        BaseCheckpointSaver,
+        CheckpointTuple,
+        get_checkpoint_id,
+    )
+except ImportError as e:
+    raise ImportError(
+        "persist.integrations.langgraph requires LangGraph: pip install langgraph"
+    ) from e
+
+try:
+    from langgraph.checkpoint.base import get_checkpoint_metadata
+except ImportError:  # langgraph-checkpoint before 2.0.13
+
+    def get_checkpoint_metadata(config, metadata):
+        return metadata
+
+
+try:
+    from importlib.metadata import version as _package_version
+
+    LANGGRAPH_VERSION = _package_version("langgraph")
+except Exception:
+    LANGGRAPH_VERSION = "unknown"
+
+
+def _segment(value):
+    """Encode a config value as a single path segment"""
+    return quote(str(value), safe="").replace(".", "%2E")
+
+
+def _config(thread_id, checkpoint_ns, checkpoint_id):
+    return {
+        "configurable": {
+            "thread_id": thread_id,
+            "checkpoint_ns": checkpoint_ns,
+            "checkpoint_id": checkpoint_id,
+        }
+    }
+
+
+class PersistCheckpointer(BaseCheckpointSaver):
+    """LangGraph checkpoint saver backed by Persist snapshots
+
+    Works with any storage backend of ``persist.PersistClient``, synchronously
+    (``graph.invoke``) and asynchronously (``graph.ainvoke``); async calls run on
+    the event loop's default executor.
+
+    Args:
+        prefix: Path/key prefix for all checkpoints, e.g. "checkpoints/"
+        client: An existing ``persist.PersistClient`` to store checkpoints with
+        serde: LangGraph serializer (default: LangGraph's ``JsonPlusSerializer``)
+        **storage_kwargs: ``persist.PersistClient`` options, when no client is given
+    """
+
+    def __init__(self, *, prefix="", client=None, serde=None, **storage_kwargs):
+        super().__init__(serde=serde)
+        if client is not None and storage_kwargs:
+            raise persist.PersistConfigurationError(
+                "Pass either client or storage options to PersistCheckpointer, not both"
+            )
+        self.client = client if client is not None else persist.PersistClient(**storage_kwargs)
+        self.prefix = prefix
+
+    # Paths
+
+    def _thread_prefix(self, thread_id):
+        return f"{self.prefix}{_segment(thread_id)}/"
+
+    def _namespace_prefix(self, thread_id, checkpoint_ns):
+        return f"{self._thread_prefix(thread_id)}ns-{_segment(checkpoint_ns)}/"
+
+    def _checkpoint_path(self, thread_id, checkpoint_ns, checkpoint_id):
+        prefix = self._namespace_prefix(thread_id, checkpoint_ns)
+        return f"{prefix}checkpoints/{_segment(checkpoint_id)}.json.gz"
+
+    def _writes_prefix(self, thread_id, checkpoint_ns, checkpoint_id):
+        prefix = self._namespace_prefix(thread_id, checkpoint_ns)
+        return f"{prefix}writes/{_segment(checkpoint_id)}/"
+
+    # Encoding
+
+    def _dump(self, value):
+        type_, data = self.serde.dumps_typed(value)
+        return [type_, base64.b64encode(data).decode("ascii")]
+
+    def _load(self, typed):
+        type_, data = typed
+        return self.serde.loads_typed((type_, base64.b64decode(data)))
+
+    # Storage
+
+    def _save(self, state, path, snapshot_index, tags):
+        self.client.snapshot_json(
+            state,
+            path,
+            agent_id=str(tags["thread_id"]),
+            session_id=tags["checkpoint_ns"] or "default_session",
+            snapshot_index=snapshot_index,
+            framework=("langgraph", LANGGRAPH_VERSION),
+            tags=tags,
+        )
+
+    def _checkpoint_entries(self, prefix, tags):
+        """Checkpoint snapshots under `prefix`, newest checkpoint id first"""
+        entries = self.client.list(
+            prefix, tags={"langgraph": "checkpoint", **tags}, sort="none"
+        )
+        return sorted(entries, key=lambda entry: entry["tags"]["checkpoint_id"], reverse=True)
+
+    def _pending_writes(self, thread_id, checkpoint_ns, checkpoint_id):
+        prefix = self._writes_prefix(thread_id, checkpoint_ns, checkpoint_id)
+        writes = []
+        for entry in self.client.list(prefix, tags={"langgraph": "writes"}, sort="none"):
+            state = self.client.restore_json(entry["path"])
+            for write in state["writes"]:
+                key = (write["task_path"], state["task_id"], write["idx"])
+                value = self._load(write["value"])
+                writes.append((key, (state["task_id"], write["channel"], value)))
+        return [write for _, write in sorted(writes, key=lambda item: item[0])]
+
+    def _load_tuple(self, path):
+        state = self.client.restore_json(path)
+        thread_id = state["thread_id"]
+        checkpoint_ns = state["checkpoint_ns"]
+        checkpoint_id = state["checkpoint_id"]
+        parent_checkpoint_id = state["parent_checkpoint_id"]
+        return CheckpointTuple(
+            config=_config(thread_id, checkpoint_ns, checkpoint_id),
+            checkpoint=self._load(state["checkpoint"]),
+            metadata=self._load(state["metadata"]),
+            parent_config=(
+                _config(thread_id, checkpoint_ns, parent_checkpoint_id)
+                if parent_checkpoint_id
+                else None
+            ),
+            pending_writes=self._pending_writes(thread_id, checkpoint_ns, checkpoint_id),
+        )
+
+    # BaseCheckpointSaver
+
+    def get_tuple(self, config):
+        configurable = config["configurable"]
+        thread_id = configurable["thread_id"]
+        checkpoint_ns = configurable.get("checkpoint_ns", "")
+        checkpoint_id = get_checkpoint_id(config)
+
+        if checkpoint_id:
+            path = self._checkpoint_path(thread_id, checkpoint_ns, checkpoint_id)
+            if not self.client.exists(path):
+                return None
+        else:
+            entries = self._checkpoint_entries(
+                self._namespace_prefix(thread_id, checkpoint_ns), {}
+            )
+            if not entries:
+                return None
+            path = entries[0]["path"]
+        return self._load_tuple(path)
+
+    def list(self, config, *, filter=None, before=None, limit=None):
+        prefix = self.prefix
+        checkpoint_id = None
+        if config is not None:
+            configurable = config["configurable"]
+            thread_id = configurable["thread_id"]
+            checkpoint_ns = configurable.get("checkpoint_ns")
+            if checkpoint_ns is None:
+                prefix = self._thread_prefix(thread_id)
+            else:
+                prefix = self._namespace_prefix(thread_id, checkpoint_ns)
+            checkpoint_id = get_checkpoint_id(config)
+        before_id = get_checkpoint_id(before) if before else None
+
+        for entry in self._checkpoint_entries(prefix, {}):
+            entry_id = entry["tags"]["checkpoint_id"]
+            if checkpoint_id and entry_id != checkpoint_id:
+                continue
+            if before_id and entry_id >= before_id:
+                continue
+            checkpoint = self._load_tuple(entry["path"])
+            if filter and any(checkpoint.metadata.get(k) != v for k, v in filter.items()):
+                continue
+            if limit is not None:
+                if limit <= 0:
+                    break
+                limit -= 1
+            yield checkpoint
+
+    def put(self, config, checkpoint, metadata, new_versions):
+        configurable = config["configurable"]
+        thread_id = configurable["thread_id"]
+        checkpoint_ns = configurable.get("checkpoint_ns", "")
+        parent_checkpoint_id = configurable.get("checkpoint_id")
+
+        state = {
+            "thread_id": thread_id,
+            "checkpoint_ns": checkpoint_ns,
+            "checkpoint_id": checkpoint["id"],
+            "parent_checkpoint_id": parent_checkpoint_id,
+            "checkpoint": self._dump(checkpoint),
+            "metadata": self._dump(get_checkpoint_metadata(config, metadata)),
+        }
+        tags = {
+            "langgraph": "checkpoint",
+            "thread_id": str(thread_id),
+            "checkpoint_ns": checkpoint_ns,
+            "checkpoint_id": checkpoint["id"],
+        }
+        if parent_checkpoint_id:
+            tags["parent_checkpoint_id"] = parent_checkpoint_id
+
+        path = self._checkpoint_path(thread_id, checkpoint_ns, checkpoint["id"])
+        self._save(state, path, "auto", tags)
+        return _config(thread_id, checkpoint_ns, checkpoint["id"])
+
+    def put_writes(self, config, writes, task_id, task_path=""):
+        configurable = config["configurable"]
+        thread_id = configurable["thread_id"]
+        checkpoint_ns = configurable.get("checkpoint_ns", "")
+        checkpoint_id = configurable["checkpoint_id"]
+        path = self._writes_prefix(thread_id, checkpoint_ns, checkpoint_id) + (
+            f"{_segment(task_id)}.json.gz"
+        )
+
+        stored = {}
+        if self.client.exists(path):
+            stored = {write["idx"]: write for write in self.client.restore_json(path)["writes"]}
+        for idx, (channel, value) in enumerate(writes):
+            idx = WRITES_IDX_MAP.get(channel, idx)
+            # Regular writes are only recorded once; special channels are replaced
+            if idx >= 0 and idx in stored:
+                continue
+            stored[idx] = {
+                "idx": idx,
+                "channel": channel,
+                "value": self._dump(value),
+                "task_path": task_path,
+            }
+
+        state = {"task_id": task_id, "writes": [stored[idx] for idx in sorted(stored)]}
+        tags = {
+            "langgraph": "writes",
+            "thread_id": str(thread_id),
+            "checkpoint_ns": checkpoint_ns,
+            "checkpoint_id": checkpoint_id,
+            "task_id": task_id,
+        }
+        self._save(state, path, None, tags)
+
+    def delete_thread(self, thread_id):
+        for entry in self.client.list(self._thread_prefix(thread_id), sort="none"):
+            self.client.delete(entry["path"])
+
+    # Async variants, run on the event loop's default executor
+
+    async def _run(self, function, *args, **kwargs):
+        loop = asyncio.get_running_loop()
+        return await loop.run_in_executor(None, functools.partial(function, *args, **kwargs))
+
+    async def aget_tuple(self, config):
+        return await self._run(self.get_tuple, config)
+
+    async def alist(self, config, *, filter=None, before=None, limit=None):
+        checkpoints = await self._run(
+            lambda: list(self.list(config, filter=filter, before=before, limit=limit))
+        )
+        for checkpoint in checkpoints:
+            yield checkpoint
+
+    async def aput(self, config, checkpoint, metadata, new_versions):
+        return await self._run(self.put, config, checkpoint, metadata, new_versions)
+
+    async def aput_writes(self, config, writes, task_id, task_path=""):
+        return await self._run(self.put_writes, config, writes, task_id, task_path)
+
+    async def adelete_thread(self, thread_id):
+        return await self._run(self.delete_thread, thread_id)
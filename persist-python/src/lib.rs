@@ -24,11 +24,44 @@ restored_agent = persist.restore("agent1/snapshot.json.gz",
 ```
 */
 
-use persist_core::{create_engine_from_config, PersistError, SnapshotMetadata, StorageConfig};
+mod auto_snapshot;
+mod diff;
+mod integrations;
+mod logging;
+mod metadata;
+mod pickling;
+mod progress;
+mod verification;
+
+use auto_snapshot::AutoSnapshotter;
+use chrono::{DateTime, Duration, Utc};
+use diff::DiffFormat;
+use metadata::PySnapshotMetadata;
+use persist_core::metadata::{
+    CompatibilityMode, FrameworkCompatPolicy, FrameworkRequirement, METADATA_FORMAT_MINOR_VERSION,
+    METADATA_FORMAT_VERSION,
+};
+use persist_core::retention;
+use persist_core::{
+    available_algorithms, available_backends, compressor_for, create_storage_from_config,
+    CallOptions, PersistError, RetentionPolicy, S3Credentials, SensitiveField, SnapshotDiff,
+    SnapshotEngine, SnapshotEngineInterface, SnapshotMetadata, SnapshotQuery, SnapshotSummary,
+    SortOrder, StorageBackend, StorageConfig,
+};
+use progress::with_progress;
 use pyo3::create_exception;
-use pyo3::exceptions::{PyException, PyIOError};
+use pyo3::exceptions::{
+    PyException, PyFileNotFoundError, PyIOError, PyImportError, PyTypeError, PyUserWarning,
+    PyValueError,
+};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyModule};
+use pyo3::types::{PyDict, PyFrozenSet, PyList, PyModule, PyString, PyTuple};
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock};
+use verification::PyVerificationResult;
 
 // Define custom Python exception types
 create_exception!(
@@ -61,6 +94,24 @@ create_exception!(
     PyPersistError,
     "Compression/decompression failed"
 );
+create_exception!(
+    persist_python,
+    PyPersistValidationError,
+    PyPersistError,
+    "Invalid input, such as a path that escapes base_dir"
+);
+create_exception!(
+    persist_python,
+    PyPersistFrameworkMismatchError,
+    PyPersistError,
+    "Snapshot was created with an incompatible agent framework version"
+);
+create_exception!(
+    persist_python,
+    PyPersistCompatibilityError,
+    PyPersistFrameworkMismatchError,
+    "Snapshot was created with another LangChain version than the installed one"
+);
 
 /// Convert a Rust PersistError to a Python exception
 fn convert_error(err: PersistError) -> PyErr {
@@ -80,15 +131,38 @@ fn convert_error(err: PersistError) -> PyErr {
         PersistError::InvalidFormat(msg) => {
             PyPersistError::new_err(format!("Invalid snapshot format: {msg}"))
         }
+        PersistError::NeedsMigration { found, current } => PyPersistError::new_err(format!(
+            "Snapshot format version {found} needs migration to {current} before it can be read"
+        )),
         PersistError::MissingMetadata(field) => {
             PyPersistError::new_err(format!("Missing required metadata field: {field}"))
         }
         PersistError::Storage(msg) => {
             PyPersistError::new_err(format!("Storage operation failed: {msg}"))
         }
+        PersistError::Throttled { message, .. } => {
+            PyPersistError::new_err(format!("Storage throttled: {message}"))
+        }
         PersistError::Validation(msg) => {
-            PyPersistError::new_err(format!("Validation error: {msg}"))
+            PyPersistValidationError::new_err(format!("Validation error: {msg}"))
+        }
+        PersistError::FrameworkMismatch { expected, actual } => {
+            PyPersistFrameworkMismatchError::new_err(format!(
+                "Framework mismatch: snapshot was created with {actual}, but {expected} is required"
+            ))
         }
+        PersistError::IndexOutOfOrder {
+            agent_id,
+            session_id,
+            index,
+            latest,
+        } => PyPersistError::new_err(format!(
+            "Snapshot index {index} for {agent_id}/{session_id} is not after the latest existing index {latest}"
+        )),
+        PersistError::Encryption(msg) => {
+            PyPersistError::new_err(format!("Metadata encryption error: {msg}"))
+        }
+        PersistError::Aborted(msg) => PyPersistError::new_err(format!("Operation aborted: {msg}")),
 
         // S3-specific errors
         PersistError::S3UploadError {
@@ -106,7 +180,6 @@ fn convert_error(err: PersistError) -> PyErr {
             "S3 download failed (bucket: {bucket}, key: {key}): {source}"
         )),
         PersistError::S3NotFound { bucket, key } => {
-            use pyo3::exceptions::PyFileNotFoundError;
             PyFileNotFoundError::new_err(format!(
                 "Snapshot not found in S3 (bucket: {bucket}, key: {key})"
             ))
@@ -117,353 +190,3539 @@ fn convert_error(err: PersistError) -> PyErr {
                 "Access denied to S3 bucket: {bucket}. Check your credentials and permissions."
             ))
         }
+        PersistError::GcsNotFound { bucket, key } => {
+            PyFileNotFoundError::new_err(format!(
+                "Snapshot not found in GCS (bucket: {bucket}, key: {key})"
+            ))
+        }
+        PersistError::GcsAccessDenied { bucket, key } => {
+            use pyo3::exceptions::PyPermissionError;
+            PyPermissionError::new_err(format!(
+                "Access denied to GCS object (bucket: {bucket}, key: {key}). Check your IAM permissions."
+            ))
+        }
         PersistError::S3Configuration(msg) => {
             PyPersistConfigurationError::new_err(format!("S3 configuration error: {msg}"))
         }
     }
 }
 
+/// Storage keyword arguments shared by the module-level functions and `PersistClient`
+///
+/// They reach each function as `**storage` and are collected by `from_kwargs`, so the
+/// list below is the only place that names them.
+#[derive(Default)]
+struct StorageOptions {
+    storage_mode: Option<String>,
+    s3_bucket: Option<String>,
+    s3_region: Option<String>,
+    s3_endpoint_url: Option<String>,
+    s3_access_key_id: Option<String>,
+    s3_secret_access_key: Option<String>,
+    s3_session_token: Option<String>,
+    s3_force_path_style: bool,
+    gcs_bucket: Option<String>,
+    gcs_prefix: Option<String>,
+    gcs_credentials_path: Option<PathBuf>,
+    base_dir: Option<PathBuf>,
+    durable_writes: bool,
+    file_permissions: Option<u32>,
+}
+
+impl StorageOptions {
+    /// Names of the storage keyword arguments
+    const KEYWORDS: [&'static str; 14] = [
+        "storage_mode",
+        "s3_bucket",
+        "s3_region",
+        "s3_endpoint_url",
+        "s3_access_key_id",
+        "s3_secret_access_key",
+        "s3_session_token",
+        "s3_force_path_style",
+        "gcs_bucket",
+        "gcs_prefix",
+        "gcs_credentials_path",
+        "base_dir",
+        "durable_writes",
+        "file_permissions",
+    ];
+
+    /// Collect the storage arguments from the `**storage` keywords of a call
+    ///
+    /// A keyword that is not a storage argument raises `TypeError`, like any unexpected
+    /// keyword, and `None` counts as not given.
+    fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let Some(kwargs) = kwargs else {
+            return Ok(Self::default());
+        };
+        for key in kwargs.keys() {
+            let key = key.extract::<String>()?;
+            if !Self::KEYWORDS.contains(&key.as_str()) {
+                return Err(PyTypeError::new_err(format!(
+                    "got an unexpected keyword argument '{key}'"
+                )));
+            }
+        }
+        Ok(Self {
+            storage_mode: keyword(kwargs, "storage_mode")?,
+            s3_bucket: keyword(kwargs, "s3_bucket")?,
+            s3_region: keyword(kwargs, "s3_region")?,
+            s3_endpoint_url: keyword(kwargs, "s3_endpoint_url")?,
+            s3_access_key_id: keyword(kwargs, "s3_access_key_id")?,
+            s3_secret_access_key: keyword(kwargs, "s3_secret_access_key")?,
+            s3_session_token: keyword(kwargs, "s3_session_token")?,
+            s3_force_path_style: keyword(kwargs, "s3_force_path_style")?.unwrap_or(false),
+            gcs_bucket: keyword(kwargs, "gcs_bucket")?,
+            gcs_prefix: keyword(kwargs, "gcs_prefix")?,
+            gcs_credentials_path: keyword(kwargs, "gcs_credentials_path")?,
+            base_dir: keyword(kwargs, "base_dir")?,
+            durable_writes: keyword(kwargs, "durable_writes")?.unwrap_or(false),
+            file_permissions: keyword(kwargs, "file_permissions")?,
+        })
+    }
+}
+
+/// Value of keyword argument `name`, with type errors naming the argument as pyo3 does
+fn keyword<'py, T: FromPyObject<'py>>(
+    kwargs: &Bound<'py, PyDict>,
+    name: &str,
+) -> PyResult<Option<T>> {
+    match kwargs.get_item(name)? {
+        Some(value) if !value.is_none() => value.extract().map(Some).map_err(|e| {
+            if e.is_instance_of::<PyTypeError>(kwargs.py()) {
+                PyTypeError::new_err(format!("argument '{name}': {}", e.value(kwargs.py())))
+            } else {
+                e
+            }
+        }),
+        _ => Ok(None),
+    }
+}
+
+/// Environment variable read when `storage_mode` is not given
+const STORAGE_MODE_ENV: &str = "PERSIST_STORAGE_MODE";
+/// Environment variable read when `s3_bucket` is not given
+const S3_BUCKET_ENV: &str = "PERSIST_S3_BUCKET";
+/// Environment variable read when `s3_region` is not given
+const S3_REGION_ENV: &str = "PERSIST_S3_REGION";
+/// Environment variable read when `base_dir` is not given in local mode
+const BASE_DIR_ENV: &str = "PERSIST_BASE_DIR";
+/// Environment variable read when `compression` is not given
+const COMPRESSION_ENV: &str = "PERSIST_COMPRESSION";
+
+/// Value of a default-providing environment variable; unset and empty count as absent
+fn env_default(name: &str) -> PyResult<Option<String>> {
+    match std::env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(std::env::VarError::NotUnicode(_)) => Err(PyPersistConfigurationError::new_err(
+            format!("Invalid {name}: the value is not valid UTF-8"),
+        )),
+    }
+}
+
 /// Create storage configuration from Python parameters
-fn create_storage_config(
-    storage_mode: Option<&str>,
-    s3_bucket: Option<&str>,
-    s3_region: Option<&str>,
-) -> PyResult<StorageConfig> {
-    let mode = storage_mode.unwrap_or("local").to_lowercase();
-
-    match mode.as_str() {
-        "local" => Ok(StorageConfig::default_local()),
+///
+/// Arguments that are not given fall back to the `PERSIST_*` environment variables
+/// above, then to the built-in defaults.
+fn create_storage_config(storage: Option<&Bound<'_, PyDict>>) -> PyResult<StorageConfig> {
+    let options = StorageOptions::from_kwargs(storage)?;
+    let mode = match options.storage_mode.as_deref() {
+        Some(mode) => mode.to_lowercase(),
+        None => match env_default(STORAGE_MODE_ENV)? {
+            Some(mode) => {
+                let mode = mode.to_lowercase();
+                if !matches!(mode.as_str(), "local" | "s3" | "gcs") {
+                    return Err(PyPersistConfigurationError::new_err(format!(
+                        "Invalid {STORAGE_MODE_ENV} '{mode}'. Must be 'local', 's3' or 'gcs'"
+                    )));
+                }
+                mode
+            }
+            None => "local".to_string(),
+        },
+    };
+
+    let mut config = match mode.as_str() {
+        "local" => StorageConfig::default_local(),
         "s3" => {
-            let mut config = if let Some(bucket) = s3_bucket {
-                StorageConfig::s3_with_bucket(bucket.to_string())
+            let bucket = match options.s3_bucket {
+                Some(bucket) => Some(bucket),
+                None => env_default(S3_BUCKET_ENV)?,
+            };
+            let mut config = if let Some(bucket) = bucket {
+                StorageConfig::s3_with_bucket(bucket)
             } else {
                 StorageConfig::default_s3()
             };
 
-            if let Some(region) = s3_region {
-                config.s3_region = Some(region.to_string());
+            // Explicit settings take precedence over the environment variables
+            config.s3_region = match options.s3_region {
+                Some(region) => Some(region),
+                None => env_default(S3_REGION_ENV)?,
+            };
+            config.s3_endpoint_url = options.s3_endpoint_url;
+            config.s3_force_path_style = options.s3_force_path_style;
+            config.s3_credentials = match (options.s3_access_key_id, options.s3_secret_access_key) {
+                (Some(access_key_id), Some(secret_access_key)) => {
+                    let credentials = S3Credentials::new(access_key_id, secret_access_key);
+                    Some(match options.s3_session_token {
+                        Some(token) => credentials.with_session_token(token),
+                        None => credentials,
+                    })
+                }
+                (None, None) if options.s3_session_token.is_none() => None,
+                _ => {
+                    return Err(PyPersistConfigurationError::new_err(
+                        "s3_access_key_id and s3_secret_access_key must be given together \
+                         (s3_session_token requires both)",
+                    ))
+                }
+            };
+
+            config
+        }
+        "gcs" => {
+            if !cfg!(feature = "gcs") {
+                return Err(PyImportError::new_err(
+                    "GCS storage is not available: this build of persist was compiled without \
+                     the 'gcs' feature (rebuild with `maturin build --features gcs`)",
+                ));
             }
 
-            Ok(config)
+            let mut config = if let Some(bucket) = options.gcs_bucket {
+                StorageConfig::gcs_with_bucket(bucket)
+            } else {
+                StorageConfig::default_gcs()
+            };
+            config.gcs_prefix = options.gcs_prefix;
+            config.gcs_credentials_path = options.gcs_credentials_path;
+
+            config
         }
-        _ => Err(PyIOError::new_err(format!(
-            "Invalid storage_mode '{mode}'. Must be 'local' or 's3'"
-        ))),
+        _ => {
+            return Err(PyIOError::new_err(format!(
+                "Invalid storage_mode '{mode}'. Must be 'local', 's3' or 'gcs'"
+            )))
+        }
+    };
+
+    if config.backend == StorageBackend::Local {
+        config.local_base_path = match options.base_dir {
+            Some(base_dir) => Some(base_dir),
+            None => env_default(BASE_DIR_ENV)?.map(PathBuf::from),
+        };
+        config.local_durable_writes = options.durable_writes;
+        config.local_file_permissions = options.file_permissions;
+    } else if options.base_dir.is_some()
+        || options.durable_writes
+        || options.file_permissions.is_some()
+    {
+        return Err(PyPersistConfigurationError::new_err(
+            "base_dir, durable_writes and file_permissions only apply to local storage",
+        ));
     }
+
+    Ok(config)
 }
 
-/// Save an agent snapshot with configurable storage backend
-///
-/// This function serializes a LangChain agent (or other compatible object) to a compressed
-/// snapshot file. Supports both local filesystem and Amazon S3 storage backends.
-///
-/// # Arguments
-/// * `agent` - The agent object to snapshot (must support LangChain serialization)
-/// * `path` - Storage path/key for the snapshot
-/// * `agent_id` - Optional unique identifier for the agent (default: "default_agent")
-/// * `session_id` - Optional session identifier (default: "default_session")
-/// * `snapshot_index` - Optional sequence number for this snapshot (default: 0)
-/// * `description` - Optional human-readable description of the snapshot
-/// * `storage_mode` - Storage backend: "local" or "s3" (default: "local")
-/// * `s3_bucket` - S3 bucket name (required for S3 mode)
-/// * `s3_region` - S3 region (optional, uses AWS environment default)
-///
-/// # Returns
-/// None on success
-///
-/// # Raises
-/// * IOError - If saving fails, JSON serialization fails, or integrity check fails
-///
-/// # Example
-/// ```python
-/// import persist
-/// from langchain.chains import ConversationChain
-///
-/// # Local storage
-/// persist.snapshot(agent, "snapshots/agent1.json.gz")
-///
-/// # S3 storage
-/// persist.snapshot(agent, "agent1/session1/snapshot.json.gz",
-///                 storage_mode="s3",
-///                 s3_bucket="my-snapshots-bucket",
-///                 agent_id="conversation_agent")
-/// ```
-#[pyfunction]
-#[pyo3(signature = (agent, path, agent_id="default_agent", session_id="default_session", snapshot_index=0, description=None, storage_mode=None, s3_bucket=None, s3_region=None))]
-#[allow(clippy::too_many_arguments)]
-fn snapshot(
-    py: Python<'_>,
-    agent: &Bound<'_, PyAny>,
-    path: &str,
-    agent_id: &str,
-    session_id: &str,
-    snapshot_index: u64,
-    description: Option<&str>,
-    storage_mode: Option<&str>,
-    s3_bucket: Option<&str>,
-    s3_region: Option<&str>,
-) -> PyResult<()> {
-    // Import LangChain's dump function
-    let langchain_load = py.import("langchain_core.load")
-        .or_else(|_| py.import("langchain.load"))  // Fallback for older versions
-        .map_err(|_| PyIOError::new_err("Could not import langchain_core.load or langchain.load. Please ensure LangChain is installed."))?;
+/// Detect the installed LangChain version, if any
+fn langchain_version(py: Python<'_>) -> Option<String> {
+    ["langchain_core", "langchain"].iter().find_map(|module| {
+        py.import(*module)
+            .and_then(|m| m.getattr("__version__"))
+            .and_then(|v| v.extract::<String>())
+            .ok()
+    })
+}
 
-    let dumps_func = langchain_load.getattr("dumps").map_err(|_| {
-        PyIOError::new_err("Could not find dumps function in LangChain load module")
-    })?;
+/// Number of storage engines created in this process
+///
+/// Exposed to Python as `_engines_created()` so tests can check that a client reuses its
+/// engine.
+static ENGINES_CREATED: AtomicUsize = AtomicUsize::new(0);
 
-    // Serialize the agent to JSON string using LangChain's dumps
-    let json_obj = dumps_func.call1((agent,)).map_err(|e| {
-        PyIOError::new_err(format!(
-            "Failed to serialize agent with LangChain dumps: {e}"
-        ))
-    })?;
+/// Snapshot engine shared by the threads of a `PersistClient`
+type Engine = dyn SnapshotEngineInterface + Send + Sync;
 
-    let agent_json: String = json_obj.extract().map_err(|e| {
-        PyIOError::new_err(format!(
-            "Failed to extract JSON string from LangChain dumps result: {e}"
-        ))
-    })?;
+/// State JSON of an agent, with its `(framework, version)` when known and the payload kind
+type SerializedAgent = (String, Option<(String, String)>, Payload);
 
-    // Create metadata
-    let mut metadata = SnapshotMetadata::new(agent_id, session_id, snapshot_index);
-    if let Some(desc) = description {
-        metadata = metadata.with_description(desc);
+/// Compression algorithm name for a `compression` argument, falling back to
+/// `PERSIST_COMPRESSION`, then gzip
+fn resolve_compression(compression: Option<&str>) -> PyResult<String> {
+    if let Some(compression) = compression {
+        return Ok(compression.to_lowercase());
     }
+    match env_default(COMPRESSION_ENV)? {
+        Some(compression) => {
+            let compression = compression.to_lowercase();
+            if !available_algorithms().contains(&compression.as_str()) {
+                return Err(PyPersistConfigurationError::new_err(format!(
+                    "Invalid {COMPRESSION_ENV} '{compression}'. Must be one of: {}",
+                    available_algorithms().join(", ")
+                )));
+            }
+            Ok(compression)
+        }
+        None => Ok("gzip".to_string()),
+    }
+}
 
-    // Create storage configuration
-    let config = create_storage_config(storage_mode, s3_bucket, s3_region)?;
-
-    // Create appropriate engine based on storage configuration
-    let engine = create_engine_from_config(config).map_err(convert_error)?;
+/// Create an engine for a storage configuration and a compression algorithm name
+fn create_engine(
+    config: StorageConfig,
+    compression: Option<&str>,
+    compression_level: Option<i32>,
+    strict_format: bool,
+) -> PyResult<Box<Engine>> {
+    let compression = resolve_compression(compression)?;
+    let compressor = compressor_for(&compression, compression_level)
+        .map_err(|e| PyPersistConfigurationError::new_err(e.to_string()))?;
+    let storage = create_storage_from_config(config).map_err(convert_error)?;
+    let mut engine = SnapshotEngine::new(storage, compressor);
+    if !strict_format {
+        engine.set_compatibility_mode(CompatibilityMode::Warn);
+    }
+    ENGINES_CREATED.fetch_add(1, Ordering::Relaxed);
+    Ok(Box::new(engine))
+}
 
-    // Save snapshot
-    let _saved_metadata = engine
-        .save_snapshot(&agent_json, &metadata, path)
-        .map_err(convert_error)?;
+/// Number of storage engines created so far in this process
+#[pyfunction]
+fn _engines_created() -> usize {
+    ENGINES_CREATED.load(Ordering::Relaxed)
+}
 
-    Ok(())
+/// Parse a Python `snapshot_index` argument: an integer, or "auto" (returned as `None`)
+/// to derive the next index from snapshots stored in the same directory
+fn parse_snapshot_index(snapshot_index: &Bound<'_, PyAny>) -> PyResult<Option<u64>> {
+    match snapshot_index.extract::<String>() {
+        Ok(mode) if mode == "auto" => Ok(None),
+        Ok(mode) => Err(PyPersistConfigurationError::new_err(format!(
+            "Invalid snapshot_index '{mode}'. Must be a non-negative integer or 'auto'"
+        ))),
+        Err(_) => snapshot_index.extract::<u64>().map(Some),
+    }
 }
 
-/// Restore an agent snapshot with configurable storage backend
-///
-/// This function loads a compressed snapshot file and reconstructs the original agent
-/// object using LangChain's loads() function. Supports both local and S3 storage.
-///
-/// # Arguments
-/// * `path` - Storage path/key of the snapshot to restore
-/// * `secrets_map` - Optional dictionary of secrets/API keys for the restored agent
-/// * `storage_mode` - Storage backend: "local" or "s3" (default: "local")
-/// * `s3_bucket` - S3 bucket name (required for S3 mode)
-/// * `s3_region` - S3 region (optional, uses AWS environment default)
-///
-/// # Returns
-/// The restored agent object
-///
-/// # Raises
-/// * IOError - If loading fails, decompression fails, or integrity check fails
-///
-/// # Example
-/// ```python
-/// import persist
-/// import os
-///
-/// # Set up AWS credentials for S3 access
-/// os.environ["AWS_ACCESS_KEY_ID"] = "your-access-key"
-/// os.environ["AWS_SECRET_ACCESS_KEY"] = "your-secret-key"
-/// os.environ["AWS_REGION"] = "us-west-2"
-///
-/// # Restore from S3
-/// restored_agent = persist.restore("agent1/session1/snapshot.json.gz",
-///                                storage_mode="s3",
-///                                s3_bucket="my-snapshots-bucket")
-/// ```
-#[pyfunction]
-#[pyo3(signature = (path, secrets_map=None, storage_mode=None, s3_bucket=None, s3_region=None))]
-fn restore(
-    py: Python<'_>,
-    path: &str,
-    secrets_map: Option<&Bound<'_, PyDict>>,
-    storage_mode: Option<&str>,
-    s3_bucket: Option<&str>,
-    s3_region: Option<&str>,
-) -> PyResult<PyObject> {
-    // Create storage configuration
-    let config = create_storage_config(storage_mode, s3_bucket, s3_region)?;
+/// Default number of snapshots the batch functions work on concurrently
+const BATCH_PARALLELISM: usize = 8;
 
-    // Create appropriate engine based on storage configuration
-    let engine = create_engine_from_config(config).map_err(convert_error)?;
+/// Maximum number of tags on a snapshot saved from Python
+const MAX_TAGS: usize = 64;
+/// Maximum length of a tag key, in UTF-8 bytes
+const MAX_TAG_KEY_BYTES: usize = 128;
+/// Maximum length of a tag value, in UTF-8 bytes
+const MAX_TAG_VALUE_BYTES: usize = 256;
 
-    // Load snapshot
-    let (_metadata, agent_json) = engine.load_snapshot(path).map_err(convert_error)?;
+/// Parse a Python `tags` argument, checking types and size limits
+///
+/// Keys and values must be `str`; other types raise TypeError instead of being
+/// stringified. Violated limits raise ValueError.
+fn parse_tags(tags: Option<&Bound<'_, PyDict>>) -> PyResult<BTreeMap<String, String>> {
+    let Some(tags) = tags else {
+        return Ok(BTreeMap::new());
+    };
+    if tags.len() > MAX_TAGS {
+        return Err(PyValueError::new_err(format!(
+            "Too many tags: {} (maximum {MAX_TAGS})",
+            tags.len()
+        )));
+    }
 
-    // Import LangChain's load function
-    let langchain_load = py.import("langchain_core.load")
-        .or_else(|_| py.import("langchain.load"))
-        .map_err(|_| PyIOError::new_err("Could not import langchain_core.load or langchain.load. Please ensure LangChain is installed."))?;
+    let mut parsed = BTreeMap::new();
+    for (key, value) in tags.iter() {
+        if !key.is_instance_of::<PyString>() {
+            return Err(PyTypeError::new_err(format!(
+                "Tag keys must be str, not {}",
+                key.get_type()
+            )));
+        }
+        let key: String = key.extract()?;
+        if !value.is_instance_of::<PyString>() {
+            return Err(PyTypeError::new_err(format!(
+                "Value of tag '{key}' must be str, not {}",
+                value.get_type()
+            )));
+        }
+        let value: String = value.extract()?;
 
-    let loads_func = langchain_load.getattr("loads").map_err(|_| {
-        PyIOError::new_err("Could not find loads function in LangChain load module")
-    })?;
+        if key.is_empty() || key.len() > MAX_TAG_KEY_BYTES {
+            return Err(PyValueError::new_err(format!(
+                "Tag key '{key}' must be 1 to {MAX_TAG_KEY_BYTES} bytes long"
+            )));
+        }
+        if value.len() > MAX_TAG_VALUE_BYTES {
+            return Err(PyValueError::new_err(format!(
+                "Value of tag '{key}' is {} bytes long (maximum {MAX_TAG_VALUE_BYTES})",
+                value.len()
+            )));
+        }
+        parsed.insert(key, value);
+    }
+    Ok(parsed)
+}
 
-    // Deserialize the agent using LangChain's loads
-    let agent_obj = if let Some(secrets) = secrets_map {
-        loads_func.call1((agent_json, secrets))
-    } else {
-        loads_func.call1((agent_json,))
+/// Parse a `prune` policy from its Python arguments
+///
+/// `older_than` is a number of seconds or a `datetime.timedelta`. A policy without
+/// any rule would delete nothing, which is almost certainly a mistake, so it raises
+/// ValueError like the CLI refuses it.
+fn parse_retention_policy(
+    keep_last: Option<usize>,
+    older_than: Option<&Bound<'_, PyAny>>,
+    max_total_bytes: Option<u64>,
+) -> PyResult<RetentionPolicy> {
+    let mut policy = RetentionPolicy::new();
+    if let Some(keep_last) = keep_last {
+        policy = policy.keep_last(keep_last);
+    }
+    if let Some(older_than) = older_than {
+        let timedelta = older_than.py().import("datetime")?.getattr("timedelta")?;
+        let seconds: f64 = if older_than.is_instance(&timedelta)? {
+            older_than.call_method0("total_seconds")?.extract()?
+        } else {
+            older_than.extract().map_err(|_| {
+                PyTypeError::new_err(format!(
+                    "older_than must be a number of seconds or a datetime.timedelta, not {}",
+                    older_than.get_type()
+                ))
+            })?
+        };
+        let age = std::time::Duration::try_from_secs_f64(seconds)
+            .ok()
+            .and_then(|age| Duration::from_std(age).ok())
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "older_than must be a non-negative duration, not {seconds} seconds"
+                ))
+            })?;
+        policy = policy.older_than(age);
+    }
+    if let Some(max_total_bytes) = max_total_bytes {
+        policy = policy.max_total_size(max_total_bytes);
     }
-    .map_err(|e| {
-        PyIOError::new_err(format!(
-            "Failed to deserialize agent with LangChain loads: {e}"
-        ))
-    })?;
+    if policy.is_empty() {
+        return Err(PyValueError::new_err(
+            "No retention rule given: pass keep_last, older_than or max_total_bytes",
+        ));
+    }
+    Ok(policy)
+}
 
-    Ok(agent_obj.into())
+/// Import LangChain's load module, falling back to the pre-`langchain_core` location
+fn langchain_load(py: Python<'_>) -> PyResult<Bound<'_, PyModule>> {
+    py.import("langchain_core.load")
+        .or_else(|_| py.import("langchain.load"))
+        .map_err(|_| PyIOError::new_err("Could not import langchain_core.load or langchain.load. Please ensure LangChain is installed."))
 }
 
-/// Get metadata for a snapshot without loading the full snapshot
-///
-/// # Arguments
-/// * `path` - Storage path/key of the snapshot
-/// * `storage_mode` - Storage backend: "local" or "s3" (default: "local")
-/// * `s3_bucket` - S3 bucket name (required for S3 mode)
-/// * `s3_region` - S3 region (optional, uses AWS environment default)
-///
-/// # Returns
-/// Dictionary containing snapshot metadata
-#[pyfunction]
-#[pyo3(signature = (path, storage_mode=None, s3_bucket=None, s3_region=None))]
-fn get_metadata(
-    py: Python<'_>,
-    path: &str,
-    storage_mode: Option<&str>,
-    s3_bucket: Option<&str>,
-    s3_region: Option<&str>,
-) -> PyResult<PyObject> {
-    let config = create_storage_config(storage_mode, s3_bucket, s3_region)?;
-    let engine = create_engine_from_config(config).map_err(convert_error)?;
+/// Metadata key recording the kind of state a snapshot holds; absent for agent JSON
+const PAYLOAD_KEY: &str = "persist.payload";
 
-    let metadata = engine.get_snapshot_metadata(path).map_err(convert_error)?;
+/// Kind of state a snapshot holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Payload {
+    /// Agent state as JSON, from `snapshot` or `snapshot_json`
+    Json,
+    /// Raw bytes from `snapshot_bytes`, stored as a base64 JSON string
+    Bytes,
+    /// An agent saved with `serializer="pickle"`
+    Pickle,
+}
 
-    // Convert metadata to Python dictionary
-    let dict = PyDict::new(py);
-    dict.set_item("agent_id", metadata.agent_id)?;
-    dict.set_item("session_id", metadata.session_id)?;
-    dict.set_item("snapshot_index", metadata.snapshot_index)?;
-    dict.set_item("timestamp", metadata.timestamp.timestamp())?;
-    dict.set_item("format_version", metadata.format_version)?;
-    dict.set_item("content_hash", metadata.content_hash)?;
-    dict.set_item("compression_algorithm", metadata.compression_algorithm)?;
+impl Payload {
+    /// Payload kind recorded in a snapshot's metadata
+    fn of(metadata: &SnapshotMetadata) -> Self {
+        match metadata.extra.get(PAYLOAD_KEY).and_then(|v| v.as_str()) {
+            Some("bytes") => Payload::Bytes,
+            Some(pickling::SERIALIZER_NAME) => Payload::Pickle,
+            _ => Payload::Json,
+        }
+    }
 
-    if let Some(desc) = &metadata.description {
-        dict.set_item("description", desc)?;
+    /// Record this payload kind in snapshot metadata
+    fn record(self, metadata: &mut SnapshotMetadata) {
+        let kind = match self {
+            Payload::Json => return,
+            Payload::Bytes => "bytes",
+            Payload::Pickle => pickling::SERIALIZER_NAME,
+        };
+        metadata.extra.insert(PAYLOAD_KEY.to_string(), kind.into());
     }
-    if let Some(size) = metadata.compressed_size {
-        dict.set_item("compressed_size", size)?;
+
+    /// Raise PersistValidationError unless the snapshot at `path` holds this kind of payload
+    fn expect(self, path: &str, metadata: &SnapshotMetadata) -> PyResult<()> {
+        match (self, Payload::of(metadata)) {
+            (Payload::Json, Payload::Bytes) => Err(PyPersistValidationError::new_err(format!(
+                "Snapshot {path} holds raw bytes saved with snapshot_bytes; restore it with restore_bytes"
+            ))),
+            (Payload::Bytes, Payload::Json) => Err(PyPersistValidationError::new_err(format!(
+                "Snapshot {path} holds agent JSON; restore it with restore or restore_json"
+            ))),
+            (Payload::Json | Payload::Bytes, Payload::Pickle) => {
+                Err(PyPersistValidationError::new_err(format!(
+                    "Snapshot {path} holds a pickled agent; restore it with restore(allow_pickle=True)"
+                )))
+            }
+            _ => Ok(()),
+        }
     }
-    if let Some(snapshot_id) = Some(&metadata.snapshot_id) {
-        dict.set_item("snapshot_id", snapshot_id)?;
+}
+
+/// What `restore` does when a snapshot was saved under another LangChain version than
+/// the installed one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompatibilityCheck {
+    /// Raise PersistCompatibilityError
+    Error,
+    /// Emit a `UserWarning` and restore anyway
+    Warn,
+    /// Restore without comparing
+    Ignore,
+}
+
+impl CompatibilityCheck {
+    /// Parse a Python `check_compatibility` argument
+    fn parse(check: &str) -> PyResult<Self> {
+        match check {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "ignore" => Ok(Self::Ignore),
+            other => Err(PyPersistConfigurationError::new_err(format!(
+                "Invalid check_compatibility '{other}'. Must be 'error', 'warn' or 'ignore'"
+            ))),
+        }
     }
 
-    Ok(dict.into())
+    /// Compare the LangChain version recorded for the snapshot at `path` with the
+    /// installed one, read at call time
+    ///
+    /// Versions match when their major.minor parts do, as LangChain breaks
+    /// compatibility between minor releases. Snapshots without a recorded version, of
+    /// another framework, or restored without LangChain installed are not checked.
+    fn apply(self, py: Python<'_>, path: &str, metadata: &SnapshotMetadata) -> PyResult<()> {
+        if self == Self::Ignore {
+            return Ok(());
+        }
+        let (Some(framework), Some(stored)) = (
+            metadata.framework.as_deref(),
+            metadata.framework_version.as_deref(),
+        ) else {
+            return Ok(());
+        };
+        if !framework.eq_ignore_ascii_case("langchain") {
+            return Ok(());
+        }
+        let Some(installed) = langchain_version(py) else {
+            return Ok(());
+        };
+        let requirement =
+            FrameworkRequirement::new("langchain", &installed, FrameworkCompatPolicy::SameMinor);
+        if requirement.check(metadata).is_ok() {
+            return Ok(());
+        }
+
+        let message = format!(
+            "Snapshot {path} was created with LangChain {stored}, but LangChain {installed} \
+             is installed"
+        );
+        if self == Self::Error {
+            return Err(PyPersistCompatibilityError::new_err(message));
+        }
+        PyErr::warn(
+            py,
+            &py.get_type::<PyUserWarning>(),
+            &CString::new(message)?,
+            1,
+        )
+    }
 }
 
-/// Verify the integrity of a snapshot
-///
-/// # Arguments
-/// * `path` - Storage path/key of the snapshot to verify
-/// * `storage_mode` - Storage backend: "local" or "s3" (default: "local")
-/// * `s3_bucket` - S3 bucket name (required for S3 mode)
-/// * `s3_region` - S3 region (optional, uses AWS environment default)
-///
-/// # Returns
-/// None on success (integrity verified)
-///
-/// # Raises
-/// * IOError - If verification fails or snapshot is corrupted
-#[pyfunction]
-#[pyo3(signature = (path, storage_mode=None, s3_bucket=None, s3_region=None))]
-fn verify_snapshot(
-    path: &str,
-    storage_mode: Option<&str>,
-    s3_bucket: Option<&str>,
-    s3_region: Option<&str>,
-) -> PyResult<()> {
-    let config = create_storage_config(storage_mode, s3_bucket, s3_region)?;
-    let engine = create_engine_from_config(config).map_err(convert_error)?;
+/// JSON text of a `snapshot_json` state: a JSON string as is, anything else through
+/// `json.dumps`
+fn state_to_json(state: &Bound<'_, PyAny>) -> PyResult<String> {
+    if state.is_instance_of::<PyString>() {
+        return state.extract();
+    }
+    state
+        .py()
+        .import("json")?
+        .call_method1("dumps", (state,))
+        .and_then(|json| json.extract())
+        .map_err(|e| PyPersistError::new_err(format!("State is not JSON serializable: {e}")))
+}
 
-    engine.verify_snapshot(path).map_err(convert_error)?;
+/// Parse a Python `sensitive` argument into the metadata fields it names
+fn parse_sensitive(sensitive: Option<Vec<String>>) -> PyResult<Vec<SensitiveField>> {
+    sensitive
+        .unwrap_or_default()
+        .iter()
+        .map(|field| {
+            field
+                .parse::<SensitiveField>()
+                .map_err(|e| PyPersistConfigurationError::new_err(e.to_string()))
+        })
+        .collect()
+}
 
-    Ok(())
+/// Validated arguments of one snapshot save, everything but the state itself
+struct SnapshotRequest {
+    path: String,
+    agent_id: String,
+    session_id: String,
+    /// None for "auto"
+    snapshot_index: Option<u64>,
+    description: Option<String>,
+    sensitive_fields: Vec<SensitiveField>,
+    framework: Option<(String, String)>,
+    tags: BTreeMap<String, String>,
+    payload: Payload,
 }
 
-/// Check if a snapshot exists
-///
-/// # Arguments
-/// * `path` - Storage path/key to check
-/// * `storage_mode` - Storage backend: "local" or "s3" (default: "local")
-/// * `s3_bucket` - S3 bucket name (required for S3 mode)
-/// * `s3_region` - S3 region (optional, uses AWS environment default)
-///
-/// # Returns
-/// True if the snapshot exists, False otherwise
-#[pyfunction]
-#[pyo3(signature = (path, storage_mode=None, s3_bucket=None, s3_region=None))]
-fn snapshot_exists(
-    path: &str,
-    storage_mode: Option<&str>,
-    s3_bucket: Option<&str>,
-    s3_region: Option<&str>,
-) -> PyResult<bool> {
-    let config = create_storage_config(storage_mode, s3_bucket, s3_region)
-        .unwrap_or_else(|_| StorageConfig::default_local()); // Fallback to local on error
+impl SnapshotRequest {
+    /// Save `agent_json` with metadata built from this request, returning the metadata
+    fn save(
+        &self,
+        engine: &Engine,
+        agent_json: &str,
+        options: &CallOptions,
+    ) -> persist_core::Result<SnapshotMetadata> {
+        let path = self.path.as_str();
+        // Derive the snapshot index from existing snapshots when "auto"
+        let snapshot_index = match self.snapshot_index {
+            Some(index) => index,
+            None => {
+                let prefix = path.rfind('/').map_or("", |idx| &path[..=idx]);
+                engine.next_index_under(prefix, &self.agent_id, &self.session_id)?
+            }
+        };
 
-    let engine = create_engine_from_config(config);
-    match engine {
-        Ok(e) => Ok(e.snapshot_exists(path)),
-        Err(_) => Ok(false), // If engine creation fails, assume snapshot doesn't exist
+        let mut builder =
+            SnapshotMetadata::builder(&self.agent_id, &self.session_id, snapshot_index);
+        if let Some(desc) = &self.description {
+            builder = builder.description(desc);
+        }
+        if let Some((name, version)) = &self.framework {
+            builder = builder.framework(name, version);
+        }
+        builder = builder.tags(self.tags.clone());
+        for field in &self.sensitive_fields {
+            builder = builder.sensitive(*field);
+        }
+
+        let mut metadata = builder.build();
+        self.payload.record(&mut metadata);
+        engine.save_snapshot_with_options(agent_json, &metadata, path, options)?;
+        Ok(metadata)
     }
 }
 
-/// Delete a snapshot
+/// Keys a `snapshot_many` item may have
+const SNAPSHOT_ITEM_KEYS: &[&str] = &[
+    "agent",
+    "state_json",
+    "path",
+    "agent_id",
+    "session_id",
+    "snapshot_index",
+    "description",
+    "sensitive",
+    "tags",
+];
+
+/// Client holding one storage engine for any number of snapshot operations
+///
+/// Creating an engine sets up the storage backend (for S3, an async runtime and an
+/// AWS client), so a client reused across calls is much cheaper than the module-level
+/// functions, which create one per call. A client can be shared between threads;
+/// storage operations run without holding the GIL.
 ///
 /// # Arguments
-/// * `path` - Storage path/key of the snapshot to delete
-/// * `storage_mode` - Storage backend: "local" or "s3" (default: "local")
-/// * `s3_bucket` - S3 bucket name (required for S3 mode)
-/// * `s3_region` - S3 region (optional, uses AWS environment default)
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `compression` - Compression for new snapshots: "gzip", "zstd" or "none" (default:
+///   `PERSIST_COMPRESSION`, then "gzip"); "zstd" needs the extension built with the `zstd` feature
+/// * `compression_level` - Codec level: 0-9 for gzip, 1-22 for zstd (default: the codec's own)
+/// * `encryption_key` - Reserved for snapshot encryption, which is not available yet;
+///   passing a key raises `PersistConfigurationError`
+/// * `strict_format` - Reject snapshots with an incompatible format version (default: True)
+/// * `serializer`, `deserializer` - Default callbacks for `snapshot` and `restore`, used
+///   instead of LangChain; arguments passed to those methods take precedence. `serializer`
+///   may also be "pickle"
 ///
-/// # Returns
-/// None on success
+/// # Example
+/// ```python
+/// import persist
 ///
-/// # Raises
-/// * IOError - If deletion fails
-#[pyfunction]
-#[pyo3(signature = (path, storage_mode=None, s3_bucket=None, s3_region=None))]
-fn delete_snapshot(
-    path: &str,
-    storage_mode: Option<&str>,
-    s3_bucket: Option<&str>,
-    s3_region: Option<&str>,
-) -> PyResult<()> {
-    let config = create_storage_config(storage_mode, s3_bucket, s3_region)?;
-    let engine = create_engine_from_config(config).map_err(convert_error)?;
+/// with persist.PersistClient(storage_mode="s3", s3_bucket="my-snapshots-bucket") as client:
+///     client.snapshot(agent, "agent1/session1/snapshot.json.gz", agent_id="agent1")
+///     for snap in client.list("agent1/"):
+///         print(snap["path"], snap["snapshot_index"])
+/// ```
+#[pyclass(module = "persist", frozen)]
+struct PersistClient {
+    /// `None` once the client is closed
+    engine: RwLock<Option<Box<Engine>>>,
+    /// Storage the engine was created for
+    config: StorageConfig,
+    /// Default for the `serializer` argument of `snapshot`
+    serializer: Option<PyObject>,
+    /// Default for the `deserializer` argument of `restore`
+    deserializer: Option<PyObject>,
+}
 
-    engine.delete_snapshot(path).map_err(convert_error)?;
+impl PersistClient {
+    fn from_config(
+        py: Python<'_>,
+        config: StorageConfig,
+        compression: Option<&str>,
+        compression_level: Option<i32>,
+        strict_format: bool,
+    ) -> PyResult<Self> {
+        // Setting up a cloud backend builds an async runtime and loads credentials,
+        // which can involve network round trips
+        let engine = py.allow_threads(|| {
+            create_engine(
+                config.clone(),
+                compression,
+                compression_level,
+                strict_format,
+            )
+        })?;
+        Ok(Self {
+            engine: RwLock::new(Some(engine)),
+            config,
+            serializer: None,
+            deserializer: None,
+        })
+    }
 
-    Ok(())
-}
+    /// Client for the storage options of the module-level functions
+    fn for_call(py: Python<'_>, storage: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let config = create_storage_config(storage)?;
+        Self::from_config(py, config, Some("gzip"), None, true)
+    }
 
-/// Python module definition
-#[pymodule]
-fn persist(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    // Add main functions
-    m.add_function(wrap_pyfunction!(snapshot, m)?)?;
+    /// Run `operation` on the engine with the GIL released
+    fn with_engine<T: Send>(
+        &self,
+        py: Python<'_>,
+        operation: impl FnOnce(&Engine) -> persist_core::Result<T> + Send,
+    ) -> PyResult<T> {
+        py.allow_threads(|| {
+            let engine = self.engine.read().unwrap_or_else(PoisonError::into_inner);
+            let engine = engine
+                .as_deref()
+                .ok_or_else(|| PyPersistError::new_err("PersistClient is closed"))?;
+            operation(engine).map_err(convert_error)
+        })
+    }
+
+    /// Run `operation` on the engines of this client and `target` with the GIL released
+    fn with_engines<T: Send>(
+        &self,
+        py: Python<'_>,
+        target: &PersistClient,
+        operation: impl FnOnce(&Engine, &Engine) -> persist_core::Result<T> + Send,
+    ) -> PyResult<T> {
+        if std::ptr::eq(self, target) {
+            return self.with_engine(py, |engine| operation(engine, engine));
+        }
+        py.allow_threads(|| {
+            let source = self.engine.read().unwrap_or_else(PoisonError::into_inner);
+            let target = target.engine.read().unwrap_or_else(PoisonError::into_inner);
+            let (Some(source), Some(target)) = (source.as_deref(), target.as_deref()) else {
+                return Err(PyPersistError::new_err("PersistClient is closed"));
+            };
+            operation(source, target).map_err(convert_error)
+        })
+    }
+
+    /// Client for a `src_storage`/`dst_storage` argument: a `PersistClient`, a dictionary
+    /// of `PersistClient` keyword arguments, or None for the defaults
+    fn for_storage<'py>(
+        py: Python<'py>,
+        name: &str,
+        storage: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PersistClient>> {
+        let class = py.get_type::<PersistClient>();
+        let client = match storage {
+            Some(storage) if storage.is_instance_of::<PersistClient>() => storage.clone(),
+            Some(storage) => {
+                let kwargs = storage.downcast::<PyDict>().map_err(|_| {
+                    PyTypeError::new_err(format!(
+                        "{name} must be a PersistClient, a dict of PersistClient arguments or None"
+                    ))
+                })?;
+                class.call((), Some(kwargs))?
+            }
+            None => class.call0()?,
+        };
+        Ok(client.downcast_into::<PersistClient>()?)
+    }
+
+    /// Copy `src_path` to `dst_path` in `target`, deleting the source afterwards if
+    /// `remove_source` is set
+    #[allow(clippy::too_many_arguments)]
+    fn copy_to(
+        &self,
+        py: Python<'_>,
+        target: &PersistClient,
+        src_path: &str,
+        dst_path: &str,
+        verify: bool,
+        remove_source: bool,
+        reveal: bool,
+        as_dict: bool,
+    ) -> PyResult<PyObject> {
+        if remove_source && src_path == dst_path && self.config == target.config {
+            return Err(PyPersistValidationError::new_err(format!(
+                "Cannot move snapshot '{src_path}' onto itself"
+            )));
+        }
+        let metadata = self.with_engines(py, target, |source, target| {
+            let metadata = persist_core::copy_snapshot(source, target, src_path, dst_path, verify)?;
+            // Only a complete (and, with verify, verified) copy lets the source go
+            if remove_source {
+                source.delete_snapshot(src_path)?;
+            }
+            Ok(metadata)
+        })?;
+        PySnapshotMetadata::new(&metadata, reveal, Some(dst_path.to_string()))?
+            .into_result(py, as_dict)
+    }
+
+    /// Load a snapshot, reporting the bytes read to `progress_callback`
+    fn load_with_progress(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        progress_callback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<(SnapshotMetadata, String)> {
+        with_progress(progress_callback, |options| {
+            self.with_engine(py, |engine| {
+                engine.load_snapshot_with_options(path, options)
+            })
+        })
+    }
+
+    /// Run `operation` on each of `paths` on up to `parallel` threads sharing this
+    /// client's engine, returning the results in the order of `paths`
+    fn map_paths<T: Send>(
+        &self,
+        py: Python<'_>,
+        paths: &[String],
+        parallel: usize,
+        operation: impl Fn(&Engine, &str) -> T + Sync,
+    ) -> PyResult<Vec<T>> {
+        self.map_items(py, paths, parallel, |engine, path| operation(engine, path))
+    }
+
+    /// Run `operation` on each of `items` like `map_paths`
+    fn map_items<I: Sync, T: Send>(
+        &self,
+        py: Python<'_>,
+        items: &[I],
+        parallel: usize,
+        operation: impl Fn(&Engine, &I) -> T + Sync,
+    ) -> PyResult<Vec<T>> {
+        if parallel == 0 {
+            return Err(PyValueError::new_err("parallel must be at least 1"));
+        }
+        self.with_engine(py, |engine| {
+            let next = AtomicUsize::new(0);
+            let worker = || {
+                let mut results = Vec::new();
+                loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(item) = items.get(i) else {
+                        break;
+                    };
+                    results.push((i, operation(engine, item)));
+                }
+                results
+            };
+            let mut results = std::thread::scope(|scope| {
+                let workers: Vec<_> = (0..parallel.min(items.len()))
+                    .map(|_| scope.spawn(worker))
+                    .collect();
+                workers
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("batch worker panicked"))
+                    .collect::<Vec<_>>()
+            });
+            results.sort_by_key(|(i, _)| *i);
+            Ok(results.into_iter().map(|(_, result)| result).collect())
+        })
+    }
+
+    /// Delete `paths` concurrently
+    ///
+    /// Returns a dictionary mapping each path to None, or to the exception its
+    /// deletion raised; failures do not stop the other deletions.
+    fn delete_paths(
+        &self,
+        py: Python<'_>,
+        paths: Vec<String>,
+        parallel: usize,
+    ) -> PyResult<Py<PyDict>> {
+        let outcomes = self.map_paths(py, &paths, parallel, |engine, path| {
+            engine.delete_snapshot(path)
+        })?;
+
+        let results = PyDict::new(py);
+        for (path, outcome) in paths.iter().zip(outcomes) {
+            match outcome {
+                Ok(()) => results.set_item(path, py.None())?,
+                Err(e) => results.set_item(path, convert_error(e).into_value(py))?,
+            }
+        }
+        Ok(results.unbind())
+    }
+
+    /// Save a JSON agent state with snapshot metadata built from the Python arguments
+    #[allow(clippy::too_many_arguments)]
+    fn save_json(
+        &self,
+        py: Python<'_>,
+        agent_json: &str,
+        path: &str,
+        agent_id: &str,
+        session_id: &str,
+        snapshot_index: Option<&Bound<'_, PyAny>>,
+        description: Option<&str>,
+        sensitive: Option<Vec<String>>,
+        framework: Option<(String, String)>,
+        tags: BTreeMap<String, String>,
+        payload: Payload,
+        options: &CallOptions,
+    ) -> PyResult<()> {
+        let request = SnapshotRequest {
+            path: path.to_string(),
+            agent_id: agent_id.to_string(),
+            session_id: session_id.to_string(),
+            snapshot_index: snapshot_index
+                .map(parse_snapshot_index)
+                .transpose()?
+                .unwrap_or(Some(0)),
+            description: description.map(str::to_string),
+            sensitive_fields: parse_sensitive(sensitive)?,
+            framework,
+            tags,
+            payload,
+        };
+        self.with_engine(py, |engine| {
+            request.save(engine, agent_json, options)?;
+            Ok(())
+        })
+    }
+
+    /// State JSON, framework and payload kind of `agent`, from `serializer`, the client's
+    /// default serializer or LangChain's `dumps`
+    fn serialize_agent<'py>(
+        &self,
+        py: Python<'py>,
+        agent: &Bound<'py, PyAny>,
+        serializer: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<SerializedAgent> {
+        let serializer = serializer
+            .cloned()
+            .or_else(|| self.serializer.as_ref().map(|s| s.bind(py).clone()));
+
+        if let Some(serializer) = serializer {
+            if serializer.is_instance_of::<PyString>() {
+                let name: String = serializer.extract()?;
+                if name != pickling::SERIALIZER_NAME {
+                    return Err(PyPersistConfigurationError::new_err(format!(
+                        "Invalid serializer '{name}'. Must be 'pickle' or a callable"
+                    )));
+                }
+                Ok((pickling::dumps(agent)?, None, Payload::Pickle))
+            } else {
+                // Exceptions raised by the callback propagate unchanged
+                let json_obj = serializer.call1((agent,))?;
+                let agent_json: String = json_obj.extract().map_err(|_| {
+                    PyTypeError::new_err(format!(
+                        "serializer must return a JSON string, not {}",
+                        json_obj.get_type()
+                    ))
+                })?;
+                Ok((agent_json, None, Payload::Json))
+            }
+        } else {
+            let dumps_func = langchain_load(py)?.getattr("dumps").map_err(|_| {
+                PyIOError::new_err("Could not find dumps function in LangChain load module")
+            })?;
+
+            // Serialize the agent to JSON string using LangChain's dumps
+            let json_obj = dumps_func.call1((agent,)).map_err(|e| {
+                PyIOError::new_err(format!(
+                    "Failed to serialize agent with LangChain dumps: {e}"
+                ))
+            })?;
+
+            let agent_json: String = json_obj.extract().map_err(|e| {
+                PyIOError::new_err(format!(
+                    "Failed to extract JSON string from LangChain dumps result: {e}"
+                ))
+            })?;
+            let framework = langchain_version(py).map(|v| ("langchain".to_string(), v));
+            Ok((agent_json, framework, Payload::Json))
+        }
+    }
+
+    /// Serialize one `snapshot_many` item, validating its arguments
+    fn snapshot_item<'py>(
+        &self,
+        py: Python<'py>,
+        item: &Bound<'py, PyAny>,
+        serializer: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<(SnapshotRequest, String)> {
+        let item = item
+            .downcast::<PyDict>()
+            .map_err(|_| PyTypeError::new_err("snapshot_many items must be dicts"))?;
+        for key in item.keys() {
+            let key: String = key.extract()?;
+            if !SNAPSHOT_ITEM_KEYS.contains(&key.as_str()) {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown snapshot_many item key '{key}'"
+                )));
+            }
+        }
+        // A None value takes the default, like an omitted argument
+        let get = |key: &str| -> PyResult<Option<Bound<'py, PyAny>>> {
+            Ok(item.get_item(key)?.filter(|value| !value.is_none()))
+        };
+
+        let path: String = get("path")?
+            .ok_or_else(|| PyValueError::new_err("snapshot_many item has no path"))?
+            .extract()?;
+        let (agent_json, framework, payload) = match (get("agent")?, get("state_json")?) {
+            (Some(agent), None) => self.serialize_agent(py, &agent, serializer)?,
+            (None, Some(state)) => (state_to_json(&state)?, None, Payload::Json),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "snapshot_many item must have exactly one of agent and state_json",
+                ))
+            }
+        };
+        let tags = match get("tags")? {
+            Some(tags) => parse_tags(Some(tags.downcast::<PyDict>()?))?,
+            None => BTreeMap::new(),
+        };
+
+        let request = SnapshotRequest {
+            path,
+            agent_id: get("agent_id")?
+                .map_or_else(|| Ok("default_agent".to_string()), |id| id.extract())?,
+            session_id: get("session_id")?
+                .map_or_else(|| Ok("default_session".to_string()), |id| id.extract())?,
+            snapshot_index: get("snapshot_index")?
+                .map(|index| parse_snapshot_index(&index))
+                .transpose()?
+                .unwrap_or(Some(0)),
+            description: get("description")?.map(|desc| desc.extract()).transpose()?,
+            sensitive_fields: parse_sensitive(
+                get("sensitive")?
+                    .map(|fields| fields.extract())
+                    .transpose()?,
+            )?,
+            framework,
+            tags,
+            payload,
+        };
+        Ok((request, agent_json))
+    }
+
+    /// Turn the state of the snapshot at `path` back into an agent; see `restore` for the
+    /// arguments
+    #[allow(clippy::too_many_arguments)]
+    fn decode_agent(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        metadata: &SnapshotMetadata,
+        agent_json: String,
+        secrets_map: Option<&Bound<'_, PyDict>>,
+        policy: Option<FrameworkCompatPolicy>,
+        deserializer: Option<&Bound<'_, PyAny>>,
+        allow_pickle: bool,
+        pickle_allowlist: Option<&Bound<'_, PyAny>>,
+        check: CompatibilityCheck,
+    ) -> PyResult<PyObject> {
+        check.apply(py, path, metadata)?;
+        if Payload::of(metadata) == Payload::Pickle {
+            if !allow_pickle {
+                return Err(pickling::refuse(path));
+            }
+            return pickling::loads(py, path, &agent_json, pickle_allowlist);
+        }
+        Payload::Json.expect(path, metadata)?;
+
+        // Enforce framework compatibility against the installed LangChain, if requested
+        if let Some(policy) = policy {
+            let installed = langchain_version(py).unwrap_or_default();
+            FrameworkRequirement::new("langchain", installed, policy)
+                .check(metadata)
+                .map_err(convert_error)?;
+        }
+
+        let deserializer = deserializer
+            .cloned()
+            .or_else(|| self.deserializer.as_ref().map(|d| d.bind(py).clone()));
+        if let Some(deserializer) = deserializer {
+            // Exceptions raised by the callback propagate unchanged
+            let agent_obj = match secrets_map {
+                Some(secrets) => deserializer.call1((agent_json, secrets))?,
+                None => deserializer.call1((agent_json,))?,
+            };
+            return Ok(agent_obj.unbind());
+        }
+
+        let loads_func = langchain_load(py)?.getattr("loads").map_err(|_| {
+            PyIOError::new_err("Could not find loads function in LangChain load module")
+        })?;
+
+        // Deserialize the agent using LangChain's loads
+        let agent_obj = if let Some(secrets) = secrets_map {
+            loads_func.call1((agent_json, secrets))
+        } else {
+            loads_func.call1((agent_json,))
+        }
+        .map_err(|e| {
+            PyIOError::new_err(format!(
+                "Failed to deserialize agent with LangChain loads: {e}"
+            ))
+        })?;
+
+        Ok(agent_obj.into())
+    }
+
+    /// Resolve the latest snapshot of an agent, raising FileNotFoundError if it has none
+    fn latest_summary(
+        &self,
+        py: Python<'_>,
+        agent_id: &str,
+        session_id: Option<&str>,
+        prefix: &str,
+    ) -> PyResult<SnapshotSummary> {
+        self.with_engine(py, |engine| {
+            engine.latest_snapshot(prefix, agent_id, session_id)
+        })?
+        .ok_or_else(|| {
+            let scope = match session_id {
+                Some(session_id) => format!("agent {agent_id}, session {session_id}"),
+                None => format!("agent {agent_id}"),
+            };
+            PyFileNotFoundError::new_err(format!("No snapshot found for {scope}"))
+        })
+    }
+}
+
+#[pymethods]
+impl PersistClient {
+    #[new]
+    #[pyo3(signature = (*, compression=None, compression_level=None, encryption_key=None, strict_format=true, serializer=None, deserializer=None, **storage))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        py: Python<'_>,
+        compression: Option<&str>,
+        compression_level: Option<i32>,
+        encryption_key: Option<&str>,
+        strict_format: bool,
+        serializer: Option<PyObject>,
+        deserializer: Option<PyObject>,
+        storage: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        if encryption_key.is_some() {
+            return Err(PyPersistConfigurationError::new_err(
+                "encryption_key is not supported: this version of persist cannot encrypt snapshots",
+            ));
+        }
+        let config = create_storage_config(storage)?;
+        Ok(Self {
+            serializer,
+            deserializer,
+            ..Self::from_config(py, config, compression, compression_level, strict_format)?
+        })
+    }
+
+    /// Save an agent snapshot; see the module-level `snapshot` for the arguments
+    #[pyo3(signature = (agent, path, agent_id="default_agent", session_id="default_session", snapshot_index=None, description=None, sensitive=None, serializer=None, tags=None, progress_callback=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn snapshot(
+        &self,
+        py: Python<'_>,
+        agent: &Bound<'_, PyAny>,
+        path: &str,
+        agent_id: &str,
+        session_id: &str,
+        snapshot_index: Option<&Bound<'_, PyAny>>,
+        description: Option<&str>,
+        sensitive: Option<Vec<String>>,
+        serializer: Option<&Bound<'_, PyAny>>,
+        tags: Option<&Bound<'_, PyDict>>,
+        progress_callback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        let tags = parse_tags(tags)?;
+        let (agent_json, framework, payload) = self.serialize_agent(py, agent, serializer)?;
+
+        with_progress(progress_callback, |options| {
+            self.save_json(
+                py,
+                &agent_json,
+                path,
+                agent_id,
+                session_id,
+                snapshot_index,
+                description,
+                sensitive,
+                framework,
+                tags,
+                payload,
+                options,
+            )
+        })
+    }
+
+    /// Restore an agent snapshot; see the module-level `restore` for the arguments
+    #[pyo3(signature = (path, secrets_map=None, framework_policy=None, deserializer=None, progress_callback=None, allow_pickle=false, pickle_allowlist=None, check_compatibility="warn"))]
+    #[allow(clippy::too_many_arguments)]
+    fn restore(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        secrets_map: Option<&Bound<'_, PyDict>>,
+        framework_policy: Option<&str>,
+        deserializer: Option<&Bound<'_, PyAny>>,
+        progress_callback: Option<&Bound<'_, PyAny>>,
+        allow_pickle: bool,
+        pickle_allowlist: Option<&Bound<'_, PyAny>>,
+        check_compatibility: &str,
+    ) -> PyResult<PyObject> {
+        let policy = framework_policy
+            .map(str::parse::<FrameworkCompatPolicy>)
+            .transpose()
+            .map_err(|e| PyPersistConfigurationError::new_err(e.to_string()))?;
+        let check = CompatibilityCheck::parse(check_compatibility)?;
+
+        let (metadata, agent_json) = self.load_with_progress(py, path, progress_callback)?;
+        self.decode_agent(
+            py,
+            path,
+            &metadata,
+            agent_json,
+            secrets_map,
+            policy,
+            deserializer,
+            allow_pickle,
+            pickle_allowlist,
+            check,
+        )
+    }
+
+    /// Save several snapshots; see the module-level `snapshot_many` for the arguments
+    #[pyo3(signature = (items, serializer=None, parallel=BATCH_PARALLELISM, as_dict=false))]
+    fn snapshot_many(
+        &self,
+        py: Python<'_>,
+        items: &Bound<'_, PyAny>,
+        serializer: Option<&Bound<'_, PyAny>>,
+        parallel: usize,
+        as_dict: bool,
+    ) -> PyResult<Vec<Py<PyDict>>> {
+        let items = items.try_iter()?.collect::<PyResult<Vec<_>>>()?;
+        // Serializing needs the GIL, so only the saves run in parallel
+        let prepared: Vec<_> = items
+            .iter()
+            .map(|item| self.snapshot_item(py, item, serializer))
+            .collect();
+        let saved = self.map_items(py, &prepared, parallel, |engine, prepared| {
+            let (request, agent_json) = prepared.as_ref().ok()?;
+            Some(request.save(engine, agent_json, &CallOptions::default()))
+        })?;
+
+        let mut results = Vec::with_capacity(items.len());
+        for ((item, prepared), saved) in items.iter().zip(prepared).zip(saved) {
+            let entry = PyDict::new(py);
+            let outcome = match (prepared, saved) {
+                (Ok((request, _)), Some(saved)) => {
+                    entry.set_item("path", &request.path)?;
+                    saved.map_err(convert_error).and_then(|metadata| {
+                        PySnapshotMetadata::new(&metadata, false, Some(request.path))?
+                            .into_result(py, as_dict)
+                    })
+                }
+                (Err(e), _) => {
+                    let path = item
+                        .downcast::<PyDict>()
+                        .ok()
+                        .and_then(|item| item.get_item("path").ok().flatten());
+                    entry.set_item("path", path)?;
+                    Err(e)
+                }
+                (Ok(_), None) => unreachable!("prepared items are always saved"),
+            };
+            match outcome {
+                Ok(metadata) => {
+                    entry.set_item("metadata", metadata)?;
+                    entry.set_item("error", py.None())?;
+                }
+                Err(e) => {
+                    entry.set_item("metadata", py.None())?;
+                    entry.set_item("error", e.into_value(py))?;
+                }
+            }
+            results.push(entry.unbind());
+        }
+        Ok(results)
+    }
+
+    /// Restore several snapshots; see the module-level `restore_many` for the arguments
+    #[pyo3(signature = (paths, secrets_map=None, framework_policy=None, deserializer=None, allow_pickle=false, pickle_allowlist=None, parallel=BATCH_PARALLELISM, as_dict=false, check_compatibility="warn"))]
+    #[allow(clippy::too_many_arguments)]
+    fn restore_many(
+        &self,
+        py: Python<'_>,
+        paths: Vec<String>,
+        secrets_map: Option<&Bound<'_, PyDict>>,
+        framework_policy: Option<&str>,
+        deserializer: Option<&Bound<'_, PyAny>>,
+        allow_pickle: bool,
+        pickle_allowlist: Option<&Bound<'_, PyAny>>,
+        parallel: usize,
+        as_dict: bool,
+        check_compatibility: &str,
+    ) -> PyResult<Vec<Py<PyDict>>> {
+        let policy = framework_policy
+            .map(str::parse::<FrameworkCompatPolicy>)
+            .transpose()
+            .map_err(|e| PyPersistConfigurationError::new_err(e.to_string()))?;
+        let check = CompatibilityCheck::parse(check_compatibility)?;
+
+        // Loading runs in parallel; deserializing needs the GIL and follows in order
+        let loaded = self.map_paths(py, &paths, parallel, |engine, path| {
+            engine.load_snapshot(path)
+        })?;
+
+        let mut results = Vec::with_capacity(paths.len());
+        for (path, loaded) in paths.into_iter().zip(loaded) {
+            let restored = loaded
+                .map_err(convert_error)
+                .and_then(|(metadata, agent_json)| {
+                    let agent = self.decode_agent(
+                        py,
+                        &path,
+                        &metadata,
+                        agent_json,
+                        secrets_map,
+                        policy,
+                        deserializer,
+                        allow_pickle,
+                        pickle_allowlist,
+                        check,
+                    )?;
+                    let metadata = PySnapshotMetadata::new(&metadata, false, Some(path.clone()))?
+                        .into_result(py, as_dict)?;
+                    Ok((agent, metadata))
+                });
+
+            let entry = PyDict::new(py);
+            entry.set_item("path", &path)?;
+            match restored {
+                Ok((agent, metadata)) => {
+                    entry.set_item("agent", agent)?;
+                    entry.set_item("metadata", metadata)?;
+                    entry.set_item("error", py.None())?;
+                }
+                Err(e) => {
+                    entry.set_item("agent", py.None())?;
+                    entry.set_item("metadata", py.None())?;
+                    entry.set_item("error", e.into_value(py))?;
+                }
+            }
+            results.push(entry.unbind());
+        }
+        Ok(results)
+    }
+
+    /// Save a JSON agent state; see the module-level `snapshot_json` for the arguments
+    #[pyo3(signature = (state_json, path, agent_id="default_agent", session_id="default_session", snapshot_index=None, description=None, sensitive=None, framework=None, tags=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn snapshot_json(
+        &self,
+        py: Python<'_>,
+        state_json: &Bound<'_, PyAny>,
+        path: &str,
+        agent_id: &str,
+        session_id: &str,
+        snapshot_index: Option<&Bound<'_, PyAny>>,
+        description: Option<&str>,
+        sensitive: Option<Vec<String>>,
+        framework: Option<(String, String)>,
+        tags: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        let tags = parse_tags(tags)?;
+        let agent_json = state_to_json(state_json)?;
+        self.save_json(
+            py,
+            &agent_json,
+            path,
+            agent_id,
+            session_id,
+            snapshot_index,
+            description,
+            sensitive,
+            framework,
+            tags,
+            Payload::Json,
+            &CallOptions::default(),
+        )
+    }
+
+    /// Restore a JSON agent state; see the module-level `restore_json` for the arguments
+    #[pyo3(signature = (path, raw=false))]
+    fn restore_json(&self, py: Python<'_>, path: &str, raw: bool) -> PyResult<PyObject> {
+        let (metadata, agent_json) = self.with_engine(py, |engine| engine.load_snapshot(path))?;
+        Payload::Json.expect(path, &metadata)?;
+        if raw {
+            return Ok(PyString::new(py, &agent_json).into_any().unbind());
+        }
+        Ok(py
+            .import("json")?
+            .call_method1("loads", (agent_json,))?
+            .unbind())
+    }
+
+    /// Save raw bytes; see the module-level `snapshot_bytes` for the arguments
+    #[pyo3(signature = (data, path, agent_id="default_agent", session_id="default_session", snapshot_index=None, description=None, sensitive=None, tags=None, progress_callback=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn snapshot_bytes(
+        &self,
+        py: Python<'_>,
+        data: &Bound<'_, PyAny>,
+        path: &str,
+        agent_id: &str,
+        session_id: &str,
+        snapshot_index: Option<&Bound<'_, PyAny>>,
+        description: Option<&str>,
+        sensitive: Option<Vec<String>>,
+        tags: Option<&Bound<'_, PyDict>>,
+        progress_callback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        let tags = parse_tags(tags)?;
+        // b64encode accepts any bytes-like object and raises TypeError otherwise
+        let encoded: String = py
+            .import("base64")?
+            .call_method1("b64encode", (data,))?
+            .call_method1("decode", ("ascii",))?
+            .extract()?;
+        let agent_json = serde_json::Value::String(encoded).to_string();
+        with_progress(progress_callback, |options| {
+            self.save_json(
+                py,
+                &agent_json,
+                path,
+                agent_id,
+                session_id,
+                snapshot_index,
+                description,
+                sensitive,
+                None,
+                tags,
+                Payload::Bytes,
+                options,
+            )
+        })
+    }
+
+    /// Restore raw bytes; see the module-level `restore_bytes` for the arguments
+    #[pyo3(signature = (path, progress_callback=None))]
+    fn restore_bytes(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        progress_callback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<PyObject> {
+        let (metadata, agent_json) = self.load_with_progress(py, path, progress_callback)?;
+        Payload::Bytes.expect(path, &metadata)?;
+        let encoded: String = serde_json::from_str(&agent_json).map_err(|e| {
+            PyPersistError::new_err(format!("Snapshot {path} holds malformed bytes: {e}"))
+        })?;
+        Ok(py
+            .import("base64")?
+            .call_method1("b64decode", (encoded,))?
+            .unbind())
+    }
+
+    /// Metadata of a snapshot; see the module-level `get_metadata`
+    #[pyo3(signature = (path, reveal=false, as_dict=false))]
+    fn get_metadata(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        reveal: bool,
+        as_dict: bool,
+    ) -> PyResult<PyObject> {
+        let metadata = self.with_engine(py, |engine| engine.get_snapshot_metadata(path))?;
+        PySnapshotMetadata::new(&metadata, reveal, None)?.into_result(py, as_dict)
+    }
+
+    /// Update the description, tags or expiry of a snapshot; see the module-level
+    /// `update_metadata` for the arguments
+    #[pyo3(signature = (path, description=None, tags=None, remove_tags=None, expires_at=None, reveal=false, as_dict=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn update_metadata(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        description: Option<String>,
+        tags: Option<BTreeMap<String, String>>,
+        remove_tags: Option<Vec<String>>,
+        expires_at: Option<f64>,
+        reveal: bool,
+        as_dict: bool,
+    ) -> PyResult<PyObject> {
+        let expires_at = expires_at.map(timestamp_from_secs).transpose()?;
+
+        let metadata = self.with_engine(py, |engine| {
+            engine.update_metadata(
+                path,
+                Box::new(|metadata: &mut SnapshotMetadata| {
+                    if let Some(description) = description {
+                        metadata.description = Some(description);
+                    }
+                    for key in remove_tags.unwrap_or_default() {
+                        metadata.tags.remove(&key);
+                    }
+                    metadata.tags.extend(tags.unwrap_or_default());
+                    if expires_at.is_some() {
+                        metadata.expires_at = expires_at;
+                    }
+                }),
+            )
+        })?;
+
+        PySnapshotMetadata::new(&metadata, reveal, None)?.into_result(py, as_dict)
+    }
+
+    /// List snapshots matching a metadata query; see the module-level `list_snapshots`
+    #[pyo3(signature = (prefix="", agent_id=None, session_id=None, since=None, until=None, min_index=None, max_index=None, tags=None, limit=None, sort="newest", reveal=false, as_dict=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn list(
+        &self,
+        py: Python<'_>,
+        prefix: &str,
+        agent_id: Option<String>,
+        session_id: Option<String>,
+        since: Option<f64>,
+        until: Option<f64>,
+        min_index: Option<u64>,
+        max_index: Option<u64>,
+        tags: Option<BTreeMap<String, String>>,
+        limit: Option<usize>,
+        sort: &str,
+        reveal: bool,
+        as_dict: bool,
+    ) -> PyResult<PyObject> {
+        let query = SnapshotQuery {
+            agent_id,
+            session_id,
+            created_after: since.map(timestamp_from_secs).transpose()?,
+            created_before: until.map(timestamp_from_secs).transpose()?,
+            min_index,
+            max_index,
+            tags: tags.unwrap_or_default(),
+            limit,
+            sort: sort
+                .parse::<SortOrder>()
+                .map_err(|e| PyPersistConfigurationError::new_err(e.to_string()))?,
+        };
+
+        let summaries = self.with_engine(py, |engine| engine.query(prefix, &query))?;
+
+        let list = PyList::empty(py);
+        for summary in summaries {
+            let metadata = PySnapshotMetadata::new(&summary.metadata, reveal, Some(summary.path))?;
+            list.append(metadata.into_result(py, as_dict)?)?;
+        }
+
+        Ok(list.into())
+    }
+
+    /// Restore the latest snapshot of an agent; see the module-level `restore_latest`
+    #[pyo3(signature = (agent_id, session_id=None, prefix="", secrets_map=None, framework_policy=None, deserializer=None, as_dict=false, allow_pickle=false, pickle_allowlist=None, check_compatibility="warn"))]
+    #[allow(clippy::too_many_arguments)]
+    fn restore_latest(
+        &self,
+        py: Python<'_>,
+        agent_id: &str,
+        session_id: Option<&str>,
+        prefix: &str,
+        secrets_map: Option<&Bound<'_, PyDict>>,
+        framework_policy: Option<&str>,
+        deserializer: Option<&Bound<'_, PyAny>>,
+        as_dict: bool,
+        allow_pickle: bool,
+        pickle_allowlist: Option<&Bound<'_, PyAny>>,
+        check_compatibility: &str,
+    ) -> PyResult<(PyObject, PyObject)> {
+        let latest = self.latest_summary(py, agent_id, session_id, prefix)?;
+        let agent = self.restore(
+            py,
+            &latest.path,
+            secrets_map,
+            framework_policy,
+            deserializer,
+            None,
+            allow_pickle,
+            pickle_allowlist,
+            check_compatibility,
+        )?;
+        let metadata = PySnapshotMetadata::new(&latest.metadata, false, Some(latest.path))?;
+        Ok((agent, metadata.into_result(py, as_dict)?))
+    }
+
+    /// Metadata of the latest snapshot of an agent; see the module-level `latest_metadata`
+    #[pyo3(signature = (agent_id, session_id=None, prefix="", reveal=false, as_dict=false))]
+    fn latest_metadata(
+        &self,
+        py: Python<'_>,
+        agent_id: &str,
+        session_id: Option<&str>,
+        prefix: &str,
+        reveal: bool,
+        as_dict: bool,
+    ) -> PyResult<PyObject> {
+        let latest = self.latest_summary(py, agent_id, session_id, prefix)?;
+        PySnapshotMetadata::new(&latest.metadata, reveal, Some(latest.path))?
+            .into_result(py, as_dict)
+    }
+
+    /// Verify the integrity of a snapshot, raising if it fails
+    fn verify(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        self.with_engine(py, |engine| engine.verify_snapshot(path))
+    }
+
+    /// Verify a snapshot without raising for failures; see the module-level `verify`
+    fn verify_result(&self, py: Python<'_>, path: &str) -> PyResult<PyVerificationResult> {
+        self.with_engine(py, |engine| PyVerificationResult::check(engine, path))
+    }
+
+    /// Verify several snapshots; see the module-level `verify_many`
+    #[pyo3(signature = (paths, parallel=BATCH_PARALLELISM))]
+    fn verify_many(
+        &self,
+        py: Python<'_>,
+        paths: Vec<String>,
+        parallel: usize,
+    ) -> PyResult<Vec<PyVerificationResult>> {
+        self.map_paths(py, &paths, parallel, PyVerificationResult::check)?
+            .into_iter()
+            .map(|result| result.map_err(convert_error))
+            .collect()
+    }
+
+    /// Whether a snapshot exists
+    fn exists(&self, py: Python<'_>, path: &str) -> PyResult<bool> {
+        self.with_engine(py, |engine| Ok(engine.snapshot_exists(path)))
+    }
+
+    /// Delete a snapshot
+    fn delete(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        self.with_engine(py, |engine| engine.delete_snapshot(path))
+    }
+
+    /// Delete several snapshots; see the module-level `delete_snapshots`
+    #[pyo3(signature = (paths, parallel=BATCH_PARALLELISM))]
+    fn delete_many(
+        &self,
+        py: Python<'_>,
+        paths: Vec<String>,
+        parallel: usize,
+    ) -> PyResult<Py<PyDict>> {
+        self.delete_paths(py, paths, parallel)
+    }
+
+    /// Delete every snapshot under a prefix; see the module-level `delete_prefix`
+    #[pyo3(signature = (prefix, dry_run=false, parallel=BATCH_PARALLELISM))]
+    fn delete_prefix(
+        &self,
+        py: Python<'_>,
+        prefix: &str,
+        dry_run: bool,
+        parallel: usize,
+    ) -> PyResult<Py<PyDict>> {
+        // Only readable snapshots match, so other files under the prefix are kept
+        let query = SnapshotQuery {
+            sort: SortOrder::Unsorted,
+            ..SnapshotQuery::default()
+        };
+        let mut paths: Vec<String> = self
+            .with_engine(py, |engine| engine.query(prefix, &query))?
+            .into_iter()
+            .map(|summary| summary.path)
+            .collect();
+        paths.sort();
+
+        if dry_run {
+            let results = PyDict::new(py);
+            for path in paths {
+                results.set_item(path, py.None())?;
+            }
+            return Ok(results.unbind());
+        }
+        self.delete_paths(py, paths, parallel)
+    }
+
+    /// Delete the snapshots under a prefix that a retention policy rejects; see the
+    /// module-level `prune`
+    #[pyo3(signature = (prefix="", keep_last=None, older_than=None, max_total_bytes=None, dry_run=false, parallel=BATCH_PARALLELISM))]
+    #[allow(clippy::too_many_arguments)]
+    fn prune(
+        &self,
+        py: Python<'_>,
+        prefix: &str,
+        keep_last: Option<usize>,
+        older_than: Option<&Bound<'_, PyAny>>,
+        max_total_bytes: Option<u64>,
+        dry_run: bool,
+        parallel: usize,
+    ) -> PyResult<Py<PyDict>> {
+        let policy = parse_retention_policy(keep_last, older_than, max_total_bytes)?;
+        // The same listing and plan as `persist prune`, so the rules behave alike
+        let plan = self.with_engine(py, |engine| {
+            let snapshots = engine.query(prefix, &SnapshotQuery::new())?;
+            Ok(policy.plan(snapshots, Utc::now()))
+        })?;
+
+        let paths: Vec<String> = plan.delete.iter().map(|s| s.path.clone()).collect();
+        let outcomes = if dry_run {
+            paths.iter().map(|_| Ok(())).collect()
+        } else {
+            self.map_paths(py, &paths, parallel, |engine, path| {
+                engine.delete_snapshot(path)
+            })?
+        };
+
+        let deleted = PyList::empty(py);
+        let failed = PyDict::new(py);
+        let mut reclaimed_bytes = 0;
+        for (summary, outcome) in plan.delete.iter().zip(outcomes) {
+            match outcome {
+                Ok(()) => {
+                    deleted.append(&summary.path)?;
+                    reclaimed_bytes += retention::stored_size(&summary.metadata);
+                }
+                Err(e) => failed.set_item(&summary.path, convert_error(e).into_value(py))?,
+            }
+        }
+        let kept: Vec<&str> = plan.keep.iter().map(|s| s.path.as_str()).collect();
+
+        let result = PyDict::new(py);
+        result.set_item("dry_run", dry_run)?;
+        result.set_item("deleted", deleted)?;
+        result.set_item("failed", failed)?;
+        result.set_item("reclaimed_bytes", reclaimed_bytes)?;
+        result.set_item("kept", kept)?;
+        result.set_item("kept_bytes", plan.keep_size())?;
+        Ok(result.unbind())
+    }
+
+    /// Awaitable `snapshot`, run on the event loop's default executor
+    #[pyo3(signature = (*args, **kwargs))]
+    fn async_snapshot<'py>(
+        slf: &Bound<'py, Self>,
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.getattr("snapshot")?, args, kwargs)
+    }
+
+    /// Awaitable `restore`, run on the event loop's default executor
+    #[pyo3(signature = (*args, **kwargs))]
+    fn async_restore<'py>(
+        slf: &Bound<'py, Self>,
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.getattr("restore")?, args, kwargs)
+    }
+
+    /// Awaitable `get_metadata`, run on the event loop's default executor
+    #[pyo3(signature = (*args, **kwargs))]
+    fn async_get_metadata<'py>(
+        slf: &Bound<'py, Self>,
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.getattr("get_metadata")?, args, kwargs)
+    }
+
+    /// Awaitable `list`, run on the event loop's default executor
+    #[pyo3(signature = (*args, **kwargs))]
+    fn async_list<'py>(
+        slf: &Bound<'py, Self>,
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.getattr("list")?, args, kwargs)
+    }
+
+    /// Release the storage engine; later calls raise `PersistError`
+    fn close(&self, py: Python<'_>) {
+        // Waits for operations still running on other threads
+        let engine = py.allow_threads(|| {
+            self.engine
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take()
+        });
+        drop(engine);
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> bool {
+        self.close(py);
+        false
+    }
+}
+
+/// Save an agent snapshot with configurable storage backend
+///
+/// This function serializes a LangChain agent (or other compatible object) to a compressed
+/// snapshot file. Supports both local filesystem and Amazon S3 storage backends.
+///
+/// # Arguments
+/// * `agent` - The agent object to snapshot (must support LangChain serialization)
+/// * `path` - Storage path/key for the snapshot
+/// * `agent_id` - Optional unique identifier for the agent (default: "default_agent")
+/// * `session_id` - Optional session identifier (default: "default_session")
+/// * `snapshot_index` - Optional sequence number for this snapshot (default: 0), or "auto" to
+///   use one more than the highest existing index for this agent/session in the same directory
+/// * `description` - Optional human-readable description of the snapshot
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `sensitive` - Optional metadata fields to treat as sensitive ("description", "tags");
+///   they are redacted in returned metadata unless `reveal=True` is passed
+/// * `compression` - Compression algorithm: "gzip", "zstd" or "none" (default:
+///   `PERSIST_COMPRESSION`, then "gzip"); restoring detects the algorithm, so any snapshot can be read back
+/// * `compression_level` - Codec level: 0-9 for gzip, 1-22 for zstd (default: the codec's own)
+/// * `serializer` - Optional callable used instead of LangChain's `dumps`: it receives the agent
+///   and must return a JSON string. Exceptions it raises propagate unchanged. "pickle" pickles
+///   the agent instead, for objects that cannot be serialized otherwise. **Unpickling can run
+///   arbitrary code**, so such snapshots are only restored with `allow_pickle=True`; never use
+///   it for snapshots that others can write to
+/// * `tags` - Optional `str` to `str` tags, at most 64 with keys up to 128 and values up to
+///   256 bytes long; they can be filtered on with `list_snapshots`
+/// * `progress_callback` - Optional callable receiving `(bytes_done, bytes_total)` of the
+///   storage write, with the GIL held, at most every 100 ms and once it completes. An exception
+///   it raises aborts the write; nothing is saved locally and propagates unchanged
+///
+/// # Returns
+/// None on success
+///
+/// # Raises
+/// * IOError - If saving fails, JSON serialization fails, or integrity check fails
+/// * PersistConfigurationError - If `serializer` is a string other than "pickle"
+/// * TypeError - If a tag key or value is not a `str`
+/// * ValueError - If the tags exceed their size limits
+///
+/// # Example
+/// ```python
+/// import persist
+/// from langchain.chains import ConversationChain
+///
+/// # Local storage
+/// persist.snapshot(agent, "snapshots/agent1.json.gz")
+///
+/// # S3 storage
+/// persist.snapshot(agent, "agent1/session1/snapshot.json.gz",
+///                 storage_mode="s3",
+///                 s3_bucket="my-snapshots-bucket",
+///                 agent_id="conversation_agent")
+/// ```
+#[pyfunction]
+#[pyo3(signature = (agent, path, agent_id="default_agent", session_id="default_session", snapshot_index=None, description=None, *, sensitive=None, compression=None, compression_level=None, serializer=None, tags=None, progress_callback=None, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn snapshot(
+    py: Python<'_>,
+    agent: &Bound<'_, PyAny>,
+    path: &str,
+    agent_id: &str,
+    session_id: &str,
+    snapshot_index: Option<&Bound<'_, PyAny>>,
+    description: Option<&str>,
+    sensitive: Option<Vec<String>>,
+    compression: Option<&str>,
+    compression_level: Option<i32>,
+    serializer: Option<&Bound<'_, PyAny>>,
+    tags: Option<&Bound<'_, PyDict>>,
+    progress_callback: Option<&Bound<'_, PyAny>>,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<()> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, compression, compression_level, true)?.snapshot(
+        py,
+        agent,
+        path,
+        agent_id,
+        session_id,
+        snapshot_index,
+        description,
+        sensitive,
+        serializer,
+        tags,
+        progress_callback,
+    )
+}
+
+/// Restore an agent snapshot with configurable storage backend
+///
+/// This function loads a compressed snapshot file and reconstructs the original agent
+/// object using LangChain's loads() function. Supports both local and S3 storage.
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot to restore
+/// * `secrets_map` - Optional dictionary of secrets/API keys for the restored agent
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `framework_policy` - Optional framework compatibility check against the installed
+///   LangChain version: "ignore", "framework", "major", "minor" or "exact" (default: no check)
+/// * `strict_format` - Reject snapshots with an incompatible format version (default: True);
+///   when False they are loaded on a best-effort basis with a logged warning
+/// * `deserializer` - Optional callable used instead of LangChain's `loads`: it receives the
+///   JSON string, plus `secrets_map` when one is given, and returns the restored agent.
+///   Exceptions it raises propagate unchanged
+/// * `progress_callback` - Optional callable receiving `(bytes_done, bytes_total)` of the
+///   storage read, with the GIL held, at most every 100 ms and once it completes. An exception
+///   it raises aborts the read and propagates unchanged
+/// * `allow_pickle` - Unpickle snapshots saved with `serializer="pickle"` (default: False).
+///   **Unpickling can run arbitrary code**: only allow it for snapshots from a trusted source
+/// * `pickle_allowlist` - Optional iterable of `"module.name"` globals, such as
+///   `"myagents.Agent"`, that unpickling may load; any other raises `pickle.UnpicklingError`
+/// * `check_compatibility` - What to do when the snapshot records another LangChain
+///   major.minor version than the installed one: "error", "warn" (a `UserWarning` naming
+///   both versions) or "ignore" (default: "warn"). Snapshots without a recorded version
+///   are not checked
+///
+/// # Returns
+/// The restored agent object
+///
+/// # Raises
+/// * IOError - If loading fails, decompression fails, or integrity check fails
+/// * PersistFrameworkMismatchError - If the snapshot's framework version violates `framework_policy`
+/// * PersistCompatibilityError - If `check_compatibility` is "error" and the LangChain
+///   versions differ
+/// * PersistValidationError - If the snapshot holds raw bytes saved with `snapshot_bytes`, or
+///   was pickled and `allow_pickle` is not set
+///
+/// # Example
+/// ```python
+/// import persist
+/// import os
+///
+/// # Set up AWS credentials for S3 access
+/// os.environ["AWS_ACCESS_KEY_ID"] = "your-access-key"
+/// os.environ["AWS_SECRET_ACCESS_KEY"] = "your-secret-key"
+/// os.environ["AWS_REGION"] = "us-west-2"
+///
+/// # Restore from S3
+/// restored_agent = persist.restore("agent1/session1/snapshot.json.gz",
+///                                storage_mode="s3",
+///                                s3_bucket="my-snapshots-bucket")
+/// ```
+#[pyfunction]
+#[pyo3(signature = (path, secrets_map=None, *, framework_policy=None, strict_format=true, deserializer=None, progress_callback=None, allow_pickle=false, pickle_allowlist=None, check_compatibility="warn", **storage))]
+#[allow(clippy::too_many_arguments)]
+fn restore(
+    py: Python<'_>,
+    path: &str,
+    secrets_map: Option<&Bound<'_, PyDict>>,
+    framework_policy: Option<&str>,
+    strict_format: bool,
+    deserializer: Option<&Bound<'_, PyAny>>,
+    progress_callback: Option<&Bound<'_, PyAny>>,
+    allow_pickle: bool,
+    pickle_allowlist: Option<&Bound<'_, PyAny>>,
+    check_compatibility: &str,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, Some("gzip"), None, strict_format)?.restore(
+        py,
+        path,
+        secrets_map,
+        framework_policy,
+        deserializer,
+        progress_callback,
+        allow_pickle,
+        pickle_allowlist,
+        check_compatibility,
+    )
+}
+
+/// Save several snapshots with one storage engine
+///
+/// Each agent is serialized in turn, then the snapshots are written concurrently with the
+/// GIL released. A failing item does not stop the others: its error is returned in its
+/// place instead of being raised.
+///
+/// # Arguments
+/// * `items` - Iterable of dicts, each with the state to save under exactly one of `agent`
+///   (serialized like `snapshot` does) or `state_json` (saved like `snapshot_json` does), its
+///   `path`, and optionally `agent_id`, `session_id`, `snapshot_index`, `description`,
+///   `sensitive` and `tags`, as for `snapshot`. An "auto" `snapshot_index` is resolved when
+///   the item is written, so items of the same agent and session should not both use it
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `compression` - Compression algorithm: "gzip", "zstd" or "none" (default:
+///   `PERSIST_COMPRESSION`, then "gzip")
+/// * `compression_level` - Codec level: 0-9 for gzip, 1-22 for zstd (default: the codec's own)
+/// * `serializer` - Optional serializer for the `agent` items, as for `snapshot`
+/// * `parallel` - Maximum number of snapshots written concurrently (default: 8)
+/// * `as_dict` - Return the metadata as plain dictionaries instead of `SnapshotMetadata`
+///
+/// # Returns
+/// A list with a dict per item, in the order given: `{"path", "metadata", "error"}`, where
+/// `metadata` is the saved snapshot's metadata and `error` None, or `metadata` is None and
+/// `error` the exception the item raised
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// results = persist.snapshot_many([
+///     {"agent": agent, "path": "agent1/s1.json.gz", "agent_id": "agent1"},
+///     {"state_json": {"step": 3}, "path": "agent2/s1.json.gz", "agent_id": "agent2"},
+/// ])
+/// failed = [r for r in results if r["error"] is not None]
+/// ```
+#[pyfunction]
+#[pyo3(signature = (items, *, compression=None, compression_level=None, serializer=None, parallel=BATCH_PARALLELISM, as_dict=false, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn snapshot_many(
+    py: Python<'_>,
+    items: &Bound<'_, PyAny>,
+    compression: Option<&str>,
+    compression_level: Option<i32>,
+    serializer: Option<&Bound<'_, PyAny>>,
+    parallel: usize,
+    as_dict: bool,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<Py<PyDict>>> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, compression, compression_level, true)?
+        .snapshot_many(py, items, serializer, parallel, as_dict)
+}
+
+/// Restore several snapshots with one storage engine
+///
+/// The snapshots are read concurrently with the GIL released, then deserialized in turn
+/// like `restore` does. A failing snapshot does not stop the others: its error is returned
+/// in its place instead of being raised.
+///
+/// # Arguments
+/// * `paths` - Storage paths/keys of the snapshots to restore
+/// * `secrets_map` - Optional dictionary of secrets/API keys for the restored agents
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `framework_policy`, `strict_format`, `deserializer`, `allow_pickle`, `pickle_allowlist`,
+///   `check_compatibility` - As for `restore`
+/// * `parallel` - Maximum number of snapshots read concurrently (default: 8)
+/// * `as_dict` - Return the metadata as plain dictionaries instead of `SnapshotMetadata`
+///
+/// # Returns
+/// A list with a dict per path, in the order given: `{"path", "agent", "metadata", "error"}`,
+/// where `error` is None, or the exception restoring raised with `agent` and `metadata` None
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// paths = [s.path for s in persist.list_snapshots("agent1/")]
+/// agents = [r["agent"] for r in persist.restore_many(paths) if r["error"] is None]
+/// ```
+#[pyfunction]
+#[pyo3(signature = (paths, secrets_map=None, *, framework_policy=None, strict_format=true, deserializer=None, allow_pickle=false, pickle_allowlist=None, parallel=BATCH_PARALLELISM, as_dict=false, check_compatibility="warn", **storage))]
+#[allow(clippy::too_many_arguments)]
+fn restore_many(
+    py: Python<'_>,
+    paths: Vec<String>,
+    secrets_map: Option<&Bound<'_, PyDict>>,
+    framework_policy: Option<&str>,
+    strict_format: bool,
+    deserializer: Option<&Bound<'_, PyAny>>,
+    allow_pickle: bool,
+    pickle_allowlist: Option<&Bound<'_, PyAny>>,
+    parallel: usize,
+    as_dict: bool,
+    check_compatibility: &str,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<Py<PyDict>>> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, Some("gzip"), None, strict_format)?.restore_many(
+        py,
+        paths,
+        secrets_map,
+        framework_policy,
+        deserializer,
+        allow_pickle,
+        pickle_allowlist,
+        parallel,
+        as_dict,
+        check_compatibility,
+    )
+}
+
+/// Save a JSON agent state without going through LangChain
+///
+/// For agents that keep their own state: the state is stored as is, with the same
+/// integrity checks and metadata as `snapshot`. No framework is recorded unless one is
+/// passed.
+///
+/// # Arguments
+/// * `state_json` - The state as a JSON string, or any value `json.dumps` accepts
+/// * `path` - Storage path/key for the snapshot
+/// * `agent_id`, `session_id`, `snapshot_index`, `description`, `sensitive` - As for `snapshot`
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `compression` - Compression algorithm: "gzip", "zstd" or "none" (default:
+///   `PERSIST_COMPRESSION`, then "gzip"); restoring detects the algorithm, so any snapshot can be read back
+/// * `compression_level` - Codec level: 0-9 for gzip, 1-22 for zstd (default: the codec's own)
+/// * `framework` - Optional `(name, version)` of the framework the state comes from, recorded
+///   in the snapshot metadata
+/// * `tags` - Optional `str` to `str` tags, as for `snapshot`
+///
+/// # Raises
+/// * PersistError - If the state is not valid JSON or cannot be serialized
+/// * PersistConfigurationError - If the compression algorithm or level is invalid
+/// * TypeError, ValueError - If the tags are invalid, as for `snapshot`
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// persist.snapshot_json({"step": 3, "notes": ["a", "b"]}, "agent1/state.json.gz")
+/// state = persist.restore_json("agent1/state.json.gz")
+/// ```
+#[pyfunction]
+#[pyo3(signature = (state_json, path, agent_id="default_agent", session_id="default_session", snapshot_index=None, description=None, *, sensitive=None, compression=None, compression_level=None, framework=None, tags=None, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn snapshot_json(
+    py: Python<'_>,
+    state_json: &Bound<'_, PyAny>,
+    path: &str,
+    agent_id: &str,
+    session_id: &str,
+    snapshot_index: Option<&Bound<'_, PyAny>>,
+    description: Option<&str>,
+    sensitive: Option<Vec<String>>,
+    compression: Option<&str>,
+    compression_level: Option<i32>,
+    framework: Option<(String, String)>,
+    tags: Option<&Bound<'_, PyDict>>,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<()> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, compression, compression_level, true)?.snapshot_json(
+        py,
+        state_json,
+        path,
+        agent_id,
+        session_id,
+        snapshot_index,
+        description,
+        sensitive,
+        framework,
+        tags,
+    )
+}
+
+/// Restore a JSON agent state without going through LangChain
+///
+/// Works on any JSON snapshot, including ones written by `snapshot`.
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot to restore
+/// * `raw` - Return the state as a JSON string instead of parsing it (default: False)
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `strict_format` - Reject snapshots with an incompatible format version (default: True)
+///
+/// # Returns
+/// The parsed state (usually a dict), or its JSON text when `raw=True`
+///
+/// # Raises
+/// * PersistValidationError - If the snapshot holds raw bytes saved with `snapshot_bytes` or a
+///   pickled agent
+#[pyfunction]
+#[pyo3(signature = (path, raw=false, *, strict_format=true, **storage))]
+fn restore_json(
+    py: Python<'_>,
+    path: &str,
+    raw: bool,
+    strict_format: bool,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, Some("gzip"), None, strict_format)?
+        .restore_json(py, path, raw)
+}
+
+/// Save already-serialized state, such as protobuf or msgpack, as raw bytes
+///
+/// The bytes are stored base64-encoded inside the usual snapshot container, so
+/// compression and integrity checks apply, and the metadata records the payload as
+/// `"persist.payload": "bytes"`. `restore` and `restore_json` refuse such snapshots.
+///
+/// # Arguments
+/// * `data` - The payload: `bytes`, `bytearray` or another bytes-like object
+/// * `path` - Storage path/key for the snapshot
+/// * `agent_id`, `session_id`, `snapshot_index`, `description`, `sensitive`, `tags` - As for
+///   `snapshot`
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `compression` - Compression algorithm: "gzip", "zstd" or "none" (default:
+///   `PERSIST_COMPRESSION`, then "gzip")
+/// * `compression_level` - Codec level: 0-9 for gzip, 1-22 for zstd (default: the codec's own)
+/// * `progress_callback` - Optional callable receiving `(bytes_done, bytes_total)`, as for
+///   `snapshot`
+///
+/// # Raises
+/// * TypeError - If `data` is not bytes-like, or the tags are not `str` to `str`
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// persist.snapshot_bytes(state.SerializeToString(), "agent1/state.bin.gz")
+/// data = persist.restore_bytes("agent1/state.bin.gz")
+/// ```
+#[pyfunction]
+#[pyo3(signature = (data, path, agent_id="default_agent", session_id="default_session", snapshot_index=None, description=None, *, sensitive=None, compression=None, compression_level=None, tags=None, progress_callback=None, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn snapshot_bytes(
+    py: Python<'_>,
+    data: &Bound<'_, PyAny>,
+    path: &str,
+    agent_id: &str,
+    session_id: &str,
+    snapshot_index: Option<&Bound<'_, PyAny>>,
+    description: Option<&str>,
+    sensitive: Option<Vec<String>>,
+    compression: Option<&str>,
+    compression_level: Option<i32>,
+    tags: Option<&Bound<'_, PyDict>>,
+    progress_callback: Option<&Bound<'_, PyAny>>,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<()> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, compression, compression_level, true)?.snapshot_bytes(
+        py,
+        data,
+        path,
+        agent_id,
+        session_id,
+        snapshot_index,
+        description,
+        sensitive,
+        tags,
+        progress_callback,
+    )
+}
+
+/// Restore the bytes saved by `snapshot_bytes`, exactly as they were passed
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot to restore
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `strict_format` - Reject snapshots with an incompatible format version (default: True)
+/// * `progress_callback` - Optional callable receiving `(bytes_done, bytes_total)`, as for
+///   `restore`
+///
+/// # Returns
+/// The original payload as `bytes`
+///
+/// # Raises
+/// * PersistValidationError - If the snapshot holds agent JSON rather than bytes
+#[pyfunction]
+#[pyo3(signature = (path, *, strict_format=true, progress_callback=None, **storage))]
+fn restore_bytes(
+    py: Python<'_>,
+    path: &str,
+    strict_format: bool,
+    progress_callback: Option<&Bound<'_, PyAny>>,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, Some("gzip"), None, strict_format)?.restore_bytes(
+        py,
+        path,
+        progress_callback,
+    )
+}
+
+/// Get metadata for a snapshot without loading the full snapshot
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `reveal` - Return sensitive fields unredacted (fails if they are encrypted)
+/// * `as_dict` - Return a plain dictionary instead of `SnapshotMetadata`
+///
+/// # Returns
+/// The snapshot's `SnapshotMetadata`
+#[pyfunction]
+#[pyo3(signature = (path, *, reveal=false, as_dict=false, **storage))]
+fn get_metadata(
+    py: Python<'_>,
+    path: &str,
+    reveal: bool,
+    as_dict: bool,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    PersistClient::for_call(py, storage)?.get_metadata(py, path, reveal, as_dict)
+}
+
+/// Update the description, tags or expiry of a snapshot without rewriting its state
+///
+/// The agent state, content hash and creation timestamp are left untouched.
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot
+/// * `description` - New description (unchanged if None)
+/// * `tags` - Tags to add or overwrite
+/// * `remove_tags` - Tag keys to remove
+/// * `expires_at` - New expiry as a Unix timestamp in seconds (unchanged if None)
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `reveal` - Return sensitive fields unredacted (fails if they are encrypted)
+/// * `as_dict` - Return a plain dictionary instead of `SnapshotMetadata`
+///
+/// # Returns
+/// The updated `SnapshotMetadata`
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// persist.update_metadata("snapshots/agent1.json.gz",
+///                         description="last good state",
+///                         tags={"status": "good"})
+/// ```
+#[pyfunction]
+#[pyo3(signature = (path, description=None, tags=None, remove_tags=None, expires_at=None, *, reveal=false, as_dict=false, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn update_metadata(
+    py: Python<'_>,
+    path: &str,
+    description: Option<String>,
+    tags: Option<BTreeMap<String, String>>,
+    remove_tags: Option<Vec<String>>,
+    expires_at: Option<f64>,
+    reveal: bool,
+    as_dict: bool,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    PersistClient::for_call(py, storage)?.update_metadata(
+        py,
+        path,
+        description,
+        tags,
+        remove_tags,
+        expires_at,
+        reveal,
+        as_dict,
+    )
+}
+
+/// List snapshots matching a metadata query
+///
+/// # Arguments
+/// * `prefix` - Only consider snapshots whose path starts with this prefix (default: all)
+/// * `agent_id` - Only match snapshots of this agent
+/// * `session_id` - Only match snapshots of this session
+/// * `since` - Only match snapshots created at or after this Unix timestamp (seconds)
+/// * `until` - Only match snapshots created before this Unix timestamp (seconds)
+/// * `min_index` - Only match snapshots with at least this index
+/// * `max_index` - Only match snapshots with at most this index
+/// * `tags` - Only match snapshots carrying all of these tags
+/// * `limit` - Maximum number of snapshots to return
+/// * `sort` - "newest", "oldest", "index", "index_desc", "size", "size_desc", "agent",
+///   "agent_desc" or "none" (default: "newest")
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `reveal` - Return sensitive fields unredacted (fails if they are encrypted)
+/// * `as_dict` - Return plain dictionaries instead of `SnapshotMetadata` objects
+///
+/// # Returns
+/// List of `SnapshotMetadata`, each with its `path` set. A prefix nothing is stored under
+/// gives an empty list; files under it that are not snapshots are skipped.
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// recent = persist.list_snapshots("snapshots/", agent_id="agent1", min_index=5, limit=20)
+/// for snap in recent:
+///     print(snap["path"], snap["snapshot_index"])
+/// ```
+#[pyfunction]
+#[pyo3(signature = (prefix="", agent_id=None, session_id=None, since=None, until=None, min_index=None, max_index=None, tags=None, limit=None, sort="newest", *, reveal=false, as_dict=false, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn list_snapshots(
+    py: Python<'_>,
+    prefix: &str,
+    agent_id: Option<String>,
+    session_id: Option<String>,
+    since: Option<f64>,
+    until: Option<f64>,
+    min_index: Option<u64>,
+    max_index: Option<u64>,
+    tags: Option<BTreeMap<String, String>>,
+    limit: Option<usize>,
+    sort: &str,
+    reveal: bool,
+    as_dict: bool,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    PersistClient::for_call(py, storage)?.list(
+        py, prefix, agent_id, session_id, since, until, min_index, max_index, tags, limit, sort,
+        reveal, as_dict,
+    )
+}
+
+/// Restore the latest snapshot of an agent
+///
+/// Within one session the snapshot with the highest index is the latest; across
+/// sessions the most recently created one is. Ties go to the later timestamp (or
+/// higher index), then to the greatest path, the same resolution as `persist latest`.
+///
+/// # Arguments
+/// * `agent_id` - Agent whose latest snapshot to restore
+/// * `session_id` - Only consider snapshots of this session (default: all sessions)
+/// * `prefix` - Only consider snapshots whose path starts with this prefix (default: all)
+/// * `secrets_map` - Optional secrets passed to LangChain's `loads`
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `framework_policy`, `strict_format`, `deserializer` - As for `restore`
+/// * `as_dict` - Return the metadata as a plain dictionary instead of `SnapshotMetadata`
+/// * `allow_pickle`, `pickle_allowlist`, `check_compatibility` - As for `restore`
+///
+/// # Returns
+/// A tuple of the restored agent and the snapshot's `SnapshotMetadata`, which includes
+/// its `path`
+///
+/// # Raises
+/// * FileNotFoundError - If the agent has no matching snapshot
+/// * PersistCompatibilityError - If `check_compatibility` is "error" and the LangChain
+///   versions differ
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// agent, metadata = persist.restore_latest("agent1", session_id="session1")
+/// print(metadata["path"], metadata["snapshot_index"])
+/// ```
+#[pyfunction]
+#[pyo3(signature = (agent_id, session_id=None, prefix="", secrets_map=None, *, framework_policy=None, strict_format=true, deserializer=None, as_dict=false, allow_pickle=false, pickle_allowlist=None, check_compatibility="warn", **storage))]
+#[allow(clippy::too_many_arguments)]
+fn restore_latest(
+    py: Python<'_>,
+    agent_id: &str,
+    session_id: Option<&str>,
+    prefix: &str,
+    secrets_map: Option<&Bound<'_, PyDict>>,
+    framework_policy: Option<&str>,
+    strict_format: bool,
+    deserializer: Option<&Bound<'_, PyAny>>,
+    as_dict: bool,
+    allow_pickle: bool,
+    pickle_allowlist: Option<&Bound<'_, PyAny>>,
+    check_compatibility: &str,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<(PyObject, PyObject)> {
+    let config = create_storage_config(storage)?;
+    PersistClient::from_config(py, config, Some("gzip"), None, strict_format)?.restore_latest(
+        py,
+        agent_id,
+        session_id,
+        prefix,
+        secrets_map,
+        framework_policy,
+        deserializer,
+        as_dict,
+        allow_pickle,
+        pickle_allowlist,
+        check_compatibility,
+    )
+}
+
+/// Get the metadata of the latest snapshot of an agent without loading it
+///
+/// The latest snapshot is resolved as for `restore_latest`.
+///
+/// # Arguments
+/// * `agent_id` - Agent whose latest snapshot to describe
+/// * `session_id` - Only consider snapshots of this session (default: all sessions)
+/// * `prefix` - Only consider snapshots whose path starts with this prefix (default: all)
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `reveal` - Return sensitive fields unredacted (fails if they are encrypted)
+/// * `as_dict` - Return a plain dictionary instead of `SnapshotMetadata`
+///
+/// # Returns
+/// The snapshot's `SnapshotMetadata`, including its `path`
+///
+/// # Raises
+/// * FileNotFoundError - If the agent has no matching snapshot
+#[pyfunction]
+#[pyo3(signature = (agent_id, session_id=None, prefix="", *, reveal=false, as_dict=false, **storage))]
+fn latest_metadata(
+    py: Python<'_>,
+    agent_id: &str,
+    session_id: Option<&str>,
+    prefix: &str,
+    reveal: bool,
+    as_dict: bool,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    PersistClient::for_call(py, storage)?
+        .latest_metadata(py, agent_id, session_id, prefix, reveal, as_dict)
+}
+
+/// Convert a Unix timestamp in seconds to a UTC datetime
+fn timestamp_from_secs(secs: f64) -> PyResult<DateTime<Utc>> {
+    DateTime::from_timestamp_millis((secs * 1000.0) as i64).ok_or_else(|| {
+        PyPersistConfigurationError::new_err(format!("Timestamp out of range: {secs}"))
+    })
+}
+
+/// Verify the integrity of a snapshot
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot to verify
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+///
+/// # Returns
+/// None on success (integrity verified)
+///
+/// # Raises
+/// * IOError - If verification fails or snapshot is corrupted
+#[pyfunction]
+#[pyo3(signature = (path, **storage))]
+fn verify_snapshot(
+    py: Python<'_>,
+    path: &str,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<()> {
+    PersistClient::for_call(py, storage)?.verify(py, path)
+}
+
+/// Verify the integrity of a snapshot, reporting failures instead of raising them
+///
+/// A missing, damaged or incompatible snapshot gives a result with `valid=False`;
+/// only storage problems that say nothing about the snapshot, such as missing
+/// credentials or an unreachable bucket, raise.
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot to verify
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+///
+/// # Returns
+/// A `VerificationResult`
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// result = persist.verify("snapshots/agent1.json.gz")
+/// if not result.valid:
+///     print(result.error, result.expected_hash, result.actual_hash)
+/// ```
+#[pyfunction]
+#[pyo3(signature = (path, **storage))]
+fn verify(
+    py: Python<'_>,
+    path: &str,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyVerificationResult> {
+    PersistClient::for_call(py, storage)?.verify_result(py, path)
+}
+
+/// Verify several snapshots with one storage engine, like `verify`
+///
+/// # Arguments
+/// * `paths` - Storage paths/keys of the snapshots to verify
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `parallel` - Maximum number of snapshots verified concurrently (default: 8)
+///
+/// # Returns
+/// A list with the `VerificationResult` of each path, in the order given
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// paths = [s.path for s in persist.list_snapshots("snapshots/")]
+/// broken = [r for r in persist.verify_many(paths) if not r.valid]
+/// ```
+#[pyfunction]
+#[pyo3(signature = (paths, *, parallel=BATCH_PARALLELISM, **storage))]
+fn verify_many(
+    py: Python<'_>,
+    paths: Vec<String>,
+    parallel: usize,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<PyVerificationResult>> {
+    PersistClient::for_call(py, storage)?.verify_many(py, paths, parallel)
+}
+
+/// Check if a snapshot exists
+///
+/// # Arguments
+/// * `path` - Storage path/key to check
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+///
+/// # Returns
+/// True if the snapshot exists, False otherwise
+#[pyfunction]
+#[pyo3(signature = (path, **storage))]
+fn snapshot_exists(
+    py: Python<'_>,
+    path: &str,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<bool> {
+    let config = match create_storage_config(storage) {
+        Ok(config) => config,
+        // A backend missing from this build, a mistyped storage argument or an unusable
+        // setting, such as an invalid PERSIST_* variable, is reported rather than treated
+        // as absent
+        Err(e)
+            if e.is_instance_of::<PyImportError>(py)
+                || e.is_instance_of::<PyTypeError>(py)
+                || e.is_instance_of::<PyPersistConfigurationError>(py) =>
+        {
+            return Err(e)
+        }
+        Err(_) => StorageConfig::default_local(), // Fallback to local on error
+    };
+
+    match PersistClient::from_config(py, config, Some("gzip"), None, true) {
+        Ok(client) => client.exists(py, path),
+        Err(_) => Ok(false), // If engine creation fails, assume snapshot doesn't exist
+    }
+}
+
+/// Delete a snapshot
+///
+/// # Arguments
+/// * `path` - Storage path/key of the snapshot to delete
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+///
+/// # Returns
+/// None on success
+///
+/// # Raises
+/// * IOError - If deletion fails
+#[pyfunction]
+#[pyo3(signature = (path, **storage))]
+fn delete_snapshot(
+    py: Python<'_>,
+    path: &str,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<()> {
+    PersistClient::for_call(py, storage)?.delete(py, path)
+}
+
+/// Delete several snapshots with one storage engine
+///
+/// Deletions run concurrently, at most `parallel` at a time. A failed deletion does
+/// not raise or stop the others; it is reported in the result instead.
+///
+/// # Arguments
+/// * `paths` - Storage paths/keys of the snapshots to delete
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `parallel` - Maximum number of concurrent deletions (default: 8)
+///
+/// # Returns
+/// Dictionary mapping each path to None if it was deleted, or to the `PersistError`
+/// that deleting it raised
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// results = persist.delete_snapshots(["run1/a.json.gz", "run1/b.json.gz"], base_dir="snapshots")
+/// failed = {path: error for path, error in results.items() if error is not None}
+/// ```
+#[pyfunction]
+#[pyo3(signature = (paths, *, parallel=BATCH_PARALLELISM, **storage))]
+fn delete_snapshots(
+    py: Python<'_>,
+    paths: Vec<String>,
+    parallel: usize,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyDict>> {
+    PersistClient::for_call(py, storage)?.delete_many(py, paths, parallel)
+}
+
+/// Delete every snapshot whose path starts with a prefix
+///
+/// The prefix is listed first, and only the readable snapshots found are deleted;
+/// other files under it are left alone. With a `base_dir` (or a GCS prefix) the
+/// listing cannot reach outside it, and a prefix that escapes `base_dir` raises
+/// `PersistValidationError` before anything is deleted.
+///
+/// # Arguments
+/// * `prefix` - Path/key prefix of the snapshots to delete
+/// * `dry_run` - Only report what would be deleted (default: False)
+/// * `storage_mode` - Storage backend: "local", "s3" or "gcs" (default: `PERSIST_STORAGE_MODE`,
+///   then "local")
+/// * `s3_bucket` - S3 bucket name (required for S3 mode; default: `PERSIST_S3_BUCKET`)
+/// * `s3_region` - S3 region (optional, uses `PERSIST_S3_REGION`, then the AWS environment)
+/// * `s3_endpoint_url` - S3-compatible endpoint such as MinIO (optional, uses `AWS_ENDPOINT_URL`)
+/// * `s3_access_key_id`, `s3_secret_access_key` - Static S3 credentials (optional, uses the AWS
+///   credential chain); `s3_session_token` adds the token of temporary STS credentials
+/// * `s3_force_path_style` - Address S3 buckets by path, as MinIO needs (default: False)
+/// * `gcs_bucket` - GCS bucket name (required for GCS mode)
+/// * `gcs_prefix` - Optional object prefix within the GCS bucket
+/// * `gcs_credentials_path` - Service account JSON file (optional, uses Application Default Credentials)
+/// * `base_dir` - Directory for local snapshots; paths become keys relative to it and may not
+///   escape it (local mode only; default: `PERSIST_BASE_DIR`)
+/// * `durable_writes` - Sync local files to disk before returning (default: False)
+/// * `file_permissions` - Unix permission bits for new local files, e.g. `0o600`
+/// * `parallel` - Maximum number of concurrent deletions (default: 8)
+///
+/// # Returns
+/// Dictionary mapping each matched path to None if it was (or with `dry_run` would
+/// be) deleted, or to the exception that deleting it raised
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// doomed = persist.delete_prefix("experiments/run-7/", dry_run=True, base_dir="snapshots")
+/// persist.delete_prefix("experiments/run-7/", base_dir="snapshots")
+/// ```
+#[pyfunction]
+#[pyo3(signature = (prefix, dry_run=false, *, parallel=BATCH_PARALLELISM, **storage))]
+fn delete_prefix(
+    py: Python<'_>,
+    prefix: &str,
+    dry_run: bool,
+    parallel: usize,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyDict>> {
+    PersistClient::for_call(py, storage)?.delete_prefix(py, prefix, dry_run, parallel)
+}
+
+/// Delete the snapshots under a prefix that a retention policy rejects
+///
+/// The rules are those of `persist prune` and combine the same way: `keep_last` and
+/// `older_than` select snapshots first, then `max_total_bytes` deletes the oldest
+/// survivors until the rest fit. At least one rule is required.
+///
+/// # Arguments
+/// * `prefix` - Path/key prefix of the snapshots to consider (default: all)
+/// * `keep_last` - Keep only this many snapshots (highest indexes) per agent and session
+/// * `older_than` - Delete snapshots created longer ago than this, in seconds or as a
+///   `datetime.timedelta`
+/// * `max_total_bytes` - Delete the oldest snapshots until the rest take at most this
+///   many stored bytes
+/// * `dry_run` - Only report what would be deleted (default: False)
+/// * `storage_mode`, `s3_bucket`, ... - The storage arguments of `delete_prefix`
+/// * `parallel` - Maximum number of concurrent deletions (default: 8)
+///
+/// # Returns
+/// Dictionary with `deleted`, the sorted paths that were (or with `dry_run` would be)
+/// deleted; `failed`, mapping paths that could not be deleted to the exception raised;
+/// `reclaimed_bytes`, the stored size of the deleted snapshots; `kept` and `kept_bytes`
+/// for the survivors; and `dry_run`
+///
+/// # Raises
+/// * ValueError - If no rule is given, `older_than` is negative or `parallel` is 0
+/// * TypeError - If `older_than` is neither a number nor a timedelta
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// persist.snapshot(agent, f"runs/7/step-{step}.json.gz", agent_id="run-7", snapshot_index=step)
+/// persist.prune("runs/7/", keep_last=5)
+/// ```
+#[pyfunction]
+#[pyo3(signature = (prefix="", keep_last=None, older_than=None, max_total_bytes=None, dry_run=false, *, parallel=BATCH_PARALLELISM, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn prune(
+    py: Python<'_>,
+    prefix: &str,
+    keep_last: Option<usize>,
+    older_than: Option<&Bound<'_, PyAny>>,
+    max_total_bytes: Option<u64>,
+    dry_run: bool,
+    parallel: usize,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyDict>> {
+    PersistClient::for_call(py, storage)?.prune(
+        py,
+        prefix,
+        keep_last,
+        older_than,
+        max_total_bytes,
+        dry_run,
+        parallel,
+    )
+}
+
+/// Copy a snapshot, possibly to another storage backend
+///
+/// The stored bytes are copied as they are: the snapshot is not recompressed and its
+/// metadata is not rewritten, so the copy keeps its timestamp and content hash. This is
+/// cheaper and more faithful than `restore` followed by `snapshot`.
+///
+/// # Arguments
+/// * `src_path` - Storage path/key of the snapshot to copy
+/// * `dst_path` - Storage path/key of the copy
+/// * `src_storage`, `dst_storage` - Where the snapshot is and where the copy goes: a
+///   `PersistClient`, a dictionary of `PersistClient` arguments such as
+///   `{"storage_mode": "s3", "s3_bucket": "my-bucket"}`, or None for the defaults
+/// * `verify` - Check the snapshot before writing the copy, then read the copy back and
+///   compare it (default: True); with False the bytes are copied unchecked
+/// * `reveal` - Return sensitive metadata fields instead of redacting them (default: False)
+/// * `as_dict` - Return a dictionary instead of a `SnapshotMetadata` (default: False)
+///
+/// # Returns
+/// Metadata of the copy
+///
+/// # Raises
+/// * OSError or PersistError - If the source snapshot cannot be read
+/// * PersistIntegrityError - If verification fails; nothing is written
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// persist.copy_snapshot("scratch/agent1.json.gz", "agents/agent1.json.gz",
+///                       src_storage={"base_dir": "/tmp/scratch"},
+///                       dst_storage={"storage_mode": "s3", "s3_bucket": "my-bucket"})
+/// ```
+#[pyfunction]
+#[pyo3(signature = (src_path, dst_path, src_storage=None, dst_storage=None, verify=true, reveal=false, as_dict=false))]
+#[allow(clippy::too_many_arguments)]
+fn copy_snapshot(
+    py: Python<'_>,
+    src_path: &str,
+    dst_path: &str,
+    src_storage: Option<&Bound<'_, PyAny>>,
+    dst_storage: Option<&Bound<'_, PyAny>>,
+    verify: bool,
+    reveal: bool,
+    as_dict: bool,
+) -> PyResult<PyObject> {
+    let source = PersistClient::for_storage(py, "src_storage", src_storage)?;
+    let target = PersistClient::for_storage(py, "dst_storage", dst_storage)?;
+    source.get().copy_to(
+        py,
+        target.get(),
+        src_path,
+        dst_path,
+        verify,
+        false,
+        reveal,
+        as_dict,
+    )
+}
+
+/// Move a snapshot, possibly to another storage backend
+///
+/// Copies the snapshot like `copy_snapshot`, then deletes the source. The source is only
+/// deleted once the copy is complete and, with `verify`, verified; if anything fails
+/// the source is left untouched. Takes the same arguments as `copy_snapshot`.
+///
+/// # Returns
+/// Metadata of the snapshot at its new location
+///
+/// # Raises
+/// * OSError or PersistError - If the source snapshot cannot be read
+/// * PersistIntegrityError - If verification fails; nothing is written or deleted
+/// * PersistValidationError - If source and destination are the same snapshot
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// persist.move_snapshot("scratch/agent1.json.gz", "kept/agent1.json.gz", dst_storage=client)
+/// ```
+#[pyfunction]
+#[pyo3(signature = (src_path, dst_path, src_storage=None, dst_storage=None, verify=true, reveal=false, as_dict=false))]
+#[allow(clippy::too_many_arguments)]
+fn move_snapshot(
+    py: Python<'_>,
+    src_path: &str,
+    dst_path: &str,
+    src_storage: Option<&Bound<'_, PyAny>>,
+    dst_storage: Option<&Bound<'_, PyAny>>,
+    verify: bool,
+    reveal: bool,
+    as_dict: bool,
+) -> PyResult<PyObject> {
+    let source = PersistClient::for_storage(py, "src_storage", src_storage)?;
+    let target = PersistClient::for_storage(py, "dst_storage", dst_storage)?;
+    source.get().copy_to(
+        py,
+        target.get(),
+        src_path,
+        dst_path,
+        verify,
+        true,
+        reveal,
+        as_dict,
+    )
+}
+
+/// Compare two snapshots
+///
+/// Both snapshots are loaded (and so verified). Metadata is compared field by field,
+/// with sensitive fields compared in redacted form; agent states are compared as JSON,
+/// objects key by key and arrays index by index.
+///
+/// # Arguments
+/// * `path_a` - Storage path/key of the first snapshot
+/// * `path_b` - Storage path/key of the second snapshot
+/// * `a_storage`, `b_storage` - Where either snapshot is, when it is not in the storage
+///   given by the other arguments: a `PersistClient` or a dictionary of `PersistClient`
+///   arguments such as `{"storage_mode": "s3", "s3_bucket": "my-bucket"}`
+/// * `max_value_len` - Replace values whose JSON text is longer than this by a string of
+///   its first `max_value_len` characters followed by "…" (default: no truncation)
+/// * `paths_only` - List only the JSON Pointers of the changed state values (default: False)
+/// * `storage_mode`, `s3_bucket`, ... - The storage arguments of `PersistClient`, used for
+///   the snapshots without `a_storage`/`b_storage`
+///
+/// # Returns
+/// Dictionary with `metadata`, mapping each changed field to `{"old": ..., "new": ...}`
+/// (changed tags are grouped the same way under `"tags"`, with None for a missing tag),
+/// `state_changed`, and `state`, a list of `{"op", "path", "old", "new"}` changes with
+/// `op` one of "add", "remove" and "replace" and `path` a JSON Pointer. `state` is empty
+/// if the snapshots are identical, or if either state is not JSON, in which case only
+/// `state_changed` tells whether they differ.
+///
+/// # Raises
+/// * OSError or PersistError - If either snapshot cannot be loaded
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// diff = persist.diff_snapshots("agent1/v1.json.gz", "agent1/v2.json.gz", max_value_len=80)
+/// for change in diff["state"]:
+///     print(change["op"], change["path"])
+/// ```
+#[pyfunction]
+#[pyo3(signature = (path_a, path_b, a_storage=None, b_storage=None, max_value_len=None, paths_only=false, **storage))]
+#[allow(clippy::too_many_arguments)]
+fn diff_snapshots(
+    py: Python<'_>,
+    path_a: &str,
+    path_b: &str,
+    a_storage: Option<&Bound<'_, PyAny>>,
+    b_storage: Option<&Bound<'_, PyAny>>,
+    max_value_len: Option<usize>,
+    paths_only: bool,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    // The storage arguments only matter for a snapshot without its own storage
+    let shared = if a_storage.is_none() || b_storage.is_none() {
+        let client = PersistClient::for_call(py, storage)?;
+        Some(Bound::new(py, client)?)
+    } else {
+        None
+    };
+    let client_a = match (a_storage, &shared) {
+        (None, Some(shared)) => shared.clone(),
+        _ => PersistClient::for_storage(py, "a_storage", a_storage)?,
+    };
+    let client_b = match (b_storage, &shared) {
+        (None, Some(shared)) => shared.clone(),
+        _ => PersistClient::for_storage(py, "b_storage", b_storage)?,
+    };
+
+    let diff = client_a
+        .get()
+        .with_engines(py, client_b.get(), |engine_a, engine_b| {
+            let (metadata_a, state_a) = engine_a.load_snapshot(path_a)?;
+            let (metadata_b, state_b) = engine_b.load_snapshot(path_b)?;
+            Ok(SnapshotDiff::new(
+                &metadata_a,
+                &state_a,
+                &metadata_b,
+                &state_b,
+            ))
+        })?;
+    DiffFormat {
+        max_value_len,
+        paths_only,
+    }
+    .to_dict(py, &diff)
+}
+
+/// Forward the log events of the Rust core to Python's `logging`
+///
+/// Without this, Persist's structured logs only appear on stderr with `RUST_LOG` set.
+/// Afterwards every event at or above `level` becomes a `LogRecord` of the logger
+/// `logger_name`, with the Rust module in the `target` attribute and the event's fields
+/// (including those of its enclosing spans, such as `path`) as further attributes;
+/// fields named like a `LogRecord` attribute get a `field_` prefix. The GIL is only
+/// taken to hand over each record, and events caused by logging handlers that call
+/// back into Persist are dropped.
+///
+/// Calling it again changes the level, logger or format instead of installing a
+/// second bridge; `level="off"` silences it.
+///
+/// # Arguments
+/// * `level` - Lowest level forwarded: "trace", "debug", "info", "warning", "error" or
+///   "off" (default: "info"); trace events are logged at level 5
+/// * `logger_name` - Name of the logger receiving the records (default: "persist")
+/// * `json` - Make each message a JSON object with `message`, `level`, `target` and
+///   `fields`, for structured log pipelines (default: False)
+///
+/// # Raises
+/// * ValueError - If the level is invalid
+/// * RuntimeError - If another tracing subscriber is already installed in the process
+///
+/// # Example
+/// ```python
+/// import logging
+/// import persist
+///
+/// logging.basicConfig(level=logging.INFO)
+/// persist.init_logging("info")
+/// ```
+#[pyfunction]
+#[pyo3(signature = (level="info", logger_name="persist", json=false))]
+fn init_logging(py: Python<'_>, level: &str, logger_name: &str, json: bool) -> PyResult<()> {
+    logging::init(py, level, logger_name, json)
+}
+
+/// Show the configuration that calls with these arguments would use
+///
+/// Merges the arguments with the `PERSIST_STORAGE_MODE`, `PERSIST_S3_BUCKET`,
+/// `PERSIST_S3_REGION`, `PERSIST_BASE_DIR` and `PERSIST_COMPRESSION` environment
+/// variables and the built-in defaults, the same way every other function does.
+/// Arguments always take precedence over the environment. Nothing is created or
+/// contacted, and secrets are left out.
+///
+/// # Arguments
+/// The storage arguments of `PersistClient`, plus `compression` and `compression_level`
+///
+/// # Returns
+/// Dictionary with `storage_mode`, `s3_bucket`, `s3_region`, `s3_endpoint_url`,
+/// `s3_force_path_style`, `s3_access_key_id`, `gcs_bucket`, `gcs_prefix`,
+/// `gcs_credentials_path`, `base_dir`, `durable_writes`, `file_permissions`,
+/// `compression` and `compression_level`; settings of other backends are None
+///
+/// # Raises
+/// * PersistConfigurationError - If an argument or environment variable is invalid
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// print(persist.effective_config())
+/// ```
+#[pyfunction]
+#[pyo3(signature = (*, compression=None, compression_level=None, **storage))]
+fn effective_config(
+    py: Python<'_>,
+    compression: Option<&str>,
+    compression_level: Option<i32>,
+    storage: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyDict>> {
+    let config = create_storage_config(storage)?;
+    let compression = resolve_compression(compression)?;
+    compressor_for(&compression, compression_level)
+        .map_err(|e| PyPersistConfigurationError::new_err(e.to_string()))?;
+
+    let storage_mode = match config.backend {
+        StorageBackend::Local => "local",
+        StorageBackend::S3 => "s3",
+        StorageBackend::GCS => "gcs",
+    };
+    let result = PyDict::new(py);
+    result.set_item("storage_mode", storage_mode)?;
+    result.set_item("s3_bucket", config.s3_bucket)?;
+    result.set_item("s3_region", config.s3_region)?;
+    result.set_item("s3_endpoint_url", config.s3_endpoint_url)?;
+    result.set_item("s3_force_path_style", config.s3_force_path_style)?;
+    result.set_item(
+        "s3_access_key_id",
+        config
+            .s3_credentials
+            .map(|credentials| credentials.access_key_id),
+    )?;
+    result.set_item("gcs_bucket", config.gcs_bucket)?;
+    result.set_item("gcs_prefix", config.gcs_prefix)?;
+    result.set_item("gcs_credentials_path", config.gcs_credentials_path)?;
+    result.set_item("base_dir", config.local_base_path)?;
+    result.set_item("durable_writes", config.local_durable_writes)?;
+    result.set_item("file_permissions", config.local_file_permissions)?;
+    result.set_item("compression", compression)?;
+    result.set_item("compression_level", compression_level)?;
+    Ok(result.unbind())
+}
+
+/// Describe what this build of persist supports
+///
+/// Everything is determined when the extension is compiled, so it always matches the
+/// installed wheel. Useful to find out why a storage backend or compression algorithm
+/// is rejected.
+///
+/// # Returns
+/// Dictionary with:
+/// * `version` - Version of the persist package
+/// * `core_version` - Version of the persist-core engine
+/// * `storage_backends` - Storage modes that can be used, e.g. `["local", "s3"]`
+/// * `compression_algorithms` - Compression algorithms that can be written
+/// * `metadata_format_version` - "major.minor" metadata format version of new snapshots
+/// * `metrics` - Whether Prometheus metrics are compiled in
+/// * `async_runtime` - Whether the Tokio runtime of the cloud backends is compiled in
+///
+/// # Example
+/// ```python
+/// import persist
+///
+/// if "zstd" not in persist.info()["compression_algorithms"]:
+///     print("rebuild with `maturin build --features zstd`")
+/// ```
+#[pyfunction]
+fn info(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let features = persist_core::enabled_features();
+    let result = PyDict::new(py);
+    result.set_item("version", env!("CARGO_PKG_VERSION"))?;
+    result.set_item("core_version", persist_core::VERSION)?;
+    result.set_item("storage_backends", available_backends())?;
+    result.set_item("compression_algorithms", available_algorithms().to_vec())?;
+    result.set_item(
+        "metadata_format_version",
+        format!("{METADATA_FORMAT_VERSION}.{METADATA_FORMAT_MINOR_VERSION}"),
+    )?;
+    result.set_item("metrics", features.contains(&"metrics"))?;
+    result.set_item("async_runtime", features.contains(&"async-rt"))?;
+    Ok(result.unbind())
+}
+
+/// Schedule `function(*args, **kwargs)` on the running event loop's default executor
+///
+/// Returns an awaitable future. The blocking call releases the GIL while it does storage
+/// I/O, so other coroutines keep running.
+fn run_in_executor<'py>(
+    function: Bound<'py, PyAny>,
+    args: &Bound<'py, PyTuple>,
+    kwargs: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = function.py();
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let partial_args: Vec<_> = std::iter::once(function).chain(args.iter()).collect();
+    let partial_args = PyTuple::new(py, partial_args)?;
+    let call = py
+        .import("functools")?
+        .getattr("partial")?
+        .call(partial_args, kwargs)?;
+    event_loop.call_method1("run_in_executor", (py.None(), call))
+}
+
+/// Asyncio variants of the snapshot functions
+///
+/// Each function takes the same arguments as its synchronous counterpart and returns an
+/// awaitable that runs the operation on the event loop's default executor.
+mod aio {
+    use super::run_in_executor;
+    use pyo3::prelude::*;
+    use pyo3::types::{PyDict, PyModule, PyTuple};
+
+    /// Awaitable `persist.snapshot`
+    #[pyfunction]
+    #[pyo3(signature = (*args, **kwargs))]
+    pub(super) fn snapshot<'py>(
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let function = wrap_pyfunction!(super::snapshot, args.py())?;
+        run_in_executor(function.into_any(), args, kwargs)
+    }
+
+    /// Awaitable `persist.restore`
+    #[pyfunction]
+    #[pyo3(signature = (*args, **kwargs))]
+    pub(super) fn restore<'py>(
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let function = wrap_pyfunction!(super::restore, args.py())?;
+        run_in_executor(function.into_any(), args, kwargs)
+    }
+
+    /// Awaitable `persist.get_metadata`
+    #[pyfunction]
+    #[pyo3(signature = (*args, **kwargs))]
+    pub(super) fn get_metadata<'py>(
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let function = wrap_pyfunction!(super::get_metadata, args.py())?;
+        run_in_executor(function.into_any(), args, kwargs)
+    }
+
+    /// Awaitable `persist.list_snapshots`
+    #[pyfunction]
+    #[pyo3(signature = (*args, **kwargs))]
+    pub(super) fn list_snapshots<'py>(
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let function = wrap_pyfunction!(super::list_snapshots, args.py())?;
+        run_in_executor(function.into_any(), args, kwargs)
+    }
+
+    pub(super) fn register(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+        let py = parent.py();
+        let module = PyModule::new(py, "aio")?;
+        module.add_function(wrap_pyfunction!(snapshot, &module)?)?;
+        module.add_function(wrap_pyfunction!(restore, &module)?)?;
+        module.add_function(wrap_pyfunction!(get_metadata, &module)?)?;
+        module.add_function(wrap_pyfunction!(list_snapshots, &module)?)?;
+        parent.add_submodule(&module)?;
+        // Extension submodules are not importable by dotted name unless registered
+        py.import("sys")?
+            .getattr("modules")?
+            .set_item("persist.aio", &module)?;
+        Ok(())
+    }
+}
+
+/// Python module definition
+#[pymodule]
+fn persist(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // Add main functions
+    m.add_function(wrap_pyfunction!(snapshot, m)?)?;
     m.add_function(wrap_pyfunction!(restore, m)?)?;
+    m.add_function(wrap_pyfunction!(snapshot_many, m)?)?;
+    m.add_function(wrap_pyfunction!(restore_many, m)?)?;
+    m.add_function(wrap_pyfunction!(snapshot_json, m)?)?;
+    m.add_function(wrap_pyfunction!(restore_json, m)?)?;
     m.add_function(wrap_pyfunction!(get_metadata, m)?)?;
+    m.add_function(wrap_pyfunction!(list_snapshots, m)?)?;
+    m.add_function(wrap_pyfunction!(update_metadata, m)?)?;
+    m.add_function(wrap_pyfunction!(snapshot_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(restore_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(restore_latest, m)?)?;
+    m.add_function(wrap_pyfunction!(latest_metadata, m)?)?;
     m.add_function(wrap_pyfunction!(verify_snapshot, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_many, m)?)?;
     m.add_function(wrap_pyfunction!(snapshot_exists, m)?)?;
     m.add_function(wrap_pyfunction!(delete_snapshot, m)?)?;
+    m.add_function(wrap_pyfunction!(delete_snapshots, m)?)?;
+    m.add_function(wrap_pyfunction!(delete_prefix, m)?)?;
+    m.add_function(wrap_pyfunction!(prune, m)?)?;
+    m.add_function(wrap_pyfunction!(copy_snapshot, m)?)?;
+    m.add_function(wrap_pyfunction!(move_snapshot, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_snapshots, m)?)?;
+    m.add_function(wrap_pyfunction!(effective_config, m)?)?;
+    m.add_function(wrap_pyfunction!(info, m)?)?;
+    m.add_function(wrap_pyfunction!(init_logging, m)?)?;
+    m.add_function(wrap_pyfunction!(auto_snapshot::auto_snapshot, m)?)?;
+    m.add_function(wrap_pyfunction!(_engines_created, m)?)?;
+    m.add_class::<PersistClient>()?;
+    m.add_class::<PySnapshotMetadata>()?;
+    m.add_class::<PyVerificationResult>()?;
+    m.add_class::<AutoSnapshotter>()?;
+    aio::register(m)?;
+    integrations::register(m)?;
 
     // Add custom exception classes
     m.add("PersistError", m.py().get_type::<PyPersistError>())?;
@@ -480,9 +3739,30 @@ fn persist(m: &Bound<'_, PyModule>) -> PyResult<()> {
         "PersistCompressionError",
         m.py().get_type::<PyPersistCompressionError>(),
     )?;
+    m.add(
+        "PersistValidationError",
+        m.py().get_type::<PyPersistValidationError>(),
+    )?;
+    m.add(
+        "PersistFrameworkMismatchError",
+        m.py().get_type::<PyPersistFrameworkMismatchError>(),
+    )?;
+    m.add(
+        "PersistCompatibilityError",
+        m.py().get_type::<PyPersistCompatibilityError>(),
+    )?;
+
+    // Compression algorithms this build can write
+    m.add("COMPRESSION_ALGORITHMS", available_algorithms().to_vec())?;
+
+    // Cargo features of the storage engine this build was compiled with
+    m.add(
+        "__features__",
+        PyFrozenSet::new(m.py(), persist_core::enabled_features())?,
+    )?;
 
     // Add version info
-    m.add("__version__", "0.1.0")?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
     m.add(
         "__doc__",
         "Enterprise-grade agent snapshot and restore system with S3 support",
@@ -0,0 +1,303 @@
+/*!
+Forwarding the tracing events of persist-core to Python's `logging` module.
+
+`init_logging` installs [`PythonLayer`] as the global tracing subscriber. Events are
+turned into `LogRecord`s of one logger, with the GIL taken only to hand each record
+over. The level and logger can be changed by calling `init_logging` again.
+*/
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use serde_json::{json, Map, Value};
+use std::cell::Cell;
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+/// `LogRecord` attributes, which event fields must not overwrite; clashing fields are
+/// passed with a `field_` prefix instead
+const RECORD_ATTRIBUTES: &[&str] = &[
+    "args",
+    "asctime",
+    "created",
+    "exc_info",
+    "exc_text",
+    "filename",
+    "funcName",
+    "levelname",
+    "levelno",
+    "lineno",
+    "message",
+    "module",
+    "msecs",
+    "msg",
+    "name",
+    "pathname",
+    "process",
+    "processName",
+    "relativeCreated",
+    "stack_info",
+    "taskName",
+    "thread",
+    "threadName",
+];
+
+/// Configuration of the bridge, set by `init_logging`
+struct Settings {
+    level: LevelFilter,
+    logger: Py<PyAny>,
+    json: bool,
+}
+
+static SETTINGS: RwLock<Option<Arc<Settings>>> = RwLock::new(None);
+
+thread_local! {
+    /// Set while a record is handed to Python, so events caused by logging handlers
+    /// calling back into Persist are dropped instead of recursing
+    static EMITTING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn settings() -> Option<Arc<Settings>> {
+    SETTINGS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Install the bridge, or reconfigure it if it is already installed
+pub(crate) fn init(py: Python<'_>, level: &str, logger_name: &str, json: bool) -> PyResult<()> {
+    let level = parse_level(level)?;
+    let logger = py
+        .import("logging")?
+        .call_method1("getLogger", (logger_name,))?
+        .unbind();
+
+    {
+        let mut settings = SETTINGS.write().unwrap_or_else(PoisonError::into_inner);
+        if settings.is_none() {
+            tracing::subscriber::set_global_default(Registry::default().with(PythonLayer))
+                .map_err(|_| {
+                    PyRuntimeError::new_err(
+                        "Another tracing subscriber is already installed in this process",
+                    )
+                })?;
+        }
+        *settings = Some(Arc::new(Settings {
+            level,
+            logger,
+            json,
+        }));
+    }
+    // Whether a callsite is enabled is cached, so a new level needs a fresh look
+    tracing::callsite::rebuild_interest_cache();
+    Ok(())
+}
+
+fn parse_level(level: &str) -> PyResult<LevelFilter> {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" => Ok(LevelFilter::TRACE),
+        "debug" => Ok(LevelFilter::DEBUG),
+        "info" => Ok(LevelFilter::INFO),
+        "warn" | "warning" => Ok(LevelFilter::WARN),
+        "error" | "critical" => Ok(LevelFilter::ERROR),
+        "off" => Ok(LevelFilter::OFF),
+        _ => Err(PyValueError::new_err(format!(
+            "Invalid log level '{level}'. Must be 'trace', 'debug', 'info', 'warning', \
+             'error' or 'off'"
+        ))),
+    }
+}
+
+/// `logging` level of a tracing level; TRACE maps to 5, below DEBUG
+fn python_level(level: Level) -> u8 {
+    if level == Level::ERROR {
+        40
+    } else if level == Level::WARN {
+        30
+    } else if level == Level::INFO {
+        20
+    } else if level == Level::DEBUG {
+        10
+    } else {
+        5
+    }
+}
+
+/// Fields recorded on a span, inherited by the events inside it
+struct SpanFields(Map<String, Value>);
+
+struct FieldVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        let value = serde_json::Number::from_f64(value)
+            .map_or_else(|| Value::String(value.to_string()), Value::Number);
+        self.0.insert(field.name().to_string(), value);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+}
+
+/// Tracing layer forwarding events to the configured Python logger
+struct PythonLayer;
+
+impl<S> Layer<S> for PythonLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        settings().is_some_and(|settings| *metadata.level() <= settings.level)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        settings().map(|settings| settings.level)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = Map::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            values.record(&mut FieldVisitor(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if EMITTING.get() {
+            return;
+        }
+        // Taking the GIL must not start an interpreter that has already shut down
+        if unsafe { pyo3::ffi::Py_IsInitialized() } == 0 {
+            return;
+        }
+        let Some(settings) = settings() else {
+            return;
+        };
+
+        let mut fields = Map::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                let extensions = span.extensions();
+                if let Some(SpanFields(span_fields)) = extensions.get::<SpanFields>() {
+                    fields.extend(span_fields.clone());
+                }
+            }
+        }
+        event.record(&mut FieldVisitor(&mut fields));
+        let message = match fields.remove("message") {
+            Some(Value::String(message)) => message,
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+
+        EMITTING.set(true);
+        Python::with_gil(|py| {
+            if let Err(e) = emit(py, &settings, event.metadata(), message, fields) {
+                e.write_unraisable(py, None);
+            }
+        });
+        EMITTING.set(false);
+    }
+}
+
+/// Hand one event to the Python logger as a `LogRecord`
+fn emit(
+    py: Python<'_>,
+    settings: &Settings,
+    metadata: &Metadata<'_>,
+    message: String,
+    fields: Map<String, Value>,
+) -> PyResult<()> {
+    let logger = settings.logger.bind(py);
+    let level = python_level(*metadata.level());
+    if !logger.call_method1("isEnabledFor", (level,))?.is_truthy()? {
+        return Ok(());
+    }
+
+    let extra = PyDict::new(py);
+    extra.set_item("target", metadata.target())?;
+    for (key, value) in &fields {
+        let key = if RECORD_ATTRIBUTES.contains(&key.as_str()) {
+            format!("field_{key}")
+        } else {
+            key.clone()
+        };
+        match value {
+            Value::Bool(value) => extra.set_item(key, value)?,
+            Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+                (Some(value), _) => extra.set_item(key, value)?,
+                (None, Some(value)) => extra.set_item(key, value)?,
+                _ => extra.set_item(key, number.as_f64())?,
+            },
+            Value::String(value) => extra.set_item(key, value)?,
+            other => extra.set_item(key, other.to_string())?,
+        }
+    }
+
+    let msg = if settings.json {
+        json!({
+            "message": message,
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "fields": fields,
+        })
+        .to_string()
+    } else {
+        message
+    };
+
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("func", metadata.module_path())?;
+    kwargs.set_item("extra", extra)?;
+    let record = logger.call_method(
+        "makeRecord",
+        (
+            logger.getattr("name")?,
+            level,
+            metadata.file().unwrap_or("<unknown>"),
+            metadata.line().unwrap_or(0),
+            msg,
+            PyTuple::empty(py),
+            py.None(),
+        ),
+        Some(&kwargs),
+    )?;
+    logger.call_method1("handle", (record,))?;
+    Ok(())
+}
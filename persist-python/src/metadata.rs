@@ -0,0 +1,273 @@
+/*!
+The `persist.SnapshotMetadata` class returned by the metadata functions.
+*/
+
+use crate::convert_error;
+use chrono::{DateTime, SecondsFormat, Utc};
+use persist_core::SnapshotMetadata;
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use std::collections::BTreeMap;
+
+/// Metadata of a stored snapshot
+///
+/// Returned by `get_metadata`, `list_snapshots` and the other metadata functions.
+/// Instances are immutable; sensitive fields hold their redacted values unless the
+/// metadata was fetched with `reveal=True`. Item access (`metadata["agent_id"]`)
+/// reads the dictionary form of `to_dict()`, so code written against the earlier
+/// dictionary results keeps working.
+#[pyclass(frozen, module = "persist", name = "SnapshotMetadata")]
+pub(crate) struct PySnapshotMetadata {
+    metadata: SnapshotMetadata,
+    path: Option<String>,
+}
+
+impl PySnapshotMetadata {
+    /// Wrap `metadata` stored at `path`, redacting sensitive fields unless `reveal` is set
+    ///
+    /// Fails if `reveal` is set but the sensitive fields are encrypted.
+    pub(crate) fn new(
+        metadata: &SnapshotMetadata,
+        reveal: bool,
+        path: Option<String>,
+    ) -> PyResult<Self> {
+        let metadata = if reveal {
+            metadata
+                .revealed()
+                .map_err(convert_error)?
+                .metadata()
+                .clone()
+        } else {
+            metadata.redacted()
+        };
+        Ok(Self { metadata, path })
+    }
+
+    /// Convert to the Python result: this object, or its dictionary form if `as_dict`
+    pub(crate) fn into_result(self, py: Python<'_>, as_dict: bool) -> PyResult<PyObject> {
+        if as_dict {
+            Ok(self.dict(py)?.into_any().unbind())
+        } else {
+            Ok(Py::new(py, self)?.into_any())
+        }
+    }
+
+    /// Dictionary form, built from the metadata's JSON export so new fields appear
+    /// automatically; `timestamp` is kept as seconds since the epoch for backwards
+    /// compatibility
+    fn dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = json_to_python(py, &self.metadata.to_json_value())?.downcast_into::<PyDict>()?;
+        dict.set_item("timestamp", self.metadata.timestamp.timestamp())?;
+        if let Some(path) = &self.path {
+            dict.set_item("path", path)?;
+        }
+        Ok(dict)
+    }
+}
+
+/// Convert a JSON value to the equivalent Python object
+fn json_to_python<'py>(py: Python<'py>, value: &serde_json::Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (value.to_string(),))
+}
+
+/// Convert a UTC timestamp to a timezone-aware `datetime`
+fn to_datetime<'py>(py: Python<'py>, timestamp: &DateTime<Utc>) -> PyResult<Bound<'py, PyAny>> {
+    // Microsecond precision with a numeric offset is what fromisoformat accepts on 3.8
+    let iso = timestamp.to_rfc3339_opts(SecondsFormat::Micros, false);
+    py.import("datetime")?
+        .getattr("datetime")?
+        .call_method1("fromisoformat", (iso,))
+}
+
+#[pymethods]
+impl PySnapshotMetadata {
+    /// Storage path/key of the snapshot, for metadata that came from a listing
+    #[getter]
+    fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    #[getter]
+    fn agent_id(&self) -> &str {
+        &self.metadata.agent_id
+    }
+
+    #[getter]
+    fn session_id(&self) -> &str {
+        &self.metadata.session_id
+    }
+
+    #[getter]
+    fn snapshot_index(&self) -> u64 {
+        self.metadata.snapshot_index
+    }
+
+    /// Creation time as a timezone-aware `datetime` in UTC
+    #[getter]
+    fn timestamp<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_datetime(py, &self.metadata.timestamp)
+    }
+
+    #[getter]
+    fn snapshot_id(&self) -> &str {
+        &self.metadata.snapshot_id
+    }
+
+    /// Content hash as `<algorithm>:<hex digest>`
+    #[getter]
+    fn content_hash(&self) -> String {
+        self.metadata
+            .parsed_content_hash()
+            .map(|hash| hash.format())
+            .unwrap_or_else(|_| self.metadata.content_hash.clone())
+    }
+
+    #[getter]
+    fn hash_algorithm(&self) -> Option<&'static str> {
+        self.metadata
+            .parsed_content_hash()
+            .ok()
+            .map(|hash| hash.algorithm.name())
+    }
+
+    #[getter]
+    fn format_version(&self) -> u8 {
+        self.metadata.format_version
+    }
+
+    #[getter]
+    fn format_minor_version(&self) -> u8 {
+        self.metadata.format_minor_version
+    }
+
+    /// Format version as `<major>.<minor>`
+    #[getter]
+    fn format_version_string(&self) -> String {
+        self.metadata.format_version_string()
+    }
+
+    /// "compatible", "needs migration" or "unsupported"
+    #[getter]
+    fn compatibility(&self) -> String {
+        self.metadata.compatibility().to_string()
+    }
+
+    #[getter]
+    fn description(&self) -> Option<&str> {
+        self.metadata.description.as_deref()
+    }
+
+    #[getter]
+    fn uncompressed_size(&self) -> usize {
+        self.metadata.uncompressed_size
+    }
+
+    #[getter]
+    fn compressed_size(&self) -> Option<usize> {
+        self.metadata.compressed_size
+    }
+
+    #[getter]
+    fn compression_algorithm(&self) -> &str {
+        &self.metadata.compression_algorithm
+    }
+
+    #[getter]
+    fn framework(&self) -> Option<&str> {
+        self.metadata.framework.as_deref()
+    }
+
+    #[getter]
+    fn framework_version(&self) -> Option<&str> {
+        self.metadata.framework_version.as_deref()
+    }
+
+    #[getter]
+    fn state_schema_version(&self) -> Option<u32> {
+        self.metadata.state_schema_version
+    }
+
+    /// A new dictionary of the tags on each access
+    #[getter]
+    fn tags(&self) -> BTreeMap<String, String> {
+        self.metadata.tags.clone()
+    }
+
+    #[getter]
+    fn parent_snapshot_id(&self) -> Option<&str> {
+        self.metadata.parent_snapshot_id.as_deref()
+    }
+
+    /// Expiry as a timezone-aware `datetime` in UTC, if the snapshot has one
+    #[getter]
+    fn expires_at<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.metadata
+            .expires_at
+            .as_ref()
+            .map(|expires_at| to_datetime(py, expires_at))
+            .transpose()
+    }
+
+    #[getter]
+    fn sensitive_fields(&self) -> Vec<&'static str> {
+        self.metadata
+            .sensitive_fields
+            .iter()
+            .map(|field| field.name())
+            .collect()
+    }
+
+    /// Fields not known to this version, such as `persist.payload`
+    #[getter]
+    fn extra<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_to_python(py, &serde_json::Value::Object(self.metadata.extra.clone()))
+    }
+
+    /// The metadata as a dictionary, in the form `get_metadata(as_dict=True)` returns
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        self.dict(py)
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.dict(py)?
+            .get_item(key)?
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+    }
+
+    fn __contains__(&self, py: Python<'_>, key: &str) -> PyResult<bool> {
+        self.dict(py)?.contains(key)
+    }
+
+    /// Keys of the dictionary form
+    fn keys<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        Ok(self.dict(py)?.keys())
+    }
+
+    /// Value of `key` in the dictionary form, or `default` if it is missing
+    #[pyo3(signature = (key, default=None))]
+    fn get(&self, py: Python<'_>, key: &str, default: Option<PyObject>) -> PyResult<PyObject> {
+        Ok(match self.dict(py)?.get_item(key)? {
+            Some(value) => value.unbind(),
+            None => default.unwrap_or_else(|| py.None()),
+        })
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let repr =
+            |value: &str| -> PyResult<String> { Ok(PyString::new(py, value).repr()?.to_string()) };
+        let path = match &self.path {
+            Some(path) => repr(path)?,
+            None => "None".to_string(),
+        };
+        Ok(format!(
+            "SnapshotMetadata(agent_id={}, session_id={}, snapshot_index={}, snapshot_id={}, path={})",
+            repr(&self.metadata.agent_id)?,
+            repr(&self.metadata.session_id)?,
+            self.metadata.snapshot_index,
+            repr(&self.metadata.snapshot_id)?,
+            path,
+        ))
+    }
+}
@@ -0,0 +1,124 @@
+/*!
+The opt-in `serializer="pickle"` of `snapshot`.
+
+A pickled agent is stored as a JSON object holding the base64-encoded pickle under a
+marker field, so it gets the same compression, integrity checks and storage as any
+other state. Unpickling runs code chosen by whoever wrote the snapshot, so `restore`
+only does it when asked to with `allow_pickle=True`, optionally limited to an
+allowlist of importable globals.
+*/
+
+use crate::{PyPersistError, PyPersistValidationError};
+use pyo3::exceptions::PyTypeError;
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyFrozenSet, PyModule, PyString};
+
+/// Name of the pickle serializer in the `serializer` argument
+pub(crate) const SERIALIZER_NAME: &str = "pickle";
+
+/// Marker field of a pickled state, naming the serializer
+const MARKER_KEY: &str = "__persist_serializer__";
+/// Field of a pickled state holding the base64-encoded pickle
+const DATA_KEY: &str = "data";
+
+/// Unpickler resolving only allowlisted globals
+const ALLOWLIST_SOURCE: &std::ffi::CStr = c_str!(
+    r#"
+import io
+import pickle
+
+
+class AllowlistUnpickler(pickle.Unpickler):
+    def __init__(self, data, allowed):
+        super().__init__(io.BytesIO(data))
+        self.allowed = allowed
+
+    def find_class(self, module, name):
+        if f"{module}.{name}" not in self.allowed:
+            raise pickle.UnpicklingError(f"{module}.{name} is not in pickle_allowlist")
+        return super().find_class(module, name)
+"#
+);
+
+static ALLOWLIST_MODULE: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+
+/// State JSON of a pickled agent
+pub(crate) fn dumps(agent: &Bound<'_, PyAny>) -> PyResult<String> {
+    let py = agent.py();
+    let pickled = py.import("pickle")?.call_method1("dumps", (agent,))?;
+    let encoded: String = py
+        .import("base64")?
+        .call_method1("b64encode", (pickled,))?
+        .call_method1("decode", ("ascii",))?
+        .extract()?;
+    Ok(serde_json::json!({ MARKER_KEY: SERIALIZER_NAME, DATA_KEY: encoded }).to_string())
+}
+
+/// Unpickle the agent in the state JSON of the snapshot at `path`
+///
+/// With an `allowlist`, an iterable of `module.name` strings, only those globals can be
+/// loaded.
+pub(crate) fn loads(
+    py: Python<'_>,
+    path: &str,
+    agent_json: &str,
+    allowlist: Option<&Bound<'_, PyAny>>,
+) -> PyResult<PyObject> {
+    let state: serde_json::Value = serde_json::from_str(agent_json).map_err(|e| {
+        PyPersistError::new_err(format!("Snapshot {path} holds a malformed pickle: {e}"))
+    })?;
+    let encoded = match (state[MARKER_KEY].as_str(), state[DATA_KEY].as_str()) {
+        (Some(SERIALIZER_NAME), Some(encoded)) => encoded,
+        _ => {
+            return Err(PyPersistError::new_err(format!(
+                "Snapshot {path} holds a malformed pickle: missing {MARKER_KEY} or {DATA_KEY}"
+            )))
+        }
+    };
+    let data = py.import("base64")?.call_method1("b64decode", (encoded,))?;
+
+    let Some(allowlist) = allowlist else {
+        return Ok(py
+            .import("pickle")?
+            .call_method1("loads", (data,))?
+            .unbind());
+    };
+    let module = ALLOWLIST_MODULE.get_or_try_init(py, || {
+        PyModule::from_code(
+            py,
+            ALLOWLIST_SOURCE,
+            c_str!("persist/_pickle_allowlist.py"),
+            c_str!("persist._pickle_allowlist"),
+        )
+        .map(Bound::unbind)
+    })?;
+    // A lone string is a mistake for a one-element list, not a set of characters
+    if allowlist.is_instance_of::<PyString>() {
+        return Err(PyTypeError::new_err(
+            "pickle_allowlist must be a collection of 'module.name' strings, not a str",
+        ));
+    }
+    let allowed = PyFrozenSet::new(
+        py,
+        allowlist
+            .try_iter()?
+            .map(|name| name?.extract::<String>())
+            .collect::<PyResult<Vec<_>>>()?,
+    )?;
+    Ok(module
+        .bind(py)
+        .getattr("AllowlistUnpickler")?
+        .call1((data, allowed))?
+        .call_method0("load")?
+        .unbind())
+}
+
+/// Raise PersistValidationError for a pickled snapshot restored without `allow_pickle`
+pub(crate) fn refuse(path: &str) -> PyErr {
+    PyPersistValidationError::new_err(format!(
+        "Snapshot {path} holds a pickled agent. Unpickling can run arbitrary code, so pass \
+         allow_pickle=True only for snapshots from a trusted source"
+    ))
+}
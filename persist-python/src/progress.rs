@@ -0,0 +1,63 @@
+/*!
+Forwarding storage progress to the `progress_callback=` of Python calls.
+*/
+
+use persist_core::{CallOptions, PersistError};
+use pyo3::prelude::*;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Minimum time between two calls of a progress callback, apart from the last
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct State {
+    last_call: Option<Instant>,
+    /// Exception raised by the callback, which aborted the transfer
+    error: Option<PyErr>,
+}
+
+/// Run `operation` with call options reporting to `callback`, if given
+///
+/// The callback receives `(bytes_done, bytes_total)` with the GIL held, on the first
+/// report, at most every 100 ms after that and once the transfer is complete. If it
+/// raises, the transfer is aborted and its exception is raised instead of whatever
+/// error the aborted operation ends with.
+pub(crate) fn with_progress<T>(
+    callback: Option<&Bound<'_, PyAny>>,
+    operation: impl FnOnce(&CallOptions) -> PyResult<T>,
+) -> PyResult<T> {
+    let Some(callback) = callback else {
+        return operation(&CallOptions::default());
+    };
+    let callback = callback.clone().unbind();
+    let state = Arc::new(Mutex::new(State::default()));
+
+    let reporter = state.clone();
+    let options = CallOptions::default().with_progress(Arc::new(move |transferred, total| {
+        let mut state = reporter.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let due = state
+            .last_call
+            .is_none_or(|last| now.duration_since(last) >= MIN_INTERVAL);
+        if !due && transferred < total {
+            return Ok(());
+        }
+        state.last_call = Some(now);
+        Python::with_gil(|py| callback.call1(py, (transferred, total)).map(drop)).map_err(|e| {
+            state.error = Some(e);
+            PersistError::aborted("progress callback raised an exception")
+        })
+    }));
+
+    let result = operation(&options);
+    let error = state
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .error
+        .take();
+    match error {
+        Some(error) => Err(error),
+        None => result,
+    }
+}
@@ -0,0 +1,136 @@
+/*!
+The `persist.VerificationResult` class returned by `verify` and `verify_many`.
+*/
+
+use crate::Engine;
+use persist_core::metadata::Compatibility;
+use persist_core::{PersistError, SnapshotMetadata};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use std::io::ErrorKind;
+
+/// Outcome of verifying one snapshot
+///
+/// `valid` is False if the snapshot is missing, cannot be decompressed or parsed,
+/// has an incompatible format version or fails its integrity check; `error` then
+/// says why. Hashes and the format version are None when the snapshot's metadata
+/// could not be read.
+#[pyclass(frozen, module = "persist", name = "VerificationResult")]
+pub(crate) struct PyVerificationResult {
+    /// Storage path/key of the snapshot
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    valid: bool,
+    /// Why verification failed
+    #[pyo3(get)]
+    error: Option<String>,
+    /// Content hash recorded in the metadata, as `<algorithm>:<hex digest>`
+    #[pyo3(get)]
+    expected_hash: Option<String>,
+    /// Hash of the stored agent state, if it could be computed
+    #[pyo3(get)]
+    actual_hash: Option<String>,
+    /// Format version as `<major>.<minor>`
+    #[pyo3(get)]
+    format_version: Option<String>,
+    /// Whether this version reads the format directly, without migration
+    #[pyo3(get)]
+    compatible: Option<bool>,
+}
+
+impl PyVerificationResult {
+    /// Verify the snapshot at `path`
+    ///
+    /// Only errors that say nothing about the snapshot itself, such as storage that
+    /// cannot be reached or denies access, are returned as errors.
+    pub(crate) fn check(engine: &Engine, path: &str) -> persist_core::Result<Self> {
+        // The metadata is read on its own first: storage errors from loading the
+        // whole snapshot lose their type, and a damaged snapshot may still have it
+        let stored = match engine.read_snapshot_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if is_verification_failure(&e) => return Ok(Self::failed(path, &e, None)),
+            Err(e) => return Err(e),
+        };
+        match engine.load_snapshot(path) {
+            Ok((metadata, _)) => {
+                let hash = content_hash(&metadata);
+                Ok(Self {
+                    path: path.to_string(),
+                    valid: true,
+                    error: None,
+                    expected_hash: Some(hash.clone()),
+                    actual_hash: Some(hash),
+                    format_version: Some(metadata.format_version_string()),
+                    compatible: Some(metadata.compatibility() == Compatibility::Compatible),
+                })
+            }
+            Err(e) if is_verification_failure(&e) => Ok(Self::failed(path, &e, Some(&stored))),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn failed(path: &str, error: &PersistError, stored: Option<&SnapshotMetadata>) -> Self {
+        let (expected_hash, actual_hash) = match error {
+            PersistError::IntegrityCheckFailed { expected, actual } => {
+                (Some(expected.clone()), Some(actual.clone()))
+            }
+            _ => (stored.map(content_hash), None),
+        };
+        Self {
+            path: path.to_string(),
+            valid: false,
+            error: Some(error.to_string()),
+            expected_hash,
+            actual_hash,
+            format_version: stored.map(SnapshotMetadata::format_version_string),
+            compatible: stored
+                .map(|metadata| metadata.compatibility() == Compatibility::Compatible),
+        }
+    }
+}
+
+/// Whether `error` means the snapshot is missing or damaged, rather than that
+/// storage could not be used
+fn is_verification_failure(error: &PersistError) -> bool {
+    match error {
+        PersistError::Io(e) => e.kind() == ErrorKind::NotFound,
+        PersistError::S3NotFound { .. }
+        | PersistError::GcsNotFound { .. }
+        | PersistError::Json(_)
+        | PersistError::Compression(_)
+        | PersistError::IntegrityCheckFailed { .. }
+        | PersistError::InvalidFormat(_)
+        | PersistError::NeedsMigration { .. }
+        | PersistError::MissingMetadata(_)
+        | PersistError::Validation(_)
+        | PersistError::FrameworkMismatch { .. } => true,
+        _ => false,
+    }
+}
+
+/// Content hash of `metadata` as `<algorithm>:<hex digest>`
+fn content_hash(metadata: &SnapshotMetadata) -> String {
+    metadata
+        .parsed_content_hash()
+        .map(|hash| hash.format())
+        .unwrap_or_else(|_| metadata.content_hash.clone())
+}
+
+#[pymethods]
+impl PyVerificationResult {
+    fn __bool__(&self) -> bool {
+        self.valid
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let path = PyString::new(py, &self.path).repr()?;
+        Ok(match &self.error {
+            None => format!("VerificationResult(path={path}, valid=True)"),
+            Some(error) => format!(
+                "VerificationResult(path={path}, valid=False, error={})",
+                PyString::new(py, error).repr()?
+            ),
+        })
+    }
+}
@@ -30,6 +30,7 @@ class TestCustomExceptions:
         assert issubclass(persist.PersistIntegrityError, persist.PersistError)
         assert issubclass(persist.PersistS3Error, persist.PersistError)
         assert issubclass(persist.PersistCompressionError, persist.PersistError)
+        assert issubclass(persist.PersistFrameworkMismatchError, persist.PersistError)
 
     def test_exceptions_are_instantiable(self):
         """Test that exceptions can be instantiated with messages."""
@@ -39,6 +40,7 @@ class TestCustomExceptions:
             persist.PersistIntegrityError,
             persist.PersistS3Error,
             persist.PersistCompressionError,
+            persist.PersistFrameworkMismatchError,
         ]
 
         for exc_class in exceptions:
@@ -143,22 +145,24 @@ class TestTypeHints:
             "session_id",
             "snapshot_index",
             "description",
-            "storage_mode",
-            "s3_bucket",
-            "s3_region",
+            "compression",
+            "tags",
         ]
 
         for param in expected_params:
             assert param in params, f"Expected parameter '{param}' not found in snapshot signature"
+        # The storage options are collected from the remaining keywords
+        assert sig.parameters["storage"].kind is inspect.Parameter.VAR_KEYWORD
 
         # Test restore function signature
         sig = inspect.signature(persist.restore)
         params = list(sig.parameters.keys())
 
-        expected_params = ["path", "storage_mode", "s3_bucket", "s3_region"]
+        expected_params = ["path", "secrets_map", "deserializer"]
 
         for param in expected_params:
             assert param in params, f"Expected parameter '{param}' not found in restore signature"
+        assert sig.parameters["storage"].kind is inspect.Parameter.VAR_KEYWORD
 
     def test_module_has_version(self):
         """Test that module exposes version information."""
@@ -3,11 +3,22 @@ Comprehensive tests for the Python SDK of Persist.
 These tests verify the Python interface and integration with LangChain.
 """
 
+import asyncio
 import json
+import logging
 import os
+import pickle
+import shutil
+import subprocess
+import sys
 import tempfile
+import textwrap
 import threading
 import time
+import types
+import warnings
+from collections import OrderedDict
+from datetime import datetime, timedelta, timezone
 from pathlib import Path
 from typing import TYPE_CHECKING
 from unittest.mock import patch
@@ -108,6 +119,14 @@ def mock_langchain_agent(sample_agent_data):
     return MockAgent(sample_agent_data)
 
 
+class LegacyAgent:
+    """Agent without a JSON form, restorable only through pickle."""
+
+    def __init__(self, name, memory):
+        self.name = name
+        self.memory = memory
+
+
 @pytest.mark.skipif(not PERSIST_AVAILABLE, reason="Persist module not available")
 class TestPersistSDK:
     """Test cases for the Persist Python SDK."""
@@ -354,6 +373,1795 @@ class TestPersistSDK:
                     restored_agent = persist.restore(snapshot_path)
                     assert restored_agent is not None
 
+    def test_get_metadata_contains_every_field(self, temp_dir, sample_agent_data):
+        """Test that get_metadata exposes every public metadata field."""
+        snapshot_path = os.path.join(temp_dir, "metadata_snapshot.json.gz")
+
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            persist.snapshot(
+                sample_agent_data, snapshot_path, description="metadata test"
+            )
+
+        metadata = persist.get_metadata(snapshot_path)
+
+        expected_fields = {
+            "snapshot_id",
+            "agent_id",
+            "session_id",
+            "snapshot_index",
+            "timestamp",
+            "format_version",
+            "format_minor_version",
+            "content_hash",
+            "hash_algorithm",
+            "compression_algorithm",
+            "uncompressed_size",
+            "compressed_size",
+            "description",
+            "framework",
+            "framework_version",
+            "state_schema_version",
+            "tags",
+            "parent_snapshot_id",
+            "expires_at",
+        }
+        assert expected_fields <= set(metadata.keys())
+        assert metadata["description"] == "metadata test"
+        assert isinstance(metadata["timestamp"], int)
+
+    def test_metadata_attributes(self, temp_dir):
+        """Test the typed attributes of SnapshotMetadata and the as_dict escape hatch."""
+        persist.snapshot_json(
+            {"step": 1},
+            "typed.json.gz",
+            "bot",
+            "s1",
+            3,
+            description="typed",
+            tags={"env": "prod"},
+            base_dir=temp_dir,
+        )
+
+        metadata = persist.get_metadata("typed.json.gz", base_dir=temp_dir)
+        assert isinstance(metadata, persist.SnapshotMetadata)
+        assert (metadata.agent_id, metadata.session_id, metadata.snapshot_index) == (
+            "bot",
+            "s1",
+            3,
+        )
+        assert metadata.description == "typed"
+        assert metadata.content_hash.startswith("sha256:")
+        assert metadata.hash_algorithm == "sha256"
+        assert metadata.compression_algorithm == "gzip"
+        assert metadata.expires_at is None
+        assert metadata.path is None
+        assert metadata.sensitive_fields == []
+
+        assert isinstance(metadata.timestamp, datetime)
+        assert metadata.timestamp.tzinfo is not None
+        assert metadata.timestamp.utcoffset().total_seconds() == 0
+        assert int(metadata.timestamp.timestamp()) == metadata["timestamp"]
+
+        # tags is a copy and the object is immutable
+        metadata.tags["env"] = "dev"
+        assert metadata.tags == {"env": "prod"}
+        with pytest.raises(AttributeError):
+            metadata.description = "changed"
+
+        as_dict = persist.get_metadata("typed.json.gz", base_dir=temp_dir, as_dict=True)
+        assert type(as_dict) is dict
+        assert as_dict == metadata.to_dict()
+        assert metadata.get("missing", "default") == "default"
+        with pytest.raises(KeyError):
+            metadata["missing"]
+
+        [listed] = persist.list_snapshots(base_dir=temp_dir)
+        assert listed.path == "typed.json.gz"
+        assert listed.to_dict() == {**as_dict, "path": "typed.json.gz"}
+        [listed] = persist.list_snapshots(base_dir=temp_dir, as_dict=True)
+        assert type(listed) is dict
+
+    def test_metadata_repr(self, temp_dir):
+        """Test that the repr of SnapshotMetadata is stable."""
+        persist.snapshot_json({}, "a/b.json.gz", "bot", "s'1", 2, base_dir=temp_dir)
+
+        metadata = persist.get_metadata("a/b.json.gz", base_dir=temp_dir)
+        assert repr(metadata) == (
+            "SnapshotMetadata(agent_id='bot', session_id=\"s'1\", snapshot_index=2, "
+            f"snapshot_id='{metadata.snapshot_id}', path=None)"
+        )
+        [listed] = persist.list_snapshots(base_dir=temp_dir)
+        assert repr(listed).endswith(", path='a/b.json.gz')")
+
+    def test_type_stubs_check_with_mypy(self, tmp_path):
+        """Test that a typed consumer of the API passes mypy against persist.pyi."""
+        pytest.importorskip("mypy")
+        stubs = tmp_path / "stubs"
+        stubs.mkdir()
+        shutil.copy(Path(__file__).parent.parent / "persist.pyi", stubs / "persist.pyi")
+        consumer = tmp_path / "consumer.py"
+        consumer.write_text(
+            textwrap.dedent(
+                """
+                from datetime import datetime
+                from typing import Any, Dict, List, Tuple
+
+                import persist
+
+                def newest(prefix: str) -> datetime:
+                    snapshots: List[persist.SnapshotMetadata] = persist.list_snapshots(prefix)
+                    return max(s.timestamp for s in snapshots)
+
+                def describe(path: str) -> str:
+                    metadata = persist.get_metadata(path)
+                    index: int = metadata.snapshot_index
+                    tags: Dict[str, str] = metadata.tags
+                    return f"{metadata.agent_id} {index} {tags} {metadata.path}"
+
+                def raw(path: str) -> Dict[str, Any]:
+                    return persist.get_metadata(path, as_dict=True)
+
+                def latest(client: persist.PersistClient) -> Tuple[Any, str]:
+                    agent, metadata = client.restore_latest("agent1")
+                    listed: List[persist.SnapshotMetadata] = client.list("agent1/")
+                    return agent, metadata.snapshot_id + str(len(listed))
+
+                try:
+                    persist.restore("agent1/snapshot.json.gz")
+                except persist.PersistIntegrityError as e:
+                    print(e)
+                """
+            )
+        )
+
+        result = subprocess.run(
+            [sys.executable, "-m", "mypy", "--strict", "--no-incremental", str(consumer)],
+            cwd=tmp_path,
+            env={**os.environ, "MYPYPATH": str(stubs)},
+            capture_output=True,
+            text=True,
+        )
+        assert result.returncode == 0, result.stdout + result.stderr
+
+    def test_list_snapshots_filters(self, temp_dir, sample_agent_data):
+        """Test listing snapshots with metadata filters."""
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            for i in range(6):
+                persist.snapshot(
+                    sample_agent_data,
+                    os.path.join(temp_dir, f"snap_{i}.json.gz"),
+                    agent_id=f"agent{i % 2}",
+                    snapshot_index=i,
+                )
+
+        prefix = temp_dir + os.sep
+        assert len(persist.list_snapshots(prefix)) == 6
+
+        agent0 = persist.list_snapshots(prefix, agent_id="agent0", sort="index_desc")
+        assert [s["snapshot_index"] for s in agent0] == [4, 2, 0]
+        assert all(s["path"].startswith(prefix) for s in agent0)
+
+        limited = persist.list_snapshots(prefix, min_index=2, limit=2, sort="index")
+        assert [s["snapshot_index"] for s in limited] == [2, 3]
+
+        with pytest.raises(persist.PersistConfigurationError):
+            persist.list_snapshots(prefix, sort="sideways")
+
+    def test_snapshot_tags(self, temp_dir):
+        """Test setting tags on save, reading them back and filtering on them."""
+        persist.snapshot_json({}, "prod.json.gz", tags={"env": "prod"}, base_dir=temp_dir)
+        persist.snapshot_json(
+            {}, "dev.json.gz", tags={"env": "dev", "team": "ml"}, base_dir=temp_dir
+        )
+        persist.snapshot({}, "untagged.json.gz", serializer=json.dumps, base_dir=temp_dir)
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            client.snapshot({}, "client.json.gz", serializer=json.dumps, tags={"env": "prod"})
+
+        assert persist.get_metadata("dev.json.gz", base_dir=temp_dir)["tags"] == {
+            "env": "dev",
+            "team": "ml",
+        }
+        assert persist.get_metadata("untagged.json.gz", base_dir=temp_dir)["tags"] == {}
+
+        prod = persist.list_snapshots(tags={"env": "prod"}, sort="none", base_dir=temp_dir)
+        assert sorted(s["path"] for s in prod) == ["client.json.gz", "prod.json.gz"]
+        assert all(s["tags"] == {"env": "prod"} for s in prod)
+        assert persist.list_snapshots(tags={"env": "dev", "team": "infra"}, base_dir=temp_dir) == []
+
+    def test_snapshot_tags_validation(self, temp_dir):
+        """Test that invalid tags are rejected before anything is written."""
+        path = os.path.join(temp_dir, "tags.json.gz")
+
+        with pytest.raises(TypeError, match="Tag keys must be str"):
+            persist.snapshot_json({}, path, tags={1: "one"})
+        with pytest.raises(TypeError, match="tag 'count' must be str"):
+            persist.snapshot_json({}, path, tags={"count": 3})
+        with pytest.raises(ValueError, match="Too many tags"):
+            persist.snapshot_json({}, path, tags={f"k{i}": "v" for i in range(65)})
+        with pytest.raises(ValueError, match="1 to 128 bytes"):
+            persist.snapshot_json({}, path, tags={"k" * 129: "v"})
+        with pytest.raises(ValueError, match="1 to 128 bytes"):
+            persist.snapshot_json({}, path, tags={"": "v"})
+        with pytest.raises(ValueError, match="maximum 256"):
+            persist.snapshot(object(), path, serializer=lambda agent: "{}", tags={"k": "v" * 257})
+        assert not os.path.exists(path)
+
+        # Limits are inclusive
+        tags = {f"{i:0128d}": "v" * 256 for i in range(64)}
+        persist.snapshot_json({}, path, tags=tags)
+        assert persist.get_metadata(path)["tags"] == tags
+
+    def test_list_snapshots_nested(self, temp_dir, sample_agent_data):
+        """Test listing snapshots in nested directories, by prefix and session."""
+        layout = [
+            ("bot_a/s1/0.json.gz", "bot_a", "s1", 0),
+            ("bot_a/s1/1.json.gz", "bot_a", "s1", 1),
+            ("bot_a/s2/0.json.gz", "bot_a", "s2", 0),
+            ("bot_b/s1/0.json.gz", "bot_b", "s1", 0),
+        ]
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            for path, agent_id, session_id, index in layout:
+                full_path = os.path.join(temp_dir, path)
+                os.makedirs(os.path.dirname(full_path), exist_ok=True)
+                persist.snapshot(
+                    sample_agent_data,
+                    full_path,
+                    agent_id=agent_id,
+                    session_id=session_id,
+                    snapshot_index=index,
+                    description=f"{agent_id} {session_id}",
+                )
+        with open(os.path.join(temp_dir, "bot_a", "notes.txt"), "w") as f:
+            f.write("not a snapshot")
+
+        prefix = temp_dir + os.sep
+        everything = persist.list_snapshots(prefix, sort="agent")
+        assert [s["path"] for s in everything] == [
+            os.path.join(temp_dir, path) for path, *_ in layout
+        ]
+        for snap in everything:
+            for key in (
+                "path",
+                "agent_id",
+                "session_id",
+                "snapshot_index",
+                "timestamp",
+                "compressed_size",
+                "description",
+                "tags",
+            ):
+                assert key in snap
+
+        bot_a = persist.list_snapshots(os.path.join(temp_dir, "bot_a") + os.sep)
+        assert len(bot_a) == 3
+        s1 = persist.list_snapshots(prefix, agent_id="bot_a", session_id="s1")
+        assert sorted(s["snapshot_index"] for s in s1) == [0, 1]
+        assert all(s["description"] == "bot_a s1" for s in s1)
+
+        # Prefixes nothing is stored under list nothing rather than failing
+        assert persist.list_snapshots(os.path.join(temp_dir, "missing") + os.sep) == []
+        assert persist.list_snapshots(prefix, agent_id="nobody") == []
+
+    def test_client_reuses_engine(self, temp_dir, sample_agent_data):
+        """Test that a client creates its engine once and works like the functions."""
+        before = persist._engines_created()
+        with persist.PersistClient(base_dir=temp_dir, compression="none") as client:
+            assert persist._engines_created() == before + 1
+            with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+                for i in range(3):
+                    client.snapshot(
+                        sample_agent_data, f"nested/{i}.json.gz", snapshot_index="auto"
+                    )
+            for i in range(3):
+                assert client.exists(f"nested/{i}.json.gz")
+                client.verify(f"nested/{i}.json.gz")
+                assert client.get_metadata(f"nested/{i}.json.gz")["snapshot_index"] == i
+            listed = client.list("nested/", sort="index")
+            assert [s["path"] for s in listed] == [f"nested/{i}.json.gz" for i in range(3)]
+            assert listed[0]["compression_algorithm"] == "none"
+            client.delete("nested/0.json.gz")
+            assert not client.exists("nested/0.json.gz")
+            assert persist._engines_created() == before + 1
+
+        with pytest.raises(persist.PersistError):
+            client.exists("nested/1.json.gz")
+
+        # Module-level functions set up an engine per call
+        persist.snapshot_exists(os.path.join(temp_dir, "nested", "1.json.gz"))
+        assert persist._engines_created() == before + 2
+
+    def test_client_reuses_s3_adapter(self, monkeypatch):
+        """Test that repeated S3-mode calls on one client share one adapter."""
+        monkeypatch.setenv("AWS_ACCESS_KEY_ID", "test")
+        monkeypatch.setenv("AWS_SECRET_ACCESS_KEY", "test")
+        monkeypatch.setenv("AWS_ENDPOINT_URL", "http://127.0.0.1:1")
+        before = persist._engines_created()
+        try:
+            client = persist.PersistClient(
+                storage_mode="s3", s3_bucket="persist-test-bucket", s3_region="us-east-1"
+            )
+        except persist.PersistError as e:
+            if "not available" in str(e):
+                pytest.skip("persist was built without S3 support")
+            raise
+        for _ in range(5):
+            assert not client.exists("missing.json.gz")
+        assert persist._engines_created() == before + 1
+        client.close()
+
+    def test_s3_explicit_endpoint_and_credentials(self, monkeypatch):
+        """Test that explicit S3 kwargs work without AWS environment variables."""
+        for name in ("AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY", "AWS_SESSION_TOKEN"):
+            monkeypatch.delenv(name, raising=False)
+        monkeypatch.setenv("AWS_ENDPOINT_URL", "http://127.0.0.1:2")
+        try:
+            client = persist.PersistClient(
+                storage_mode="s3",
+                s3_bucket="persist-test-bucket",
+                s3_region="us-east-1",
+                s3_endpoint_url="http://127.0.0.1:1",
+                s3_access_key_id="AKIDEXAMPLE",
+                s3_secret_access_key="not-a-real-secret",
+                s3_session_token="not-a-real-token",
+                s3_force_path_style=True,
+            )
+        except persist.PersistError as e:
+            if "not available" in str(e):
+                pytest.skip("persist was built without S3 support")
+            raise
+        with pytest.raises((persist.PersistError, OSError)) as excinfo:
+            client.get_metadata("missing.json.gz")
+        # The explicit endpoint wins over AWS_ENDPOINT_URL, and secrets never leak
+        message = str(excinfo.value)
+        assert "127.0.0.1:2" not in message
+        assert "not-a-real-secret" not in message
+        assert "not-a-real-token" not in message
+        client.close()
+
+    def test_s3_partial_credentials_rejected(self):
+        """Test that S3 credentials must be given as a key id and secret pair."""
+        with pytest.raises(persist.PersistConfigurationError) as excinfo:
+            persist.PersistClient(
+                storage_mode="s3", s3_bucket="bucket", s3_secret_access_key="not-a-real-secret"
+            )
+        assert "not-a-real-secret" not in str(excinfo.value)
+        with pytest.raises(persist.PersistConfigurationError):
+            persist.snapshot_exists(
+                "a.json.gz", storage_mode="s3", s3_bucket="bucket", s3_session_token="token"
+            )
+
+    @pytest.mark.skipif(
+        os.environ.get("RUN_LOCALSTACK_TESTS") != "1",
+        reason="set RUN_LOCALSTACK_TESTS=1 and run LocalStack with a persist-test-bucket",
+    )
+    def test_s3_bytes_round_trip_localstack(self):
+        """Test a snapshot round trip against LocalStack configured through kwargs only."""
+        options = dict(
+            storage_mode="s3",
+            s3_bucket="persist-test-bucket",
+            s3_region="us-east-1",
+            s3_endpoint_url=os.environ.get("PERSIST_TEST_S3_ENDPOINT", "http://localhost:4566"),
+            s3_access_key_id="test",
+            s3_secret_access_key="test",
+            s3_force_path_style=True,
+        )
+        path = f"python-kwargs/{time.time_ns()}.bin.gz"
+        persist.snapshot_bytes(b"payload", path, **options)
+        assert persist.restore_bytes(path, **options) == b"payload"
+        persist.delete_snapshot(path, **options)
+
+    def test_client_shared_between_threads(self, temp_dir, sample_agent_data):
+        """Test concurrent use of one client from several Python threads."""
+        client = persist.PersistClient(base_dir=temp_dir)
+        before = persist._engines_created()
+        errors = []
+
+        def worker(i):
+            try:
+                path = f"threads/{i}.json.gz"
+                client.snapshot(sample_agent_data, path, agent_id=f"agent{i}")
+                assert client.get_metadata(path)["agent_id"] == f"agent{i}"
+                client.verify(path)
+            except Exception as e:  # pragma: no cover - reported below
+                errors.append(e)
+
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            threads = [threading.Thread(target=worker, args=(i,)) for i in range(8)]
+            for thread in threads:
+                thread.start()
+            for thread in threads:
+                thread.join()
+
+        assert errors == []
+        assert len(client.list("threads/")) == 8
+        assert persist._engines_created() == before
+
+    @pytest.mark.skipif((os.cpu_count() or 1) < 4, reason="needs at least 4 CPUs")
+    def test_concurrent_snapshots_release_gil(self, temp_dir):
+        """Test that snapshots from several threads run in parallel."""
+        data = os.urandom(5 * 1024 * 1024)  # Incompressible, so gzip does real work
+
+        def save(name):
+            persist.snapshot_bytes(data, os.path.join(temp_dir, f"{name}.bin.gz"))
+
+        single = min(self._timed(lambda: save(f"single{i}")) for i in range(2))
+
+        threads = [threading.Thread(target=save, args=(f"thread{i}",)) for i in range(8)]
+
+        def run_threads():
+            for thread in threads:
+                thread.start()
+            for thread in threads:
+                thread.join()
+
+        parallel = self._timed(run_threads)
+        # Holding the GIL through compression and I/O would serialize all 8 saves
+        assert parallel < 6 * single, f"8 threads took {parallel:.2f}s, one took {single:.2f}s"
+        for i in range(8):
+            assert persist.restore_bytes(os.path.join(temp_dir, f"thread{i}.bin.gz")) == data
+
+    @staticmethod
+    def _timed(function):
+        start = time.perf_counter()
+        function()
+        return time.perf_counter() - start
+
+    def test_callbacks_and_errors_from_threads(self, temp_dir):
+        """Test callbacks and exceptions of calls made while other threads hold the GIL."""
+        client = persist.PersistClient(base_dir=temp_dir)
+        reports = {i: [] for i in range(4)}
+        errors = []
+
+        class Stop(Exception):
+            pass
+
+        def stop(done, total):
+            raise Stop()
+
+        def worker(i):
+            try:
+                path = f"callbacks/{i}.bin.gz"
+                client.snapshot_bytes(
+                    b"x" * 100_000,
+                    path,
+                    progress_callback=lambda done, total: reports[i].append((done, total)),
+                )
+                with pytest.raises(Stop):
+                    client.restore_bytes(path, progress_callback=stop)
+                with pytest.raises(ValueError):
+                    client.snapshot({"n": i}, path, serializer=self._raise_value_error)
+                with pytest.raises((persist.PersistError, OSError)):
+                    client.get_metadata(f"callbacks/missing{i}.bin.gz")
+            except Exception as e:  # pragma: no cover - reported below
+                errors.append(e)
+
+        threads = [threading.Thread(target=worker, args=(i,)) for i in range(4)]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        assert errors == []
+        for i in range(4):
+            assert reports[i] and reports[i][-1][0] == reports[i][-1][1]
+        client.close()
+
+    @staticmethod
+    def _raise_value_error(agent):
+        raise ValueError("cannot serialize")
+
+    def test_custom_serializer_roundtrip(self, temp_dir):
+        """Test snapshot/restore with custom serializer and deserializer callbacks."""
+
+        class CounterAgent:
+            def __init__(self, name, count):
+                self.name = name
+                self.count = count
+
+            def to_json(self):
+                return json.dumps({"name": self.name, "count": self.count})
+
+            @classmethod
+            def from_json(cls, text, secrets=None):
+                data = json.loads(text)
+                agent = cls(data["name"], data["count"])
+                agent.secrets = secrets
+                return agent
+
+        path = os.path.join(temp_dir, "counter.json.gz")
+        persist.snapshot(CounterAgent("counter", 7), path, serializer=CounterAgent.to_json)
+
+        restored = persist.restore(path, deserializer=CounterAgent.from_json)
+        assert (restored.name, restored.count, restored.secrets) == ("counter", 7, None)
+        with_secrets = persist.restore(
+            path, secrets_map={"api_key": "k"}, deserializer=CounterAgent.from_json
+        )
+        assert with_secrets.secrets == {"api_key": "k"}
+        assert persist.get_metadata(path)["agent_id"] == "default_agent"
+
+        with persist.PersistClient(
+            base_dir=temp_dir,
+            serializer=CounterAgent.to_json,
+            deserializer=CounterAgent.from_json,
+        ) as client:
+            client.snapshot(CounterAgent("client", 3), "client.json.gz")
+            assert client.restore("client.json.gz").count == 3
+            # Per-call callbacks take precedence over the client's
+            assert client.restore("client.json.gz", deserializer=json.loads) == {
+                "name": "client",
+                "count": 3,
+            }
+
+    def test_custom_serializer_errors_propagate(self, temp_dir):
+        """Test that callback exceptions reach the caller unchanged."""
+
+        class SerializerFailure(Exception):
+            pass
+
+        def failing_serializer(agent):
+            raise SerializerFailure("cannot serialize")
+
+        def failing_deserializer(text):
+            raise KeyError("missing field")
+
+        path = os.path.join(temp_dir, "errors.json.gz")
+        with pytest.raises(SerializerFailure, match="cannot serialize") as excinfo:
+            persist.snapshot(object(), path, serializer=failing_serializer)
+        assert excinfo.traceback[-1].name == "failing_serializer"
+        assert not os.path.exists(path)
+
+        with pytest.raises(TypeError, match="JSON string"):
+            persist.snapshot(object(), path, serializer=lambda agent: 42)
+
+        persist.snapshot_json({"ok": True}, path)
+        with pytest.raises(KeyError, match="missing field") as excinfo:
+            persist.restore(path, deserializer=failing_deserializer)
+        assert excinfo.traceback[-1].name == "failing_deserializer"
+
+    def test_pickle_serializer(self, temp_dir):
+        """Test the opt-in pickle serializer and the refusal to unpickle by default."""
+        path = os.path.join(temp_dir, "legacy.json")
+        agent = LegacyAgent("legacy", {"history": [1, 2], "seen": OrderedDict(a=1, b=2)})
+        persist.snapshot(agent, path, agent_id="legacy", serializer="pickle", compression="none")
+
+        assert persist.get_metadata(path).extra == {"persist.payload": "pickle"}
+        state = json.loads(Path(path).read_text())["agent_state"]
+        assert state["__persist_serializer__"] == "pickle"
+
+        with pytest.raises(persist.PersistValidationError, match="allow_pickle"):
+            persist.restore(path)
+        with pytest.raises(persist.PersistValidationError, match="pickled"):
+            persist.restore_json(path)
+
+        restored = persist.restore(path, allow_pickle=True)
+        assert isinstance(restored, LegacyAgent)
+        assert (restored.name, restored.memory) == (agent.name, agent.memory)
+
+        # The allowlist names every global the pickle may load
+        allowed = [f"{LegacyAgent.__module__}.LegacyAgent", "collections.OrderedDict"]
+        restored = persist.restore(path, allow_pickle=True, pickle_allowlist=allowed)
+        assert restored.memory["seen"] == OrderedDict(a=1, b=2)
+        with pytest.raises(pickle.UnpicklingError, match="collections.OrderedDict"):
+            persist.restore(path, allow_pickle=True, pickle_allowlist=allowed[:1])
+        with pytest.raises(TypeError, match="pickle_allowlist"):
+            persist.restore(path, allow_pickle=True, pickle_allowlist=allowed[0])
+
+        with persist.PersistClient(base_dir=temp_dir, serializer="pickle") as client:
+            client.snapshot(agent, "client.json.gz", agent_id="legacy")
+            with pytest.raises(persist.PersistValidationError):
+                client.restore_latest("legacy")
+            restored, metadata = client.restore_latest("legacy", allow_pickle=True)
+            assert metadata.path == "client.json.gz"
+            assert restored.memory == agent.memory
+
+        with pytest.raises(persist.PersistConfigurationError, match="serializer"):
+            persist.snapshot(agent, path, serializer="marshal")
+
+    def test_check_compatibility(self, temp_dir, monkeypatch):
+        """Test the LangChain version check of restore in its three modes."""
+        monkeypatch.setitem(sys.modules, "langchain_core", types.ModuleType("langchain_core"))
+        sys.modules["langchain_core"].__version__ = "0.3.1"
+        path = os.path.join(temp_dir, "old.json.gz")
+        persist.snapshot_json({"step": 1}, path, agent_id="old", framework=("langchain", "0.1.20"))
+
+        metadata = persist.get_metadata(path)
+        assert (metadata.framework, metadata.framework_version) == ("langchain", "0.1.20")
+
+        with pytest.raises(persist.PersistCompatibilityError, match="0.1.20.*0.3.1") as excinfo:
+            persist.restore(path, deserializer=json.loads, check_compatibility="error")
+        assert isinstance(excinfo.value, persist.PersistFrameworkMismatchError)
+
+        with pytest.warns(UserWarning, match="LangChain 0.1.20, but LangChain 0.3.1"):
+            assert persist.restore(path, deserializer=json.loads) == {"step": 1}
+
+        with warnings.catch_warnings():
+            warnings.simplefilter("error")
+            restored = persist.restore(path, deserializer=json.loads, check_compatibility="ignore")
+            assert restored == {"step": 1}
+
+            # The installed version is read at each call
+            sys.modules["langchain_core"].__version__ = "0.1.3"
+            restored = persist.restore(path, deserializer=json.loads, check_compatibility="error")
+            assert restored == {"step": 1}
+            sys.modules["langchain_core"].__version__ = "0.3.1"
+
+            # Snapshots without a recorded framework are not checked
+            bare = os.path.join(temp_dir, "bare.json.gz")
+            persist.snapshot_json({"step": 2}, bare, agent_id="bare")
+            assert persist.get_metadata(bare).framework_version is None
+            restored = persist.restore(bare, deserializer=json.loads, check_compatibility="error")
+            assert restored == {"step": 2}
+
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            with pytest.raises(persist.PersistCompatibilityError):
+                client.restore_latest("old", deserializer=json.loads, check_compatibility="error")
+            results = client.restore_many(
+                ["old.json.gz", "bare.json.gz"],
+                deserializer=json.loads,
+                check_compatibility="error",
+            )
+            assert isinstance(results[0]["error"], persist.PersistCompatibilityError)
+            assert results[1]["agent"] == {"step": 2}
+
+        with pytest.raises(persist.PersistConfigurationError, match="check_compatibility"):
+            persist.restore(path, deserializer=json.loads, check_compatibility="strict")
+
+    @pytest.mark.parametrize("size", [0, 1, 4096, 3 * 1024 * 1024])
+    def test_snapshot_bytes_roundtrip(self, temp_dir, size):
+        """Test that snapshot_bytes/restore_bytes return exactly the original bytes."""
+        data = os.urandom(size)
+        if size:
+            data = b"\x00" + data + b"\x00\x00"
+        path = os.path.join(temp_dir, "state.bin.gz")
+
+        persist.snapshot_bytes(data, path, agent_id="blob", tags={"codec": "raw"})
+        restored = persist.restore_bytes(path)
+        assert type(restored) is bytes
+        assert restored == data
+
+        metadata = persist.get_metadata(path)
+        assert metadata["persist.payload"] == "bytes"
+        assert metadata["agent_id"] == "blob"
+        assert metadata["tags"] == {"codec": "raw"}
+        persist.verify_snapshot(path)
+
+    def test_snapshot_bytes_payload_kind_is_enforced(self, temp_dir):
+        """Test that bytes and JSON snapshots are not restored with the wrong API."""
+        bytes_path = os.path.join(temp_dir, "state.bin.gz")
+        json_path = os.path.join(temp_dir, "state.json.gz")
+        persist.snapshot_bytes(bytearray(b"\x08\x96\x01"), bytes_path, compression="none")
+        persist.snapshot_json({"step": 1}, json_path)
+
+        with pytest.raises(persist.PersistValidationError, match="restore_bytes"):
+            persist.restore(bytes_path, deserializer=json.loads)
+        with pytest.raises(persist.PersistValidationError, match="restore_bytes"):
+            persist.restore_json(bytes_path, raw=True)
+        with pytest.raises(persist.PersistValidationError, match="restore_json"):
+            persist.restore_bytes(json_path)
+        with pytest.raises(TypeError):
+            persist.snapshot_bytes("not bytes", os.path.join(temp_dir, "str.bin.gz"))
+
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            client.snapshot_bytes(memoryview(b"abc"), "view.bin.gz")
+            assert client.restore_bytes("view.bin.gz") == b"abc"
+            assert client.restore_bytes("state.bin.gz") == b"\x08\x96\x01"
+
+    def test_progress_callback_reports_transfers(self, temp_dir):
+        """Test that progress callbacks see increasing byte counts ending at the total."""
+        data = os.urandom(4 * 1024 * 1024)
+        path = os.path.join(temp_dir, "large.bin.gz")
+
+        def transfer_reports(call):
+            reports = []
+            call(lambda done, total: reports.append((done, total)))
+            assert reports
+            assert all(a[0] <= b[0] for a, b in zip(reports, reports[1:]))
+            assert reports[-1][0] == reports[-1][1]
+            return reports
+
+        saved = transfer_reports(
+            lambda cb: persist.snapshot_bytes(data, path, progress_callback=cb)
+        )
+        loaded = transfer_reports(lambda cb: persist.restore_bytes(path, progress_callback=cb))
+        # Both see the compressed snapshot file
+        assert saved[-1][1] == loaded[-1][1] == os.path.getsize(path)
+
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            saved = transfer_reports(
+                lambda cb: client.snapshot(
+                    {"step": 1}, "small.json.gz", serializer=json.dumps, progress_callback=cb
+                )
+            )
+            loaded = transfer_reports(
+                lambda cb: client.restore(
+                    "small.json.gz", deserializer=json.loads, progress_callback=cb
+                )
+            )
+            size = os.path.getsize(os.path.join(temp_dir, "small.json.gz"))
+            assert saved == loaded == [(size, size)]
+
+    def test_progress_callback_exception_aborts(self, temp_dir):
+        """Test that an exception raised by a progress callback aborts the transfer."""
+
+        class Stop(Exception):
+            pass
+
+        def stop(done, total):
+            raise Stop(done)
+
+        data = os.urandom(4 * 1024 * 1024)
+        path = os.path.join(temp_dir, "large.bin.gz")
+        with pytest.raises(Stop):
+            persist.snapshot_bytes(data, path, progress_callback=stop)
+        # Neither the snapshot nor a temporary file is left behind
+        assert os.listdir(temp_dir) == []
+
+        persist.snapshot_bytes(data, path)
+        with persist.PersistClient() as client:
+            with pytest.raises(Stop):
+                client.restore_bytes(path, progress_callback=stop)
+            # The client stays usable
+            assert client.restore_bytes(path) == data
+
+    def test_restore_latest_picks_newest_snapshot(self, temp_dir):
+        """Test that restore_latest resolves the newest snapshot of an agent."""
+        # Within a session the highest index wins, even when written first
+        persist.snapshot_json({"step": 5}, "s1/high.json.gz", "bot", "s1", 5, base_dir=temp_dir)
+        persist.snapshot_json({"step": 2}, "s1/low.json.gz", "bot", "s1", 2, base_dir=temp_dir)
+        # Across sessions the most recently created snapshot wins
+        persist.snapshot_json({"step": 0}, "s2/first.json.gz", "bot", "s2", 0, base_dir=temp_dir)
+        persist.snapshot_json({"step": 9}, "other.json.gz", "other-bot", base_dir=temp_dir)
+
+        agent, metadata = persist.restore_latest(
+            "bot", session_id="s1", base_dir=temp_dir, deserializer=json.loads
+        )
+        assert agent == {"step": 5}
+        assert metadata["path"] == "s1/high.json.gz"
+        assert metadata["snapshot_index"] == 5
+
+        agent, metadata = persist.restore_latest("bot", base_dir=temp_dir, deserializer=json.loads)
+        assert agent == {"step": 0}
+        assert metadata["session_id"] == "s2"
+
+        latest = persist.latest_metadata("bot", prefix="s1/", base_dir=temp_dir)
+        assert latest["path"] == "s1/high.json.gz"
+        metadata = persist.get_metadata("s1/high.json.gz", base_dir=temp_dir)
+        assert latest.to_dict() == {**metadata.to_dict(), "path": "s1/high.json.gz"}
+
+        with persist.PersistClient(base_dir=temp_dir, deserializer=json.loads) as client:
+            agent, metadata = client.restore_latest("other-bot")
+            assert (agent, metadata["path"]) == ({"step": 9}, "other.json.gz")
+            assert client.latest_metadata("bot", session_id="s2")["path"] == "s2/first.json.gz"
+
+    def test_restore_latest_breaks_ties_by_path(self, temp_dir):
+        """Test that snapshots with the same index resolve to a stable choice."""
+        for name in ("b", "c", "a"):
+            path = f"ties/{name}.json.gz"
+            persist.snapshot_json({"name": name}, path, "bot", "s", 1, base_dir=temp_dir)
+
+        paths = {
+            persist.latest_metadata("bot", session_id="s", base_dir=temp_dir)["path"]
+            for _ in range(3)
+        }
+        assert len(paths) == 1
+        # The last write has the latest timestamp unless the clock did not move
+        assert paths <= {"ties/a.json.gz", "ties/c.json.gz"}
+
+    def test_restore_latest_without_match(self, temp_dir):
+        """Test that restore_latest/latest_metadata raise FileNotFoundError."""
+        with pytest.raises(FileNotFoundError, match="agent ghost"):
+            persist.restore_latest("ghost", base_dir=temp_dir)
+        with pytest.raises(FileNotFoundError, match="agent ghost, session s1"):
+            persist.latest_metadata("ghost", session_id="s1", base_dir=temp_dir)
+
+        persist.snapshot_json({}, "ghost/state.json.gz", "ghost", "s1", base_dir=temp_dir)
+        with pytest.raises(FileNotFoundError):
+            persist.latest_metadata("ghost", prefix="elsewhere/", base_dir=temp_dir)
+        with pytest.raises(FileNotFoundError):
+            persist.latest_metadata("ghost", session_id="s2", base_dir=temp_dir)
+
+    def test_snapshot_json_dict_and_string(self, temp_dir):
+        """Test snapshot_json/restore_json with dict and string state."""
+        state = {"step": 3, "notes": ["a", "b"], "nested": {"ok": True, "score": 0.5}}
+        dict_path = os.path.join(temp_dir, "dict.json.gz")
+        string_path = os.path.join(temp_dir, "string.json.gz")
+
+        persist.snapshot_json(state, dict_path, agent_id="plain", snapshot_index=2)
+        persist.snapshot_json(json.dumps(state), string_path)
+
+        assert persist.restore_json(dict_path) == state
+        assert persist.restore_json(string_path) == state
+        assert json.loads(persist.restore_json(dict_path, raw=True)) == state
+
+        metadata = persist.get_metadata(dict_path)
+        assert metadata["agent_id"] == "plain"
+        assert metadata["snapshot_index"] == 2
+        persist.verify_snapshot(dict_path)
+
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            client.snapshot_json([1, 2, 3], "client/list.json.gz")
+            assert client.restore_json("client/list.json.gz") == [1, 2, 3]
+            assert client.restore_json("dict.json.gz") == state
+
+    def test_snapshot_json_rejects_non_serializable(self, temp_dir):
+        """Test that unserializable state and invalid JSON text raise PersistError."""
+        path = os.path.join(temp_dir, "bad.json.gz")
+        with pytest.raises(persist.PersistError, match="not JSON serializable"):
+            persist.snapshot_json({"when": object()}, path)
+        with pytest.raises(persist.PersistError):
+            persist.snapshot_json("{not json", path)
+        assert not persist.snapshot_exists(path)
+
+    @pytest.mark.skipif(not LANGCHAIN_AVAILABLE, reason="LangChain not available")
+    def test_snapshot_json_interop_with_restore(self, temp_dir):
+        """Test that LangChain dumps saved with snapshot_json restore with restore."""
+        from langchain_core.load import dumps
+
+        message = HumanMessage(content="hello from json")
+        path = os.path.join(temp_dir, "message.json.gz")
+        persist.snapshot_json(dumps(message), path)
+
+        restored = persist.restore(path)
+        assert restored.content == "hello from json"
+
+        # And snapshots written by snapshot restore as plain JSON
+        other = os.path.join(temp_dir, "langchain.json.gz")
+        persist.snapshot(message, other)
+        assert persist.restore_json(other)["kwargs"]["content"] == "hello from json"
+
+    def test_aio_functions_do_not_block_loop(self, temp_dir, sample_agent_data):
+        """Test that persist.aio calls leave the event loop free to run other tasks."""
+        import persist.aio
+
+        paths = [os.path.join(temp_dir, "aio", f"{i}.json.gz") for i in range(8)]
+
+        async def main():
+            ticks = 0
+            done = asyncio.Event()
+
+            async def heartbeat():
+                nonlocal ticks
+                while not done.is_set():
+                    ticks += 1
+                    await asyncio.sleep(0)
+
+            beat = asyncio.create_task(heartbeat())
+            await asyncio.gather(
+                *(
+                    persist.aio.snapshot(sample_agent_data, path, snapshot_index=i)
+                    for i, path in enumerate(paths)
+                )
+            )
+            metadata = await asyncio.gather(*(persist.aio.get_metadata(p) for p in paths))
+            listed = await persist.aio.list_snapshots(
+                os.path.join(temp_dir, "aio") + os.sep, sort="index"
+            )
+            done.set()
+            await beat
+            return ticks, metadata, listed
+
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            ticks, metadata, listed = asyncio.run(main())
+
+        assert ticks > 1
+        assert [m["snapshot_index"] for m in metadata] == list(range(8))
+        assert [s["path"] for s in listed] == paths
+
+    def test_aio_errors_propagate(self, temp_dir):
+        """Test that awaiting a failed async call raises the persist exception."""
+        import persist.aio
+
+        async def main():
+            await persist.aio.get_metadata(os.path.join(temp_dir, "missing.json.gz"))
+
+        with pytest.raises(persist.PersistError):
+            asyncio.run(main())
+
+    def test_client_async_methods(self, temp_dir, sample_agent_data):
+        """Test the async_ methods of PersistClient."""
+
+        async def main(client):
+            await asyncio.gather(
+                *(
+                    client.async_snapshot(
+                        sample_agent_data, f"async/{i}.json.gz", agent_id=f"agent{i}"
+                    )
+                    for i in range(4)
+                )
+            )
+            metadata = await client.async_get_metadata("async/2.json.gz")
+            listed = await client.async_list("async/")
+            return metadata, listed
+
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            before = persist._engines_created()
+            with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+                metadata, listed = asyncio.run(main(client))
+            assert persist._engines_created() == before
+
+        assert metadata["agent_id"] == "agent2"
+        assert len(listed) == 4
+
+    @pytest.mark.parametrize("codec", ["gzip", "zstd", "none"])
+    def test_compression_codecs(self, temp_dir, codec):
+        """Test that the chosen codec is recorded and any codec restores."""
+        if codec not in persist.COMPRESSION_ALGORITHMS:
+            pytest.skip(f"persist was built without {codec} support")
+        state = {"messages": ["the same message again"] * 500}
+        path = os.path.join(temp_dir, f"{codec}.json.gz")
+
+        persist.snapshot_json(state, path, compression=codec)
+
+        metadata = persist.get_metadata(path)
+        assert metadata["compression_algorithm"] == codec
+        assert persist.restore_json(path) == state
+        with persist.PersistClient(compression="gzip") as client:
+            assert client.restore_json(path) == state
+
+    def test_compression_reduces_size(self, temp_dir):
+        """Test that compressed snapshots of compressible state are smaller."""
+        state = {"messages": ["the same message again"] * 500}
+        sizes = {}
+        for codec in persist.COMPRESSION_ALGORITHMS:
+            path = os.path.join(temp_dir, f"{codec}.json.gz")
+            persist.snapshot_json(state, path, compression=codec)
+            sizes[codec] = os.path.getsize(path)
+        for codec, size in sizes.items():
+            if codec != "none":
+                assert size * 4 < sizes["none"]
+
+        fast = os.path.join(temp_dir, "fast.json.gz")
+        with persist.PersistClient(compression="gzip", compression_level=1) as client:
+            client.snapshot_json(state, fast)
+        assert persist.restore_json(fast) == state
+
+    def test_compression_rejects_invalid_options(self, temp_dir):
+        """Test the errors for unknown codecs and out-of-range levels."""
+        path = os.path.join(temp_dir, "bad.json.gz")
+        with pytest.raises(persist.PersistConfigurationError) as excinfo:
+            persist.snapshot_json({}, path, compression="brotli")
+        message = str(excinfo.value)
+        assert "brotli" in message
+        for codec in persist.COMPRESSION_ALGORITHMS:
+            assert codec in message
+
+        with pytest.raises(persist.PersistConfigurationError, match="out of range"):
+            persist.snapshot_json({}, path, compression="gzip", compression_level=42)
+        with pytest.raises(persist.PersistConfigurationError):
+            persist.PersistClient(compression="none", compression_level=3)
+        if "zstd" not in persist.COMPRESSION_ALGORITHMS:
+            with pytest.raises(persist.PersistConfigurationError, match="zstd"):
+                persist.snapshot_json({}, path, compression="zstd")
+        assert not os.path.exists(path)
+
+    def test_base_dir_relative_keys(self, temp_dir, sample_agent_data):
+        """Test that base_dir turns paths into relative keys under one directory."""
+        key = "agent1/session1/snapshot.json.gz"
+        persist.snapshot_json(
+            sample_agent_data, key, agent_id="agent1", base_dir=temp_dir, durable_writes=True
+        )
+
+        assert os.path.isfile(os.path.join(temp_dir, "agent1", "session1", "snapshot.json.gz"))
+        assert persist.snapshot_exists(key, base_dir=temp_dir)
+        assert persist.get_metadata(key, base_dir=temp_dir)["agent_id"] == "agent1"
+        assert persist.restore_json(key, base_dir=temp_dir) == sample_agent_data
+        assert [s["path"] for s in persist.list_snapshots("agent1/", base_dir=temp_dir)] == [key]
+        persist.verify_snapshot(key, base_dir=temp_dir)
+        persist.delete_snapshot(key, base_dir=temp_dir)
+        assert not persist.snapshot_exists(key, base_dir=temp_dir)
+
+    def test_base_dir_rejects_traversal(self, temp_dir, sample_agent_data):
+        """Test that keys escaping base_dir raise PersistValidationError."""
+        base_dir = os.path.join(temp_dir, "store")
+        with pytest.raises(persist.PersistValidationError):
+            persist.snapshot_json(sample_agent_data, "../escape.json.gz", base_dir=base_dir)
+        with pytest.raises(persist.PersistValidationError):
+            persist.restore_json("nested/../../escape.json.gz", base_dir=base_dir)
+        assert not os.path.exists(os.path.join(temp_dir, "escape.json.gz"))
+
+        with persist.PersistClient(base_dir=base_dir) as client:
+            with pytest.raises(persist.PersistError):
+                client.snapshot_json(sample_agent_data, "../escape.json.gz")
+
+        with pytest.raises(persist.PersistConfigurationError):
+            persist.get_metadata("a.json.gz", storage_mode="s3", s3_bucket="b", base_dir=base_dir)
+
+    def test_snapshot_many_and_restore_many(self, temp_dir):
+        """Test that batch saves and restores report failing items in their positions."""
+        base_dir = os.path.join(temp_dir, "store")
+
+        def serializer(agent):
+            if agent == "unserializable":
+                raise RuntimeError("cannot serialize")
+            return json.dumps({"agent": agent})
+
+        items = [
+            {
+                "state_json": {"i": i},
+                "path": f"batch/{i:02}.json.gz",
+                "agent_id": "batch",
+                "snapshot_index": i,
+                "tags": {"i": str(i)},
+            }
+            for i in range(50)
+        ]
+        items[3] = {"agent": "agent-3", "path": "batch/03.json.gz", "agent_id": "batch"}
+        items[7] = {"agent": "unserializable", "path": "batch/07.json.gz"}
+        items[31] = {"state_json": {"i": 31}, "path": "../escape.json.gz"}
+
+        results = persist.snapshot_many(items, base_dir=base_dir, serializer=serializer, parallel=4)
+
+        assert len(results) == 50
+        assert [i for i, result in enumerate(results) if result["error"] is not None] == [7, 31]
+        assert isinstance(results[7]["error"], RuntimeError)
+        assert isinstance(results[31]["error"], persist.PersistValidationError)
+        assert results[31]["path"] == "../escape.json.gz"
+        assert results[7]["metadata"] is None and results[31]["metadata"] is None
+        assert not os.path.exists(os.path.join(temp_dir, "escape.json.gz"))
+        for i, result in enumerate(results):
+            if i not in (3, 7, 31):
+                assert result["path"] == f"batch/{i:02}.json.gz"
+                assert result["metadata"].snapshot_index == i
+                assert result["metadata"].tags == {"i": str(i)}
+
+        paths = [f"batch/{i:02}.json.gz" for i in range(50)]
+        restored = persist.restore_many(
+            paths, base_dir=base_dir, deserializer=json.loads, parallel=4, as_dict=True
+        )
+
+        assert [result["path"] for result in restored] == paths
+        assert [i for i, result in enumerate(restored) if result["error"] is not None] == [7, 31]
+        assert isinstance(restored[7]["error"], OSError)
+        assert restored[7]["agent"] is None and restored[7]["metadata"] is None
+        assert restored[3]["agent"] == {"agent": "agent-3"}
+        assert restored[49]["agent"] == {"i": 49}
+        assert restored[49]["metadata"]["agent_id"] == "batch"
+
+        with persist.PersistClient(base_dir=base_dir) as client:
+            [saved] = client.snapshot_many([{"state_json": "{}", "path": "client.json.gz"}])
+            assert saved["error"] is None
+            [bad] = client.snapshot_many([{"state_json": "{}", "agent": 1, "path": "x.json.gz"}])
+            assert isinstance(bad["error"], ValueError)
+            [result] = client.restore_many(["client.json.gz"], deserializer=json.loads)
+            assert result["agent"] == {}
+            with pytest.raises(ValueError):
+                client.restore_many(["client.json.gz"], parallel=0)
+
+    def test_delete_snapshots_reports_partial_failures(self, temp_dir):
+        """Test that batch deletion reports failed paths without raising."""
+        for name in ("a", "b", "c"):
+            persist.snapshot_json({}, f"run1/{name}.json.gz", base_dir=temp_dir)
+        os.makedirs(os.path.join(temp_dir, "run1", "dir.json.gz"))
+        outside = os.path.join(os.path.dirname(temp_dir), f"{Path(temp_dir).name}-outside.json.gz")
+        Path(outside).write_text("keep")
+
+        try:
+            paths = [
+                "run1/a.json.gz",
+                "run1/dir.json.gz",
+                f"../{Path(outside).name}",
+                "run1/c.json.gz",
+            ]
+            results = persist.delete_snapshots(paths, base_dir=temp_dir, parallel=2)
+
+            assert list(results) == paths
+            assert results["run1/a.json.gz"] is None
+            assert results["run1/c.json.gz"] is None
+            assert isinstance(results["run1/dir.json.gz"], persist.PersistError)
+            assert isinstance(results[f"../{Path(outside).name}"], persist.PersistError)
+            assert os.path.exists(outside)
+            assert not persist.snapshot_exists("run1/a.json.gz", base_dir=temp_dir)
+            assert persist.snapshot_exists("run1/b.json.gz", base_dir=temp_dir)
+        finally:
+            os.remove(outside)
+
+        assert persist.delete_snapshots([], base_dir=temp_dir) == {}
+        with pytest.raises(ValueError):
+            persist.delete_snapshots(["run1/b.json.gz"], base_dir=temp_dir, parallel=0)
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            assert client.delete_many(("run1/b.json.gz",)) == {"run1/b.json.gz": None}
+            assert not client.exists("run1/b.json.gz")
+
+    def test_delete_prefix(self, temp_dir):
+        """Test dry runs, deletion and base_dir confinement of delete_prefix."""
+        base_dir = os.path.join(temp_dir, "store")
+        for i in range(12):
+            persist.snapshot_json({"i": i}, f"run1/{i:02}.json.gz", base_dir=base_dir)
+        persist.snapshot_json({}, "run10/keep.json.gz", base_dir=base_dir)
+        persist.snapshot_json({}, os.path.join(temp_dir, "run1-outside.json.gz"))
+        Path(base_dir, "run1", "notes.txt").write_text("not a snapshot")
+        expected = [f"run1/{i:02}.json.gz" for i in range(12)]
+
+        dry_run = persist.delete_prefix("run1/", dry_run=True, base_dir=base_dir)
+        assert dry_run == dict.fromkeys(expected)
+        assert len(persist.list_snapshots("run1/", base_dir=base_dir)) == 12
+
+        with pytest.raises(persist.PersistValidationError):
+            persist.delete_prefix("../", base_dir=base_dir)
+        with pytest.raises(persist.PersistValidationError):
+            persist.delete_prefix("../run1", dry_run=True, base_dir=base_dir)
+        assert os.path.exists(os.path.join(temp_dir, "run1-outside.json.gz"))
+
+        results = persist.delete_prefix("run1/", base_dir=base_dir, parallel=3)
+        assert results == dict.fromkeys(expected)
+        assert persist.list_snapshots("run1/", base_dir=base_dir) == []
+        assert Path(base_dir, "run1", "notes.txt").exists()
+        assert persist.snapshot_exists("run10/keep.json.gz", base_dir=base_dir)
+        assert os.path.exists(os.path.join(temp_dir, "run1-outside.json.gz"))
+
+        with persist.PersistClient(base_dir=base_dir) as client:
+            assert client.delete_prefix("run", dry_run=True) == {"run10/keep.json.gz": None}
+            assert client.delete_prefix("run1/") == {}
+
+    def test_prune(self, temp_dir):
+        """Test the survivors of each retention rule and dry runs of prune."""
+        storage = {"base_dir": temp_dir}
+        paths = []
+        for run, count in (("a", 4), ("b", 2)):
+            for i in range(count):
+                path = f"runs/{run}/{i}.json.gz"
+                persist.snapshot_json(
+                    {"run": run, "step": i}, path, agent_id=run, snapshot_index=i, **storage
+                )
+                paths.append(path)
+                time.sleep(0.01)
+        sizes = {path: persist.get_metadata(path, **storage).compressed_size for path in paths}
+
+        plan = persist.prune("runs/", keep_last=1, dry_run=True, **storage)
+        expected = [path for path in paths if path not in ("runs/a/3.json.gz", "runs/b/1.json.gz")]
+        assert plan["dry_run"] is True
+        assert plan["deleted"] == expected
+        assert plan["kept"] == ["runs/a/3.json.gz", "runs/b/1.json.gz"]
+        assert plan["reclaimed_bytes"] == sum(sizes[path] for path in expected)
+        assert all(persist.snapshot_exists(path, **storage) for path in paths)
+
+        # The size rule deletes the oldest snapshots until the rest fit
+        total = sum(sizes.values())
+        plan = persist.prune("runs/", max_total_bytes=total - 1, dry_run=True, **storage)
+        assert plan["deleted"] == ["runs/a/0.json.gz"]
+
+        result = persist.prune("runs/a/", keep_last=2, **storage)
+        assert result["deleted"] == ["runs/a/0.json.gz", "runs/a/1.json.gz"]
+        assert result["failed"] == {}
+        assert result["kept_bytes"] == sizes["runs/a/2.json.gz"] + sizes["runs/a/3.json.gz"]
+        remaining = sorted(s.path for s in persist.list_snapshots("runs/", **storage))
+        assert remaining == ["runs/a/2.json.gz", "runs/a/3.json.gz"] + paths[4:]
+
+        with persist.PersistClient(**storage) as client:
+            assert client.prune("runs/b/", keep_last=2)["deleted"] == []
+
+        with pytest.raises(ValueError, match="retention rule"):
+            persist.prune("runs/", **storage)
+
+    def test_prune_older_than(self, temp_dir):
+        """Test that older_than accepts seconds and timedeltas."""
+        storage = {"base_dir": temp_dir}
+        persist.snapshot_json({"step": 0}, "old.json.gz", **storage)
+        time.sleep(1.5)
+        persist.snapshot_json({"step": 1}, "new.json.gz", **storage)
+
+        assert persist.prune(older_than=1, dry_run=True, **storage)["deleted"] == ["old.json.gz"]
+        assert persist.prune(older_than=0.5, dry_run=True, **storage)["deleted"] == ["old.json.gz"]
+        assert persist.prune(older_than=3600, dry_run=True, **storage)["deleted"] == []
+        result = persist.prune(older_than=timedelta(seconds=1), **storage)
+        assert result["deleted"] == ["old.json.gz"]
+        assert not persist.snapshot_exists("old.json.gz", **storage)
+        assert persist.prune(older_than=timedelta(hours=1), **storage)["deleted"] == []
+
+        with pytest.raises(ValueError, match="non-negative"):
+            persist.prune(older_than=-1, **storage)
+        with pytest.raises(ValueError, match="non-negative"):
+            persist.prune(older_than=timedelta(seconds=-5), **storage)
+        with pytest.raises(TypeError, match="older_than"):
+            persist.prune(older_than="7d", **storage)
+
+    def test_auto_snapshotter(self, temp_dir):
+        """Test the cadence, index sequencing and retention of AutoSnapshotter."""
+        now = [100.0]
+        client = persist.PersistClient(
+            base_dir=temp_dir, serializer=json.dumps, deserializer=json.loads
+        )
+        snapshotter = persist.AutoSnapshotter(
+            client,
+            "loop",
+            "s1",
+            every_steps=3,
+            every_seconds=10,
+            keep_last=2,
+            clock=lambda: now[0],
+        )
+
+        saved = {}
+        for step in range(1, 10):
+            now[0] += 1
+            path = snapshotter.maybe_snapshot({"step": step})
+            if path is not None:
+                saved[step] = path
+        assert saved == {
+            3: "loop/s1/snapshot-000000.json.gz",
+            6: "loop/s1/snapshot-000001.json.gz",
+            9: "loop/s1/snapshot-000002.json.gz",
+        }
+
+        # Ten seconds after the last snapshot one step is enough
+        now[0] += 10
+        assert snapshotter.maybe_snapshot({"step": 10}) == "loop/s1/snapshot-000003.json.gz"
+        assert snapshotter.maybe_snapshot({"step": 11}) is None
+        assert snapshotter.maybe_snapshot({"step": 12}, force=True) == (
+            "loop/s1/snapshot-000004.json.gz"
+        )
+        kept = client.list("loop/", sort="index")
+        assert [metadata.snapshot_index for metadata in kept] == [3, 4]
+        assert client.restore("loop/s1/snapshot-000004.json.gz") == {"step": 12}
+
+        # A new snapshotter continues after the stored indexes
+        resumed = persist.AutoSnapshotter(client, "loop", "s1", every_steps=1)
+        assert resumed.maybe_snapshot({"step": 13}) == "loop/s1/snapshot-000005.json.gz"
+
+        with pytest.raises(ValueError):
+            persist.AutoSnapshotter(client, "loop", "s1")
+        with pytest.raises(ValueError):
+            persist.AutoSnapshotter(client, "loop", "s1", every_steps=0)
+
+    def test_auto_snapshotter_failures(self, temp_dir, caplog):
+        """Test that failed auto-snapshots are logged and retried, or raised when strict."""
+        client = persist.PersistClient(base_dir=temp_dir, serializer=json.dumps)
+        snapshotter = persist.AutoSnapshotter(client, "loop", "s2", every_steps=2)
+
+        assert snapshotter.maybe_snapshot(object()) is None
+        with caplog.at_level(logging.WARNING, logger="persist"):
+            assert snapshotter.maybe_snapshot(object()) is None
+        assert "Auto-snapshot of agent loop, session s2 failed" in caplog.text
+        assert snapshotter.maybe_snapshot({}) == "loop/s2/snapshot-000000.json.gz"
+
+        strict = persist.AutoSnapshotter(client, "loop", "s3", every_steps=1, strict=True)
+        with pytest.raises(TypeError):
+            strict.maybe_snapshot(object())
+
+    def test_auto_snapshot_decorator(self, temp_dir):
+        """Test that the decorator snapshots the agent argument after calls."""
+        client = persist.PersistClient(
+            base_dir=temp_dir, serializer=json.dumps, deserializer=json.loads
+        )
+
+        @persist.auto_snapshot(client, "deco", "s1", every_steps=2)
+        def step(agent, delta=1):
+            """Advance the agent."""
+            agent["count"] += delta
+
+        agent = {"count": 0}
+        for _ in range(5):
+            step(agent)
+        step(delta=10, agent=agent)
+
+        assert step.__name__ == "step"
+        assert step.__doc__ == "Advance the agent."
+        assert step.snapshotter.agent_id == "deco"
+        restored = [client.restore(m.path) for m in client.list("deco/", sort="index")]
+        assert restored == [{"count": 2}, {"count": 4}, {"count": 15}]
+
+        with pytest.raises(TypeError, match="no parameter 'agent'"):
+            persist.auto_snapshot(client, "deco", "s1", every_steps=1)(lambda state: None)
+
+    @staticmethod
+    def _tamper(path):
+        """Change the agent state of an uncompressed snapshot without its hash."""
+        container = json.loads(Path(path).read_text())
+        container["agent_state"] = {"step": 2}
+        Path(path).write_text(json.dumps(container))
+
+    def test_copy_snapshot(self, temp_dir):
+        """Test copying snapshots as stored between local stores."""
+        src = {"base_dir": os.path.join(temp_dir, "scratch")}
+        dst = {"base_dir": os.path.join(temp_dir, "kept")}
+        persist.snapshot_json({"step": 1}, "a.json.gz", agent_id="agent1", **src)
+
+        metadata = persist.copy_snapshot(
+            "a.json.gz", "b/a.json.gz", src_storage=src, dst_storage=dst
+        )
+        assert metadata.agent_id == "agent1"
+        assert metadata.path == "b/a.json.gz"
+        src_bytes = Path(temp_dir, "scratch", "a.json.gz").read_bytes()
+        assert Path(temp_dir, "kept", "b", "a.json.gz").read_bytes() == src_bytes
+        assert persist.snapshot_exists("a.json.gz", **src)
+        original = persist.get_metadata("a.json.gz", **src)
+        assert persist.get_metadata("b/a.json.gz", **dst).timestamp == original.timestamp
+
+        # Clients work as storage too, and a dict can be returned
+        with persist.PersistClient(**dst) as client:
+            copied = persist.copy_snapshot(
+                "b/a.json.gz", "c.json.gz", src_storage=client, dst_storage=client, as_dict=True
+            )
+            assert copied["agent_id"] == "agent1"
+            assert client.restore_json("c.json.gz") == {"step": 1}
+
+        with pytest.raises(TypeError, match="dst_storage"):
+            persist.copy_snapshot("a.json.gz", "d.json.gz", src_storage=src, dst_storage="kept")
+
+    def test_copy_snapshot_verification(self, temp_dir):
+        """Test that verification keeps damaged snapshots from being copied."""
+        src = {"base_dir": os.path.join(temp_dir, "scratch")}
+        dst = {"base_dir": os.path.join(temp_dir, "kept")}
+        persist.snapshot_json({"step": 1}, "bad.json", compression="none", **src)
+        self._tamper(os.path.join(temp_dir, "scratch", "bad.json"))
+
+        with pytest.raises(persist.PersistIntegrityError):
+            persist.copy_snapshot("bad.json", "bad.json", src_storage=src, dst_storage=dst)
+        assert not os.path.exists(os.path.join(temp_dir, "kept", "bad.json"))
+
+        # verify=False copies the bytes unchecked
+        persist.copy_snapshot(
+            "bad.json", "bad.json", src_storage=src, dst_storage=dst, verify=False
+        )
+        assert not persist.verify("bad.json", **dst).valid
+
+    def test_move_snapshot(self, temp_dir):
+        """Test that moves delete the source only after a verified copy."""
+        src = {"base_dir": os.path.join(temp_dir, "scratch")}
+        dst = {"base_dir": os.path.join(temp_dir, "kept")}
+        persist.snapshot_json({"step": 1}, "good.json.gz", **src)
+        persist.snapshot_json({"step": 1}, "bad.json", compression="none", **src)
+        bad_path = os.path.join(temp_dir, "scratch", "bad.json")
+        self._tamper(bad_path)
+        bad_bytes = Path(bad_path).read_bytes()
+
+        metadata = persist.move_snapshot(
+            "good.json.gz", "good.json.gz", src_storage=src, dst_storage=dst
+        )
+        assert metadata.path == "good.json.gz"
+        assert not persist.snapshot_exists("good.json.gz", **src)
+        assert persist.restore_json("good.json.gz", **dst) == {"step": 1}
+
+        # A failed verification leaves the source as it was and writes nothing
+        with pytest.raises(persist.PersistIntegrityError):
+            persist.move_snapshot("bad.json", "bad.json", src_storage=src, dst_storage=dst)
+        assert Path(bad_path).read_bytes() == bad_bytes
+        assert not os.path.exists(os.path.join(temp_dir, "kept", "bad.json"))
+
+        with pytest.raises(persist.PersistValidationError):
+            persist.move_snapshot("good.json.gz", "good.json.gz", src_storage=dst, dst_storage=dst)
+        assert persist.snapshot_exists("good.json.gz", **dst)
+
+    @pytest.mark.skipif(
+        os.environ.get("RUN_LOCALSTACK_TESTS") != "1",
+        reason="set RUN_LOCALSTACK_TESTS=1 and run LocalStack with a persist-test-bucket",
+    )
+    def test_copy_snapshot_local_to_s3_localstack(self, temp_dir):
+        """Test a cross-backend copy and move between local storage and LocalStack."""
+        s3 = {
+            "storage_mode": "s3",
+            "s3_bucket": "persist-test-bucket",
+            "s3_region": "us-east-1",
+            "s3_endpoint_url": os.environ.get("PERSIST_TEST_S3_ENDPOINT", "http://localhost:4566"),
+            "s3_access_key_id": "test",
+            "s3_secret_access_key": "test",
+            "s3_force_path_style": True,
+        }
+        local = {"base_dir": temp_dir}
+        key = f"python-copy/{time.time_ns()}.json.gz"
+        persist.snapshot_json({"step": 1}, "a.json.gz", **local)
+
+        persist.copy_snapshot("a.json.gz", key, src_storage=local, dst_storage=s3)
+        assert persist.restore_json(key, **s3) == {"step": 1}
+        persist.move_snapshot(key, "back.json.gz", src_storage=s3, dst_storage=local)
+        assert not persist.snapshot_exists(key, **s3)
+        assert persist.restore_json("back.json.gz", **local) == {"step": 1}
+
+    def test_diff_snapshots(self, temp_dir):
+        """Test diffs of nested object and list changes between two snapshots."""
+        storage = {"base_dir": temp_dir}
+        before = {"memory": {"user": "ada", "facts": ["a", "b", "c"]}, "step": 1}
+        after = {"memory": {"user": "ada", "facts": ["a", "x"], "mood": "ok"}, "step": 2}
+        persist.snapshot_json(
+            before, "v1.json.gz", description="first", tags={"run": "1"}, **storage
+        )
+        persist.snapshot_json(
+            after, "v2.json.gz", description="second", tags={"run": "2"}, **storage
+        )
+
+        diff = persist.diff_snapshots("v1.json.gz", "v2.json.gz", **storage)
+        assert diff["state_changed"] is True
+        assert diff["metadata"]["description"] == {"old": "first", "new": "second"}
+        assert diff["metadata"]["tags"] == {"run": {"old": "1", "new": "2"}}
+        state = {change["path"]: change for change in diff["state"]}
+        assert state["/memory/facts/1"] == {
+            "op": "replace",
+            "path": "/memory/facts/1",
+            "old": "b",
+            "new": "x",
+        }
+        assert state["/memory/facts/2"] == {
+            "op": "remove",
+            "path": "/memory/facts/2",
+            "old": "c",
+            "new": None,
+        }
+        assert state["/memory/mood"] == {
+            "op": "add",
+            "path": "/memory/mood",
+            "old": None,
+            "new": "ok",
+        }
+        assert state["/step"]["old"] == 1 and state["/step"]["new"] == 2
+        assert "/memory/user" not in state
+
+        paths = persist.diff_snapshots("v1.json.gz", "v2.json.gz", paths_only=True, **storage)
+        assert sorted(paths["state"]) == sorted(state)
+
+        # Each side can live in its own storage
+        other = {"base_dir": os.path.join(temp_dir, "other")}
+        persist.copy_snapshot("v2.json.gz", "v2.json.gz", src_storage=storage, dst_storage=other)
+        across = persist.diff_snapshots("v1.json.gz", "v2.json.gz", b_storage=other, **storage)
+        assert across == diff
+
+    def test_diff_snapshots_truncation_and_identical(self, temp_dir):
+        """Test value truncation and that identical snapshots have empty diffs."""
+        storage = {"base_dir": temp_dir}
+        persist.snapshot_json({"log": "x" * 1000, "n": 1}, "a.json.gz", **storage)
+        persist.snapshot_json({"log": "y" * 1000, "n": 1}, "b.json.gz", **storage)
+
+        diff = persist.diff_snapshots("a.json.gz", "b.json.gz", max_value_len=10, **storage)
+        (change,) = diff["state"]
+        assert change["path"] == "/log"
+        assert change["old"] == '"' + "x" * 9 + "…"
+        assert change["new"] == '"' + "y" * 9 + "…"
+        untruncated = persist.diff_snapshots("a.json.gz", "b.json.gz", **storage)
+        assert untruncated["state"][0]["old"] == "x" * 1000
+
+        # A copy keeps the timestamp and content hash, so nothing differs
+        persist.copy_snapshot(
+            "a.json.gz", "a-copy.json.gz", src_storage=storage, dst_storage=storage
+        )
+        identical = persist.diff_snapshots("a.json.gz", "a-copy.json.gz", **storage)
+        assert identical == {"metadata": {}, "state_changed": False, "state": []}
+
+        with pytest.raises((persist.PersistError, OSError)):
+            persist.diff_snapshots("a.json.gz", "missing.json.gz", **storage)
+
+    def test_verify_reports_results(self, temp_dir):
+        """Test that verify reports valid, corrupted and missing snapshots as results."""
+        persist.snapshot_json({"step": 1}, "good.json.gz", base_dir=temp_dir)
+        persist.snapshot_json({"step": 1}, "bad.json", compression="none", base_dir=temp_dir)
+        bad_path = os.path.join(temp_dir, "bad.json")
+        container = json.loads(Path(bad_path).read_text())
+        container["agent_state"] = {"step": 2}
+        Path(bad_path).write_text(json.dumps(container))
+        Path(temp_dir, "garbage.json.gz").write_bytes(b"not a snapshot")
+
+        good = persist.verify("good.json.gz", base_dir=temp_dir)
+        assert good.valid and good
+        assert good.error is None
+        assert good.expected_hash == good.actual_hash
+        metadata = persist.get_metadata("good.json.gz", base_dir=temp_dir)
+        assert good.expected_hash == metadata.content_hash
+        assert good.format_version == metadata.format_version_string
+        assert good.compatible is True
+        assert repr(good) == "VerificationResult(path='good.json.gz', valid=True)"
+
+        bad = persist.verify("bad.json", base_dir=temp_dir)
+        assert not bad.valid and not bad
+        assert "Integrity check failed" in bad.error
+        assert bad.expected_hash == container["metadata"]["content_hash"]
+        assert bad.actual_hash.startswith("sha256:")
+        assert bad.actual_hash != bad.expected_hash
+        assert bad.compatible is True
+
+        missing = persist.verify("missing.json.gz", base_dir=temp_dir)
+        assert not missing.valid
+        assert missing.error
+        assert (missing.expected_hash, missing.actual_hash) == (None, None)
+        assert (missing.format_version, missing.compatible) == (None, None)
+
+        garbage = persist.verify("garbage.json.gz", base_dir=temp_dir)
+        assert not garbage.valid
+        assert garbage.expected_hash is None
+
+        paths = ["good.json.gz", "bad.json", "missing.json.gz", "garbage.json.gz"]
+        results = persist.verify_many(paths, base_dir=temp_dir, parallel=2)
+        assert [r.path for r in results] == paths
+        assert [r.valid for r in results] == [True, False, False, False]
+        with persist.PersistClient(base_dir=temp_dir) as client:
+            assert client.verify_result("bad.json").actual_hash == bad.actual_hash
+            assert [r.valid for r in client.verify_many(paths[:2])] == [True, False]
+
+        # The raising function is unchanged
+        with pytest.raises(persist.PersistIntegrityError):
+            persist.verify_snapshot("bad.json", base_dir=temp_dir)
+
+    @pytest.mark.skipif(os.name != "posix", reason="Unix permission bits")
+    def test_file_permissions(self, temp_dir, sample_agent_data):
+        """Test that file_permissions sets the mode of created snapshot files."""
+        persist.snapshot_json(
+            sample_agent_data, "private.json.gz", base_dir=temp_dir, file_permissions=0o600
+        )
+        with persist.PersistClient(base_dir=temp_dir, file_permissions=0o640) as client:
+            client.snapshot_json(sample_agent_data, "shared.json.gz")
+
+        assert os.stat(os.path.join(temp_dir, "private.json.gz")).st_mode & 0o777 == 0o600
+        assert os.stat(os.path.join(temp_dir, "shared.json.gz")).st_mode & 0o777 == 0o640
+
+    def test_gcs_storage_mode(self, tmp_path):
+        """Test GCS options: a clear ImportError without the gcs feature, errors with it."""
+        try:
+            persist.PersistClient(storage_mode="gcs", gcs_bucket="persist-test-bucket")
+        except ImportError as e:
+            assert "gcs" in str(e)
+            with pytest.raises(ImportError):
+                persist.get_metadata("a.json.gz", storage_mode="gcs", gcs_bucket="b")
+            with pytest.raises(ImportError):
+                persist.snapshot_exists("a.json.gz", storage_mode="gcs", gcs_bucket="b")
+            with pytest.raises(ImportError):
+                persist.list_snapshots("agent1/", storage_mode="GCS", gcs_bucket="b")
+            return
+        except persist.PersistError:
+            pass
+
+        # Built with GCS: unusable credentials fail when the client is created
+        with pytest.raises(persist.PersistError):
+            persist.PersistClient(
+                storage_mode="gcs",
+                gcs_bucket="persist-test-bucket",
+                gcs_prefix="snapshots",
+                gcs_credentials_path=tmp_path / "missing-credentials.json",
+            )
+
+    def test_client_rejects_invalid_options(self, temp_dir):
+        """Test client configuration errors."""
+        with pytest.raises(persist.PersistConfigurationError):
+            persist.PersistClient(compression="brotli")
+        with pytest.raises(persist.PersistConfigurationError):
+            persist.PersistClient(encryption_key="secret")
+        with pytest.raises(persist.PersistConfigurationError):
+            persist.PersistClient(storage_mode="s3", s3_bucket="bucket", base_dir=temp_dir)
+        with pytest.raises(TypeError, match="unexpected keyword argument 's3_bukcet'"):
+            persist.PersistClient(s3_bukcet="bucket")
+        with pytest.raises(TypeError, match="argument 'file_permissions'"):
+            persist.snapshot_exists("a.json.gz", base_dir=temp_dir, file_permissions="0o600")
+        with pytest.raises(TypeError):
+            persist.restore("a.json.gz", None, "s3")
+
+    def test_env_defaults(self, temp_dir, monkeypatch):
+        """Test that PERSIST_* environment variables supply omitted arguments."""
+        monkeypatch.delenv("PERSIST_STORAGE_MODE", raising=False)
+        monkeypatch.setenv("PERSIST_BASE_DIR", temp_dir)
+        monkeypatch.setenv("PERSIST_COMPRESSION", "none")
+
+        persist.snapshot_bytes(b"payload", "env/state.bin")
+        assert os.path.exists(os.path.join(temp_dir, "env", "state.bin"))
+        assert persist.get_metadata("env/state.bin").compression_algorithm == "none"
+        assert persist.restore_bytes("env/state.bin") == b"payload"
+
+        config = persist.effective_config()
+        assert config["storage_mode"] == "local"
+        assert os.fspath(config["base_dir"]) == temp_dir
+        assert config["compression"] == "none"
+
+    def test_info(self, temp_dir, monkeypatch):
+        """Test that persist.info() describes the build and its backends work."""
+        info = persist.info()
+        assert set(info) == {
+            "version",
+            "core_version",
+            "storage_backends",
+            "compression_algorithms",
+            "metadata_format_version",
+            "metrics",
+            "async_runtime",
+        }
+        assert info["version"] == persist.__version__
+        assert isinstance(info["core_version"], str)
+        assert info["compression_algorithms"] == persist.COMPRESSION_ALGORITHMS
+        assert isinstance(info["metrics"], bool)
+        assert isinstance(info["async_runtime"], bool)
+        assert info["metrics"] == ("metrics" in persist.__features__)
+
+        assert isinstance(persist.__features__, frozenset)
+        assert set(info["storage_backends"]) <= {"local", "s3", "gcs"}
+        for backend in ("s3", "gcs"):
+            assert (backend in info["storage_backends"]) == (backend in persist.__features__)
+
+        path = os.path.join(temp_dir, "info.json.gz")
+        major, minor = (int(part) for part in info["metadata_format_version"].split("."))
+        persist.snapshot_json({"step": 1}, path)
+        metadata = persist.get_metadata(path)
+        assert (metadata.format_version, metadata.format_minor_version) == (major, minor)
+
+        # Every advertised backend and algorithm can be used
+        assert "local" in info["storage_backends"]
+        for codec in info["compression_algorithms"]:
+            with persist.PersistClient(base_dir=temp_dir, compression=codec) as client:
+                client.snapshot_json({"codec": codec}, f"{codec}.json")
+                assert client.restore_json(f"{codec}.json") == {"codec": codec}
+        if "s3" in info["storage_backends"]:
+            monkeypatch.setenv("AWS_ENDPOINT_URL", "http://127.0.0.1:1")
+            persist.PersistClient(
+                storage_mode="s3",
+                s3_bucket="persist-test-bucket",
+                s3_region="us-east-1",
+                s3_access_key_id="AKIDEXAMPLE",
+                s3_secret_access_key="not-a-real-secret",
+            ).close()
+        else:
+            with pytest.raises(persist.PersistError, match="not available"):
+                persist.PersistClient(storage_mode="s3", s3_bucket="persist-test-bucket")
+
+    def test_env_defaults_precedence(self, temp_dir, monkeypatch):
+        """Test that explicit arguments always win over PERSIST_* variables."""
+        monkeypatch.setenv("PERSIST_STORAGE_MODE", "s3")
+        monkeypatch.setenv("PERSIST_S3_BUCKET", "env-bucket")
+        monkeypatch.setenv("PERSIST_S3_REGION", "eu-west-1")
+        monkeypatch.setenv("PERSIST_BASE_DIR", temp_dir)
+        monkeypatch.setenv("PERSIST_COMPRESSION", "none")
+
+        config = persist.effective_config()
+        assert config["storage_mode"] == "s3"
+        assert config["s3_bucket"] == "env-bucket"
+        assert config["s3_region"] == "eu-west-1"
+        # PERSIST_BASE_DIR only applies to local storage, and is not an error here
+        assert config["base_dir"] is None
+
+        config = persist.effective_config(
+            s3_bucket="kwarg-bucket", s3_region="us-east-1", compression="gzip"
+        )
+        assert config["s3_bucket"] == "kwarg-bucket"
+        assert config["s3_region"] == "us-east-1"
+        assert config["compression"] == "gzip"
+
+        other_dir = os.path.join(temp_dir, "other")
+        config = persist.effective_config(storage_mode="local", base_dir=other_dir)
+        assert config["storage_mode"] == "local"
+        assert config["s3_bucket"] is None
+        assert os.fspath(config["base_dir"]) == other_dir
+
+    def test_env_defaults_invalid(self, temp_dir, monkeypatch):
+        """Test that invalid PERSIST_* values raise naming the variable."""
+        monkeypatch.setenv("PERSIST_STORAGE_MODE", "ftp")
+        with pytest.raises(persist.PersistConfigurationError, match="PERSIST_STORAGE_MODE"):
+            persist.effective_config()
+        with pytest.raises(persist.PersistConfigurationError, match="PERSIST_STORAGE_MODE"):
+            persist.snapshot_exists("a.json.gz")
+        # An explicit argument never reads the variable
+        assert persist.effective_config(storage_mode="local")["storage_mode"] == "local"
+
+        monkeypatch.delenv("PERSIST_STORAGE_MODE")
+        monkeypatch.setenv("PERSIST_COMPRESSION", "brotli")
+        with pytest.raises(persist.PersistConfigurationError, match="PERSIST_COMPRESSION"):
+            persist.snapshot_bytes(b"payload", os.path.join(temp_dir, "a.bin.gz"))
+        persist.snapshot_bytes(b"payload", os.path.join(temp_dir, "a.bin.gz"), compression="gzip")
+
+    def test_init_logging(self, temp_dir, caplog):
+        """Test that Rust log events reach Python logging with mapped levels and fields."""
+        caplog.set_level(logging.DEBUG, logger="persist")
+
+        def records():
+            return [record for record in caplog.records if record.name == "persist"]
+
+        try:
+            persist.init_logging(level="debug")
+            persist.init_logging(level="debug")
+            persist.snapshot_json({"step": 1}, "a.json.gz", base_dir=temp_dir)
+            assert persist.restore_json("a.json.gz", base_dir=temp_dir) == {"step": 1}
+
+            by_message = {record.getMessage(): record for record in records()}
+            saved = by_message["Successfully saved snapshot to local storage"]
+            assert saved.levelno == logging.INFO
+            assert saved.target.startswith("persist_core")
+            assert saved.path == "a.json.gz"
+            assert saved.pathname.endswith("local.rs")
+            assert by_message["Path resolved and validated"].levelno == logging.DEBUG
+            assert "Successfully loaded snapshot from local storage" in by_message
+
+            # Reconfiguring changes the level and format without a second bridge
+            caplog.clear()
+            persist.init_logging(level="info", json=True)
+            persist.restore_json("a.json.gz", base_dir=temp_dir)
+            assert records()
+            assert all(record.levelno >= logging.INFO for record in records())
+            entries = [json.loads(record.getMessage()) for record in records()]
+            loaded = next(
+                entry
+                for entry in entries
+                if entry["message"] == "Successfully loaded snapshot from local storage"
+            )
+            assert loaded["level"] == "INFO"
+            assert loaded["target"].startswith("persist_core")
+            assert loaded["fields"]["path"] == "a.json.gz"
+
+            with pytest.raises(ValueError, match="log level"):
+                persist.init_logging(level="loud")
+        finally:
+            persist.init_logging(level="off")
+
+        caplog.clear()
+        persist.restore_json("a.json.gz", base_dir=temp_dir)
+        assert records() == []
+
+    def test_init_logging_reentrant_handler(self, temp_dir):
+        """Test that a logging handler calling back into Persist does not recurse."""
+
+        class CallsPersist(logging.Handler):
+            def __init__(self):
+                super().__init__()
+                self.messages = []
+
+            def emit(self, record):
+                self.messages.append(record.getMessage())
+                persist.snapshot_exists("a.json.gz", base_dir=temp_dir)
+
+        handler = CallsPersist()
+        logger = logging.getLogger("persist.reentrant")
+        logger.addHandler(handler)
+        logger.setLevel(logging.DEBUG)
+        logger.propagate = False
+        try:
+            persist.init_logging(level="debug", logger_name="persist.reentrant")
+            persist.snapshot_json({}, "a.json.gz", base_dir=temp_dir)
+        finally:
+            persist.init_logging(level="off")
+            logger.removeHandler(handler)
+        assert "Successfully saved snapshot to local storage" in handler.messages
+
+    def test_snapshot_index_auto(self, temp_dir, sample_agent_data):
+        """Test that snapshot_index="auto" continues the agent/session history."""
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            for i in range(3):
+                persist.snapshot(
+                    sample_agent_data,
+                    os.path.join(temp_dir, f"auto_{i}.json.gz"),
+                    snapshot_index="auto",
+                )
+
+            with pytest.raises(persist.PersistConfigurationError):
+                persist.snapshot(
+                    sample_agent_data,
+                    os.path.join(temp_dir, "bad.json.gz"),
+                    snapshot_index="latest",
+                )
+
+        indices = [
+            persist.get_metadata(os.path.join(temp_dir, f"auto_{i}.json.gz"))[
+                "snapshot_index"
+            ]
+            for i in range(3)
+        ]
+        assert indices == [0, 1, 2]
+
+    def test_update_metadata_keeps_state(self, temp_dir, sample_agent_data):
+        """Test that updating metadata leaves the content hash untouched."""
+        snapshot_path = os.path.join(temp_dir, "annotated.json.gz")
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            persist.snapshot(sample_agent_data, snapshot_path, description="before")
+
+        before = persist.get_metadata(snapshot_path)
+        updated = persist.update_metadata(
+            snapshot_path, description="known good", tags={"status": "good"}
+        )
+        after = persist.get_metadata(snapshot_path)
+
+        assert updated["description"] == "known good"
+        assert after["tags"] == {"status": "good"}
+        assert after["content_hash"] == before["content_hash"]
+        assert after["timestamp"] == before["timestamp"]
+        persist.verify_snapshot(snapshot_path)
+
+    def test_sensitive_description_redacted(self, temp_dir, sample_agent_data):
+        """Test that sensitive metadata is redacted unless revealed."""
+        snapshot_path = os.path.join(temp_dir, "sensitive.json.gz")
+        with patch("persist.dumps", return_value=json.dumps(sample_agent_data)):
+            persist.snapshot(
+                sample_agent_data,
+                snapshot_path,
+                description="customer 4711",
+                sensitive=["description"],
+            )
+
+        redacted = persist.get_metadata(snapshot_path)
+        assert redacted["description"].startswith("[redacted sha256:")
+        assert redacted["sensitive_fields"] == ["description"]
+
+        revealed = persist.get_metadata(snapshot_path, reveal=True)
+        assert revealed["description"] == "customer 4711"
+
     def test_error_handling_malformed_data(self, temp_dir):
         """Test error handling with malformed agent data."""
 
@@ -457,6 +2265,164 @@ class TestPersistSDK:
             assert results["compression_ratio"] < 1.0, f"No compression achieved for {size_name}"
 
 
+@pytest.mark.llamaindex
+@pytest.mark.skipif(not PERSIST_AVAILABLE, reason="Persist module not available")
+class TestLlamaIndexIntegration:
+    """Tests for persist.integrations.llamaindex."""
+
+    @pytest.fixture
+    def index(self):
+        core = pytest.importorskip("llama_index.core")
+        from llama_index.core.embeddings import MockEmbedding
+
+        core.Settings.embed_model = MockEmbedding(embed_dim=8)
+        documents = [
+            core.Document(text="Persist saves agent state.", doc_id="doc-1"),
+            core.Document(text="LlamaIndex builds indexes.", doc_id="doc-2"),
+        ]
+        return core.VectorStoreIndex.from_documents(documents)
+
+    def test_index_roundtrip(self, temp_dir, index):
+        """Test that an in-memory index restores with the same documents."""
+        import llama_index.core
+
+        from persist.integrations import llamaindex
+
+        llamaindex.snapshot_index(
+            index, "rag/index.json.gz", agent_id="rag", base_dir=temp_dir, compression="none"
+        )
+
+        metadata = persist.get_metadata("rag/index.json.gz", base_dir=temp_dir)
+        assert metadata["agent_id"] == "rag"
+        assert metadata["framework"] == "llamaindex"
+        assert metadata["framework_version"] == llama_index.core.__version__
+
+        restored = llamaindex.restore_index("rag/index.json.gz", base_dir=temp_dir)
+        assert restored.index_id == index.index_id
+        assert set(restored.docstore.docs) == set(index.docstore.docs)
+        assert set(restored.ref_doc_info) == {"doc-1", "doc-2"}
+
+    def test_helpers_are_importable_without_llamaindex(self):
+        """Test that the integration module loads without importing LlamaIndex."""
+        import sys
+
+        import persist.integrations.llamaindex as llamaindex
+
+        assert callable(llamaindex.snapshot_index)
+        if "llama_index.core" not in sys.modules:
+            with pytest.raises(ImportError, match="llama-index-core"):
+                llamaindex.restore_index("missing.json.gz")
+
+
+@pytest.mark.langgraph
+@pytest.mark.skipif(not PERSIST_AVAILABLE, reason="Persist module not available")
+class TestLangGraphIntegration:
+    """Tests for persist.integrations.langgraph."""
+
+    @pytest.fixture
+    def builder(self):
+        pytest.importorskip("langgraph.types")
+        from typing import TypedDict
+
+        from langgraph.graph import END, START, StateGraph
+        from langgraph.types import interrupt
+
+        class State(TypedDict):
+            question: str
+            answer: str
+
+        def ask(state):
+            return {"answer": interrupt(state["question"])}
+
+        def finish(state):
+            return {"answer": state["answer"].upper()}
+
+        builder = StateGraph(State)
+        builder.add_node("ask", ask)
+        builder.add_node("finish", finish)
+        builder.add_edge(START, "ask")
+        builder.add_edge("ask", "finish")
+        builder.add_edge("finish", END)
+        return builder
+
+    def test_resume_from_stored_checkpoint(self, temp_dir, builder):
+        """Test that an interrupted graph resumes with a fresh checkpointer."""
+        from langgraph.types import Command
+
+        from persist.integrations.langgraph import PersistCheckpointer
+
+        config = {"configurable": {"thread_id": "user-42"}}
+        graph = builder.compile(checkpointer=PersistCheckpointer(base_dir=temp_dir))
+        graph.invoke({"question": "name?", "answer": ""}, config)
+        assert graph.get_state(config).next == ("ask",)
+
+        resumed = builder.compile(checkpointer=PersistCheckpointer(base_dir=temp_dir))
+        assert resumed.get_state(config).next == ("ask",)
+        result = resumed.invoke(Command(resume="ada"), config)
+        assert result == {"question": "name?", "answer": "ADA"}
+
+        snapshots = persist.list_snapshots(
+            tags={"langgraph": "checkpoint", "thread_id": "user-42"}, base_dir=temp_dir
+        )
+        assert snapshots
+        assert {snap["agent_id"] for snap in snapshots} == {"user-42"}
+        assert {snap["framework"] for snap in snapshots} == {"langgraph"}
+
+    def test_async_resume(self, temp_dir, builder):
+        """Test that ainvoke stores and resumes checkpoints."""
+        from langgraph.types import Command
+
+        from persist.integrations.langgraph import PersistCheckpointer
+
+        config = {"configurable": {"thread_id": "async-thread"}}
+
+        async def run():
+            graph = builder.compile(checkpointer=PersistCheckpointer(base_dir=temp_dir))
+            await graph.ainvoke({"question": "color?", "answer": ""}, config)
+            return await graph.ainvoke(Command(resume="blue"), config)
+
+        assert asyncio.run(run()) == {"question": "color?", "answer": "BLUE"}
+
+    def test_checkpoint_roundtrips_byte_exact(self, temp_dir):
+        """Test that a stored checkpoint serializes to the same bytes."""
+        pytest.importorskip("langgraph")
+        from langgraph.checkpoint.base import empty_checkpoint
+
+        from persist.integrations.langgraph import PersistCheckpointer
+
+        saver = PersistCheckpointer(prefix="graphs/", base_dir=temp_dir)
+        checkpoint = empty_checkpoint()
+        checkpoint["channel_values"] = {"messages": ["hi", b"\x00\xff"], "count": 3}
+        config = {"configurable": {"thread_id": "t/1", "checkpoint_ns": ""}}
+        stored = saver.put(config, checkpoint, {"source": "input", "step": -1}, {})
+        saver.put_writes(stored, [("messages", "pending")], task_id="task-1")
+
+        loaded = saver.get_tuple(stored)
+        assert saver.serde.dumps_typed(loaded.checkpoint) == saver.serde.dumps_typed(
+            checkpoint
+        )
+        assert loaded.metadata["step"] == -1
+        assert loaded.pending_writes == [("task-1", "messages", "pending")]
+        assert saver.get_tuple({"configurable": {"thread_id": "t/1"}}).config == stored
+        assert [t.config for t in saver.list({"configurable": {"thread_id": "t/1"}})] == [stored]
+
+        saver.delete_thread("t/1")
+        assert saver.get_tuple({"configurable": {"thread_id": "t/1"}}) is None
+
+    def test_module_is_importable_without_langgraph(self):
+        """Test that the integration module loads without importing LangGraph."""
+        import importlib.util
+
+        import persist.integrations.langgraph as integration
+
+        assert integration.__all__ == ["PersistCheckpointer"]
+        with pytest.raises(AttributeError):
+            integration.missing
+        if importlib.util.find_spec("langgraph") is None:
+            with pytest.raises(ImportError, match="pip install langgraph"):
+                integration.PersistCheckpointer
+
+
 @pytest.mark.skipif(not LANGCHAIN_AVAILABLE, reason="LangChain not available")
 class TestLangChainIntegration:
     """Test cases for LangChain integration (if available)."""
@@ -0,0 +1,236 @@
+//! Jitter applied to backoff delays
+//!
+//! Without jitter, workers that failed together retry together, which keeps a
+//! throttled service throttled. The modes follow the usual "full", "equal" and
+//! "decorrelated" jitter strategies.
+
+use backoff::backoff::Backoff;
+use backoff::ExponentialBackoff;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How a computed backoff delay is randomized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JitterMode {
+    /// Use the computed delay as is
+    #[default]
+    None,
+    /// Uniformly random between zero and the computed delay
+    Full,
+    /// Half the computed delay plus a uniformly random share of the other half
+    Equal,
+    /// Uniformly random between the initial interval and three times the previous
+    /// delay, capped at the maximum interval
+    Decorrelated,
+}
+
+/// Small seedable pseudo-random generator (SplitMix64) for jitter
+#[derive(Debug, Clone)]
+pub(crate) struct JitterRng(u64);
+
+impl JitterRng {
+    /// Deterministic generator for a fixed seed
+    pub(crate) fn seeded(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Generator seeded from the clock, distinct for each call
+    pub(crate) fn from_entropy() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self(nanos ^ COUNTER.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform duration in `[low, high]`
+    fn between(&mut self, low: Duration, high: Duration) -> Duration {
+        if high <= low {
+            return low;
+        }
+        low + (high - low).mul_f64(self.next_f64())
+    }
+}
+
+/// Backoff schedule of a [`RetryPolicy`](crate::RetryPolicy) with jitter applied
+///
+/// Implements [`Backoff`], so besides [`with_policy`](crate::with_policy) and
+/// [`retry_blocking`](crate::retry_blocking) it can drive `backoff::retry`.
+#[derive(Debug, Clone)]
+pub struct JitteredBackoff {
+    inner: ExponentialBackoff,
+    mode: JitterMode,
+    rng: JitterRng,
+    previous: Option<Duration>,
+    deadline: Option<Instant>,
+    deadline_reached: bool,
+}
+
+impl JitteredBackoff {
+    pub(crate) fn new(inner: ExponentialBackoff, mode: JitterMode, rng: JitterRng) -> Self {
+        Self {
+            inner,
+            mode,
+            rng,
+            previous: None,
+            deadline: None,
+            deadline_reached: false,
+        }
+    }
+
+    /// Stop the schedule before any delay that would end after `deadline`
+    pub(crate) fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Use `delay` instead of the delay just returned, unless it would pass the deadline
+    pub(crate) fn override_delay(&mut self, delay: Duration) -> Option<Duration> {
+        if self.passes_deadline(delay) {
+            self.deadline_reached = true;
+            return None;
+        }
+        self.previous = Some(delay);
+        Some(delay)
+    }
+
+    fn passes_deadline(&self, delay: Duration) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() + delay > deadline)
+    }
+
+    /// Whether the schedule stopped because the next delay would pass the deadline
+    pub fn deadline_reached(&self) -> bool {
+        self.deadline_reached
+    }
+
+    fn jitter(&mut self, delay: Duration) -> Duration {
+        match self.mode {
+            JitterMode::None => delay,
+            JitterMode::Full => self.rng.between(Duration::ZERO, delay),
+            JitterMode::Equal => {
+                let half = delay / 2;
+                half + self.rng.between(Duration::ZERO, delay - half)
+            }
+            JitterMode::Decorrelated => {
+                let base = self.inner.initial_interval;
+                let previous = self.previous.unwrap_or(base);
+                let next = self.rng.between(base, previous.saturating_mul(3));
+                next.min(self.inner.max_interval)
+            }
+        }
+    }
+}
+
+impl Backoff for JitteredBackoff {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.previous = None;
+        self.deadline_reached = false;
+    }
+
+    fn next_backoff(&mut self) -> Option<Duration> {
+        let delay = self.inner.next_backoff()?;
+        let delay = self.jitter(delay);
+        if self.passes_deadline(delay) {
+            self.deadline_reached = true;
+            return None;
+        }
+        self.previous = Some(delay);
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backoff::ExponentialBackoffBuilder;
+
+    fn schedule(mode: JitterMode, seed: u64, max_ms: u64) -> JitteredBackoff {
+        let inner = ExponentialBackoffBuilder::new()
+            .with_initial_interval(Duration::from_millis(100))
+            .with_max_interval(Duration::from_millis(max_ms))
+            .with_randomization_factor(0.0)
+            .with_max_elapsed_time(None)
+            .build();
+        JitteredBackoff::new(inner, mode, JitterRng::seeded(seed))
+    }
+
+    /// Jittered delays around a constant 100ms base delay
+    fn samples(mode: JitterMode, seed: u64, count: usize) -> Vec<Duration> {
+        let mut schedule = schedule(mode, seed, 100);
+        (0..count)
+            .map(|_| schedule.next_backoff().unwrap())
+            .collect()
+    }
+
+    fn mean_ms(delays: &[Duration]) -> f64 {
+        delays.iter().map(|d| d.as_secs_f64() * 1000.0).sum::<f64>() / delays.len() as f64
+    }
+
+    #[test]
+    fn test_no_jitter_keeps_delay() {
+        assert!(samples(JitterMode::None, 1, 10)
+            .iter()
+            .all(|d| *d == Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_full_jitter_bounds() {
+        let delays = samples(JitterMode::Full, 7, 2000);
+        assert!(delays.iter().all(|d| *d <= Duration::from_millis(100)));
+        let mean = mean_ms(&delays);
+        assert!((45.0..55.0).contains(&mean), "mean {mean}");
+        assert!(delays.iter().any(|d| *d < Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_equal_jitter_bounds() {
+        let delays = samples(JitterMode::Equal, 7, 2000);
+        assert!(delays
+            .iter()
+            .all(|d| *d >= Duration::from_millis(50) && *d <= Duration::from_millis(100)));
+        let mean = mean_ms(&delays);
+        assert!((70.0..80.0).contains(&mean), "mean {mean}");
+    }
+
+    #[test]
+    fn test_decorrelated_jitter_bounds() {
+        let mut schedule = schedule(JitterMode::Decorrelated, 7, 1000);
+        let delays: Vec<Duration> = (0..500).map(|_| schedule.next_backoff().unwrap()).collect();
+        assert!(delays
+            .iter()
+            .all(|d| *d >= Duration::from_millis(100) && *d <= Duration::from_millis(1000)));
+        assert!(delays[0] <= Duration::from_millis(300));
+        assert!(delays.iter().any(|d| *d > Duration::from_millis(500)));
+
+        schedule.reset();
+        assert!(schedule.next_backoff().unwrap() <= Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_fixed_seed_is_deterministic() {
+        assert_eq!(
+            samples(JitterMode::Full, 42, 20),
+            samples(JitterMode::Full, 42, 20)
+        );
+        assert_ne!(
+            samples(JitterMode::Full, 42, 20),
+            samples(JitterMode::Full, 43, 20)
+        );
+    }
+}
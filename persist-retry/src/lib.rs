@@ -4,31 +4,59 @@
 //! for all storage backends in the Persist ecosystem.
 
 use async_trait::async_trait;
+use backoff::backoff::Backoff;
 use backoff::{ExponentialBackoff, ExponentialBackoffBuilder};
 use futures::Future;
+use std::fmt;
 use std::pin::Pin;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tracing::{debug, warn};
 
+mod jitter;
+mod operation;
+
+pub use jitter::{JitterMode, JitteredBackoff};
+pub use operation::{Operation, OperationKind};
+#[cfg(feature = "async-rt")]
+pub use tokio_util::sync::CancellationToken;
+
 /// Common retry error types
 #[derive(Error, Debug)]
 pub enum RetryError {
-    #[error("Operation '{operation}' exceeded maximum retry attempts: {source}")]
+    #[error(
+        "Operation '{operation}' gave up after {} attempts: {last_error}",
+        .attempt_errors.len()
+    )]
     MaxRetriesExceeded {
-        operation: &'static str,
-        source: Box<dyn std::error::Error + Send + Sync>,
+        operation: Operation,
+        /// Error of the final attempt
+        #[source]
+        last_error: Box<dyn std::error::Error + Send + Sync>,
+        /// Display of every attempt's error, in order
+        attempt_errors: Vec<String>,
     },
     #[error("Transient error in '{operation}': {source}")]
     Transient {
-        operation: &'static str,
+        operation: Operation,
         source: Box<dyn std::error::Error + Send + Sync>,
+        /// Server-provided delay before the next attempt (e.g. a `Retry-After` header)
+        retry_after: Option<Duration>,
     },
     #[error("Permanent error in '{operation}': {source}")]
     Permanent {
-        operation: &'static str,
+        operation: Operation,
         source: Box<dyn std::error::Error + Send + Sync>,
     },
+    #[error("Operation '{operation}' was cancelled")]
+    Cancelled { operation: Operation },
+    #[error("Operation '{operation}' did not complete before its deadline")]
+    DeadlineExceeded {
+        operation: Operation,
+        /// Error of the last attempt, if any attempt was made
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
 }
 
 /// Result type for retry operations
@@ -38,7 +66,7 @@ pub type RetryResult<T> = std::result::Result<T, RetryError>;
 pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = RetryResult<T>> + Send + 'a>>;
 
 /// Execute an operation with exponential backoff retry logic
-pub async fn with_backoff<F, T>(op_name: &'static str, f: F) -> RetryResult<T>
+pub async fn with_backoff<F, T>(op_name: impl Into<Operation>, f: F) -> RetryResult<T>
 where
     F: FnMut(usize) -> BoxFuture<'static, T>,
 {
@@ -47,68 +75,581 @@ where
 }
 
 /// Execute an operation with custom backoff policy
+///
+/// Delays between attempts come from the policy's `next_backoff`, so its initial
+/// interval, multiplier, randomization, `max_interval` and `max_elapsed_time` all
+/// apply. Retrying stops as soon as the policy is exhausted, returning
+/// `MaxRetriesExceeded` with the last underlying error as its source and a summary of
+/// every attempt's error. A `Permanent` error stops retrying immediately and is
+/// returned unchanged, so callers can tell it apart from exhausted retries.
 pub async fn with_custom_backoff<F, T>(
-    op_name: &'static str,
-    mut _policy: ExponentialBackoff,
+    op_name: impl Into<Operation>,
+    policy: ExponentialBackoff,
+    f: F,
+) -> RetryResult<T>
+where
+    F: FnMut(usize) -> BoxFuture<'static, T>,
+{
+    with_policy(op_name, RetryPolicy::from(policy), f).await
+}
+
+/// Execute an operation with the default policy, giving up at `deadline`
+///
+/// No attempt is started once the deadline has passed, and no retry is scheduled
+/// whose delay would end after it; both cases return [`RetryError::DeadlineExceeded`].
+pub async fn with_backoff_until<F, T>(
+    op_name: impl Into<Operation>,
+    deadline: Instant,
+    f: F,
+) -> RetryResult<T>
+where
+    F: FnMut(usize) -> BoxFuture<'static, T>,
+{
+    let policy = RetryPolicy::default_policy().with_deadline(deadline);
+    with_policy(op_name, policy, f).await
+}
+
+/// Execute an operation according to a [`RetryPolicy`]
+///
+/// Behaves like [`with_custom_backoff`], additionally stopping after
+/// `policy.max_attempts` attempts, never retrying when the policy's classifier
+/// is [`RetryClassifier::Never`], and honouring `policy.deadline`.
+///
+/// With `policy.attempt_timeout` set, an attempt still running after that long is
+/// dropped and counts as a transient failure.
+pub async fn with_policy<F, T>(
+    op_name: impl Into<Operation>,
+    policy: RetryPolicy,
+    f: F,
+) -> RetryResult<T>
+where
+    F: FnMut(usize) -> BoxFuture<'static, T>,
+{
+    run_policy(op_name.into(), policy, None, f).await
+}
+
+/// Execute an operation according to a [`RetryPolicy`] until `token` is cancelled
+///
+/// Cancelling the token aborts the sleep between attempts immediately and returns
+/// [`RetryError::Cancelled`]; an attempt already in flight is not interrupted.
+#[cfg(feature = "async-rt")]
+pub async fn with_policy_cancellable<F, T>(
+    op_name: impl Into<Operation>,
+    policy: RetryPolicy,
+    token: &CancellationToken,
+    f: F,
+) -> RetryResult<T>
+where
+    F: FnMut(usize) -> BoxFuture<'static, T>,
+{
+    run_policy(op_name.into(), policy, Some(token), f).await
+}
+
+/// Cancellation source accepted by the retry loop
+#[cfg(feature = "async-rt")]
+type Cancellation<'a> = Option<&'a CancellationToken>;
+#[cfg(not(feature = "async-rt"))]
+type Cancellation<'a> = Option<&'a std::convert::Infallible>;
+
+#[cfg(feature = "async-rt")]
+fn is_cancelled(cancel: Cancellation<'_>) -> bool {
+    cancel.is_some_and(|token| token.is_cancelled())
+}
+
+#[cfg(not(feature = "async-rt"))]
+fn is_cancelled(_cancel: Cancellation<'_>) -> bool {
+    false
+}
+
+/// Sleep before the next attempt; returns `false` if cancelled while sleeping
+#[cfg(feature = "async-rt")]
+async fn sleep_unless_cancelled(delay: Duration, cancel: Cancellation<'_>) -> bool {
+    match cancel {
+        Some(token) => tokio::select! {
+            _ = token.cancelled() => false,
+            _ = tokio::time::sleep(delay) => true,
+        },
+        None => {
+            tokio::time::sleep(delay).await;
+            true
+        }
+    }
+}
+
+#[cfg(not(feature = "async-rt"))]
+async fn sleep_unless_cancelled(delay: Duration, _cancel: Cancellation<'_>) -> bool {
+    std::thread::sleep(delay);
+    true
+}
+
+/// Await one attempt, giving up once `timeout` (if any) has elapsed
+///
+/// Returns `None` if the attempt timed out. [`with_policy`] bounds every attempt
+/// with it; blocking callers that drive an async client on their own runtime can use
+/// it to apply [`RetryPolicy::attempt_timeout`] inside [`retry_blocking`].
+#[cfg(feature = "async-rt")]
+pub async fn attempt_with_timeout<F: Future>(
+    timeout: Option<Duration>,
+    attempt: F,
+) -> Option<F::Output> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, attempt).await.ok(),
+        None => Some(attempt.await),
+    }
+}
+
+/// Await one attempt; without the `async-rt` feature the timeout is not enforced
+#[cfg(not(feature = "async-rt"))]
+pub async fn attempt_with_timeout<F: Future>(
+    _timeout: Option<Duration>,
+    attempt: F,
+) -> Option<F::Output> {
+    Some(attempt.await)
+}
+
+/// Error recorded for an attempt that exceeded its timeout
+fn attempt_timed_out(timeout: Duration) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!("attempt timed out after {timeout:?}"),
+    )
+}
+
+async fn run_policy<F, T>(
+    operation: Operation,
+    policy: RetryPolicy,
+    cancel: Cancellation<'_>,
     mut f: F,
 ) -> RetryResult<T>
 where
     F: FnMut(usize) -> BoxFuture<'static, T>,
 {
-    // Simple implementation without complex retry logic for MVP
-    // This can be enhanced later with proper async retry logic
+    let mut backoff = policy.schedule();
     let mut attempt = 1;
+    let mut attempt_errors = Vec::new();
+
+    if policy.deadline_passed() {
+        return Err(RetryError::DeadlineExceeded {
+            operation,
+            source: None,
+        });
+    }
 
     loop {
-        debug!("Attempting operation '{}' (attempt {})", op_name, attempt);
+        if is_cancelled(cancel) {
+            return Err(RetryError::Cancelled { operation });
+        }
 
-        match f(attempt).await {
-            Ok(result) => {
+        debug!("Attempting operation '{}' (attempt {})", operation, attempt);
+        policy.record_attempt(&operation, attempt);
+
+        let outcome = attempt_with_timeout(policy.attempt_timeout, f(attempt)).await;
+        let err = match outcome {
+            Some(Ok(result)) => {
                 if attempt > 1 {
                     debug!(
                         "Operation '{}' succeeded after {} attempts",
-                        op_name, attempt
+                        operation, attempt
                     );
                 }
+                policy.record_success(&operation, attempt);
                 return Ok(result);
             }
-            Err(RetryError::Permanent { .. }) => {
+            Some(Err(err)) => err,
+            // A hung attempt is retried like any other transient failure
+            None => RetryError::Transient {
+                operation: operation.clone(),
+                source: Box::new(attempt_timed_out(
+                    policy.attempt_timeout.unwrap_or_default(),
+                )),
+                retry_after: None,
+            },
+        };
+
+        warn!(
+            "Operation '{}' failed on attempt {}: {}",
+            operation, attempt, err
+        );
+        let (source, retry_after) = match err {
+            RetryError::Transient {
+                source,
+                retry_after,
+                ..
+            } => (source, retry_after),
+            RetryError::MaxRetriesExceeded {
+                last_error: source, ..
+            } => (source, None),
+            // Never retried, or the operation itself gave up; pass that on unchanged
+            RetryError::Permanent { .. }
+            | RetryError::Cancelled { .. }
+            | RetryError::DeadlineExceeded { .. } => return Err(err),
+        };
+        attempt_errors.push(source.to_string());
+
+        match policy.next_delay(&mut backoff, attempt, true, retry_after) {
+            Some(delay) => {
+                policy.notify_retry(&operation, attempt, &source, delay);
+                attempt += 1;
+
+                if !sleep_unless_cancelled(delay, cancel).await {
+                    return Err(RetryError::Cancelled { operation });
+                }
+            }
+            None => {
                 warn!(
-                    "Operation '{}' failed permanently on attempt {}",
-                    op_name, attempt
+                    "Operation '{}' giving up after {} attempts",
+                    operation, attempt
                 );
+                policy.record_exhausted(&operation, attempt);
+                if backoff.deadline_reached() {
+                    return Err(RetryError::DeadlineExceeded {
+                        operation,
+                        source: Some(source),
+                    });
+                }
                 return Err(RetryError::MaxRetriesExceeded {
-                    operation: op_name,
-                    source: "Permanent error".into(),
+                    operation,
+                    last_error: source,
+                    attempt_errors,
                 });
             }
-            Err(err) => {
-                warn!(
-                    "Operation '{}' failed on attempt {}: {}",
-                    op_name, attempt, err
-                );
+        }
+    }
+}
 
-                // Simple retry logic - max 3 attempts for MVP
-                if attempt >= 3 {
-                    return Err(RetryError::MaxRetriesExceeded {
-                        operation: op_name,
-                        source: "Maximum retry attempts exceeded".into(),
-                    });
-                }
+/// Execute a blocking operation according to a [`RetryPolicy`]
+///
+/// Synchronous counterpart of [`with_policy`] for storage adapters calling blocking
+/// APIs. Failures are classified through [`RetryableError`], including any
+/// server-provided retry delay; when retrying stops, the error of the last attempt is
+/// returned unchanged. Callers check [`RetryPolicy::deadline_passed`] before calling,
+/// since no error value exists yet.
+///
+/// `policy.attempt_timeout` is best-effort here: a blocking closure cannot be
+/// interrupted, so the closure itself must bound each attempt, e.g. by awaiting its
+/// client through [`attempt_with_timeout`].
+pub fn retry_blocking<T, E, F>(
+    op_name: impl Into<Operation>,
+    policy: &RetryPolicy,
+    mut op: F,
+) -> std::result::Result<T, E>
+where
+    F: FnMut(usize) -> std::result::Result<T, E>,
+    E: RetryableError + fmt::Display,
+{
+    let operation = op_name.into();
+    let mut backoff = policy.schedule();
+    let mut attempt = 1;
 
-                attempt += 1;
+    loop {
+        debug!("Attempting operation '{}' (attempt {})", operation, attempt);
+        policy.record_attempt(&operation, attempt);
 
-                // Simple delay - can be enhanced with proper backoff later
-                #[cfg(feature = "async-rt")]
-                tokio::time::sleep(std::time::Duration::from_millis(100 * attempt as u64)).await;
+        let err = match op(attempt) {
+            Ok(result) => {
+                policy.record_success(&operation, attempt);
+                return Ok(result);
+            }
+            Err(err) => err,
+        };
+
+        warn!(
+            "Operation '{}' failed on attempt {}: {}",
+            operation, attempt, err
+        );
+        let transient = err.is_transient();
 
-                #[cfg(not(feature = "async-rt"))]
-                std::thread::sleep(std::time::Duration::from_millis(100 * attempt as u64));
+        match policy.next_delay(&mut backoff, attempt, transient, err.retry_after()) {
+            Some(delay) => {
+                policy.notify_retry(&operation, attempt, &err, delay);
+                attempt += 1;
+                std::thread::sleep(delay);
+            }
+            None => {
+                if transient {
+                    warn!(
+                        "Operation '{}' giving up after {} attempts",
+                        operation, attempt
+                    );
+                    policy.record_exhausted(&operation, attempt);
+                }
+                return Err(err);
             }
         }
     }
 }
 
+/// Sink for retry metrics
+///
+/// Attach one to a [`RetryPolicy`] with [`RetryPolicy::with_metrics`]; the retry
+/// loops report to it without callers instrumenting each operation.
+pub trait RetryMetricsSink: Send + Sync {
+    /// An attempt (the first or a retry) of `operation` is starting
+    fn record_attempt(&self, operation: &Operation, attempt: usize);
+
+    /// Retrying `operation` stopped after `attempts` attempts with a transient failure
+    fn record_exhausted(&self, operation: &Operation, attempts: usize);
+
+    /// `operation` succeeded on attempt number `attempts`, after at least one retry
+    fn record_success_after_retry(&self, operation: &Operation, attempts: usize);
+}
+
+/// Which failures a [`RetryPolicy`] retries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetryClassifier {
+    /// Retry errors classified as transient (the default)
+    #[default]
+    Transient,
+    /// Never retry; every operation is attempted exactly once
+    Never,
+}
+
+/// A failed attempt that is about to be retried
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryEvent {
+    /// Name of the retried operation
+    pub operation: Operation,
+    /// Number of the attempt that failed, starting at 1
+    pub attempt: usize,
+    /// Display of the error the attempt failed with
+    pub error: String,
+    /// Delay before the next attempt
+    pub delay: Duration,
+}
+
+/// Callback invoked for every retry, before sleeping
+pub type RetryHook = Arc<dyn Fn(RetryEvent) + Send + Sync>;
+
+/// Complete retry policy: backoff schedule, jitter, attempt cap and retry classification
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// Backoff schedule; its `max_elapsed_time` caps the total retry duration
+    pub backoff: ExponentialBackoff,
+    /// Jitter applied to each computed delay
+    ///
+    /// Any mode other than [`JitterMode::None`] replaces the backoff's own
+    /// `randomization_factor`.
+    pub jitter: JitterMode,
+    /// Seed for the jitter RNG; a fixed seed makes delays reproducible (random if `None`)
+    pub jitter_seed: Option<u64>,
+    /// Maximum number of attempts, including the first (unlimited if `None`)
+    pub max_attempts: Option<usize>,
+    /// Which failures are retried
+    pub retry_on: RetryClassifier,
+    /// Hook notified of every retry (for logging and metrics)
+    pub on_retry: Option<RetryHook>,
+    /// Point in time after which no attempt is started and no retry scheduled
+    pub deadline: Option<Instant>,
+    /// Sink receiving attempt, exhaustion and recovery counts
+    pub metrics: Option<Arc<dyn RetryMetricsSink>>,
+    /// Longest a single attempt may run before it is abandoned (unbounded if `None`)
+    pub attempt_timeout: Option<Duration>,
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("backoff", &self.backoff)
+            .field("jitter", &self.jitter)
+            .field("jitter_seed", &self.jitter_seed)
+            .field("max_attempts", &self.max_attempts)
+            .field("retry_on", &self.retry_on)
+            .field("on_retry", &self.on_retry.as_ref().map(|_| "<hook>"))
+            .field("deadline", &self.deadline)
+            .field("metrics", &self.metrics.as_ref().map(|_| "<sink>"))
+            .field("attempt_timeout", &self.attempt_timeout)
+            .finish()
+    }
+}
+
+impl RetryPolicy {
+    /// Policy using [`default_backoff_policy`], without jitter
+    pub fn default_policy() -> Self {
+        default_backoff_policy_with_jitter(JitterMode::None)
+    }
+
+    /// Policy using [`cloud_storage_backoff_policy`] with full jitter, so that workers
+    /// throttled together do not retry together
+    pub fn cloud_storage() -> Self {
+        cloud_storage_backoff_policy_with_jitter(JitterMode::Full)
+    }
+
+    /// Policy using [`local_storage_backoff_policy`], without jitter
+    pub fn local_storage() -> Self {
+        local_storage_backoff_policy_with_jitter(JitterMode::None)
+    }
+
+    /// Policy that attempts every operation exactly once
+    pub fn never_retry() -> Self {
+        Self::default_policy().with_retry_on(RetryClassifier::Never)
+    }
+
+    /// Cap the number of attempts, including the first
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Choose which failures are retried
+    pub fn with_retry_on(mut self, retry_on: RetryClassifier) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Choose how delays are randomized
+    pub fn with_jitter(mut self, jitter: JitterMode) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Seed the jitter RNG so that delays are reproducible (for tests)
+    pub fn with_jitter_seed(mut self, seed: u64) -> Self {
+        self.jitter_seed = Some(seed);
+        self
+    }
+
+    /// Give up once `deadline` has passed
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Whether the policy's deadline has already passed
+    ///
+    /// Synchronous callers check this before their first attempt; the
+    /// [`schedule`](RetryPolicy::schedule) stops retries at the deadline by itself.
+    pub fn deadline_passed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Register a hook notified of every retry
+    pub fn with_on_retry(mut self, hook: impl Fn(RetryEvent) + Send + Sync + 'static) -> Self {
+        self.on_retry = Some(Arc::new(hook));
+        self
+    }
+
+    /// Abandon any single attempt still running after `timeout`
+    pub fn with_attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// Report retry metrics to `sink`
+    pub fn with_metrics(mut self, sink: Arc<dyn RetryMetricsSink>) -> Self {
+        self.metrics = Some(sink);
+        self
+    }
+
+    /// Report a retry of failed attempt number `attempt` to the `on_retry` hook
+    ///
+    /// Called by [`with_policy`] and [`retry_blocking`] before sleeping.
+    pub fn notify_retry(
+        &self,
+        operation: &Operation,
+        attempt: usize,
+        error: &dyn fmt::Display,
+        delay: Duration,
+    ) {
+        if let Some(hook) = &self.on_retry {
+            hook(RetryEvent {
+                operation: operation.clone(),
+                attempt,
+                error: error.to_string(),
+                delay,
+            });
+        }
+    }
+
+    fn record_attempt(&self, operation: &Operation, attempt: usize) {
+        if let Some(sink) = &self.metrics {
+            sink.record_attempt(operation, attempt);
+        }
+    }
+
+    fn record_exhausted(&self, operation: &Operation, attempts: usize) {
+        if let Some(sink) = &self.metrics {
+            sink.record_exhausted(operation, attempts);
+        }
+    }
+
+    /// Report a success, counting it as a recovery if it needed retries
+    fn record_success(&self, operation: &Operation, attempts: usize) {
+        if let (Some(sink), true) = (&self.metrics, attempts > 1) {
+            sink.record_success_after_retry(operation, attempts);
+        }
+    }
+
+    /// Fresh backoff schedule for one operation, with jitter applied
+    ///
+    /// Drives [`with_policy`] and [`retry_blocking`]; other synchronous callers can
+    /// pass it to `backoff::retry`.
+    pub fn schedule(&self) -> JitteredBackoff {
+        let mut backoff = self.backoff.clone();
+        if self.jitter != JitterMode::None {
+            backoff.randomization_factor = 0.0;
+        }
+        backoff.reset();
+        let rng = match self.jitter_seed {
+            Some(seed) => jitter::JitterRng::seeded(seed),
+            None => jitter::JitterRng::from_entropy(),
+        };
+        JitteredBackoff::new(backoff, self.jitter, rng).with_deadline(self.deadline)
+    }
+
+    /// Whether a transient failure of attempt number `attempt` (starting at 1) may be
+    /// retried; the backoff schedule may still stop retries earlier
+    ///
+    /// Callers driving `backoff::retry` with [`RetryPolicy::schedule`] use this to
+    /// apply the attempt cap and classifier.
+    pub fn allows_retry(&self, attempt: usize) -> bool {
+        self.retry_on != RetryClassifier::Never && self.max_attempts.is_none_or(|max| attempt < max)
+    }
+
+    /// Delay before the next attempt, or `None` if the failed attempt should not be retried
+    ///
+    /// A server-provided `retry_after` hint replaces the computed delay, capped at the
+    /// backoff's `max_interval`.
+    fn next_delay(
+        &self,
+        backoff: &mut JitteredBackoff,
+        attempt: usize,
+        transient: bool,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if !transient || !self.allows_retry(attempt) {
+            return None;
+        }
+        let delay = backoff.next_backoff()?;
+        match retry_after {
+            Some(hint) => backoff.override_delay(hint.min(self.backoff.max_interval)),
+            None => Some(delay),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::default_policy()
+    }
+}
+
+impl From<ExponentialBackoff> for RetryPolicy {
+    fn from(backoff: ExponentialBackoff) -> Self {
+        Self {
+            backoff,
+            jitter: JitterMode::None,
+            jitter_seed: None,
+            max_attempts: None,
+            retry_on: RetryClassifier::Transient,
+            on_retry: None,
+            deadline: None,
+            metrics: None,
+            attempt_timeout: None,
+        }
+    }
+}
+
 /// Default backoff policy for general operations
 pub fn default_backoff_policy() -> ExponentialBackoff {
     ExponentialBackoffBuilder::new()
@@ -139,6 +680,35 @@ pub fn local_storage_backoff_policy() -> ExponentialBackoff {
         .build()
 }
 
+/// Policy using [`default_backoff_policy`], randomized only by `jitter`
+///
+/// The backoff's own randomization is turned off, so with [`JitterMode::None`] the
+/// delays are deterministic.
+pub fn default_backoff_policy_with_jitter(jitter: JitterMode) -> RetryPolicy {
+    jittered_policy(default_backoff_policy(), jitter)
+}
+
+/// Policy using [`cloud_storage_backoff_policy`], randomized only by `jitter`
+///
+/// The backoff's own randomization is turned off, so with [`JitterMode::None`] the
+/// delays are deterministic.
+pub fn cloud_storage_backoff_policy_with_jitter(jitter: JitterMode) -> RetryPolicy {
+    jittered_policy(cloud_storage_backoff_policy(), jitter)
+}
+
+/// Policy using [`local_storage_backoff_policy`], randomized only by `jitter`
+///
+/// The backoff's own randomization is turned off, so with [`JitterMode::None`] the
+/// delays are deterministic.
+pub fn local_storage_backoff_policy_with_jitter(jitter: JitterMode) -> RetryPolicy {
+    jittered_policy(local_storage_backoff_policy(), jitter)
+}
+
+fn jittered_policy(mut backoff: ExponentialBackoff, jitter: JitterMode) -> RetryPolicy {
+    backoff.randomization_factor = 0.0;
+    RetryPolicy::from(backoff).with_jitter(jitter)
+}
+
 /// Trait for categorizing errors as transient or permanent
 #[async_trait]
 pub trait RetryableError {
@@ -149,6 +719,18 @@ pub trait RetryableError {
     fn is_permanent(&self) -> bool {
         !self.is_transient()
     }
+
+    /// Server-provided delay before the next attempt, if the error carries one
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Parse an HTTP `Retry-After` header value given in seconds
+///
+/// The HTTP-date form is not supported and yields `None`.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
 }
 
 /// Helper macro for creating transient errors
@@ -156,8 +738,16 @@ pub trait RetryableError {
 macro_rules! transient_error {
     ($op:expr, $err:expr) => {
         RetryError::Transient {
-            operation: $op,
+            operation: $crate::Operation::from($op),
+            source: Box::new($err),
+            retry_after: None,
+        }
+    };
+    ($op:expr, $err:expr, retry_after = $delay:expr) => {
+        RetryError::Transient {
+            operation: $crate::Operation::from($op),
             source: Box::new($err),
+            retry_after: Some($delay),
         }
     };
 }
@@ -167,7 +757,7 @@ macro_rules! transient_error {
 macro_rules! permanent_error {
     ($op:expr, $err:expr) => {
         RetryError::Permanent {
-            operation: $op,
+            operation: $crate::Operation::from($op),
             source: Box::new($err),
         }
     };
@@ -227,7 +817,599 @@ mod tests {
         })
         .await;
 
+        assert!(matches!(result, Err(RetryError::Permanent { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_permanent_error_after_retries_returned_unchanged() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let attempt_count_clone = Arc::clone(&attempt_count);
+        let policy = RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10)));
+
+        let result: RetryResult<()> = with_policy("test_op", policy, move |attempt| {
+            attempt_count_clone.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                if attempt == 1 {
+                    Err(transient_error!("test_op", std::io::Error::other("busy")))
+                } else {
+                    Err(permanent_error!(
+                        "test_op",
+                        std::io::Error::other("403 forbidden")
+                    ))
+                }
+            })
+        })
+        .await;
+
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 2);
+        match result {
+            Err(RetryError::Permanent { operation, source }) => {
+                assert_eq!(operation, Operation::from("test_op"));
+                assert_eq!(source.to_string(), "403 forbidden");
+            }
+            other => panic!("expected Permanent, got {other:?}"),
+        }
+    }
+
+    /// Display of every error in the chain, outermost first
+    fn error_chain(err: &dyn std::error::Error) -> Vec<String> {
+        std::iter::successors(Some(err), |e| e.source())
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_error_chain_preserved_after_exhaustion() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy =
+            RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10))).with_max_attempts(3);
+
+        let err = with_policy("test_op", policy, always_failing(&attempt_count))
+            .await
+            .unwrap_err();
+
+        let chain = error_chain(&err);
+        assert_eq!(chain.len(), 2);
+        assert!(chain[0].contains("gave up after 3 attempts: timed out"));
+        assert_eq!(chain[1], "timed out");
+        let last_error = std::error::Error::source(&err).unwrap();
+        assert_eq!(
+            last_error
+                .downcast_ref::<std::io::Error>()
+                .map(|e| e.kind()),
+            Some(std::io::ErrorKind::TimedOut)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_operation_target_in_error() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy =
+            RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10))).with_max_attempts(2);
+        let operation = Operation::new(OperationKind::Save).with_target("my-bucket/a.json.gz");
+
+        let err = with_policy(operation, policy, always_failing(&attempt_count))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Operation 'save my-bucket/a.json.gz' gave up after 2 attempts: timed out"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_chain_preserved_after_permanent_failure() {
+        let result: RetryResult<()> = with_backoff("test_op", |_attempt| {
+            Box::pin(async {
+                Err(permanent_error!(
+                    "test_op",
+                    std::io::Error::new(std::io::ErrorKind::PermissionDenied, "403 forbidden")
+                ))
+            })
+        })
+        .await;
+
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("403 forbidden"));
+        assert_eq!(error_chain(&err).last().unwrap(), "403 forbidden");
+        assert!(!err.to_string().contains("gave up"));
+        assert!(matches!(err, RetryError::Permanent { .. }));
+    }
+
+    /// Deterministic policy: no randomization, doubling from `initial_ms` up to `max_ms`
+    fn tight_policy(initial_ms: u64, max_ms: u64, max_elapsed: Duration) -> ExponentialBackoff {
+        ExponentialBackoffBuilder::new()
+            .with_initial_interval(Duration::from_millis(initial_ms))
+            .with_max_interval(Duration::from_millis(max_ms))
+            .with_randomization_factor(0.0)
+            .with_multiplier(2.0)
+            .with_max_elapsed_time(Some(max_elapsed))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_custom_policy_delays_and_attempts() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let attempt_count_clone = Arc::clone(&attempt_count);
+        let policy = tight_policy(10, 40, Duration::from_secs(10));
+
+        let start = std::time::Instant::now();
+        let result = with_custom_backoff("test_op", policy, move |_attempt| {
+            let count = attempt_count_clone.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                if count < 4 {
+                    Err(transient_error!(
+                        "test_op",
+                        std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out")
+                    ))
+                } else {
+                    Ok(count)
+                }
+            })
+        })
+        .await;
+        let elapsed = start.elapsed();
+
+        assert_eq!(result.unwrap(), 4);
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 5);
+        // 10 + 20 + 40 + 40 (capped by max_interval)
+        assert!(elapsed >= Duration::from_millis(110), "elapsed {elapsed:?}");
+        assert!(elapsed < Duration::from_secs(2), "elapsed {elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_max_elapsed_time_stops_retries() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let attempt_count_clone = Arc::clone(&attempt_count);
+        let policy = tight_policy(20, 20, Duration::from_millis(100));
+
+        let start = std::time::Instant::now();
+        let result: RetryResult<()> = with_custom_backoff("test_op", policy, move |attempt| {
+            attempt_count_clone.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                Err(transient_error!(
+                    "test_op",
+                    std::io::Error::other(format!("failure {attempt}"))
+                ))
+            })
+        })
+        .await;
+        let elapsed = start.elapsed();
+
+        let attempts = attempt_count.load(Ordering::SeqCst);
+        assert!((2..=6).contains(&attempts), "attempts {attempts}");
+        assert!(elapsed < Duration::from_secs(2), "elapsed {elapsed:?}");
+        match result {
+            Err(RetryError::MaxRetriesExceeded {
+                operation,
+                last_error,
+                attempt_errors,
+            }) => {
+                assert_eq!(operation, Operation::from("test_op"));
+                assert_eq!(last_error.to_string(), format!("failure {attempts}"));
+                assert_eq!(attempt_errors.len(), attempts);
+                assert_eq!(attempt_errors[0], "failure 1");
+            }
+            other => panic!("expected MaxRetriesExceeded, got {other:?}"),
+        }
+    }
+
+    fn always_failing(
+        attempt_count: &Arc<AtomicUsize>,
+    ) -> impl FnMut(usize) -> BoxFuture<'static, ()> {
+        let attempt_count = Arc::clone(attempt_count);
+        move |_attempt| {
+            attempt_count.fetch_add(1, Ordering::SeqCst);
+            let future: BoxFuture<'static, ()> = Box::pin(async {
+                Err(transient_error!(
+                    "test_op",
+                    std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out")
+                ))
+            });
+            future
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attempt_capped_policy() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy =
+            RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10))).with_max_attempts(4);
+
+        let result = with_policy("test_op", policy, always_failing(&attempt_count)).await;
+
+        assert!(matches!(result, Err(RetryError::MaxRetriesExceeded { .. })));
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_on_retry_hook_events() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let collected = Arc::clone(&events);
+        let policy = RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10)))
+            .with_max_attempts(3)
+            .with_on_retry(move |event| collected.lock().unwrap().push(event));
+
+        let result = with_policy("test_op", policy, always_failing(&attempt_count)).await;
+
+        assert!(matches!(result, Err(RetryError::MaxRetriesExceeded { .. })));
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 3);
+        let expected: Vec<RetryEvent> = (1..=2)
+            .map(|attempt| RetryEvent {
+                operation: "test_op".into(),
+                attempt,
+                error: "timed out".to_string(),
+                delay: Duration::from_millis(1),
+            })
+            .collect();
+        assert_eq!(*events.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_before_first_attempt_skips_execution() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+
+        let result =
+            with_backoff_until("test_op", Instant::now(), always_failing(&attempt_count)).await;
+
+        assert!(matches!(
+            result,
+            Err(RetryError::DeadlineExceeded { source: None, .. })
+        ));
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_truncates_retries() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy = RetryPolicy::from(tight_policy(100, 100, Duration::from_secs(10)))
+            .with_deadline(Instant::now() + Duration::from_millis(250));
+
+        let start = Instant::now();
+        let result = with_policy("test_op", policy, always_failing(&attempt_count)).await;
+
+        assert!(matches!(
+            result,
+            Err(RetryError::DeadlineExceeded {
+                source: Some(_),
+                ..
+            })
+        ));
+        // Attempts at roughly 0ms, 100ms and 200ms; a retry at 300ms would pass the deadline
+        assert!((2..=3).contains(&attempt_count.load(Ordering::SeqCst)));
+        assert!(start.elapsed() < Duration::from_millis(300));
+    }
+
+    #[cfg(feature = "async-rt")]
+    #[tokio::test]
+    async fn test_cancellation_during_sleep_returns_promptly() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy = RetryPolicy::from(tight_policy(10_000, 10_000, Duration::from_secs(60)));
+        let token = CancellationToken::new();
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+
+        let start = Instant::now();
+        let result =
+            with_policy_cancellable("test_op", policy, &token, always_failing(&attempt_count))
+                .await;
+
+        assert!(matches!(result, Err(RetryError::Cancelled { .. })));
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 1);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[derive(Default)]
+    struct CountingSink {
+        attempts: AtomicUsize,
+        exhausted: AtomicUsize,
+        recovered: AtomicUsize,
+    }
+
+    impl RetryMetricsSink for CountingSink {
+        fn record_attempt(&self, _operation: &Operation, _attempt: usize) {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn record_exhausted(&self, _operation: &Operation, _attempts: usize) {
+            self.exhausted.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn record_success_after_retry(&self, _operation: &Operation, _attempts: usize) {
+            self.recovered.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counts(sink: &CountingSink) -> (usize, usize, usize) {
+        (
+            sink.attempts.load(Ordering::SeqCst),
+            sink.exhausted.load(Ordering::SeqCst),
+            sink.recovered.load(Ordering::SeqCst),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_metrics_sink_counts_flaky_operation() {
+        let sink = Arc::new(CountingSink::default());
+        let policy = RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10)))
+            .with_max_attempts(5)
+            .with_metrics(sink.clone());
+
+        // Fails twice, then succeeds
+        let result = with_policy("test_op", policy.clone(), |attempt| {
+            Box::pin(async move {
+                if attempt < 3 {
+                    Err(transient_error!(
+                        "test_op",
+                        std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out")
+                    ))
+                } else {
+                    Ok(attempt)
+                }
+            })
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(counts(&sink), (3, 0, 1));
+
+        // Always fails: five more attempts, then exhausted
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let result = with_policy("test_op", policy, always_failing(&attempt_count)).await;
+        assert!(result.is_err());
+        assert_eq!(counts(&sink), (8, 1, 1));
+    }
+
+    /// Blocking test error: `Busy` is transient, optionally with a retry hint
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Busy(Option<Duration>),
+        Denied,
+    }
+
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{self:?}")
+        }
+    }
+
+    impl RetryableError for TestError {
+        fn is_transient(&self) -> bool {
+            matches!(self, TestError::Busy(_))
+        }
+
+        fn retry_after(&self) -> Option<Duration> {
+            match self {
+                TestError::Busy(hint) => *hint,
+                TestError::Denied => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_blocking_classifies_and_reports() {
+        let sink = Arc::new(CountingSink::default());
+        let policy = RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10)))
+            .with_max_attempts(4)
+            .with_metrics(sink.clone());
+
+        let result = retry_blocking("test_op", &policy, |attempt| {
+            if attempt < 2 {
+                Err(TestError::Busy(None))
+            } else {
+                Ok(attempt)
+            }
+        });
+        assert_eq!(result, Ok(2));
+        assert_eq!(counts(&sink), (2, 0, 1));
+
+        // Permanent failures are returned unchanged without retrying or counting as exhausted
+        let result: std::result::Result<(), TestError> =
+            retry_blocking("test_op", &policy, |_| Err(TestError::Denied));
+        assert_eq!(result, Err(TestError::Denied));
+        assert_eq!(counts(&sink), (3, 0, 1));
+    }
+
+    /// Delays chosen by a policy, observed through its `on_retry` hook
+    fn delay_recorder(policy: RetryPolicy) -> (RetryPolicy, Arc<std::sync::Mutex<Vec<Duration>>>) {
+        let delays = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let collected = Arc::clone(&delays);
+        let policy = policy.with_on_retry(move |event| collected.lock().unwrap().push(event.delay));
+        (policy, delays)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after_hint_overrides_backoff() {
+        let (policy, delays) = delay_recorder(
+            RetryPolicy::from(tight_policy(10, 5_000, Duration::from_secs(60)))
+                .with_max_attempts(2),
+        );
+
+        let result: RetryResult<()> = with_policy("test_op", policy, |_attempt| {
+            Box::pin(async {
+                Err(transient_error!(
+                    "test_op",
+                    std::io::Error::other("503 slow down"),
+                    retry_after = Duration::from_secs(2)
+                ))
+            })
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(*delays.lock().unwrap(), vec![Duration::from_secs(2)]);
+    }
+
+    #[test]
+    fn test_retry_after_hint_capped_by_max_interval() {
+        let (policy, delays) = delay_recorder(
+            RetryPolicy::from(tight_policy(1, 50, Duration::from_secs(60))).with_max_attempts(2),
+        );
+
+        let result: std::result::Result<(), TestError> = retry_blocking("test_op", &policy, |_| {
+            Err(TestError::Busy(Some(Duration::from_secs(2))))
+        });
+
         assert!(result.is_err());
-        matches!(result, Err(RetryError::MaxRetriesExceeded { .. }));
+        assert_eq!(*delays.lock().unwrap(), vec![Duration::from_millis(50)]);
+        assert_eq!(parse_retry_after(" 2 "), Some(Duration::from_secs(2)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    /// Operation whose attempts before `succeed_on` hang for an hour
+    #[cfg(feature = "async-rt")]
+    fn hanging_until(
+        succeed_on: usize,
+        attempt_count: &Arc<AtomicUsize>,
+    ) -> impl FnMut(usize) -> BoxFuture<'static, ()> {
+        let attempt_count = Arc::clone(attempt_count);
+        move |attempt| {
+            attempt_count.fetch_add(1, Ordering::SeqCst);
+            let future: BoxFuture<'static, ()> = Box::pin(async move {
+                if attempt < succeed_on {
+                    tokio::time::sleep(Duration::from_secs(3600)).await;
+                }
+                Ok(())
+            });
+            future
+        }
+    }
+
+    #[cfg(feature = "async-rt")]
+    #[tokio::test(start_paused = true)]
+    async fn test_attempt_timeout_triggers_retry() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy = RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10)))
+            .with_attempt_timeout(Duration::from_millis(50));
+
+        let result = with_policy("test_op", policy, hanging_until(2, &attempt_count)).await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "async-rt")]
+    #[tokio::test(start_paused = true)]
+    async fn test_attempt_timeout_noted_in_final_error() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy = RetryPolicy::from(tight_policy(1, 1, Duration::from_secs(10)))
+            .with_max_attempts(2)
+            .with_attempt_timeout(Duration::from_millis(50));
+
+        let err = with_policy("test_op", policy, hanging_until(usize::MAX, &attempt_count))
+            .await
+            .unwrap_err();
+
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            err.to_string(),
+            "Operation 'test_op' gave up after 2 attempts: attempt timed out after 50ms"
+        );
+        let last_error = std::error::Error::source(&err).unwrap();
+        assert_eq!(
+            last_error
+                .downcast_ref::<std::io::Error>()
+                .map(|e| e.kind()),
+            Some(std::io::ErrorKind::TimedOut)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_time_capped_policy() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+        let policy = RetryPolicy::from(tight_policy(10, 10, Duration::from_millis(50)));
+
+        let start = std::time::Instant::now();
+        let result = with_policy("test_op", policy, always_failing(&attempt_count)).await;
+
+        assert!(matches!(result, Err(RetryError::MaxRetriesExceeded { .. })));
+        let attempts = attempt_count.load(Ordering::SeqCst);
+        assert!((2..=7).contains(&attempts), "attempts {attempts}");
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_never_retry_classifier() {
+        let attempt_count = Arc::new(AtomicUsize::new(0));
+
+        let result = with_policy(
+            "test_op",
+            RetryPolicy::never_retry(),
+            always_failing(&attempt_count),
+        )
+        .await;
+
+        assert!(matches!(result, Err(RetryError::MaxRetriesExceeded { .. })));
+        assert_eq!(attempt_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_allows_retry() {
+        let capped = RetryPolicy::default_policy().with_max_attempts(3);
+        assert!(capped.allows_retry(1));
+        assert!(capped.allows_retry(2));
+        assert!(!capped.allows_retry(3));
+
+        assert!(RetryPolicy::cloud_storage().allows_retry(100));
+        assert!(!RetryPolicy::never_retry().allows_retry(1));
+    }
+
+    #[test]
+    fn test_policy_jitter_defaults_and_seeding() {
+        assert_eq!(RetryPolicy::cloud_storage().jitter, JitterMode::Full);
+        assert_eq!(RetryPolicy::local_storage().jitter, JitterMode::None);
+
+        let delays = |policy: &RetryPolicy| {
+            let mut schedule = policy.schedule();
+            (0..8)
+                .map(|_| schedule.next_backoff().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let seeded = RetryPolicy::cloud_storage().with_jitter_seed(7);
+        assert_eq!(delays(&seeded), delays(&seeded));
+
+        // Full jitter never exceeds the unjittered schedule
+        let plain = cloud_storage_backoff_policy_with_jitter(JitterMode::None);
+        for (jittered, max) in delays(&seeded).into_iter().zip(delays(&plain)) {
+            assert!(jittered <= max);
+        }
+    }
+
+    #[test]
+    fn test_policies_without_jitter_are_deterministic() {
+        let delays = |policy: &RetryPolicy| {
+            let mut schedule = policy.schedule();
+            (0..4)
+                .map(|_| schedule.next_backoff().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let millis = |ms: &[u64]| {
+            ms.iter()
+                .map(|&ms| Duration::from_millis(ms))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            delays(&RetryPolicy::default_policy()),
+            millis(&[100, 200, 400, 800])
+        );
+        assert_eq!(
+            delays(&RetryPolicy::local_storage()),
+            millis(&[50, 100, 200, 400])
+        );
+        assert_eq!(
+            delays(&cloud_storage_backoff_policy_with_jitter(JitterMode::None))[..3],
+            millis(&[500, 750, 1125])
+        );
+        assert_eq!(
+            default_backoff_policy_with_jitter(JitterMode::Equal).jitter,
+            JitterMode::Equal
+        );
     }
 }
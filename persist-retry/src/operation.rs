@@ -0,0 +1,130 @@
+//! Identification of retried operations
+//!
+//! An [`Operation`] pairs a fixed [`OperationKind`], suitable as a metrics label,
+//! with an optional target such as `bucket/key` that makes errors self-describing.
+
+use std::fmt;
+
+/// What a retried operation does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    Save,
+    Load,
+    Exists,
+    Delete,
+    List,
+    /// Any other operation, by name
+    Other(&'static str),
+}
+
+impl OperationKind {
+    /// Lowercase name of the kind, used as the metrics label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationKind::Save => "save",
+            OperationKind::Load => "load",
+            OperationKind::Exists => "exists",
+            OperationKind::Delete => "delete",
+            OperationKind::List => "list",
+            OperationKind::Other(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&'static str> for OperationKind {
+    fn from(name: &'static str) -> Self {
+        match name {
+            "save" => OperationKind::Save,
+            "load" => OperationKind::Load,
+            "exists" => OperationKind::Exists,
+            "delete" => OperationKind::Delete,
+            "list" => OperationKind::List,
+            other => OperationKind::Other(other),
+        }
+    }
+}
+
+/// A retried operation: its kind and, optionally, what it acts on
+///
+/// Displays as the kind alone, or as `kind target` (e.g. `save my-bucket/a.json.gz`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Operation {
+    /// What the operation does
+    pub kind: OperationKind,
+    /// What the operation acts on, such as `bucket/key`
+    pub target: Option<String>,
+}
+
+impl Operation {
+    /// Operation of `kind` without a target
+    pub fn new(kind: OperationKind) -> Self {
+        Self { kind, target: None }
+    }
+
+    /// Set what the operation acts on
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.target {
+            Some(target) => write!(f, "{} {}", self.kind, target),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl From<OperationKind> for Operation {
+    fn from(kind: OperationKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<&'static str> for Operation {
+    fn from(name: &'static str) -> Self {
+        Self::new(name.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Operation::new(OperationKind::Save).to_string(), "save");
+        assert_eq!(
+            Operation::new(OperationKind::Load)
+                .with_target("my-bucket/snapshots/a.json.gz")
+                .to_string(),
+            "load my-bucket/snapshots/a.json.gz"
+        );
+        assert_eq!(
+            Operation::new(OperationKind::Other("compact"))
+                .with_target("x")
+                .to_string(),
+            "compact x"
+        );
+    }
+
+    #[test]
+    fn test_from_static_str() {
+        let save: Operation = "save".into();
+        assert_eq!(save, Operation::new(OperationKind::Save));
+        assert_eq!(save.target, None);
+
+        let custom = Operation::from("test_op");
+        assert_eq!(custom.kind, OperationKind::Other("test_op"));
+        assert_eq!(custom.kind.as_str(), "test_op");
+        assert_eq!(custom.to_string(), "test_op");
+    }
+}
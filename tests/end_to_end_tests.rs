@@ -5,7 +5,7 @@ These tests verify the complete functionality from Python SDK through to storage
 
 use persist_core::{
     create_default_engine, create_engine_from_config,
-    SnapshotMetadata, StorageConfig, StorageBackend,
+    SnapshotMetadata, StorageConfig,
 };
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -372,14 +372,7 @@ fn test_configuration_variants() {
     let temp_dir = TempDir::new().unwrap();
     
     // Test different storage configurations
-    let local_config = StorageConfig {
-        backend: StorageBackend::Local,
-        bucket_name: None,
-        region: None,
-        access_key_id: None,
-        secret_access_key: None,
-        endpoint_url: None,
-    };
+    let local_config = StorageConfig::default_local();
     
     let local_engine = create_engine_from_config(local_config).unwrap();
     
@@ -354,3 +354,28 @@ async fn test_localstack_performance_metrics() {
     
     tracing::info!("LocalStack performance metrics test completed");
 }
+
+#[test]
+fn test_localstack_config_fields() {
+    if !check_localstack_available() {
+        println!("Skipping LocalStack config fields test");
+        return;
+    }
+
+    // Everything comes from the configuration, overriding the AWS_* variables
+    let mut config = StorageConfig::s3_with_bucket("persist-test-bucket".to_string());
+    config.s3_region = Some("us-east-1".to_string());
+    config.s3_endpoint_url = Some("http://localhost:4566".to_string());
+    config.s3_force_path_style = true;
+    config.s3_credentials = Some(persist_core::S3Credentials::new("test", "test"));
+    config.s3_profile = Some("persist-missing-profile".to_string());
+
+    let engine = create_engine_from_config(config).unwrap();
+    let agent_json = serde_json::json!({"test": "config fields"}).to_string();
+    let metadata = SnapshotMetadata::new("config_fields_agent", "session", 0);
+    let s3_key = "test/config_fields_test.json.gz";
+
+    engine.save_snapshot(&agent_json, &metadata, s3_key).unwrap();
+    let (_, loaded_data) = engine.load_snapshot(s3_key).unwrap();
+    assert_eq!(loaded_data, agent_json);
+}
@@ -37,6 +37,7 @@ fn test_metrics_initialization() {
     assert!(metrics_text.contains("persist_s3_requests_total"));
     assert!(metrics_text.contains("persist_s3_errors_total"));
     assert!(metrics_text.contains("persist_s3_latency_seconds"));
+    // Deprecated alias of persist_retry_attempts_total{backend="s3"}
     assert!(metrics_text.contains("persist_s3_retries_total"));
     assert!(metrics_text.contains("persist_state_size_bytes"));
 }
@@ -52,7 +53,7 @@ fn test_metrics_recording() {
     metrics.record_s3_request("get_object");
     metrics.record_s3_error("put_object");
     metrics.record_s3_latency("put_object", Duration::from_millis(150));
-    metrics.record_s3_retry("put_object");
+    metrics.record_retry_attempt("s3", "save");
     metrics.record_state_size(2048);
     
     // Give metrics time to be processed
@@ -64,6 +65,9 @@ fn test_metrics_recording() {
     assert!(metrics_text.contains("persist_s3_requests_total"));
     assert!(metrics_text.contains("persist_s3_errors_total"));
     assert!(metrics_text.contains("persist_s3_latency_seconds"));
+    assert!(metrics_text.contains(
+        "persist_retry_attempts_total{backend=\"s3\",operation=\"save\"}"
+    ));
     
     println!("Metrics output:\n{}", metrics_text);
 }